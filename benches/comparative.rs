@@ -10,7 +10,7 @@
 
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use omni_index::{
-    DeadCodeAnalyzer, IncrementalIndexer, InterventionEngine, create_state,
+    DeadCodeAnalyzer, IncrementalIndexer, InterventionEngine, bench::write_fixture, create_state,
     topology::TopologyBuilder,
 };
 use std::fs;
@@ -24,149 +24,10 @@ use tempfile::TempDir;
 /// This allows direct comparison of results.
 fn create_benchmark_fixture(num_files: usize) -> TempDir {
     let temp = TempDir::new().expect("Failed to create temp dir");
-    let src = temp.path().join("src");
-    fs::create_dir_all(&src).unwrap();
-
-    // Create lib.rs with module declarations
-    let mut lib = String::from("//! Benchmark fixture\n\n");
-    for i in 0..num_files {
-        lib.push_str(&format!("pub mod module_{};\n", i));
-    }
-    fs::write(src.join("lib.rs"), lib).unwrap();
-
-    // Create module files with realistic patterns
-    for i in 0..num_files {
-        let content = generate_module_content(i, num_files);
-        fs::write(src.join(format!("module_{}.rs", i)), content).unwrap();
-    }
-
+    write_fixture(temp.path(), num_files).expect("Failed to write fixture");
     temp
 }
 
-/// Generate module content with imports, structs, impl blocks, functions, calls, and tests.
-/// Mirrors what code-index benchmarks use.
-fn generate_module_content(module_idx: usize, total_modules: usize) -> String {
-    let mut code = String::with_capacity(4000);
-
-    // Module doc
-    code.push_str(&format!(
-        "//! Module {} for benchmark testing.\n\n",
-        module_idx
-    ));
-
-    // Imports (some cross-module)
-    code.push_str("use std::collections::HashMap;\n");
-    if module_idx > 0 {
-        code.push_str(&format!(
-            "use crate::module_{}::helper_fn;\n",
-            module_idx - 1
-        ));
-    }
-    code.push('\n');
-
-    // Struct with impl
-    code.push_str(&format!(
-        r#"/// Main struct for module {idx}.
-#[derive(Debug, Clone)]
-pub struct Module{idx}State {{
-    pub data: HashMap<String, i32>,
-    pub counter: usize,
-}}
-
-impl Module{idx}State {{
-    /// Create new state.
-    pub fn new() -> Self {{
-        Self {{
-            data: HashMap::new(),
-            counter: 0,
-        }}
-    }}
-
-    /// Process some data.
-    pub fn process(&mut self, key: &str, value: i32) -> i32 {{
-        self.counter += 1;
-        self.data.insert(key.to_string(), value);
-        helper_fn(value)
-    }}
-
-    /// Get a value.
-    pub fn get(&self, key: &str) -> Option<i32> {{
-        self.data.get(key).copied()
-    }}
-}}
-
-"#,
-        idx = module_idx
-    ));
-
-    // Helper function (called by process)
-    code.push_str(&format!(
-        r#"/// Helper function for module {idx}.
-pub fn helper_fn(x: i32) -> i32 {{
-    transform(x) * 2
-}}
-
-/// Transform function.
-fn transform(x: i32) -> i32 {{
-    x.saturating_add(1)
-}}
-
-"#,
-        idx = module_idx
-    ));
-
-    // Cross-module call (if not first module)
-    if module_idx < total_modules - 1 {
-        code.push_str(&format!(
-            r#"/// Cross-module function.
-pub fn call_next() -> i32 {{
-    // Would call module_{next}::helper_fn in real code
-    42
-}}
-
-"#,
-            next = module_idx + 1
-        ));
-    }
-
-    // Test functions
-    code.push_str(&format!(
-        r#"#[cfg(test)]
-mod tests {{
-    use super::*;
-
-    #[test]
-    fn test_new() {{
-        let state = Module{idx}State::new();
-        assert_eq!(state.counter, 0);
-    }}
-
-    #[test]
-    fn test_process() {{
-        let mut state = Module{idx}State::new();
-        let result = state.process("key", 42);
-        assert!(result > 0);
-    }}
-
-    #[test]
-    fn test_get() {{
-        let mut state = Module{idx}State::new();
-        state.process("key", 42);
-        assert_eq!(state.get("key"), Some(42));
-    }}
-
-    #[test]
-    fn test_helper() {{
-        assert_eq!(helper_fn(10), 22);
-    }}
-}}
-"#,
-        idx = module_idx
-    ));
-
-    code
-}
-
 // ============================================================================
 // Benchmarks matching code-index's benchmark categories
 // ============================================================================
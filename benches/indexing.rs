@@ -10,6 +10,7 @@
 //! ## Full Indexing
 //! - Repository indexing throughput (files/sec, symbols/sec)
 //! - Scaling behavior with repo size
+//! - Indexing while other threads query the state (server mode)
 //!
 //! ## Incremental Updates
 //! - Single file update latency (critical for IDE-like responsiveness)
@@ -378,6 +379,67 @@ fn bench_full_index(c: &mut Criterion) {
     group.finish();
 }
 
+/// Full index while reader threads query the same state, as the MCP server
+/// does. Each file is committed as one batch (see `OciState::commit_file`),
+/// so the indexer takes the call graph's write lock once per file rather
+/// than once per edge; the reads completed per index are printed alongside
+/// the timing.
+fn bench_index_with_readers(c: &mut Criterion) {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let mut group = c.benchmark_group("indexing/full_repo_with_readers");
+    group.sample_size(10);
+
+    let (temp, total_symbols) = create_test_repo(25, 80);
+    group.throughput(Throughput::Elements(total_symbols as u64));
+
+    for readers in [0, 2] {
+        let reads = AtomicUsize::new(0);
+        let indexes = AtomicUsize::new(0);
+        group.bench_with_input(
+            BenchmarkId::new("readers", readers),
+            &readers,
+            |b, &readers| {
+                b.iter(|| {
+                    let state = create_state(temp.path().to_path_buf());
+                    let indexer = IncrementalIndexer::new();
+                    let done = AtomicBool::new(false);
+                    std::thread::scope(|scope| {
+                        for _ in 0..readers {
+                            scope.spawn(|| {
+                                while !done.load(Ordering::Relaxed) {
+                                    black_box(state.find_callers("bench_function_1"));
+                                    black_box(state.find_by_name("new"));
+                                    reads.fetch_add(1, Ordering::Relaxed);
+                                }
+                            });
+                        }
+                        let rt = tokio::runtime::Builder::new_current_thread()
+                            .enable_all()
+                            .build()
+                            .unwrap();
+                        rt.block_on(async {
+                            indexer.full_index(&state, temp.path()).await.unwrap()
+                        });
+                        done.store(true, Ordering::Relaxed);
+                    });
+                    indexes.fetch_add(1, Ordering::Relaxed);
+                    black_box(state.stats())
+                });
+            },
+        );
+        if readers > 0 {
+            println!(
+                "\n  {} readers: {} reads per index",
+                readers,
+                reads.load(Ordering::Relaxed) / indexes.load(Ordering::Relaxed).max(1)
+            );
+        }
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // Incremental Update Benchmarks - IDE Responsiveness
 // ============================================================================
@@ -749,7 +811,7 @@ criterion_group!(
         .sample_size(10)
         .warm_up_time(std::time::Duration::from_millis(500))
         .measurement_time(std::time::Duration::from_secs(5));
-    targets = bench_full_index, bench_incremental_update
+    targets = bench_full_index, bench_index_with_readers, bench_incremental_update
 );
 
 criterion_group!(
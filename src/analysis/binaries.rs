@@ -0,0 +1,496 @@
+//! Binary entry points and `main`-rooted reachability.
+//!
+//! Discovers the binary targets of a Cargo package or workspace
+//! (`src/main.rs`, `src/bin/*`, `[[bin]]` entries, and examples) and walks
+//! the call graph from each binary's `main`. This answers "what does this
+//! binary actually use?" and classifies symbols that no binary reaches.
+
+use crate::state::OciState;
+use crate::types::{
+    BinaryKind, BinaryReachability, BinaryTarget, BinaryUsageReport, IGNORE_DEAD_CODE,
+    InternedString, SymbolKind,
+};
+use anyhow::{Context, Result};
+use globset::Glob;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Discover every binary target under a Cargo package or workspace root.
+///
+/// Returns an empty list if `root` has no `Cargo.toml`.
+pub fn discover_binaries(root: &Path) -> Result<Vec<BinaryTarget>> {
+    let Some(manifest) = read_manifest(root)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut targets = Vec::new();
+    if manifest.get("package").is_some() {
+        targets.extend(package_targets(root, &manifest));
+    }
+
+    if let Some(workspace) = manifest.get("workspace") {
+        let excluded: Vec<PathBuf> = string_array(workspace.get("exclude"))
+            .map(|p| root.join(p))
+            .collect();
+        for member in string_array(workspace.get("members")) {
+            for dir in expand_member(root, member)? {
+                if dir == root || excluded.contains(&dir) {
+                    continue;
+                }
+                if let Some(member_manifest) = read_manifest(&dir)? {
+                    targets.extend(package_targets(&dir, &member_manifest));
+                }
+            }
+        }
+    }
+
+    targets.sort_by(|a, b| (&a.package, a.kind, &a.name).cmp(&(&b.package, b.kind, &b.name)));
+    Ok(targets)
+}
+
+fn read_manifest(dir: &Path) -> Result<Option<toml::Value>> {
+    let path = dir.join("Cargo.toml");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let value =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(value))
+}
+
+fn string_array(value: Option<&toml::Value>) -> impl Iterator<Item = &str> {
+    value
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+}
+
+/// Expand a workspace member entry, which may glob its last path segment.
+fn expand_member(root: &Path, member: &str) -> Result<Vec<PathBuf>> {
+    if !member.contains(['*', '?', '[']) {
+        return Ok(vec![root.join(member)]);
+    }
+
+    let (parent, _) = member.rsplit_once('/').unwrap_or(("", member));
+    let matcher = Glob::new(member)
+        .with_context(|| format!("Invalid workspace member glob: {}", member))?
+        .compile_matcher();
+    let parent_dir = root.join(parent);
+    let Ok(entries) = fs::read_dir(&parent_dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            p.strip_prefix(root)
+                .is_ok_and(|rel| matcher.is_match(rel.to_string_lossy().replace('\\', "/")))
+        })
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Binary and example targets of a single package, explicit and auto-discovered.
+fn package_targets(dir: &Path, manifest: &toml::Value) -> Vec<BinaryTarget> {
+    let Some(package) = manifest.get("package") else {
+        return Vec::new();
+    };
+    let package_name = package
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let auto = |key: &str| package.get(key).and_then(|v| v.as_bool()) != Some(false);
+
+    let mut targets = Vec::new();
+    let mut push = |name: String, kind: BinaryKind, path: PathBuf| {
+        if path.is_file() && !targets.iter().any(|t: &BinaryTarget| t.path == path) {
+            targets.push(BinaryTarget {
+                name,
+                kind,
+                package: package_name.clone(),
+                path,
+            });
+        }
+    };
+
+    for (key, kind, default_dir) in [
+        ("bin", BinaryKind::Bin, "src/bin"),
+        ("example", BinaryKind::Example, "examples"),
+    ] {
+        let entries = manifest.get(key).and_then(|v| v.as_array());
+        for entry in entries.into_iter().flatten() {
+            let Some(name) = entry.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let path = match entry.get("path").and_then(|v| v.as_str()) {
+                Some(path) => dir.join(path),
+                None if kind == BinaryKind::Bin
+                    && name == package_name
+                    && dir.join("src/main.rs").is_file() =>
+                {
+                    dir.join("src/main.rs")
+                }
+                None => {
+                    let single = dir.join(default_dir).join(format!("{}.rs", name));
+                    if single.is_file() {
+                        single
+                    } else {
+                        dir.join(default_dir).join(name).join("main.rs")
+                    }
+                }
+            };
+            push(name.to_string(), kind, path);
+        }
+    }
+
+    if auto("autobins") {
+        push(
+            package_name.clone(),
+            BinaryKind::Bin,
+            dir.join("src/main.rs"),
+        );
+        for (name, path) in auto_targets(&dir.join("src/bin")) {
+            push(name, BinaryKind::Bin, path);
+        }
+    }
+    if auto("autoexamples") {
+        for (name, path) in auto_targets(&dir.join("examples")) {
+            push(name, BinaryKind::Example, path);
+        }
+    }
+
+    targets
+}
+
+/// Cargo's auto-discovery layout: `<dir>/<name>.rs` and `<dir>/<name>/main.rs`.
+fn auto_targets(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<(String, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            if path.is_dir() {
+                let main = path.join("main.rs");
+                let name = path.file_name()?.to_string_lossy().to_string();
+                main.is_file().then_some((name, main))
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                let name = path.file_stem()?.to_string_lossy().to_string();
+                Some((name, path))
+            } else {
+                None
+            }
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Computes call-graph reachability rooted at binary entry points.
+pub struct BinaryAnalyzer {
+    include_ignored: bool,
+}
+
+impl BinaryAnalyzer {
+    /// Creates a new binary analyzer.
+    pub fn new() -> Self {
+        Self {
+            include_ignored: false,
+        }
+    }
+
+    /// Report functions annotated `omni:ignore dead_code` as unused (for audits).
+    pub fn include_ignored(mut self, include: bool) -> Self {
+        self.include_ignored = include;
+        self
+    }
+
+    /// Analyze every target and classify functions that no binary reaches.
+    pub fn analyze(&self, state: &OciState, targets: &[BinaryTarget]) -> BinaryUsageReport {
+        let binaries: Vec<BinaryReachability> = targets
+            .iter()
+            .map(|target| self.reachability(state, target))
+            .collect();
+
+        let used: HashSet<InternedString> = binaries
+            .iter()
+            .flat_map(|b| b.reachable.iter().map(|(sym, _)| *sym))
+            .chain(binaries.iter().filter_map(|b| b.entry))
+            .collect();
+        let unused = self.unreached(state, &used);
+
+        BinaryUsageReport { binaries, unused }
+    }
+
+    /// Walk the call graph from a single binary's `main`.
+    ///
+    /// Every binary's `main` shares the scoped name `crate::main`, so the
+    /// walk starts from the call edges located in the target's file rather
+    /// than from the symbol itself.
+    pub fn reachability(&self, state: &OciState, target: &BinaryTarget) -> BinaryReachability {
+        let main_edges: Vec<_> = {
+            let edges = state.call_edges.read();
+            edges
+                .iter()
+                .filter(|e| {
+                    e.location.file == target.path
+                        && state.resolve(e.caller).rsplit("::").next() == Some("main")
+                })
+                .cloned()
+                .collect()
+        };
+
+        let entry = state
+            .find_by_name("main")
+            .into_iter()
+            .find(|s| matches!(s.kind, SymbolKind::Function) && s.location.file == target.path)
+            .map(|s| s.scoped_name)
+            .or_else(|| main_edges.first().map(|e| e.caller));
+
+        let mut depths: HashMap<InternedString, u32> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut visit = |scoped: InternedString, depth: u32, queue: &mut VecDeque<_>| {
+            if Some(scoped) != entry && !depths.contains_key(&scoped) {
+                depths.insert(scoped, depth);
+                queue.push_back((scoped, depth));
+            }
+        };
+
+        for edge in &main_edges {
+            for callee in state.call_targets(edge) {
+                visit(callee.scoped_name, 1, &mut queue);
+            }
+        }
+
+        while let Some((current, depth)) = queue.pop_front() {
+            for edge in state.find_callees(current) {
+                for callee in state.call_targets(&edge) {
+                    visit(callee.scoped_name, depth + 1, &mut queue);
+                }
+            }
+
+            // A reachable method keeps its impl/struct alive
+            if let Some(parent) = state.get_symbol(current).and_then(|s| s.parent) {
+                visit(parent, depth, &mut queue);
+            }
+        }
+
+        let mut reachable: Vec<(InternedString, u32)> = depths.into_iter().collect();
+        reachable.sort_by(|a, b| {
+            a.1.cmp(&b.1)
+                .then_with(|| state.resolve(a.0).cmp(state.resolve(b.0)))
+        });
+
+        BinaryReachability {
+            target: target.clone(),
+            entry,
+            reachable,
+        }
+    }
+
+    /// Functions and methods a binary does not reach (excluding tests).
+    pub fn unreachable_from(
+        &self,
+        state: &OciState,
+        binary: &BinaryReachability,
+    ) -> Vec<InternedString> {
+        let used: HashSet<InternedString> = binary
+            .reachable
+            .iter()
+            .map(|(sym, _)| *sym)
+            .chain(binary.entry)
+            .collect();
+        self.unreached(state, &used)
+    }
+
+    fn unreached(&self, state: &OciState, used: &HashSet<InternedString>) -> Vec<InternedString> {
+        let mut unused: Vec<InternedString> = state
+            .symbols
+            .iter()
+            .filter(|entry| !used.contains(entry.key()))
+            .filter(|entry| {
+                let symbol = entry.value();
+                matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+                    && !symbol.is_test()
+                    && state.resolve(symbol.name) != "main"
+                    && (self.include_ignored || !symbol.is_ignored(IGNORE_DEAD_CODE))
+            })
+            .map(|entry| *entry.key())
+            .collect();
+        unused.sort_by(|a, b| state.resolve(*a).cmp(state.resolve(*b)));
+        unused
+    }
+}
+
+impl Default for BinaryAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::create_state;
+    use crate::types::{CallEdge, Location, Signature, SymbolDef, Visibility};
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_discovers_package_binaries() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "Cargo.toml",
+            r#"
+[package]
+name = "app"
+
+[[bin]]
+name = "custom"
+path = "tools/custom.rs"
+"#,
+        );
+        write(root, "src/main.rs", "fn main() {}");
+        write(root, "src/bin/helper.rs", "fn main() {}");
+        write(root, "src/bin/multi/main.rs", "fn main() {}");
+        write(root, "tools/custom.rs", "fn main() {}");
+        write(root, "examples/demo.rs", "fn main() {}");
+
+        let targets = discover_binaries(root).unwrap();
+        let names: Vec<(&str, BinaryKind)> =
+            targets.iter().map(|t| (t.name.as_str(), t.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("app", BinaryKind::Bin),
+                ("custom", BinaryKind::Bin),
+                ("helper", BinaryKind::Bin),
+                ("multi", BinaryKind::Bin),
+                ("demo", BinaryKind::Example),
+            ]
+        );
+        assert_eq!(targets[1].path, root.join("tools/custom.rs"));
+    }
+
+    #[test]
+    fn test_discovers_workspace_members() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skip\"]\n",
+        );
+        write(root, "crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+        write(root, "crates/cli/src/main.rs", "fn main() {}");
+        write(
+            root,
+            "crates/lib/Cargo.toml",
+            "[package]\nname = \"lib\"\nautobins = false\n",
+        );
+        write(root, "crates/lib/src/main.rs", "fn main() {}");
+        write(
+            root,
+            "crates/skip/Cargo.toml",
+            "[package]\nname = \"skip\"\n",
+        );
+        write(root, "crates/skip/src/main.rs", "fn main() {}");
+
+        let targets = discover_binaries(root).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "cli");
+        assert_eq!(targets[0].package, "cli");
+    }
+
+    fn add_fn(state: &OciState, scoped: &str, file: &str) -> InternedString {
+        let name = scoped.rsplit("::").next().unwrap();
+        let scoped_name = state.intern(scoped);
+        state.add_symbol(SymbolDef {
+            name: state.intern(name),
+            scoped_name,
+            kind: SymbolKind::Function,
+            location: Location::new(PathBuf::from(file), 0, 10),
+            extended_location: None,
+            signature: Some(Signature::default()),
+            visibility: Visibility::Public,
+            attributes: vec![],
+            doc_comment: None,
+            parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
+        });
+        scoped_name
+    }
+
+    fn add_call(state: &OciState, caller: InternedString, callee: &str, file: &str) {
+        state.add_call_edge(CallEdge {
+            caller,
+            callee_name: callee.to_string(),
+            location: Location::new(PathBuf::from(file), 1, 2),
+            is_method_call: false,
+            via_trait: None,
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
+        });
+    }
+
+    #[test]
+    fn test_reachability_is_per_binary() {
+        let state = create_state(PathBuf::from("/ws"));
+        let main = add_fn(&state, "crate::main", "/ws/src/main.rs");
+        let serve = add_fn(&state, "crate::server::serve", "/ws/src/server.rs");
+        let handle = add_fn(&state, "crate::server::handle", "/ws/src/server.rs");
+        let parse = add_fn(&state, "crate::cli::parse", "/ws/src/cli.rs");
+        let orphan = add_fn(&state, "crate::orphan", "/ws/src/lib.rs");
+
+        // Two binaries share the `crate::main` scoped name
+        add_call(&state, main, "serve", "/ws/src/main.rs");
+        add_call(&state, main, "parse", "/ws/src/bin/tool.rs");
+        add_call(&state, serve, "handle", "/ws/src/server.rs");
+
+        let target = |name: &str, path: &str| BinaryTarget {
+            name: name.to_string(),
+            kind: BinaryKind::Bin,
+            package: "ws".to_string(),
+            path: PathBuf::from(path),
+        };
+        let targets = vec![
+            target("server", "/ws/src/main.rs"),
+            target("tool", "/ws/src/bin/tool.rs"),
+        ];
+
+        let analyzer = BinaryAnalyzer::new();
+        let report = analyzer.analyze(&state, &targets);
+
+        let server = &report.binaries[0];
+        assert_eq!(server.entry, Some(main));
+        assert_eq!(server.reachable, vec![(serve, 1), (handle, 2)]);
+
+        let tool = &report.binaries[1];
+        assert_eq!(tool.reachable, vec![(parse, 1)]);
+
+        assert_eq!(report.unused, vec![orphan]);
+        let unused_by_tool = analyzer.unreachable_from(&state, tool);
+        assert!(unused_by_tool.contains(&serve));
+        assert!(!unused_by_tool.contains(&parse));
+    }
+}
@@ -0,0 +1,232 @@
+//! `omni check` findings and baselines.
+//!
+//! A baseline records the findings a repository already has so that
+//! `omni check` only fails on findings introduced since. Entries are keyed by
+//! rule, file, and scoped symbol (not line), so unrelated edits that shift
+//! code around do not invalidate the baseline.
+
+use crate::analysis::DeadCodeAnalyzer;
+use crate::state::OciState;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Default baseline file name, stored at the repository root.
+pub const BASELINE_FILE: &str = ".omni-baseline.json";
+
+/// Current baseline file format version.
+pub const BASELINE_VERSION: u32 = 1;
+
+/// A single check finding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct Finding {
+    /// Rule that produced the finding (e.g. `dead-code`)
+    pub rule: String,
+    /// Scoped symbol name
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Finding {
+    fn key(&self) -> BaselineEntry {
+        BaselineEntry {
+            rule: self.rule.clone(),
+            file: self.file.clone(),
+            symbol: self.symbol.clone(),
+        }
+    }
+}
+
+/// A baselined finding.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, schemars::JsonSchema,
+)]
+pub struct BaselineEntry {
+    pub rule: String,
+    pub file: String,
+    pub symbol: String,
+}
+
+/// Findings accepted as pre-existing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub findings: Vec<BaselineEntry>,
+}
+
+/// Findings compared against a baseline.
+#[derive(Debug, Clone, Default)]
+pub struct CheckDiff {
+    /// Findings not covered by the baseline
+    pub new: Vec<Finding>,
+    /// Number of findings covered by the baseline
+    pub baselined: usize,
+    /// Baseline entries that no longer occur and can be pruned
+    pub fixed: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Build a baseline covering all of `findings`, sorted for stable diffs.
+    pub fn from_findings(findings: &[Finding]) -> Self {
+        let mut entries: Vec<BaselineEntry> = findings.iter().map(Finding::key).collect();
+        entries.sort();
+        Self {
+            version: BASELINE_VERSION,
+            findings: entries,
+        }
+    }
+
+    /// Load a baseline file, or `None` if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        let baseline: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline: {}", path.display()))?;
+        if baseline.version > BASELINE_VERSION {
+            anyhow::bail!(
+                "Baseline {} has version {}, newer than supported {}",
+                path.display(),
+                baseline.version,
+                BASELINE_VERSION
+            );
+        }
+        Ok(Some(baseline))
+    }
+
+    /// Write the baseline as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write baseline: {}", path.display()))
+    }
+
+    /// Split `findings` into new and baselined, and report stale entries.
+    ///
+    /// Entries are matched as a multiset: a baseline entry covers at most one
+    /// finding with the same key.
+    pub fn diff(&self, findings: &[Finding]) -> CheckDiff {
+        let mut remaining: HashMap<&BaselineEntry, usize> = HashMap::new();
+        for entry in &self.findings {
+            *remaining.entry(entry).or_default() += 1;
+        }
+
+        let mut diff = CheckDiff::default();
+        for finding in findings {
+            match remaining.get_mut(&finding.key()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    diff.baselined += 1;
+                }
+                _ => diff.new.push(finding.clone()),
+            }
+        }
+
+        let mut fixed: Vec<BaselineEntry> = remaining
+            .into_iter()
+            .flat_map(|(entry, count)| std::iter::repeat_n(entry.clone(), count))
+            .collect();
+        fixed.sort();
+        diff.fixed = fixed;
+        diff
+    }
+}
+
+/// Run all checks against an indexed state.
+///
+/// Findings suppressed by `omni:ignore` are skipped unless `include_ignored`.
+/// Findings are sorted by file, line, and rule.
+pub fn run_checks(state: &OciState, root: &Path, include_ignored: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let report = DeadCodeAnalyzer::new()
+        .include_ignored(include_ignored)
+        .analyze(state);
+    for scoped_name in report.dead_symbols {
+        let Some(symbol) = state.get_symbol(scoped_name) else {
+            continue;
+        };
+        let file = symbol
+            .location
+            .file
+            .strip_prefix(root)
+            .unwrap_or(&symbol.location.file);
+        findings.push(Finding {
+            rule: "dead-code".to_string(),
+            symbol: state.resolve(scoped_name).to_string(),
+            file: file.to_string_lossy().replace('\\', "/"),
+            line: symbol.location.start_line,
+            message: format!("{:?} is unreachable from any entry point", symbol.kind),
+        });
+    }
+
+    findings.sort_by(|a, b| {
+        (&a.file, a.line, &a.rule, &a.symbol).cmp(&(&b.file, b.line, &b.rule, &b.symbol))
+    });
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(rule: &str, file: &str, symbol: &str, line: usize) -> Finding {
+        Finding {
+            rule: rule.to_string(),
+            symbol: symbol.to_string(),
+            file: file.to_string(),
+            line,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_ignores_line_shifts_and_reports_fixed() {
+        let before = vec![
+            finding("dead-code", "src/a.rs", "crate::a::old", 10),
+            finding("dead-code", "src/a.rs", "crate::a::gone", 20),
+        ];
+        let baseline = Baseline::from_findings(&before);
+
+        let after = vec![
+            finding("dead-code", "src/a.rs", "crate::a::old", 42),
+            finding("dead-code", "src/b.rs", "crate::b::fresh", 3),
+        ];
+        let diff = baseline.diff(&after);
+
+        assert_eq!(diff.baselined, 1);
+        assert_eq!(diff.new, vec![after[1].clone()]);
+        assert_eq!(diff.fixed.len(), 1);
+        assert_eq!(diff.fixed[0].symbol, "crate::a::gone");
+    }
+
+    #[test]
+    fn test_duplicate_findings_need_duplicate_entries() {
+        let one = finding("dead-code", "src/a.rs", "crate::a::f", 1);
+        let baseline = Baseline::from_findings(std::slice::from_ref(&one));
+
+        let diff = baseline.diff(&[one.clone(), one.clone()]);
+        assert_eq!(diff.baselined, 1);
+        assert_eq!(diff.new.len(), 1);
+    }
+
+    #[test]
+    fn test_baseline_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(BASELINE_FILE);
+        assert!(Baseline::load(&path).unwrap().is_none());
+
+        let baseline =
+            Baseline::from_findings(&[finding("dead-code", "src/a.rs", "crate::a::f", 1)]);
+        baseline.save(&path).unwrap();
+        assert_eq!(Baseline::load(&path).unwrap(), Some(baseline));
+    }
+}
@@ -1,14 +1,24 @@
-//! File churn analysis via git history.
+//! File and symbol churn analysis via git history.
 //!
 //! Analyzes git commit history to identify:
 //! - Files with high change frequency (hotspots)
 //! - Per-file churn metrics (commits, lines changed, authors)
-//! - Code stability patterns
+//! - Per-symbol change counts, by mapping each commit's hunks back to the
+//!   current line numbers of the file
+//!
+//! One `git log --numstat -p --unified=0` pass over the window feeds all of
+//! it; [`ChurnAnalyzer::analyze_index`] attributes the changes to indexed
+//! symbols and stores the report in [`OciState::churn`].
 
+use crate::budget::Budget;
+use crate::state::OciState;
+use crate::types::{SymbolKind, TopologyNode};
 use anyhow::{Context, Result};
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 /// A churn analyzer that uses git history to identify code hotspots.
 pub struct ChurnAnalyzer;
@@ -28,7 +38,7 @@ impl ChurnAnalyzer {
     /// Removes all GIT_* vars that hooks may set, ensuring commands target the specified root.
     /// Note: This intentionally ignores any externally-set GIT_DIR; callers must pass the
     /// repo root explicitly via the `root` parameter.
-    fn git_cmd(root: &Path) -> Command {
+    pub(crate) fn git_cmd(root: &Path) -> Command {
         let mut cmd = Command::new("git");
         cmd.current_dir(root);
         Self::remove_git_env(&mut cmd);
@@ -42,46 +52,206 @@ impl ChurnAnalyzer {
     /// * `days` - Number of days of history to analyze
     ///
     /// # Returns
-    /// A `ChurnReport` containing file churn metrics and hotspots.
+    /// A `ChurnReport` containing file churn metrics and hotspots. Symbol
+    /// churn is left empty; see [`analyze_index`](Self::analyze_index).
     ///
     /// # Errors
     /// Returns an error if:
     /// - The directory is not a git repository
     /// - Git commands fail to execute
     pub fn analyze(root: &Path, days: u32) -> Result<ChurnReport> {
+        Self::analyze_within(root, days, &Budget::unlimited())
+    }
+
+    /// [`analyze`](Self::analyze), charging `budget` one unit per commit
+    /// and stopping at the first commit it has no room for. The report
+    /// then covers only the newest commits of the window.
+    pub fn analyze_within(root: &Path, days: u32, budget: &Budget) -> Result<ChurnReport> {
         // Verify this is a git repository
         if !Self::is_git_repo(root)? {
             anyhow::bail!("Not a git repository: {}", root.display());
         }
 
-        // Get list of all files changed in the time period
-        let changed_files = Self::get_changed_files(root, days)?;
-
-        // Build churn metrics for each file
-        let mut file_churn = Vec::new();
-        for file_path in changed_files {
-            if let Ok(churn) = Self::analyze_file(root, &file_path, days) {
-                file_churn.push(churn);
+        let since = format!("{} days ago", days);
+        // Paths relative to `root`, which may be below the repository top.
+        // Renames show up as a delete and an add, so history stops at them.
+        let mut child = Self::git_cmd(root)
+            .args([
+                "-c",
+                "core.quotepath=off",
+                "log",
+                "--since",
+                &since,
+                "--no-merges",
+                "--no-renames",
+                "--relative",
+                "--numstat",
+                "-p",
+                "--unified=0",
+                "--format=%x1e%H%x1f%an%x1f%ai",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute git log")?;
+        let stdout = child.stdout.take().context("git log has no stdout")?;
+
+        let mut parser = LogParser::new(root);
+        for line in BufReader::new(stdout).split(b'\n') {
+            let line = line.context("Failed to read git log output")?;
+            if line.first() == Some(&0x1e) && !budget.charge(1) {
+                // Killed mid-stream; its exit status means nothing
+                let _ = child.kill();
+                break;
             }
-            // Silently skip files that no longer exist or can't be analyzed
+            parser.line(&String::from_utf8_lossy(&line));
         }
+        let output = child
+            .wait_with_output()
+            .context("Failed to wait for git log")?;
+        if !output.status.success() && !budget.was_cut_short() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // An unborn branch has no history to report
+            if !stderr.contains("does not have any commits") {
+                anyhow::bail!("Git log failed: {}", stderr);
+            }
+        }
+        let (files, line_changes) = parser.finish();
+
+        let mut file_churn: Vec<FileChurn> = files
+            .into_iter()
+            .map(|(path, stats)| {
+                let mut authors: Vec<String> = stats.authors.into_iter().collect();
+                authors.sort();
+                FileChurn {
+                    path,
+                    commits: stats.commits,
+                    lines_added: stats.lines_added,
+                    lines_removed: stats.lines_removed,
+                    authors,
+                    last_modified: stats.last_modified,
+                }
+            })
+            .collect();
+        file_churn.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.path.cmp(&b.path)));
 
         // Identify hotspots: files with high change frequency
-        let mut hotspots: Vec<(PathBuf, u32)> = file_churn
+        let hotspots: Vec<(PathBuf, u32)> = file_churn
             .iter()
             .filter(|fc| fc.commits > 3) // More than 3 commits = hotspot
             .map(|fc| (fc.path.clone(), fc.commits))
             .collect();
 
-        // Sort hotspots by change frequency (descending)
-        hotspots.sort_by(|a, b| b.1.cmp(&a.1));
-
         Ok(ChurnReport {
+            days,
             file_churn,
             hotspots,
+            symbols: Vec::new(),
+            line_changes,
         })
     }
 
+    /// Analyze git history, attribute each commit's changed lines to the
+    /// indexed symbols they fall in, and store the report in
+    /// [`OciState::churn`] (file nodes also get their
+    /// [`TopologyMetrics::churn_count`](crate::types::TopologyMetrics::churn_count)).
+    pub fn analyze_index(state: &OciState, root: &Path, days: u32) -> Result<ChurnReport> {
+        Self::analyze_index_within(state, root, days, &Budget::unlimited())
+    }
+
+    /// [`analyze_index`](Self::analyze_index) within `budget` (see
+    /// [`analyze_within`](Self::analyze_within)). A report cut short is
+    /// not stored.
+    pub fn analyze_index_within(
+        state: &OciState,
+        root: &Path,
+        days: u32,
+        budget: &Budget,
+    ) -> Result<ChurnReport> {
+        let mut report = Self::analyze_within(root, days, budget)?;
+        report.symbols = Self::attribute_symbols(state, root, &report.line_changes);
+        if budget.was_cut_short() {
+            return Ok(report);
+        }
+
+        let graph = state.topology.read();
+        for mut entry in state.topology_metrics.iter_mut() {
+            if let Some(TopologyNode::File { path, .. }) = graph.node_weight(*entry.key()) {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                entry.value_mut().churn_count = report
+                    .file_churn
+                    .iter()
+                    .find(|f| f.path == relative)
+                    .map_or(0, |f| f.commits);
+            }
+        }
+        drop(graph);
+
+        *state.churn.write() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Per-symbol commit and changed-line counts, most changed first.
+    fn attribute_symbols(
+        state: &OciState,
+        root: &Path,
+        line_changes: &HashMap<PathBuf, Vec<LineChange>>,
+    ) -> Vec<SymbolChurn> {
+        let mut symbols = Vec::new();
+        for (path, changes) in line_changes {
+            let Some(file_id) = state.file_ids.get(&root.join(path)).map(|id| *id) else {
+                continue;
+            };
+            let Some(mut scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+                continue;
+            };
+            // A type and its impls share a scoped name
+            scoped_names.sort_unstable();
+            scoped_names.dedup();
+            for scoped in scoped_names {
+                let Some(def) = state.get_symbol(scoped) else {
+                    continue;
+                };
+                if matches!(
+                    def.kind,
+                    SymbolKind::Module
+                        | SymbolKind::Field
+                        | SymbolKind::Variant
+                        | SymbolKind::Column
+                ) {
+                    continue;
+                }
+                let range = def.location.start_line..=def.location.end_line;
+                let mut commits = 0;
+                let mut lines_changed = 0;
+                for change in changes {
+                    let touched = change.lines.iter().filter(|l| range.contains(l)).count();
+                    if touched > 0 {
+                        commits += 1;
+                        lines_changed += touched as u32;
+                    }
+                }
+                if commits > 0 {
+                    symbols.push(SymbolChurn {
+                        symbol: state.resolve(scoped).to_string(),
+                        kind: def.kind.as_str().to_string(),
+                        file: path.clone(),
+                        line: def.location.start_line,
+                        commits,
+                        lines_changed,
+                    });
+                }
+            }
+        }
+        symbols.sort_by(|a, b| {
+            b.commits
+                .cmp(&a.commits)
+                .then(b.lines_changed.cmp(&a.lines_changed))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        symbols
+    }
+
     /// Check if a directory is a git repository.
     fn is_git_repo(root: &Path) -> Result<bool> {
         let output = Self::git_cmd(root)
@@ -92,147 +262,240 @@ impl ChurnAnalyzer {
 
         Ok(output.status.success())
     }
+}
 
-    /// Get list of all files changed in the time period.
-    fn get_changed_files(root: &Path, days: u32) -> Result<HashSet<PathBuf>> {
-        let since = format!("{} days ago", days);
-
-        let output = Self::git_cmd(root)
-            .args(["log", "--since", &since, "--pretty=format:", "--name-only"])
-            .output()
-            .context("Failed to get changed files from git")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Git log failed: {}", stderr);
-        }
+/// Per-file totals gathered while parsing the log.
+#[derive(Default)]
+struct FileStats {
+    commits: u32,
+    lines_added: u32,
+    lines_removed: u32,
+    authors: HashSet<String>,
+    last_modified: String,
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let files: HashSet<PathBuf> = stdout
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| PathBuf::from(line.trim()))
-            .collect();
+/// A `@@ -old_start,old_len +new_start,new_len @@` hunk header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Hunk {
+    old_len: usize,
+    new_start: usize,
+    new_len: usize,
+}
 
-        Ok(files)
+impl Hunk {
+    fn parse(line: &str) -> Option<Self> {
+        let mut ranges = line.strip_prefix("@@ -")?.split(' ');
+        let old = ranges.next()?;
+        let new = ranges.next()?.strip_prefix('+')?;
+        let range = |r: &str| -> Option<(usize, usize)> {
+            match r.split_once(',') {
+                Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+                None => Some((r.parse().ok()?, 1)),
+            }
+        };
+        let (_, old_len) = range(old)?;
+        let (new_start, new_len) = range(new)?;
+        Some(Self {
+            old_len,
+            new_start,
+            new_len,
+        })
     }
 
-    /// Analyze a single file's churn metrics.
-    fn analyze_file(root: &Path, file_path: &Path, days: u32) -> Result<FileChurn> {
-        let since = format!("{} days ago", days);
-
-        // Get commit hashes, authors, and dates for this file
-        let log_output = Self::git_cmd(root)
-            .args([
-                "log",
-                "--since",
-                &since,
-                "--pretty=format:%H|%an|%ad",
-                "--date=iso",
-                "--",
-                file_path.to_str().unwrap_or(""),
-            ])
-            .output()
-            .context("Failed to get git log for file")?;
-
-        if !log_output.status.success() {
-            anyhow::bail!("Git log failed for file: {}", file_path.display());
+    /// 0-based index in the post-image where the hunk's lines begin; a pure
+    /// deletion sits after line `new_start`.
+    fn post_index(&self) -> usize {
+        if self.new_len == 0 {
+            self.new_start
+        } else {
+            self.new_start.saturating_sub(1)
         }
+    }
+}
 
-        let log_stdout = String::from_utf8_lossy(&log_output.stdout);
-        let commits: Vec<&str> = log_stdout.lines().filter(|line| !line.is_empty()).collect();
-
-        let commit_count = commits.len() as u32;
-
-        // Extract unique authors
-        let mut authors = HashSet::new();
-        let mut last_modified = String::new();
+/// Streaming parser for `git log --numstat -p --unified=0` output, newest
+/// commit first.
+struct LogParser<'a> {
+    root: &'a Path,
+    files: HashMap<PathBuf, FileStats>,
+    /// For each file still present, the current (0-based) line each line of
+    /// the file after the commit being parsed maps to, or `None` for lines
+    /// deleted along with all their surroundings. `None` for files that no
+    /// longer exist.
+    origins: HashMap<PathBuf, Option<Vec<Option<u32>>>>,
+    line_changes: HashMap<PathBuf, Vec<LineChange>>,
+    commit: u32,
+    author: String,
+    date: String,
+    /// Post-image path and hunks of the file diff being read
+    file: Option<PathBuf>,
+    hunks: Vec<Hunk>,
+    in_patch: bool,
+    /// Content lines left in the current hunk
+    remaining: usize,
+}
 
-        for (idx, commit_line) in commits.iter().enumerate() {
-            let parts: Vec<&str> = commit_line.split('|').collect();
-            if parts.len() >= 3 {
-                authors.insert(parts[1].to_string());
-                // First entry is the most recent (git log is reverse chronological)
-                if idx == 0 {
-                    last_modified = parts[2].to_string();
-                }
-            }
+impl<'a> LogParser<'a> {
+    fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            files: HashMap::new(),
+            origins: HashMap::new(),
+            line_changes: HashMap::new(),
+            commit: 0,
+            author: String::new(),
+            date: String::new(),
+            file: None,
+            hunks: Vec::new(),
+            in_patch: false,
+            remaining: 0,
         }
-
-        let authors_vec: Vec<String> = authors.into_iter().collect();
-
-        // Get line change statistics
-        let (lines_added, lines_removed) = Self::get_line_stats(root, file_path, days)?;
-
-        Ok(FileChurn {
-            path: file_path.to_path_buf(),
-            commits: commit_count,
-            lines_added,
-            lines_removed,
-            authors: authors_vec,
-            last_modified,
-        })
     }
 
-    /// Get line addition/deletion statistics for a file.
-    fn get_line_stats(root: &Path, file_path: &Path, days: u32) -> Result<(u32, u32)> {
-        let since = format!("{} days ago", days);
-
-        let output = Self::git_cmd(root)
-            .args([
-                "log",
-                "--since",
-                &since,
-                "--numstat",
-                "--pretty=format:",
-                "--",
-                file_path.to_str().unwrap_or(""),
-            ])
-            .output()
-            .context("Failed to get numstat for file")?;
-
-        if !output.status.success() {
-            // If numstat fails, return zeros rather than error
-            return Ok((0, 0));
+    fn line(&mut self, line: &str) {
+        if self.remaining > 0 {
+            if !line.starts_with('\\') {
+                self.remaining -= 1;
+            }
+            return;
         }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut total_added = 0u32;
-        let mut total_removed = 0u32;
-
-        for line in stdout.lines() {
-            if line.trim().is_empty() {
-                continue;
+        if let Some(header) = line.strip_prefix('\x1e') {
+            self.flush_file();
+            let mut fields = header.split('\x1f');
+            fields.next(); // hash
+            self.commit += 1;
+            self.author = fields.next().unwrap_or_default().to_string();
+            self.date = fields.next().unwrap_or_default().to_string();
+            self.in_patch = false;
+        } else if line.starts_with("diff --git ") {
+            self.flush_file();
+            self.in_patch = true;
+        } else if !self.in_patch {
+            // Numstat: `added<TAB>removed<TAB>path`, `-` for binary files
+            let mut parts = line.splitn(3, '\t');
+            let (Some(added), Some(removed), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return;
+            };
+            let stats = self.files.entry(PathBuf::from(path)).or_default();
+            stats.commits += 1;
+            stats.lines_added += added.parse::<u32>().unwrap_or(0);
+            stats.lines_removed += removed.parse::<u32>().unwrap_or(0);
+            stats.authors.insert(self.author.clone());
+            // Newest commit comes first
+            if stats.last_modified.is_empty() {
+                stats.last_modified = self.date.clone();
             }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            self.file = path.strip_prefix("b/").map(PathBuf::from);
+        } else if let Some(hunk) = Hunk::parse(line) {
+            self.remaining = hunk.old_len + hunk.new_len;
+            self.hunks.push(hunk);
+        }
+    }
 
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                // Format: added removed filename
-                // Handle binary files (shown as "-")
-                if let Ok(added) = parts[0].parse::<u32>() {
-                    total_added += added;
-                }
-                if let Ok(removed) = parts[1].parse::<u32>() {
-                    total_removed += removed;
+    /// Map the finished file diff's hunks onto current lines, then move the
+    /// file's line origins back to before the commit.
+    fn flush_file(&mut self) {
+        let hunks = std::mem::take(&mut self.hunks);
+        let Some(path) = self.file.take() else {
+            return;
+        };
+        let root = self.root;
+        let origin = self.origins.entry(path.clone()).or_insert_with(|| {
+            let contents = std::fs::read(root.join(&path)).ok()?;
+            let lines = contents.split(|&b| b == b'\n').count();
+            Some((0..lines as u32).map(Some).collect())
+        });
+        let Some(origin) = origin else {
+            return;
+        };
+
+        let mut lines = Vec::new();
+        for hunk in &hunks {
+            let start = hunk.post_index();
+            if hunk.new_len == 0 {
+                // A deletion changes the line it follows
+                if let Some(Some(line)) = start.checked_sub(1).and_then(|i| origin.get(i)) {
+                    lines.push(*line as usize);
                 }
+            } else {
+                lines.extend(
+                    origin
+                        .iter()
+                        .skip(start)
+                        .take(hunk.new_len)
+                        .flatten()
+                        .map(|&l| l as usize),
+                );
             }
         }
 
-        Ok((total_added, total_removed))
+        // Replaced lines stand for their replacements, deleted lines for
+        // the line they followed, so older changes land where the code is now
+        let mut pre = Vec::with_capacity(origin.len());
+        let mut pos = 0;
+        for hunk in &hunks {
+            let start = hunk.post_index().clamp(pos, origin.len());
+            let end = (start + hunk.new_len).min(origin.len());
+            pre.extend_from_slice(&origin[pos..start]);
+            let replacement = &origin[start..end];
+            let anchor = start
+                .checked_sub(1)
+                .and_then(|i| origin.get(i))
+                .or(origin.get(start))
+                .copied()
+                .flatten();
+            pre.extend((0..hunk.old_len).map(|k| match replacement.len() {
+                0 => anchor,
+                len => replacement[k.min(len - 1)],
+            }));
+            pos = end;
+        }
+        pre.extend_from_slice(&origin[pos..]);
+        *origin = pre;
+
+        if !lines.is_empty() {
+            lines.sort_unstable();
+            lines.dedup();
+            self.line_changes.entry(path).or_default().push(LineChange {
+                commit: self.commit,
+                lines,
+            });
+        }
+    }
+
+    fn finish(
+        mut self,
+    ) -> (
+        HashMap<PathBuf, FileStats>,
+        HashMap<PathBuf, Vec<LineChange>>,
+    ) {
+        self.flush_file();
+        (self.files, self.line_changes)
     }
 }
 
 /// Report containing churn analysis results.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
 pub struct ChurnReport {
-    /// Per-file churn metrics
+    /// Days of history analyzed
+    pub days: u32,
+    /// Per-file churn metrics, most commits first
     pub file_churn: Vec<FileChurn>,
     /// Files with high change frequency (more than 3 commits)
     pub hotspots: Vec<(PathBuf, u32)>,
+    /// Per-symbol churn, most commits first (filled by
+    /// [`ChurnAnalyzer::analyze_index`])
+    pub symbols: Vec<SymbolChurn>,
+    /// Current lines each commit changed, per file (relative to the root)
+    #[serde(skip)]
+    pub line_changes: HashMap<PathBuf, Vec<LineChange>>,
 }
 
 /// Churn metrics for a single file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
 pub struct FileChurn {
     /// Path to the file (relative to repository root)
     pub path: PathBuf,
@@ -248,6 +511,31 @@ pub struct FileChurn {
     pub last_modified: String,
 }
 
+/// Churn metrics for a single symbol.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SymbolChurn {
+    pub symbol: String,
+    pub kind: String,
+    /// File relative to the repository root
+    pub file: PathBuf,
+    /// 0-based start line
+    pub line: usize,
+    /// Commits that changed a line of the symbol
+    pub commits: u32,
+    /// Changed lines summed over those commits
+    pub lines_changed: u32,
+}
+
+/// The current lines of a file one commit changed.
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    /// Position of the commit in the log, 1 for the newest
+    pub commit: u32,
+    /// 0-based current lines the commit changed, sorted; a line replaced
+    /// since counts as its replacement
+    pub lines: Vec<usize>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -473,5 +761,101 @@ mod tests {
         assert!(hotspot.is_some());
         let (_, commit_count) = hotspot.unwrap();
         assert_eq!(*commit_count, 5);
+
+        // Out of budget: only the newest commits are read
+        let budget = Budget::new(None, Some(2));
+        let partial = ChurnAnalyzer::analyze_within(repo_path, 365, &budget).unwrap();
+        assert!(budget.was_cut_short());
+        let hotspot = partial
+            .file_churn
+            .iter()
+            .find(|f| f.path == Path::new("hotspot.rs"))
+            .unwrap();
+        assert_eq!(hotspot.commits, 2);
+    }
+
+    #[test]
+    fn test_hunk_headers() {
+        let hunk =
+            Hunk::parse("@@ -43 +43,3 @@ pub use complexity::{ComplexityAnalyzer};").unwrap();
+        assert_eq!((hunk.old_len, hunk.new_start, hunk.new_len), (1, 43, 3));
+        assert_eq!(hunk.post_index(), 42);
+        let deletion = Hunk::parse("@@ -5,2 +4,0 @@").unwrap();
+        assert_eq!(deletion.post_index(), 4);
+        assert!(Hunk::parse("+@@ -1 +1 @@").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_attributes_changes_to_current_symbols() {
+        use crate::incremental::IncrementalIndexer;
+        use crate::state::create_state;
+
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        git_cmd(root)
+            .args(["init", "--initial-branch=main"])
+            .output()
+            .unwrap();
+        let commit = |message: &str| {
+            git_cmd(root).args(["add", "."]).output().unwrap();
+            let output = git_cmd(root)
+                .args([
+                    "-c",
+                    "user.name=Test User",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "--no-gpg-sign",
+                    "-m",
+                    message,
+                ])
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+        };
+
+        let stable = "pub fn stable() -> u32 {\n    1\n}\n";
+        fs::write(
+            root.join("lib.rs"),
+            format!("{stable}\npub fn busy() -> u32 {{\n    0\n}}\n"),
+        )
+        .unwrap();
+        commit("add");
+        for i in 1..=2 {
+            fs::write(
+                root.join("lib.rs"),
+                format!("{stable}\npub fn busy() -> u32 {{\n    {i}\n}}\n"),
+            )
+            .unwrap();
+            commit("change busy");
+        }
+        // Lines inserted above shift both functions down
+        fs::write(
+            root.join("lib.rs"),
+            format!("//! Library.\n\n{stable}\npub fn busy() -> u32 {{\n    2\n}}\n"),
+        )
+        .unwrap();
+        commit("document");
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let report = ChurnAnalyzer::analyze_index(&state, root, 365).unwrap();
+
+        assert_eq!(report.file_churn[0].commits, 4);
+        let churn: Vec<_> = report
+            .symbols
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.commits, s.lines_changed))
+            .collect();
+        assert_eq!(churn, [("crate::busy", 3, 5), ("crate::stable", 1, 3)]);
+        assert_eq!(report.symbols[0].line, 6);
+
+        let stored = state.churn.read();
+        assert_eq!(stored.as_ref().unwrap().symbols.len(), 2);
+        let node = *state.path_to_node.get(&root.join("lib.rs")).unwrap();
+        assert_eq!(state.topology_metrics.get(&node).unwrap().churn_count, 4);
     }
 }
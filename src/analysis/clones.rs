@@ -0,0 +1,486 @@
+//! Duplicated function body detection.
+//!
+//! Every function and method body is tokenized with literals normalized and
+//! comments dropped, cut into token shingles, and summarized by a MinHash
+//! signature. Locality-sensitive hashing over signature bands proposes
+//! candidate pairs without comparing every body with every other; candidates
+//! are then confirmed with the exact Jaccard similarity of their shingles
+//! and linked into clone groups.
+//!
+//! Tests are left out (their repeated setup is covered by
+//! [`super::test_clones`]), as are bodies annotated `omni:ignore duplication`.
+
+use super::test_clones::{find, jaccard};
+use crate::budget::Budget;
+use crate::state::OciState;
+use crate::types::{IGNORE_DUPLICATION, ModuleRole, SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Tokens per shingle when comparing bodies.
+const SHINGLE_SIZE: usize = 5;
+
+/// MinHash signature length.
+const NUM_HASHES: usize = 64;
+
+/// Signature rows per LSH band. With 16 bands of 4 rows, pairs around 0.5
+/// similarity become candidates half the time and pairs above 0.8 almost
+/// always do.
+const BAND_ROWS: usize = 4;
+
+/// Thresholds for clone detection.
+#[derive(Debug, Clone)]
+pub struct CloneConfig {
+    /// Bodies with fewer tokens than this are ignored
+    pub min_tokens: usize,
+    /// Bodies spanning fewer lines than this are ignored
+    pub min_lines: usize,
+    /// Minimum Jaccard similarity of token shingles to link two bodies
+    pub min_similarity: f32,
+    /// Include functions annotated `omni:ignore duplication` (for audits)
+    pub include_ignored: bool,
+}
+
+impl Default for CloneConfig {
+    fn default() -> Self {
+        Self {
+            min_tokens: 40,
+            min_lines: 5,
+            min_similarity: 0.8,
+            include_ignored: false,
+        }
+    }
+}
+
+/// A function whose body belongs to a clone group.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct CloneLocation {
+    /// Scoped name of the function
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    pub end_line: usize,
+    /// Normalized tokens in the body
+    pub tokens: usize,
+}
+
+/// Functions with duplicated or near-duplicated bodies.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CloneGroup {
+    pub functions: Vec<CloneLocation>,
+    /// Lowest similarity among the links that formed the group (1.0 for
+    /// bodies identical up to literals and formatting)
+    pub similarity: f32,
+    /// Tokens that would go away if the group shared one body
+    pub duplicated_tokens: usize,
+}
+
+/// Finds duplicated function bodies.
+pub struct CloneAnalyzer {
+    config: CloneConfig,
+}
+
+/// A function body, prepared for comparison.
+struct Candidate {
+    location: CloneLocation,
+    shingles: HashSet<u64>,
+    signature: Vec<u64>,
+}
+
+impl CloneAnalyzer {
+    /// Creates an analyzer with default thresholds.
+    pub fn new() -> Self {
+        Self {
+            config: CloneConfig::default(),
+        }
+    }
+
+    /// Use custom thresholds.
+    pub fn with_config(mut self, config: CloneConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Cluster function bodies by similarity.
+    ///
+    /// Groups are sorted by duplicated tokens, largest first.
+    pub fn analyze(&self, state: &OciState, root: &Path) -> Vec<CloneGroup> {
+        self.analyze_within(state, root, &Budget::unlimited())
+    }
+
+    /// [`analyze`](Self::analyze), charging `budget` one unit per file read
+    /// and per pair of bodies compared, and grouping only what was compared
+    /// before it runs out.
+    pub fn analyze_within(
+        &self,
+        state: &OciState,
+        root: &Path,
+        budget: &Budget,
+    ) -> Vec<CloneGroup> {
+        let candidates = self.collect(state, root, budget);
+
+        // Bodies sharing any band of their signature are compared
+        let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+        for (i, c) in candidates.iter().enumerate() {
+            for (band, rows) in c.signature.chunks(BAND_ROWS).enumerate() {
+                buckets.entry((band, hash(rows))).or_default().push(i);
+            }
+        }
+        let mut pairs: HashSet<(usize, usize)> = HashSet::new();
+        for ids in buckets.values() {
+            for (n, &i) in ids.iter().enumerate() {
+                for &j in &ids[n + 1..] {
+                    pairs.insert((i.min(j), i.max(j)));
+                }
+            }
+        }
+        let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        pairs.sort_unstable();
+
+        // Union-find over confirmed pairs
+        let mut parent: Vec<usize> = (0..candidates.len()).collect();
+        let mut link_similarity = vec![1.0f32; candidates.len()];
+        for (i, j) in pairs.into_iter().take_while(|_| budget.charge(1)) {
+            let similarity = jaccard(&candidates[i].shingles, &candidates[j].shingles);
+            if similarity < self.config.min_similarity {
+                continue;
+            }
+            let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+            let lowest = similarity.min(link_similarity[ri]).min(link_similarity[rj]);
+            if ri != rj {
+                parent[rj] = ri;
+            }
+            link_similarity[ri] = lowest;
+        }
+
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..candidates.len() {
+            let root = find(&mut parent, i);
+            members.entry(root).or_default().push(i);
+        }
+
+        let mut groups: Vec<CloneGroup> = members
+            .into_iter()
+            .filter(|(_, ids)| ids.len() >= 2)
+            .map(|(root, ids)| {
+                let functions: Vec<CloneLocation> = ids
+                    .iter()
+                    .map(|&i| candidates[i].location.clone())
+                    .collect();
+                let total: usize = functions.iter().map(|f| f.tokens).sum();
+                let largest = functions.iter().map(|f| f.tokens).max().unwrap_or(0);
+                CloneGroup {
+                    functions,
+                    similarity: link_similarity[root],
+                    duplicated_tokens: total - largest,
+                }
+            })
+            .collect();
+
+        groups.sort_by(|a, b| {
+            b.duplicated_tokens
+                .cmp(&a.duplicated_tokens)
+                .then_with(|| a.functions[0].file.cmp(&b.functions[0].file))
+                .then_with(|| a.functions[0].line.cmp(&b.functions[0].line))
+        });
+        groups
+    }
+
+    /// Bodies of every indexed non-test function, in file order.
+    fn collect(&self, state: &OciState, root: &Path, budget: &Budget) -> Vec<Candidate> {
+        let mut files: Vec<PathBuf> = state.file_ids.iter().map(|e| e.key().clone()).collect();
+        files.sort();
+
+        let mut candidates = Vec::new();
+        for file in &files {
+            if state.file_role(file) == ModuleRole::Tests {
+                continue;
+            }
+            if !budget.charge(1) {
+                break;
+            }
+            let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+                continue;
+            };
+            let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+                continue;
+            };
+            let mut functions: Vec<SymbolDef> = scoped_names
+                .into_iter()
+                .filter_map(|scoped| state.get_symbol(scoped))
+                // Same-named symbols in other files overwrite each other
+                .filter(|s| s.location.file == *file && is_candidate(s))
+                .filter(|s| self.config.include_ignored || !s.is_ignored(IGNORE_DUPLICATION))
+                .collect();
+            if functions.is_empty() {
+                continue;
+            }
+            functions.sort_by_key(|s| s.location.start_byte);
+            let Ok(contents) = std::fs::read_to_string(file) else {
+                continue;
+            };
+            let rel = file
+                .strip_prefix(root)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            for symbol in functions {
+                let lines = symbol
+                    .location
+                    .end_line
+                    .saturating_sub(symbol.location.start_line)
+                    + 1;
+                if lines < self.config.min_lines {
+                    continue;
+                }
+                let Some(source) =
+                    contents.get(symbol.location.start_byte..symbol.location.end_byte)
+                else {
+                    continue;
+                };
+                let tokens = tokenize(body(source));
+                if tokens.len() < self.config.min_tokens {
+                    continue;
+                }
+                let shingles = shingles(&tokens);
+                candidates.push(Candidate {
+                    location: CloneLocation {
+                        symbol: state.resolve(symbol.scoped_name).to_string(),
+                        file: rel.clone(),
+                        line: symbol.location.start_line,
+                        end_line: symbol.location.end_line,
+                        tokens: tokens.len(),
+                    },
+                    signature: minhash(&shingles),
+                    shingles,
+                });
+            }
+        }
+        candidates
+    }
+}
+
+impl Default for CloneAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Non-test functions and methods.
+fn is_candidate(symbol: &SymbolDef) -> bool {
+    matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+        && !symbol.is_test()
+        && !symbol.is_test_context
+}
+
+/// The body of a function: everything after the first `{`, or after the
+/// signature line for languages without braces.
+fn body(source: &str) -> &str {
+    match source.find('{') {
+        Some(open) => &source[open + 1..],
+        None => source.split_once('\n').map_or("", |(_, rest)| rest),
+    }
+}
+
+/// Tokens of a body with string and numeric literals replaced, comments
+/// dropped, and whitespace ignored.
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '/' && chars.peek() == Some(&'/') {
+            for next in chars.by_ref() {
+                if next == '\n' {
+                    break;
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut star = false;
+            for next in chars.by_ref() {
+                if star && next == '/' {
+                    break;
+                }
+                star = next == '*';
+            }
+        } else if c == '"' || c == '\'' || c == '`' {
+            let mut escaped = false;
+            for next in chars.by_ref() {
+                if escaped {
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == c {
+                    break;
+                }
+            }
+            tokens.push("\"_\"".to_string());
+        } else if c.is_ascii_digit() {
+            while chars
+                .peek()
+                .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.' || *n == '_')
+            {
+                chars.next();
+            }
+            tokens.push("0".to_string());
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut ident = c.to_string();
+            while let Some(&n) = chars.peek() {
+                if !(n.is_alphanumeric() || n == '_') {
+                    break;
+                }
+                ident.push(n);
+                chars.next();
+            }
+            tokens.push(ident);
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+fn shingles(tokens: &[String]) -> HashSet<u64> {
+    tokens
+        .windows(SHINGLE_SIZE.min(tokens.len()).max(1))
+        .map(hash)
+        .collect()
+}
+
+/// Minimum of each of [`NUM_HASHES`] seeded hashes over the shingles.
+fn minhash(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..NUM_HASHES as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|&s| mix(s ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// splitmix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    const BODY: &str = r#"    let mut total = 0;
+    for item in items {
+        if item.price > 100 {
+            total += item.price * item.quantity;
+        } else {
+            total += item.price;
+        }
+    }
+    let tax = total * 8 / 100;
+    println!("total {} tax {}", total, tax);
+    total + tax
+"#;
+
+    #[tokio::test]
+    async fn test_groups_duplicated_bodies() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/orders.rs"),
+            format!(
+                "pub fn order_total(items: &[Item]) -> u64 {{\n{}}}\n\n\
+                 pub fn unrelated(a: u64, b: u64) -> u64 {{\n    let c = a * 2;\n    let d = b * 3;\n    c + d\n}}\n",
+                BODY
+            ),
+        )
+        .unwrap();
+        // Same logic with different literals and a comment
+        std::fs::write(
+            root.join("src/invoices.rs"),
+            format!(
+                "pub fn invoice_total(items: &[Item]) -> u64 {{\n    // copied from orders\n{}}}\n",
+                BODY.replace("100", "250")
+                    .replace("\"total {} tax {}\"", "\"sum {}\"")
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/ignored.rs"),
+            format!(
+                "// omni:ignore duplication\npub fn cart_total(items: &[Item]) -> u64 {{\n{}}}\n",
+                BODY
+            ),
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let groups = CloneAnalyzer::new().analyze(&state, root);
+        assert_eq!(groups.len(), 1, "{:?}", groups);
+        let group = &groups[0];
+        let mut symbols: Vec<&str> = group.functions.iter().map(|f| f.symbol.as_str()).collect();
+        symbols.sort();
+        assert_eq!(symbols.len(), 2);
+        assert!(symbols[0].ends_with("invoice_total"));
+        assert!(symbols[1].ends_with("order_total"));
+        assert_eq!(group.similarity, 1.0);
+        assert_eq!(group.duplicated_tokens, group.functions[0].tokens);
+        let orders = group
+            .functions
+            .iter()
+            .find(|f| f.file == "src/orders.rs")
+            .unwrap();
+        assert_eq!(orders.line, 0);
+
+        let audit = CloneAnalyzer::new().with_config(CloneConfig {
+            include_ignored: true,
+            ..CloneConfig::default()
+        });
+        assert_eq!(audit.analyze(&state, root)[0].functions.len(), 3);
+    }
+
+    #[test]
+    fn test_near_duplicates_link_above_threshold() {
+        let a = tokenize(BODY);
+        let edited = BODY.replace(
+            "let tax = total * 8 / 100;",
+            "let tax = total * 8 / 100 + fee;",
+        );
+        let b = tokenize(&edited);
+        let similarity = jaccard(&shingles(&a), &shingles(&b));
+        assert!(
+            similarity >= CloneConfig::default().min_similarity && similarity < 1.0,
+            "{}",
+            similarity
+        );
+
+        let (sa, sb) = (minhash(&shingles(&a)), minhash(&shingles(&b)));
+        let agreeing = sa.iter().zip(&sb).filter(|(x, y)| x == y).count();
+        assert!(agreeing as f32 / NUM_HASHES as f32 > 0.6);
+        assert_eq!(
+            tokenize("f(1, \"a\") // x\n/* y */"),
+            tokenize("f(22, 'b')")
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Change coupling mined from git history.
+//!
+//! Files and symbols that keep changing in the same commits belong together
+//! even when no call or import says so: a model and its serializer, a handler
+//! and its route table, a schema and its migration. For a target changed in
+//! `n` commits of the churn window (see [`super::churn`]), every file or
+//! symbol changed in `k` of those commits gets an association rule with
+//! support `k` and confidence `k / n`.
+//!
+//! Commits touching more than [`MAX_COMMIT_FILES`] files (mass renames,
+//! formatting sweeps) say nothing about coupling and are skipped.
+
+use super::churn::{ChurnAnalyzer, ChurnReport, LineChange};
+use crate::budget::Budget;
+use crate::state::OciState;
+use crate::types::{SymbolDef, SymbolKind};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Default history window in days; coupling needs more history than churn.
+pub const DEFAULT_DAYS: u32 = 180;
+/// Default number of files and symbols reported.
+pub const DEFAULT_LIMIT: usize = 10;
+/// Fewest shared commits for a rule to be reported.
+pub const MIN_SUPPORT: u32 = 2;
+/// Commits changing more files than this are ignored.
+pub const MAX_COMMIT_FILES: usize = 30;
+
+/// A file that changed along with the target.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct CoChangedFile {
+    /// Path relative to the root
+    pub file: String,
+    /// Commits that changed both
+    pub together: u32,
+    /// `together` over the target's commits (0.0-1.0)
+    pub confidence: f64,
+}
+
+/// A function, type, or other symbol that changed along with the target.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct CoChangedSymbol {
+    /// Scoped name
+    pub symbol: String,
+    pub kind: String,
+    /// Path relative to the root
+    pub file: String,
+    /// 0-based line of the definition
+    pub line: usize,
+    /// Commits that changed both
+    pub together: u32,
+    /// `together` over the target's commits (0.0-1.0)
+    pub confidence: f64,
+}
+
+/// What usually changes with a file or symbol, most confident first.
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct CoChangeReport {
+    /// Days of history considered
+    pub days: u32,
+    /// The file (relative to the root) or scoped symbol analyzed
+    pub target: String,
+    /// Commits in the window that changed the target
+    pub commits: u32,
+    pub files: Vec<CoChangedFile>,
+    pub symbols: Vec<CoChangedSymbol>,
+}
+
+/// Association rules between files and symbols from git history.
+pub struct CoChangeAnalyzer;
+
+impl CoChangeAnalyzer {
+    /// Analyze `days` of history and report what changes with `target`, an
+    /// indexed file (absolute or relative to `root`), a scoped symbol name,
+    /// or a simple symbol name. Returns `Ok(None)` if the target is unknown.
+    pub fn analyze(
+        state: &OciState,
+        root: &Path,
+        target: &str,
+        days: u32,
+    ) -> Result<Option<CoChangeReport>> {
+        Self::analyze_within(state, root, target, days, &Budget::unlimited())
+    }
+
+    /// [`analyze`](Self::analyze) over the commits `budget` has room for
+    /// (see [`ChurnAnalyzer::analyze_within`]).
+    pub fn analyze_within(
+        state: &OciState,
+        root: &Path,
+        target: &str,
+        days: u32,
+        budget: &Budget,
+    ) -> Result<Option<CoChangeReport>> {
+        let churn = ChurnAnalyzer::analyze_index_within(state, root, days, budget)?;
+        Ok(Self::from_churn(state, root, &churn, target))
+    }
+
+    /// Like [`analyze`](Self::analyze), over an existing churn report.
+    pub fn from_churn(
+        state: &OciState,
+        root: &Path,
+        churn: &ChurnReport,
+        target: &str,
+    ) -> Option<CoChangeReport> {
+        let target = Target::resolve(state, root, target)?;
+        let wide = wide_commits(&churn.line_changes);
+        let commits: HashSet<u32> = churn
+            .line_changes
+            .get(&target.file)
+            .into_iter()
+            .flatten()
+            .filter(|change| !wide.contains(&change.commit))
+            .filter(|change| match &target.symbol {
+                Some(symbol) => touches(symbol, &change.lines),
+                None => true,
+            })
+            .map(|change| change.commit)
+            .collect();
+
+        let mut report = CoChangeReport {
+            days: churn.days,
+            target: match &target.symbol {
+                Some(symbol) => state.resolve(symbol.scoped_name).to_string(),
+                None => target.file.display().to_string(),
+            },
+            commits: commits.len() as u32,
+            ..Default::default()
+        };
+        if commits.is_empty() {
+            return Some(report);
+        }
+        let confidence = |together: u32| together as f64 / commits.len() as f64;
+
+        let mut symbols: HashMap<String, (SymbolDef, PathBuf, u32)> = HashMap::new();
+        for (path, changes) in &churn.line_changes {
+            let shared: Vec<&LineChange> = changes
+                .iter()
+                .filter(|change| commits.contains(&change.commit))
+                .collect();
+            if shared.is_empty() {
+                continue;
+            }
+            if *path != target.file {
+                report.files.push(CoChangedFile {
+                    file: path.display().to_string(),
+                    together: shared.len() as u32,
+                    confidence: confidence(shared.len() as u32),
+                });
+            }
+
+            let defs = file_symbols(state, &root.join(path));
+            for change in shared {
+                let mut seen = HashSet::new();
+                for def in change
+                    .lines
+                    .iter()
+                    .filter_map(|&line| innermost(&defs, line))
+                {
+                    if !seen.insert(def.scoped_name) {
+                        continue;
+                    }
+                    let same = match &target.symbol {
+                        Some(symbol) => def.scoped_name == symbol.scoped_name,
+                        None => *path == target.file,
+                    };
+                    if same {
+                        continue;
+                    }
+                    symbols
+                        .entry(state.resolve(def.scoped_name).to_string())
+                        .or_insert_with(|| (def.clone(), path.clone(), 0))
+                        .2 += 1;
+                }
+            }
+        }
+
+        report.files.retain(|f| f.together >= MIN_SUPPORT);
+        report.files.sort_by(|a, b| {
+            b.together
+                .cmp(&a.together)
+                .then_with(|| a.file.cmp(&b.file))
+        });
+        report.symbols = symbols
+            .into_iter()
+            .filter(|(_, (_, _, together))| *together >= MIN_SUPPORT)
+            .map(|(symbol, (def, file, together))| CoChangedSymbol {
+                symbol,
+                kind: def.kind.as_str().to_string(),
+                file: file.display().to_string(),
+                line: def.location.start_line,
+                together,
+                confidence: confidence(together),
+            })
+            .collect();
+        report.symbols.sort_by(|a, b| {
+            b.together
+                .cmp(&a.together)
+                .then_with(|| a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+        });
+        Some(report)
+    }
+}
+
+/// A file, or a symbol within it.
+struct Target {
+    /// Relative to the root, as in [`ChurnReport::line_changes`]
+    file: PathBuf,
+    symbol: Option<SymbolDef>,
+}
+
+impl Target {
+    fn resolve(state: &OciState, root: &Path, spec: &str) -> Option<Self> {
+        let path = Path::new(spec);
+        for candidate in [path.to_path_buf(), root.join(path)] {
+            let candidate = candidate.canonicalize().unwrap_or(candidate);
+            if state.file_ids.contains_key(&candidate) {
+                return Some(Self {
+                    file: relative(root, &candidate),
+                    symbol: None,
+                });
+            }
+        }
+
+        let symbol = match state.lookup(spec).and_then(|key| state.get_symbol(key)) {
+            Some(symbol) => symbol,
+            None => {
+                let mut candidates = state.find_by_name(spec);
+                candidates.sort_by(|a, b| {
+                    a.is_test_context
+                        .cmp(&b.is_test_context)
+                        .then_with(|| a.location.file.cmp(&b.location.file))
+                        .then(a.location.start_byte.cmp(&b.location.start_byte))
+                });
+                candidates.into_iter().next()?
+            }
+        };
+        Some(Self {
+            file: relative(root, &symbol.location.file),
+            symbol: Some(symbol),
+        })
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    path.strip_prefix(&root).unwrap_or(path).to_path_buf()
+}
+
+/// Commits that changed more than [`MAX_COMMIT_FILES`] files.
+fn wide_commits(line_changes: &HashMap<PathBuf, Vec<LineChange>>) -> HashSet<u32> {
+    let mut files_per_commit: HashMap<u32, usize> = HashMap::new();
+    for change in line_changes.values().flatten() {
+        *files_per_commit.entry(change.commit).or_default() += 1;
+    }
+    files_per_commit
+        .into_iter()
+        .filter(|(_, files)| *files > MAX_COMMIT_FILES)
+        .map(|(commit, _)| commit)
+        .collect()
+}
+
+fn touches(symbol: &SymbolDef, lines: &[usize]) -> bool {
+    let range = symbol.location.start_line..=symbol.location.end_line;
+    lines.iter().any(|line| range.contains(line))
+}
+
+/// Symbols defined in `path` that changes are attributed to.
+fn file_symbols(state: &OciState, path: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(path).map(|id| *id) else {
+        return Vec::new();
+    };
+    let Some(mut scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+        return Vec::new();
+    };
+    // A type and its impls share a scoped name
+    scoped_names.sort_unstable();
+    scoped_names.dedup();
+    scoped_names
+        .into_iter()
+        .filter_map(|name| state.get_symbol(name))
+        .filter(|def| def.location.file == path)
+        .filter(|def| {
+            !matches!(
+                def.kind,
+                SymbolKind::Module | SymbolKind::Field | SymbolKind::Variant | SymbolKind::Column
+            )
+        })
+        .collect()
+}
+
+/// The smallest symbol spanning `line`, so a changed method counts for the
+/// method and not its impl.
+fn innermost(defs: &[SymbolDef], line: usize) -> Option<&SymbolDef> {
+    defs.iter()
+        .filter(|def| (def.location.start_line..=def.location.end_line).contains(&line))
+        .min_by_key(|def| def.location.end_line - def.location.start_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_files_and_symbols_changed_together() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let git = |args: &[&str]| {
+            let output = ChurnAnalyzer::git_cmd(root)
+                .args([
+                    "-c",
+                    "user.name=Test User",
+                    "-c",
+                    "user.email=test@example.com",
+                ])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        let commit = |message: &str| {
+            git(&["add", "."]);
+            git(&["commit", "--no-gpg-sign", "-m", message]);
+        };
+        let write = |file: &str, contents: String| fs::write(root.join(file), contents).unwrap();
+        let model = |fields: &str| format!("pub struct User {{\n{fields}}}\n");
+        let serializer = |fields: &str| {
+            format!(
+                "pub fn to_json() -> &'static str {{\n    \"{fields}\"\n}}\n\npub fn unrelated() {{}}\n"
+            )
+        };
+
+        git(&["init", "--initial-branch=main"]);
+        write("model.rs", model("    pub id: u32,\n"));
+        write("serialize.rs", serializer("id"));
+        write("other.rs", "pub fn other() {}\n".to_string());
+        commit("add");
+        for (field, json) in [("name", "id,name"), ("email", "id,name,email")] {
+            let fields = ["id", "name", "email"]
+                .iter()
+                .take_while(|f| **f != field)
+                .chain(std::iter::once(&field))
+                .map(|f| format!("    pub {f}: String,\n"))
+                .collect::<String>();
+            write("model.rs", model(&fields));
+            write("serialize.rs", serializer(json));
+            commit("add a field");
+        }
+        write(
+            "other.rs",
+            "pub fn other() {\n    // changed alone\n}\n".to_string(),
+        );
+        commit("other");
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let report = CoChangeAnalyzer::analyze(&state, root, "model.rs", 365)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.target, "model.rs");
+        assert_eq!(report.commits, 3);
+        assert_eq!(
+            report.files,
+            [CoChangedFile {
+                file: "serialize.rs".to_string(),
+                together: 3,
+                confidence: 1.0,
+            }]
+        );
+        let symbols: Vec<_> = report
+            .symbols
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.together))
+            .collect();
+        assert_eq!(symbols, [("crate::to_json", 3)]);
+
+        // By symbol, reusing the stored history
+        let churn = state.churn.read().clone().unwrap();
+        let report = CoChangeAnalyzer::from_churn(&state, root, &churn, "to_json").unwrap();
+        assert_eq!(report.target, "crate::to_json");
+        assert_eq!(report.files[0].file, "model.rs");
+        assert_eq!(report.symbols[0].symbol, "crate::User");
+
+        assert!(CoChangeAnalyzer::from_churn(&state, root, &churn, "missing").is_none());
+    }
+}
@@ -0,0 +1,204 @@
+//! Cyclomatic complexity from the tree-sitter AST.
+//!
+//! A function's complexity is 1 plus its decision points: conditionals,
+//! loops, match/switch arms, catch clauses, ternaries, and short-circuit
+//! `&&`/`||`. Node kinds are matched across the supported grammars, so the
+//! count works for every language with a parser. Decisions in closures and
+//! nested functions count toward the innermost indexed function.
+
+use super::panics::{file_functions, relative};
+use crate::parsing::parser_for_file;
+use crate::state::OciState;
+use serde::Serialize;
+use std::path::Path;
+use tree_sitter::{Node, Parser};
+
+/// Node kinds that add a path through a function, across the Rust,
+/// TypeScript, Java, and Kotlin grammars.
+const DECISION_KINDS: &[&str] = &[
+    // Conditionals
+    "if_expression",
+    "if_statement",
+    "ternary_expression",
+    // Loops
+    "while_expression",
+    "while_statement",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "enhanced_for_statement",
+    "do_statement",
+    "do_while_statement",
+    // Arms and cases
+    "match_arm",
+    "switch_case",
+    "switch_block_statement_group",
+    "switch_rule",
+    "when_entry",
+    // Exception handlers
+    "catch_clause",
+    "catch_block",
+    // Kotlin `&&` and `||`
+    "conjunction_expression",
+    "disjunction_expression",
+];
+
+/// Complexity of one function or method.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct FunctionComplexity {
+    /// Scoped name
+    pub symbol: String,
+    /// Path relative to the root
+    pub file: String,
+    /// 0-based line of the definition
+    pub line: usize,
+    pub complexity: u32,
+}
+
+/// Complexity of a file: the sum over its functions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct FileComplexity {
+    /// Path relative to the root
+    pub file: String,
+    pub complexity: u32,
+    /// Functions, most complex first
+    pub functions: Vec<FunctionComplexity>,
+}
+
+/// Computes cyclomatic complexity for indexed files.
+#[derive(Debug, Default)]
+pub struct ComplexityAnalyzer;
+
+impl ComplexityAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Complexity of the indexed functions in `file`, or `None` if it has
+    /// no parser or cannot be read.
+    pub fn analyze_file(
+        &self,
+        state: &OciState,
+        root: &Path,
+        file: &Path,
+    ) -> Option<FileComplexity> {
+        let language = parser_for_file(file)?.language();
+        let contents = match state.overlays.get(file) {
+            Some(overlay) => overlay.contents.to_string(),
+            None => std::fs::read_to_string(file).ok()?,
+        };
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        let tree = parser.parse(&contents, None)?;
+        let mut decisions = Vec::new();
+        walk(tree.root_node(), contents.as_bytes(), &mut decisions);
+
+        let rel = relative(root, file);
+        let functions = file_functions(state, file);
+        let mut complexities: Vec<FunctionComplexity> = functions
+            .iter()
+            .map(|function| FunctionComplexity {
+                symbol: state.resolve(function.scoped_name).to_string(),
+                file: rel.clone(),
+                line: function.location.start_line,
+                complexity: 1,
+            })
+            .collect();
+        for byte in decisions {
+            // Innermost function containing the decision
+            let innermost = functions
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.location.start_byte <= byte && byte < f.location.end_byte)
+                .min_by_key(|(_, f)| f.location.end_byte - f.location.start_byte);
+            if let Some((i, _)) = innermost {
+                complexities[i].complexity += 1;
+            }
+        }
+        complexities.sort_by(|a, b| b.complexity.cmp(&a.complexity).then(a.line.cmp(&b.line)));
+
+        Some(FileComplexity {
+            file: rel,
+            complexity: complexities.iter().map(|f| f.complexity).sum(),
+            functions: complexities,
+        })
+    }
+}
+
+/// Collect the start byte of every decision point under `node`.
+fn walk(node: Node, bytes: &[u8], found: &mut Vec<usize>) {
+    if is_decision(node, bytes) {
+        found.push(node.start_byte());
+    }
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, found);
+        }
+    }
+}
+
+fn is_decision(node: Node, bytes: &[u8]) -> bool {
+    match node.kind() {
+        "binary_expression" => node
+            .child_by_field_name("operator")
+            .and_then(|op| op.utf8_text(bytes).ok())
+            .is_some_and(|op| matches!(op, "&&" | "||")),
+        kind => DECISION_KINDS.contains(&kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_counts_decisions_per_function() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("lib.rs"),
+            "pub fn flat() -> u32 {\n    1\n}\n\n\
+             pub fn branchy(x: Option<u32>, y: bool) -> u32 {\n    \
+                 if y && x.is_some() {\n        return 1;\n    }\n    \
+                 for _ in 0..3 {}\n    \
+                 match x {\n        Some(0) => 0,\n        Some(_) => 1,\n        None => 2,\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("app.ts"),
+            "export function check(a: boolean, b: boolean): number {\n  if (a || b) {\n    return a ? 1 : 2;\n  }\n  return 3;\n}\n",
+        )
+        .unwrap();
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let analyzer = ComplexityAnalyzer::new();
+        let report = analyzer
+            .analyze_file(&state, root, &root.join("lib.rs"))
+            .unwrap();
+        let complexity = |name: &str| {
+            report
+                .functions
+                .iter()
+                .find(|f| f.symbol == name)
+                .unwrap()
+                .complexity
+        };
+        assert_eq!(complexity("crate::flat"), 1);
+        // if, &&, for, three arms
+        assert_eq!(complexity("crate::branchy"), 7);
+        assert_eq!(report.functions[0].symbol, "crate::branchy");
+        assert_eq!(report.complexity, 8);
+
+        let typescript = analyzer
+            .analyze_file(&state, root, &root.join("app.ts"))
+            .unwrap();
+        // if, ||, ternary
+        assert_eq!(typescript.complexity, 4);
+    }
+}
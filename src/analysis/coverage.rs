@@ -3,10 +3,11 @@
 //! Supports LLVM coverage (from `cargo llvm-cov --json`) and Tarpaulin JSON formats.
 //! Correlates coverage data with symbol definitions to provide per-symbol coverage metrics.
 
+use crate::budget::Budget;
 use crate::state::OciState;
-use crate::types::SymbolCoverage;
+use crate::types::{SymbolCoverage, SymbolKind, Visibility};
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
@@ -23,6 +24,25 @@ pub struct CoverageData {
     pub branch_coverage: HashMap<PathBuf, BranchCoverage>,
 }
 
+impl CoverageData {
+    /// Line coverage of `file`, which reports name by absolute path or
+    /// relative to the workspace `root`.
+    pub fn lines_for(&self, file: &Path, root: &Path) -> Option<&LineCoverage> {
+        self.line_coverage.get(file).or_else(|| {
+            let relative = file.strip_prefix(root).ok()?;
+            self.line_coverage.get(relative)
+        })
+    }
+
+    /// Branch coverage of `file`, looked up like [`lines_for`](Self::lines_for).
+    pub fn branches_for(&self, file: &Path, root: &Path) -> Option<&BranchCoverage> {
+        self.branch_coverage.get(file).or_else(|| {
+            let relative = file.strip_prefix(root).ok()?;
+            self.branch_coverage.get(relative)
+        })
+    }
+}
+
 /// Line coverage information for a file.
 #[derive(Debug, Clone, Default)]
 pub struct LineCoverage {
@@ -39,6 +59,37 @@ pub struct BranchCoverage {
     pub branches_per_line: HashMap<usize, (u32, u32)>,
 }
 
+/// Coverage of one function or method.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FunctionCoverage {
+    pub symbol: String,
+    pub kind: String,
+    pub visibility: String,
+    /// File relative to the workspace root
+    pub file: String,
+    /// 0-based start line
+    pub line: usize,
+    pub lines_covered: u32,
+    pub lines_total: u32,
+    pub branches_covered: u32,
+    pub branches_total: u32,
+    /// Share of lines covered, 0.0-1.0
+    pub coverage: f32,
+}
+
+/// A coverage report mapped onto the index's functions and methods.
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct CoverageReport {
+    /// Functions and methods with executable lines in the report
+    pub functions: usize,
+    pub lines_covered: u32,
+    pub lines_total: u32,
+    /// Least covered first, larger functions first among equals
+    pub least_covered: Vec<FunctionCoverage>,
+    /// Public functions and methods none of whose lines ran, by file and line
+    pub untested_public: Vec<FunctionCoverage>,
+}
+
 // ============================================================================
 // LLVM Coverage Format
 // ============================================================================
@@ -54,51 +105,109 @@ struct LlvmCoverageData {
     files: Vec<LlvmCoverageFile>,
 }
 
+/// Segments and branches are arrays in `llvm-cov export` output; the object
+/// forms are accepted too.
 #[derive(Debug, Deserialize)]
 struct LlvmCoverageFile {
     filename: String,
     #[serde(default)]
-    segments: Vec<LlvmSegment>,
+    segments: Vec<serde_json::Value>,
     #[serde(default)]
-    branches: Vec<LlvmBranch>,
+    branches: Vec<serde_json::Value>,
 }
 
 /// LLVM coverage segment: [line, col, count, has_count, is_region_entry, is_gap_region]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct LlvmSegment {
     #[serde(default)]
     line: usize,
     #[serde(default)]
-    #[allow(dead_code)]
-    col: usize,
-    #[serde(default)]
     count: u64,
     #[serde(default)]
     has_count: bool,
     #[serde(default)]
     is_region_entry: bool,
+    #[serde(default)]
+    is_gap_region: bool,
 }
 
-/// LLVM branch coverage data
-#[derive(Debug, Deserialize)]
-struct LlvmBranch {
-    #[serde(default)]
-    line: usize,
-    #[serde(default)]
-    #[allow(dead_code)]
-    count: u64,
-    #[serde(default)]
-    covered: bool,
+impl LlvmSegment {
+    fn parse(value: serde_json::Value) -> Option<Self> {
+        match value {
+            serde_json::Value::Array(fields) => {
+                let number = |i: usize| fields.get(i).and_then(|v| v.as_u64());
+                let flag = |i: usize| fields.get(i).and_then(|v| v.as_bool()).unwrap_or(false);
+                Some(Self {
+                    line: number(0)? as usize,
+                    count: number(2).unwrap_or(0),
+                    has_count: flag(3),
+                    is_region_entry: flag(4),
+                    is_gap_region: flag(5),
+                })
+            }
+            value => serde_json::from_value(value).ok(),
+        }
+    }
+}
+
+/// LLVM branch coverage: `[line_start, col_start, line_end, col_end,
+/// true_count, false_count, ...]`, one entry per two-way branch, or
+/// `{line, covered}` for a single outcome.
+fn llvm_branch_outcomes(value: serde_json::Value) -> Option<(usize, u32, u32)> {
+    match value {
+        serde_json::Value::Array(fields) => {
+            let number = |i: usize| fields.get(i).and_then(|v| v.as_u64());
+            let line = number(0)? as usize;
+            let covered = [number(4)?, number(5)?].iter().filter(|&&c| c > 0).count();
+            Some((line, covered as u32, 2))
+        }
+        serde_json::Value::Object(fields) => {
+            let line = fields.get("line")?.as_u64()? as usize;
+            let covered = fields.get("covered").and_then(|v| v.as_bool()) == Some(true);
+            Some((line, covered as u32, 1))
+        }
+        _ => None,
+    }
 }
 
 // ============================================================================
 // Tarpaulin Format
 // ============================================================================
 
-/// Tarpaulin JSON format.
+/// Tarpaulin JSON format: `cargo tarpaulin --out Json` writes a list of
+/// files with per-line traces; a map of files with `covered`/`uncovered`
+/// line lists is accepted too.
 #[derive(Debug, Deserialize)]
 struct TarpaulinRoot {
-    files: HashMap<String, TarpaulinFile>,
+    files: TarpaulinFiles,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TarpaulinFiles {
+    Traces(Vec<TarpaulinTracedFile>),
+    Lines(HashMap<String, TarpaulinFile>),
+}
+
+#[derive(Debug, Deserialize)]
+struct TarpaulinTracedFile {
+    /// Path components, starting with `/` for absolute paths
+    path: Vec<String>,
+    #[serde(default)]
+    traces: Vec<TarpaulinTrace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TarpaulinTrace {
+    line: usize,
+    stats: TarpaulinStats,
+}
+
+/// Hit count of a line trace (`{"Line": 3}`)
+#[derive(Debug, Deserialize)]
+struct TarpaulinStats {
+    #[serde(rename = "Line")]
+    line: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -110,6 +219,51 @@ struct TarpaulinFile {
     uncovered: Vec<usize>,
 }
 
+/// Line coverage from LLVM segments, following `llvm-cov`'s own line stats:
+/// a line is executable if a region starts on it or a counted region wraps
+/// into it, and its count is the largest of those regions.
+fn llvm_line_coverage(segments: &[LlvmSegment]) -> LineCoverage {
+    let mut line_cov = LineCoverage::default();
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return line_cov;
+    };
+    let starts_region = |s: &LlvmSegment| !s.is_gap_region && s.has_count && s.is_region_entry;
+
+    let mut wrapped: Option<&LlvmSegment> = None;
+    let mut next = 0;
+    for line in first.line.max(1)..=last.line {
+        let start = next;
+        while next < segments.len() && segments[next].line <= line {
+            next += 1;
+        }
+        let on_line = &segments[start..next];
+
+        let skipped = on_line
+            .first()
+            .is_some_and(|s| !s.has_count && s.is_region_entry);
+        let region_starts = on_line.iter().any(starts_region);
+        let mapped = !skipped && (wrapped.is_some_and(|w| w.has_count) || region_starts);
+        if mapped {
+            let count = on_line
+                .iter()
+                .filter(|s| starts_region(s))
+                .map(|s| s.count)
+                .chain(wrapped.filter(|w| w.has_count).map(|w| w.count))
+                .max()
+                .unwrap_or(0);
+            if count > 0 {
+                line_cov.covered_lines.push(line);
+            } else {
+                line_cov.uncovered_lines.push(line);
+            }
+        }
+        if let Some(last_on_line) = on_line.last() {
+            wrapped = Some(last_on_line);
+        }
+    }
+    line_cov
+}
+
 // ============================================================================
 // Coverage Analyzer
 // ============================================================================
@@ -118,6 +272,25 @@ struct TarpaulinFile {
 pub struct CoverageAnalyzer;
 
 impl CoverageAnalyzer {
+    /// Load coverage data in either supported format, detected from the
+    /// top-level keys (`data` for LLVM, `files` for Tarpaulin).
+    pub fn load(path: &Path) -> Result<CoverageData> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read coverage file: {:?}", path))?;
+        let json: serde_json::Value = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse coverage JSON: {:?}", path))?;
+        if json.get("data").is_some() {
+            Self::load_llvm_cov(path)
+        } else if json.get("files").is_some() {
+            Self::load_tarpaulin(path)
+        } else {
+            anyhow::bail!(
+                "Unrecognized coverage format in {:?}; expected `cargo llvm-cov --json` or tarpaulin JSON",
+                path
+            )
+        }
+    }
+
     /// Load coverage data from LLVM coverage JSON format.
     ///
     /// This parses the JSON output from `cargo llvm-cov --json`.
@@ -134,48 +307,24 @@ impl CoverageAnalyzer {
             for file in data.files {
                 let file_path = PathBuf::from(&file.filename);
 
-                // Process segments to determine line coverage
-                let mut line_execution_counts: HashMap<usize, u64> = HashMap::new();
-
-                for segment in file.segments {
-                    if segment.line > 0 && segment.has_count && segment.is_region_entry {
-                        // Track the maximum execution count for each line
-                        line_execution_counts
-                            .entry(segment.line)
-                            .and_modify(|c| *c = (*c).max(segment.count))
-                            .or_insert(segment.count);
-                    }
-                }
-
-                // Build line coverage
-                let mut line_cov = LineCoverage::default();
-                for (line, count) in line_execution_counts {
-                    if count > 0 {
-                        line_cov.covered_lines.push(line);
-                    } else {
-                        line_cov.uncovered_lines.push(line);
-                    }
-                }
-
-                // Sort for consistent output
-                line_cov.covered_lines.sort_unstable();
-                line_cov.uncovered_lines.sort_unstable();
+                let segments: Vec<LlvmSegment> = file
+                    .segments
+                    .into_iter()
+                    .filter_map(LlvmSegment::parse)
+                    .collect();
+                let line_cov = llvm_line_coverage(&segments);
 
                 coverage.line_coverage.insert(file_path.clone(), line_cov);
 
                 // Process branches
                 let mut branch_cov = BranchCoverage::default();
-                for branch in file.branches {
-                    if branch.line > 0 {
-                        let (covered, total) = branch_cov
-                            .branches_per_line
-                            .entry(branch.line)
-                            .or_insert((0, 0));
-
-                        *total += 1;
-                        if branch.covered {
-                            *covered += 1;
-                        }
+                for (line, covered, total) in
+                    file.branches.into_iter().filter_map(llvm_branch_outcomes)
+                {
+                    if line > 0 {
+                        let entry = branch_cov.branches_per_line.entry(line).or_insert((0, 0));
+                        entry.0 += covered;
+                        entry.1 += total;
                     }
                 }
 
@@ -200,19 +349,45 @@ impl CoverageAnalyzer {
 
         let mut coverage = CoverageData::default();
 
-        for (_, file_data) in root.files {
-            let file_path = PathBuf::from(&file_data.path);
-
-            let mut line_cov = LineCoverage {
-                covered_lines: file_data.covered.clone(),
-                uncovered_lines: file_data.uncovered.clone(),
-            };
-
-            // Sort for consistent output
-            line_cov.covered_lines.sort_unstable();
-            line_cov.uncovered_lines.sort_unstable();
-
-            coverage.line_coverage.insert(file_path, line_cov);
+        match root.files {
+            TarpaulinFiles::Traces(files) => {
+                for file in files {
+                    let file_path: PathBuf = file.path.iter().collect();
+                    let mut line_cov = LineCoverage::default();
+                    for trace in file.traces {
+                        // Branch and condition traces carry no line hits
+                        match trace.stats.line {
+                            Some(0) => line_cov.uncovered_lines.push(trace.line),
+                            Some(_) => line_cov.covered_lines.push(trace.line),
+                            None => {}
+                        }
+                    }
+                    line_cov.covered_lines.sort_unstable();
+                    line_cov.covered_lines.dedup();
+                    line_cov.uncovered_lines.sort_unstable();
+                    line_cov.uncovered_lines.dedup();
+                    line_cov
+                        .uncovered_lines
+                        .retain(|l| line_cov.covered_lines.binary_search(l).is_err());
+                    coverage.line_coverage.insert(file_path, line_cov);
+                }
+            }
+            TarpaulinFiles::Lines(files) => {
+                for (_, file_data) in files {
+                    let mut line_cov = LineCoverage {
+                        covered_lines: file_data.covered,
+                        uncovered_lines: file_data.uncovered,
+                    };
+
+                    // Sort for consistent output
+                    line_cov.covered_lines.sort_unstable();
+                    line_cov.uncovered_lines.sort_unstable();
+
+                    coverage
+                        .line_coverage
+                        .insert(PathBuf::from(&file_data.path), line_cov);
+                }
+            }
         }
 
         // Tarpaulin doesn't provide branch coverage in basic JSON format
@@ -222,12 +397,23 @@ impl CoverageAnalyzer {
     /// Correlate coverage data with symbols in the index.
     ///
     /// For each symbol, this finds the file and line range, checks which lines
-    /// are covered, and calculates coverage metrics.
+    /// are covered, and calculates coverage metrics. Symbols whose lines hold
+    /// no executable code are left out.
     pub fn correlate_symbols(state: &OciState, coverage: &CoverageData) -> Vec<SymbolCoverage> {
+        Self::correlate_within(state, coverage, &Budget::unlimited())
+    }
+
+    /// [`correlate_symbols`](Self::correlate_symbols), charging `budget`
+    /// one unit per symbol and stopping when it runs out.
+    fn correlate_within(
+        state: &OciState,
+        coverage: &CoverageData,
+        budget: &Budget,
+    ) -> Vec<SymbolCoverage> {
         let mut results = Vec::new();
 
         // Iterate over all symbols
-        for symbol_entry in state.symbols.iter() {
+        for symbol_entry in state.symbols.iter().take_while(|_| budget.charge(1)) {
             let scoped_name = *symbol_entry.key();
             let symbol_def = symbol_entry.value();
 
@@ -235,20 +421,14 @@ impl CoverageAnalyzer {
             let file_path = &location.file;
 
             // Get coverage data for this file
-            let line_cov = match coverage.line_coverage.get(file_path) {
-                Some(cov) => cov,
-                None => continue, // No coverage data for this file
+            let Some(line_cov) = coverage.lines_for(file_path, &state.root_path) else {
+                continue; // No coverage data for this file
             };
+            let branch_cov = coverage.branches_for(file_path, &state.root_path);
 
-            let branch_cov = coverage.branch_coverage.get(file_path);
-
-            // Determine the line range for this symbol
-            let start_line = location.start_line;
-            let end_line = location.end_line;
-
-            if start_line == 0 || end_line == 0 {
-                continue; // Invalid location
-            }
+            // Symbol lines are 0-based, report lines 1-based
+            let start_line = location.start_line + 1;
+            let end_line = location.end_line + 1;
 
             // Count covered and total lines
             let mut lines_covered = 0u32;
@@ -296,6 +476,74 @@ impl CoverageAnalyzer {
         results
     }
 
+    /// Map coverage onto non-test functions and methods: totals, the least
+    /// covered, and the public ones no test reaches.
+    pub fn report(state: &OciState, coverage: &CoverageData, root: &Path) -> CoverageReport {
+        Self::report_within(state, coverage, root, &Budget::unlimited())
+    }
+
+    /// [`report`](Self::report) over the symbols `budget` has room for, one
+    /// unit each.
+    pub fn report_within(
+        state: &OciState,
+        coverage: &CoverageData,
+        root: &Path,
+        budget: &Budget,
+    ) -> CoverageReport {
+        let mut functions: Vec<FunctionCoverage> = Self::correlate_within(state, coverage, budget)
+            .into_iter()
+            .filter(|c| c.lines_total > 0)
+            .filter_map(|c| {
+                let def = state.get_symbol(c.symbol)?;
+                if !matches!(def.kind, SymbolKind::Function | SymbolKind::Method)
+                    || def.is_test()
+                    || def.is_test_context
+                {
+                    return None;
+                }
+                Some(FunctionCoverage {
+                    symbol: state.resolve(c.symbol).to_string(),
+                    kind: def.kind.as_str().to_string(),
+                    visibility: def.visibility.as_str().to_string(),
+                    file: def
+                        .location
+                        .file
+                        .strip_prefix(root)
+                        .unwrap_or(&def.location.file)
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                    line: def.location.start_line,
+                    lines_covered: c.lines_covered,
+                    lines_total: c.lines_total,
+                    branches_covered: c.branches_covered,
+                    branches_total: c.branches_total,
+                    coverage: Self::coverage_percentage(&c),
+                })
+            })
+            .collect();
+
+        let mut untested_public: Vec<FunctionCoverage> = functions
+            .iter()
+            .filter(|f| f.lines_covered == 0 && f.visibility == Visibility::Public.as_str())
+            .cloned()
+            .collect();
+        untested_public.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+
+        functions.sort_by(|a, b| {
+            a.coverage
+                .total_cmp(&b.coverage)
+                .then(b.lines_total.cmp(&a.lines_total))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        CoverageReport {
+            functions: functions.len(),
+            lines_covered: functions.iter().map(|f| f.lines_covered).sum(),
+            lines_total: functions.iter().map(|f| f.lines_total).sum(),
+            least_covered: functions,
+            untested_public,
+        }
+    }
+
     /// Calculate coverage percentage for a symbol.
     pub fn coverage_percentage(coverage: &SymbolCoverage) -> f32 {
         if coverage.lines_total == 0 {
@@ -328,13 +576,14 @@ mod tests {
                 "files": [{
                     "filename": "/path/to/src/main.rs",
                     "segments": [
-                        {"line": 10, "col": 1, "count": 5, "has_count": true, "is_region_entry": true},
-                        {"line": 11, "col": 1, "count": 0, "has_count": true, "is_region_entry": true},
-                        {"line": 12, "col": 1, "count": 3, "has_count": true, "is_region_entry": true}
+                        [10, 1, 5, true, true, false],
+                        [10, 12, 0, true, true, false],
+                        [12, 1, 5, true, false, false],
+                        [12, 5, 3, true, true, false],
+                        [12, 9, 0, false, false, false]
                     ],
                     "branches": [
-                        {"line": 10, "count": 2, "covered": true},
-                        {"line": 10, "count": 0, "covered": false}
+                        [10, 4, 10, 8, 2, 0, 0, 0, 4]
                     ]
                 }]
             }]
@@ -377,6 +626,95 @@ mod tests {
 
         assert_eq!(line_cov.covered_lines, vec![10, 12, 15]);
         assert_eq!(line_cov.uncovered_lines, vec![11, 13]);
+
+        // Detected without naming the format
+        let detected = CoverageAnalyzer::load(temp_file.path()).unwrap();
+        assert_eq!(
+            detected.line_coverage[&file_path].covered_lines,
+            vec![10, 12, 15]
+        );
+
+        let mut unknown = NamedTempFile::new().unwrap();
+        unknown.write_all(br#"{"coverage": []}"#).unwrap();
+        assert!(CoverageAnalyzer::load(unknown.path()).is_err());
+    }
+
+    #[test]
+    fn test_load_tarpaulin_traces() {
+        let tarpaulin_json = r#"{
+            "files": [{
+                "path": ["/", "path", "to", "src", "main.rs"],
+                "content": "",
+                "traces": [
+                    {"line": 10, "address": [1], "length": 1, "stats": {"Line": 4}},
+                    {"line": 11, "address": [2], "length": 1, "stats": {"Line": 0}},
+                    {"line": 11, "address": [3], "length": 1, "stats": {"Line": 2}},
+                    {"line": 13, "address": [4], "length": 1, "stats": {"Line": 0}}
+                ],
+                "covered": 2,
+                "coverable": 3
+            }],
+            "coverage_percent": 66.6
+        }"#;
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(tarpaulin_json.as_bytes()).unwrap();
+
+        let coverage = CoverageAnalyzer::load(temp_file.path()).unwrap();
+        let line_cov = &coverage.line_coverage[Path::new("/path/to/src/main.rs")];
+        assert_eq!(line_cov.covered_lines, vec![10, 11]);
+        assert_eq!(line_cov.uncovered_lines, vec![13]);
+    }
+
+    #[test]
+    fn test_report_lists_untested_public_functions() {
+        let state = create_state(PathBuf::from("/repo"));
+        let file_path = PathBuf::from("/repo/src/lib.rs");
+        let add = |name: &str, visibility: Visibility, start: usize, end: usize, test: bool| {
+            state.add_symbol(SymbolDef {
+                name: state.intern(name),
+                scoped_name: state.intern(&format!("crate::{}", name)),
+                kind: SymbolKind::Function,
+                location: Location::new(file_path.clone(), 0, 0).with_positions(start, 0, end, 0),
+                extended_location: None,
+                signature: None,
+                visibility,
+                attributes: vec![],
+                doc_comment: None,
+                parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: test,
+            });
+        };
+        // 0-based lines; the report below is 1-based
+        add("covered", Visibility::Public, 0, 2, false);
+        add("untested", Visibility::Public, 4, 6, false);
+        add("helper", Visibility::Private, 8, 9, false);
+        add("a_test", Visibility::Private, 11, 12, true);
+
+        // Relative to the workspace root, as some tools write it
+        let mut coverage = CoverageData::default();
+        coverage.line_coverage.insert(
+            PathBuf::from("src/lib.rs"),
+            LineCoverage {
+                covered_lines: vec![1, 2, 3, 12, 13],
+                uncovered_lines: vec![5, 6, 7, 9, 10],
+            },
+        );
+
+        let report = CoverageAnalyzer::report(&state, &coverage, Path::new("/repo"));
+        assert_eq!(report.functions, 3);
+        assert_eq!((report.lines_covered, report.lines_total), (3, 8));
+        let least: Vec<_> = report.least_covered.iter().map(|f| &f.symbol).collect();
+        assert_eq!(
+            least,
+            ["crate::untested", "crate::helper", "crate::covered"]
+        );
+        assert_eq!(report.untested_public.len(), 1);
+        assert_eq!(report.untested_public[0].symbol, "crate::untested");
+        assert_eq!(report.untested_public[0].file, "src/lib.rs");
+        assert_eq!(report.untested_public[0].line, 4);
     }
 
     #[test]
@@ -385,7 +723,8 @@ mod tests {
 
         // Add a test symbol
         let file_path = PathBuf::from("/path/to/src/main.rs");
-        let location = Location::new(file_path.clone(), 0, 100).with_positions(10, 0, 15, 0);
+        // Report lines 10-14
+        let location = Location::new(file_path.clone(), 0, 100).with_positions(9, 0, 13, 0);
 
         let name = state.intern("test_function");
         let scoped = state.intern("crate::test_function");
@@ -395,11 +734,15 @@ mod tests {
             scoped_name: scoped,
             kind: SymbolKind::Function,
             location,
+            extended_location: None,
             signature: None,
             visibility: Visibility::Public,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         };
 
         state.add_symbol(symbol);
@@ -0,0 +1,138 @@
+//! Analyses compiled in from other crates.
+//!
+//! A crate that depends on omni implements [`CustomAnalysis`] and hands it
+//! to [`register`] before starting omni (e.g. before
+//! `omni_index::mcp::run_server`). Registered analyses then run under their
+//! name wherever a built-in one would: `omni analyze <name>` (with
+//! `--format problems|sarif`) and the MCP `analyze` tool. Built-in analyses
+//! take precedence over a custom one of the same name.
+
+use super::problems::Problem;
+use crate::budget::Budget;
+use crate::state::OciState;
+use anyhow::{Result, bail};
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// What a custom analysis reports: one [`Problem`] per finding, with file
+/// paths relative to the workspace root.
+pub type Findings = Vec<Problem>;
+
+/// An analysis over the index, registered with [`register`].
+pub trait CustomAnalysis: Send + Sync {
+    /// Name it runs under, e.g. `unsafe-blocks`.
+    fn name(&self) -> &str;
+
+    /// One line on what it reports, shown next to the name in listings.
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// Run over an indexed workspace.
+    fn run(&self, state: &OciState) -> Result<Findings>;
+
+    /// [`run`](Self::run) within a request's time and work limits,
+    /// returning the findings so far once [`Budget::charge`] refuses. The
+    /// default ignores the budget.
+    fn run_within(&self, state: &OciState, _budget: &Budget) -> Result<Findings> {
+        self.run(state)
+    }
+}
+
+static REGISTRY: RwLock<Vec<Arc<dyn CustomAnalysis>>> = RwLock::new(Vec::new());
+
+/// Make `analysis` available to `omni analyze` and the MCP `analyze` tool
+/// for the rest of the process. Fails if the name is empty or taken.
+pub fn register(analysis: impl CustomAnalysis + 'static) -> Result<()> {
+    let name = analysis.name();
+    if name.trim().is_empty() {
+        bail!("Custom analysis name must not be empty");
+    }
+    let mut registry = REGISTRY.write();
+    if registry.iter().any(|a| a.name() == name) {
+        bail!("Custom analysis already registered: {}", name);
+    }
+    registry.push(Arc::new(analysis));
+    Ok(())
+}
+
+/// The registered analysis called `name`.
+pub fn find(name: &str) -> Option<Arc<dyn CustomAnalysis>> {
+    REGISTRY.read().iter().find(|a| a.name() == name).cloned()
+}
+
+/// Every registered analysis, by name.
+pub fn registered() -> Vec<Arc<dyn CustomAnalysis>> {
+    let mut analyses = REGISTRY.read().clone();
+    analyses.sort_by(|a, b| a.name().cmp(b.name()));
+    analyses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::problems::{Severity, location_line};
+    use crate::incremental::{IncrementalIndexer, IndexOptions};
+    use crate::state::create_state;
+    use crate::types::SymbolKind;
+    use tempfile::TempDir;
+
+    /// Public functions without a doc comment.
+    struct UndocumentedFunctions;
+
+    impl CustomAnalysis for UndocumentedFunctions {
+        fn name(&self) -> &str {
+            "test-undocumented-functions"
+        }
+
+        fn run(&self, state: &OciState) -> Result<Findings> {
+            let mut findings = Vec::new();
+            for entry in state.symbols.iter() {
+                let symbol = entry.value();
+                if symbol.kind == SymbolKind::Function && symbol.doc_comment.is_none() {
+                    let file = symbol.location.file.strip_prefix(&state.root_path)?;
+                    findings.push(Problem::new(
+                        self.name(),
+                        Severity::Note,
+                        &file.to_string_lossy(),
+                        location_line(state, &symbol.location.file, symbol.location.start_line),
+                        format!("{} has no doc comment", state.resolve(symbol.scoped_name)),
+                    ));
+                }
+            }
+            Ok(findings)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_analysis_runs_over_the_index() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "/// Documented.\npub fn documented() {}\n\npub fn bare() {}\n",
+        )
+        .unwrap();
+        let state = create_state(dir.path().to_path_buf());
+        IncrementalIndexer::new()
+            .index(&state, dir.path(), &IndexOptions::default())
+            .await
+            .unwrap();
+
+        register(UndocumentedFunctions).unwrap();
+        assert!(register(UndocumentedFunctions).is_err());
+        assert!(
+            registered()
+                .iter()
+                .any(|a| a.name() == "test-undocumented-functions")
+        );
+
+        let analysis = find("test-undocumented-functions").unwrap();
+        let findings = analysis.run(&state).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, "src/lib.rs");
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(findings[0].message, "crate::bare has no doc comment");
+        assert!(find("missing").is_none());
+    }
+}
@@ -3,18 +3,46 @@
 //! This module performs whole-program analysis to identify potentially dead code
 //! by building a reachability graph from entry points (main functions, tests, public APIs)
 //! and marking all symbols that are transitively called from those entry points.
+//! Symbols annotated `omni:ignore dead_code` are reported separately as ignored.
+//! Each dead symbol comes with a [`DeadExplanation`] saying why it is
+//! unreachable, so false positives can be triaged quickly.
+//! With [`DeadCodeAnalyzer::exclude_tests`], test code neither keeps symbols
+//! alive nor is reported, so code only exercised by tests shows up as dead.
 
+use crate::budget::Budget;
 use crate::state::OciState;
-use crate::types::{DeadCodeReport, InternedString, SymbolKind, Visibility};
-use std::collections::{HashSet, VecDeque};
+use crate::types::{
+    CallEdge, DeadCodeReport, DeadExplanation, DeadReason, IGNORE_DEAD_CODE, InternedString,
+    SymbolKind, Visibility,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Analyzes code to detect potentially dead (unreachable) symbols.
-pub struct DeadCodeAnalyzer;
+pub struct DeadCodeAnalyzer {
+    include_ignored: bool,
+    exclude_tests: bool,
+}
 
 impl DeadCodeAnalyzer {
     /// Creates a new dead code analyzer.
     pub fn new() -> Self {
-        Self
+        Self {
+            include_ignored: false,
+            exclude_tests: false,
+        }
+    }
+
+    /// Report symbols suppressed by `omni:ignore dead_code` as dead (for audits).
+    pub fn include_ignored(mut self, include: bool) -> Self {
+        self.include_ignored = include;
+        self
+    }
+
+    /// Ignore test code: tests are not entry points, calls from tests do not
+    /// count, and dead test helpers are not reported.
+    pub fn exclude_tests(mut self, exclude: bool) -> Self {
+        self.exclude_tests = exclude;
+        self
     }
 
     /// Performs dead code analysis on the entire codebase.
@@ -25,6 +53,13 @@ impl DeadCodeAnalyzer {
     /// 3. Marking all reachable symbols as live
     /// 4. Reporting unreachable symbols as potentially dead
     pub fn analyze(&self, state: &OciState) -> DeadCodeReport {
+        self.analyze_within(state, &Budget::unlimited())
+    }
+
+    /// [`analyze`](Self::analyze), charging `budget` one unit per symbol
+    /// checked for reachability and reporting only the dead symbols found
+    /// before it runs out.
+    pub fn analyze_within(&self, state: &OciState, budget: &Budget) -> DeadCodeReport {
         // Step 1: Identify all entry points
         let entry_points = self.identify_entry_points(state);
 
@@ -32,16 +67,21 @@ impl DeadCodeAnalyzer {
         let reachable = self.compute_reachable(state, &entry_points);
 
         // Step 3: Identify dead symbols (symbols not in reachable set)
-        let dead_symbols = self.find_dead_symbols(state, &reachable);
+        let (dead_symbols, ignored) = self.find_dead_symbols(state, &reachable, budget);
 
         // Step 4: Identify potentially live symbols (conservative estimation)
         // These are symbols that might be used through dynamic dispatch, FFI, etc.
         let potentially_live = self.identify_potentially_live(state, &reachable);
 
+        // Step 5: Explain why each dead symbol is unreachable
+        let explanations = explain_dead(state, &dead_symbols, self.exclude_tests);
+
         DeadCodeReport {
             dead_symbols,
             entry_points,
             potentially_live,
+            ignored,
+            explanations,
         }
     }
 
@@ -71,6 +111,10 @@ impl DeadCodeAnalyzer {
 
     /// Determines if a symbol is an entry point.
     fn is_entry_point(&self, state: &OciState, symbol: &crate::types::SymbolDef) -> bool {
+        if self.exclude_tests && symbol.is_test_context {
+            return false;
+        }
+
         // 1. Functions named "main" are always entry points
         let name = state.resolve(symbol.name);
         if name == "main" && matches!(symbol.kind, SymbolKind::Function) {
@@ -78,7 +122,7 @@ impl DeadCodeAnalyzer {
         }
 
         // 2. Functions with #[test] attribute
-        if symbol.attributes.iter().any(|attr| attr.contains("test")) {
+        if symbol.is_test() {
             return true;
         }
 
@@ -135,8 +179,12 @@ impl DeadCodeAnalyzer {
             let callees = state.find_callees(current);
 
             for call_edge in callees {
+                if self.exclude_tests && call_edge.is_test_context {
+                    continue;
+                }
+
                 // Resolve callee name to scoped symbols
-                let callee_symbols = state.find_by_name(&call_edge.callee_name);
+                let callee_symbols = state.call_targets(&call_edge);
 
                 for callee_sym in callee_symbols {
                     let callee_scoped = callee_sym.scoped_name;
@@ -162,15 +210,18 @@ impl DeadCodeAnalyzer {
         reachable
     }
 
-    /// Finds symbols that are not reachable (potentially dead).
+    /// Finds symbols that are not reachable (potentially dead), split from
+    /// those suppressed by `omni:ignore`.
     fn find_dead_symbols(
         &self,
         state: &OciState,
         reachable: &HashSet<InternedString>,
-    ) -> Vec<InternedString> {
+        budget: &Budget,
+    ) -> (Vec<InternedString>, Vec<InternedString>) {
         let mut dead_symbols = Vec::new();
+        let mut ignored = Vec::new();
 
-        for entry in state.symbols.iter() {
+        for entry in state.symbols.iter().take_while(|_| budget.charge(1)) {
             let scoped_name = *entry.key();
             let symbol = entry.value();
 
@@ -179,18 +230,30 @@ impl DeadCodeAnalyzer {
                 continue;
             }
 
+            if self.exclude_tests && symbol.is_test_context {
+                continue;
+            }
+
             // Skip certain symbol kinds that are not meaningful for dead code analysis
             if matches!(
                 symbol.kind,
-                SymbolKind::Module | SymbolKind::Field | SymbolKind::Variant
+                SymbolKind::Module
+                    | SymbolKind::Field
+                    | SymbolKind::Variant
+                    | SymbolKind::Table
+                    | SymbolKind::Column
             ) {
                 continue;
             }
 
-            dead_symbols.push(scoped_name);
+            if !self.include_ignored && symbol.is_ignored(IGNORE_DEAD_CODE) {
+                ignored.push(scoped_name);
+            } else {
+                dead_symbols.push(scoped_name);
+            }
         }
 
-        dead_symbols
+        (dead_symbols, ignored)
     }
 
     /// Identifies symbols that are potentially live through non-standard mechanisms.
@@ -217,12 +280,12 @@ impl DeadCodeAnalyzer {
 
             // Check for attributes that might indicate dynamic usage
             let has_special_attrs = symbol.attributes.iter().any(|attr| {
-                attr.contains("derive")
-                    || attr.contains("macro")
-                    || attr.contains("no_mangle")
-                    || attr.contains("export_name")
-                    || attr.contains("link_name")
-                    || attr.contains("used")
+                let name = attr.simple_name();
+                name.contains("macro")
+                    || matches!(
+                        name,
+                        "derive" | "no_mangle" | "export_name" | "link_name" | "used"
+                    )
             });
 
             if has_special_attrs {
@@ -246,6 +309,144 @@ impl DeadCodeAnalyzer {
     }
 }
 
+/// Explain each of `dead` (all unreachable) from its callers. With
+/// `exclude_tests`, callers in test code are set aside.
+fn explain_dead(
+    state: &OciState,
+    dead: &[InternedString],
+    exclude_tests: bool,
+) -> HashMap<InternedString, DeadExplanation> {
+    // Edges by callee name: simple names, plus scoped names for approximate edges
+    let mut edges_by_callee: HashMap<String, Vec<CallEdge>> = HashMap::new();
+    for edge in state.call_edges.read().iter() {
+        edges_by_callee
+            .entry(edge.callee_name.clone())
+            .or_default()
+            .push(edge.clone());
+    }
+    // Callers of `symbol`, from production code or (`tests`) test code
+    let callers_from = |symbol: InternedString, tests: bool| -> Vec<InternedString> {
+        let Some(def) = state.get_symbol(symbol) else {
+            return Vec::new();
+        };
+        let mut callers: Vec<InternedString> = [state.resolve(def.name), state.resolve(symbol)]
+            .into_iter()
+            .flat_map(|name| edges_by_callee.get(name).into_iter().flatten())
+            .filter(|edge| !exclude_tests || edge.is_test_context == tests)
+            .map(|edge| edge.caller)
+            .filter(|&caller| caller != symbol)
+            .collect();
+        callers.sort_by(|a, b| state.resolve(*a).cmp(state.resolve(*b)));
+        callers.dedup();
+        callers
+    };
+    let callers_of = |symbol: InternedString| callers_from(symbol, false);
+
+    dead.iter()
+        .filter_map(|&symbol| {
+            let def = state.get_symbol(symbol)?;
+            let callers = callers_of(symbol);
+            let test_callers = if exclude_tests && callers.is_empty() {
+                callers_from(symbol, true)
+            } else {
+                Vec::new()
+            };
+            let reason = if !test_callers.is_empty() {
+                DeadReason::TestOnly {
+                    callers: test_callers,
+                }
+            } else if callers.is_empty() {
+                DeadReason::NoCallers
+            } else if let Some(cfgs) = callers
+                .iter()
+                .map(|&caller| enclosing_cfgs(state, caller))
+                .collect::<Option<Vec<_>>>()
+            {
+                let mut cfgs: Vec<String> = cfgs.into_iter().flatten().collect();
+                cfgs.sort();
+                cfgs.dedup();
+                DeadReason::CfgOnly { callers, cfgs }
+            } else {
+                // Follow the first caller not yet visited until one has no callers
+                let mut chain = vec![symbol];
+                let mut cyclic = false;
+                let mut next = callers.first().copied();
+                while let Some(caller) = next.take() {
+                    chain.push(caller);
+                    let upstream = callers_of(caller);
+                    next = upstream.iter().copied().find(|c| !chain.contains(c));
+                    cyclic = next.is_none() && !upstream.is_empty();
+                }
+                DeadReason::DeadCallers {
+                    callers,
+                    chain,
+                    cyclic,
+                }
+            };
+            Some((
+                symbol,
+                DeadExplanation {
+                    visibility: def.visibility,
+                    reason,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// `#[cfg(...)]` predicates on `symbol` and its parents, or `None` if it is
+/// not conditionally compiled.
+fn enclosing_cfgs(state: &OciState, symbol: InternedString) -> Option<Vec<String>> {
+    let mut cfgs = Vec::new();
+    let mut current = state.get_symbol(symbol);
+    while let Some(def) = current {
+        cfgs.extend(def.cfgs().map(str::to_string));
+        current = def.parent.and_then(|p| state.get_symbol(p));
+    }
+    (!cfgs.is_empty()).then_some(cfgs)
+}
+
+impl DeadExplanation {
+    /// Human-readable explanation path, e.g. `["not public (private)",
+    /// "only called by dead code (crate::b): crate::a <- crate::b (no callers)"]`.
+    pub fn steps(&self, state: &OciState) -> Vec<String> {
+        let names = |symbols: &[InternedString], sep: &str| {
+            symbols
+                .iter()
+                .map(|&s| state.resolve(s))
+                .collect::<Vec<_>>()
+                .join(sep)
+        };
+        let reason = match &self.reason {
+            DeadReason::NoCallers => "no callers found".to_string(),
+            DeadReason::TestOnly { callers } => {
+                format!("only called from tests ({})", names(callers, ", "))
+            }
+            DeadReason::DeadCallers {
+                callers,
+                chain,
+                cyclic,
+            } => {
+                format!(
+                    "only called by dead code ({}): {} ({})",
+                    names(callers, ", "),
+                    names(chain, " <- "),
+                    if *cyclic { "cycle" } else { "no callers" }
+                )
+            }
+            DeadReason::CfgOnly { callers, cfgs } => format!(
+                "only called from cfg'd-out code ({}) under {}",
+                names(callers, ", "),
+                cfgs.iter()
+                    .map(|c| format!("cfg({})", c))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        vec![format!("not public ({})", self.visibility.as_str()), reason]
+    }
+}
+
 impl Default for DeadCodeAnalyzer {
     fn default() -> Self {
         Self::new()
@@ -256,7 +457,7 @@ impl Default for DeadCodeAnalyzer {
 mod tests {
     use super::*;
     use crate::state::create_state;
-    use crate::types::{Location, Signature, SymbolDef};
+    use crate::types::{Attribute, Location, Signature, SymbolDef};
     use std::path::PathBuf;
 
     #[test]
@@ -272,11 +473,15 @@ mod tests {
             scoped_name: main_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 0, 10),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add a test function
@@ -287,11 +492,15 @@ mod tests {
             scoped_name: test_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 20, 30),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
-            attributes: vec!["test".to_string()],
+            attributes: vec![Attribute::new("test", None)],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add a public function
@@ -302,11 +511,15 @@ mod tests {
             scoped_name: pub_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 40, 50),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Public,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add a private function (not an entry point)
@@ -317,11 +530,15 @@ mod tests {
             scoped_name: priv_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 60, 70),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         let entry_points = analyzer.identify_entry_points(&state);
@@ -346,11 +563,15 @@ mod tests {
             scoped_name: main_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 0, 10),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add helper function (called by main)
@@ -360,11 +581,15 @@ mod tests {
             scoped_name: helper_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 20, 30),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add dead function (not called)
@@ -374,11 +599,15 @@ mod tests {
             scoped_name: dead_scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/main.rs"), 40, 50),
+            extended_location: None,
             signature: Some(Signature::default()),
             visibility: Visibility::Private,
             attributes: vec![],
             doc_comment: None,
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add call edge: main -> helper
@@ -388,6 +617,11 @@ mod tests {
             callee_name: "helper".to_string(),
             location: Location::new(PathBuf::from("/test/main.rs"), 5, 6),
             is_method_call: false,
+            via_trait: None,
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
         });
 
         let report = analyzer.analyze(&state);
@@ -401,4 +635,212 @@ mod tests {
         // dead is not reachable
         assert!(report.dead_symbols.contains(&dead_scoped));
     }
+
+    #[tokio::test]
+    async fn test_ignored_symbols_are_reported_separately() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("main.rs"),
+            "fn main() {}\n\n// omni:ignore dead_code -- called from C\nfn exported() {}\n\nfn unused() {}\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        let names = |symbols: &[InternedString]| -> Vec<String> {
+            let mut names: Vec<String> = symbols
+                .iter()
+                .map(|s| state.resolve(*s).to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let report = DeadCodeAnalyzer::new().analyze(&state);
+        assert_eq!(names(&report.dead_symbols), vec!["crate::unused"]);
+        assert_eq!(names(&report.ignored), vec!["crate::exported"]);
+
+        let audit = DeadCodeAnalyzer::new()
+            .include_ignored(true)
+            .analyze(&state);
+        assert_eq!(
+            names(&audit.dead_symbols),
+            vec!["crate::exported", "crate::unused"]
+        );
+        assert!(audit.ignored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dead_symbols_are_explained() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("main.rs"),
+            "fn main() {}\n\nfn orphan() { helper(); }\n\nfn helper() { leaf(); }\n\nfn leaf() {}\n\n#[cfg(feature = \"legacy\")]\nfn legacy() { shim(); }\n\nfn shim() {}\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        let report = DeadCodeAnalyzer::new().analyze(&state);
+        let explain = |name: &str| report.explanations[&state.intern(name)].clone();
+        let names = |symbols: &[InternedString]| -> Vec<&str> {
+            symbols.iter().map(|s| state.resolve(*s)).collect()
+        };
+
+        assert_eq!(explain("crate::orphan").reason, DeadReason::NoCallers);
+        assert_eq!(explain("crate::orphan").visibility, Visibility::Private);
+
+        let DeadReason::DeadCallers { chain, cyclic, .. } = explain("crate::leaf").reason else {
+            panic!("expected dead callers");
+        };
+        assert_eq!(
+            names(&chain),
+            vec!["crate::leaf", "crate::helper", "crate::orphan"]
+        );
+        assert!(!cyclic);
+        assert_eq!(
+            explain("crate::leaf").steps(&state),
+            vec![
+                "not public (private)",
+                "only called by dead code (crate::helper): crate::leaf <- crate::helper <- crate::orphan (no callers)",
+            ]
+        );
+
+        let DeadReason::CfgOnly { callers, cfgs } = explain("crate::shim").reason else {
+            panic!("expected cfg'd-out callers");
+        };
+        assert_eq!(names(&callers), vec!["crate::legacy"]);
+        assert_eq!(cfgs, vec!["feature = \"legacy\""]);
+
+        // Out of budget: only the symbols checked so far are reported
+        let budget = Budget::new(None, Some(2));
+        let partial = DeadCodeAnalyzer::new().analyze_within(&state, &budget);
+        assert!(budget.was_cut_short());
+        assert!(partial.dead_symbols.len() < report.dead_symbols.len());
+    }
+
+    #[tokio::test]
+    async fn test_exclude_tests_reports_test_only_code() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("main.rs"),
+            "fn main() { used(); }\n\nfn used() {}\n\nfn only_tested() {}\n\n#[cfg(test)]\nmod tests {\n    fn setup() {}\n\n    #[test]\n    fn it_works() { setup(); crate::only_tested(); }\n}\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        let setup = state.intern("crate::tests::setup");
+        assert!(state.symbols.get(&setup).unwrap().is_test_context);
+        assert!(
+            !state
+                .symbols
+                .get(&state.intern("crate::used"))
+                .unwrap()
+                .is_test_context
+        );
+        let test_calls = state.find_callees(state.intern("crate::tests::it_works"));
+        assert_eq!(test_calls.len(), 2);
+        assert!(test_calls.iter().all(|edge| edge.is_test_context));
+
+        let report = DeadCodeAnalyzer::new().analyze(&state);
+        assert!(
+            !report
+                .dead_symbols
+                .contains(&state.intern("crate::only_tested"))
+        );
+
+        let report = DeadCodeAnalyzer::new().exclude_tests(true).analyze(&state);
+        assert_eq!(
+            report.dead_symbols,
+            vec![state.intern("crate::only_tested")]
+        );
+        let explanation = &report.explanations[&state.intern("crate::only_tested")];
+        assert_eq!(
+            explanation.reason,
+            DeadReason::TestOnly {
+                callers: vec![state.intern("crate::tests::it_works")]
+            }
+        );
+        assert_eq!(
+            explanation.steps(&state),
+            vec![
+                "not public (private)",
+                "only called from tests (crate::tests::it_works)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dyn_dispatch_edges_reach_impls() {
+        use crate::types::{CallEdge, FileId, TraitImpl};
+
+        let state = create_state(PathBuf::from("/test"));
+        let main_scoped = state.intern("crate::main");
+        let area_scoped = state.intern("crate::Circle::area");
+
+        for (name, scoped, kind) in [
+            ("main", main_scoped, SymbolKind::Function),
+            ("area", area_scoped, SymbolKind::Method),
+        ] {
+            state.add_symbol(SymbolDef {
+                name: state.intern(name),
+                scoped_name: scoped,
+                kind,
+                location: Location::new(PathBuf::from("/test/main.rs"), 0, 10),
+                extended_location: None,
+                signature: Some(Signature::default()),
+                visibility: Visibility::Private,
+                attributes: vec![],
+                doc_comment: None,
+                parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
+            });
+        }
+
+        state.trait_impls.insert(
+            FileId(0),
+            vec![TraitImpl {
+                trait_name: "Shape".to_string(),
+                type_name: "Circle".to_string(),
+                methods: vec![area_scoped],
+                location: Location::new(PathBuf::from("/test/main.rs"), 0, 10),
+            }],
+        );
+        state.add_call_edge(CallEdge {
+            caller: main_scoped,
+            callee_name: "area".to_string(),
+            location: Location::new(PathBuf::from("/test/main.rs"), 5, 6),
+            is_method_call: true,
+            via_trait: Some("Shape".to_string()),
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
+        });
+
+        assert_eq!(state.link_dyn_dispatch(), 1);
+        // Relinking replaces rather than duplicates approximate edges
+        assert_eq!(state.link_dyn_dispatch(), 1);
+
+        let approx: Vec<_> = state
+            .find_callees(main_scoped)
+            .into_iter()
+            .filter(|e| e.is_approximate)
+            .collect();
+        assert_eq!(approx.len(), 1);
+        assert_eq!(approx[0].callee_name, "crate::Circle::area");
+        assert_eq!(state.call_targets(&approx[0])[0].scoped_name, area_scoped);
+
+        let report = DeadCodeAnalyzer::new().analyze(&state);
+        assert!(!report.dead_symbols.contains(&area_scoped));
+    }
 }
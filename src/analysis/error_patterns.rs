@@ -0,0 +1,520 @@
+//! Repeated error mapping that a helper or `From` impl would replace.
+//!
+//! Collects the error adapters of indexed Rust code (`.map_err(|e| ...)`,
+//! `.ok_or_else(|| ...)`, `.ok_or(...)`) and reduces each to a shape: the
+//! closure parameter is renamed to `e` and other local variables become `_`,
+//! while paths, functions, methods, macros and literals are kept. So
+//! `.map_err(|err| OmniError::io(&path, err))` and
+//! `.map_err(|e| OmniError::io(&dir, e))` share the shape
+//! `.map_err(|e| OmniError::io(&_, e))`.
+//!
+//! Shapes seen at [`DEFAULT_MIN_SITES`] or more call sites are reported with a
+//! suggestion: a `From` impl when the closure only wraps the error in a type
+//! (`|e| Error::Io(e)`), so `?` can do the mapping, and a helper function
+//! otherwise. Test functions, `#[cfg(test)]` modules and test files are
+//! skipped.
+
+use super::panics::{file_functions, is_test_item, relative, text};
+use crate::state::OciState;
+use crate::types::ModuleRole;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+/// Call sites a shape needs before it is reported.
+pub const DEFAULT_MIN_SITES: usize = 3;
+
+/// Sites listed per pattern; the rest are only counted.
+const MAX_SITES: usize = 10;
+
+/// Methods whose argument maps or builds an error.
+const ADAPTERS: &[&str] = &["map_err", "ok_or_else", "ok_or"];
+
+/// How to factor out a repeated pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPatternFix {
+    /// The closure only wraps the error: implement `From` and use `?`
+    FromImpl,
+    /// Extract a function taking the varying parts
+    Helper,
+}
+
+impl ErrorPatternFix {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::FromImpl => "From impl",
+            Self::Helper => "helper",
+        }
+    }
+}
+
+/// One occurrence of a pattern.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ErrorPatternSite {
+    /// Scoped name of the containing function
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+/// An error mapping repeated across call sites.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ErrorPattern {
+    /// Normalized adapter call, e.g. `.map_err(|e| OmniError::io(&_, e))`
+    pub shape: String,
+    /// Total call sites
+    pub count: usize,
+    /// Call sites in file order, at most ten
+    pub sites: Vec<ErrorPatternSite>,
+    pub fix: ErrorPatternFix,
+    pub suggestion: String,
+}
+
+/// Repeated error patterns, most sites first.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErrorPatternReport {
+    pub patterns: Vec<ErrorPattern>,
+}
+
+/// Finds error mapping repeated across the indexed Rust code.
+pub struct ErrorPatternAnalyzer {
+    min_sites: usize,
+}
+
+impl ErrorPatternAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            min_sites: DEFAULT_MIN_SITES,
+        }
+    }
+
+    /// Report shapes seen at `min_sites` or more call sites.
+    pub fn with_min_sites(mut self, min_sites: usize) -> Self {
+        self.min_sites = min_sites.max(1);
+        self
+    }
+
+    pub fn analyze(&self, state: &OciState, root: &Path) -> ErrorPatternReport {
+        let mut by_shape: HashMap<String, (Option<Wrapper>, Vec<ErrorPatternSite>)> =
+            HashMap::new();
+        for (shape, wrapper, site) in collect_sites(state, root) {
+            let entry = by_shape.entry(shape).or_insert((wrapper, Vec::new()));
+            entry.1.push(site);
+        }
+
+        let mut patterns: Vec<ErrorPattern> = by_shape
+            .into_iter()
+            .filter(|(_, (_, sites))| sites.len() >= self.min_sites)
+            .map(|(shape, (wrapper, mut sites))| {
+                let count = sites.len();
+                sites.truncate(MAX_SITES);
+                let (fix, suggestion) = suggest(&shape, wrapper.as_ref(), count);
+                ErrorPattern {
+                    shape,
+                    count,
+                    sites,
+                    fix,
+                    suggestion,
+                }
+            })
+            .collect();
+        patterns.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.shape.cmp(&b.shape)));
+        ErrorPatternReport { patterns }
+    }
+}
+
+impl Default for ErrorPatternAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shapes of the error adapters in a Rust snippet (e.g. code about to be
+/// written), in source order.
+pub fn snippet_shapes(code: &str) -> Vec<String> {
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(code, None) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    walk(tree.root_node(), code.as_bytes(), &mut found);
+    found.into_iter().map(|f| f.shape).collect()
+}
+
+/// The type an adapter closure wraps its error in, for `|e| Error::Io(e)`.
+#[derive(Debug, Clone)]
+struct Wrapper {
+    /// Constructor as written (`Error::Io`)
+    constructor: String,
+    /// Type to implement `From` for (`Error`)
+    target: String,
+}
+
+struct Found {
+    shape: String,
+    wrapper: Option<Wrapper>,
+    start_byte: usize,
+    end_byte: usize,
+    line: usize,
+}
+
+fn collect_sites(
+    state: &OciState,
+    root: &Path,
+) -> Vec<(String, Option<Wrapper>, ErrorPatternSite)> {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|f| state.file_role(f) != ModuleRole::Tests)
+        .collect();
+    files.sort();
+
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    let mut sites = Vec::new();
+    for file in &files {
+        let contents = match state.overlays.get(file) {
+            Some(overlay) => overlay.contents.to_string(),
+            None => match std::fs::read_to_string(file) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            },
+        };
+        let Some(tree) = parser.parse(&contents, None) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        walk(tree.root_node(), contents.as_bytes(), &mut found);
+        if found.is_empty() {
+            continue;
+        }
+
+        let functions = file_functions(state, file);
+        let rel = relative(root, file);
+        for f in found {
+            let Some(function) = functions
+                .iter()
+                .filter(|s| {
+                    s.location.start_byte <= f.start_byte && f.start_byte < s.location.end_byte
+                })
+                .min_by_key(|s| s.location.end_byte - s.location.start_byte)
+            else {
+                continue;
+            };
+            sites.push((
+                f.shape,
+                f.wrapper,
+                ErrorPatternSite {
+                    symbol: state.resolve(function.scoped_name).to_string(),
+                    file: rel.clone(),
+                    line: f.line,
+                    start_byte: f.start_byte,
+                    end_byte: f.end_byte,
+                },
+            ));
+        }
+    }
+    sites
+}
+
+/// Collect the error adapters under `node`, skipping test functions and
+/// modules.
+fn walk(node: Node, bytes: &[u8], found: &mut Vec<Found>) {
+    if matches!(node.kind(), "function_item" | "mod_item") && is_test_item(node, bytes) {
+        return;
+    }
+    if node.kind() == "call_expression"
+        && let Some(f) = adapter_call(node, bytes)
+    {
+        found.push(f);
+    }
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, found);
+        }
+    }
+}
+
+fn adapter_call(node: Node, bytes: &[u8]) -> Option<Found> {
+    let method = node
+        .child_by_field_name("function")
+        .filter(|f| f.kind() == "field_expression")
+        .and_then(|f| f.child_by_field_name("field"))
+        .map(|field| text(field, bytes))
+        .filter(|name| ADAPTERS.contains(name))?;
+    let args = node.child_by_field_name("arguments")?;
+    if args.named_child_count() != 1 {
+        return None;
+    }
+    let arg = args.named_child(0)?;
+
+    let (shape, wrapper) = if arg.kind() == "closure_expression" {
+        let params = arg.child_by_field_name("parameters")?;
+        let param = closure_param(params, bytes);
+        let body = arg.child_by_field_name("body")?;
+        let mut tokens = Vec::new();
+        normalize(body, bytes, param, &mut tokens);
+        let head = match param {
+            Some(_) => "|e|",
+            None if params.named_child_count() == 0 && text(params, bytes) == "||" => "||",
+            None => "|_|",
+        };
+        let shape = format!(".{}({} {})", method, head, join(&tokens));
+        (shape, param.and_then(|p| wrapper(body, bytes, p)))
+    } else if method == "ok_or" {
+        let mut tokens = Vec::new();
+        normalize(arg, bytes, None, &mut tokens);
+        (format!(".ok_or({})", join(&tokens)), None)
+    } else {
+        // `.map_err(Error::Io)` already names its mapping
+        return None;
+    };
+
+    Some(Found {
+        shape,
+        wrapper,
+        start_byte: node.start_byte(),
+        end_byte: node.end_byte(),
+        line: node.start_position().row,
+    })
+}
+
+/// Name bound by a single-identifier closure parameter (`|e|`, `|e: Error|`).
+fn closure_param<'a>(params: Node, bytes: &'a [u8]) -> Option<&'a str> {
+    let param = params.named_child(0)?;
+    let pattern = match param.kind() {
+        "identifier" => param,
+        "parameter" => param.child_by_field_name("pattern")?,
+        _ => return None,
+    };
+    (pattern.kind() == "identifier").then(|| text(pattern, bytes))
+}
+
+/// Append the normalized tokens of `node`: `param` becomes `e`, other local
+/// variables `_`.
+fn normalize(node: Node, bytes: &[u8], param: Option<&str>, tokens: &mut Vec<String>) {
+    if node.kind() == "string_literal" || node.kind() == "raw_string_literal" {
+        tokens.push(text(node, bytes).to_string());
+        return;
+    }
+    if node.child_count() == 0 {
+        let token = text(node, bytes);
+        let is_local = node.kind() == "identifier" && !is_named_item(node, bytes);
+        tokens.push(if is_local && Some(token) == param {
+            "e".to_string()
+        } else if is_local {
+            "_".to_string()
+        } else {
+            token.to_string()
+        });
+        return;
+    }
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            normalize(child, bytes, param, tokens);
+        }
+    }
+}
+
+/// Whether an identifier names an item (path segment, called function or
+/// macro, method) rather than a local variable.
+fn is_named_item(node: Node, bytes: &[u8]) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        "scoped_identifier" | "scoped_type_identifier" => true,
+        "call_expression" => parent
+            .child_by_field_name("function")
+            .is_some_and(|f| f.id() == node.id()),
+        "macro_invocation" => parent
+            .child_by_field_name("macro")
+            .is_some_and(|m| m.id() == node.id()),
+        // Macro arguments are unparsed tokens: judge by the neighbours
+        "token_tree" => {
+            let prev = node.prev_sibling().map(|n| text(n, bytes));
+            let next = node.next_sibling().map(|n| text(n, bytes));
+            matches!(prev, Some("::" | ".")) || matches!(next, Some("::" | "(" | "!"))
+        }
+        _ => false,
+    }
+}
+
+/// `|e| Path(e)`: a constructor applied to the error alone.
+fn wrapper(body: Node, bytes: &[u8], param: &str) -> Option<Wrapper> {
+    if body.kind() != "call_expression" {
+        return None;
+    }
+    let function = body.child_by_field_name("function")?;
+    let args = body.child_by_field_name("arguments")?;
+    let only_param = args.named_child_count() == 1
+        && args
+            .named_child(0)
+            .is_some_and(|a| a.kind() == "identifier" && text(a, bytes) == param);
+    if !only_param || !matches!(function.kind(), "identifier" | "scoped_identifier") {
+        return None;
+    }
+    let constructor = text(function, bytes).to_string();
+    let segments: Vec<&str> = constructor.split("::").collect();
+    let is_type = |s: &&str| s.starts_with(|c: char| c.is_ascii_uppercase());
+    // `Error::Io` and `Error::from_io` build an `Error`; `Wrapped` builds itself
+    let target = match segments.as_slice() {
+        [.., ty, _] if is_type(ty) => segments[..segments.len() - 1].join("::"),
+        [.., last] if is_type(last) => constructor.clone(),
+        _ => return None,
+    };
+    Some(Wrapper {
+        constructor,
+        target,
+    })
+}
+
+fn suggest(shape: &str, wrapper: Option<&Wrapper>, count: usize) -> (ErrorPatternFix, String) {
+    match wrapper {
+        Some(w) if shape.starts_with(".map_err(") => (
+            ErrorPatternFix::FromImpl,
+            format!(
+                "Implement `From<_>` for `{}` (building `{}`) and replace these {} `map_err` calls with `?`",
+                w.target, w.constructor, count
+            ),
+        ),
+        _ => (
+            ErrorPatternFix::Helper,
+            format!(
+                "Extract a helper function taking the `_` parts as arguments and call it from these {} sites",
+                count
+            ),
+        ),
+    }
+}
+
+/// Join tokens, spacing only where two words would otherwise merge and
+/// after commas.
+fn join(tokens: &[String]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        let word = |c: char| c.is_alphanumeric() || c == '_';
+        if out.ends_with(word) && token.starts_with(word) {
+            out.push(' ');
+        }
+        out.push_str(token);
+        if token == "," {
+            out.push(' ');
+        }
+    }
+    out.replace(" )", ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[test]
+    fn test_shapes_ignore_local_names() {
+        let code = r#"
+fn a(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|err| OmniError::io(&path, err))
+}
+fn b(dir: &Path) -> Result<String> {
+    fs::read_to_string(dir).map_err(|e| OmniError::io(&dir, e))?;
+    let x = v.first().ok_or_else(|| anyhow!("empty {}", name))?;
+    s.parse::<u32>().map_err(Error::Parse)
+}
+"#;
+        assert_eq!(
+            snippet_shapes(code),
+            vec![
+                ".map_err(|e| OmniError::io(&_, e))",
+                ".map_err(|e| OmniError::io(&_, e))",
+                r#".ok_or_else(|| anyhow!("empty {}", _))"#,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reports_repeated_patterns_with_fixes() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            r#"
+pub fn load(path: &str) -> Result<String, AppError> {
+    std::fs::read_to_string(path).map_err(|e| AppError::Io(e))
+}
+
+pub fn save(path: &str, data: &str) -> Result<(), AppError> {
+    std::fs::write(path, data).map_err(|err| AppError::Io(err))
+}
+
+pub fn remove(path: &str) -> Result<(), AppError> {
+    std::fs::remove_file(path).map_err(|e| AppError::Io(e))
+}
+
+pub fn port(raw: &str) -> Result<u16, AppError> {
+    raw.parse().map_err(|e| AppError::Invalid(format!("port {}: {}", raw, e)))
+}
+
+pub fn host(raw: &str) -> Result<u16, AppError> {
+    raw.parse().map_err(|e| AppError::Invalid(format!("port {}: {}", raw, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    fn fixture() {
+        std::fs::read_to_string("x").map_err(|e| AppError::Io(e)).ok();
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let report = ErrorPatternAnalyzer::new().analyze(&state, root);
+        assert_eq!(report.patterns.len(), 1);
+        let io = &report.patterns[0];
+        assert_eq!(io.shape, ".map_err(|e| AppError::Io(e))");
+        assert_eq!(io.count, 3);
+        assert_eq!(io.fix, ErrorPatternFix::FromImpl);
+        assert!(io.suggestion.contains("`From<_>` for `AppError`"));
+        let symbols: Vec<&str> = io.sites.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["crate::load", "crate::save", "crate::remove"]);
+
+        let report = ErrorPatternAnalyzer::new()
+            .with_min_sites(2)
+            .analyze(&state, root);
+        assert_eq!(report.patterns.len(), 2);
+        let invalid = &report.patterns[1];
+        assert_eq!(
+            invalid.shape,
+            r#".map_err(|e| AppError::Invalid(format!("port {}: {}", _, e)))"#
+        );
+        assert_eq!(invalid.fix, ErrorPatternFix::Helper);
+    }
+}
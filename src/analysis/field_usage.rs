@@ -0,0 +1,613 @@
+//! Field access patterns for a struct.
+//!
+//! For one Rust struct, finds where each field is read and written (field
+//! expressions like `x.count` and `x.count += 1`, and struct patterns), which
+//! fields are never touched outside the functions that construct the struct,
+//! and which fields each impl method uses. Useful for planning how to split
+//! a struct that has grown too many responsibilities.
+//!
+//! Without type information an access `x.name` is attributed to the struct
+//! when `x` is `self` inside one of its methods, or when the file mentions
+//! the struct by name; same-named fields of other types in such files are
+//! counted too. Method calls on a field (`self.items.push(x)`) count as
+//! reads, and only `=`, compound assignment and `&mut` count as writes.
+
+use super::panics::{file_functions, relative, text};
+use crate::state::OciState;
+use crate::types::{SymbolDef, SymbolKind};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+/// Maximum number of accesses listed per field.
+const MAX_ACCESSES: usize = 10;
+
+/// One read or write of a field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct FieldAccess {
+    /// Scoped name of the containing function
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    pub write: bool,
+}
+
+/// How one field is used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct FieldUsage {
+    /// Field name, or its index for tuple structs
+    pub name: String,
+    /// Line of the field declaration
+    pub line: usize,
+    pub reads: usize,
+    pub writes: usize,
+    /// Not accessed anywhere except in constructors
+    pub constructor_only: bool,
+    /// Accesses in file order, at most ten
+    pub accesses: Vec<FieldAccess>,
+}
+
+/// The fields one impl method reads and writes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct MethodFields {
+    pub method: String,
+    pub line: usize,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+}
+
+/// Field usage of a struct.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FieldUsageReport {
+    /// Scoped name of the struct
+    pub type_name: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// Fields in declaration order
+    pub fields: Vec<FieldUsage>,
+    /// Functions that build the struct with a struct expression
+    pub constructors: Vec<String>,
+    /// Impl methods in file order
+    pub methods: Vec<MethodFields>,
+}
+
+/// Reports how a struct's fields are accessed.
+pub struct FieldUsageAnalyzer;
+
+impl FieldUsageAnalyzer {
+    /// Creates a new field usage analyzer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Field usage of the struct named `name`, either scoped
+    /// (`crate::state::OciState`) or simple (`OciState`) if unambiguous.
+    pub fn analyze(&self, state: &OciState, root: &Path, name: &str) -> Result<FieldUsageReport> {
+        let def = find_struct(state, name)?;
+        let type_name = state.resolve(def.name).to_string();
+        let scoped = state.resolve(def.scoped_name).to_string();
+
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_rust::LANGUAGE.into())?;
+        let StructDecl {
+            file: struct_file,
+            line: struct_line,
+            fields: declared,
+        } = locate_struct(state, &mut parser, &def, &type_name)
+            .ok_or_else(|| anyhow::anyhow!("No struct definition found for {}", scoped))?;
+        let names: HashSet<&str> = declared.iter().map(|(name, _)| name.as_str()).collect();
+
+        let method_prefix = format!("{}::", scoped);
+        let mut found: Vec<(SymbolDef, Access)> = Vec::new();
+        let mut constructors: BTreeSet<String> = BTreeSet::new();
+        for file in rust_files(state) {
+            let Ok(contents) = read(state, &file) else {
+                continue;
+            };
+            if file != struct_file && !mentions(&contents, &type_name) {
+                continue;
+            }
+            let Some(tree) = parser.parse(&contents, None) else {
+                continue;
+            };
+            let mut accesses = Vec::new();
+            let mut builds = Vec::new();
+            let target = Target {
+                type_name: &type_name,
+                fields: &names,
+            };
+            walk(
+                tree.root_node(),
+                contents.as_bytes(),
+                &target,
+                &mut accesses,
+                &mut builds,
+            );
+
+            let functions = file_functions(state, &file);
+            let innermost = |byte: usize| {
+                functions
+                    .iter()
+                    .filter(|f| f.location.start_byte <= byte && byte < f.location.end_byte)
+                    .min_by_key(|f| f.location.end_byte - f.location.start_byte)
+            };
+            for (byte, via_self) in builds {
+                let Some(function) = innermost(byte) else {
+                    continue;
+                };
+                let function_name = state.resolve(function.scoped_name);
+                // `Self { .. }` only builds this struct inside its own impls
+                if !via_self || function_name.starts_with(&method_prefix) {
+                    constructors.insert(function_name.to_string());
+                }
+            }
+            for access in accesses {
+                let Some(function) = innermost(access.byte) else {
+                    continue;
+                };
+                if access.via_self
+                    && !state
+                        .resolve(function.scoped_name)
+                        .starts_with(&method_prefix)
+                {
+                    continue;
+                }
+                found.push((
+                    function.clone(),
+                    Access {
+                        file: file.clone(),
+                        ..access
+                    },
+                ));
+            }
+        }
+        found.sort_by(|a, b| (&a.1.file, a.1.byte).cmp(&(&b.1.file, b.1.byte)));
+
+        let fields = declared
+            .iter()
+            .map(|(name, line)| {
+                let mut usage = FieldUsage {
+                    name: name.clone(),
+                    line: *line,
+                    reads: 0,
+                    writes: 0,
+                    constructor_only: true,
+                    accesses: Vec::new(),
+                };
+                for (function, access) in found.iter().filter(|(_, a)| &a.field == name) {
+                    let symbol = state.resolve(function.scoped_name).to_string();
+                    if access.write {
+                        usage.writes += 1;
+                    } else {
+                        usage.reads += 1;
+                    }
+                    if !constructors.contains(&symbol) {
+                        usage.constructor_only = false;
+                    }
+                    if usage.accesses.len() < MAX_ACCESSES {
+                        usage.accesses.push(FieldAccess {
+                            symbol,
+                            file: relative(root, &access.file),
+                            line: access.line,
+                            write: access.write,
+                        });
+                    }
+                }
+                usage
+            })
+            .collect();
+
+        let mut methods: Vec<SymbolDef> = state
+            .symbols
+            .iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|s| {
+                state
+                    .resolve(s.scoped_name)
+                    .strip_prefix(&method_prefix)
+                    .is_some_and(|rest| !rest.contains("::"))
+            })
+            .map(|s| s.value().clone())
+            .collect();
+        methods.sort_by(|a, b| {
+            (&a.location.file, a.location.start_line)
+                .cmp(&(&b.location.file, b.location.start_line))
+        });
+        let mut touched: HashMap<_, (BTreeSet<String>, BTreeSet<String>)> = HashMap::new();
+        for (function, access) in &found {
+            let (reads, writes) = touched.entry(function.scoped_name).or_default();
+            if access.write {
+                writes.insert(access.field.clone());
+            } else {
+                reads.insert(access.field.clone());
+            }
+        }
+        let methods = methods
+            .into_iter()
+            .map(|m| {
+                let (reads, writes) = touched.remove(&m.scoped_name).unwrap_or_default();
+                MethodFields {
+                    method: state.resolve(m.scoped_name).to_string(),
+                    line: m.location.start_line,
+                    reads: reads.into_iter().collect(),
+                    writes: writes.into_iter().collect(),
+                }
+            })
+            .collect();
+
+        Ok(FieldUsageReport {
+            type_name: scoped,
+            file: relative(root, &struct_file),
+            line: struct_line,
+            fields,
+            constructors: constructors.into_iter().collect(),
+            methods,
+        })
+    }
+}
+
+impl Default for FieldUsageAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A field access found in a file.
+struct Access {
+    field: String,
+    file: PathBuf,
+    byte: usize,
+    line: usize,
+    write: bool,
+    /// Through `self`, so only counted in the struct's own methods
+    via_self: bool,
+}
+
+/// Where a struct is declared, with `(name, line)` of each field.
+struct StructDecl {
+    file: PathBuf,
+    line: usize,
+    fields: Vec<(String, usize)>,
+}
+
+/// The struct being analyzed, as seen by [`walk`].
+struct Target<'a> {
+    type_name: &'a str,
+    fields: &'a HashSet<&'a str>,
+}
+
+/// Whether a symbol can stand for a Rust struct. An `impl Foo` block is
+/// indexed under the same scoped name as `struct Foo` and may have replaced it.
+fn is_struct_like(symbol: &SymbolDef) -> bool {
+    matches!(symbol.kind, SymbolKind::Struct | SymbolKind::Impl)
+        && symbol
+            .location
+            .file
+            .extension()
+            .is_some_and(|ext| ext == "rs")
+}
+
+/// The indexed struct matching `name` by scoped or simple name.
+fn find_struct(state: &OciState, name: &str) -> Result<SymbolDef> {
+    if let Some(def) = state
+        .lookup(name)
+        .and_then(|scoped| state.get_symbol(scoped))
+        .filter(is_struct_like)
+    {
+        return Ok(def);
+    }
+    let simple = name.rsplit("::").next().unwrap_or(name);
+    let mut candidates: Vec<SymbolDef> = state
+        .find_by_name(simple)
+        .into_iter()
+        .filter(is_struct_like)
+        .collect();
+    candidates.sort_by_key(|s| state.resolve(s.scoped_name).to_string());
+    candidates.dedup_by_key(|s| s.scoped_name);
+    match candidates.len() {
+        0 => anyhow::bail!("Unknown struct: {}", name),
+        1 => Ok(candidates.remove(0)),
+        _ => {
+            let scoped: Vec<&str> = candidates
+                .iter()
+                .map(|s| state.resolve(s.scoped_name))
+                .collect();
+            anyhow::bail!("Ambiguous struct {}: {}", name, scoped.join(", "))
+        }
+    }
+}
+
+fn read(state: &OciState, file: &Path) -> Result<String> {
+    Ok(match state.overlays.get(file) {
+        Some(overlay) => overlay.contents.to_string(),
+        None => std::fs::read_to_string(file)?,
+    })
+}
+
+/// Indexed Rust files, sorted.
+fn rust_files(state: &OciState) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Whether `name` appears in `contents` as a whole identifier.
+fn mentions(contents: &str, name: &str) -> bool {
+    contents
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == name)
+}
+
+/// Declaration of the struct `def` stands for: the `struct_item` at `def`
+/// itself, or for an impl block, the first struct of that name in its file
+/// or else in any indexed Rust file.
+fn locate_struct(
+    state: &OciState,
+    parser: &mut Parser,
+    def: &SymbolDef,
+    name: &str,
+) -> Option<StructDecl> {
+    let at = (def.kind == SymbolKind::Struct).then_some(def.location.start_byte);
+    let mut files = vec![def.location.file.clone()];
+    if at.is_none() {
+        files.extend(
+            rust_files(state)
+                .into_iter()
+                .filter(|f| *f != def.location.file),
+        );
+    }
+    files.into_iter().find_map(|file| {
+        let contents = read(state, &file).ok()?;
+        if !mentions(&contents, name) {
+            return None;
+        }
+        let tree = parser.parse(&contents, None)?;
+        let node = find_struct_node(tree.root_node(), contents.as_bytes(), name, at)?;
+        Some(StructDecl {
+            line: node.start_position().row,
+            fields: declared_fields(node, contents.as_bytes()),
+            file,
+        })
+    })
+}
+
+/// The `struct_item` named `name`, containing byte `at` if given.
+fn find_struct_node<'a>(
+    node: Node<'a>,
+    bytes: &[u8],
+    name: &str,
+    at: Option<usize>,
+) -> Option<Node<'a>> {
+    let contains = |n: Node| at.is_none_or(|at| n.start_byte() <= at && at < n.end_byte());
+    if node.kind() == "struct_item"
+        && contains(node)
+        && node
+            .child_by_field_name("name")
+            .is_some_and(|n| text(n, bytes) == name)
+    {
+        return Some(node);
+    }
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .filter(|child| contains(*child))
+        .find_map(|child| find_struct_node(child, bytes, name, at))
+}
+
+/// `(name, line)` of each declared field; tuple fields are named by index.
+fn declared_fields(node: Node, bytes: &[u8]) -> Vec<(String, usize)> {
+    let Some(body) = node.child_by_field_name("body") else {
+        return Vec::new();
+    };
+    let mut cursor = body.walk();
+    match body.kind() {
+        "field_declaration_list" => body
+            .named_children(&mut cursor)
+            .filter(|f| f.kind() == "field_declaration")
+            .filter_map(|f| f.child_by_field_name("name"))
+            .map(|n| (text(n, bytes).to_string(), n.start_position().row))
+            .collect(),
+        "ordered_field_declaration_list" => body
+            .children_by_field_name("type", &mut cursor)
+            .enumerate()
+            .map(|(i, ty)| (i.to_string(), ty.start_position().row))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Collect field accesses of the target's fields and the start byte of each
+/// struct expression building it (with whether it is spelled `Self`).
+fn walk(
+    node: Node,
+    bytes: &[u8],
+    target: &Target,
+    accesses: &mut Vec<Access>,
+    builds: &mut Vec<(usize, bool)>,
+) {
+    match node.kind() {
+        "field_expression" => {
+            if let (Some(value), Some(field)) = (
+                node.child_by_field_name("value"),
+                node.child_by_field_name("field"),
+            ) {
+                let field = text(field, bytes);
+                if target.fields.contains(field) {
+                    accesses.push(Access {
+                        field: field.to_string(),
+                        file: PathBuf::new(),
+                        byte: node.start_byte(),
+                        line: node.start_position().row,
+                        write: is_write(node),
+                        via_self: value.kind() == "self",
+                    });
+                }
+            }
+        }
+        "struct_expression" | "struct_pattern" => {
+            let named = node
+                .child_by_field_name(if node.kind() == "struct_expression" {
+                    "name"
+                } else {
+                    "type"
+                })
+                .map(|n| type_ident(text(n, bytes)));
+            let via_self = named == Some("Self");
+            if named == Some(target.type_name) || via_self {
+                if node.kind() == "struct_expression" {
+                    builds.push((node.start_byte(), via_self));
+                } else {
+                    let mut cursor = node.walk();
+                    for pattern in node.named_children(&mut cursor) {
+                        let name = match pattern.kind() {
+                            "field_pattern" => pattern.child_by_field_name("name"),
+                            "shorthand_field_identifier" => Some(pattern),
+                            _ => None,
+                        };
+                        let Some(name) = name.map(|n| text(n, bytes)) else {
+                            continue;
+                        };
+                        if target.fields.contains(name) {
+                            accesses.push(Access {
+                                field: name.to_string(),
+                                file: PathBuf::new(),
+                                byte: pattern.start_byte(),
+                                line: pattern.start_position().row,
+                                write: false,
+                                via_self,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, target, accesses, builds);
+        }
+    }
+}
+
+/// Last path segment of a type, without generics: `a::Foo::<T>` -> `Foo`.
+fn type_ident(ty: &str) -> &str {
+    let ty = ty.split('<').next().unwrap_or(ty).trim_end_matches("::");
+    ty.rsplit("::").next().unwrap_or(ty).trim()
+}
+
+/// Whether a place expression is assigned to or mutably borrowed, directly or
+/// through a field or index of it (`self.a.b = 1` writes `a`).
+fn is_write(node: Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let is = |field: &str| {
+        parent
+            .child_by_field_name(field)
+            .is_some_and(|n| n.id() == node.id())
+    };
+    match parent.kind() {
+        "assignment_expression" | "compound_assignment_expr" => is("left"),
+        "reference_expression" => (0..parent.child_count())
+            .filter_map(|i| parent.child(i))
+            .any(|c| c.kind() == "mutable_specifier"),
+        "field_expression" => is("value") && is_write(parent),
+        "index_expression" => {
+            parent.named_child(0).is_some_and(|n| n.id() == node.id()) && is_write(parent)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_reports_reads_writes_and_constructor_only_fields() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("lib.rs"),
+            r#"pub mod other;
+
+pub struct Counter {
+    count: u32,
+    label: String,
+    created: u64,
+}
+
+impl Counter {
+    pub fn new(label: String) -> Self {
+        Self { count: 0, label, created: 1 }
+    }
+
+    pub fn bump(&mut self) {
+        self.count += 1;
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+pub struct Other {
+    count: u32,
+}
+
+impl Other {
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("other.rs"),
+            "use crate::Counter;\n\npub fn peek(c: &Counter) -> u32 {\n    let Counter { label, .. } = c;\n    label.len() as u32 + c.count\n}\n",
+        )
+        .unwrap();
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let report = FieldUsageAnalyzer::new()
+            .analyze(&state, root, "Counter")
+            .unwrap();
+        assert_eq!(report.type_name, "crate::Counter");
+        assert_eq!(report.constructors, vec!["crate::Counter::new"]);
+
+        let field = |name: &str| report.fields.iter().find(|f| f.name == name).unwrap();
+        // Other::reset writes its own `count`
+        assert_eq!((field("count").reads, field("count").writes), (1, 1));
+        assert!(!field("count").constructor_only);
+        assert_eq!(field("label").reads, 2);
+        assert!(field("created").constructor_only);
+        assert!(field("created").accesses.is_empty());
+
+        let method = |name: &str| report.methods.iter().find(|m| m.method == name).unwrap();
+        assert_eq!(method("crate::Counter::bump").writes, vec!["count"]);
+        assert!(method("crate::Counter::bump").reads.is_empty());
+        assert_eq!(method("crate::Counter::label").reads, vec!["label"]);
+        assert_eq!(report.methods.len(), 3);
+
+        let err = FieldUsageAnalyzer::new()
+            .analyze(&state, root, "Missing")
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown struct"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Generic instantiation hotspots.
+//!
+//! Approximates monomorphization pressure by counting the distinct type
+//! arguments each generic function is called with. Only statically visible
+//! arguments are seen (turbofish calls such as `parse::<u32>()`); inferred
+//! instantiations are counted as call sites but not as distinct types.
+
+use crate::state::OciState;
+use crate::types::{GenericHotspot, InternedString, SymbolDef, SymbolKind};
+use std::collections::{BTreeSet, HashMap};
+
+/// Finds generic functions with many distinct instantiations.
+pub struct GenericsAnalyzer;
+
+impl GenericsAnalyzer {
+    /// Creates a new generics analyzer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Rank called generic functions by distinct instantiations, then call sites.
+    pub fn analyze(&self, state: &OciState) -> Vec<GenericHotspot> {
+        let mut usage: HashMap<InternedString, (usize, BTreeSet<String>)> = HashMap::new();
+
+        let edges = state.call_edges.read().clone();
+        for edge in &edges {
+            for target in state.call_targets(edge) {
+                if !is_generic(&target) {
+                    continue;
+                }
+                let (call_sites, types) = usage.entry(target.scoped_name).or_default();
+                *call_sites += 1;
+                if let Some(args) = &edge.type_args {
+                    types.insert(args.split_whitespace().collect());
+                }
+            }
+        }
+
+        let mut hotspots: Vec<GenericHotspot> = usage
+            .into_iter()
+            .map(|(symbol, (call_sites, types))| GenericHotspot {
+                symbol,
+                call_sites,
+                instantiations: types.into_iter().collect(),
+            })
+            .collect();
+        hotspots.sort_by(|a, b| {
+            b.instantiations
+                .len()
+                .cmp(&a.instantiations.len())
+                .then(b.call_sites.cmp(&a.call_sites))
+                .then_with(|| state.resolve(a.symbol).cmp(state.resolve(b.symbol)))
+        });
+        hotspots
+    }
+}
+
+impl Default for GenericsAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a function takes type parameters (lifetimes alone don't count).
+fn is_generic(symbol: &SymbolDef) -> bool {
+    if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) {
+        return false;
+    }
+    let Some(sig) = &symbol.signature else {
+        return false;
+    };
+    let has_type_params = sig.generics.as_deref().is_some_and(|g| {
+        g.trim_start_matches('<')
+            .trim_end_matches('>')
+            .split(',')
+            .map(str::trim)
+            .any(|p| !p.is_empty() && !p.starts_with('\''))
+    });
+    has_type_params || sig.params.iter().any(|p| p.contains("impl "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::LanguageParser;
+    use crate::parsing::rust::RustParser;
+    use crate::state::create_state;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ranks_by_distinct_turbofish_types() {
+        let source = r#"
+fn decode<T: std::str::FromStr>(s: &str) -> Option<T> { s.parse().ok() }
+fn wrap<T>(t: T) -> Vec<T> { vec![t] }
+fn borrow<'a>(s: &'a str) -> &'a str { s }
+
+fn main() {
+    let a = decode::<u32>("1");
+    let b = decode::<i64>("2");
+    let c = decode::< u32 >("3");
+    let d = wrap(1);
+    let e = wrap::<String>(String::new());
+    borrow("x");
+}
+"#;
+        let state = create_state(PathBuf::from("/test"));
+        let parser = RustParser::new();
+        let path = PathBuf::from("/test/src/main.rs");
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        for sym in parser
+            .extract_symbols(&tree, source, &path, &state.interner)
+            .unwrap()
+        {
+            state.add_symbol(sym);
+        }
+        for call in parser
+            .extract_calls(&tree, source, &path, &state.interner)
+            .unwrap()
+        {
+            state.add_call_edge(call);
+        }
+
+        let hotspots = GenericsAnalyzer::new().analyze(&state);
+        let names: Vec<&str> = hotspots.iter().map(|h| state.resolve(h.symbol)).collect();
+        assert_eq!(names, vec!["crate::decode", "crate::wrap"]);
+        assert_eq!(hotspots[0].call_sites, 3);
+        assert_eq!(hotspots[0].instantiations, vec!["<i64>", "<u32>"]);
+        assert_eq!(hotspots[1].call_sites, 2);
+        assert_eq!(hotspots[1].instantiations, vec!["<String>"]);
+    }
+}
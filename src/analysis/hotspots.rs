@@ -0,0 +1,409 @@
+//! Hotspots: code that changes often, is complex, is poorly tested and
+//! matters to the rest of the codebase.
+//!
+//! Each file and function gets a risk score
+//!
+//! ```text
+//! risk = commits * complexity * (2 - coverage) * (1 + centrality)
+//! ```
+//!
+//! from its commits over the churn window (see [`super::churn`]), its
+//! cyclomatic complexity (see [`super::complexity`]), the share of its lines
+//! a coverage report says ran (1 when no report is given or the report does
+//! not mention the code) and its file's PageRank relative to the most
+//! central file. Code that is busy, tangled, untested and widely depended on
+//! ranks first: that is where refactoring pays off.
+
+use super::churn::ChurnAnalyzer;
+use super::complexity::{ComplexityAnalyzer, FunctionComplexity};
+use super::coverage::{CoverageAnalyzer, CoverageData};
+use crate::budget::Budget;
+use crate::state::OciState;
+use crate::types::TopologyNode;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Default churn window in days.
+pub const DEFAULT_DAYS: u32 = 30;
+/// Default number of hotspots reported.
+pub const DEFAULT_LIMIT: usize = 20;
+/// Most complex functions listed per hotspot.
+const TOP_FUNCTIONS: usize = 3;
+
+/// A frequently changed, complex file.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct Hotspot {
+    /// Path relative to the root
+    pub file: String,
+    /// Commits touching the file in the window
+    pub commits: u32,
+    /// Lines added plus removed in the window
+    pub lines_changed: u32,
+    /// Sum of the cyclomatic complexity of its functions
+    pub complexity: u32,
+    /// `commits * complexity`
+    pub score: u64,
+    /// Share of its lines covered (0.0-1.0), when a coverage report has them
+    pub coverage: Option<f32>,
+    /// PageRank relative to the most central file (0.0-1.0)
+    pub centrality: f64,
+    /// Composite risk (see the module docs)
+    pub risk: f64,
+    /// Its most complex functions
+    pub functions: Vec<FunctionComplexity>,
+}
+
+/// A frequently changed, complex function or method.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct SymbolHotspot {
+    /// Scoped name
+    pub symbol: String,
+    /// Path relative to the root
+    pub file: String,
+    /// 0-based line of the definition
+    pub line: usize,
+    /// Commits that changed its lines in the window
+    pub commits: u32,
+    pub complexity: u32,
+    /// Share of its lines covered (0.0-1.0), when a coverage report has them
+    pub coverage: Option<f32>,
+    /// PageRank of its file relative to the most central file (0.0-1.0)
+    pub centrality: f64,
+    /// Composite risk (see the module docs)
+    pub risk: f64,
+}
+
+/// Files and functions ranked by risk.
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct HotspotReport {
+    /// Days of history considered
+    pub days: u32,
+    /// Whether a coverage report fed the scores
+    pub with_coverage: bool,
+    /// Highest risk first
+    pub files: Vec<Hotspot>,
+    /// Highest risk first
+    pub symbols: Vec<SymbolHotspot>,
+}
+
+/// Ranks indexed files and functions by churn, complexity, coverage and
+/// centrality.
+#[derive(Debug, Clone)]
+pub struct HotspotAnalyzer {
+    days: u32,
+    limit: usize,
+    coverage: Option<CoverageData>,
+}
+
+impl Default for HotspotAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HotspotAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            days: DEFAULT_DAYS,
+            limit: DEFAULT_LIMIT,
+            coverage: None,
+        }
+    }
+
+    /// Only count commits from the last `days` days.
+    pub fn with_days(mut self, days: u32) -> Self {
+        self.days = days;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Weigh in test coverage from a loaded report (see
+    /// [`CoverageAnalyzer::load`]).
+    pub fn with_coverage(mut self, coverage: CoverageData) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Hotspots among the indexed files under `root` and their functions,
+    /// highest risk first. The churn behind them is stored in
+    /// [`OciState::churn`].
+    ///
+    /// Fails if `root` is not in a git repository.
+    pub fn analyze(&self, state: &OciState, root: &Path) -> Result<HotspotReport> {
+        self.analyze_within(state, root, &Budget::unlimited())
+    }
+
+    /// [`analyze`](Self::analyze), charging `budget` one unit per commit
+    /// read (see [`ChurnAnalyzer::analyze_within`]) and per file measured,
+    /// and ranking only what was scored before it runs out.
+    pub fn analyze_within(
+        &self,
+        state: &OciState,
+        root: &Path,
+        budget: &Budget,
+    ) -> Result<HotspotReport> {
+        let churn = ChurnAnalyzer::analyze_index_within(state, root, self.days, budget)?;
+        let complexity = ComplexityAnalyzer::new();
+        let max_relevance = max_file_relevance(state);
+        let symbol_churn: HashMap<(String, &str), u32> = churn
+            .symbols
+            .iter()
+            .map(|s| ((relative(&s.file), s.symbol.as_str()), s.commits))
+            .collect();
+        let symbol_coverage: HashMap<String, f32> = self
+            .coverage
+            .as_ref()
+            .map(|coverage| {
+                CoverageAnalyzer::correlate_symbols(state, coverage)
+                    .into_iter()
+                    .filter(|c| c.lines_total > 0)
+                    .map(|c| {
+                        let pct = CoverageAnalyzer::coverage_percentage(&c);
+                        (state.resolve(c.symbol).to_string(), pct)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut files = Vec::new();
+        let mut symbols = Vec::new();
+        for churn in &churn.file_churn {
+            let file = root.join(&churn.path);
+            if !state.file_ids.contains_key(&file) {
+                continue;
+            }
+            if !budget.charge(1) {
+                break;
+            }
+            let Some(mut report) = complexity.analyze_file(state, root, &file) else {
+                continue;
+            };
+            let score = churn.commits as u64 * report.complexity as u64;
+            if score == 0 {
+                continue;
+            }
+            let centrality = if max_relevance > 0.0 {
+                state.relevance_of_file(&file) / max_relevance
+            } else {
+                0.0
+            };
+
+            for function in &report.functions {
+                let key = (report.file.clone(), function.symbol.as_str());
+                let Some(&commits) = symbol_churn.get(&key) else {
+                    continue;
+                };
+                let coverage = symbol_coverage.get(&function.symbol).copied();
+                symbols.push(SymbolHotspot {
+                    symbol: function.symbol.clone(),
+                    file: function.file.clone(),
+                    line: function.line,
+                    commits,
+                    complexity: function.complexity,
+                    coverage,
+                    centrality,
+                    risk: risk(commits, function.complexity, coverage, centrality),
+                });
+            }
+
+            let coverage = self
+                .coverage
+                .as_ref()
+                .and_then(|c| c.lines_for(&file, root))
+                .and_then(|lines| {
+                    let covered = lines.covered_lines.len();
+                    let total = covered + lines.uncovered_lines.len();
+                    (total > 0).then(|| covered as f32 / total as f32)
+                });
+            report.functions.truncate(TOP_FUNCTIONS);
+            files.push(Hotspot {
+                file: report.file,
+                commits: churn.commits,
+                lines_changed: churn.lines_added + churn.lines_removed,
+                complexity: report.complexity,
+                score,
+                coverage,
+                centrality,
+                risk: risk(churn.commits, report.complexity, coverage, centrality),
+                functions: report.functions,
+            });
+        }
+        files.sort_by(|a, b| b.risk.total_cmp(&a.risk).then_with(|| a.file.cmp(&b.file)));
+        files.truncate(self.limit);
+        symbols.sort_by(|a, b| {
+            b.risk
+                .total_cmp(&a.risk)
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        symbols.truncate(self.limit);
+        Ok(HotspotReport {
+            days: self.days,
+            with_coverage: self.coverage.is_some(),
+            files,
+            symbols,
+        })
+    }
+}
+
+/// `commits * complexity * (2 - coverage) * (1 + centrality)`
+fn risk(commits: u32, complexity: u32, coverage: Option<f32>, centrality: f64) -> f64 {
+    let untested = 2.0 - coverage.unwrap_or(1.0) as f64;
+    commits as f64 * complexity as f64 * untested * (1.0 + centrality)
+}
+
+/// Highest PageRank among indexed files.
+fn max_file_relevance(state: &OciState) -> f64 {
+    let graph = state.topology.read();
+    state
+        .topology_metrics
+        .iter()
+        .filter(|entry| {
+            matches!(
+                graph.node_weight(*entry.key()),
+                Some(TopologyNode::File { .. })
+            )
+        })
+        .map(|entry| entry.value().relevance_score)
+        .fold(0.0, f64::max)
+}
+
+fn relative(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn git(repo: &Path, args: &[&str]) {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(repo);
+        for (key, _) in std::env::vars_os() {
+            if key.to_str().is_some_and(|k| k.starts_with("GIT_")) {
+                cmd.env_remove(&key);
+            }
+        }
+        let output = cmd
+            .args([
+                "-c",
+                "user.name=Test User",
+                "-c",
+                "user.email=test@example.com",
+            ])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ranks_busy_complex_files_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        // The indexed root is below the repository top
+        let root = repo.join("app");
+        std::fs::create_dir_all(&root).unwrap();
+        git(repo, &["init", "--initial-branch=main"]);
+        for i in 0..3 {
+            std::fs::write(
+                root.join("busy.rs"),
+                format!(
+                    "pub fn route(x: u32) -> u32 {{\n    if x > {i} {{\n        return 1;\n    }}\n    while x < 3 {{}}\n    0\n}}\n"
+                ),
+            )
+            .unwrap();
+            std::fs::write(
+                root.join("plain.rs"),
+                format!("pub fn id() -> u32 {{\n    {i}\n}}\n"),
+            )
+            .unwrap();
+            git(repo, &["add", "."]);
+            git(
+                repo,
+                &["commit", "--no-gpg-sign", "-m", &format!("change {i}")],
+            );
+        }
+        std::fs::write(
+            root.join("quiet.rs"),
+            "pub fn maze(a: bool, b: bool) -> u32 {\n    if a && b { 1 } else if a { 2 } else { 3 }\n}\n",
+        )
+        .unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "--no-gpg-sign", "-m", "quiet"]);
+
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let report = HotspotAnalyzer::new().analyze(&state, &root).unwrap();
+        let hotspots = &report.files;
+        let files: Vec<_> = hotspots.iter().map(|h| h.file.as_str()).collect();
+        assert_eq!(files, ["busy.rs", "quiet.rs", "plain.rs"]);
+        assert_eq!(hotspots[0].commits, 3);
+        assert_eq!(hotspots[0].complexity, 3);
+        assert_eq!(hotspots[0].score, 9);
+        assert_eq!(hotspots[0].functions[0].symbol, "crate::route");
+        assert_eq!(hotspots[2].score, 3);
+        assert_eq!(hotspots[0].coverage, None);
+        assert!(hotspots[0].centrality > 0.0);
+        assert_eq!(report.symbols[0].symbol, "crate::route");
+        assert_eq!(report.symbols[0].commits, 3);
+        assert!(state.churn.read().is_some());
+
+        // Untested code weighs double; covered code keeps its score
+        let mut coverage = CoverageData::default();
+        coverage.line_coverage.insert(
+            root.join("busy.rs"),
+            crate::analysis::LineCoverage {
+                covered_lines: (1..=7).collect(),
+                uncovered_lines: vec![],
+            },
+        );
+        coverage.line_coverage.insert(
+            PathBuf::from("quiet.rs"),
+            crate::analysis::LineCoverage {
+                covered_lines: vec![],
+                uncovered_lines: vec![1, 2, 3],
+            },
+        );
+        let covered = HotspotAnalyzer::new()
+            .with_coverage(coverage)
+            .analyze(&state, &root)
+            .unwrap();
+        let find = |report: &HotspotReport, file: &str| {
+            report.files.iter().find(|h| h.file == file).unwrap().risk
+        };
+        assert_eq!(find(&covered, "busy.rs"), find(&report, "busy.rs"));
+        assert_eq!(find(&covered, "quiet.rs"), 2.0 * find(&report, "quiet.rs"));
+        let maze = covered
+            .symbols
+            .iter()
+            .find(|s| s.symbol == "crate::maze")
+            .unwrap();
+        assert_eq!(maze.coverage, Some(0.0));
+
+        let top = HotspotAnalyzer::new()
+            .with_limit(1)
+            .analyze(&state, &root)
+            .unwrap()
+            .files;
+        assert_eq!(top.len(), 1);
+    }
+}
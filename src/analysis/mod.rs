@@ -1,13 +1,64 @@
 //! Code analysis modules.
 //!
 //! - Dead code detection
+//! - Binary entry points and per-binary reachability
 //! - Test coverage integration
 //! - Churn analysis
+//! - Files and symbols that change together (co-change rules)
+//! - Cyclomatic complexity and churn x complexity hotspots
+//! - Struct field reads and writes, per field and per impl method
+//! - `omni check` findings and baselines
+//! - Generic instantiation hotspots
+//! - Panic sites reachable from the public API
+//! - Risky API calls (process spawning, raw SQL, file deletion, environment
+//!   mutation, secret reads) and the entry points that reach them
+//! - Seams for swapping implementations (single-impl traits, injected
+//!   dependencies, global singletons)
+//! - Duplicated function bodies (MinHash over token shingles)
+//! - Copy-pasted test setup
+//! - Repeated error mapping (`map_err` closures) to factor out
+//! - Words spelled more than one way across identifiers
+//! - Findings as editor problem lines and SARIF
+//! - Analyses registered by other crates ([`CustomAnalysis`])
 
+pub mod binaries;
+pub mod check;
 pub mod churn;
+pub mod clones;
+pub mod cochange;
+pub mod complexity;
 pub mod coverage;
+pub mod custom;
 pub mod dead_code;
+pub mod error_patterns;
+pub mod field_usage;
+pub mod generics;
+pub mod hotspots;
+pub mod panics;
+pub mod problems;
+pub mod risky_apis;
+pub mod seams;
+pub mod spelling;
+pub mod test_clones;
 
 // Re-exports
-pub use coverage::{BranchCoverage, CoverageAnalyzer, CoverageData, LineCoverage};
+pub use binaries::{BinaryAnalyzer, discover_binaries};
+pub use churn::{ChurnAnalyzer, ChurnReport, FileChurn, SymbolChurn};
+pub use clones::{CloneAnalyzer, CloneConfig, CloneGroup};
+pub use cochange::{CoChangeAnalyzer, CoChangeReport, CoChangedFile, CoChangedSymbol};
+pub use complexity::{ComplexityAnalyzer, FileComplexity};
+pub use coverage::{
+    BranchCoverage, CoverageAnalyzer, CoverageData, CoverageReport, FunctionCoverage, LineCoverage,
+};
+pub use custom::{CustomAnalysis, Findings};
 pub use dead_code::DeadCodeAnalyzer;
+pub use error_patterns::{ErrorPatternAnalyzer, ErrorPatternReport};
+pub use field_usage::{FieldUsageAnalyzer, FieldUsageReport};
+pub use generics::GenericsAnalyzer;
+pub use hotspots::{Hotspot, HotspotAnalyzer, HotspotReport, SymbolHotspot};
+pub use panics::{PanicAnalyzer, PanicReport};
+pub use problems::{Problem, Severity};
+pub use risky_apis::{RiskyApiAnalyzer, RiskyApiReport};
+pub use seams::{SeamAnalyzer, SeamReport};
+pub use spelling::{SpellingAnalyzer, SpellingConfig, SpellingReport};
+pub use test_clones::{TestCloneAnalyzer, TestCloneConfig, TestCloneGroup};
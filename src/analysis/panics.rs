@@ -0,0 +1,428 @@
+//! Panic sites reachable from the public API.
+//!
+//! Finds `unwrap()`, `expect()`, `panic!`, `todo!` and `unimplemented!` in
+//! indexed Rust code, attributes each to its containing function, and walks
+//! the call graph forward from every `pub` function to count the sites it can
+//! transitively reach. Entry points reaching the most sites are the best
+//! candidates for returning errors instead.
+//!
+//! Tests (`#[test]` functions, `#[cfg(test)]` modules and test files) are
+//! skipped, and only panics in function bodies outside macro arguments are
+//! seen: `format!("{}", x.unwrap())` is opaque to tree-sitter.
+
+use crate::state::OciState;
+use crate::types::{InternedString, ModuleRole, SymbolDef, SymbolKind, Visibility};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+/// Maximum number of panicking functions listed per entry point.
+const MAX_VIA: usize = 5;
+
+/// What kind of panic a site is.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PanicKind {
+    Unwrap,
+    Expect,
+    Panic,
+    Todo,
+    Unimplemented,
+}
+
+impl PanicKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unwrap => "unwrap",
+            Self::Expect => "expect",
+            Self::Panic => "panic!",
+            Self::Todo => "todo!",
+            Self::Unimplemented => "unimplemented!",
+        }
+    }
+
+    fn from_method(name: &str) -> Option<Self> {
+        match name {
+            "unwrap" => Some(Self::Unwrap),
+            "expect" => Some(Self::Expect),
+            _ => None,
+        }
+    }
+
+    fn from_macro(name: &str) -> Option<Self> {
+        match name {
+            "panic" => Some(Self::Panic),
+            "todo" => Some(Self::Todo),
+            "unimplemented" => Some(Self::Unimplemented),
+            _ => None,
+        }
+    }
+}
+
+/// A call that can panic.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct PanicSite {
+    pub kind: PanicKind,
+    /// Scoped name of the containing function
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+}
+
+/// A public function and the panic sites it can reach.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct PanicEntryPoint {
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// Sites reachable through calls, including its own
+    pub sites: usize,
+    /// Sites in its own body
+    pub direct: usize,
+    /// Reachable sites by kind
+    pub kinds: BTreeMap<PanicKind, usize>,
+    /// Reachable functions containing the most sites, at most five
+    pub via: Vec<String>,
+}
+
+/// Panic sites and the public entry points that reach them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PanicReport {
+    /// Every site, in file order
+    pub sites: Vec<PanicSite>,
+    /// Entry points reaching at least one site, most sites first
+    pub entry_points: Vec<PanicEntryPoint>,
+}
+
+/// Finds panic sites and the public functions that can reach them.
+pub struct PanicAnalyzer;
+
+impl PanicAnalyzer {
+    /// Creates a new panic analyzer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn analyze(&self, state: &OciState, root: &Path) -> PanicReport {
+        let sites = collect_sites(state, root);
+        let mut by_symbol: HashMap<InternedString, Vec<usize>> = HashMap::new();
+        for (i, (scoped, _)) in sites.iter().enumerate() {
+            by_symbol.entry(*scoped).or_default().push(i);
+        }
+
+        let callees = callee_map(state);
+
+        let mut entry_points: Vec<PanicEntryPoint> = state
+            .symbols
+            .iter()
+            .filter(|s| is_entry_point(state, s.value()))
+            .filter_map(|entry| {
+                let entry = entry.value();
+                let reached = reachable(entry.scoped_name, &callees);
+                let mut kinds = BTreeMap::new();
+                let mut via: Vec<(InternedString, usize)> = Vec::new();
+                for scoped in reached {
+                    let Some(ids) = by_symbol.get(&scoped) else {
+                        continue;
+                    };
+                    for &i in ids {
+                        *kinds.entry(sites[i].1.kind).or_insert(0) += 1;
+                    }
+                    via.push((scoped, ids.len()));
+                }
+                if via.is_empty() {
+                    return None;
+                }
+                via.sort_by(|a, b| {
+                    b.1.cmp(&a.1)
+                        .then_with(|| state.resolve(a.0).cmp(state.resolve(b.0)))
+                });
+                Some(PanicEntryPoint {
+                    symbol: state.resolve(entry.scoped_name).to_string(),
+                    file: relative(root, &entry.location.file),
+                    line: entry.location.start_line,
+                    sites: via.iter().map(|(_, n)| n).sum(),
+                    direct: by_symbol.get(&entry.scoped_name).map_or(0, Vec::len),
+                    kinds,
+                    via: via
+                        .iter()
+                        .take(MAX_VIA)
+                        .map(|(s, _)| state.resolve(*s).to_string())
+                        .collect(),
+                })
+            })
+            .collect();
+        entry_points.sort_by(|a, b| b.sites.cmp(&a.sites).then_with(|| a.symbol.cmp(&b.symbol)));
+
+        PanicReport {
+            sites: sites.into_iter().map(|(_, site)| site).collect(),
+            entry_points,
+        }
+    }
+}
+
+impl Default for PanicAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Functions each function calls, resolved through [`OciState::call_targets`].
+pub(super) fn callee_map(state: &OciState) -> HashMap<InternedString, Vec<InternedString>> {
+    let mut callees: HashMap<InternedString, Vec<InternedString>> = HashMap::new();
+    let edges = state.call_edges.read().clone();
+    for edge in &edges {
+        let targets = callees.entry(edge.caller).or_default();
+        targets.extend(state.call_targets(edge).into_iter().map(|s| s.scoped_name));
+    }
+    callees
+}
+
+/// Non-test `pub` functions and methods.
+pub(super) fn is_entry_point(state: &OciState, symbol: &SymbolDef) -> bool {
+    matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+        && symbol.visibility == Visibility::Public
+        && !symbol.is_test()
+        && state.file_role(&symbol.location.file) != ModuleRole::Tests
+}
+
+/// `start` and every function reachable from it through calls.
+fn reachable(
+    start: InternedString,
+    callees: &HashMap<InternedString, Vec<InternedString>>,
+) -> HashSet<InternedString> {
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for &callee in callees.get(&current).into_iter().flatten() {
+            if seen.insert(callee) {
+                queue.push_back(callee);
+            }
+        }
+    }
+    seen
+}
+
+/// Panic sites in every indexed non-test Rust file, with the scoped name of
+/// their containing function.
+fn collect_sites(state: &OciState, root: &Path) -> Vec<(InternedString, PanicSite)> {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|f| state.file_role(f) != ModuleRole::Tests)
+        .collect();
+    files.sort();
+
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    let mut sites = Vec::new();
+    for file in &files {
+        let contents = match state.overlays.get(file) {
+            Some(overlay) => overlay.contents.to_string(),
+            None => match std::fs::read_to_string(file) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            },
+        };
+        let Some(tree) = parser.parse(&contents, None) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        walk(tree.root_node(), contents.as_bytes(), &mut found);
+        if found.is_empty() {
+            continue;
+        }
+
+        let functions = file_functions(state, file);
+        let rel = relative(root, file);
+        for (kind, byte, line) in found {
+            // Innermost function containing the site
+            let Some(function) = functions
+                .iter()
+                .filter(|s| s.location.start_byte <= byte && byte < s.location.end_byte)
+                .min_by_key(|s| s.location.end_byte - s.location.start_byte)
+            else {
+                continue;
+            };
+            sites.push((
+                function.scoped_name,
+                PanicSite {
+                    kind,
+                    symbol: state.resolve(function.scoped_name).to_string(),
+                    file: rel.clone(),
+                    line,
+                },
+            ));
+        }
+    }
+    sites
+}
+
+pub(super) fn file_functions(state: &OciState, file: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+        return Vec::new();
+    };
+    let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+        return Vec::new();
+    };
+    scoped_names
+        .into_iter()
+        .filter_map(|scoped| state.get_symbol(scoped))
+        // Same-named symbols in other files overwrite each other
+        .filter(|s| s.location.file == file)
+        .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+        .collect()
+}
+
+/// Collect `(kind, byte, line)` of panic sites under `node`, skipping test
+/// functions and modules.
+fn walk(node: Node, bytes: &[u8], found: &mut Vec<(PanicKind, usize, usize)>) {
+    if matches!(node.kind(), "function_item" | "mod_item") && is_test_item(node, bytes) {
+        return;
+    }
+
+    let kind = match node.kind() {
+        "call_expression" => node
+            .child_by_field_name("function")
+            .filter(|f| f.kind() == "field_expression")
+            .and_then(|f| f.child_by_field_name("field"))
+            .and_then(|field| PanicKind::from_method(text(field, bytes))),
+        "macro_invocation" => node
+            .child_by_field_name("macro")
+            .and_then(|m| text(m, bytes).rsplit("::").next())
+            .and_then(PanicKind::from_macro),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        found.push((kind, node.start_byte(), node.start_position().row));
+    }
+
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, found);
+        }
+    }
+}
+
+/// Whether an item carries `#[test]`, `#[tokio::test]` or `#[cfg(test)]`.
+pub(super) fn is_test_item(node: Node, bytes: &[u8]) -> bool {
+    let mut prev = node.prev_named_sibling();
+    while let Some(attr) = prev.filter(|p| p.kind() == "attribute_item") {
+        let attr_text: String = text(attr, bytes).split_whitespace().collect();
+        let path = attr_text
+            .trim_start_matches("#[")
+            .split(['(', ']'])
+            .next()
+            .unwrap_or_default();
+        if path.rsplit("::").next() == Some("test") || attr_text.starts_with("#[cfg(test") {
+            return true;
+        }
+        prev = attr.prev_named_sibling();
+    }
+    false
+}
+
+pub(super) fn text<'a>(node: Node, bytes: &'a [u8]) -> &'a str {
+    std::str::from_utf8(&bytes[node.byte_range()]).unwrap_or_default()
+}
+
+pub(super) fn relative(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_counts_sites_reachable_from_public_functions() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            r#"
+pub fn load(path: &str) -> String {
+    let raw = read(path);
+    decode(&raw).expect("valid")
+}
+
+pub fn safe() -> u32 {
+    1
+}
+
+fn read(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn decode(raw: &str) -> Option<String> {
+    if raw.is_empty() {
+        todo!()
+    }
+    Some(raw.to_string())
+}
+
+fn unused() {
+    panic!("never called");
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_load() {
+        super::load("x").len().checked_add(1).unwrap();
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let report = PanicAnalyzer::new().analyze(&state, root);
+
+        let sites: Vec<(&str, PanicKind)> = report
+            .sites
+            .iter()
+            .map(|s| (s.symbol.as_str(), s.kind))
+            .collect();
+        assert_eq!(
+            sites,
+            vec![
+                ("crate::load", PanicKind::Expect),
+                ("crate::read", PanicKind::Unwrap),
+                ("crate::decode", PanicKind::Todo),
+                ("crate::unused", PanicKind::Panic),
+            ]
+        );
+        assert_eq!(report.sites[0].file, "src/lib.rs");
+
+        assert_eq!(report.entry_points.len(), 1);
+        let load = &report.entry_points[0];
+        assert_eq!(load.symbol, "crate::load");
+        assert_eq!((load.sites, load.direct), (3, 1));
+        assert_eq!(load.kinds[&PanicKind::Unwrap], 1);
+        assert_eq!(load.via.len(), 3);
+    }
+}
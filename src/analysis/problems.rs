@@ -0,0 +1,408 @@
+//! Analysis findings as editor problems and SARIF.
+//!
+//! `omni analyze <type> --format problems` prints one
+//! `file:line:col: severity: message [rule]` line per finding, the shape VS
+//! Code's problem matchers (and most editors' compiler-output parsers)
+//! pick up, and `--format sarif` a SARIF 2.1.0 log for GitHub code
+//! scanning. The `from_*` functions turn analysis reports into
+//! [`Problem`]s.
+
+use super::clones::CloneGroup;
+use super::error_patterns::ErrorPattern;
+use super::panics::PanicSite;
+use super::risky_apis::RiskySite;
+use super::test_clones::TestCloneGroup;
+use crate::state::OciState;
+use crate::types::{DeadExplanation, InternedString};
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// How serious a problem is.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Worth a look, e.g. a refactoring opportunity
+    Note,
+}
+
+impl Severity {
+    /// Name in problem lines (`error`, `warning`, `info`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "info",
+        }
+    }
+
+    /// SARIF result level.
+    pub fn sarif_level(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+        }
+    }
+}
+
+/// One finding at a source location.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, schemars::JsonSchema)]
+pub struct Problem {
+    /// Analysis that reported it (e.g. `dead-code`)
+    pub rule: String,
+    pub severity: Severity,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    /// 0-based; see [`location_line`] for lines of parsed symbols
+    pub line: usize,
+    /// 0-based; 0 when the analysis only knows the line
+    pub column: usize,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(rule: &str, severity: Severity, file: &str, line: usize, message: String) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity,
+            file: file.to_string(),
+            line,
+            column: 0,
+            message,
+        }
+    }
+
+    /// `file:line:col: severity: message [rule]`, 1-based.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}:{}:{}: {}: {} [{}]",
+            self.file,
+            self.line + 1,
+            self.column + 1,
+            self.severity.as_str(),
+            self.message,
+            self.rule
+        )
+    }
+}
+
+/// 0-based line of a location the parser of `file` placed at `line`, as
+/// parsers count from 0 or 1 (see [`OciState::first_line`]).
+pub fn location_line(state: &OciState, file: &Path, line: usize) -> usize {
+    line.saturating_sub(state.first_line(file))
+}
+
+/// Problem lines for `problems`, one per line, in file and line order.
+pub fn render_problems(problems: &[Problem]) -> String {
+    sorted(problems)
+        .iter()
+        .map(|problem| format!("{}\n", problem.to_line()))
+        .collect()
+}
+
+/// A SARIF 2.1.0 log with one run of `omni` holding `problems`. Locations
+/// are relative to the `%SRCROOT%` base, the repository root.
+pub fn to_sarif(problems: &[Problem]) -> Value {
+    let rules: BTreeSet<&str> = problems.iter().map(|p| p.rule.as_str()).collect();
+    let results: Vec<Value> = sorted(problems)
+        .iter()
+        .map(|problem| {
+            json!({
+                "ruleId": problem.rule,
+                "level": problem.severity.sarif_level(),
+                "message": { "text": problem.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": problem.file,
+                            "uriBaseId": "%SRCROOT%",
+                        },
+                        "region": {
+                            "startLine": problem.line + 1,
+                            "startColumn": problem.column + 1,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "omni",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Dead symbols, with the reason from their explanation when there is one.
+pub fn from_dead_code(
+    state: &OciState,
+    root: &Path,
+    dead: &[InternedString],
+    explanations: &HashMap<InternedString, DeadExplanation>,
+) -> Vec<Problem> {
+    dead.iter()
+        .filter_map(|&scoped_name| {
+            let symbol = state.get_symbol(scoped_name)?;
+            let file = symbol
+                .location
+                .file
+                .strip_prefix(root)
+                .unwrap_or(&symbol.location.file)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let reason = match explanations.get(&scoped_name) {
+                Some(explanation) => format!(" ({})", explanation.reason.as_str()),
+                None => String::new(),
+            };
+            Some(Problem::new(
+                "dead-code",
+                Severity::Warning,
+                &file,
+                location_line(state, &symbol.location.file, symbol.location.start_line),
+                format!(
+                    "{:?} {} is unreachable from any entry point{}",
+                    symbol.kind,
+                    state.resolve(scoped_name),
+                    reason
+                ),
+            ))
+        })
+        .collect()
+}
+
+pub fn from_panic_sites(sites: &[PanicSite]) -> Vec<Problem> {
+    sites
+        .iter()
+        .map(|site| {
+            Problem::new(
+                "panics",
+                Severity::Warning,
+                &site.file,
+                site.line,
+                format!("{} can panic in {}", site.kind.as_str(), site.symbol),
+            )
+        })
+        .collect()
+}
+
+pub fn from_risky_sites(sites: &[RiskySite]) -> Vec<Problem> {
+    sites
+        .iter()
+        .map(|site| {
+            let detail = match &site.detail {
+                Some(detail) => format!(" ({})", detail),
+                None => String::new(),
+            };
+            Problem::new(
+                "risky-apis",
+                Severity::Warning,
+                &site.file,
+                site.line,
+                format!(
+                    "{}: {}{} in {}",
+                    site.category.as_str(),
+                    site.api,
+                    detail,
+                    site.symbol
+                ),
+            )
+        })
+        .collect()
+}
+
+/// One problem per listed site of each pattern.
+pub fn from_error_patterns(patterns: &[ErrorPattern]) -> Vec<Problem> {
+    patterns
+        .iter()
+        .flat_map(|pattern| {
+            pattern.sites.iter().map(|site| {
+                Problem::new(
+                    "error-patterns",
+                    Severity::Note,
+                    &site.file,
+                    site.line,
+                    format!(
+                        "`{}` repeated at {} sites: {}",
+                        pattern.shape, pattern.count, pattern.suggestion
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// One problem per test of each group, naming the others.
+pub fn from_test_clones(state: &OciState, root: &Path, groups: &[TestCloneGroup]) -> Vec<Problem> {
+    groups
+        .iter()
+        .flat_map(|group| {
+            group.tests.iter().map(|test| {
+                let others: Vec<&str> = group
+                    .tests
+                    .iter()
+                    .filter(|other| other.test != test.test)
+                    .map(|other| other.test.as_str())
+                    .collect();
+                Problem::new(
+                    "test-clones",
+                    Severity::Note,
+                    &test.file,
+                    location_line(state, &root.join(&test.file), test.line),
+                    format!(
+                        "setup of {} duplicates {}: {}",
+                        test.test,
+                        others.join(", "),
+                        group.suggestion
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// One problem per function of each clone group, naming the others.
+pub fn from_clones(state: &OciState, root: &Path, groups: &[CloneGroup]) -> Vec<Problem> {
+    groups
+        .iter()
+        .flat_map(|group| {
+            group.functions.iter().map(|function| {
+                let others: Vec<String> = group
+                    .functions
+                    .iter()
+                    .filter(|other| other != &function)
+                    .map(|other| {
+                        let line = location_line(state, &root.join(&other.file), other.line);
+                        format!("{} ({}:{})", other.symbol, other.file, line + 1)
+                    })
+                    .collect();
+                Problem::new(
+                    "clones",
+                    Severity::Note,
+                    &function.file,
+                    location_line(state, &root.join(&function.file), function.line),
+                    format!(
+                        "body of {} is {:.0}% similar to {}",
+                        function.symbol,
+                        group.similarity * 100.0,
+                        others.join(", ")
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+fn sorted(problems: &[Problem]) -> Vec<&Problem> {
+    let mut sorted: Vec<&Problem> = problems.iter().collect();
+    sorted.sort_by(|a, b| {
+        (&a.file, a.line, a.column, &a.rule).cmp(&(&b.file, b.line, b.column, &b.rule))
+    });
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem_lines_and_sarif() {
+        let problems = vec![
+            Problem::new(
+                "panics",
+                Severity::Warning,
+                "src/b.rs",
+                4,
+                "unwrap can panic in crate::load".to_string(),
+            ),
+            Problem::new(
+                "dead-code",
+                Severity::Note,
+                "src/a.rs",
+                0,
+                "Function crate::old is unreachable from any entry point".to_string(),
+            ),
+        ];
+        assert_eq!(
+            render_problems(&problems),
+            "src/a.rs:1:1: info: Function crate::old is unreachable from any entry point [dead-code]\n\
+             src/b.rs:5:1: warning: unwrap can panic in crate::load [panics]\n"
+        );
+
+        let sarif = to_sarif(&problems);
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], "panics");
+        assert_eq!(result["level"], "warning");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/b.rs");
+        assert_eq!(location["region"]["startLine"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_dead_code_lines_match_the_source() {
+        use crate::incremental::IncrementalIndexer;
+        use crate::state::create_state;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "\n\nfn old() {}\n").unwrap();
+        std::fs::write(root.join("src/util.ts"), "\n\nfunction old() {}\n").unwrap();
+        // Parsed as JavaScript for its shebang and as TypeScript by override
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::write(
+            root.join("bin/tool"),
+            "#!/usr/bin/env node\n\nfunction old() {}\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("tools")).unwrap();
+        std::fs::write(root.join("tools/gen"), "\n\nfunction old() {}\n").unwrap();
+        let overrides =
+            crate::parsing::LanguageOverrides::from_rules(&["tools/*=typescript".to_string()])
+                .unwrap();
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .with_language_overrides(overrides)
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let dead: Vec<InternedString> = state
+            .find_by_name("old")
+            .iter()
+            .map(|symbol| symbol.scoped_name)
+            .collect();
+        assert_eq!(dead.len(), 4);
+        let problems = from_dead_code(&state, &root, &dead, &HashMap::new());
+        let lines = render_problems(&problems);
+        // Both functions are on line 3, whatever base their parser counts from
+        assert!(lines.contains("src/lib.rs:3:1: warning:"), "{lines}");
+        assert!(lines.contains("src/util.ts:3:1: warning:"), "{lines}");
+        assert!(lines.contains("bin/tool:3:1: warning:"), "{lines}");
+        assert!(lines.contains("tools/gen:3:1: warning:"), "{lines}");
+        let sarif = to_sarif(&problems);
+        for result in sarif["runs"][0]["results"].as_array().unwrap() {
+            assert_eq!(
+                result["locations"][0]["physicalLocation"]["region"]["startLine"],
+                3
+            );
+        }
+    }
+}
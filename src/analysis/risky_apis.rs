@@ -0,0 +1,611 @@
+//! Risky API usage reachable from entry points, for security review.
+//!
+//! Finds calls that spawn processes (`Command::new`), run raw SQL
+//! (`sqlx::query`, `diesel::sql_query`, `.execute("DELETE ...")`), delete
+//! files (`fs::remove_file`, `remove_dir_all`), mutate the process
+//! environment (`env::set_var`, `remove_var`, `set_current_dir`), or read
+//! secret-looking environment variables (`env::var("API_TOKEN")`) in indexed
+//! Rust code. Each site is attributed to its containing function, and the
+//! call graph is walked forward from every `pub` function and `main` to find
+//! the shortest call chain to each risky function it can reach.
+//!
+//! Like [`super::panics`], tests are skipped and calls inside macro
+//! arguments are not seen.
+
+use super::panics::{callee_map, file_functions, is_entry_point, is_test_item, relative, text};
+use crate::state::OciState;
+use crate::types::{InternedString, ModuleRole, SymbolDef};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+/// Maximum number of call chains listed per entry point.
+const MAX_CHAINS: usize = 10;
+
+/// Environment variable names containing one of these look like secrets.
+const SECRET_MARKERS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "API_KEY",
+    "APIKEY",
+    "PRIVATE_KEY",
+    "ACCESS_KEY",
+    "CREDENTIAL",
+];
+
+/// Keywords that make a string literal passed to `.execute()` and friends
+/// look like SQL.
+const SQL_KEYWORDS: &[&str] = &[
+    "SELECT ",
+    "INSERT ",
+    "UPDATE ",
+    "DELETE ",
+    "CREATE ",
+    "DROP ",
+    "ALTER ",
+    "TRUNCATE ",
+];
+
+/// Methods that run SQL text when given it.
+const SQL_METHODS: &[&str] = &[
+    "execute",
+    "execute_batch",
+    "batch_execute",
+    "query",
+    "query_row",
+    "query_map",
+    "prepare",
+    "simple_query",
+];
+
+/// What a risky call does.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskCategory {
+    /// Starts another program
+    ProcessSpawn,
+    /// Runs SQL text
+    RawSql,
+    /// Deletes files or directories
+    FsDeletion,
+    /// Changes the process environment or working directory
+    EnvMutation,
+    /// Reads a secret-looking environment variable
+    SecretRead,
+}
+
+impl RiskCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ProcessSpawn => "process_spawn",
+            Self::RawSql => "raw_sql",
+            Self::FsDeletion => "fs_deletion",
+            Self::EnvMutation => "env_mutation",
+            Self::SecretRead => "secret_read",
+        }
+    }
+}
+
+/// A call to a risky API.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct RiskySite {
+    pub category: RiskCategory,
+    /// The API as written (`Command::new`, `fs::remove_dir_all`, `execute`)
+    pub api: String,
+    /// Scoped name of the containing function
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// The variable name for secret reads; `format!` for SQL built at runtime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// The shortest call chain from an entry point to a function with risky
+/// sites.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct RiskChain {
+    /// Scoped name of the function containing the sites
+    pub function: String,
+    /// Categories of its sites
+    pub categories: Vec<RiskCategory>,
+    pub sites: usize,
+    /// Scoped names from the entry point to `function`, both included
+    pub path: Vec<String>,
+}
+
+/// An entry point and the risky sites it can reach.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct RiskyEntryPoint {
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// Sites reachable through calls, including its own
+    pub sites: usize,
+    /// Reachable sites by category
+    pub categories: BTreeMap<RiskCategory, usize>,
+    /// Chains to the reachable risky functions, shortest first, at most ten
+    pub chains: Vec<RiskChain>,
+}
+
+/// Risky API sites and the entry points that reach them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RiskyApiReport {
+    /// Every site, in file order
+    pub sites: Vec<RiskySite>,
+    /// Entry points reaching at least one site, most sites first
+    pub entry_points: Vec<RiskyEntryPoint>,
+}
+
+/// Finds risky API calls and the entry points that can reach them.
+pub struct RiskyApiAnalyzer;
+
+impl RiskyApiAnalyzer {
+    /// Creates a new risky API analyzer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn analyze(&self, state: &OciState, root: &Path) -> RiskyApiReport {
+        let sites = collect_sites(state, root);
+        let mut by_symbol: HashMap<InternedString, Vec<usize>> = HashMap::new();
+        for (i, (scoped, _)) in sites.iter().enumerate() {
+            by_symbol.entry(*scoped).or_default().push(i);
+        }
+        let callees = callee_map(state);
+
+        let mut entry_points: Vec<RiskyEntryPoint> = state
+            .symbols
+            .iter()
+            .filter(|s| is_risk_entry_point(state, s.value()))
+            .filter_map(|entry| {
+                let entry = entry.value();
+                let parents = shortest_paths(entry.scoped_name, &callees);
+                let mut categories = BTreeMap::new();
+                let mut chains = Vec::new();
+                for (&function, ids) in &by_symbol {
+                    if !parents.contains_key(&function) {
+                        continue;
+                    }
+                    let mut chain_categories = BTreeSet::new();
+                    for &i in ids {
+                        *categories.entry(sites[i].1.category).or_insert(0) += 1;
+                        chain_categories.insert(sites[i].1.category);
+                    }
+                    chains.push(RiskChain {
+                        function: state.resolve(function).to_string(),
+                        categories: chain_categories.into_iter().collect(),
+                        sites: ids.len(),
+                        path: path_to(function, &parents)
+                            .into_iter()
+                            .map(|s| state.resolve(s).to_string())
+                            .collect(),
+                    });
+                }
+                if chains.is_empty() {
+                    return None;
+                }
+                chains.sort_by(|a, b| {
+                    a.path
+                        .len()
+                        .cmp(&b.path.len())
+                        .then_with(|| a.function.cmp(&b.function))
+                });
+                let sites = chains.iter().map(|c| c.sites).sum();
+                chains.truncate(MAX_CHAINS);
+                Some(RiskyEntryPoint {
+                    symbol: state.resolve(entry.scoped_name).to_string(),
+                    file: relative(root, &entry.location.file),
+                    line: entry.location.start_line,
+                    sites,
+                    categories,
+                    chains,
+                })
+            })
+            .collect();
+        entry_points.sort_by(|a, b| b.sites.cmp(&a.sites).then_with(|| a.symbol.cmp(&b.symbol)));
+
+        RiskyApiReport {
+            sites: sites.into_iter().map(|(_, site)| site).collect(),
+            entry_points,
+        }
+    }
+}
+
+impl Default for RiskyApiAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Public API functions, plus `main` of binaries.
+fn is_risk_entry_point(state: &OciState, symbol: &SymbolDef) -> bool {
+    is_entry_point(state, symbol)
+        || (state.resolve(symbol.name) == "main"
+            && !symbol.is_test()
+            && state.file_role(&symbol.location.file) != ModuleRole::Tests)
+}
+
+/// Breadth-first parents of every function reachable from `start`; `start`
+/// maps to itself.
+fn shortest_paths(
+    start: InternedString,
+    callees: &HashMap<InternedString, Vec<InternedString>>,
+) -> HashMap<InternedString, InternedString> {
+    let mut parents = HashMap::from([(start, start)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for &callee in callees.get(&current).into_iter().flatten() {
+            if let Entry::Vacant(e) = parents.entry(callee) {
+                e.insert(current);
+                queue.push_back(callee);
+            }
+        }
+    }
+    parents
+}
+
+fn path_to(
+    target: InternedString,
+    parents: &HashMap<InternedString, InternedString>,
+) -> Vec<InternedString> {
+    let mut path = vec![target];
+    let mut current = target;
+    while let Some(&parent) = parents.get(&current) {
+        if parent == current {
+            break;
+        }
+        path.push(parent);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Risky sites in every indexed non-test Rust file, with the scoped name of
+/// their containing function.
+fn collect_sites(state: &OciState, root: &Path) -> Vec<(InternedString, RiskySite)> {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|f| state.file_role(f) != ModuleRole::Tests)
+        .collect();
+    files.sort();
+
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return Vec::new();
+    }
+
+    let mut sites = Vec::new();
+    for file in &files {
+        let contents = match state.overlays.get(file) {
+            Some(overlay) => overlay.contents.to_string(),
+            None => match std::fs::read_to_string(file) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            },
+        };
+        let Some(tree) = parser.parse(&contents, None) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        walk(tree.root_node(), contents.as_bytes(), &mut found);
+        if found.is_empty() {
+            continue;
+        }
+
+        let functions = file_functions(state, file);
+        let rel = relative(root, file);
+        for found in found {
+            // Innermost function containing the site
+            let Some(function) = functions
+                .iter()
+                .filter(|s| s.location.start_byte <= found.byte && found.byte < s.location.end_byte)
+                .min_by_key(|s| s.location.end_byte - s.location.start_byte)
+            else {
+                continue;
+            };
+            sites.push((
+                function.scoped_name,
+                RiskySite {
+                    category: found.category,
+                    api: found.api,
+                    symbol: state.resolve(function.scoped_name).to_string(),
+                    file: rel.clone(),
+                    line: found.line,
+                    detail: found.detail,
+                },
+            ));
+        }
+    }
+    sites
+}
+
+/// A risky call found in a syntax tree.
+struct Found {
+    category: RiskCategory,
+    api: String,
+    detail: Option<String>,
+    byte: usize,
+    line: usize,
+}
+
+/// Collect risky calls under `node`, skipping test functions and modules.
+fn walk(node: Node, bytes: &[u8], found: &mut Vec<Found>) {
+    if matches!(node.kind(), "function_item" | "mod_item") && is_test_item(node, bytes) {
+        return;
+    }
+
+    if node.kind() == "call_expression"
+        && let Some((category, api, detail)) = classify_call(node, bytes)
+    {
+        found.push(Found {
+            category,
+            api,
+            detail,
+            byte: node.start_byte(),
+            line: node.start_position().row,
+        });
+    }
+
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, found);
+        }
+    }
+}
+
+/// Category, API name, and detail of a risky `call_expression`.
+fn classify_call(node: Node, bytes: &[u8]) -> Option<(RiskCategory, String, Option<String>)> {
+    let mut function = node.child_by_field_name("function")?;
+    if function.kind() == "generic_function" {
+        function = function.child_by_field_name("function")?;
+    }
+    let first_arg = node
+        .child_by_field_name("arguments")
+        .and_then(|args| args.named_child(0));
+
+    if function.kind() == "field_expression" {
+        let method = text(function.child_by_field_name("field")?, bytes);
+        let arg = first_arg?;
+        if !SQL_METHODS.contains(&method) {
+            return None;
+        }
+        let sql = sql_text(arg, bytes)?;
+        let dynamic = sql.starts_with("format!");
+        return Some((
+            RiskCategory::RawSql,
+            method.to_string(),
+            dynamic.then(|| "format!".to_string()),
+        ));
+    }
+
+    if !matches!(function.kind(), "identifier" | "scoped_identifier") {
+        return None;
+    }
+    let api: String = text(function, bytes).split_whitespace().collect();
+    let mut segments = api.rsplit("::");
+    let last = segments.next()?;
+    let parent = segments.next();
+
+    let category = match (parent, last) {
+        (Some("Command"), "new") => RiskCategory::ProcessSpawn,
+        (Some("libc"), "system" | "execv" | "execvp" | "execve" | "fork") => {
+            RiskCategory::ProcessSpawn
+        }
+        (Some("fs") | None, "remove_file" | "remove_dir" | "remove_dir_all") => {
+            RiskCategory::FsDeletion
+        }
+        (Some("env") | None, "set_var" | "remove_var" | "set_current_dir") => {
+            RiskCategory::EnvMutation
+        }
+        (Some("sqlx"), "query" | "query_as" | "query_scalar" | "raw_sql") => RiskCategory::RawSql,
+        (_, "sql_query") => RiskCategory::RawSql,
+        (Some("env" | "dotenv" | "dotenvy"), "var" | "var_os") => {
+            let key = first_arg
+                .filter(|a| a.kind() == "string_literal")
+                .map(|a| text(a, bytes).trim_matches('"').to_string())?;
+            let upper = key.to_ascii_uppercase();
+            if !SECRET_MARKERS.iter().any(|m| upper.contains(m)) {
+                return None;
+            }
+            return Some((RiskCategory::SecretRead, api, Some(key)));
+        }
+        _ => return None,
+    };
+    let detail = match category {
+        RiskCategory::RawSql => first_arg
+            .and_then(|a| sql_text(a, bytes))
+            .filter(|sql| sql.starts_with("format!"))
+            .map(|_| "format!".to_string()),
+        _ => None,
+    };
+    Some((category, api, detail))
+}
+
+/// Text of an argument that is SQL: a string literal containing a SQL
+/// keyword, or a `format!` whose template does, possibly borrowed.
+fn sql_text<'a>(arg: Node, bytes: &'a [u8]) -> Option<&'a str> {
+    let arg = if arg.kind() == "reference_expression" {
+        arg.child_by_field_name("value")?
+    } else {
+        arg
+    };
+    let is_sql = |s: &str| {
+        let upper = s.to_ascii_uppercase();
+        SQL_KEYWORDS.iter().any(|k| upper.contains(k))
+    };
+    match arg.kind() {
+        "string_literal" | "raw_string_literal" => {
+            let sql = text(arg, bytes);
+            is_sql(sql).then_some(sql)
+        }
+        "macro_invocation" => {
+            let name = text(arg.child_by_field_name("macro")?, bytes);
+            let body = text(arg, bytes);
+            (name.rsplit("::").next() == Some("format") && is_sql(body)).then_some(body)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_reports_risky_sites_with_chains_from_entry_points() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            r#"
+use std::process::Command;
+
+fn main() {
+    cleanup("/tmp/x");
+}
+
+pub fn cleanup(dir: &str) {
+    wipe(dir);
+    std::env::set_var("MODE", "clean");
+}
+
+fn wipe(dir: &str) {
+    std::fs::remove_dir_all(dir).ok();
+    Command::new("rm").arg(dir).status().ok();
+}
+
+pub fn purge(conn: &Conn, table: &str) {
+    conn.execute(&format!("DELETE FROM {}", table));
+    conn.execute("not sql");
+}
+
+fn token() -> String {
+    std::env::var("GITHUB_TOKEN").unwrap_or_default() + &std::env::var("HOME").unwrap_or_default()
+}
+
+pub struct Conn;
+
+impl Conn {
+    pub fn execute(&self, _sql: &str) {}
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_wipe() {
+        std::fs::remove_file("x").ok();
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let report = RiskyApiAnalyzer::new().analyze(&state, root);
+
+        let sites: Vec<(&str, RiskCategory, &str, Option<&str>)> = report
+            .sites
+            .iter()
+            .map(|s| {
+                (
+                    s.symbol.as_str(),
+                    s.category,
+                    s.api.as_str(),
+                    s.detail.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sites,
+            vec![
+                (
+                    "crate::cleanup",
+                    RiskCategory::EnvMutation,
+                    "std::env::set_var",
+                    None
+                ),
+                (
+                    "crate::wipe",
+                    RiskCategory::FsDeletion,
+                    "std::fs::remove_dir_all",
+                    None
+                ),
+                (
+                    "crate::wipe",
+                    RiskCategory::ProcessSpawn,
+                    "Command::new",
+                    None
+                ),
+                (
+                    "crate::purge",
+                    RiskCategory::RawSql,
+                    "execute",
+                    Some("format!")
+                ),
+                (
+                    "crate::token",
+                    RiskCategory::SecretRead,
+                    "std::env::var",
+                    Some("GITHUB_TOKEN")
+                ),
+            ]
+        );
+        assert_eq!(report.sites[0].file, "src/main.rs");
+
+        let entry = |name: &str| {
+            report
+                .entry_points
+                .iter()
+                .find(|e| e.symbol == name)
+                .unwrap_or_else(|| panic!("no entry point {name}"))
+        };
+        let main = entry("crate::main");
+        assert_eq!(main.sites, 3);
+        assert_eq!(main.categories[&RiskCategory::FsDeletion], 1);
+        assert_eq!(
+            main.chains
+                .iter()
+                .map(|c| c.path.join(" -> "))
+                .collect::<Vec<_>>(),
+            vec![
+                "crate::main -> crate::cleanup",
+                "crate::main -> crate::cleanup -> crate::wipe",
+            ]
+        );
+        assert_eq!(
+            main.chains[1].categories,
+            vec![RiskCategory::ProcessSpawn, RiskCategory::FsDeletion]
+        );
+        assert_eq!(entry("crate::purge").sites, 1);
+        // Private and unreachable from any entry point
+        assert!(
+            report
+                .entry_points
+                .iter()
+                .all(|e| e.chains.iter().all(|c| c.function != "crate::token"))
+        );
+    }
+}
@@ -0,0 +1,790 @@
+//! Seams: places where an implementation can be swapped, e.g. for a test.
+//!
+//! Three kinds are reported for indexed Rust code:
+//!
+//! - traits with exactly one (non-test) impl, which a test double could
+//!   implement without touching callers;
+//! - constructor-injected dependencies: associated functions returning
+//!   `Self` that take a workspace trait as `dyn Trait`, `impl Trait` or a
+//!   generic bound;
+//! - global singletons: `static mut` items and statics holding a
+//!   `OnceLock`, `OnceCell`, `Lazy`, `LazyLock`, `Mutex` or `RwLock`.
+//!
+//! Each seam lists its impls and construction sites: struct literals and
+//! constructor calls for the implementing type, calls to the injecting
+//! constructor, or the `get_or_init`/`set` calls filling a singleton.
+//! Tests are skipped.
+
+use super::panics::{file_functions, is_test_item, relative, text};
+use crate::state::OciState;
+use crate::types::{ModuleRole, SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+/// Construction sites listed per seam.
+const MAX_SITES: usize = 10;
+
+/// Cell types that make a `static` a lazily or mutably shared singleton.
+const SINGLETON_TYPES: &[&str] = &[
+    "OnceLock", "OnceCell", "Lazy", "LazyLock", "Mutex", "RwLock",
+];
+
+/// Methods that initialize a singleton cell.
+const SINGLETON_INITS: &[&str] = &["get_or_init", "get_or_try_init", "set", "init"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SeamKind {
+    /// A trait with exactly one impl
+    SingleImplTrait,
+    /// A constructor taking a trait-typed dependency
+    InjectedDependency,
+    /// A `static` holding shared state
+    GlobalSingleton,
+}
+
+impl SeamKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SingleImplTrait => "single-impl trait",
+            Self::InjectedDependency => "injected dependency",
+            Self::GlobalSingleton => "global singleton",
+        }
+    }
+}
+
+/// Where a seam's implementation is constructed or initialized.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct ConstructionSite {
+    /// Scoped name of the containing function, or of the static itself
+    pub symbol: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+}
+
+/// A place where an implementation can be swapped.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Seam {
+    pub kind: SeamKind,
+    /// Scoped name of the trait, constructor, or static
+    pub name: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// Trait abstracted over (trait seams and injected dependencies)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trait_name: Option<String>,
+    /// The injected parameter (`store: Box<dyn Store>`) or the static's type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Implementing types
+    pub impls: Vec<String>,
+    /// Construction sites in file order, at most ten
+    pub construction_sites: Vec<ConstructionSite>,
+    /// Construction sites found in total
+    pub construction_count: usize,
+}
+
+/// Every seam, grouped by kind and then sorted by name.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SeamReport {
+    pub seams: Vec<Seam>,
+}
+
+/// Finds seams in indexed Rust code.
+pub struct SeamAnalyzer;
+
+impl SeamAnalyzer {
+    /// Creates a new seam analyzer.
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn analyze(&self, state: &OciState, root: &Path) -> SeamReport {
+        let symbols: Vec<SymbolDef> = state
+            .symbols
+            .iter()
+            .map(|e| e.value().clone())
+            .filter(|s| is_production(state, s))
+            .collect();
+        let scan = scan_files(state, root);
+
+        // Workspace traits and their non-test implementors
+        let traits: BTreeMap<&str, &SymbolDef> = symbols
+            .iter()
+            .filter(|s| s.kind == SymbolKind::Trait)
+            .map(|s| (state.resolve(s.name), s))
+            .collect();
+        let mut impls: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in state.trait_impls.iter() {
+            for imp in entry.value() {
+                if state.file_role(&imp.location.file) == ModuleRole::Tests
+                    || scan.in_test_item(&imp.location.file, imp.location.start_byte)
+                {
+                    continue;
+                }
+                let types = impls.entry(imp.trait_name.clone()).or_default();
+                if !types.contains(&imp.type_name) {
+                    types.push(imp.type_name.clone());
+                }
+            }
+        }
+
+        // Constructors: associated functions without `self` returning `Self`
+        let constructors: Vec<(&str, &SymbolDef)> = symbols
+            .iter()
+            .filter_map(|s| {
+                let ty = state.resolve(s.parent?);
+                is_constructor(s, ty).then_some((ty, s))
+            })
+            .collect();
+        let constructor_names: HashSet<(&str, &str)> = constructors
+            .iter()
+            .map(|(ty, s)| (*ty, state.resolve(s.name)))
+            .collect();
+
+        let mut seams = Vec::new();
+        for (name, def) in &traits {
+            let Some(types) = impls.get(*name).filter(|t| t.len() == 1) else {
+                continue;
+            };
+            let sites: Vec<&ConstructionSite> = scan
+                .constructions
+                .iter()
+                .filter(|c| {
+                    c.type_name == types[0]
+                        && c.method
+                            .as_deref()
+                            .is_none_or(|m| constructor_names.contains(&(&types[0], m)))
+                })
+                .map(|c| &c.site)
+                .collect();
+            seams.push(seam(
+                state,
+                root,
+                SeamKind::SingleImplTrait,
+                def,
+                Some(name.to_string()),
+                None,
+                types.clone(),
+                &sites,
+            ));
+        }
+
+        for (ty, ctor) in &constructors {
+            let Some(sig) = &ctor.signature else {
+                continue;
+            };
+            let mut clauses: Vec<&str> = scan
+                .impl_bounds
+                .get(*ty)
+                .map(|c| c.iter().map(String::as_str).collect())
+                .unwrap_or_default();
+            clauses.extend(sig.generics.as_deref());
+            clauses.extend(sig.where_clause.as_deref());
+            let bounds = generic_bounds(&clauses);
+            for param in &sig.params {
+                let Some(trait_name) = injected_traits(param, &bounds)
+                    .into_iter()
+                    .find(|t| traits.contains_key(t.as_str()))
+                else {
+                    continue;
+                };
+                let ctor_name = state.resolve(ctor.name);
+                let sites: Vec<&ConstructionSite> = scan
+                    .constructions
+                    .iter()
+                    .filter(|c| c.type_name == *ty && c.method.as_deref() == Some(ctor_name))
+                    .map(|c| &c.site)
+                    .collect();
+                let types = impls.get(&trait_name).cloned().unwrap_or_default();
+                seams.push(seam(
+                    state,
+                    root,
+                    SeamKind::InjectedDependency,
+                    ctor,
+                    Some(trait_name),
+                    Some(param.clone()),
+                    types,
+                    &sites,
+                ));
+            }
+        }
+
+        for (def, ty) in &scan.singletons {
+            let Some(symbol) = symbols.iter().find(|s| {
+                s.kind == SymbolKind::Static && state.resolve(s.scoped_name) == def.symbol
+            }) else {
+                continue;
+            };
+            let name = state.resolve(symbol.name);
+            let mut sites: Vec<&ConstructionSite> = scan
+                .singleton_inits
+                .iter()
+                .filter(|(static_name, _)| static_name == name)
+                .map(|(_, site)| site)
+                .collect();
+            if sites.is_empty() {
+                // Initialized in place (`Lazy::new(..)`, `Mutex::new(..)`)
+                sites.push(def);
+            }
+            seams.push(seam(
+                state,
+                root,
+                SeamKind::GlobalSingleton,
+                symbol,
+                None,
+                Some(ty.clone()),
+                Vec::new(),
+                &sites,
+            ));
+        }
+
+        seams.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+        SeamReport { seams }
+    }
+}
+
+impl Default for SeamAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn seam(
+    state: &OciState,
+    root: &Path,
+    kind: SeamKind,
+    def: &SymbolDef,
+    trait_name: Option<String>,
+    detail: Option<String>,
+    impls: Vec<String>,
+    sites: &[&ConstructionSite],
+) -> Seam {
+    Seam {
+        kind,
+        name: state.resolve(def.scoped_name).to_string(),
+        file: relative(root, &def.location.file),
+        line: def.location.start_line,
+        trait_name,
+        detail,
+        impls,
+        construction_sites: sites.iter().take(MAX_SITES).map(|s| (*s).clone()).collect(),
+        construction_count: sites.len(),
+    }
+}
+
+/// Non-test Rust symbols outside test files.
+fn is_production(state: &OciState, symbol: &SymbolDef) -> bool {
+    symbol
+        .location
+        .file
+        .extension()
+        .is_some_and(|ext| ext == "rs")
+        && !symbol.is_test()
+        && state.file_role(&symbol.location.file) != ModuleRole::Tests
+}
+
+/// An associated function of `ty` that takes no `self` and returns `Self`
+/// (or `ty`), possibly wrapped (`Result<Self>`, `Arc<Self>`).
+fn is_constructor(symbol: &SymbolDef, ty: &str) -> bool {
+    let Some(sig) = &symbol.signature else {
+        return false;
+    };
+    let takes_self = sig.params.first().is_some_and(|p| {
+        let p = p.trim_start_matches('&').trim_start_matches("mut ").trim();
+        p.starts_with("self") || p.starts_with("'") && p.contains("self")
+    });
+    let returns_self = sig.return_type.as_deref().is_some_and(|ret| {
+        ret.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| word == "Self" || word == ty)
+    });
+    symbol.kind == SymbolKind::Method && !takes_self && returns_self
+}
+
+/// Trait bounds of each generic parameter, from `<T: A + B>` and
+/// `where T: C` clauses (last path segment of each trait).
+fn generic_bounds(clauses: &[&str]) -> HashMap<String, Vec<String>> {
+    let mut bounds: HashMap<String, Vec<String>> = HashMap::new();
+    let lists = clauses.iter().map(|c| {
+        let c = c.trim();
+        c.strip_prefix("where")
+            .unwrap_or_else(|| c.trim_start_matches('<').trim_end_matches('>'))
+    });
+    for clause in lists.flat_map(split_top_level) {
+        let Some((param, traits)) = clause.split_once(':') else {
+            continue;
+        };
+        let entry = bounds.entry(param.trim().to_string()).or_default();
+        for bound in traits.split('+') {
+            if let Some(name) = trait_path_name(bound) {
+                entry.push(name);
+            }
+        }
+    }
+    bounds
+}
+
+/// Workspace-candidate traits a parameter depends on: `dyn T`, `impl T`, or
+/// a generic parameter bounded by `T`.
+fn injected_traits(param: &str, bounds: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let Some((_, ty)) = param.split_once(':') else {
+        return Vec::new();
+    };
+    let mut traits = Vec::new();
+    let words: Vec<&str> = ty
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != ':')
+        .filter(|w| !w.is_empty())
+        .collect();
+    for (i, word) in words.iter().enumerate() {
+        if matches!(*word, "dyn" | "impl") {
+            traits.extend(words.get(i + 1).and_then(|t| trait_path_name(t)));
+        } else if let Some(bound) = bounds.get(*word) {
+            traits.extend(bound.iter().cloned());
+        }
+    }
+    traits
+}
+
+/// Last path segment of a trait bound, without generics (`io::Read<T>` ->
+/// `Read`); `None` for lifetimes and `?Sized`.
+fn trait_path_name(bound: &str) -> Option<String> {
+    let bound = bound.trim();
+    if bound.starts_with('\'') || bound.starts_with('?') || bound.is_empty() {
+        return None;
+    }
+    let path = bound.split('<').next().unwrap_or(bound);
+    path.rsplit("::").next().map(|s| s.trim().to_string())
+}
+
+/// Split on commas outside angle brackets and parentheses.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+/// A struct literal (`method: None`) or `Type::method(..)` call.
+struct Construction {
+    type_name: String,
+    method: Option<String>,
+    site: ConstructionSite,
+}
+
+#[derive(Default)]
+struct Scan {
+    constructions: Vec<Construction>,
+    /// Singleton statics and their declared type
+    singletons: Vec<(ConstructionSite, String)>,
+    /// `NAME.get_or_init(..)`-style calls, by static name
+    singleton_inits: Vec<(String, ConstructionSite)>,
+    /// `impl` generics and where clauses, by implementing type
+    impl_bounds: HashMap<String, Vec<String>>,
+    /// Byte ranges of skipped test items, by file
+    test_items: HashMap<PathBuf, Vec<(usize, usize)>>,
+}
+
+impl Scan {
+    fn in_test_item(&self, file: &Path, byte: usize) -> bool {
+        self.test_items
+            .get(file)
+            .is_some_and(|ranges| ranges.iter().any(|(s, e)| (*s..*e).contains(&byte)))
+    }
+}
+
+/// A syntax-level finding, before it is attributed to a function.
+enum Found {
+    Construction {
+        type_name: String,
+        method: Option<String>,
+    },
+    Singleton {
+        name: String,
+        ty: String,
+    },
+    SingletonInit {
+        name: String,
+    },
+    ImplBounds {
+        type_name: String,
+        clauses: Vec<String>,
+    },
+    TestItem {
+        end_byte: usize,
+    },
+}
+
+/// Parse every indexed non-test Rust file for constructions and singletons.
+fn scan_files(state: &OciState, root: &Path) -> Scan {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|f| state.file_role(f) != ModuleRole::Tests)
+        .collect();
+    files.sort();
+
+    let mut scan = Scan::default();
+    let mut parser = Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return scan;
+    }
+
+    for file in &files {
+        let contents = match state.overlays.get(file) {
+            Some(overlay) => overlay.contents.to_string(),
+            None => match std::fs::read_to_string(file) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            },
+        };
+        let Some(tree) = parser.parse(&contents, None) else {
+            continue;
+        };
+        let mut found = Vec::new();
+        walk(
+            tree.root_node(),
+            contents.as_bytes(),
+            &mut Vec::new(),
+            &mut found,
+        );
+        if found.is_empty() {
+            continue;
+        }
+
+        let functions = file_functions(state, file);
+        let rel = relative(root, file);
+        let module = module_scope(state, file);
+        for (item, byte, line) in found {
+            let containing = functions
+                .iter()
+                .filter(|s| s.location.start_byte <= byte && byte < s.location.end_byte)
+                .min_by_key(|s| s.location.end_byte - s.location.start_byte);
+            let site = |symbol: String| ConstructionSite {
+                symbol,
+                file: rel.clone(),
+                line,
+            };
+            match (item, containing) {
+                (Found::TestItem { end_byte }, _) => {
+                    scan.test_items
+                        .entry(file.clone())
+                        .or_default()
+                        .push((byte, end_byte));
+                }
+                (Found::ImplBounds { type_name, clauses }, _) => {
+                    scan.impl_bounds
+                        .entry(type_name)
+                        .or_default()
+                        .extend(clauses);
+                }
+                (Found::Singleton { name, ty }, _) => {
+                    let scoped = format!("{module}::{name}");
+                    scan.singletons.push((site(scoped), ty));
+                }
+                (Found::Construction { type_name, method }, Some(containing)) => {
+                    let symbol = state.resolve(containing.scoped_name).to_string();
+                    scan.constructions.push(Construction {
+                        type_name,
+                        method,
+                        site: site(symbol),
+                    });
+                }
+                (Found::SingletonInit { name }, Some(containing)) => {
+                    let symbol = state.resolve(containing.scoped_name).to_string();
+                    scan.singleton_inits.push((name, site(symbol)));
+                }
+                // Outside any function, e.g. in a static or const initializer
+                (Found::Construction { .. } | Found::SingletonInit { .. }, None) => {}
+            }
+        }
+    }
+    scan
+}
+
+/// Scoped module prefix of a file's top-level items (`crate` or
+/// `crate::a::b`), taken from any symbol defined in it.
+fn module_scope(state: &OciState, file: &Path) -> String {
+    let symbol = state.file_ids.get(file).and_then(|id| {
+        let names = state.file_symbols.get(&*id)?;
+        names
+            .iter()
+            .filter_map(|s| state.get_symbol(*s))
+            .find(|s| s.location.file == file && s.parent.is_none())
+    });
+    symbol
+        .and_then(|s| {
+            state
+                .resolve(s.scoped_name)
+                .rsplit_once("::")
+                .map(|(m, _)| m.to_string())
+        })
+        .unwrap_or_else(|| "crate".to_string())
+}
+
+/// Collect constructions, singleton statics, singleton initializations,
+/// and impl bounds under `node` as `(finding, byte, line)`. Test items are
+/// recorded but not descended into. `impls` tracks the enclosing impl types
+/// so `Self` resolves.
+fn walk(node: Node, bytes: &[u8], impls: &mut Vec<String>, found: &mut Vec<(Found, usize, usize)>) {
+    let (byte, line) = (node.start_byte(), node.start_position().row);
+    if matches!(node.kind(), "function_item" | "mod_item" | "impl_item")
+        && is_test_item(node, bytes)
+    {
+        let end_byte = node.end_byte();
+        found.push((Found::TestItem { end_byte }, byte, line));
+        return;
+    }
+    let resolve_self = |name: &str, impls: &[String]| {
+        if name == "Self" {
+            impls.last().cloned()
+        } else {
+            Some(name.to_string())
+        }
+    };
+
+    let item = match node.kind() {
+        "call_expression" => node
+            .child_by_field_name("function")
+            .filter(|f| f.kind() == "scoped_identifier")
+            .and_then(|f| {
+                let path = f.child_by_field_name("path")?;
+                let type_name = type_name_of(text(path, bytes))?;
+                let method = text(f.child_by_field_name("name")?, bytes).to_string();
+                Some(Found::Construction {
+                    type_name: resolve_self(&type_name, impls)?,
+                    method: Some(method),
+                })
+            })
+            .or_else(|| singleton_init(node, bytes)),
+        "struct_expression" => node
+            .child_by_field_name("name")
+            .and_then(|n| type_name_of(text(n, bytes)))
+            .and_then(|type_name| {
+                Some(Found::Construction {
+                    type_name: resolve_self(&type_name, impls)?,
+                    method: None,
+                })
+            }),
+        "static_item" => singleton_static(node, bytes),
+        _ => None,
+    };
+    if let Some(item) = item {
+        found.push((item, byte, line));
+    }
+
+    let impl_type = (node.kind() == "impl_item")
+        .then(|| node.child_by_field_name("type"))
+        .flatten()
+        .and_then(|t| type_name_of(text(t, bytes)));
+    if let Some(ty) = &impl_type {
+        let clauses = (0..node.child_count())
+            .filter_map(|i| node.child(i))
+            .filter(|c| matches!(c.kind(), "type_parameters" | "where_clause"))
+            .map(|c| text(c, bytes).to_string())
+            .collect();
+        found.push((
+            Found::ImplBounds {
+                type_name: ty.clone(),
+                clauses,
+            },
+            byte,
+            line,
+        ));
+        impls.push(ty.clone());
+    }
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk(child, bytes, impls, found);
+        }
+    }
+    if impl_type.is_some() {
+        impls.pop();
+    }
+}
+
+/// Type named by a path (`crate::store::Store<T>` -> `Store`), if it looks
+/// like a type (`Self` or capitalized).
+fn type_name_of(path: &str) -> Option<String> {
+    let path = path.split('<').next().unwrap_or(path);
+    let name = path.rsplit("::").next()?.trim();
+    name.starts_with(|c: char| c.is_uppercase())
+        .then(|| name.to_string())
+}
+
+/// `static mut` items and statics of a [`SINGLETON_TYPES`] cell.
+fn singleton_static(node: Node, bytes: &[u8]) -> Option<Found> {
+    let name = text(node.child_by_field_name("name")?, bytes).to_string();
+    let ty = text(node.child_by_field_name("type")?, bytes).to_string();
+    let is_mut = (0..node.child_count())
+        .filter_map(|i| node.child(i))
+        .any(|c| c.kind() == "mutable_specifier");
+    let is_cell = ty
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|word| SINGLETON_TYPES.contains(&word));
+    (is_mut || is_cell).then_some(Found::Singleton { name, ty })
+}
+
+/// `NAME.get_or_init(..)` and friends on an upper-case static.
+fn singleton_init(node: Node, bytes: &[u8]) -> Option<Found> {
+    let function = node
+        .child_by_field_name("function")
+        .filter(|f| f.kind() == "field_expression")?;
+    let method = text(function.child_by_field_name("field")?, bytes);
+    let value = function.child_by_field_name("value")?;
+    let name = text(value, bytes).rsplit("::").next()?.to_string();
+    let is_static_name = name
+        .chars()
+        .all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '_')
+        && name.chars().any(|c| c.is_uppercase());
+    (SINGLETON_INITS.contains(&method) && is_static_name).then_some(Found::SingletonInit { name })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_finds_trait_injection_and_singleton_seams() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            r#"
+use std::sync::OnceLock;
+
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+pub struct SystemClock {
+    offset: u64,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { offset: 0 }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        self.offset
+    }
+}
+
+pub trait Store {}
+pub struct Disk;
+pub struct Memory;
+impl Store for Disk {}
+impl Store for Memory {}
+
+pub struct Service<S> {
+    clock: Box<dyn Clock>,
+    store: S,
+}
+
+impl<S: Store> Service<S> {
+    pub fn new(clock: Box<dyn Clock>, store: S) -> Self {
+        Self { clock, store }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.clock.now()
+    }
+}
+
+static CONFIG: OnceLock<String> = OnceLock::new();
+static LIMIT: u32 = 10;
+
+pub fn start() -> Service<Disk> {
+    CONFIG.get_or_init(|| "default".to_string());
+    Service::new(Box::new(SystemClock::new()), Disk)
+}
+
+#[cfg(test)]
+mod tests {
+    struct FakeClock;
+    impl super::Clock for FakeClock {
+        fn now(&self) -> u64 {
+            1
+        }
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let report = SeamAnalyzer::new().analyze(&state, root);
+
+        let kinds: Vec<(SeamKind, &str)> = report
+            .seams
+            .iter()
+            .map(|s| (s.kind, s.name.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (SeamKind::SingleImplTrait, "crate::Clock"),
+                (SeamKind::InjectedDependency, "crate::Service::new"),
+                (SeamKind::InjectedDependency, "crate::Service::new"),
+                (SeamKind::GlobalSingleton, "crate::CONFIG"),
+            ]
+        );
+
+        let clock = &report.seams[0];
+        assert_eq!(clock.impls, vec!["SystemClock"]);
+        let sites: Vec<&str> = clock
+            .construction_sites
+            .iter()
+            .map(|s| s.symbol.as_str())
+            .collect();
+        assert_eq!(sites, vec!["crate::SystemClock::new", "crate::start"]);
+
+        let injected: Vec<(Option<&str>, Option<&str>)> = report.seams[1..3]
+            .iter()
+            .map(|s| (s.trait_name.as_deref(), s.detail.as_deref()))
+            .collect();
+        assert_eq!(
+            injected,
+            vec![
+                (Some("Clock"), Some("clock: Box<dyn Clock>")),
+                (Some("Store"), Some("store: S")),
+            ]
+        );
+        assert_eq!(report.seams[2].impls, vec!["Disk", "Memory"]);
+        assert_eq!(report.seams[1].construction_sites[0].symbol, "crate::start");
+
+        let config = &report.seams[3];
+        assert_eq!(config.detail.as_deref(), Some("OnceLock<String>"));
+        assert_eq!(config.construction_sites[0].symbol, "crate::start");
+    }
+}
@@ -0,0 +1,391 @@
+//! Inconsistent spellings across identifiers.
+//!
+//! Splits every indexed symbol name into words (`cancelledJobs` ->
+//! `cancelled`, `jobs`) and clusters words that are spellings of the same
+//! word: British and American variants (`initialise`/`initialize`,
+//! `colour`/`color`, `cancelled`/`canceled`, `centre`/`center`) and common
+//! misspellings (`recieve` for `receive`). A cluster is reported when the
+//! workspace uses two or more of its spellings, or any misspelling, with
+//! the spelling to standardize on: the correct one for misspellings, the
+//! most used one otherwise.
+//!
+//! Words can be allowlisted under `[spelling]` in `omni.toml` at the
+//! workspace root (`allow = ["initialise"]`); allowed spellings are left out
+//! of every cluster.
+
+use super::panics::relative;
+use crate::config::load_section;
+use crate::search::tokenize;
+use crate::state::OciState;
+use crate::types::SymbolKind;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+
+/// Identifiers listed per spelling; the rest are only counted.
+const MAX_EXAMPLES: usize = 3;
+
+/// Misspellings and the word they stand for.
+const MISSPELLINGS: &[(&str, &str)] = &[
+    ("accross", "across"),
+    ("adress", "address"),
+    ("arguement", "argument"),
+    ("begining", "beginning"),
+    ("calender", "calendar"),
+    ("comparision", "comparison"),
+    ("definately", "definitely"),
+    ("dependancy", "dependency"),
+    ("dependant", "dependent"),
+    ("enviroment", "environment"),
+    ("existant", "existent"),
+    ("heigth", "height"),
+    ("initalize", "initialize"),
+    ("intial", "initial"),
+    ("lenght", "length"),
+    ("occured", "occurred"),
+    ("occurence", "occurrence"),
+    ("paramater", "parameter"),
+    ("paramter", "parameter"),
+    ("persistant", "persistent"),
+    ("recieve", "receive"),
+    ("recieved", "received"),
+    ("reciever", "receiver"),
+    ("refered", "referred"),
+    ("responce", "response"),
+    ("retreive", "retrieve"),
+    ("seperate", "separate"),
+    ("seperator", "separator"),
+    ("similiar", "similar"),
+    ("sucess", "success"),
+    ("succesful", "successful"),
+    ("threshhold", "threshold"),
+    ("untill", "until"),
+    ("widht", "width"),
+];
+
+/// British spellings the suffix rules in [`canonical`] don't cover, and
+/// their American counterparts.
+const VARIANTS: &[(&str, &str)] = &[
+    ("analogue", "analog"),
+    ("catalogue", "catalog"),
+    ("defence", "defense"),
+    ("dialogue", "dialog"),
+    ("grey", "gray"),
+    ("licence", "license"),
+    ("offence", "offense"),
+    ("programme", "program"),
+];
+
+/// Stems whose final `l` British English doubles before `-ed`, `-ing`, and
+/// `-er` (`cancelled`, `labelling`).
+const DOUBLED_L: &[&str] = &[
+    "cancel", "channel", "dial", "fuel", "label", "level", "marshal", "model", "panel", "signal",
+    "total", "travel", "tunnel",
+];
+
+/// `[spelling]` table of `omni.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SpellingConfig {
+    /// Spellings never reported, compared case-insensitively
+    pub allow: Vec<String>,
+}
+
+impl SpellingConfig {
+    /// Read the `[spelling]` table of `omni.toml` under `root`; the default
+    /// if the file or the table is missing.
+    pub fn load(root: &Path) -> Result<Self> {
+        load_section(root, "spelling")
+    }
+}
+
+/// An identifier using a spelling.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SpellingExample {
+    pub identifier: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+}
+
+/// One spelling of a word and the identifiers using it.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SpellingVariant {
+    /// The word, lowercased
+    pub spelling: String,
+    /// Symbols whose name contains the word
+    pub count: usize,
+    /// Whether the spelling is a known misspelling
+    pub misspelled: bool,
+    /// First identifiers by file and line, at most three
+    pub examples: Vec<SpellingExample>,
+}
+
+/// Spellings of one word in use across the workspace.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SpellingCluster {
+    /// Spelling to standardize on
+    pub preferred: String,
+    /// Symbols using any spelling
+    pub total: usize,
+    /// Most used first
+    pub variants: Vec<SpellingVariant>,
+}
+
+/// Inconsistent spellings, most symbols affected first.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpellingReport {
+    pub clusters: Vec<SpellingCluster>,
+}
+
+/// Finds words spelled more than one way across identifiers.
+pub struct SpellingAnalyzer {
+    allow: HashSet<String>,
+}
+
+impl SpellingAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            allow: HashSet::new(),
+        }
+    }
+
+    /// Leave the config's allowlisted spellings out of the report.
+    pub fn with_config(mut self, config: &SpellingConfig) -> Self {
+        self.allow
+            .extend(config.allow.iter().map(|w| w.to_lowercase()));
+        self
+    }
+
+    pub fn analyze(&self, state: &OciState) -> SpellingReport {
+        // canonical word -> spelling -> (symbols, examples)
+        let mut words: BTreeMap<String, BTreeMap<String, Vec<SpellingExample>>> = BTreeMap::new();
+        for entry in state.symbols.iter() {
+            let symbol = entry.value();
+            if symbol.kind == SymbolKind::Impl {
+                continue;
+            }
+            let identifier = symbol.display_name(state);
+            let example = SpellingExample {
+                identifier: identifier.to_string(),
+                file: relative(&state.root_path, &symbol.location.file),
+                line: symbol.location.start_line,
+            };
+            let mut seen = BTreeSet::new();
+            for word in identifier_words(identifier) {
+                if self.allow.contains(&word) || !seen.insert(word.clone()) {
+                    continue;
+                }
+                let Some(key) = canonical(&word) else {
+                    continue;
+                };
+                words
+                    .entry(key)
+                    .or_default()
+                    .entry(word)
+                    .or_default()
+                    .push(example.clone());
+            }
+        }
+
+        let mut clusters: Vec<SpellingCluster> = words
+            .into_iter()
+            .filter_map(|(key, spellings)| cluster(key, spellings))
+            .collect();
+        clusters.sort_by(|a, b| {
+            b.total
+                .cmp(&a.total)
+                .then_with(|| a.preferred.cmp(&b.preferred))
+        });
+        SpellingReport { clusters }
+    }
+}
+
+impl Default for SpellingAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reportable cluster: two spellings in use, or one misspelling.
+fn cluster(
+    key: String,
+    spellings: BTreeMap<String, Vec<SpellingExample>>,
+) -> Option<SpellingCluster> {
+    let misspelled = |word: &str| MISSPELLINGS.iter().any(|(wrong, _)| *wrong == word);
+    if spellings.len() < 2 && !spellings.keys().any(|w| misspelled(w)) {
+        return None;
+    }
+    let mut variants: Vec<SpellingVariant> = spellings
+        .into_iter()
+        .map(|(spelling, mut examples)| {
+            examples.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+            let count = examples.len();
+            examples.truncate(MAX_EXAMPLES);
+            SpellingVariant {
+                misspelled: misspelled(&spelling),
+                spelling,
+                count,
+                examples,
+            }
+        })
+        .collect();
+    variants.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.spelling.cmp(&b.spelling))
+    });
+    let total = variants.iter().map(|v| v.count).sum();
+    let preferred = if variants.iter().any(|v| v.misspelled) {
+        key
+    } else {
+        variants[0].spelling.clone()
+    };
+    Some(SpellingCluster {
+        preferred,
+        total,
+        variants,
+    })
+}
+
+/// Lowercased alphabetic words of an identifier.
+fn identifier_words(identifier: &str) -> Vec<String> {
+    let mut parts: Vec<&str> = tokenize(identifier).collect();
+    if parts.len() > 1 {
+        // The identifier itself comes last
+        parts.pop();
+    }
+    parts
+        .into_iter()
+        .filter(|p| p.len() > 2 && p.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The American, correctly spelled form of `word`, if it has spellings to
+/// cluster; `None` for words with only one known spelling.
+fn canonical(word: &str) -> Option<String> {
+    if let Some((_, right)) = MISSPELLINGS.iter().find(|(wrong, _)| *wrong == word) {
+        return Some(right.to_string());
+    }
+    if let Some((_, american)) = VARIANTS
+        .iter()
+        .find(|(british, american)| *british == word || *american == word)
+    {
+        return Some(american.to_string());
+    }
+    for stem in DOUBLED_L {
+        if let Some(suffix) = word.strip_prefix(stem)
+            && let Some(suffix) = ["led", "ling", "ler", "lers", "ed", "ing", "er", "ers"]
+                .into_iter()
+                .find(|s| *s == suffix)
+        {
+            return Some(format!("{}{}", stem, suffix.trim_start_matches('l')));
+        }
+    }
+    // -ise/-isation and -yse endings; the stem must be long enough that the
+    // word isn't just `raise` or `wise`
+    const ENDINGS: &[(&str, &str)] = &[
+        ("isation", "ization"),
+        ("isations", "izations"),
+        ("ise", "ize"),
+        ("ised", "ized"),
+        ("ises", "izes"),
+        ("ising", "izing"),
+        ("iser", "izer"),
+        ("isers", "izers"),
+        ("yse", "yze"),
+        ("ysed", "yzed"),
+        ("yses", "yzes"),
+        ("ysing", "yzing"),
+        ("our", "or"),
+        ("ours", "ors"),
+        ("oured", "ored"),
+        ("ouring", "oring"),
+        ("tre", "ter"),
+        ("tres", "ters"),
+    ];
+    for (british, american) in ENDINGS {
+        for (from, to) in [(british, american), (american, british)] {
+            if let Some(stem) = word.strip_suffix(from)
+                && stem.len() >= 3
+            {
+                let american = if from == american {
+                    word.to_string()
+                } else {
+                    format!("{stem}{to}")
+                };
+                return Some(american);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[test]
+    fn test_canonical_spellings() {
+        assert_eq!(canonical("initialise").as_deref(), Some("initialize"));
+        assert_eq!(canonical("initialize").as_deref(), Some("initialize"));
+        assert_eq!(canonical("cancelled").as_deref(), Some("canceled"));
+        assert_eq!(canonical("canceled").as_deref(), Some("canceled"));
+        assert_eq!(canonical("behaviour").as_deref(), Some("behavior"));
+        assert_eq!(canonical("centre").as_deref(), Some("center"));
+        assert_eq!(canonical("colour").as_deref(), Some("color"));
+        assert_eq!(canonical("recieve").as_deref(), Some("receive"));
+        assert_eq!(canonical("licence").as_deref(), Some("license"));
+        assert_eq!(canonical("raise"), None);
+        assert_eq!(canonical("filled"), None);
+        assert_eq!(
+            identifier_words("cancelledJobs_count"),
+            vec!["cancelled", "jobs", "count"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clusters_inconsistent_spellings() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub fn is_cancelled() {}\npub fn mark_canceled() {}\npub fn was_canceled() {}\n\
+             pub fn initialize() {}\npub fn recieve_frame() {}\n",
+        )
+        .unwrap();
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let report = SpellingAnalyzer::new().analyze(&state);
+        let preferred: Vec<&str> = report
+            .clusters
+            .iter()
+            .map(|c| c.preferred.as_str())
+            .collect();
+        assert_eq!(preferred, vec!["canceled", "receive"]);
+        let cancel = &report.clusters[0];
+        assert_eq!(cancel.total, 3);
+        assert_eq!(cancel.variants[0].spelling, "canceled");
+        assert_eq!(cancel.variants[0].count, 2);
+        assert_eq!(cancel.variants[1].examples[0].identifier, "is_cancelled");
+        assert!(report.clusters[1].variants[0].misspelled);
+
+        std::fs::write(
+            root.join(crate::config::CONFIG_FILE),
+            "[spelling]\nallow = [\"Cancelled\", \"recieve\"]\n",
+        )
+        .unwrap();
+        let config = SpellingConfig::load(&root).unwrap();
+        let report = SpellingAnalyzer::new().with_config(&config).analyze(&state);
+        assert!(report.clusters.is_empty());
+    }
+}
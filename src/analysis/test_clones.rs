@@ -0,0 +1,473 @@
+//! Copy-pasted test setup detection.
+//!
+//! Tests repeat the same arrange steps (create a temp dir, write fixture
+//! files, build an index) far more often than production code repeats logic.
+//! This analysis takes the setup prefix of every test, i.e. the statements
+//! before its first assertion, clusters near-identical prefixes, and suggests
+//! a shared fixture or helper for each cluster.
+//!
+//! It only looks at tests and has its own [`TestCloneConfig`], so it can be
+//! tuned or skipped without affecting production-code findings. Tests
+//! annotated `omni:ignore duplication` are left out.
+
+use crate::state::OciState;
+use crate::types::{IGNORE_DUPLICATION, ModuleRole, SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Tokens per shingle when comparing setup blocks.
+const SHINGLE_SIZE: usize = 4;
+
+/// Maximum number of shared setup lines echoed per group.
+const MAX_SHARED_LINES: usize = 10;
+
+/// Thresholds for test clone detection.
+#[derive(Debug, Clone)]
+pub struct TestCloneConfig {
+    /// Setup blocks shorter than this (in statements) are ignored
+    pub min_setup_lines: usize,
+    /// Minimum Jaccard similarity of token shingles to link two tests
+    pub min_similarity: f32,
+    /// Minimum number of tests in a reported group
+    pub min_group_size: usize,
+    /// Include tests annotated `omni:ignore duplication` (for audits)
+    pub include_ignored: bool,
+}
+
+impl Default for TestCloneConfig {
+    fn default() -> Self {
+        Self {
+            min_setup_lines: 3,
+            min_similarity: 0.8,
+            min_group_size: 2,
+            include_ignored: false,
+        }
+    }
+}
+
+/// A test whose setup belongs to a clone group.
+#[derive(Debug, Clone, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct TestSetup {
+    /// Scoped name of the test
+    pub test: String,
+    /// File path relative to the repository root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    /// Number of setup statements before the first assertion
+    pub setup_lines: usize,
+}
+
+/// Tests with near-identical setup.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TestCloneGroup {
+    pub tests: Vec<TestSetup>,
+    /// Lowest similarity among the links that formed the group
+    pub similarity: f32,
+    /// Setup statements common to every test in the group
+    pub shared_setup: Vec<String>,
+    pub suggestion: String,
+}
+
+/// Finds tests with copy-pasted setup.
+pub struct TestCloneAnalyzer {
+    config: TestCloneConfig,
+}
+
+/// A test's setup prefix, prepared for comparison.
+struct Candidate {
+    setup: TestSetup,
+    name: String,
+    /// Original (trimmed) setup statements
+    lines: Vec<String>,
+    /// Setup statements with literals normalized
+    normalized: Vec<String>,
+    shingles: HashSet<u64>,
+}
+
+impl TestCloneAnalyzer {
+    /// Creates an analyzer with default thresholds.
+    pub fn new() -> Self {
+        Self {
+            config: TestCloneConfig::default(),
+        }
+    }
+
+    /// Use custom thresholds.
+    pub fn with_config(mut self, config: TestCloneConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Cluster tests by setup similarity.
+    ///
+    /// Groups are sorted by the amount of duplicated setup, largest first.
+    pub fn analyze(&self, state: &OciState, root: &Path) -> Vec<TestCloneGroup> {
+        let candidates = self.collect(state, root);
+
+        // Union-find over pairs above the threshold
+        let mut parent: Vec<usize> = (0..candidates.len()).collect();
+        let mut link_similarity = vec![1.0f32; candidates.len()];
+        for i in 0..candidates.len() {
+            for j in i + 1..candidates.len() {
+                let (a, b) = (&candidates[i].shingles, &candidates[j].shingles);
+                // Jaccard is bounded by the size ratio
+                let bound = a.len().min(b.len()) as f32 / a.len().max(b.len()) as f32;
+                if bound < self.config.min_similarity {
+                    continue;
+                }
+                let similarity = jaccard(a, b);
+                if similarity < self.config.min_similarity {
+                    continue;
+                }
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                let lowest = similarity.min(link_similarity[ri]).min(link_similarity[rj]);
+                if ri != rj {
+                    parent[rj] = ri;
+                }
+                link_similarity[ri] = lowest;
+            }
+        }
+
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..candidates.len() {
+            let root = find(&mut parent, i);
+            members.entry(root).or_default().push(i);
+        }
+
+        let mut groups: Vec<TestCloneGroup> = members
+            .into_iter()
+            .filter(|(_, ids)| ids.len() >= self.config.min_group_size.max(2))
+            .map(|(root, ids)| {
+                let group: Vec<&Candidate> = ids.iter().map(|&i| &candidates[i]).collect();
+                TestCloneGroup {
+                    tests: group.iter().map(|c| c.setup.clone()).collect(),
+                    similarity: link_similarity[root],
+                    shared_setup: shared_lines(&group),
+                    suggestion: suggestion(&group),
+                }
+            })
+            .collect();
+
+        groups.sort_by(|a, b| {
+            let weight = |g: &TestCloneGroup| g.tests.len() * g.shared_setup.len().max(1);
+            weight(b)
+                .cmp(&weight(a))
+                .then_with(|| a.tests[0].file.cmp(&b.tests[0].file))
+                .then_with(|| a.tests[0].line.cmp(&b.tests[0].line))
+        });
+        groups
+    }
+
+    /// Setup prefixes of every indexed test, in file order.
+    fn collect(&self, state: &OciState, root: &Path) -> Vec<Candidate> {
+        let mut files: Vec<PathBuf> = state.file_ids.iter().map(|e| e.key().clone()).collect();
+        files.sort();
+
+        let mut candidates = Vec::new();
+        for file in &files {
+            let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+                continue;
+            };
+            let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+                continue;
+            };
+            let in_test_file = state.file_role(file) == ModuleRole::Tests;
+            let mut tests: Vec<SymbolDef> = scoped_names
+                .into_iter()
+                .filter_map(|scoped| state.get_symbol(scoped))
+                // Same-named symbols in other files overwrite each other
+                .filter(|s| s.location.file == *file && is_test(s, in_test_file))
+                .filter(|s| self.config.include_ignored || !s.is_ignored(IGNORE_DUPLICATION))
+                .collect();
+            if tests.is_empty() {
+                continue;
+            }
+            tests.sort_by_key(|s| s.location.start_byte);
+            let Ok(contents) = std::fs::read_to_string(file) else {
+                continue;
+            };
+            let rel = file
+                .strip_prefix(root)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            for symbol in tests {
+                let Some(body) = contents.get(symbol.location.start_byte..symbol.location.end_byte)
+                else {
+                    continue;
+                };
+                let lines = setup_lines(body);
+                if lines.len() < self.config.min_setup_lines {
+                    continue;
+                }
+                let normalized: Vec<String> = lines.iter().map(|l| normalize(l)).collect();
+                candidates.push(Candidate {
+                    setup: TestSetup {
+                        test: state.resolve(symbol.scoped_name).to_string(),
+                        file: rel.clone(),
+                        line: symbol.location.start_line,
+                        setup_lines: lines.len(),
+                    },
+                    name: state.resolve(symbol.name).to_string(),
+                    shingles: shingles(&normalized),
+                    lines,
+                    normalized,
+                });
+            }
+        }
+        candidates
+    }
+}
+
+impl Default for TestCloneAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Test functions: annotated as tests, or any function in a test file.
+fn is_test(symbol: &SymbolDef, in_test_file: bool) -> bool {
+    matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+        && (in_test_file || symbol.is_test())
+}
+
+/// Statements of a function body up to its first assertion.
+fn setup_lines(source: &str) -> Vec<String> {
+    let Some(open) = source.find('{') else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    for line in source[open + 1..].lines() {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with("//")
+            || line.starts_with("/*")
+            || line.starts_with('*')
+            || line.chars().all(|c| "{}()[];,".contains(c))
+        {
+            continue;
+        }
+        if is_assertion(line) {
+            break;
+        }
+        lines.push(line.to_string());
+    }
+    lines
+}
+
+fn is_assertion(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    lower.contains("assert")
+        || lower.contains("expect(")
+        || lower.contains("expect!")
+        || lower.contains(".should")
+        || lower.starts_with("verify(")
+}
+
+/// Replace string and numeric literals so fixtures differing only in data
+/// still match, and drop whitespace differences.
+fn normalize(line: &str) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            let mut escaped = false;
+            for next in chars.by_ref() {
+                if escaped {
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == c {
+                    break;
+                }
+            }
+            tokens.push("\"_\"".to_string());
+        } else if c.is_ascii_digit() {
+            while chars
+                .peek()
+                .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.')
+            {
+                chars.next();
+            }
+            tokens.push("0".to_string());
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut ident = c.to_string();
+            while let Some(&n) = chars.peek() {
+                if !(n.is_alphanumeric() || n == '_') {
+                    break;
+                }
+                ident.push(n);
+                chars.next();
+            }
+            tokens.push(ident);
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens.join(" ")
+}
+
+fn shingles(lines: &[String]) -> HashSet<u64> {
+    let tokens: Vec<&str> = lines.iter().flat_map(|l| l.split(' ')).collect();
+    tokens
+        .windows(SHINGLE_SIZE.min(tokens.len()).max(1))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+pub(super) fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+pub(super) fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Setup statements of the first test that every other test also has.
+fn shared_lines(group: &[&Candidate]) -> Vec<String> {
+    let first = group[0];
+    first
+        .lines
+        .iter()
+        .zip(&first.normalized)
+        .filter(|(_, norm)| group[1..].iter().all(|c| c.normalized.contains(norm)))
+        .map(|(line, _)| line.clone())
+        .take(MAX_SHARED_LINES)
+        .collect()
+}
+
+/// Suggest a helper named after the tests' common name prefix.
+fn suggestion(group: &[&Candidate]) -> String {
+    let words = |name: &str| -> Vec<String> {
+        name.trim_start_matches("test")
+            .split('_')
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut prefix = words(&group[0].name);
+    for c in &group[1..] {
+        let other = words(&c.name);
+        let common = prefix
+            .iter()
+            .zip(&other)
+            .take_while(|(a, b)| a == b)
+            .count();
+        prefix.truncate(common);
+    }
+    let helper = if prefix.is_empty() {
+        "setup".to_string()
+    } else {
+        format!("setup_{}", prefix.join("_"))
+    };
+
+    let first_file = &group[0].setup.file;
+    let place = if group.iter().all(|c| &c.setup.file == first_file) {
+        format!("in {}", first_file)
+    } else if group.iter().all(|c| c.setup.file.starts_with("tests/")) {
+        "in tests/common/mod.rs".to_string()
+    } else {
+        "in a shared test support module".to_string()
+    };
+    format!(
+        "{} tests repeat the same setup; extract it into a shared fixture `{}()` {}",
+        group.len(),
+        helper,
+        place
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    const SETUP: &str = r#"    let temp = tempfile::tempdir().unwrap();
+    let root = temp.path().join("{name}");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("lib.rs"), "pub fn {name}() {}").unwrap();
+    let state = create_state(root.clone());
+"#;
+
+    fn test_fn(name: &str, extra: &str) -> String {
+        format!(
+            "#[test]\nfn {name}() {{\n{}{extra}    assert!(state.is_empty());\n}}\n\n",
+            SETUP.replace("{name}", name)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_groups_tests_with_shared_setup() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let mut suite = test_fn("test_index_basic", "");
+        suite.push_str(&test_fn("test_index_removed", "    state.reset();\n"));
+        suite.push_str(
+            "#[test]\nfn test_unrelated() {\n    let a = 1;\n    let b = 2;\n    let c = a + b;\n    assert_eq!(c, 3);\n}\n",
+        );
+        std::fs::write(root.join("tests/index.rs"), &suite).unwrap();
+        std::fs::write(root.join("tests/other.rs"), test_fn("test_index_other", "")).unwrap();
+        // Production code with the same statements is not a test clone
+        std::fs::write(
+            root.join("src/lib.rs"),
+            format!("pub fn build() {{\n{}}}\n", SETUP.replace("{name}", "x")),
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let groups = TestCloneAnalyzer::new().analyze(&state, root);
+        assert_eq!(groups.len(), 1, "{:?}", groups);
+        let group = &groups[0];
+        let mut tests: Vec<&str> = group.tests.iter().map(|t| t.test.as_str()).collect();
+        tests.sort();
+        assert_eq!(tests.len(), 3);
+        assert!(tests.iter().all(|t| t.contains("test_index_")));
+        assert!(group.similarity >= 0.8);
+        assert_eq!(group.shared_setup.len(), 5);
+        assert!(group.suggestion.contains("`setup_index()`"));
+        assert!(group.suggestion.contains("tests/common/mod.rs"));
+
+        let strict = TestCloneAnalyzer::new().with_config(TestCloneConfig {
+            min_group_size: 4,
+            ..TestCloneConfig::default()
+        });
+        assert!(strict.analyze(&state, root).is_empty());
+    }
+
+    #[test]
+    fn test_setup_stops_at_first_assertion() {
+        let body = "fn t() {\n    // arrange\n    let x = make(\"a\", 1);\n\n    let y = x.run();\n    assert_eq!(y, 2);\n    let z = 3;\n}";
+        assert_eq!(
+            setup_lines(body),
+            vec!["let x = make(\"a\", 1);", "let y = x.run();"]
+        );
+        assert_eq!(
+            normalize("let x = make(\"a\", 1);"),
+            normalize("let  x = make('b', 22);")
+        );
+    }
+}
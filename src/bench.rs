@@ -0,0 +1,393 @@
+//! Synthetic workload for `omni bench`.
+//!
+//! [`write_fixture`] generates the same crate the criterion benches index
+//! (`benches/comparative.rs`), and [`run`] times a cold index, a no-change
+//! re-index, and a batch of BM25 queries against it. The numbers are meant
+//! to be comparable across machines and configurations, so bug reports and
+//! tuning experiments (`TOKIO_WORKER_THREADS`, feature sets) can quote them.
+
+use crate::budget::Budget;
+use crate::error::{OmniError, Result};
+use crate::incremental::{IncrementalIndexer, IndexOptions};
+use crate::query::{QueryFilters, require_search_index, search_with_fallback};
+use crate::state::create_state;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Queries cycled through by the query benchmark.
+const QUERIES: &[&str] = &[
+    "helper_fn",
+    "process data",
+    "transform",
+    "get value",
+    "Module1State",
+    "new state",
+    "counter",
+    "call_next",
+];
+
+/// Results requested per query.
+const QUERY_LIMIT: usize = 10;
+
+/// Workload size.
+#[derive(Debug, Clone, Copy)]
+pub struct BenchOptions {
+    /// Generated module files
+    pub files: usize,
+    /// Queries run after indexing
+    pub queries: usize,
+}
+
+impl BenchOptions {
+    /// A few seconds on a laptop.
+    pub fn quick() -> Self {
+        Self {
+            files: 100,
+            queries: 200,
+        }
+    }
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            files: 1000,
+            queries: 1000,
+        }
+    }
+}
+
+/// Throughput numbers from one [`run`].
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct BenchReport {
+    pub files: usize,
+    pub bytes: u64,
+    pub symbols: usize,
+    /// Threads available to the indexer (`TOKIO_WORKER_THREADS` or the CPU
+    /// count)
+    pub threads: usize,
+    pub version: String,
+    /// Cargo features compiled in
+    pub features: Vec<String>,
+    pub index_ms: f64,
+    pub files_per_sec: f64,
+    pub mb_per_sec: f64,
+    pub symbols_per_sec: f64,
+    /// No-change re-index (fingerprint checks only)
+    pub reindex_ms: f64,
+    pub queries: usize,
+    pub queries_per_sec: f64,
+    pub query_p50_us: f64,
+    pub query_p95_us: f64,
+}
+
+/// Write the benchmark crate (`src/lib.rs` and `files` modules) under `dir`.
+pub fn write_fixture(dir: &Path, files: usize) -> Result<()> {
+    let src = dir.join("src");
+    fs::create_dir_all(&src).map_err(|e| OmniError::io(&src, e))?;
+
+    let mut lib = String::from("//! Benchmark fixture\n\n");
+    for i in 0..files {
+        lib.push_str(&format!("pub mod module_{};\n", i));
+    }
+    let path = src.join("lib.rs");
+    fs::write(&path, lib).map_err(|e| OmniError::io(&path, e))?;
+
+    for i in 0..files {
+        let path = src.join(format!("module_{}.rs", i));
+        fs::write(&path, generate_module_content(i, files)).map_err(|e| OmniError::io(&path, e))?;
+    }
+    Ok(())
+}
+
+/// Generate module content with imports, structs, impl blocks, functions, calls, and tests.
+/// Mirrors what code-index benchmarks use.
+pub fn generate_module_content(module_idx: usize, total_modules: usize) -> String {
+    let mut code = String::with_capacity(4000);
+
+    // Module doc
+    code.push_str(&format!(
+        "//! Module {} for benchmark testing.\n\n",
+        module_idx
+    ));
+
+    // Imports (some cross-module)
+    code.push_str("use std::collections::HashMap;\n");
+    if module_idx > 0 {
+        code.push_str(&format!(
+            "use crate::module_{}::helper_fn;\n",
+            module_idx - 1
+        ));
+    }
+    code.push('\n');
+
+    // Struct with impl
+    code.push_str(&format!(
+        r#"/// Main struct for module {idx}.
+#[derive(Debug, Clone)]
+pub struct Module{idx}State {{
+    pub data: HashMap<String, i32>,
+    pub counter: usize,
+}}
+
+impl Module{idx}State {{
+    /// Create new state.
+    pub fn new() -> Self {{
+        Self {{
+            data: HashMap::new(),
+            counter: 0,
+        }}
+    }}
+
+    /// Process some data.
+    pub fn process(&mut self, key: &str, value: i32) -> i32 {{
+        self.counter += 1;
+        self.data.insert(key.to_string(), value);
+        helper_fn(value)
+    }}
+
+    /// Get a value.
+    pub fn get(&self, key: &str) -> Option<i32> {{
+        self.data.get(key).copied()
+    }}
+}}
+
+"#,
+        idx = module_idx
+    ));
+
+    // Helper function (called by process)
+    code.push_str(&format!(
+        r#"/// Helper function for module {idx}.
+pub fn helper_fn(x: i32) -> i32 {{
+    transform(x) * 2
+}}
+
+/// Transform function.
+fn transform(x: i32) -> i32 {{
+    x.saturating_add(1)
+}}
+
+"#,
+        idx = module_idx
+    ));
+
+    // Cross-module call (if not first module)
+    if module_idx < total_modules - 1 {
+        code.push_str(&format!(
+            r#"/// Cross-module function.
+pub fn call_next() -> i32 {{
+    // Would call module_{next}::helper_fn in real code
+    42
+}}
+
+"#,
+            next = module_idx + 1
+        ));
+    }
+
+    // Test functions
+    code.push_str(&format!(
+        r#"#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn test_new() {{
+        let state = Module{idx}State::new();
+        assert_eq!(state.counter, 0);
+    }}
+
+    #[test]
+    fn test_process() {{
+        let mut state = Module{idx}State::new();
+        let result = state.process("key", 42);
+        assert!(result > 0);
+    }}
+
+    #[test]
+    fn test_get() {{
+        let mut state = Module{idx}State::new();
+        state.process("key", 42);
+        assert_eq!(state.get("key"), Some(42));
+    }}
+
+    #[test]
+    fn test_helper() {{
+        assert_eq!(helper_fn(10), 22);
+    }}
+}}
+"#,
+        idx = module_idx
+    ));
+
+    code
+}
+
+/// Generate a fixture in a temporary directory and benchmark it. The
+/// directory is removed afterwards.
+pub async fn run(options: BenchOptions) -> Result<BenchReport> {
+    let dir = ScratchDir::new()?;
+    let root = dir.0.as_path();
+    write_fixture(root, options.files)?;
+    let bytes = source_bytes(&root.join("src"))?;
+
+    let indexer = IncrementalIndexer::new();
+    let state = create_state(root.to_path_buf());
+    let start = Instant::now();
+    let report = indexer
+        .index(&state, root, &IndexOptions::default())
+        .await?;
+    let index_time = start.elapsed();
+    let symbols = state.symbols.len();
+
+    let start = Instant::now();
+    indexer
+        .index(
+            &create_state(root.to_path_buf()),
+            root,
+            &IndexOptions::default(),
+        )
+        .await?;
+    let reindex_time = start.elapsed();
+
+    let index = require_search_index(root)?;
+    let filters = QueryFilters::default();
+    let mut latencies = Vec::with_capacity(options.queries);
+    let start = Instant::now();
+    for query in QUERIES.iter().cycle().take(options.queries) {
+        let query_start = Instant::now();
+        search_with_fallback(
+            &index,
+            query,
+            QUERY_LIMIT,
+            &filters,
+            None,
+            &Budget::unlimited(),
+        );
+        latencies.push(query_start.elapsed());
+    }
+    let query_time = start.elapsed();
+    latencies.sort();
+
+    let index_secs = index_time.as_secs_f64().max(f64::EPSILON);
+    Ok(BenchReport {
+        files: report.total_files,
+        bytes,
+        symbols,
+        threads: worker_threads(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features: enabled_features(),
+        index_ms: millis(index_time),
+        files_per_sec: report.total_files as f64 / index_secs,
+        mb_per_sec: bytes as f64 / (1024.0 * 1024.0) / index_secs,
+        symbols_per_sec: symbols as f64 / index_secs,
+        reindex_ms: millis(reindex_time),
+        queries: latencies.len(),
+        queries_per_sec: latencies.len() as f64 / query_time.as_secs_f64().max(f64::EPSILON),
+        query_p50_us: micros(percentile(&latencies, 0.50)),
+        query_p95_us: micros(percentile(&latencies, 0.95)),
+    })
+}
+
+/// A temporary directory removed on drop.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> Result<Self> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let path =
+            std::env::temp_dir().join(format!("omni-bench-{}-{}", std::process::id(), nanos));
+        fs::create_dir_all(&path).map_err(|e| OmniError::io(&path, e))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn source_bytes(src: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(src).map_err(|e| OmniError::io(src, e))? {
+        let entry = entry.map_err(|e| OmniError::io(src, e))?;
+        total += entry
+            .metadata()
+            .map_err(|e| OmniError::io(&entry.path(), e))?
+            .len();
+    }
+    Ok(total)
+}
+
+fn worker_threads() -> usize {
+    std::env::var("TOKIO_WORKER_THREADS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
+fn enabled_features() -> Vec<String> {
+    [
+        ("mcp", cfg!(feature = "mcp")),
+        ("semantic", cfg!(feature = "semantic")),
+        ("analysis", cfg!(feature = "analysis")),
+        ("context", cfg!(feature = "context")),
+        ("intervention", cfg!(feature = "intervention")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+/// Nearest-rank percentile of sorted durations.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn micros(d: Duration) -> f64 {
+    d.as_secs_f64() * 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_indexes_and_queries_fixture() {
+        let report = run(BenchOptions {
+            files: 3,
+            queries: 16,
+        })
+        .await
+        .unwrap();
+        assert_eq!(report.files, 4);
+        assert!(report.symbols > 3 * 5);
+        assert!(report.bytes > 0);
+        assert_eq!(report.queries, 16);
+        assert!(report.query_p50_us <= report.query_p95_us);
+    }
+
+    #[test]
+    fn test_percentile_is_nearest_rank() {
+        let sorted: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 0.5), Duration::from_millis(10));
+        assert_eq!(percentile(&sorted, 0.95), Duration::from_millis(19));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+}
@@ -0,0 +1,186 @@
+//! Per-branch index snapshots, so switching branches is nearly free.
+//!
+//! The manifest records the git branch it was built on. When an index runs
+//! on another branch, the outgoing branch's manifest and search docs are
+//! copied to `.omni/branches/<branch>/`, and the incoming branch's snapshot,
+//! if one exists, is loaded as a sibling cache: a file that differs from the
+//! current cache but has the content hash the snapshot recorded takes its
+//! docs from the snapshot instead of being re-parsed. Going back and forth
+//! between two branches then only parses files edited since the last visit.
+//!
+//! At most [`MAX_BRANCH_SNAPSHOTS`] snapshots are kept, least recently
+//! written first to go. Snapshots from another schema version are ignored,
+//! and `omni index --force` clears them with the rest of `.omni/`.
+
+use crate::cache::{self, IndexManifest};
+use crate::error::{OmniError, Result};
+use crate::query::SearchDoc;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Branch snapshots kept under `.omni/branches/`.
+pub const MAX_BRANCH_SNAPSHOTS: usize = 8;
+
+/// The persisted index of another branch.
+#[derive(Debug, Clone)]
+pub struct BranchSnapshot {
+    pub manifest: IndexManifest,
+    docs_by_file: HashMap<String, Vec<SearchDoc>>,
+}
+
+impl BranchSnapshot {
+    /// Docs of the root-relative `file`, if the snapshot indexed it with
+    /// this content hash.
+    pub fn docs_for(&self, file: &str, content_hash: u64) -> Option<&[SearchDoc]> {
+        let fingerprint = self.manifest.files.get(file)?;
+        if content_hash == 0 || fingerprint.content_hash != content_hash {
+            return None;
+        }
+        Some(self.docs_by_file.get(file).map_or(&[], Vec::as_slice))
+    }
+}
+
+/// Branch checked out in the git repository containing `root`; `None`
+/// outside a repository or on a detached HEAD.
+pub fn current_branch(root: &Path) -> Option<String> {
+    let dot_git = root
+        .ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|path| path.exists())?;
+    // Worktrees and submodules have a `.git` file pointing at the git dir
+    let git_dir = if dot_git.is_file() {
+        let link = fs::read_to_string(&dot_git).ok()?;
+        let dir = PathBuf::from(link.trim().strip_prefix("gitdir:")?.trim());
+        if dir.is_relative() {
+            dot_git.parent()?.join(dir)
+        } else {
+            dir
+        }
+    } else {
+        dot_git
+    };
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(str::to_string)
+}
+
+/// Copy the current manifest and search docs to `branch`'s snapshot, and
+/// drop the oldest snapshots beyond [`MAX_BRANCH_SNAPSHOTS`].
+pub fn save_snapshot(root: &Path, branch: &str) -> Result<()> {
+    let manifest_path = cache::manifest_path(root);
+    let state_path = cache::state_path(root);
+    if !manifest_path.exists() || !state_path.exists() {
+        return Ok(());
+    }
+    let dir = snapshot_dir(root, branch);
+    fs::create_dir_all(&dir).map_err(|e| OmniError::io(&dir, e))?;
+    // Docs first, so a snapshot with a manifest is always complete
+    let docs = dir.join(cache::STATE_FILE);
+    fs::copy(&state_path, &docs).map_err(|e| OmniError::io(&docs, e))?;
+    let manifest = dir.join(cache::MANIFEST_FILE);
+    fs::copy(&manifest_path, &manifest).map_err(|e| OmniError::io(&manifest, e))?;
+    prune_snapshots(root)
+}
+
+/// Load `branch`'s snapshot, if there is a usable one.
+pub fn load_snapshot(root: &Path, branch: &str) -> Result<Option<BranchSnapshot>> {
+    let dir = snapshot_dir(root, branch);
+    let Some(manifest) = cache::load_manifest_from(&dir.join(cache::MANIFEST_FILE))? else {
+        return Ok(None);
+    };
+    if manifest.schema_version != crate::migrations::SCHEMA_VERSION
+        || manifest.branch.as_deref() != Some(branch)
+    {
+        return Ok(None);
+    }
+    let Some(state) = crate::query::load_search_state_from(&dir.join(cache::STATE_FILE))? else {
+        return Ok(None);
+    };
+    let mut docs_by_file: HashMap<String, Vec<SearchDoc>> = HashMap::new();
+    for doc in state.docs {
+        docs_by_file.entry(doc.file.clone()).or_default().push(doc);
+    }
+    Ok(Some(BranchSnapshot {
+        manifest,
+        docs_by_file,
+    }))
+}
+
+/// Snapshot directory of `branch`, with the name percent-encoded so every
+/// branch (`feature/x`, `..`) maps to one plain directory.
+fn snapshot_dir(root: &Path, branch: &str) -> PathBuf {
+    let name: String = branch
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (b as char).to_string(),
+            b => format!("%{:02X}", b),
+        })
+        .collect();
+    cache::branches_dir(root).join(name)
+}
+
+fn prune_snapshots(root: &Path) -> Result<()> {
+    let dir = cache::branches_dir(root);
+    let entries = fs::read_dir(&dir).map_err(|e| OmniError::io(&dir, e))?;
+    let mut snapshots: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .map(|path| {
+            let written = fs::metadata(path.join(cache::MANIFEST_FILE))
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (written, path)
+        })
+        .collect();
+    snapshots.sort_by_key(|(written, _)| std::cmp::Reverse(*written));
+    for (_, path) in snapshots.into_iter().skip(MAX_BRANCH_SNAPSHOTS) {
+        fs::remove_dir_all(&path).map_err(|e| OmniError::io(&path, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_branch_reads_head_and_worktree_links() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/feature/cache\n").unwrap();
+        assert_eq!(
+            current_branch(&repo.join("src")).as_deref(),
+            Some("feature/cache")
+        );
+
+        let worktree = temp.path().join("worktree");
+        fs::create_dir_all(repo.join(".git/worktrees/wt")).unwrap();
+        fs::write(
+            repo.join(".git/worktrees/wt/HEAD"),
+            "ref: refs/heads/main\n",
+        )
+        .unwrap();
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: ../repo/.git/worktrees/wt\n").unwrap();
+        assert_eq!(current_branch(&worktree).as_deref(), Some("main"));
+
+        fs::write(
+            repo.join(".git/HEAD"),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904\n",
+        )
+        .unwrap();
+        assert_eq!(current_branch(&repo), None);
+    }
+
+    #[test]
+    fn test_snapshot_names_stay_inside_the_cache() {
+        let root = Path::new("/workspace");
+        let dir = snapshot_dir(root, "../feature/x");
+        assert_eq!(dir, cache::branches_dir(root).join("%2E%2E%2Ffeature%2Fx"));
+    }
+}
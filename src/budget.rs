@@ -0,0 +1,87 @@
+//! Per-request time and work limits.
+//!
+//! Expensive operations (search, context synthesis, analyses) take a
+//! [`Budget`] and charge it as they scan candidates. Once it runs out they
+//! stop and return what they have, flagged as truncated, instead of running
+//! unbounded on a huge workspace.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// A deadline and/or a cap on units of work (e.g. candidates scanned).
+#[derive(Debug, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+    max_work: Option<usize>,
+    used: AtomicUsize,
+    refused: AtomicBool,
+}
+
+impl Budget {
+    /// A budget starting now. `None` leaves that dimension unlimited.
+    pub fn new(timeout: Option<Duration>, max_work: Option<usize>) -> Self {
+        Self {
+            deadline: timeout.map(|t| Instant::now() + t),
+            max_work,
+            used: AtomicUsize::new(0),
+            refused: AtomicBool::new(false),
+        }
+    }
+
+    /// A budget that never runs out.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Spend `units` of work; returns `false` if the budget was already
+    /// exhausted, in which case the work should not be done.
+    pub fn charge(&self, units: usize) -> bool {
+        if self.is_exhausted() {
+            self.refused.store(true, Ordering::Relaxed);
+            return false;
+        }
+        self.used.fetch_add(units, Ordering::Relaxed);
+        true
+    }
+
+    /// Whether the deadline passed or the work cap was reached.
+    pub fn is_exhausted(&self) -> bool {
+        self.max_work
+            .is_some_and(|max| self.used.load(Ordering::Relaxed) >= max)
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Units of work charged so far.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Whether a charge was refused, i.e. some work was skipped. Unlike
+    /// [`is_exhausted`](Self::is_exhausted), false for work that finished
+    /// just as the budget ran out.
+    pub fn was_cut_short(&self) -> bool {
+        self.refused.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_and_time_limits() {
+        let budget = Budget::new(None, Some(2));
+        assert!(budget.charge(1));
+        assert!(budget.charge(1));
+        assert!(budget.is_exhausted());
+        assert!(!budget.was_cut_short());
+        assert!(!budget.charge(1));
+        assert!(budget.was_cut_short());
+        assert_eq!(budget.used(), 2);
+
+        assert!(Budget::new(Some(Duration::ZERO), None).is_exhausted());
+        let unlimited = Budget::unlimited();
+        assert!(unlimited.charge(usize::MAX / 2));
+        assert!(!unlimited.is_exhausted());
+    }
+}
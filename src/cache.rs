@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use crate::error::{OmniError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -8,17 +8,52 @@ pub const CACHE_DIR: &str = ".omni";
 pub const MANIFEST_FILE: &str = "manifest.json";
 pub const STATE_FILE: &str = "state.bin";
 pub const BM25_FILE: &str = "bm25.bin";
+pub const OCI_FILE: &str = "oci.bin";
+pub const RENAMES_FILE: &str = "renames.json";
+pub const TOMBSTONES_FILE: &str = "tombstones.json";
+pub const POPULARITY_FILE: &str = "popularity.json";
+pub const FOCUS_FILE: &str = "focus.json";
+pub const BRANCHES_DIR: &str = "branches";
+pub const WRITER_LOCK_FILE: &str = "writer.lock";
+pub const EMBEDDINGS_DIR: &str = "embeddings";
+pub const DUPLICATION_FILE: &str = "duplication.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FileFingerprint {
     pub mtime_ms: u64,
     pub size_bytes: u64,
+    /// [`content_hash`] of the file; 0 if unknown (older manifests)
+    #[serde(default)]
+    pub content_hash: u64,
+}
+
+impl FileFingerprint {
+    /// Whether mtime and size match, i.e. the file was not touched.
+    pub fn same_metadata(&self, other: &Self) -> bool {
+        self.mtime_ms == other.mtime_ms && self.size_bytes == other.size_bytes
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of file contents.
+///
+/// Persisted in the manifest, so it must not depend on the Rust version
+/// (unlike `DefaultHasher`).
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct IndexManifest {
+    /// Persisted format version; absent in pre-migration caches (v0)
+    #[serde(default)]
+    pub schema_version: u32,
     pub tool_version: String,
     pub root: Option<String>,
+    /// Git branch the index was last built on (see [`crate::branch_cache`])
+    #[serde(default)]
+    pub branch: Option<String>,
     pub files: HashMap<String, FileFingerprint>,
 }
 
@@ -28,8 +63,7 @@ pub fn cache_dir(root: &Path) -> PathBuf {
 
 pub fn ensure_cache_dir(root: &Path) -> Result<PathBuf> {
     let dir = cache_dir(root);
-    fs::create_dir_all(&dir)
-        .with_context(|| format!("Failed to create cache dir: {}", dir.display()))?;
+    fs::create_dir_all(&dir).map_err(|e| OmniError::io(&dir, e))?;
     Ok(dir)
 }
 
@@ -45,32 +79,89 @@ pub fn bm25_path(root: &Path) -> PathBuf {
     cache_dir(root).join(BM25_FILE)
 }
 
+pub fn oci_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(OCI_FILE)
+}
+
+pub fn renames_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(RENAMES_FILE)
+}
+
+pub fn tombstones_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(TOMBSTONES_FILE)
+}
+
+pub fn popularity_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(POPULARITY_FILE)
+}
+
+pub fn focus_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(FOCUS_FILE)
+}
+
+pub fn branches_dir(root: &Path) -> PathBuf {
+    cache_dir(root).join(BRANCHES_DIR)
+}
+
+pub fn embeddings_dir(root: &Path) -> PathBuf {
+    cache_dir(root).join(EMBEDDINGS_DIR)
+}
+
+pub fn duplication_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(DUPLICATION_FILE)
+}
+
+pub fn writer_lock_path(root: &Path) -> PathBuf {
+    cache_dir(root).join(WRITER_LOCK_FILE)
+}
+
 pub fn load_manifest(root: &Path) -> Result<Option<IndexManifest>> {
-    let path = manifest_path(root);
+    load_manifest_from(&manifest_path(root))
+}
+
+pub(crate) fn load_manifest_from(path: &Path) -> Result<Option<IndexManifest>> {
     if !path.exists() {
         return Ok(None);
     }
-    let data =
-        fs::read(&path).with_context(|| format!("Failed to read manifest: {}", path.display()))?;
-    let manifest: IndexManifest = serde_json::from_slice(&data)
-        .with_context(|| format!("Failed to parse manifest: {}", path.display()))?;
+    let data = fs::read(path).map_err(|e| OmniError::io(path, e))?;
+    let manifest: IndexManifest =
+        serde_json::from_slice(&data).map_err(|e| OmniError::corrupt(path, e))?;
     Ok(Some(manifest))
 }
 
 pub fn save_manifest(root: &Path, manifest: &IndexManifest) -> Result<()> {
     ensure_cache_dir(root)?;
     let path = manifest_path(root);
-    let data = serde_json::to_vec_pretty(manifest)?;
-    fs::write(&path, data)
-        .with_context(|| format!("Failed to write manifest: {}", path.display()))?;
+    let data = serde_json::to_vec_pretty(manifest).map_err(|e| OmniError::corrupt(&path, e))?;
+    fs::write(&path, data).map_err(|e| OmniError::io(&path, e))?;
     Ok(())
 }
 
+/// Remove the cache of `root`, except for the writer lock of a running
+/// server (see [`crate::shared`]).
 pub fn clear_cache(root: &Path) -> Result<()> {
     let dir = cache_dir(root);
-    if dir.exists() {
-        fs::remove_dir_all(&dir)
-            .with_context(|| format!("Failed to remove cache dir: {}", dir.display()))?;
+    if !writer_lock_path(root).exists() {
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| OmniError::io(&dir, e))?;
+        }
+        return Ok(());
+    }
+    let entries = fs::read_dir(&dir).map_err(|e| OmniError::io(&dir, e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| OmniError::io(&dir, e))?.path();
+        if path
+            .file_name()
+            .is_some_and(|name| name == WRITER_LOCK_FILE)
+        {
+            continue;
+        }
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        removed.map_err(|e| OmniError::io(&path, e))?;
     }
     Ok(())
 }
@@ -0,0 +1,237 @@
+//! Call-graph rollups.
+//!
+//! A symbol with hundreds of callers is hard to read as a flat list. These
+//! helpers bucket call edges by file, module (directory), or crate and keep a
+//! few example call sites per bucket, so agents can see how usage is
+//! distributed before drilling into one area.
+
+use crate::state::{OciState, package_root};
+use crate::types::{CallEdge, TopologyNode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Example call sites kept per group.
+pub const MAX_GROUP_EXAMPLES: usize = 3;
+
+/// What to bucket call edges by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallGrouping {
+    File,
+    /// The directory containing the file
+    Module,
+    /// The innermost crate/package containing the file
+    Crate,
+}
+
+impl CallGrouping {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Module => "module",
+            Self::Crate => "crate",
+        }
+    }
+}
+
+impl FromStr for CallGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(Self::File),
+            "module" => Ok(Self::Module),
+            "crate" => Ok(Self::Crate),
+            other => Err(format!(
+                "Unknown group_by: {other} (expected module, crate, or file)"
+            )),
+        }
+    }
+}
+
+/// Which end of each edge decides its group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallSide {
+    /// Where the call is made (for "who calls X")
+    Caller,
+    /// Where the called symbol is defined (for "what does X call")
+    Callee,
+}
+
+/// One call site, as shown in a group's examples.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CallExample {
+    pub caller: String,
+    pub callee: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// Call edges that share a file, module, or crate.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CallGroup {
+    /// File or directory relative to the workspace root, or the crate name
+    pub group: String,
+    pub count: usize,
+    /// Up to [`MAX_GROUP_EXAMPLES`] call sites, in file/line order
+    pub examples: Vec<CallExample>,
+}
+
+/// Bucket `edges` by `grouping`, largest group first.
+///
+/// With [`CallSide::Callee`], edges whose target isn't indexed land in an
+/// `(unresolved)` group.
+pub fn group_calls(
+    state: &OciState,
+    edges: &[CallEdge],
+    grouping: CallGrouping,
+    side: CallSide,
+) -> Vec<CallGroup> {
+    let crates = crate_roots(state);
+    let mut sorted: Vec<&CallEdge> = edges.iter().collect();
+    sorted.sort_by(|a, b| {
+        a.location
+            .file
+            .cmp(&b.location.file)
+            .then(a.location.start_line.cmp(&b.location.start_line))
+    });
+
+    let mut groups: HashMap<String, CallGroup> = HashMap::new();
+    for edge in sorted {
+        let file = match side {
+            CallSide::Caller => Some(edge.location.file.clone()),
+            CallSide::Callee => state
+                .call_targets(edge)
+                .first()
+                .map(|target| target.location.file.clone()),
+        };
+        let key = match file {
+            Some(file) => group_key(state, &crates, &file, grouping),
+            None => "(unresolved)".to_string(),
+        };
+        let group = groups.entry(key).or_insert_with_key(|key| CallGroup {
+            group: key.clone(),
+            count: 0,
+            examples: Vec::new(),
+        });
+        group.count += 1;
+        if group.examples.len() < MAX_GROUP_EXAMPLES {
+            group.examples.push(CallExample {
+                caller: state.resolve(edge.caller).to_string(),
+                callee: edge.callee_name.clone(),
+                file: relative(state, &edge.location.file),
+                line: edge.location.start_line,
+            });
+        }
+    }
+
+    let mut groups: Vec<CallGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.group.cmp(&b.group)));
+    groups
+}
+
+/// Crate directories and names from the topology, innermost first.
+fn crate_roots(state: &OciState) -> Vec<(PathBuf, String)> {
+    let graph = state.topology.read();
+    let mut crates: Vec<(PathBuf, String)> = graph
+        .node_weights()
+        .filter_map(|node| match node {
+            TopologyNode::Crate { name, path, .. } => Some((path.clone(), name.clone())),
+            _ => None,
+        })
+        .collect();
+    crates.sort_by_key(|(path, _)| std::cmp::Reverse(path.components().count()));
+    crates
+}
+
+fn group_key(
+    state: &OciState,
+    crates: &[(PathBuf, String)],
+    file: &Path,
+    grouping: CallGrouping,
+) -> String {
+    match grouping {
+        CallGrouping::File => relative(state, file).display().to_string(),
+        CallGrouping::Module => {
+            let dir = relative(state, file.parent().unwrap_or(file));
+            if dir.as_os_str().is_empty() {
+                ".".to_string()
+            } else {
+                dir.display().to_string()
+            }
+        }
+        CallGrouping::Crate => {
+            if let Some((_, name)) = crates.iter().find(|(path, _)| file.starts_with(path)) {
+                return name.clone();
+            }
+            // No topology (or a non-Cargo package): name it by its directory
+            package_root(file.parent().unwrap_or(file), &state.root_path)
+                .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "(no crate)".to_string())
+        }
+    }
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Location;
+
+    fn edge(state: &OciState, caller: &str, file: &str, line: usize) -> CallEdge {
+        CallEdge {
+            caller: state.intern(caller),
+            callee_name: "parse".to_string(),
+            location: Location::new(state.root_path.join(file), 0, 0)
+                .with_positions(line, 0, line, 0),
+            is_method_call: false,
+            via_trait: None,
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
+        }
+    }
+
+    #[test]
+    fn test_groups_by_file_and_module() {
+        let state = OciState::new(PathBuf::from("/workspace"));
+        let edges = vec![
+            edge(&state, "crate::api::get", "src/api/get.rs", 4),
+            edge(&state, "crate::api::get", "src/api/get.rs", 9),
+            edge(&state, "crate::api::put", "src/api/put.rs", 2),
+            edge(&state, "crate::main", "src/main.rs", 1),
+        ];
+
+        let by_module = group_calls(&state, &edges, CallGrouping::Module, CallSide::Caller);
+        let counts: Vec<(&str, usize)> = by_module
+            .iter()
+            .map(|g| (g.group.as_str(), g.count))
+            .collect();
+        assert_eq!(counts, vec![("src/api", 3), ("src", 1)]);
+        assert_eq!(by_module[0].examples[0].caller, "crate::api::get");
+        assert_eq!(
+            by_module[0].examples[0].file,
+            PathBuf::from("src/api/get.rs")
+        );
+
+        let by_file = group_calls(&state, &edges, CallGrouping::File, CallSide::Caller);
+        assert_eq!(by_file[0].group, "src/api/get.rs");
+        assert_eq!(by_file[0].count, 2);
+        assert_eq!(by_file.len(), 3);
+
+        let unresolved = group_calls(&state, &edges, CallGrouping::File, CallSide::Callee);
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].group, "(unresolved)");
+        assert_eq!(unresolved[0].examples.len(), MAX_GROUP_EXAMPLES);
+
+        assert_eq!("crate".parse(), Ok(CallGrouping::Crate));
+        assert!("package".parse::<CallGrouping>().is_err());
+    }
+}
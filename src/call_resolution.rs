@@ -0,0 +1,592 @@
+//! Caller resolution for shared names.
+//!
+//! Call edges only record the callee's simple name, so "who calls `parse`"
+//! mixes the callers of every `parse` in the workspace. [`resolve_callers`]
+//! scores each call site against each candidate definition, from the type
+//! the call is qualified with (`Json::parse`), the imports visible in the
+//! caller's file, and the module distance between the two files, and files
+//! every call under its most likely target with a confidence (its share of
+//! the scores over all candidates).
+//!
+//! [`resolve_call_edges`] runs the same scoring once over the whole call
+//! graph after indexing and binds each edge whose target is clear to its
+//! definition ([`CallEdge::resolved_callee`]).
+
+use crate::state::OciState;
+use crate::types::{CallEdge, ImportInfo, InternedString, SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Why a call site was attributed to a candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    /// The call names the candidate's type (`Json::parse`, `Self::parse`)
+    Qualified,
+    /// The call is in the candidate's own file
+    SameFile,
+    /// The caller's file imports the candidate (or its type) by name
+    Import,
+    /// The caller's file glob-imports the candidate's module
+    GlobImport,
+    /// No import; nearest by module distance
+    ModuleDistance,
+}
+
+impl Resolution {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Qualified => "qualified",
+            Self::SameFile => "same file",
+            Self::Import => "import",
+            Self::GlobImport => "glob import",
+            Self::ModuleDistance => "module distance",
+        }
+    }
+}
+
+/// One call site attributed to a candidate.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ResolvedCaller {
+    pub caller: String,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    /// Share of this candidate's score over all candidates, in `(0, 1]`
+    pub confidence: f32,
+    pub resolution: Resolution,
+    /// The call is made from test code
+    pub is_test_context: bool,
+}
+
+/// A definition sharing the queried name, and the calls attributed to it.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CallerCandidate {
+    /// Scoped name of the definition
+    pub callee: String,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    /// Most confident first
+    pub callers: Vec<ResolvedCaller>,
+}
+
+/// Partition the callers of `name` across the functions and methods that
+/// share it, most-called candidate first.
+///
+/// Returns an empty list when no function or method by that name is
+/// indexed. Every call site lands under exactly one candidate, except calls
+/// qualified with a type that defines none of them (`Vec::new` when asking
+/// about `new`), which are left out.
+pub fn resolve_callers(state: &OciState, name: &str) -> Vec<CallerCandidate> {
+    let mut targets: Vec<SymbolDef> = state
+        .find_by_name(name)
+        .into_iter()
+        .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+        .collect();
+    targets.sort_by(|a, b| {
+        a.location
+            .file
+            .cmp(&b.location.file)
+            .then(a.location.start_line.cmp(&b.location.start_line))
+    });
+    let mut candidates: Vec<CallerCandidate> = targets
+        .iter()
+        .map(|t| CallerCandidate {
+            callee: state.resolve(t.scoped_name).to_string(),
+            file: relative(state, &t.location.file),
+            line: t.location.start_line,
+            callers: Vec::new(),
+        })
+        .collect();
+    if targets.is_empty() {
+        return candidates;
+    }
+
+    let mut files = FileCache::default();
+    for edge in state.find_callers(name) {
+        let Some(scores) = files.score_targets(state, &edge, &targets) else {
+            continue;
+        };
+        let total: f32 = scores.iter().map(|(s, _)| s).sum();
+        let (best, &(score, resolution)) = scores
+            .iter()
+            .enumerate()
+            .max_by(|(ia, a), (ib, b)| a.0.total_cmp(&b.0).then(ib.cmp(ia)))
+            .expect("at least one target");
+        candidates[best].callers.push(ResolvedCaller {
+            caller: state.resolve(edge.caller).to_string(),
+            file: relative(state, &edge.location.file),
+            line: edge.location.start_line,
+            confidence: score / total,
+            resolution,
+            is_test_context: edge.is_test_context,
+        });
+    }
+
+    for candidate in &mut candidates {
+        candidate.callers.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+        });
+    }
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.callers.len()));
+    candidates
+}
+
+/// Bind every call edge to the definition it most likely calls, and return
+/// the number of edges bound.
+///
+/// Edges are scored against the functions and methods sharing the callee's
+/// name as in [`resolve_callers`], but only bound on specific evidence: the
+/// call is qualified with the candidate's type, made in the candidate's
+/// file, or the caller's file imports it, and no other candidate scores as
+/// high. An unqualified call is also bound when the workspace defines
+/// exactly one free function by that name. The receiver of a method call
+/// has no known type, so method calls are only bound through `self` (or
+/// `this` in TypeScript), to a method of the caller's own type. Approximate `dyn Trait` edges already
+/// name their target and are left alone.
+pub fn resolve_call_edges(state: &OciState) -> usize {
+    let mut targets_by_name: HashMap<String, Vec<SymbolDef>> = HashMap::new();
+    let mut files = FileCache::default();
+    let bindings: Vec<Option<InternedString>> = {
+        let edges = state.call_edges.read();
+        edges
+            .iter()
+            .map(|edge| {
+                if edge.is_approximate {
+                    return None;
+                }
+                let targets = targets_by_name
+                    .entry(edge.callee_name.clone())
+                    .or_insert_with_key(|name| {
+                        state
+                            .find_by_name(name)
+                            .into_iter()
+                            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+                            .collect()
+                    });
+                bind(state, edge, targets, &mut files)
+            })
+            .collect()
+    };
+
+    let mut edges = state.call_edges.write();
+    if edges.len() != bindings.len() {
+        return 0;
+    }
+    let mut bound = 0;
+    for (edge, binding) in edges.iter_mut().zip(bindings) {
+        bound += usize::from(binding.is_some());
+        edge.resolved_callee = binding;
+    }
+    bound
+}
+
+/// The target `edge` is bound to, if the evidence singles one out.
+fn bind(
+    state: &OciState,
+    edge: &CallEdge,
+    targets: &[SymbolDef],
+    files: &mut FileCache,
+) -> Option<InternedString> {
+    if edge.is_method_call {
+        let source = files.source(state, &edge.location.file)?;
+        let call = source.get(edge.location.start_byte..edge.location.end_byte)?;
+        let rest = call
+            .strip_prefix("self.")
+            .or_else(|| call.strip_prefix("this."))?
+            .strip_prefix(edge.callee_name.as_str())?;
+        if !rest.starts_with(['(', ':']) {
+            return None;
+        }
+        let caller = state.resolve(edge.caller);
+        let (scope, _) = caller.rsplit_once("::")?;
+        let self_type = scope.rsplit("::").next()?;
+        let mut own = targets
+            .iter()
+            .filter(|t| t.parent.is_some_and(|p| state.resolve(p) == self_type));
+        return match (own.next(), own.next()) {
+            (Some(target), None) => Some(target.scoped_name),
+            _ => None,
+        };
+    }
+
+    let scores = files.score_targets(state, edge, targets)?;
+    let (best, &(best_score, resolution)) = scores
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0))?;
+    let target = &targets[best];
+    let specific = match resolution {
+        Resolution::Qualified
+        | Resolution::SameFile
+        | Resolution::Import
+        | Resolution::GlobImport => true,
+        Resolution::ModuleDistance => {
+            targets.len() == 1 && target.kind == SymbolKind::Function && target.parent.is_none()
+        }
+    };
+    let unique = scores
+        .iter()
+        .enumerate()
+        .all(|(i, (score, _))| i == best || *score < best_score);
+    (specific && unique).then_some(target.scoped_name)
+}
+
+/// Sources and imports of caller files, loaded once per pass.
+#[derive(Default)]
+struct FileCache {
+    sources: HashMap<PathBuf, Option<Arc<str>>>,
+    imports: HashMap<PathBuf, Vec<ImportInfo>>,
+}
+
+impl FileCache {
+    fn source(&mut self, state: &OciState, file: &Path) -> Option<&str> {
+        self.sources
+            .entry(file.to_path_buf())
+            .or_insert_with_key(|file| file_source(state, file))
+            .as_deref()
+    }
+
+    /// Scores of `edge` against each of `targets`, or `None` when the call
+    /// is qualified with a type that defines none of them.
+    fn score_targets(
+        &mut self,
+        state: &OciState,
+        edge: &CallEdge,
+        targets: &[SymbolDef],
+    ) -> Option<Vec<(f32, Resolution)>> {
+        let file = &edge.location.file;
+        let qualifier = self
+            .source(state, file)
+            .and_then(|source| call_qualifier(state, edge, source));
+        if let Some(q) = &qualifier
+            && !targets
+                .iter()
+                .any(|t| t.parent.is_some_and(|p| state.resolve(p) == q))
+        {
+            return None;
+        }
+        let imports = self
+            .imports
+            .entry(file.clone())
+            .or_insert_with_key(|file| file_imports(state, file));
+        Some(
+            targets
+                .iter()
+                .map(|t| score(state, edge, qualifier.as_deref(), imports, t))
+                .collect(),
+        )
+    }
+}
+
+/// How likely `edge` is to call `target`, before normalizing across
+/// candidates.
+fn score(
+    state: &OciState,
+    edge: &CallEdge,
+    qualifier: Option<&str>,
+    imports: &[ImportInfo],
+    target: &SymbolDef,
+) -> (f32, Resolution) {
+    let parent = target.parent.map(|p| state.resolve(p));
+    match (qualifier, parent) {
+        (Some(q), Some(p)) if q == p => return (1.0, Resolution::Qualified),
+        // Called through one of the other candidates' types
+        (Some(_), _) => return (0.01, Resolution::ModuleDistance),
+        _ => {}
+    }
+
+    let caller_file = &edge.location.file;
+    let target_file = &target.location.file;
+    if caller_file == target_file {
+        return (0.8, Resolution::SameFile);
+    }
+
+    // A method is reached through its type, so importing the type counts
+    let names: Vec<&str> = std::iter::once(state.resolve(target.name))
+        .chain(parent)
+        .collect();
+    let module = module_components(&relative(state, target_file));
+    let named: Vec<&ImportInfo> = imports
+        .iter()
+        .filter(|i| !i.is_glob && names.contains(&i.original_name.as_deref().unwrap_or(&i.name)))
+        .collect();
+    if named
+        .iter()
+        .any(|i| import_matches(&i.path, false, &module))
+    {
+        return (0.9, Resolution::Import);
+    }
+    let distance = module_distance(caller_file, target_file);
+    if !named.is_empty() {
+        // The name is imported, but from somewhere else
+        return (0.05 / (1.0 + distance as f32), Resolution::ModuleDistance);
+    }
+    if imports
+        .iter()
+        .any(|i| i.is_glob && import_matches(&i.path, true, &module))
+    {
+        return (0.7, Resolution::GlobImport);
+    }
+    (0.5 / (1.0 + distance as f32), Resolution::ModuleDistance)
+}
+
+/// Type a call is qualified with: `Json` in `Json::parse(..)` or
+/// `formats::Json::<T>::parse(..)`, the caller's impl type for `Self::`.
+/// `None` for unqualified calls, method calls, and module paths.
+fn call_qualifier(state: &OciState, edge: &CallEdge, source: &str) -> Option<String> {
+    if edge.is_method_call {
+        return None;
+    }
+    let call = source.get(edge.location.start_byte..edge.location.end_byte)?;
+    let callee = call.split('(').next()?.trim_end();
+    // Turbofish on the callee: `Json::parse::<T>`
+    let callee = match callee.strip_suffix('>') {
+        Some(_) => callee.rsplit_once("::<").map_or(callee, |(path, _)| path),
+        None => callee,
+    };
+    let path = callee.strip_suffix(edge.callee_name.as_str())?;
+    let path = path.strip_suffix("::")?;
+    let segment = path
+        .rsplit("::")
+        .find(|s| !s.starts_with('<') && !s.is_empty())?
+        .split('<')
+        .next()?
+        .trim();
+    if segment == "Self" {
+        let caller = state.resolve(edge.caller);
+        let (scope, _) = caller.rsplit_once("::")?;
+        return scope.rsplit("::").next().map(str::to_string);
+    }
+    segment
+        .starts_with(|c: char| c.is_uppercase())
+        .then(|| segment.to_string())
+}
+
+fn file_source(state: &OciState, file: &Path) -> Option<Arc<str>> {
+    if let Some(overlay) = state.overlays.get(file) {
+        return Some(overlay.contents.clone());
+    }
+    if let Some(contents) = state.file_contents.get(file) {
+        return Some(contents.clone());
+    }
+    std::fs::read_to_string(file).ok().map(Arc::from)
+}
+
+fn file_imports(state: &OciState, file: &Path) -> Vec<ImportInfo> {
+    state
+        .file_ids
+        .get(file)
+        .and_then(|id| state.imports.get(&*id).map(|imports| imports.clone()))
+        .unwrap_or_default()
+}
+
+/// Whether an import path names an item in the module whose file path
+/// components are `module` (`crate::json::Json` and `crate::json` as a glob
+/// both match `src/json.rs`).
+fn import_matches(path: &str, is_glob: bool, module: &[String]) -> bool {
+    let path = path.trim_end_matches('*').trim_end_matches("::");
+    let mut segments: Vec<&str> = path
+        .split(['/', '.', ':'])
+        .filter(|s| !s.is_empty() && !matches!(*s, "crate" | "self" | "super"))
+        .collect();
+    if !is_glob {
+        segments.pop();
+    }
+    match (segments.last(), module.last()) {
+        (Some(segment), Some(component)) => segment == component,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Module path of a root-relative source file, from its directories and
+/// stem, without `src` and crate-root or `mod` file names.
+fn module_components(rel: &Path) -> Vec<String> {
+    let stem = rel.with_extension("");
+    stem.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .filter(|c| !matches!(c.as_str(), "src" | "lib" | "main" | "mod" | "index"))
+        .collect()
+}
+
+/// Directory steps from one file's directory to the other's.
+fn module_distance(a: &Path, b: &Path) -> usize {
+    let a: Vec<Component> = a
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    let b: Vec<Component> = b
+        .parent()
+        .map(|p| p.components().collect())
+        .unwrap_or_default();
+    let common = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    (a.len() - common) + (b.len() - common)
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_partitions_callers_by_qualifier_import_and_distance() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src/formats")).unwrap();
+        std::fs::write(
+            root.join("src/json.rs"),
+            "pub struct Json;\nimpl Json {\n    pub fn parse(s: &str) -> Self {\n        Json\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/formats/toml.rs"),
+            "pub struct Toml;\nimpl Toml {\n    pub fn parse(s: &str) -> Self {\n        Toml\n    }\n    pub fn reload(&self) {\n        Self::parse(\"\");\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/formats/sniff.rs"),
+            "use super::toml::Toml;\n\npub fn sniff(s: &str) {\n    Toml::parse(s);\n}\n\npub fn guess() {\n    parse(\"\");\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/formats/detect.rs"),
+            "pub fn detect() {\n    parse(\"\");\n    Yaml::parse(\"\");\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/app.rs"),
+            "use crate::json::Json;\n\npub fn load(s: &str) {\n    Json::parse(s);\n}\n",
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let candidates = resolve_callers(&state, "parse");
+
+        let summary: Vec<(&str, Vec<(&str, Resolution)>)> = candidates
+            .iter()
+            .map(|c| {
+                (
+                    c.callee.as_str(),
+                    c.callers
+                        .iter()
+                        .map(|r| (r.caller.as_str(), r.resolution))
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "crate::Toml::parse",
+                    vec![
+                        ("crate::sniff", Resolution::Qualified),
+                        ("crate::Toml::reload", Resolution::Qualified),
+                        ("crate::guess", Resolution::Import),
+                        ("crate::detect", Resolution::ModuleDistance),
+                    ]
+                ),
+                (
+                    "crate::Json::parse",
+                    vec![("crate::load", Resolution::Qualified)]
+                ),
+            ]
+        );
+        assert_eq!(candidates[0].file, PathBuf::from("src/formats/toml.rs"));
+        assert!(candidates[1].callers[0].confidence > 0.95);
+        assert!(candidates[0].callers[2].confidence > 0.75);
+        // Unimported: nearer wins, but only with two thirds of the score
+        let detect = &candidates[0].callers[3];
+        assert!((detect.confidence - 2.0 / 3.0).abs() < 1e-6);
+
+        assert!(resolve_callers(&state, "missing").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_binds_call_edges_from_qualifiers_and_self() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/a.rs"),
+            "pub struct Job;\nimpl Job {\n    pub fn new() -> Self {\n        Job\n    }\n    pub fn run(&self) {\n        self.step();\n    }\n    fn step(&self) {}\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/b.rs"),
+            "pub fn unique() {}\n\npub struct Task;\nimpl Task {\n    pub fn new() -> Self {\n        Task\n    }\n    fn step(&self) {}\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/app.rs"),
+            "use crate::b::Task;\n\npub fn main() {\n    unique();\n    Job::new().run();\n    Task::new();\n    Vec::<u8>::new();\n    job.step();\n}\n",
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let bound = |caller: &str, callee: &str| -> Option<String> {
+            let edges = state.call_edges.read();
+            let edge = edges
+                .iter()
+                .find(|e| state.resolve(e.caller) == caller && e.callee_name == callee)
+                .unwrap_or_else(|| panic!("no call from {caller} to {callee}"));
+            edge.resolved_callee
+                .map(|key| state.resolve(key).to_string())
+        };
+        assert_eq!(
+            bound("crate::main", "unique").as_deref(),
+            Some("crate::unique")
+        );
+        assert_eq!(
+            bound("crate::Job::run", "step").as_deref(),
+            Some("crate::Job::step")
+        );
+        // The receiver's type is unknown
+        assert_eq!(bound("crate::main", "run"), None);
+        assert_eq!(bound("crate::main", "step"), None);
+
+        let edges = state.call_edges.read();
+        let news: Vec<Option<&str>> = edges
+            .iter()
+            .filter(|e| e.callee_name == "new")
+            .map(|e| e.resolved_callee.map(|key| state.resolve(key)))
+            .collect();
+        // `Vec::new` is outside the workspace
+        assert_eq!(
+            news,
+            vec![Some("crate::Job::new"), Some("crate::Task::new"), None]
+        );
+        drop(edges);
+
+        let job_new = state.lookup("crate::Job::new").unwrap();
+        let callers = state.find_resolved_callers(job_new);
+        assert_eq!(callers.len(), 1);
+        assert_eq!(state.resolve(callers[0].caller), "crate::main");
+    }
+}
@@ -0,0 +1,361 @@
+//! Transitive call trees.
+//!
+//! Direct callers answer "who calls X"; agents also ask "who reaches X
+//! within 3 hops". [`call_tree`] walks callers or callees breadth-first up to
+//! a depth. Each symbol is expanded once, at the shallowest depth it appears;
+//! later appearances are listed without children, and calls back into an
+//! ancestor are marked as cycles.
+
+use crate::call_groups::CallSide;
+use crate::state::OciState;
+use crate::types::{CallEdge, InternedString};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Upper bound on the nodes of one tree.
+pub const MAX_TREE_NODES: usize = 500;
+
+/// One caller or callee, with the calls between it and its parent.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CallTreeNode {
+    /// Scoped name, or for a callee matching no single definition the name
+    /// it is called by
+    pub symbol: String,
+    /// First call site between this node and its parent
+    pub file: PathBuf,
+    pub line: usize,
+    /// Call sites between this node and its parent
+    pub calls: usize,
+    /// Hops from the root (1 for direct callers/callees)
+    pub depth: u32,
+    /// Every call site is in test code
+    pub is_test_context: bool,
+    /// Already listed closer to the root; its children are not repeated
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repeated: bool,
+    /// One of its own ancestors (or the root), closing a cycle
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<CallTreeNode>,
+}
+
+/// Callers or callees of a symbol, up to a depth.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CallTree {
+    pub root: String,
+    pub depth: u32,
+    /// Distinct symbols in the tree
+    pub symbols: usize,
+    /// Stopped at [`MAX_TREE_NODES`] nodes
+    pub truncated: bool,
+    pub children: Vec<CallTreeNode>,
+}
+
+impl CallTree {
+    /// Render as an indented list, two spaces per hop.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut stack: Vec<&CallTreeNode> = self.children.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            let _ = write!(
+                out,
+                "{}- {} at {}:{}",
+                "  ".repeat(node.depth as usize - 1),
+                node.symbol,
+                node.file.display(),
+                node.line
+            );
+            if node.calls > 1 {
+                let _ = write!(out, " ({} calls)", node.calls);
+            }
+            if node.is_test_context {
+                out.push_str(" [test]");
+            }
+            if node.cycle {
+                out.push_str(" (cycle)");
+            } else if node.repeated {
+                out.push_str(" (see above)");
+            }
+            out.push('\n');
+            stack.extend(node.children.iter().rev());
+        }
+        if self.truncated {
+            let _ = writeln!(out, "... stopped at {} nodes", MAX_TREE_NODES);
+        }
+        out
+    }
+}
+
+/// A tree node before its children are attached.
+struct Pending {
+    node: CallTreeNode,
+    key: Option<InternedString>,
+    parent: Option<usize>,
+}
+
+/// Walk the callers (`CallSide::Caller`) or callees (`CallSide::Callee`) of
+/// `name` breadth-first, up to `depth` hops.
+///
+/// `name` is looked up like `omni calls` does: a scoped name of an indexed
+/// symbol follows only the calls bound to that definition, a simple name
+/// every call by that name. Deeper hops follow the calls bound to each
+/// symbol and the unbound calls by its name.
+pub fn call_tree(
+    state: &OciState,
+    name: &str,
+    side: CallSide,
+    depth: u32,
+    exclude_tests: bool,
+) -> CallTree {
+    let bound = name
+        .contains("::")
+        .then(|| state.lookup(name))
+        .flatten()
+        .filter(|key| state.symbols.contains_key(key));
+    let roots: Vec<InternedString> = match bound {
+        Some(key) => vec![key],
+        None => state
+            .find_by_name(name)
+            .into_iter()
+            .map(|s| s.scoped_name)
+            .collect(),
+    };
+
+    let edges = state.call_edges.read();
+    let edges: Vec<&CallEdge> = edges
+        .iter()
+        .filter(|e| !exclude_tests || !e.is_test_context)
+        .collect();
+    let mut by_caller: HashMap<InternedString, Vec<&CallEdge>> = HashMap::new();
+    let mut by_resolved: HashMap<InternedString, Vec<&CallEdge>> = HashMap::new();
+    let mut by_name: HashMap<&str, Vec<&CallEdge>> = HashMap::new();
+    for &edge in &edges {
+        match side {
+            CallSide::Callee => by_caller.entry(edge.caller).or_default().push(edge),
+            CallSide::Caller => match edge.resolved_callee {
+                Some(key) => by_resolved.entry(key).or_default().push(edge),
+                // Approximate edges are named by the scoped impl method
+                None => by_name
+                    .entry(edge.callee_name.as_str())
+                    .or_default()
+                    .push(edge),
+            },
+        }
+    }
+    let edges_of = |key: InternedString| -> Vec<&CallEdge> {
+        match side {
+            CallSide::Callee => by_caller.get(&key).cloned().unwrap_or_default(),
+            CallSide::Caller => {
+                let scoped = state.resolve(key);
+                let simple = scoped.rsplit("::").next().unwrap_or(scoped);
+                let mut found: Vec<&CallEdge> = by_resolved.get(&key).cloned().unwrap_or_default();
+                for name in [simple, scoped] {
+                    found.extend(
+                        by_name
+                            .get(name)
+                            .into_iter()
+                            .flatten()
+                            .filter(|e| e.is_approximate == (name == scoped)),
+                    );
+                }
+                found
+            }
+        }
+    };
+    let root_edges: Vec<&CallEdge> = match (side, bound) {
+        (CallSide::Caller, None) => {
+            let name = crate::parsing::normalize_identifier(name);
+            edges
+                .iter()
+                .copied()
+                .filter(|e| e.callee_name == name)
+                .collect()
+        }
+        (CallSide::Caller, Some(key)) => by_resolved.get(&key).cloned().unwrap_or_default(),
+        (CallSide::Callee, _) => roots.iter().flat_map(|&key| edges_of(key)).collect(),
+    };
+
+    let mut pending: Vec<Pending> = Vec::new();
+    let mut expanded: HashSet<InternedString> = roots.iter().copied().collect();
+    let mut queue = VecDeque::new();
+    let mut truncated = false;
+    let mut add_children = |pending: &mut Vec<Pending>,
+                            queue: &mut VecDeque<usize>,
+                            parent: Option<usize>,
+                            edges: Vec<&CallEdge>| {
+        let child_depth = parent.map_or(1, |p| pending[p].node.depth + 1);
+        for (key, symbol, group) in group_edges(state, side, edges) {
+            if pending.len() >= MAX_TREE_NODES {
+                truncated = true;
+                return;
+            }
+            let first = group[0];
+            queue.push_back(pending.len());
+            pending.push(Pending {
+                node: CallTreeNode {
+                    symbol,
+                    file: first.location.file.clone(),
+                    line: first.location.start_line,
+                    calls: group.len(),
+                    depth: child_depth,
+                    is_test_context: group.iter().all(|e| e.is_test_context),
+                    repeated: false,
+                    cycle: false,
+                    children: Vec::new(),
+                },
+                key,
+                parent,
+            });
+        }
+    };
+    add_children(&mut pending, &mut queue, None, root_edges);
+    while let Some(index) = queue.pop_front() {
+        let Some(key) = pending[index].key else {
+            continue;
+        };
+        let mut ancestor = pending[index].parent;
+        let mut cycle = roots.contains(&key);
+        while let (false, Some(a)) = (cycle, ancestor) {
+            cycle = pending[a].key == Some(key);
+            ancestor = pending[a].parent;
+        }
+        if cycle {
+            pending[index].node.cycle = true;
+        } else if !expanded.insert(key) {
+            pending[index].node.repeated = true;
+        } else if pending[index].node.depth < depth {
+            add_children(&mut pending, &mut queue, Some(index), edges_of(key));
+        }
+    }
+
+    let symbols = pending
+        .iter()
+        .map(|p| p.node.symbol.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    // Children come after their parents, so attaching from the back leaves
+    // every node complete before it is moved
+    let mut children = Vec::new();
+    let mut nodes: Vec<Option<Pending>> = pending.into_iter().map(Some).collect();
+    for index in (0..nodes.len()).rev() {
+        let Some(mut entry) = nodes[index].take() else {
+            continue;
+        };
+        entry.node.children.reverse();
+        match entry.parent.and_then(|p| nodes[p].as_mut()) {
+            Some(parent) => parent.node.children.push(entry.node),
+            None => children.push(entry.node),
+        }
+    }
+    children.reverse();
+
+    CallTree {
+        root: name.to_string(),
+        depth,
+        symbols,
+        truncated,
+        children,
+    }
+}
+
+/// Group call edges by the caller or callee they lead to, in order of their
+/// first call site. A callee bound or resolving to one definition is keyed
+/// by it; an unbound method call, or a call resolving to no or several
+/// definitions, is a leaf named as called.
+fn group_edges<'a>(
+    state: &OciState,
+    side: CallSide,
+    mut edges: Vec<&'a CallEdge>,
+) -> Vec<(Option<InternedString>, String, Vec<&'a CallEdge>)> {
+    edges.sort_by(|a, b| {
+        a.location
+            .file
+            .cmp(&b.location.file)
+            .then(a.location.start_line.cmp(&b.location.start_line))
+    });
+    let mut groups: Vec<(Option<InternedString>, String, Vec<&CallEdge>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for edge in edges {
+        let (key, symbol) = match side {
+            CallSide::Caller => (Some(edge.caller), state.resolve(edge.caller).to_string()),
+            // Unbound method calls are left as called, like `.collect()`
+            // that merely shares a workspace method's name
+            CallSide::Callee
+                if edge.is_method_call
+                    && edge.resolved_callee.is_none()
+                    && !edge.is_approximate =>
+            {
+                (None, edge.callee_name.clone())
+            }
+            CallSide::Callee => match state.call_targets(edge).as_slice() {
+                [target] => (
+                    Some(target.scoped_name),
+                    state.resolve(target.scoped_name).to_string(),
+                ),
+                _ => (None, edge.callee_name.clone()),
+            },
+        };
+        match index.get(&symbol) {
+            Some(&i) => groups[i].2.push(edge),
+            None => {
+                index.insert(symbol.clone(), groups.len());
+                groups.push((key, symbol, vec![edge]));
+            }
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_call_tree_walks_callers_to_depth_with_cycles() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("lib.rs"),
+            "fn validate_input() { check(); }\n\nfn check() {}\n\nfn handle() { validate_input(); validate_input(); }\n\nfn serve() { handle(); }\n\nfn main() { serve(); }\n\nfn retry() { validate_input(); retry(); }\n\nfn recurse() { validate_input(); check(); }\n",
+        )
+        .unwrap();
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let tree = call_tree(&state, "validate_input", CallSide::Caller, 2, false);
+        let names: Vec<&str> = tree.children.iter().map(|n| n.symbol.as_str()).collect();
+        assert_eq!(names, ["crate::handle", "crate::retry", "crate::recurse"]);
+        let handle = &tree.children[0];
+        assert_eq!(handle.calls, 2);
+        assert_eq!(handle.children[0].symbol, "crate::serve");
+        assert_eq!(handle.children[0].depth, 2);
+        // Depth 2 stops before main
+        assert!(handle.children[0].children.is_empty());
+        let retry = &tree.children[1];
+        assert!(retry.children[0].cycle);
+
+        let deeper = call_tree(&state, "validate_input", CallSide::Caller, 3, false);
+        assert_eq!(
+            deeper.children[0].children[0].children[0].symbol,
+            "crate::main"
+        );
+        assert!(deeper.render().contains("    - crate::main at "));
+
+        let callees = call_tree(&state, "recurse", CallSide::Callee, 3, false);
+        let names: Vec<&str> = callees.children.iter().map(|n| n.symbol.as_str()).collect();
+        assert_eq!(names, ["crate::validate_input", "crate::check"]);
+        // check is expanded where it is a direct callee, not under validate_input
+        assert_eq!(callees.children[0].children[0].symbol, "crate::check");
+        assert!(callees.children[0].children[0].repeated);
+        assert!(!callees.children[1].repeated);
+        assert_eq!(callees.symbols, 2);
+    }
+}
@@ -27,13 +27,57 @@
 //! - Exit codes: 0 = success, 1 = error
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
 #[cfg(feature = "analysis")]
-use omni_index::DeadCodeAnalyzer;
-use omni_index::export::export_engram_memory;
-use omni_index::query::{QueryResponse, execute_query, load_search_index, parse_query_filters};
-use omni_index::{IncrementalIndexer, IndexOptions, SymbolDef, create_state};
+use omni_index::analysis::{
+    ChurnAnalyzer, CloneAnalyzer, CloneConfig, CoChangeAnalyzer, CoverageAnalyzer,
+    ErrorPatternAnalyzer, FieldUsageAnalyzer, GenericsAnalyzer, HotspotAnalyzer, PanicAnalyzer,
+    RiskyApiAnalyzer, SeamAnalyzer, SpellingAnalyzer, SpellingConfig, TestCloneAnalyzer,
+    TestCloneConfig,
+};
+#[cfg(feature = "analysis")]
+use omni_index::analysis::{check, custom, discover_binaries, problems};
+use omni_index::bench::{BenchOptions, BenchReport};
+use omni_index::call_groups::{CallGroup, CallGrouping, CallSide, group_calls};
+use omni_index::call_resolution::{CallerCandidate, resolve_callers};
+use omni_index::call_tree::{CallTree, CallTreeNode, call_tree};
+use omni_index::completion::Completion;
+use omni_index::config::IndexConfig;
+use omni_index::config_refs::{ConfigRefs, find_config_refs};
+use omni_index::duplication::DuplicationPressure;
+use omni_index::explain::{Explanation, explain};
+use omni_index::export::{export_ctags, export_engram_memory, export_etags, export_jsonl_symbols};
+use omni_index::impact::{CrateImpact, crate_impact};
+use omni_index::implementations::{Implementations, TraitOrigin, implementations, trait_origins};
+use omni_index::output::{
+    CallResult, MIN_SCHEMA_VERSION, ReferenceResult, SCHEMA_VERSION, SuccessResponse, SymbolResult,
+    downgrade,
+};
+#[cfg(feature = "analysis")]
+use omni_index::output::{DeadResult, ReachableResult};
+use omni_index::query::{
+    QueryResponse, SearchIndex, SearchStrategy, apply_overlays, execute_query, load_search_index,
+    parse_query_filters, search_with_fallback,
+};
+use omni_index::reachability::{ReachableSet, reachable_from};
+use omni_index::refactor::{RenameImpact, rename_impact};
+use omni_index::renames::RenameLog;
+use omni_index::sql_refs::{SqlRefs, find_sql_refs};
+use omni_index::stack::StackReport;
+use omni_index::test_impact::{AffectedTests, affected_tests};
+use omni_index::tombstones::{Tombstone, TombstoneLog};
+use omni_index::{
+    Attribute, Budget, EmbeddingDriftReport, FileDiscovery, IncrementalIndexer, IndexEvent,
+    IndexOptions, IndexPhase, OciState, OmniError, PackageScope, SymbolDef, TestSplit,
+    create_state,
+};
+#[cfg(feature = "analysis")]
+use omni_index::{BinaryAnalyzer, DeadCodeAnalyzer};
+use std::collections::HashSet;
+use std::io::Read;
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Parser)]
@@ -54,7 +98,7 @@ Designed for automation: use --json for machine-readable output.
 "#)]
 struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 
     /// Root directory to analyze (alias: --workspace)
     #[arg(short, long, global = true, default_value = ".", alias = "workspace")]
@@ -63,6 +107,30 @@ struct Cli {
     /// Output JSON instead of human-readable text
     #[arg(long, global = true)]
     json: bool,
+
+    /// JSON output version to emit, for consumers of an older format
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = SCHEMA_VERSION,
+        value_parser = clap::value_parser!(u32).range(MIN_SCHEMA_VERSION as i64..=SCHEMA_VERSION as i64)
+    )]
+    schema_version: u32,
+
+    /// Read the index published by a running `OMNI_SHARED_INDEX=1` server
+    /// instead of building one (falls back when no server writes it)
+    #[arg(long, global = true)]
+    shared: bool,
+
+    /// Limit index, query, symbol, calls, and analyze to one Cargo package
+    /// or npm workspace package, by manifest name
+    #[arg(long, global = true, value_name = "NAME")]
+    package: Option<String>,
+
+    /// Print the JSON Schema of every command's `--json` output and exit
+    #[arg(long)]
+    schema: bool,
 }
 
 #[derive(Subcommand)]
@@ -96,6 +164,36 @@ enum Commands {
         /// Max file size in bytes (ignored if --include-large)
         #[arg(long, default_value = "2097152")]
         max_file_size: u64,
+
+        /// Force a language for matching paths, e.g. `scripts/*=typescript`
+        /// (can be used multiple times)
+        #[arg(long = "lang-override", value_name = "GLOB=LANG")]
+        lang_overrides: Vec<String>,
+
+        /// File or directory the current task is about: indexed and
+        /// searchable first, then boosted in results for a while (can be
+        /// used multiple times)
+        #[arg(long, value_name = "PATH")]
+        focus: Vec<String>,
+
+        /// Days to keep records of deleted symbols (default 30, kept
+        /// until changed)
+        #[arg(long, value_name = "DAYS")]
+        tombstone_retention_days: Option<u64>,
+
+        /// Don't show the progress bar
+        #[arg(long, short = 'q')]
+        quiet: bool,
+
+        /// Emit line-delimited JSON progress events on stdout, ending with
+        /// the JSON result
+        #[arg(long, conflicts_with = "quiet")]
+        porcelain: bool,
+
+        /// Don't build or update the semantic index after parsing (also
+        /// `semantic = false` under `[index]` in omni.toml)
+        #[arg(long)]
+        no_semantic: bool,
     },
 
     /// Index multiple workspaces in one command
@@ -117,12 +215,27 @@ enum Commands {
         /// Additional filters (path:..., ext:..., -path:...)
         #[arg(long, value_name = "FILTER")]
         filters: Vec<String>,
+
+        /// Only include code reachable (via calls or imports) from this file or symbol
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        reachable_from: Option<String>,
+
+        /// Read the unsaved contents of this file from stdin and search those
+        /// instead of what is on disk
+        #[arg(long, value_name = "PATH")]
+        stdin_file: Option<PathBuf>,
     },
 
-    /// Find symbol definitions by name
+    /// Find symbol definitions by name or attribute
     Symbol {
-        /// Symbol name to find
-        name: String,
+        /// Symbol name to find; optional with `--attr`
+        #[arg(required_unless_present = "attr")]
+        name: Option<String>,
+
+        /// Only symbols carrying this attribute or annotation (`deprecated`,
+        /// `tokio::main`, `derive(Serialize)`); repeat to require several
+        #[arg(long, value_name = "ATTR")]
+        attr: Vec<String>,
 
         /// Use scoped name matching
         #[arg(long)]
@@ -131,30 +244,230 @@ enum Commands {
         /// Maximum results
         #[arg(short = 'n', long, default_value = "10")]
         limit: usize,
+
+        /// Rank matches by proximity to this file (same file, module, crate)
+        #[arg(long, value_name = "FILE")]
+        context_file: Option<PathBuf>,
+
+        /// Only include code reachable (via calls or imports) from this file or symbol
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        reachable_from: Option<String>,
+
+        /// Also list recently deleted symbols of that name
+        #[arg(long)]
+        include_deleted: bool,
+    },
+
+    /// Complete a symbol name prefix (`eng` -> `engine`, `Engine`, ...)
+    Complete {
+        /// Name prefix; include `::` to complete scoped names
+        prefix: String,
+
+        /// Maximum results
+        #[arg(short = 'n', long, default_value_t = omni_index::completion::DEFAULT_COMPLETION_LIMIT)]
+        limit: usize,
     },
 
     /// Find callers or callees of a symbol
     Calls {
-        /// Symbol to analyze
+        /// Symbol to analyze; a scoped name (`crate::json::Json::parse`)
+        /// lists only the calls resolved to that definition
         symbol: String,
 
         /// Direction: callers or callees
         #[arg(short, long, default_value = "callers")]
         direction: String,
+
+        /// Roll calls up into counts per module, crate, or file
+        #[arg(long, value_name = "module|crate|file")]
+        group_by: Option<String>,
+
+        /// Leave out calls made from test code
+        #[arg(long)]
+        exclude_tests: bool,
+
+        /// Follow calls transitively up to this many hops, as a tree
+        #[arg(long, default_value = "1")]
+        depth: u32,
+    },
+
+    /// Find every usage of a type, trait, or function: calls, struct
+    /// literals, type annotations, trait bounds, and `impl` blocks
+    References {
+        /// Name to find; a scoped name is matched by its last segment
+        name: String,
+
+        /// Leave out usages in test code
+        #[arg(long)]
+        exclude_tests: bool,
+    },
+
+    /// Show where a config key or environment variable is defined and read
+    ConfigRefs {
+        /// Dotted config key (`oci.workspace`) or env var name (`OCI_WORKSPACE`)
+        key: String,
+    },
+
+    /// Show a SQL table's current columns and the code querying it
+    SqlRefs {
+        /// Table (`users`), column (`users.email`), or a column name that
+        /// only one table has
+        name: String,
     },
 
     /// Run code analysis
     Analyze {
-        /// Analysis type: dead-code, coverage, churn
+        /// Analysis type: dead-code, binaries, generics, clones, test-clones, panics, risky-apis, seams, error-patterns, spelling, field-usage, coverage, churn, hotspots, or a registered custom analysis
         analysis_type: String,
+
+        /// Struct to report on, for field-usage (scoped or simple name)
+        #[arg(value_name = "TYPE")]
+        target: Option<String>,
+
+        /// Root the analysis at this binary's `main` (see `analyze binaries`)
+        #[arg(long, value_name = "NAME")]
+        binary: Option<String>,
+
+        /// Report symbols suppressed by `omni:ignore` comments too
+        #[arg(long)]
+        include_ignored: bool,
+
+        /// Coverage report for `coverage` (and, optionally, `hotspots`):
+        /// `cargo llvm-cov --json` or `cargo tarpaulin --out Json` output
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        /// Days of git history for `churn` and `hotspots`
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+
+        /// Only report dead code in files reachable (via calls or imports) from this file or symbol
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        reachable_from: Option<String>,
+
+        /// For dead-code, ignore test code: calls from tests do not keep
+        /// symbols alive and test helpers are not reported
+        #[arg(long)]
+        exclude_tests: bool,
+
+        /// Print every finding as `problems` (`file:line:col: severity:
+        /// message`, for editor problem matchers) or `sarif` (for code
+        /// scanning), for dead-code, clones, test-clones, panics, risky-apis,
+        /// error-patterns, and custom analyses
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<String>,
+    },
+
+    /// Check for findings, failing only on those not in the baseline
+    Check {
+        /// Record current findings as the baseline instead of checking
+        #[arg(long)]
+        write_baseline: bool,
+
+        /// Baseline file (default: <root>/.omni-baseline.json)
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Check symbols suppressed by `omni:ignore` comments too
+        #[arg(long)]
+        include_ignored: bool,
+    },
+
+    /// Rebuild the index and check its invariants (for CI)
+    Verify,
+
+    /// Show index size and health, including the duplication pressure
+    /// cached by the last semantic build
+    Stats,
+
+    /// Show what a change affects: every definition, call site, import, and
+    /// re-export a rename or signature change of a symbol touches, grouped
+    /// by file, or with `--crates` the crates a changed file rebuilds
+    Impact {
+        /// Symbol (scoped or simple name), or with `--crates` a changed file
+        /// (relative to the root or absolute)
+        target: String,
+
+        /// List the workspace crates that rebuild: the file's crate and every
+        /// crate depending on it, directly or transitively
+        #[arg(long)]
+        crates: bool,
+    },
+
+    /// List the impl blocks of a trait, or with `--method` the trait a
+    /// method is defined for
+    Impls {
+        /// Trait name or path; with `--method`, a method (scoped or simple
+        /// name)
+        name: String,
+
+        /// Show which trait the method comes from
+        #[arg(long)]
+        method: bool,
+    },
+
+    /// Rank the tests a change is likely to affect
+    Tests {
+        /// Changed file (relative to the root or absolute) or symbol; tests
+        /// calling into it, directly or transitively, are listed nearest first
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        affected_by: String,
+
+        /// Maximum number of tests to list
+        #[arg(short, long, default_value_t = omni_index::test_impact::DEFAULT_LIMIT)]
+        limit: usize,
+    },
+
+    /// Explain a symbol from index evidence: definition and docs, callers
+    /// and callees, related types, PageRank standing, tests, history, owners
+    Explain {
+        /// Symbol to explain (scoped or simple name)
+        symbol: String,
+
+        /// Maximum callers, callees, and tests to list
+        #[arg(short = 'n', long, default_value_t = omni_index::explain::DEFAULT_LIMIT)]
+        limit: usize,
+
+        /// llvm-cov or tarpaulin JSON report to read the symbol's line
+        /// coverage from
+        #[arg(long, value_name = "FILE")]
+        coverage: Option<PathBuf>,
+    },
+
+    /// Files and symbols that historically change together with a file or
+    /// symbol, mined from git history
+    CoChanges {
+        /// Indexed file (absolute or relative to the root) or symbol
+        #[arg(value_name = "FILE|SYMBOL")]
+        target: String,
+
+        /// Days of history to mine
+        #[arg(long, default_value = "180")]
+        days: u32,
+
+        /// Maximum files and symbols to list
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Suggest `//!` docs for modules that lack them
+    SuggestDocs {
+        /// File, directory, or module path (e.g. `crate::parsing`); every
+        /// module if omitted
+        module: Option<String>,
     },
 
     /// Export a context summary for downstream tools (e.g., Engram)
     Export {
-        /// Export format: engram
+        /// Export format: engram, jsonl-symbols, ctags, etags
         #[arg(long, default_value = "engram")]
         format: String,
 
+        /// Write the export to this file instead of stdout (jsonl-symbols,
+        /// ctags, etags)
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
         /// Max files to include in the summary
         #[arg(long, default_value = "20")]
         max_files: usize,
@@ -164,6 +477,33 @@ enum Commands {
         max_symbols: usize,
     },
 
+    /// Re-embed vectors produced by an outdated embedding model
+    Reembed {
+        /// Number of parallel embedding sessions (default: min(cores, 4))
+        #[arg(long)]
+        sessions: Option<usize>,
+
+        /// Texts per embedding batch
+        #[arg(long, default_value = "64")]
+        batch_size: usize,
+    },
+
+    /// Benchmark indexing and search on a generated repo, for comparable
+    /// performance numbers
+    Bench {
+        /// Small workload (100 files, 200 queries) that finishes in seconds
+        #[arg(long)]
+        quick: bool,
+
+        /// Generated module files (overrides the workload size)
+        #[arg(long)]
+        files: Option<usize>,
+
+        /// Queries to run (overrides the workload size)
+        #[arg(long)]
+        queries: Option<usize>,
+    },
+
     /// Search the index (Claudette interface)
     Search {
         /// Search query
@@ -176,6 +516,19 @@ enum Commands {
         /// Maximum results
         #[arg(short = 'n', long, default_value = "10")]
         limit: usize,
+
+        /// Only include code reachable (via calls or imports) from this file or symbol
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        reachable_from: Option<String>,
+
+        /// Read the unsaved contents of this file from stdin and search those
+        /// instead of what is on disk
+        #[arg(long, value_name = "PATH")]
+        stdin_file: Option<PathBuf>,
+
+        /// Leave out results from test code
+        #[arg(long)]
+        exclude_tests: bool,
     },
 }
 
@@ -191,26 +544,49 @@ async fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    if cli.schema {
+        let schema = schemars::schema_for!(Response);
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+    let Some(command) = &cli.command else {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingSubcommand,
+                "a command is required (or --schema)",
+            )
+            .exit();
+    };
     let root = cli.root.clone();
     let root = root.canonicalize().unwrap_or(root);
 
-    match run_command(&cli, &root).await {
+    match run_command(&cli, command, &root).await {
         Ok(output) => {
-            if cli.json {
-                let response = SuccessResponse {
-                    ok: true,
-                    data: output,
-                };
-                println!("{}", serde_json::to_string_pretty(&response)?);
+            let failed = output.is_failure();
+            if output.streamed_to_stdout() {
+                if cli.json {
+                    let response = SuccessResponse::new(&output);
+                    eprintln!("{}", cli.render_json(&response, true)?);
+                } else if let Output::ExportSymbols { symbols, .. } = &output {
+                    eprintln!("Exported {} symbols", symbols);
+                }
+            } else if cli.porcelain() {
+                println!("{}", cli.render_json(&SuccessResponse::new(output), false)?);
+            } else if cli.json {
+                println!("{}", cli.render_json(&SuccessResponse::new(output), true)?);
             } else {
                 print_human_readable(&output);
             }
+            if failed {
+                std::process::exit(1);
+            }
             Ok(())
         }
         Err(e) => {
-            if cli.json {
-                let response = error_response(&e);
-                eprintln!("{}", serde_json::to_string_pretty(&response)?);
+            if cli.porcelain() {
+                println!("{}", cli.render_json(&error_response(&e), false)?);
+            } else if cli.json {
+                eprintln!("{}", cli.render_json(&error_response(&e), true)?);
             } else {
                 eprintln!("Error: {}", e);
             }
@@ -219,11 +595,18 @@ async fn main() -> Result<()> {
     }
 }
 
-async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
+async fn run_command(cli: &Cli, command: &Commands, root: &std::path::Path) -> Result<Output> {
     let state = create_state(root.to_path_buf());
-    let indexer = IncrementalIndexer::new();
-
-    match &cli.command {
+    let indexer = IncrementalIndexer::new()
+        .with_shared(cli.shared)
+        .with_language_overrides(IndexConfig::load(root)?.overrides()?);
+    let package = cli
+        .package
+        .as_deref()
+        .map(|name| package_scope(root, name))
+        .transpose()?;
+
+    match command {
         Commands::Index {
             force,
             include,
@@ -232,7 +615,22 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
             include_hidden,
             include_large,
             max_file_size,
+            lang_overrides,
+            focus,
+            tombstone_retention_days,
+            quiet,
+            porcelain,
+            no_semantic,
         } => {
+            // The index stays whole, since calls cross packages; the
+            // package is parsed first and boosted like a focus path
+            let mut focus = focus.clone();
+            if let Some(scope) = &package {
+                let dir = scope.relative_dir(root);
+                if dir != "." && !focus.contains(&dir) {
+                    focus.push(dir);
+                }
+            }
             let options = IndexOptions {
                 force: *force,
                 include: include.clone(),
@@ -241,8 +639,31 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
                 include_hidden: *include_hidden,
                 include_large: *include_large,
                 max_file_size: *max_file_size,
+                language_overrides: lang_overrides.clone(),
+                focus,
+                tombstone_retention_days: *tombstone_retention_days,
+            };
+            let embed = !*no_semantic && IndexConfig::load(root)?.semantic;
+            let bar = index_progress_bar(*quiet || cli.json || *porcelain);
+            let mut on_event = |event: &IndexEvent| {
+                if !*porcelain {
+                    update_progress_bar(&bar, event);
+                } else if let Ok(line) = serde_json::to_string(event) {
+                    println!("{}", line);
+                }
+            };
+            let report = indexer
+                .index_with_progress(&state, root, &options, &mut on_event)
+                .await;
+            let embedded = match &report {
+                Ok(_) if embed => embed_phase(&state, &mut on_event),
+                _ => None,
             };
-            let report = indexer.index(&state, root, &options).await?;
+            bar.finish_and_clear();
+            let mut report = report?;
+            if let Some(elapsed) = embedded {
+                report.phases.push((IndexPhase::Embed, elapsed));
+            }
             let docs_total = omni_index::query::load_search_state(root)?
                 .map(|s| s.docs.len())
                 .unwrap_or(0);
@@ -251,8 +672,20 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
                 symbols: docs_total,
                 parsed: report.parsed_files,
                 skipped: report.skipped_files,
+                unchanged: report.unchanged_files,
+                reused: report.reused_files,
                 removed: report.removed_files,
                 root: root.display().to_string(),
+                phases: report
+                    .phases
+                    .iter()
+                    .map(|(phase, elapsed)| PhaseTiming {
+                        phase: phase.as_str(),
+                        elapsed_ms: elapsed.as_millis() as u64,
+                    })
+                    .collect(),
+                stack: state.stack.read().clone().unwrap_or_default(),
+                semantic: state.semantic_status.read().as_str().to_string(),
             })
         }
         Commands::IndexAll { workspaces } => {
@@ -280,11 +713,17 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
             query,
             top_k,
             filters,
+            reachable_from,
+            stdin_file,
         } => {
-            let (query_text, parsed_filters) = parse_query_filters(query, filters);
+            let (query_text, mut parsed_filters) = parse_query_filters(query, filters);
             if query_text.trim().is_empty() {
                 return Err(CliError::invalid_query("Query must include search terms").into());
             }
+            if let Some(spec) = reachable_from {
+                indexer.full_index_cached(&state, root).await?;
+                parsed_filters.files = Some(reachable_slice(&state, spec)?.relative_files(root));
+            }
 
             let mut index = load_search_index(root)?;
             if index.is_none() {
@@ -297,9 +736,24 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
                 index = load_search_index(root)?;
             }
 
-            let Some(index) = index else {
+            let Some(mut index) = index else {
                 return Err(CliError::index_missing("Index not found; run `omni index`").into());
             };
+            if let Some(file) = stdin_file {
+                overlay_stdin(&indexer, &state, root, file, &mut index).await?;
+            }
+            if let Some(scope) = &package {
+                let files: HashSet<String> = index
+                    .docs
+                    .iter()
+                    .filter(|doc| scope.contains_relative(root, &doc.file))
+                    .map(|doc| doc.file.clone())
+                    .collect();
+                parsed_filters.files = Some(match parsed_filters.files.take() {
+                    Some(slice) => slice.intersection(&files).cloned().collect(),
+                    None => files,
+                });
+            }
 
             let mut response = execute_query(&index, &query_text, *top_k, &parsed_filters);
             response.query = query.clone();
@@ -308,146 +762,989 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
 
         Commands::Symbol {
             name,
+            attr,
             scoped,
             limit,
+            context_file,
+            reachable_from,
+            include_deleted,
         } => {
-            indexer.full_index(&state, root).await?;
-
-            let symbols: Vec<SymbolDef> = if *scoped {
-                // For scoped lookup, try to find the symbol directly
-                let interned = state.intern(name);
-                state.get_symbol(interned).into_iter().collect()
-            } else {
-                state.find_by_name(name)
+            indexer.full_index_cached(&state, root).await?;
+            let slice = reachable_from
+                .as_deref()
+                .map(|spec| reachable_slice(&state, spec))
+                .transpose()?;
+
+            let queries = attr
+                .iter()
+                .map(|a| {
+                    Attribute::query(a).ok_or_else(|| anyhow::anyhow!("Invalid attribute: {}", a))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let symbols: Vec<SymbolDef> = match name {
+                None => state.find_by_attributes(&queries),
+                Some(name) if *scoped => {
+                    // For scoped lookup, try to find the symbol directly
+                    let interned = state.intern(name);
+                    state.get_symbol(interned).into_iter().collect()
+                }
+                Some(name) => match context_file {
+                    Some(file) => {
+                        let file = file.canonicalize().unwrap_or_else(|_| file.clone());
+                        state.find_by_name_near(name, &file)
+                    }
+                    None => state.find_by_name(name),
+                },
+            };
+            // Nothing under that name: try names it was renamed to
+            let symbols: Vec<(SymbolDef, Option<String>)> = match name {
+                Some(name) if symbols.is_empty() => RenameLog::load(root)
+                    .unwrap_or_default()
+                    .lookup(name)
+                    .into_iter()
+                    .filter_map(|alias| {
+                        let symbol = state.get_symbol(state.intern(&alias.new_name))?;
+                        Some((symbol, Some(alias.note())))
+                    })
+                    .collect(),
+                _ => symbols.into_iter().map(|s| (s, None)).collect(),
             };
 
             Ok(Output::Symbols {
-                query: name.clone(),
+                query: name.clone().unwrap_or_default(),
+                attributes: attr.clone(),
                 results: symbols
                     .into_iter()
+                    .filter(|(s, _)| s.has_attributes(&queries))
+                    .filter(|(s, _)| slice.as_ref().is_none_or(|r| in_slice(r, s)))
+                    .filter(|(s, _)| in_package(&package, &s.location.file))
                     .take(*limit)
-                    .map(|s| SymbolResult {
-                        name: state.resolve(s.scoped_name).to_string(),
-                        kind: format!("{:?}", s.kind),
-                        file: s.location.file.display().to_string(),
-                        line: s.location.start_line,
+                    .map(|(s, renamed)| SymbolResult {
+                        renamed,
+                        attributes: s
+                            .attributes
+                            .iter()
+                            .filter(|a| queries.iter().any(|q| a.matches(q)))
+                            .map(ToString::to_string)
+                            .collect(),
+                        ..SymbolResult::new(&state, &s)
                     })
                     .collect(),
+                deleted: match name {
+                    Some(name) if *include_deleted => TombstoneLog::load(root)?
+                        .lookup(name)
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                    _ => Vec::new(),
+                },
             })
         }
 
-        Commands::Calls { symbol, direction } => {
-            indexer.full_index(&state, root).await?;
+        Commands::Complete { prefix, limit } => {
+            indexer.full_index_cached(&state, root).await?;
+            Ok(Output::Completions {
+                prefix: prefix.clone(),
+                results: state.complete_symbol(prefix, *limit),
+            })
+        }
 
-            let results: Vec<CallResult> = match direction.as_str() {
-                "callers" => state
-                    .find_callers(symbol)
-                    .into_iter()
-                    .map(|edge| CallResult {
-                        caller: state.resolve(edge.caller).to_string(),
-                        callee: edge.callee_name.clone(),
-                        file: edge.location.file.display().to_string(),
-                        line: edge.location.start_line,
-                    })
-                    .collect(),
-                "callees" => {
-                    // For callees, we need the scoped name of the caller
-                    let symbols = state.find_by_name(symbol);
-                    let mut all_callees = Vec::new();
-                    for sym in symbols {
-                        let edges = state.find_callees(sym.scoped_name);
-                        for edge in edges {
-                            all_callees.push(CallResult {
-                                caller: state.resolve(edge.caller).to_string(),
-                                callee: edge.callee_name.clone(),
-                                file: edge.location.file.display().to_string(),
-                                line: edge.location.start_line,
+        Commands::Calls {
+            symbol,
+            direction,
+            group_by,
+            exclude_tests,
+            depth,
+        } => {
+            let grouping = group_by
+                .as_deref()
+                .map(str::parse::<CallGrouping>)
+                .transpose()
+                .map_err(|e| anyhow::anyhow!(e))?;
+            indexer.full_index_cached(&state, root).await?;
+
+            if *depth > 1 && grouping.is_none() {
+                let side = match direction.as_str() {
+                    "callers" => CallSide::Caller,
+                    "callees" => CallSide::Callee,
+                    _ => return Err(anyhow::anyhow!("Direction must be 'callers' or 'callees'")),
+                };
+                let mut tree = call_tree(&state, symbol, side, *depth, *exclude_tests);
+                if package.is_some() {
+                    retain_call_tree(&mut tree, &|node| in_package(&package, &node.file));
+                }
+                return Ok(Output::CallTree {
+                    direction: direction.clone(),
+                    tree,
+                });
+            }
+
+            // A scoped name asks for the calls bound to that definition
+            let bound = symbol
+                .contains("::")
+                .then(|| state.lookup(symbol))
+                .flatten()
+                .filter(|key| state.symbols.contains_key(key));
+            let (mut edges, side) = match direction.as_str() {
+                "callers" => match bound {
+                    Some(key) => (state.find_resolved_callers(key), CallSide::Caller),
+                    None => (state.find_callers(symbol), CallSide::Caller),
+                },
+                "callees" => (
+                    state
+                        .find_by_name(symbol)
+                        .iter()
+                        .flat_map(|sym| state.find_callees(sym.scoped_name))
+                        .collect(),
+                    CallSide::Callee,
+                ),
+                _ => return Err(anyhow::anyhow!("Direction must be 'callers' or 'callees'")),
+            };
+            edges.retain(|e| in_package(&package, &e.location.file));
+            let counts = TestSplit::count(edges.iter().map(|e| e.is_test_context));
+            if *exclude_tests {
+                edges.retain(|e| !e.is_test_context);
+            }
+
+            if let Some(grouping) = grouping {
+                return Ok(Output::Calls {
+                    symbol: symbol.clone(),
+                    direction: direction.clone(),
+                    counts,
+                    results: Vec::new(),
+                    groups: group_calls(&state, &edges, grouping, side),
+                    candidates: Vec::new(),
+                });
+            }
+
+            // A shared name: split callers by the definition they most
+            // likely call
+            if direction == "callers" && bound.is_none() {
+                let mut candidates = resolve_callers(&state, symbol);
+                if candidates.len() > 1 {
+                    if *exclude_tests || package.is_some() {
+                        for c in &mut candidates {
+                            c.callers.retain(|r| {
+                                !(*exclude_tests && r.is_test_context)
+                                    && in_package(&package, &root.join(&r.file))
                             });
                         }
+                        candidates.sort_by_key(|c| std::cmp::Reverse(c.callers.len()));
                     }
-                    all_callees
+                    return Ok(Output::Calls {
+                        symbol: symbol.clone(),
+                        direction: direction.clone(),
+                        counts,
+                        results: Vec::new(),
+                        groups: Vec::new(),
+                        candidates,
+                    });
                 }
-                _ => return Err(anyhow::anyhow!("Direction must be 'callers' or 'callees'")),
-            };
+            }
+
+            let results = edges
+                .into_iter()
+                .map(|edge| CallResult::new(&state, &edge))
+                .collect();
 
             Ok(Output::Calls {
                 symbol: symbol.clone(),
                 direction: direction.clone(),
+                counts,
                 results,
+                groups: Vec::new(),
+                candidates: Vec::new(),
+            })
+        }
+
+        Commands::References {
+            name,
+            exclude_tests,
+        } => {
+            indexer.full_index_cached(&state, root).await?;
+            let mut refs = state.find_references(name);
+            refs.retain(|r| in_package(&package, &r.location.file));
+            let counts = TestSplit::count(refs.iter().map(|r| r.is_test_context));
+            if *exclude_tests {
+                refs.retain(|r| !r.is_test_context);
+            }
+            Ok(Output::References {
+                name: name.clone(),
+                counts,
+                results: refs
+                    .iter()
+                    .map(|r| ReferenceResult::new(&state, r))
+                    .collect(),
             })
         }
 
+        Commands::ConfigRefs { key } => {
+            indexer.full_index_cached(&state, root).await?;
+            Ok(Output::ConfigRefs {
+                refs: find_config_refs(&state, key)?,
+            })
+        }
+
+        Commands::SqlRefs { name } => {
+            indexer.full_index_cached(&state, root).await?;
+            let refs = find_sql_refs(&state, name).ok_or_else(|| {
+                CliError::invalid_query(&format!("No table or column in the SQL schema: {}", name))
+            })?;
+            Ok(Output::SqlRefs { refs })
+        }
+
         #[cfg(feature = "analysis")]
-        Commands::Analyze { analysis_type } => match analysis_type.as_str() {
-            "dead-code" => {
-                indexer.full_index(&state, root).await?;
-                let analyzer = DeadCodeAnalyzer::new();
-                let report = analyzer.analyze(&state);
-
-                Ok(Output::DeadCode {
-                    dead_count: report.dead_symbols.len(),
-                    symbols: report
-                        .dead_symbols
+        Commands::Analyze {
+            analysis_type,
+            target,
+            file,
+            days,
+            binary,
+            include_ignored,
+            reachable_from,
+            exclude_tests,
+            format,
+        } => {
+            if let Some(format) = format {
+                let supported = [
+                    "dead-code",
+                    "clones",
+                    "test-clones",
+                    "panics",
+                    "risky-apis",
+                    "error-patterns",
+                ];
+                if !matches!(format.as_str(), "problems" | "sarif") {
+                    return Err(CliError::invalid_query(&format!(
+                        "Unknown format: {}. Use: problems, sarif",
+                        format
+                    ))
+                    .into());
+                }
+                if !supported.contains(&analysis_type.as_str())
+                    && custom::find(analysis_type).is_none()
+                {
+                    return Err(CliError::invalid_query(&format!(
+                        "--format is not available for {}. Use it with: {}",
+                        analysis_type,
+                        supported.join(", ")
+                    ))
+                    .into());
+                }
+            }
+            let symbol_result = |scoped_name| {
+                state
+                    .get_symbol(scoped_name)
+                    .map(|s| SymbolResult::new(&state, &s))
+            };
+            let find_binary = |name: &str| -> Result<omni_index::BinaryTarget> {
+                let targets = discover_binaries(root)?;
+                let available: Vec<String> = targets.iter().map(|t| t.name.clone()).collect();
+                targets.into_iter().find(|t| t.name == name).ok_or_else(|| {
+                    CliError::invalid_query(&format!(
+                        "Unknown binary: {}. Available: {}",
+                        name,
+                        available.join(", ")
+                    ))
+                    .into()
+                })
+            };
+
+            // Restrict dead-code reports to files in the reachability slice
+            // and the package
+            let in_slice_files =
+                |state: &OciState, dead: Vec<omni_index::InternedString>| -> Result<Vec<_>> {
+                    let slice = reachable_from
+                        .as_deref()
+                        .map(|spec| reachable_slice(state, spec))
+                        .transpose()?;
+                    Ok(dead
                         .into_iter()
-                        .take(50) // Limit output
-                        .filter_map(|scoped_name| {
-                            state.get_symbol(scoped_name).map(|s| SymbolResult {
-                                name: state.resolve(s.scoped_name).to_string(),
-                                kind: format!("{:?}", s.kind),
-                                file: s.location.file.display().to_string(),
-                                line: s.location.start_line,
+                        .filter(|s| {
+                            state.get_symbol(*s).is_some_and(|s| {
+                                slice
+                                    .as_ref()
+                                    .is_none_or(|slice| slice.contains_file(&s.location.file))
+                                    && in_package(&package, &s.location.file)
                             })
                         })
-                        .collect(),
-                })
+                        .collect())
+                };
+            // Reports give root-relative files
+            let in_package_relative = |file: &str| {
+                package
+                    .as_ref()
+                    .is_none_or(|scope| scope.contains_relative(root, file))
+            };
+
+            match (analysis_type.as_str(), binary) {
+                ("dead-code", None) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let analyzer = DeadCodeAnalyzer::new()
+                        .include_ignored(*include_ignored)
+                        .exclude_tests(*exclude_tests);
+                    let report = analyzer.analyze(&state);
+                    let dead = in_slice_files(&state, report.dead_symbols)?;
+                    if let Some(format) = format {
+                        let problems =
+                            problems::from_dead_code(&state, root, &dead, &report.explanations);
+                        return emit_problems(format, &problems);
+                    }
+
+                    Ok(Output::DeadCode {
+                        dead_count: dead.len(),
+                        counts: test_split(&state, &dead),
+                        symbols: dead
+                            .into_iter()
+                            .take(50) // Limit output
+                            .filter_map(|scoped_name| {
+                                let symbol = symbol_result(scoped_name)?;
+                                Some(DeadResult::new(
+                                    &state,
+                                    symbol,
+                                    report.explanations.get(&scoped_name),
+                                ))
+                            })
+                            .collect(),
+                    })
+                }
+                ("dead-code", Some(name)) => {
+                    let target = find_binary(name)?;
+                    indexer.full_index_cached(&state, root).await?;
+                    let analyzer = BinaryAnalyzer::new().include_ignored(*include_ignored);
+                    let mut unused = in_slice_files(
+                        &state,
+                        analyzer.unreachable_from(&state, &analyzer.reachability(&state, &target)),
+                    )?;
+                    if *exclude_tests {
+                        unused.retain(|&s| !state.get_symbol(s).is_some_and(|s| s.is_test_context));
+                    }
+                    if let Some(format) = format {
+                        let problems =
+                            problems::from_dead_code(&state, root, &unused, &Default::default());
+                        return emit_problems(format, &problems);
+                    }
+
+                    Ok(Output::DeadCode {
+                        dead_count: unused.len(),
+                        counts: test_split(&state, &unused),
+                        symbols: unused
+                            .into_iter()
+                            .take(50)
+                            .filter_map(symbol_result)
+                            .map(|symbol| DeadResult::new(&state, symbol, None))
+                            .collect(),
+                    })
+                }
+                ("binaries", None) => {
+                    let mut targets = discover_binaries(root)?;
+                    targets.retain(|t| in_package(&package, &t.path));
+                    indexer.full_index_cached(&state, root).await?;
+                    let report = BinaryAnalyzer::new().analyze(&state, &targets);
+
+                    Ok(Output::Binaries {
+                        binaries: report
+                            .binaries
+                            .iter()
+                            .map(|b| BinaryResult {
+                                name: b.target.name.clone(),
+                                kind: b.target.kind.as_str().to_string(),
+                                package: b.target.package.clone(),
+                                file: b.target.path.display().to_string(),
+                                reachable: b.reachable.len(),
+                            })
+                            .collect(),
+                        unused_count: report.unused.len(),
+                        unused: report
+                            .unused
+                            .into_iter()
+                            .take(50)
+                            .filter_map(symbol_result)
+                            .collect(),
+                    })
+                }
+                ("binaries", Some(name)) => {
+                    let target = find_binary(name)?;
+                    indexer.full_index_cached(&state, root).await?;
+                    let reach = BinaryAnalyzer::new().reachability(&state, &target);
+
+                    Ok(Output::BinaryGraph {
+                        binary: target.name.clone(),
+                        entry: reach.entry.map(|e| state.resolve(e).to_string()),
+                        symbols: reach
+                            .reachable
+                            .iter()
+                            .filter_map(|&(scoped_name, depth)| {
+                                symbol_result(scoped_name)
+                                    .map(|symbol| ReachableResult { symbol, depth })
+                            })
+                            .collect(),
+                    })
+                }
+                ("generics", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut hotspots = GenericsAnalyzer::new().analyze(&state);
+                    hotspots.retain(|h| {
+                        state
+                            .get_symbol(h.symbol)
+                            .is_some_and(|s| in_package(&package, &s.location.file))
+                    });
+
+                    Ok(Output::Generics {
+                        hotspots: hotspots
+                            .into_iter()
+                            .take(50)
+                            .filter_map(|h| {
+                                let symbol = symbol_result(h.symbol)?;
+                                Some(GenericResult {
+                                    symbol,
+                                    call_sites: h.call_sites,
+                                    instantiations: h.instantiations,
+                                })
+                            })
+                            .collect(),
+                    })
+                }
+                ("clones", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut groups = CloneAnalyzer::new()
+                        .with_config(CloneConfig {
+                            include_ignored: *include_ignored,
+                            ..Default::default()
+                        })
+                        .analyze(&state, root);
+                    groups.retain(|g| g.functions.iter().any(|f| in_package_relative(&f.file)));
+                    if let Some(format) = format {
+                        return emit_problems(
+                            format,
+                            &problems::from_clones(&state, root, &groups),
+                        );
+                    }
+                    Ok(Output::Clones { groups })
+                }
+                ("test-clones", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut groups = TestCloneAnalyzer::new()
+                        .with_config(TestCloneConfig {
+                            include_ignored: *include_ignored,
+                            ..Default::default()
+                        })
+                        .analyze(&state, root);
+                    // Clones reaching into the package stay whole
+                    groups.retain(|g| g.tests.iter().any(|t| in_package_relative(&t.file)));
+                    if let Some(format) = format {
+                        return emit_problems(
+                            format,
+                            &problems::from_test_clones(&state, root, &groups),
+                        );
+                    }
+                    Ok(Output::TestClones { groups })
+                }
+                ("panics", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = PanicAnalyzer::new().analyze(&state, root);
+                    report.sites.retain(|s| in_package_relative(&s.file));
+                    report.entry_points.retain(|e| in_package_relative(&e.file));
+                    if let Some(format) = format {
+                        return emit_problems(format, &problems::from_panic_sites(&report.sites));
+                    }
+                    Ok(Output::Panics {
+                        site_count: report.sites.len(),
+                        sites: report.sites.into_iter().take(50).collect(),
+                        entry_points: report.entry_points.into_iter().take(50).collect(),
+                    })
+                }
+                ("risky-apis", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = RiskyApiAnalyzer::new().analyze(&state, root);
+                    report.sites.retain(|s| in_package_relative(&s.file));
+                    report.entry_points.retain(|e| in_package_relative(&e.file));
+                    if let Some(format) = format {
+                        return emit_problems(format, &problems::from_risky_sites(&report.sites));
+                    }
+                    Ok(Output::RiskyApis {
+                        site_count: report.sites.len(),
+                        sites: report.sites.into_iter().take(50).collect(),
+                        entry_points: report.entry_points.into_iter().take(50).collect(),
+                    })
+                }
+                ("seams", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = SeamAnalyzer::new().analyze(&state, root);
+                    report.seams.retain(|s| in_package_relative(&s.file));
+                    Ok(Output::Seams {
+                        seams: report.seams,
+                    })
+                }
+                ("error-patterns", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = ErrorPatternAnalyzer::new().analyze(&state, root);
+                    report
+                        .patterns
+                        .retain(|p| p.sites.iter().any(|s| in_package_relative(&s.file)));
+                    if let Some(format) = format {
+                        let problems = problems::from_error_patterns(&report.patterns);
+                        return emit_problems(format, &problems);
+                    }
+                    Ok(Output::ErrorPatterns {
+                        patterns: report.patterns,
+                    })
+                }
+                ("spelling", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let config = SpellingConfig::load(root)?;
+                    let mut report = SpellingAnalyzer::new().with_config(&config).analyze(&state);
+                    report.clusters.retain(|c| {
+                        c.variants
+                            .iter()
+                            .flat_map(|v| &v.examples)
+                            .any(|e| in_package_relative(&e.file))
+                    });
+                    Ok(Output::Spelling {
+                        clusters: report.clusters,
+                    })
+                }
+                ("field-usage", _) => {
+                    let Some(name) = target else {
+                        return Err(CliError::invalid_query(
+                            "field-usage needs a struct: omni analyze field-usage <TYPE>",
+                        )
+                        .into());
+                    };
+                    indexer.full_index_cached(&state, root).await?;
+                    let report = FieldUsageAnalyzer::new()
+                        .analyze(&state, root, name)
+                        .map_err(|e| CliError::invalid_query(&e.to_string()))?;
+                    Ok(Output::FieldUsage { report })
+                }
+                ("coverage", _) => {
+                    let Some(path) = file else {
+                        return Err(CliError::invalid_query(
+                            "coverage needs a report: omni analyze coverage --file <FILE>",
+                        )
+                        .into());
+                    };
+                    let path = if path.is_absolute() {
+                        path.clone()
+                    } else {
+                        root.join(path)
+                    };
+                    let coverage = CoverageAnalyzer::load(&path)?;
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = CoverageAnalyzer::report(&state, &coverage, root);
+                    report
+                        .least_covered
+                        .retain(|f| in_package_relative(&f.file));
+                    report
+                        .untested_public
+                        .retain(|f| in_package_relative(&f.file));
+                    report.functions = report.least_covered.len();
+                    report.lines_covered =
+                        report.least_covered.iter().map(|f| f.lines_covered).sum();
+                    report.lines_total = report.least_covered.iter().map(|f| f.lines_total).sum();
+                    report.least_covered.truncate(50);
+                    Ok(Output::Coverage { report })
+                }
+                ("churn", _) => {
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = ChurnAnalyzer::analyze_index(&state, root, *days)?;
+                    let in_package_path =
+                        |path: &std::path::Path| in_package_relative(&path.to_string_lossy());
+                    report.file_churn.retain(|f| in_package_path(&f.path));
+                    report.hotspots.retain(|(path, _)| in_package_path(path));
+                    report.symbols.retain(|s| in_package_path(&s.file));
+                    let files_changed = report.file_churn.len();
+                    report.file_churn.truncate(50);
+                    report.symbols.truncate(50);
+                    Ok(Output::Churn {
+                        files_changed,
+                        report,
+                    })
+                }
+                ("hotspots", _) => {
+                    let mut analyzer = HotspotAnalyzer::new().with_days(*days);
+                    if let Some(path) = file {
+                        let path = if path.is_absolute() {
+                            path.clone()
+                        } else {
+                            root.join(path)
+                        };
+                        analyzer = analyzer.with_coverage(CoverageAnalyzer::load(&path)?);
+                    }
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut report = analyzer.analyze(&state, root)?;
+                    report.files.retain(|h| in_package_relative(&h.file));
+                    report.symbols.retain(|s| in_package_relative(&s.file));
+                    Ok(Output::Hotspots { report })
+                }
+                (other, _) => {
+                    let Some(analysis) = custom::find(other) else {
+                        let custom: Vec<String> = custom::registered()
+                            .iter()
+                            .map(|a| format!(", {}", a.name()))
+                            .collect();
+                        return Err(anyhow::anyhow!(
+                            "Unknown analysis type: {}. Use: dead-code, binaries, generics, clones, test-clones, panics, risky-apis, seams, error-patterns, spelling, field-usage, coverage, churn, hotspots{}",
+                            other,
+                            custom.concat()
+                        ));
+                    };
+                    indexer.full_index_cached(&state, root).await?;
+                    let mut findings = analysis.run(&state)?;
+                    findings.retain(|f| in_package_relative(&f.file));
+                    if let Some(format) = format {
+                        return emit_problems(format, &findings);
+                    }
+                    Ok(Output::Custom {
+                        analysis: analysis.name().to_string(),
+                        findings,
+                    })
+                }
             }
-            other => Err(anyhow::anyhow!(
-                "Unknown analysis type: {}. Use: dead-code",
-                other
-            )),
-        },
+        }
 
         #[cfg(not(feature = "analysis"))]
         Commands::Analyze { .. } => Err(anyhow::anyhow!(
             "Analysis requires the 'analysis' feature.\n\
              Rebuild with: cargo build --features analysis"
         )),
-        Commands::Export {
-            format,
-            max_files,
-            max_symbols,
+
+        #[cfg(feature = "analysis")]
+        Commands::Check {
+            write_baseline,
+            baseline,
+            include_ignored,
         } => {
-            indexer.full_index(&state, root).await?;
-            match format.as_str() {
-                "engram" => {
-                    let export = export_engram_memory(&state, root, *max_files, *max_symbols)?;
-                    Ok(Output::ExportEngram { export })
-                }
-                other => Err(anyhow::anyhow!(
-                    "Unknown export format: {}. Use: engram",
-                    other
-                )),
+            let baseline_path = baseline
+                .clone()
+                .unwrap_or_else(|| root.join(check::BASELINE_FILE));
+            indexer.full_index_cached(&state, root).await?;
+            let findings = check::run_checks(&state, root, *include_ignored);
+
+            if *write_baseline {
+                check::Baseline::from_findings(&findings).save(&baseline_path)?;
+                return Ok(Output::Check {
+                    baseline: Some(baseline_path.display().to_string()),
+                    baseline_written: true,
+                    findings: findings.len(),
+                    baselined: findings.len(),
+                    new: Vec::new(),
+                    fixed: Vec::new(),
+                });
             }
+
+            let loaded = check::Baseline::load(&baseline_path)?;
+            let diff = loaded.as_ref().map_or_else(
+                || check::Baseline::default().diff(&findings),
+                |b| b.diff(&findings),
+            );
+            Ok(Output::Check {
+                baseline: loaded.map(|_| baseline_path.display().to_string()),
+                baseline_written: false,
+                findings: findings.len(),
+                baselined: diff.baselined,
+                new: diff.new,
+                fixed: diff.fixed,
+            })
         }
 
-        Commands::Search {
-            query,
-            workspace,
-            limit,
-        } => {
-            // Resolve workspace: -w flag overrides global --root
-            let search_root = workspace.as_ref().unwrap_or(&cli.root);
-            let search_root = search_root
-                .canonicalize()
-                .unwrap_or_else(|_| search_root.clone());
+        #[cfg(not(feature = "analysis"))]
+        Commands::Check { .. } => Err(anyhow::anyhow!(
+            "Check requires the 'analysis' feature.\n\
+             Rebuild with: cargo build --features analysis"
+        )),
+        Commands::Verify => {
+            indexer.full_index_cached(&state, root).await?;
+            Ok(Output::Verify {
+                report: omni_index::verify::verify(&state),
+            })
+        }
 
-            // Delegate to query logic
-            let (query_text, parsed_filters) = parse_query_filters(query, &[]);
-            if query_text.trim().is_empty() {
+        Commands::Stats => {
+            indexer.full_index_cached(&state, root).await?;
+            let stats = state.stats();
+            Ok(Output::Stats {
+                files: stats.file_count as usize,
+                symbols: stats.symbol_count as usize,
+                call_edges: stats.call_edge_count as usize,
+                topology_nodes: stats.topology_node_count as usize,
+                duplication: DuplicationPressure::load(root).ok().flatten(),
+                embedding_drift: stats.embedding_drift,
+            })
+        }
+
+        Commands::Impact { target, crates } => {
+            indexer.full_index_cached(&state, root).await?;
+            if !crates {
+                let impact = rename_impact(&state, target).ok_or_else(|| {
+                    CliError::invalid_query(&format!("No symbol found: {}", target))
+                })?;
+                return Ok(Output::RenameImpact { impact });
+            }
+            let file = PathBuf::from(target);
+            let path = if file.is_absolute() {
+                file.clone()
+            } else {
+                root.join(&file)
+            };
+            let path = path.canonicalize().unwrap_or(path);
+            let impact = crate_impact(&state, &path).ok_or_else(|| {
+                CliError::invalid_query(&format!(
+                    "{} is not inside a workspace crate",
+                    file.display()
+                ))
+            })?;
+            Ok(Output::Impact {
+                file: file.display().to_string(),
+                impact,
+            })
+        }
+
+        Commands::Impls { name, method } => {
+            indexer.full_index_cached(&state, root).await?;
+            if *method {
+                let origins = trait_origins(&state, name).ok_or_else(|| {
+                    CliError::invalid_query(&format!("No method found: {}", name))
+                })?;
+                return Ok(Output::TraitOrigins {
+                    method: name.clone(),
+                    origins,
+                });
+            }
+            let implementations = implementations(&state, name).ok_or_else(|| {
+                CliError::invalid_query(&format!("No trait defined or implemented: {}", name))
+            })?;
+            Ok(Output::Implementations { implementations })
+        }
+
+        Commands::Tests { affected_by, limit } => {
+            indexer.full_index_cached(&state, root).await?;
+            let affected = affected_tests(&state, affected_by, *limit).ok_or_else(|| {
+                CliError::invalid_query(&format!("No file or symbol found: {}", affected_by))
+            })?;
+            Ok(Output::AffectedTests {
+                affected_by: affected_by.clone(),
+                affected,
+            })
+        }
+
+        Commands::Explain {
+            symbol,
+            limit,
+            coverage,
+        } => {
+            indexer.full_index_cached(&state, root).await?;
+            #[allow(unused_mut)]
+            let mut explanation = explain(&state, symbol, *limit)
+                .ok_or_else(|| CliError::invalid_query(&format!("No symbol found: {}", symbol)))?;
+            if let Some(path) = coverage {
+                #[cfg(feature = "analysis")]
+                {
+                    let path = if path.is_absolute() {
+                        path.clone()
+                    } else {
+                        root.join(path)
+                    };
+                    let data = CoverageAnalyzer::load(&path)?;
+                    let scoped = state.lookup(&explanation.symbol);
+                    explanation.coverage = CoverageAnalyzer::correlate_symbols(&state, &data)
+                        .into_iter()
+                        .find(|c| Some(c.symbol) == scoped)
+                        .map(|c| omni_index::explain::CoverageSummary {
+                            lines_covered: c.lines_covered,
+                            lines_total: c.lines_total,
+                            branches_covered: c.branches_covered,
+                            branches_total: c.branches_total,
+                        });
+                }
+                #[cfg(not(feature = "analysis"))]
+                {
+                    let _ = path;
+                    return Err(anyhow::anyhow!(
+                        "--coverage requires the 'analysis' feature.\n\
+                         Rebuild with: cargo build --features analysis"
+                    ));
+                }
+            }
+            Ok(Output::Explain {
+                explanation: Box::new(explanation),
+            })
+        }
+
+        #[cfg(feature = "analysis")]
+        Commands::CoChanges {
+            target,
+            days,
+            limit,
+        } => {
+            indexer.full_index_cached(&state, root).await?;
+            let mut report =
+                CoChangeAnalyzer::analyze(&state, root, target, *days)?.ok_or_else(|| {
+                    CliError::invalid_query(&format!(
+                        "Unknown co-change target: {} (expected an indexed file or symbol)",
+                        target
+                    ))
+                })?;
+            report
+                .files
+                .retain(|f| in_package(&package, &root.join(&f.file)));
+            report
+                .symbols
+                .retain(|s| in_package(&package, &root.join(&s.file)));
+            report.files.truncate(*limit);
+            report.symbols.truncate(*limit);
+            Ok(Output::CoChanges { report })
+        }
+
+        #[cfg(not(feature = "analysis"))]
+        Commands::CoChanges { .. } => Err(anyhow::anyhow!(
+            "Co-change mining requires the 'analysis' feature.\n\
+             Rebuild with: cargo build --features analysis"
+        )),
+
+        #[cfg(feature = "context")]
+        Commands::SuggestDocs { module } => {
+            indexer.full_index_cached(&state, root).await?;
+            let (suggestions, documented) =
+                omni_index::context::suggest_module_docs(&state, module.as_deref()).await;
+            Ok(Output::SuggestDocs {
+                suggestions,
+                documented,
+            })
+        }
+
+        #[cfg(not(feature = "context"))]
+        Commands::SuggestDocs { .. } => Err(anyhow::anyhow!(
+            "Doc suggestions require the 'context' feature.\n\
+             Rebuild with: cargo build --features context"
+        )),
+
+        Commands::Export {
+            format,
+            output,
+            max_files,
+            max_symbols,
+        } => {
+            if !matches!(
+                format.as_str(),
+                "engram" | "jsonl-symbols" | "ctags" | "etags"
+            ) {
+                return Err(anyhow::anyhow!(
+                    "Unknown export format: {}. Use: engram, jsonl-symbols, ctags, etags",
+                    format
+                ));
+            }
+            indexer.full_index_cached(&state, root).await?;
+            match format.as_str() {
+                "engram" => {
+                    let export = export_engram_memory(&state, root, *max_files, *max_symbols)?;
+                    Ok(Output::ExportEngram { export })
+                }
+                format => {
+                    let out: Box<dyn std::io::Write> = match output {
+                        Some(path) => {
+                            let file =
+                                std::fs::File::create(path).map_err(|e| OmniError::io(path, e))?;
+                            Box::new(std::io::BufWriter::new(file))
+                        }
+                        None => Box::new(std::io::stdout().lock()),
+                    };
+                    let symbols = match format {
+                        "ctags" => export_ctags(&state, root, out)?,
+                        "etags" => export_etags(&state, root, out)?,
+                        _ => export_jsonl_symbols(&state, root, out)?,
+                    };
+                    Ok(Output::ExportSymbols {
+                        symbols,
+                        output: output.as_ref().map(|p| p.display().to_string()),
+                    })
+                }
+            }
+        }
+
+        #[cfg(feature = "semantic")]
+        Commands::Reembed {
+            sessions,
+            batch_size,
+        } => {
+            indexer.full_index_cached(&state, root).await?;
+            let mut pool =
+                omni_index::semantic::EmbeddingPoolConfig::default().with_batch_size(*batch_size);
+            if let Some(sessions) = sessions {
+                pool = pool.with_sessions(*sessions);
+            }
+            let index = omni_index::semantic::build_index_with(&state, pool)?;
+            let before = index.drift_report();
+            let migrated = index.reembed(&state)?;
+            let after = index.drift_report();
+
+            Ok(Output::Reembed {
+                current_model: after.current_model,
+                vectors: after.vectors_by_model.values().sum(),
+                stale_before: before.stale_vectors,
+                migrated,
+            })
+        }
+
+        #[cfg(not(feature = "semantic"))]
+        Commands::Reembed { .. } => Err(anyhow::anyhow!(
+            "Re-embedding requires the 'semantic' feature.\n\
+             Rebuild with: cargo build --features semantic"
+        )),
+
+        Commands::Bench {
+            quick,
+            files,
+            queries,
+        } => {
+            let mut options = if *quick {
+                BenchOptions::quick()
+            } else {
+                BenchOptions::default()
+            };
+            options.files = files.unwrap_or(options.files);
+            options.queries = queries.unwrap_or(options.queries);
+            if !cli.json {
+                eprintln!(
+                    "Benchmarking {} files and {} queries...",
+                    options.files, options.queries
+                );
+            }
+            Ok(Output::Bench {
+                report: omni_index::bench::run(options).await?,
+            })
+        }
+
+        Commands::Search {
+            query,
+            workspace,
+            limit,
+            reachable_from,
+            stdin_file,
+            exclude_tests,
+        } => {
+            // Resolve workspace: -w flag overrides global --root
+            let search_root = workspace.as_ref().unwrap_or(&cli.root);
+            let search_root = search_root
+                .canonicalize()
+                .unwrap_or_else(|_| search_root.clone());
+
+            // Delegate to query logic
+            let (query_text, mut parsed_filters) = parse_query_filters(query, &[]);
+            if query_text.trim().is_empty() {
                 return Err(CliError::invalid_query("Query must include search terms").into());
             }
+            parsed_filters.exclude_tests = *exclude_tests;
 
             let search_state = create_state(search_root.clone());
+            if let Some(spec) = reachable_from {
+                indexer
+                    .full_index_cached(&search_state, &search_root)
+                    .await?;
+                parsed_filters.files =
+                    Some(reachable_slice(&search_state, spec)?.relative_files(&search_root));
+            }
             let mut index = load_search_index(&search_root)?;
             if index.is_none() {
                 if !cli.json {
@@ -459,23 +1756,39 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
                 index = load_search_index(&search_root)?;
             }
 
-            let Some(index) = index else {
+            let Some(mut index) = index else {
                 return Err(CliError::index_missing("Index not found; run `omni index`").into());
             };
+            if let Some(file) = stdin_file {
+                overlay_stdin(&indexer, &search_state, &search_root, file, &mut index).await?;
+            }
 
-            let response = execute_query(&index, &query_text, *limit, &parsed_filters);
+            let response = search_with_fallback(
+                &index,
+                &query_text,
+                *limit,
+                &parsed_filters,
+                None,
+                &Budget::unlimited(),
+            );
 
             // Return in Search-specific format for backward compat
             Ok(Output::Search {
+                strategy: response.strategy,
+                counts: TestSplit::count(response.results.iter().map(|r| r.is_test_context)),
                 results: response
                     .results
                     .into_iter()
                     .map(|r| SearchResult {
                         symbol: r.symbol,
-                        kind: "symbol".to_string(), // Default kind since QueryResult doesn't have it
+                        kind: r.kind.as_str().to_string(),
                         file: r.file,
                         line: r.start_line,
                         score: r.score,
+                        role: r.role,
+                        is_test_context: r.is_test_context,
+                        deprecated: r.deprecated,
+                        renamed: r.renamed,
                     })
                     .collect(),
             })
@@ -483,7 +1796,7 @@ async fn run_command(cli: &Cli, root: &std::path::Path) -> Result<Output> {
     }
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 enum Output {
     Index {
@@ -491,8 +1804,18 @@ enum Output {
         symbols: usize,
         parsed: usize,
         skipped: usize,
+        /// Skipped files that were touched but whose content is unchanged
+        unchanged: usize,
+        /// Changed files whose docs came from the branch's index snapshot
+        reused: usize,
         removed: usize,
         root: String,
+        phases: Vec<PhaseTiming>,
+        /// Languages, frameworks, build systems, and test runners
+        stack: StackReport,
+        /// Semantic index status after the embed phase ("not built" when
+        /// opted out)
+        semantic: String,
     },
     IndexAll {
         results: Vec<IndexAllResult>,
@@ -503,40 +1826,306 @@ enum Output {
     },
     Symbols {
         query: String,
+        /// Attribute filters (`--attr`)
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        attributes: Vec<String>,
         results: Vec<SymbolResult>,
+        /// Recently deleted symbols of that name (`--include-deleted`)
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        deleted: Vec<Tombstone>,
+    },
+    Completions {
+        prefix: String,
+        results: Vec<Completion>,
     },
     Calls {
         symbol: String,
         direction: String,
+        /// Calls from production and test code, before `--exclude-tests`
+        counts: TestSplit,
         results: Vec<CallResult>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        groups: Vec<CallGroup>,
+        /// Callers split across same-named definitions
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        candidates: Vec<CallerCandidate>,
+    },
+    CallTree {
+        direction: String,
+        #[serde(flatten)]
+        tree: CallTree,
+    },
+    References {
+        name: String,
+        /// Usages from production and test code, before `--exclude-tests`
+        counts: TestSplit,
+        results: Vec<ReferenceResult>,
+    },
+    ConfigRefs {
+        #[serde(flatten)]
+        refs: ConfigRefs,
+    },
+    SqlRefs {
+        #[serde(flatten)]
+        refs: SqlRefs,
     },
     #[cfg(feature = "analysis")]
     DeadCode {
         dead_count: usize,
-        symbols: Vec<SymbolResult>,
+        /// Dead symbols in production and test code
+        counts: TestSplit,
+        symbols: Vec<DeadResult>,
+    },
+    #[cfg(feature = "analysis")]
+    Binaries {
+        binaries: Vec<BinaryResult>,
+        unused_count: usize,
+        unused: Vec<SymbolResult>,
+    },
+    #[cfg(feature = "analysis")]
+    BinaryGraph {
+        binary: String,
+        entry: Option<String>,
+        symbols: Vec<ReachableResult>,
+    },
+    #[cfg(feature = "analysis")]
+    Generics {
+        hotspots: Vec<GenericResult>,
+    },
+    #[cfg(feature = "analysis")]
+    Clones {
+        groups: Vec<omni_index::analysis::CloneGroup>,
+    },
+    #[cfg(feature = "analysis")]
+    Churn {
+        files_changed: usize,
+        #[serde(flatten)]
+        report: omni_index::analysis::ChurnReport,
+    },
+    #[cfg(feature = "analysis")]
+    Hotspots {
+        #[serde(flatten)]
+        report: omni_index::analysis::HotspotReport,
+    },
+    #[cfg(feature = "analysis")]
+    CoChanges {
+        #[serde(flatten)]
+        report: omni_index::analysis::CoChangeReport,
+    },
+    #[cfg(feature = "analysis")]
+    Coverage {
+        #[serde(flatten)]
+        report: omni_index::analysis::CoverageReport,
+    },
+    #[cfg(feature = "analysis")]
+    TestClones {
+        groups: Vec<omni_index::analysis::TestCloneGroup>,
+    },
+    #[cfg(feature = "analysis")]
+    Panics {
+        site_count: usize,
+        sites: Vec<omni_index::analysis::panics::PanicSite>,
+        entry_points: Vec<omni_index::analysis::panics::PanicEntryPoint>,
+    },
+    #[cfg(feature = "analysis")]
+    RiskyApis {
+        site_count: usize,
+        sites: Vec<omni_index::analysis::risky_apis::RiskySite>,
+        entry_points: Vec<omni_index::analysis::risky_apis::RiskyEntryPoint>,
+    },
+    #[cfg(feature = "analysis")]
+    Seams {
+        seams: Vec<omni_index::analysis::seams::Seam>,
+    },
+    #[cfg(feature = "analysis")]
+    ErrorPatterns {
+        patterns: Vec<omni_index::analysis::error_patterns::ErrorPattern>,
+    },
+    #[cfg(feature = "analysis")]
+    Spelling {
+        clusters: Vec<omni_index::analysis::spelling::SpellingCluster>,
+    },
+    #[cfg(feature = "analysis")]
+    FieldUsage {
+        #[serde(flatten)]
+        report: omni_index::analysis::FieldUsageReport,
+    },
+    /// Findings of an analysis registered by another crate
+    #[cfg(feature = "analysis")]
+    Custom {
+        analysis: String,
+        findings: Vec<problems::Problem>,
+    },
+    #[cfg(feature = "analysis")]
+    Check {
+        baseline: Option<String>,
+        baseline_written: bool,
+        findings: usize,
+        baselined: usize,
+        new: Vec<check::Finding>,
+        fixed: Vec<check::BaselineEntry>,
+    },
+    Verify {
+        #[serde(flatten)]
+        report: omni_index::verify::VerifyReport,
+    },
+    Stats {
+        files: usize,
+        symbols: usize,
+        call_edges: usize,
+        topology_nodes: usize,
+        /// Near-duplicate symbols, once a semantic index has been built
+        duplication: Option<DuplicationPressure>,
+        /// Models of the cached embeddings, once a semantic index has been
+        /// built
+        embedding_drift: Option<EmbeddingDriftReport>,
+    },
+    Impact {
+        file: String,
+        #[serde(flatten)]
+        impact: CrateImpact,
+    },
+    RenameImpact {
+        #[serde(flatten)]
+        impact: RenameImpact,
+    },
+    Implementations {
+        #[serde(flatten)]
+        implementations: Implementations,
+    },
+    TraitOrigins {
+        method: String,
+        /// Empty for inherent methods
+        origins: Vec<TraitOrigin>,
+    },
+    AffectedTests {
+        affected_by: String,
+        #[serde(flatten)]
+        affected: AffectedTests,
+    },
+    Explain {
+        #[serde(flatten)]
+        explanation: Box<Explanation>,
+    },
+    #[cfg(feature = "context")]
+    SuggestDocs {
+        suggestions: Vec<omni_index::context::ModuleDocSuggestion>,
+        /// Matching modules skipped because they already have docs
+        documented: usize,
     },
     ExportEngram {
         export: omni_index::export::EngramMemoryExport,
     },
+    Bench {
+        #[serde(flatten)]
+        report: BenchReport,
+    },
+    ExportSymbols {
+        symbols: usize,
+        /// Output file, or `None` when streamed to stdout
+        output: Option<String>,
+    },
+    /// Analysis findings streamed to stdout by `analyze --format`
+    #[cfg(feature = "analysis")]
+    Problems {
+        /// `problems` or `sarif`
+        format: String,
+        problems: usize,
+    },
+    #[cfg(feature = "semantic")]
+    Reembed {
+        current_model: String,
+        vectors: usize,
+        stale_before: usize,
+        migrated: usize,
+    },
     Search {
+        strategy: SearchStrategy,
+        /// Results from production and test code
+        counts: TestSplit,
         results: Vec<SearchResult>,
     },
 }
 
-#[derive(serde::Serialize)]
-struct SuccessResponse<T> {
-    ok: bool,
-    #[serde(flatten)]
-    data: T,
+impl Cli {
+    /// Whether output is a stream of JSON lines (`index --porcelain`).
+    fn porcelain(&self) -> bool {
+        matches!(
+            self.command,
+            Some(Commands::Index {
+                porcelain: true,
+                ..
+            })
+        )
+    }
+
+    /// Serialize a response in the requested `--schema-version`.
+    fn render_json(&self, response: &impl serde::Serialize, pretty: bool) -> Result<String> {
+        // Current output is written directly, keeping its field order
+        if self.schema_version == SCHEMA_VERSION {
+            return Ok(if pretty {
+                serde_json::to_string_pretty(response)?
+            } else {
+                serde_json::to_string(response)?
+            });
+        }
+        let mut value = serde_json::to_value(response)?;
+        downgrade(&mut value, self.schema_version)?;
+        Ok(if pretty {
+            serde_json::to_string_pretty(&value)?
+        } else {
+            serde_json::to_string(&value)?
+        })
+    }
+}
+
+impl Output {
+    /// Whether the command succeeded but should still exit non-zero.
+    fn is_failure(&self) -> bool {
+        match self {
+            #[cfg(feature = "analysis")]
+            Self::Check { new, .. } => !new.is_empty(),
+            Self::Verify { report } => !report.is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Whether the command wrote its data to stdout, so the summary must go
+    /// to stderr to keep the stream parseable.
+    fn streamed_to_stdout(&self) -> bool {
+        match self {
+            Self::ExportSymbols { output, .. } => output.is_none(),
+            #[cfg(feature = "analysis")]
+            Self::Problems { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+struct PhaseTiming {
+    phase: &'static str,
+    elapsed_ms: u64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 struct ErrorResponse {
     ok: bool,
+    /// Version of the output format (see `--schema-version`)
+    schema_version: u32,
     error: ErrorInfo,
 }
 
-#[derive(serde::Serialize)]
+/// Any `--json` response, for `--schema`.
+#[derive(schemars::JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum Response {
+    Success(SuccessResponse<Output>),
+    Error(ErrorResponse),
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
 struct ErrorInfo {
     code: String,
     message: String,
@@ -573,6 +2162,7 @@ fn error_response(err: &anyhow::Error) -> ErrorResponse {
     if let Some(cli_err) = err.downcast_ref::<CliError>() {
         return ErrorResponse {
             ok: false,
+            schema_version: SCHEMA_VERSION,
             error: ErrorInfo {
                 code: cli_err.code().to_string(),
                 message: cli_err.to_string(),
@@ -580,9 +2170,21 @@ fn error_response(err: &anyhow::Error) -> ErrorResponse {
             },
         };
     }
+    if let Some(omni_err) = err.downcast_ref::<OmniError>() {
+        return ErrorResponse {
+            ok: false,
+            schema_version: SCHEMA_VERSION,
+            error: ErrorInfo {
+                code: omni_err.code().to_string(),
+                message: omni_err.to_string(),
+                details: None,
+            },
+        };
+    }
 
     ErrorResponse {
         ok: false,
+        schema_version: SCHEMA_VERSION,
         error: ErrorInfo {
             code: "internal".to_string(),
             message: err.to_string(),
@@ -591,7 +2193,7 @@ fn error_response(err: &anyhow::Error) -> ErrorResponse {
     }
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 struct IndexAllResult {
     workspace: String,
     files: usize,
@@ -599,29 +2201,215 @@ struct IndexAllResult {
     call_edges: usize,
 }
 
-#[derive(serde::Serialize)]
-struct SymbolResult {
+#[cfg(feature = "analysis")]
+#[derive(serde::Serialize, schemars::JsonSchema)]
+struct BinaryResult {
     name: String,
     kind: String,
+    package: String,
     file: String,
-    line: usize,
+    reachable: usize,
 }
 
-#[derive(serde::Serialize)]
-struct CallResult {
-    caller: String,
-    callee: String,
-    file: String,
-    line: usize,
+#[cfg(feature = "analysis")]
+#[derive(serde::Serialize, schemars::JsonSchema)]
+struct GenericResult {
+    #[serde(flatten)]
+    symbol: SymbolResult,
+    call_sites: usize,
+    instantiations: Vec<String>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 struct SearchResult {
     symbol: String,
     kind: String,
     file: String,
     line: usize,
     score: f32,
+    role: String,
+    /// From test code
+    is_test_context: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renamed: Option<String>,
+}
+
+/// Progress bar for `omni index` on stderr (not drawn unless stderr is a terminal).
+fn index_progress_bar(hidden: bool) -> ProgressBar {
+    if hidden {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} {msg:<12} [{bar:30}] {pos}/{len} {elapsed}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> "),
+    );
+    bar
+}
+
+fn update_progress_bar(bar: &ProgressBar, event: &IndexEvent) {
+    match event {
+        IndexEvent::PhaseStarted { phase, total } => {
+            bar.set_message(phase.as_str());
+            bar.set_length(*total as u64);
+            bar.set_position(0);
+        }
+        IndexEvent::Progress { done, .. } => bar.set_position(*done as u64),
+        IndexEvent::PhaseFinished { .. } => bar.tick(),
+        IndexEvent::FocusReady { files } => {
+            bar.println(format!("{} focus files searchable", files));
+        }
+    }
+}
+
+/// Build the semantic index after `omni index`, as its embed phase. Without
+/// the embedding model search stays BM25-only, so a failure is only logged
+/// and left in the semantic status.
+#[cfg(feature = "semantic")]
+fn embed_phase(state: &OciState, on_event: impl FnMut(&IndexEvent)) -> Option<Duration> {
+    let pool = omni_index::semantic::EmbeddingPoolConfig::default();
+    match omni_index::semantic::load_as_phase(state, pool, on_event) {
+        Ok(elapsed) => Some(elapsed),
+        Err(e) => {
+            tracing::warn!("Semantic index not built, search is BM25-only: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "semantic"))]
+fn embed_phase(_state: &OciState, _on_event: impl FnMut(&IndexEvent)) -> Option<Duration> {
+    None
+}
+
+/// Overlay stdin as the unsaved contents of `file` (`--stdin-file`) and swap
+/// its docs into `index`.
+async fn overlay_stdin(
+    indexer: &IncrementalIndexer,
+    state: &OciState,
+    root: &std::path::Path,
+    file: &std::path::Path,
+    index: &mut SearchIndex,
+) -> Result<()> {
+    let mut contents = String::new();
+    std::io::stdin().read_to_string(&mut contents)?;
+    let path = root.join(file);
+    indexer.overlay_file(state, &path, root, contents).await?;
+    apply_overlays(index, state);
+    Ok(())
+}
+
+/// Resolve a `--reachable-from` root to its slice.
+fn reachable_slice(state: &OciState, spec: &str) -> Result<ReachableSet> {
+    reachable_from(state, spec).ok_or_else(|| {
+        CliError::invalid_query(&format!(
+            "Unknown reachability root: {} (expected an indexed file or symbol)",
+            spec
+        ))
+        .into()
+    })
+}
+
+/// The scope of `--package`, among the packages under `root`.
+fn package_scope(root: &std::path::Path, name: &str) -> Result<PackageScope> {
+    let packages = FileDiscovery::new().discover_packages(root)?;
+    PackageScope::find(&packages, name).ok_or_else(|| {
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        CliError::invalid_query(&format!(
+            "Unknown package: {}. Available: {}",
+            name,
+            names.join(", ")
+        ))
+        .into()
+    })
+}
+
+/// Whether `file` (absolute) is in the `--package` scope, if one is set.
+fn in_package(package: &Option<PackageScope>, file: &std::path::Path) -> bool {
+    package
+        .as_ref()
+        .is_none_or(|scope| scope.contains_file(file))
+}
+
+/// Drop call tree nodes failing `keep`, along with their subtrees, and
+/// recount the symbols left.
+fn retain_call_tree(tree: &mut CallTree, keep: &dyn Fn(&CallTreeNode) -> bool) {
+    fn retain(
+        nodes: &mut Vec<CallTreeNode>,
+        keep: &dyn Fn(&CallTreeNode) -> bool,
+        symbols: &mut HashSet<String>,
+    ) {
+        nodes.retain(|node| keep(node));
+        for node in nodes {
+            symbols.insert(node.symbol.clone());
+            retain(&mut node.children, keep, symbols);
+        }
+    }
+    let mut symbols = HashSet::new();
+    retain(&mut tree.children, keep, &mut symbols);
+    tree.symbols = symbols.len();
+}
+
+/// Whether a symbol is part of the slice (scoped names alone are ambiguous across files).
+fn in_slice(slice: &ReachableSet, symbol: &SymbolDef) -> bool {
+    slice.contains_symbol(symbol.scoped_name) && slice.contains_file(&symbol.location.file)
+}
+
+/// ` [test]` marker for human-readable listings.
+fn test_suffix(is_test_context: bool) -> &'static str {
+    if is_test_context { " [test]" } else { "" }
+}
+
+/// Production/test breakdown of indexed symbols.
+#[cfg(feature = "analysis")]
+fn test_split(state: &OciState, symbols: &[omni_index::InternedString]) -> TestSplit {
+    TestSplit::count(
+        symbols
+            .iter()
+            .filter_map(|&s| state.get_symbol(s))
+            .map(|s| s.is_test_context),
+    )
+}
+
+/// Production/test breakdown line for human-readable listings.
+fn print_test_split(counts: &TestSplit) {
+    println!(
+        "{} from production code, {} from tests",
+        counts.production, counts.tests
+    );
+}
+
+/// ` [deprecated: note]` marker for human-readable listings.
+fn deprecated_suffix(note: Option<&str>) -> String {
+    match note {
+        Some("") => " [deprecated]".to_string(),
+        Some(note) => format!(" [deprecated: {}]", note),
+        None => String::new(),
+    }
+}
+
+/// Indented rename note under a listing entry.
+fn print_renamed(note: Option<&str>) {
+    if let Some(note) = note {
+        println!("      ({})", note);
+    }
+}
+
+/// Print `problems` to stdout in `format` (`problems` or `sarif`).
+#[cfg(feature = "analysis")]
+fn emit_problems(format: &str, problems: &[problems::Problem]) -> Result<Output> {
+    let text = match format {
+        "sarif" => serde_json::to_string_pretty(&problems::to_sarif(problems))? + "\n",
+        _ => problems::render_problems(problems),
+    };
+    std::io::Write::write_all(&mut std::io::stdout().lock(), text.as_bytes())?;
+    Ok(Output::Problems {
+        format: format.to_string(),
+        problems: problems.len(),
+    })
 }
 
 fn print_human_readable(output: &Output) {
@@ -631,14 +2419,31 @@ fn print_human_readable(output: &Output) {
             symbols,
             parsed,
             skipped,
+            unchanged,
+            reused,
             removed,
             root,
+            phases,
+            stack,
+            semantic,
         } => {
             println!("Indexed {} files, {} symbols", files, symbols);
+            if !stack.is_empty() {
+                println!("Stack: {}", stack.summary());
+            }
             println!(
-                "Parsed: {}, skipped: {}, removed: {}",
-                parsed, skipped, removed
+                "Parsed: {}, skipped: {} ({} touched but unchanged), removed: {}",
+                parsed, skipped, unchanged, removed
             );
+            if *reused > 0 {
+                println!("Reused {} files from the branch's index snapshot", reused);
+            }
+            let phases: Vec<String> = phases
+                .iter()
+                .map(|p| format!("{} {}ms", p.phase, p.elapsed_ms))
+                .collect();
+            println!("Phases: {}", phases.join(", "));
+            println!("Semantic index: {}", semantic);
             println!("Root: {}", root);
         }
         Output::IndexAll { results } => {
@@ -655,53 +2460,1088 @@ fn print_human_readable(output: &Output) {
             println!("Found {} results:", response.results.len());
             for r in &response.results {
                 println!(
-                    "  {:.2} {} at {}:{}",
-                    r.score, r.symbol, r.file, r.start_line
+                    "  {:.2} {} at {}:{} [{}]{}",
+                    r.score,
+                    r.symbol,
+                    r.file,
+                    r.start_line,
+                    r.role,
+                    deprecated_suffix(r.deprecated.as_deref())
                 );
+                print_renamed(r.renamed.as_deref());
             }
         }
-        Output::Symbols { query, results } => {
-            println!("Symbol: \"{}\"", query);
+        Output::Symbols {
+            query,
+            attributes,
+            results,
+            deleted,
+        } => {
+            match (query.is_empty(), attributes.is_empty()) {
+                (false, true) => println!("Symbol: \"{}\"", query),
+                (true, _) => println!("Attributes: {}", attributes.join(", ")),
+                (false, false) => {
+                    println!("Symbol: \"{}\" with {}", query, attributes.join(", "))
+                }
+            }
             println!("Found {} matches:", results.len());
             for s in results {
-                println!("  {} ({}) at {}:{}", s.name, s.kind, s.file, s.line);
+                println!(
+                    "  {} ({}) at {}:{}{}",
+                    s.name,
+                    s.kind,
+                    s.file,
+                    s.line,
+                    deprecated_suffix(s.deprecated.as_deref())
+                );
+                if let Some(path) = &s.canonical_path {
+                    println!("      path: {}", path);
+                }
+                if !s.attributes.is_empty() {
+                    println!("      attributes: {}", s.attributes.join(", "));
+                }
+                print_renamed(s.renamed.as_deref());
+            }
+            if !deleted.is_empty() {
+                println!("Deleted:");
+                for t in deleted {
+                    println!("  {} (generation {})", t.note(), t.generation);
+                }
+            }
+        }
+        Output::Completions { prefix, results } => {
+            println!("Completions for \"{}\":", prefix);
+            for c in results {
+                if c.definitions > 1 {
+                    println!(
+                        "  {} ({}, {} definitions)",
+                        c.name,
+                        c.kinds.join("/"),
+                        c.definitions
+                    );
+                } else {
+                    println!("  {} ({})", c.name, c.kinds.join("/"));
+                }
+            }
+        }
+        Output::Calls {
+            symbol,
+            direction,
+            counts,
+            groups,
+            ..
+        } if !groups.is_empty() => {
+            let total: usize = groups.iter().map(|g| g.count).sum();
+            println!("{} of \"{}\":", direction, symbol);
+            print_test_split(counts);
+            println!("Found {} calls in {} groups:", total, groups.len());
+            for g in groups {
+                println!("  {}: {} calls", g.group, g.count);
+                for e in &g.examples {
+                    println!(
+                        "    e.g. {} -> {} at {}:{}",
+                        e.caller,
+                        e.callee,
+                        e.file.display(),
+                        e.line
+                    );
+                }
+            }
+        }
+        Output::Calls {
+            symbol,
+            direction,
+            counts,
+            candidates,
+            ..
+        } if !candidates.is_empty() => {
+            println!("{} of \"{}\":", direction, symbol);
+            print_test_split(counts);
+            println!(
+                "Found {} definitions named \"{}\":",
+                candidates.len(),
+                symbol
+            );
+            for c in candidates {
+                println!(
+                    "  {} at {}:{} ({} callers)",
+                    c.callee,
+                    c.file.display(),
+                    c.line,
+                    c.callers.len()
+                );
+                for r in &c.callers {
+                    println!(
+                        "    {} at {}:{} ({:.0}%, {}){}",
+                        r.caller,
+                        r.file.display(),
+                        r.line,
+                        r.confidence * 100.0,
+                        r.resolution.as_str(),
+                        test_suffix(r.is_test_context)
+                    );
+                }
             }
         }
         Output::Calls {
             symbol,
             direction,
+            counts,
             results,
+            ..
         } => {
             println!("{} of \"{}\":", direction, symbol);
+            print_test_split(counts);
             println!("Found {} results:", results.len());
             for c in results {
-                println!("  {} -> {} at {}:{}", c.caller, c.callee, c.file, c.line);
+                println!(
+                    "  {} -> {} at {}:{}{}",
+                    c.caller,
+                    c.resolved.as_deref().unwrap_or(&c.callee),
+                    c.file,
+                    c.line,
+                    test_suffix(c.is_test_context)
+                );
+            }
+        }
+        Output::CallTree { direction, tree } => {
+            println!(
+                "{} of \"{}\" within {} hops:",
+                direction, tree.root, tree.depth
+            );
+            println!("Found {} symbols:", tree.symbols);
+            print!("{}", tree.render());
+        }
+        Output::References {
+            name,
+            counts,
+            results,
+        } => {
+            println!("References to \"{}\":", name);
+            print_test_split(counts);
+            println!("Found {} results:", results.len());
+            for r in results {
+                println!(
+                    "  {} in {} at {}:{}{}",
+                    r.kind.as_str(),
+                    r.from.as_deref().unwrap_or("<top level>"),
+                    r.file,
+                    r.line,
+                    test_suffix(r.is_test_context)
+                );
+            }
+        }
+        Output::ConfigRefs { refs } => {
+            println!("Config key \"{}\":", refs.key);
+            println!("Defined in {} places:", refs.definitions.len());
+            for d in &refs.definitions {
+                match &d.value {
+                    Some(value) => {
+                        println!("  {} = {} at {}:{}", d.key, value, d.file.display(), d.line)
+                    }
+                    None => println!("  {} at {}:{}", d.key, d.file.display(), d.line),
+                }
+            }
+            println!("Read in {} places:", refs.readers.len());
+            for r in &refs.readers {
+                println!(
+                    "  {} \"{}\" in {} at {}:{}",
+                    r.access.as_str(),
+                    r.matched,
+                    r.symbol.as_deref().unwrap_or("<top level>"),
+                    r.file.display(),
+                    r.line
+                );
+            }
+        }
+        Output::SqlRefs { refs } => {
+            let table = &refs.table;
+            println!(
+                "Table {} at {}:{}:",
+                table.name,
+                table.file.display(),
+                table.line
+            );
+            for c in &table.columns {
+                if refs.column.as_ref().is_some_and(|name| *name != c.name) {
+                    continue;
+                }
+                println!(
+                    "  {} {} at {}:{}",
+                    c.name,
+                    c.data_type.as_deref().unwrap_or("-"),
+                    c.file.display(),
+                    c.line
+                );
+            }
+            println!("Queried in {} places:", refs.queries.len());
+            for q in &refs.queries {
+                println!(
+                    "  {} at {}:{}",
+                    q.symbol.as_deref().unwrap_or("<top level>"),
+                    q.file.display(),
+                    q.line
+                );
+                println!("    {}", q.query);
             }
         }
         #[cfg(feature = "analysis")]
         Output::DeadCode {
             dead_count,
+            counts,
             symbols,
         } => {
             println!("Dead code analysis:");
-            println!("Found {} potentially dead symbols", dead_count);
+            println!(
+                "Found {} potentially dead symbols ({} in production code, {} in tests)",
+                dead_count, counts.production, counts.tests
+            );
             if !symbols.is_empty() {
                 println!("Top results:");
                 for s in symbols {
-                    println!("  {} ({}) at {}:{}", s.name, s.kind, s.file, s.line);
+                    let d = &s.symbol;
+                    println!("  {} ({}) at {}:{}", d.name, d.kind, d.file, d.line);
+                    for step in &s.why {
+                        println!("    - {}", step);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Binaries {
+            binaries,
+            unused_count,
+            unused,
+        } => {
+            println!("Found {} binaries:", binaries.len());
+            for b in binaries {
+                println!(
+                    "  {} ({}, {}) at {}: {} reachable symbols",
+                    b.name, b.kind, b.package, b.file, b.reachable
+                );
+            }
+            println!("{} functions are not reached by any binary", unused_count);
+            for s in unused {
+                println!("  {} ({}) at {}:{}", s.name, s.kind, s.file, s.line);
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::BinaryGraph {
+            binary,
+            entry,
+            symbols,
+        } => {
+            println!(
+                "Binary \"{}\" (entry: {}) reaches {} symbols:",
+                binary,
+                entry.as_deref().unwrap_or("main not indexed"),
+                symbols.len()
+            );
+            for r in symbols {
+                println!(
+                    "  [{}] {} ({}) at {}:{}",
+                    r.depth, r.symbol.name, r.symbol.kind, r.symbol.file, r.symbol.line
+                );
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Generics { hotspots } => {
+            println!("Generic instantiation hotspots:");
+            for h in hotspots {
+                println!(
+                    "  {} at {}:{}: {} distinct instantiations over {} call sites",
+                    h.symbol.name,
+                    h.symbol.file,
+                    h.symbol.line,
+                    h.instantiations.len(),
+                    h.call_sites
+                );
+                if !h.instantiations.is_empty() {
+                    println!("    {}", h.instantiations.join(", "));
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Clones { groups } => {
+            println!(
+                "Found {} groups of functions with duplicated bodies:",
+                groups.len()
+            );
+            for g in groups {
+                println!(
+                    "  {} functions, {:.0}% similar, {} duplicated tokens",
+                    g.functions.len(),
+                    g.similarity * 100.0,
+                    g.duplicated_tokens
+                );
+                for f in &g.functions {
+                    println!(
+                        "    {} at {}:{}-{} ({} tokens)",
+                        f.symbol, f.file, f.line, f.end_line, f.tokens
+                    );
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Churn {
+            files_changed,
+            report,
+        } => {
+            println!(
+                "Churn over the last {} days: {} files changed",
+                report.days, files_changed
+            );
+            for f in &report.file_churn {
+                println!(
+                    "  {}: {} commits, +{} -{}, {} authors, last {}",
+                    f.path.display(),
+                    f.commits,
+                    f.lines_added,
+                    f.lines_removed,
+                    f.authors.len(),
+                    f.last_modified
+                );
+            }
+            println!("\nMost changed symbols:");
+            for s in &report.symbols {
+                println!(
+                    "  {} ({}) at {}:{}: {} commits, {} lines changed",
+                    s.symbol,
+                    s.kind,
+                    s.file.display(),
+                    s.line,
+                    s.commits,
+                    s.lines_changed
+                );
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Hotspots { report } => {
+            println!(
+                "Hotspots over the last {} days (risk = commits x complexity x (2 - coverage) x (1 + centrality)):",
+                report.days
+            );
+            for h in &report.files {
+                let coverage = h
+                    .coverage
+                    .map(|c| format!("{:.0}% covered, ", c * 100.0))
+                    .unwrap_or_default();
+                println!(
+                    "  {}: risk {:.1} ({} commits, complexity {}, {}centrality {:.2})",
+                    h.file, h.risk, h.commits, h.complexity, coverage, h.centrality
+                );
+            }
+            println!("\nRiskiest functions:");
+            for s in &report.symbols {
+                let coverage = s
+                    .coverage
+                    .map(|c| format!(", {:.0}% covered", c * 100.0))
+                    .unwrap_or_default();
+                println!(
+                    "  {} at {}:{}: risk {:.1} ({} commits, complexity {}{})",
+                    s.symbol, s.file, s.line, s.risk, s.commits, s.complexity, coverage
+                );
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Coverage { report } => {
+            println!(
+                "Coverage: {} functions, {}/{} lines ({:.1}%)",
+                report.functions,
+                report.lines_covered,
+                report.lines_total,
+                if report.lines_total == 0 {
+                    0.0
+                } else {
+                    report.lines_covered as f32 * 100.0 / report.lines_total as f32
+                }
+            );
+            println!(
+                "\nUntested public functions ({}):",
+                report.untested_public.len()
+            );
+            for f in &report.untested_public {
+                println!(
+                    "  {} at {}:{} ({} lines)",
+                    f.symbol, f.file, f.line, f.lines_total
+                );
+            }
+            println!("\nLeast covered:");
+            for f in &report.least_covered {
+                print!(
+                    "  {} at {}:{}: {:.1}% ({}/{} lines",
+                    f.symbol,
+                    f.file,
+                    f.line,
+                    f.coverage * 100.0,
+                    f.lines_covered,
+                    f.lines_total
+                );
+                if f.branches_total > 0 {
+                    print!(", {}/{} branches", f.branches_covered, f.branches_total);
+                }
+                println!(")");
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::TestClones { groups } => {
+            println!(
+                "Found {} groups of tests with copy-pasted setup:",
+                groups.len()
+            );
+            for g in groups {
+                println!("  {} ({:.0}% similar)", g.suggestion, g.similarity * 100.0);
+                for t in &g.tests {
+                    println!(
+                        "    {} at {}:{} ({} setup lines)",
+                        t.test, t.file, t.line, t.setup_lines
+                    );
+                }
+                for line in &g.shared_setup {
+                    println!("      | {}", line);
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Panics {
+            site_count,
+            sites,
+            entry_points,
+        } => {
+            println!(
+                "Found {} panic sites, reachable from {} public functions:",
+                site_count,
+                entry_points.len()
+            );
+            for e in entry_points {
+                let kinds: Vec<String> = e
+                    .kinds
+                    .iter()
+                    .map(|(kind, n)| format!("{} {}", n, kind.as_str()))
+                    .collect();
+                println!(
+                    "  {} at {}:{}: {} sites ({}), {} direct",
+                    e.symbol,
+                    e.file,
+                    e.line,
+                    e.sites,
+                    kinds.join(", "),
+                    e.direct
+                );
+                println!("    via {}", e.via.join(", "));
+            }
+            if !sites.is_empty() {
+                println!("Sites:");
+            }
+            for s in sites {
+                println!(
+                    "  {} in {} at {}:{}",
+                    s.kind.as_str(),
+                    s.symbol,
+                    s.file,
+                    s.line
+                );
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::RiskyApis {
+            site_count,
+            sites,
+            entry_points,
+        } => {
+            println!(
+                "Found {} risky API sites, reachable from {} entry points:",
+                site_count,
+                entry_points.len()
+            );
+            for e in entry_points {
+                let categories: Vec<String> = e
+                    .categories
+                    .iter()
+                    .map(|(category, n)| format!("{} {}", n, category.as_str()))
+                    .collect();
+                println!(
+                    "  {} at {}:{}: {} sites ({})",
+                    e.symbol,
+                    e.file,
+                    e.line,
+                    e.sites,
+                    categories.join(", ")
+                );
+                for c in &e.chains {
+                    let categories: Vec<&str> = c.categories.iter().map(|c| c.as_str()).collect();
+                    println!("    {}: {}", categories.join(", "), c.path.join(" -> "));
+                }
+            }
+            if !sites.is_empty() {
+                println!("Sites:");
+            }
+            for s in sites {
+                let detail = s
+                    .detail
+                    .as_ref()
+                    .map(|d| format!(" ({})", d))
+                    .unwrap_or_default();
+                println!(
+                    "  {} {}{} in {} at {}:{}",
+                    s.category.as_str(),
+                    s.api,
+                    detail,
+                    s.symbol,
+                    s.file,
+                    s.line
+                );
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Seams { seams } => {
+            println!("Found {} seams:", seams.len());
+            for s in seams {
+                println!("  {} {} at {}:{}", s.kind.as_str(), s.name, s.file, s.line);
+                if let Some(detail) = &s.detail {
+                    println!("    {}", detail);
+                }
+                if !s.impls.is_empty() {
+                    println!("    impls: {}", s.impls.join(", "));
+                }
+                for c in &s.construction_sites {
+                    println!("    constructed in {} at {}:{}", c.symbol, c.file, c.line);
+                }
+                if s.construction_count > s.construction_sites.len() {
+                    println!(
+                        "    ... and {} more",
+                        s.construction_count - s.construction_sites.len()
+                    );
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::ErrorPatterns { patterns } => {
+            println!("Found {} repeated error patterns:", patterns.len());
+            for p in patterns {
+                println!("  {} ({} sites, {})", p.shape, p.count, p.fix.as_str());
+                println!("    {}", p.suggestion);
+                for s in &p.sites {
+                    println!("    in {} at {}:{}", s.symbol, s.file, s.line);
+                }
+                if p.count > p.sites.len() {
+                    println!("    ... and {} more", p.count - p.sites.len());
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Spelling { clusters } => {
+            println!("Found {} inconsistently spelled words:", clusters.len());
+            for c in clusters {
+                println!("  {} ({} symbols)", c.preferred, c.total);
+                for v in &c.variants {
+                    let note = if v.misspelled { " [misspelling]" } else { "" };
+                    println!("    {}: {} symbols{}", v.spelling, v.count, note);
+                    for e in &v.examples {
+                        println!("      {} at {}:{}", e.identifier, e.file, e.line);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Custom { analysis, findings } => {
+            println!("{}: {} findings", analysis, findings.len());
+            print!("{}", problems::render_problems(findings));
+        }
+        #[cfg(feature = "analysis")]
+        Output::FieldUsage { report } => {
+            println!(
+                "Field usage of {} ({}:{}):",
+                report.type_name, report.file, report.line
+            );
+            for f in &report.fields {
+                let note = if f.constructor_only {
+                    " [only in constructors]"
+                } else {
+                    ""
+                };
+                println!(
+                    "  {}: {} reads, {} writes{}",
+                    f.name, f.reads, f.writes, note
+                );
+                for a in &f.accesses {
+                    let access = if a.write { "write" } else { "read" };
+                    println!("    {} in {} at {}:{}", access, a.symbol, a.file, a.line);
+                }
+                if f.reads + f.writes > f.accesses.len() {
+                    println!("    ... and {} more", f.reads + f.writes - f.accesses.len());
+                }
+            }
+            if !report.constructors.is_empty() {
+                println!("Constructors: {}", report.constructors.join(", "));
+            }
+            if !report.methods.is_empty() {
+                println!("Methods:");
+            }
+            for m in &report.methods {
+                let mut touched = Vec::new();
+                if !m.reads.is_empty() {
+                    touched.push(format!("reads {}", m.reads.join(", ")));
+                }
+                if !m.writes.is_empty() {
+                    touched.push(format!("writes {}", m.writes.join(", ")));
+                }
+                if touched.is_empty() {
+                    touched.push("no fields".to_string());
+                }
+                println!("  {} ({})", m.method, touched.join("; "));
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::Check {
+            baseline,
+            baseline_written,
+            findings,
+            baselined,
+            new,
+            fixed,
+        } => {
+            if *baseline_written {
+                println!(
+                    "Wrote baseline with {} findings to {}",
+                    findings,
+                    baseline.as_deref().unwrap_or_default()
+                );
+                return;
+            }
+            match baseline {
+                Some(path) => println!("Baseline: {} ({} findings baselined)", path, baselined),
+                None => println!("No baseline; all findings are new"),
+            }
+            println!("{} new findings:", new.len());
+            for f in new {
+                println!(
+                    "  {}:{} [{}] {}: {}",
+                    f.file, f.line, f.rule, f.symbol, f.message
+                );
+            }
+            if !fixed.is_empty() {
+                println!(
+                    "{} baseline entries no longer occur (rerun with --write-baseline to prune):",
+                    fixed.len()
+                );
+                for e in fixed {
+                    println!("  {} [{}] {}", e.file, e.rule, e.symbol);
+                }
+            }
+        }
+        Output::Stats {
+            files,
+            symbols,
+            call_edges,
+            topology_nodes,
+            duplication,
+            embedding_drift,
+        } => {
+            println!("Files: {}", files);
+            println!("Symbols: {}", symbols);
+            println!("Call edges: {}", call_edges);
+            println!("Topology nodes: {}", topology_nodes);
+            if let Some(report) = embedding_drift.as_ref().filter(|r| r.has_drift()) {
+                println!(
+                    "Embedding drift: {} of {} vectors not from {}; run omni reembed",
+                    report.stale_vectors,
+                    report.vectors_by_model.values().sum::<usize>(),
+                    report.current_model
+                );
+            }
+            let Some(pressure) = duplication else {
+                println!("Duplication pressure: unknown (no semantic index built yet)");
+                return;
+            };
+            println!("Duplication pressure: {}", pressure.summary());
+            for cluster in &pressure.clusters {
+                println!(
+                    "  {} symbols, up to {:.2}: {}",
+                    cluster.size,
+                    cluster.max_similarity,
+                    cluster.symbols.join(", ")
+                );
+            }
+        }
+        Output::Verify { report } => {
+            println!(
+                "Verified {} files, {} symbols, {} call edges",
+                report.files, report.symbols, report.call_edges
+            );
+            if report.is_ok() {
+                println!("All invariants hold");
+                return;
+            }
+            for (invariant, count) in report.counts() {
+                println!("{}: {} violations", invariant, count);
+            }
+            for v in &report.violations {
+                match &v.file {
+                    Some(file) => println!("  [{}] {}: {}", v.invariant, file, v.message),
+                    None => println!("  [{}] {}", v.invariant, v.message),
+                }
+            }
+        }
+        #[cfg(feature = "context")]
+        Output::SuggestDocs {
+            suggestions,
+            documented,
+        } => {
+            println!(
+                "{} modules without docs ({} already documented)",
+                suggestions.len(),
+                documented
+            );
+            for s in suggestions {
+                println!();
+                println!("{} ({}, {}):", s.file.display(), s.module, s.role);
+                print!("{}", s.doc);
+            }
+        }
+        Output::Bench { report: r } => {
+            println!(
+                "omni {} ({} threads, features: {})",
+                r.version,
+                r.threads,
+                if r.features.is_empty() {
+                    "core".to_string()
+                } else {
+                    r.features.join(", ")
+                }
+            );
+            println!(
+                "Index: {} files, {:.1} MB, {} symbols in {:.0} ms",
+                r.files,
+                r.bytes as f64 / (1024.0 * 1024.0),
+                r.symbols,
+                r.index_ms
+            );
+            println!(
+                "  {:.0} files/s, {:.2} MB/s, {:.0} symbols/s",
+                r.files_per_sec, r.mb_per_sec, r.symbols_per_sec
+            );
+            println!("Re-index (no changes): {:.0} ms", r.reindex_ms);
+            println!(
+                "Search: {} queries, {:.0} queries/s, p50 {:.0} us, p95 {:.0} us",
+                r.queries, r.queries_per_sec, r.query_p50_us, r.query_p95_us
+            );
+        }
+        Output::Impact { file, impact } => {
+            println!("{} is in crate {}", file, impact.owner.name);
+            if impact.dependents.is_empty() {
+                println!("No workspace crates depend on it");
+                return;
+            }
+            println!("Dependent crates ({}):", impact.dependents.len());
+            for c in &impact.dependents {
+                let via = if c.depth == 1 {
+                    "direct".to_string()
+                } else {
+                    format!("{} hops", c.depth)
+                };
+                println!("  {} ({}) at {}", c.name, via, c.path.display());
+            }
+        }
+        Output::RenameImpact { impact } => {
+            println!(
+                "Changing {} touches {} definitions, {} calls, {} imports and {} re-exports in {} files:",
+                impact.name,
+                impact.definitions.len(),
+                impact.calls,
+                impact.imports,
+                impact.reexports,
+                impact.files.len()
+            );
+            for file in &impact.files {
+                println!("\n{}:", file.file.display());
+                for site in &file.sites {
+                    let test = if site.is_test { " [test]" } else { "" };
+                    println!(
+                        "  {}: {} {}{}",
+                        site.line,
+                        site.kind.as_str(),
+                        site.detail,
+                        test
+                    );
+                }
+            }
+        }
+        Output::Implementations { implementations } => {
+            let defined = match implementations.definitions.as_slice() {
+                [] => String::new(),
+                defs => format!(" ({})", defs.join(", ")),
+            };
+            println!(
+                "Trait {}{}: {} implementations",
+                implementations.trait_name,
+                defined,
+                implementations.implementations.len()
+            );
+            for imp in &implementations.implementations {
+                println!(
+                    "  {} at {}:{}{}",
+                    imp.type_symbol.as_deref().unwrap_or(&imp.type_name),
+                    imp.file.display(),
+                    imp.line,
+                    test_suffix(imp.is_test)
+                );
+                for method in &imp.methods {
+                    println!("    {}", method);
+                }
+            }
+        }
+        Output::TraitOrigins { method, origins } => {
+            if origins.is_empty() {
+                println!("{} is not a trait method", method);
+                return;
+            }
+            for origin in origins {
+                println!(
+                    "{} implements {} for {} at {}:{}",
+                    origin.method,
+                    origin.trait_symbol.as_deref().unwrap_or(&origin.trait_name),
+                    origin.implementation.type_name,
+                    origin.implementation.file.display(),
+                    origin.implementation.line
+                );
+            }
+        }
+        Output::AffectedTests {
+            affected_by,
+            affected,
+        } => {
+            if affected.tests.is_empty() {
+                println!("No tests reach {}", affected_by);
+                return;
+            }
+            println!(
+                "{} tests affected by {} (showing {}):",
+                affected.total,
+                affected_by,
+                affected.tests.len()
+            );
+            for t in &affected.tests {
+                let via = match t.depth {
+                    0 => "changed".to_string(),
+                    1 => "direct".to_string(),
+                    depth => format!("{} hops", depth),
+                };
+                println!(
+                    "  {} ({}) at {}:{}",
+                    t.symbol,
+                    via,
+                    t.file.display(),
+                    t.line
+                );
+                if t.depth > 1 {
+                    println!("    via {}", t.via.join(" -> "));
+                }
+            }
+            if !affected.commands.is_empty() {
+                println!("Run:");
+            }
+            for command in &affected.commands {
+                println!("  {}", command);
+            }
+            if let Some(filter) = &affected.nextest_filter {
+                println!("  cargo nextest run -E '{}'", filter);
+            }
+        }
+        #[cfg(feature = "analysis")]
+        Output::CoChanges { report } => {
+            if report.commits == 0 {
+                println!(
+                    "No commits changed {} in the last {} days",
+                    report.target, report.days
+                );
+                return;
+            }
+            println!(
+                "Changed with {} ({} commits in the last {} days):",
+                report.target, report.commits, report.days
+            );
+            if report.files.is_empty() && report.symbols.is_empty() {
+                println!("  nothing changed with it more than once");
+            }
+            if !report.files.is_empty() {
+                println!("Files:");
+            }
+            for f in &report.files {
+                println!(
+                    "  {}: {} of {} commits ({:.0}%)",
+                    f.file,
+                    f.together,
+                    report.commits,
+                    f.confidence * 100.0
+                );
+            }
+            if !report.symbols.is_empty() {
+                println!("Symbols:");
+            }
+            for s in &report.symbols {
+                println!(
+                    "  {} ({}) at {}:{}: {} of {} commits ({:.0}%)",
+                    s.symbol,
+                    s.kind,
+                    s.file,
+                    s.line,
+                    s.together,
+                    report.commits,
+                    s.confidence * 100.0
+                );
+            }
+        }
+        Output::Explain { explanation: e } => {
+            println!(
+                "{} ({} {}) at {}:{}-{}",
+                e.symbol,
+                e.visibility,
+                e.kind,
+                e.file.display(),
+                e.line,
+                e.end_line
+            );
+            if let Some(signature) = &e.signature {
+                println!("  {}", signature);
+            }
+            if let Some(doc) = &e.doc {
+                for line in doc.lines() {
+                    println!("  {}", line);
+                }
+            }
+            if !e.attributes.is_empty() {
+                println!("  attributes: {}", e.attributes.join(", "));
+            }
+            if !e.other_definitions.is_empty() {
+                println!("  also defined as: {}", e.other_definitions.join(", "));
+            }
+            let neighbor = |n: &omni_index::explain::Neighbor| {
+                let location = match (&n.file, n.line) {
+                    (Some(file), Some(line)) => format!(" at {}:{}", file.display(), line),
+                    _ => String::new(),
+                };
+                let test = if n.is_test { " [test]" } else { "" };
+                format!("{} x{}{}{}", n.symbol, n.calls, location, test)
+            };
+            println!("Callers ({} call sites):", e.caller_count);
+            for n in &e.callers {
+                println!("  {}", neighbor(n));
+            }
+            println!("Callees ({} call sites):", e.callee_count);
+            for n in &e.callees {
+                println!("  {}", neighbor(n));
+            }
+            if !e.types.is_empty() {
+                println!("Types:");
+                for t in &e.types {
+                    println!(
+                        "  {} {} ({}) at {}:{}",
+                        t.kind,
+                        t.symbol,
+                        t.relation,
+                        t.file.display(),
+                        t.line
+                    );
+                }
+            }
+            println!(
+                "Standing: file rank {} of {} (relevance {:.4}, {})",
+                e.standing.file_rank, e.standing.files, e.standing.file_relevance, e.standing.role
+            );
+            println!("Tests reaching it: {}", e.tests.total);
+            for t in &e.tests.nearest {
+                println!(
+                    "  {} at {}:{} ({} hops)",
+                    t.symbol,
+                    t.file.display(),
+                    t.line,
+                    t.depth
+                );
+            }
+            if let Some(c) = &e.coverage {
+                println!(
+                    "Coverage: {}/{} lines, {}/{} branches",
+                    c.lines_covered, c.lines_total, c.branches_covered, c.branches_total
+                );
+            }
+            match &e.history {
+                Some(h) => {
+                    println!(
+                        "History: {} commits, {} to {}",
+                        h.commits,
+                        h.first_changed.as_deref().unwrap_or("-"),
+                        h.last_changed.as_deref().unwrap_or("-")
+                    );
+                    for c in &h.recent {
+                        println!("  {} {} {}: {}", c.hash, c.date, c.author, c.subject);
+                    }
                 }
+                None => println!("History: not in a git repository"),
+            }
+            if e.owners.owners.is_empty() {
+                println!("Owners: unknown");
+            } else {
+                println!(
+                    "Owners ({}): {}",
+                    e.owners.source,
+                    e.owners.owners.join(", ")
+                );
             }
         }
         Output::ExportEngram { export } => {
             println!("{}", export.content);
         }
-        Output::Search { results } => {
-            println!("Found {} results:", results.len());
+        Output::ExportSymbols { symbols, output } => {
+            println!(
+                "Exported {} symbols to {}",
+                symbols,
+                output.as_deref().unwrap_or("stdout")
+            );
+        }
+        #[cfg(feature = "analysis")]
+        Output::Problems { problems, .. } => {
+            println!("{} problems", problems);
+        }
+        #[cfg(feature = "semantic")]
+        Output::Reembed {
+            current_model,
+            vectors,
+            stale_before,
+            migrated,
+        } => {
+            println!("Embedding model: {}", current_model);
+            println!(
+                "Re-embedded {} of {} stale vectors ({} total)",
+                migrated, stale_before, vectors
+            );
+        }
+        Output::Search {
+            strategy,
+            counts,
+            results,
+        } => {
+            if *strategy == SearchStrategy::Bm25 {
+                println!("Found {} results:", results.len());
+            } else {
+                println!("Found {} results ({}):", results.len(), strategy.as_str());
+            }
+            if counts.tests > 0 {
+                print_test_split(counts);
+            }
             for r in results {
                 println!(
-                    "  {:.2} {} ({}) at {}:{}",
-                    r.score, r.symbol, r.kind, r.file, r.line
+                    "  {:.2} {} ({}) at {}:{} [{}]{}{}",
+                    r.score,
+                    r.symbol,
+                    r.kind,
+                    r.file,
+                    r.line,
+                    r.role,
+                    test_suffix(r.is_test_context),
+                    deprecated_suffix(r.deprecated.as_deref())
                 );
+                print_renamed(r.renamed.as_deref());
             }
         }
     }
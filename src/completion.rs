@@ -0,0 +1,154 @@
+//! Prefix autocomplete over symbol names.
+//!
+//! Interactive UIs and agents building exact queries need "what names start
+//! with `eng`?" answered per keystroke. [`CompletionIndex`] keeps the interned
+//! simple and scoped names sorted by their lowercased spelling, so a prefix
+//! maps to a contiguous range found by binary search. The index is built on
+//! first use and dropped whenever symbols change (see
+//! [`OciState::complete_symbol`]).
+
+use crate::state::OciState;
+use crate::types::InternedString;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Default number of completions returned.
+pub const DEFAULT_COMPLETION_LIMIT: usize = 20;
+
+/// A name that completes a prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct Completion {
+    /// Simple name, or the scoped name when the prefix contains `::`
+    pub name: String,
+    /// Number of symbols defined under this name
+    pub definitions: usize,
+    /// Kinds of those symbols (`function`, `struct`, ...), deduplicated
+    pub kinds: Vec<String>,
+}
+
+/// Interned names sorted by lowercased spelling.
+#[derive(Debug, Default)]
+pub struct CompletionIndex {
+    simple: Vec<(Box<str>, InternedString)>,
+    scoped: Vec<(Box<str>, InternedString)>,
+}
+
+impl CompletionIndex {
+    /// Snapshot the names currently in `state`.
+    pub fn build(state: &OciState) -> Self {
+        let sorted = |names: Vec<InternedString>| {
+            let mut entries: Vec<(Box<str>, InternedString)> = names
+                .into_iter()
+                .map(|name| (state.resolve(name).to_lowercase().into_boxed_str(), name))
+                .collect();
+            entries.sort_by(|a, b| {
+                a.0.cmp(&b.0)
+                    .then_with(|| state.resolve(a.1).cmp(state.resolve(b.1)))
+            });
+            entries
+        };
+        let simple = state
+            .name_to_scoped
+            .iter()
+            .filter(|entry| !entry.value().is_empty())
+            .map(|entry| *entry.key())
+            .collect();
+        let scoped = state.symbols.iter().map(|entry| *entry.key()).collect();
+        Self {
+            simple: sorted(simple),
+            scoped: sorted(scoped),
+        }
+    }
+
+    /// Names starting with `prefix`, case-insensitively, in sorted order.
+    ///
+    /// A prefix containing `::` completes scoped names
+    /// (`crate::engine::Eng`); anything else completes simple names. An
+    /// empty prefix matches nothing.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<InternedString> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let entries = if prefix.contains("::") {
+            &self.scoped
+        } else {
+            &self.simple
+        };
+        let key = prefix.to_lowercase();
+        let start = entries.partition_point(|(name, _)| name.as_ref() < key.as_str());
+        entries[start..]
+            .iter()
+            .take_while(|(name, _)| name.starts_with(key.as_str()))
+            .take(limit)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// Describe a completed name: how many symbols it covers and their kinds.
+pub fn describe(state: &OciState, name: InternedString, scoped: bool) -> Completion {
+    let symbols: Vec<InternedString> = if scoped {
+        vec![name]
+    } else {
+        state
+            .name_to_scoped
+            .get(&name)
+            .map(|names| names.clone())
+            .unwrap_or_default()
+    };
+    let kinds: BTreeSet<&'static str> = symbols
+        .iter()
+        .filter_map(|s| state.symbols.get(s).map(|sym| sym.kind.as_str()))
+        .collect();
+    Completion {
+        name: state.resolve(name).to_string(),
+        definitions: symbols.len(),
+        kinds: kinds.into_iter().map(str::to_string).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_complete_symbol_by_prefix() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "pub struct Engine;\n\nimpl Engine {\n    pub fn start(&self) {}\n}\n\npub fn engage() {}\n\npub fn engine_config() {}\n\npub fn other() {}\n\nmod nested {\n    pub fn engage() {}\n}\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let names = |prefix: &str, limit: usize| -> Vec<String> {
+            state
+                .complete_symbol(prefix, limit)
+                .into_iter()
+                .map(|c| c.name)
+                .collect()
+        };
+        assert_eq!(names("eng", 10), vec!["engage", "Engine", "engine_config"]);
+        assert_eq!(names("ENGI", 10), vec!["Engine", "engine_config"]);
+        assert_eq!(names("eng", 1), vec!["engage"]);
+        assert!(names("zzz", 10).is_empty());
+        assert!(names("", 10).is_empty());
+
+        let engage = &state.complete_symbol("engage", 1)[0];
+        assert_eq!(engage.definitions, 2);
+        assert_eq!(engage.kinds, vec!["function"]);
+        assert_eq!(names("crate::nested::", 10), vec!["crate::nested::engage"]);
+
+        // The index is rebuilt after symbols change
+        std::fs::write(temp.path().join("extra.rs"), "pub fn engrave() {}\n").unwrap();
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        assert!(names("engr", 10).contains(&"engrave".to_string()));
+    }
+}
@@ -0,0 +1,93 @@
+//! Workspace settings from `omni.toml` at the workspace root.
+//!
+//! Each feature reads its own table (`[index]`, `[spelling]`, ...); a
+//! missing file or table means the defaults.
+
+use crate::parsing::LanguageOverrides;
+use anyhow::{Context as _, Result};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// Workspace config file, relative to the root.
+pub const CONFIG_FILE: &str = "omni.toml";
+
+/// The `[section]` table of `omni.toml` under `root`, or `T::default()` if
+/// the file or the table is missing. Fails on a file that doesn't parse.
+pub fn load_section<T: DeserializeOwned + Default>(root: &Path, section: &str) -> Result<T> {
+    let path = root.join(CONFIG_FILE);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Ok(T::default());
+    };
+    let mut table: toml::Table =
+        toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+    match table.remove(section) {
+        Some(value) => value
+            .try_into()
+            .with_context(|| format!("Invalid [{}] in {}", section, path.display())),
+        None => Ok(T::default()),
+    }
+}
+
+/// `[index]` table: what `omni index` and the MCP `index` tool build.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// Build or update the semantic index after parsing (with the
+    /// `semantic` feature)
+    pub semantic: bool,
+    /// `GLOB=LANGUAGE` rules forcing a parser for matching files, like
+    /// `omni index --lang-override`
+    pub language_overrides: Vec<String>,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            semantic: true,
+            language_overrides: Vec::new(),
+        }
+    }
+}
+
+impl IndexConfig {
+    pub fn load(root: &Path) -> Result<Self> {
+        load_section(root, "index")
+    }
+
+    /// [`language_overrides`](Self::language_overrides), parsed.
+    pub fn overrides(&self) -> Result<LanguageOverrides> {
+        LanguageOverrides::from_rules(&self.language_overrides)
+            .with_context(|| format!("Invalid [index] language_overrides in {CONFIG_FILE}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_section() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(IndexConfig::load(temp.path()).unwrap().semantic);
+
+        std::fs::write(temp.path().join(CONFIG_FILE), "[index]\nsemantic = false\n").unwrap();
+        assert!(!IndexConfig::load(temp.path()).unwrap().semantic);
+
+        std::fs::write(temp.path().join(CONFIG_FILE), "[index]\nsemantic = 1\n").unwrap();
+        assert!(IndexConfig::load(temp.path()).is_err());
+
+        std::fs::write(
+            temp.path().join(CONFIG_FILE),
+            "[index]\nlanguage_overrides = [\"scripts/*=typescript\"]\n",
+        )
+        .unwrap();
+        let config = IndexConfig::load(temp.path()).unwrap();
+        assert!(config.semantic);
+        let overrides = config.overrides().unwrap();
+        assert_eq!(
+            overrides.language_for(Path::new("scripts/deploy")),
+            Some("typescript")
+        );
+    }
+}
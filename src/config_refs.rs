@@ -0,0 +1,327 @@
+//! Cross-references between config keys and the code that reads them.
+//!
+//! [`find_config_refs`] answers "where is `OCI_WORKSPACE` consumed?": it
+//! lists the config files defining a key (see [`crate::parsing::config`])
+//! and every place in code naming it, either as a string literal or through
+//! an environment variable accessor (`env::var("OCI_WORKSPACE")`,
+//! `process.env.OCI_WORKSPACE`, `os.getenv(...)`).
+//!
+//! A dotted key and its environment spelling are treated as the same key, so
+//! `oci.workspace` in a TOML file and `OCI_WORKSPACE` in code meet; a dotted
+//! key also matches literals naming its last segment (`"workspace"`), which
+//! is how most config structs read their fields.
+
+use crate::discovery::FileDiscovery;
+use crate::error::Result;
+use crate::parsing::config::{ConfigFormat, extract_config_keys};
+use crate::state::OciState;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Environment accessors, as written immediately before the key literal.
+const ENV_CALLS: &[&str] = &[
+    "env::var(",
+    "env::var_os(",
+    "env!(",
+    "option_env!(",
+    "process.env[",
+    "os.environ[",
+    "os.environ.get(",
+    "os.getenv(",
+    "getenv(",
+    "Getenv(",
+    "LookupEnv(",
+    "ENV[",
+    "ENV.fetch(",
+];
+
+/// Environment objects read with a property access (`process.env.KEY`).
+const ENV_PROPERTIES: &[&str] = &["process.env.", "import.meta.env."];
+
+/// How a code reference names the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessKind {
+    /// Read through an environment variable accessor
+    Env,
+    /// A string literal equal to the key
+    Literal,
+}
+
+impl AccessKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Env => "env",
+            Self::Literal => "literal",
+        }
+    }
+}
+
+/// A config file entry defining the key.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ConfigDefinition {
+    /// Full dotted path of the key
+    pub key: String,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    pub value: Option<String>,
+}
+
+/// A place in code naming the key.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ConfigReader {
+    /// Innermost symbol containing the reference, if any
+    pub symbol: Option<String>,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    /// The spelling found in code
+    pub matched: String,
+    pub access: AccessKind,
+}
+
+/// Where a config key is defined and read.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ConfigRefs {
+    pub key: String,
+    pub definitions: Vec<ConfigDefinition>,
+    pub readers: Vec<ConfigReader>,
+}
+
+/// Find the definitions and readers of `key`. Readers come from the indexed
+/// source files; config files are discovered under the workspace root, since
+/// not every index build parses them.
+pub fn find_config_refs(state: &OciState, key: &str) -> Result<ConfigRefs> {
+    let key = key.trim();
+    let env = env_name(key);
+    let mut names = vec![key.to_string()];
+    if env != key {
+        names.push(env.clone());
+    }
+    if let Some((_, leaf)) = key.rsplit_once('.')
+        && !leaf.is_empty()
+    {
+        names.push(leaf.to_string());
+    }
+
+    let mut config_files: Vec<PathBuf> = FileDiscovery::new()
+        .discover(&state.root_path)?
+        .into_iter()
+        .filter(|path| ConfigFormat::from_path(path).is_some())
+        .collect();
+    config_files.sort();
+    let mut source_files: Vec<PathBuf> = state
+        .file_hashes
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|path| ConfigFormat::from_path(path).is_none())
+        .collect();
+    source_files.sort();
+
+    let mut definitions = Vec::new();
+    for file in &config_files {
+        let (Some(format), Some(source)) =
+            (ConfigFormat::from_path(file), file_source(state, file))
+        else {
+            continue;
+        };
+        definitions.extend(
+            extract_config_keys(format, &source)
+                .into_iter()
+                .filter(|k| k.path == key || env_name(&k.path) == env)
+                .map(|k| ConfigDefinition {
+                    key: k.path,
+                    file: relative(state, file),
+                    line: k.line,
+                    value: k.value,
+                }),
+        );
+    }
+    let mut readers = Vec::new();
+    for file in &source_files {
+        if let Some(source) = file_source(state, file) {
+            readers.extend(scan_readers(state, file, &source, &names));
+        }
+    }
+    readers.sort_by(|a, b| {
+        b.access
+            .eq(&AccessKind::Env)
+            .cmp(&a.access.eq(&AccessKind::Env))
+            .then(a.file.cmp(&b.file))
+            .then(a.line.cmp(&b.line))
+    });
+
+    Ok(ConfigRefs {
+        key: key.to_string(),
+        definitions,
+        readers,
+    })
+}
+
+/// Environment spelling of a key: `oci.workspace` -> `OCI_WORKSPACE`.
+fn env_name(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            '.' | '-' => '_',
+            c => c.to_ascii_uppercase(),
+        })
+        .collect()
+}
+
+fn scan_readers(
+    state: &OciState,
+    file: &Path,
+    source: &str,
+    names: &[String],
+) -> Vec<ConfigReader> {
+    let mut readers = Vec::new();
+    for name in names {
+        for (start, _) in source.match_indices(name.as_str()) {
+            let end = start + name.len();
+            let before = &source[..start];
+            let after = &source[end..];
+            let access = match (before.chars().next_back(), after.chars().next()) {
+                (Some(open @ ('"' | '\'' | '`')), Some(close)) if open == close => {
+                    let call = before[..before.len() - 1].trim_end();
+                    if ENV_CALLS.iter().any(|c| call.ends_with(c)) {
+                        AccessKind::Env
+                    } else {
+                        AccessKind::Literal
+                    }
+                }
+                _ if ENV_PROPERTIES.iter().any(|p| before.ends_with(p))
+                    && !after.starts_with(|c: char| c.is_alphanumeric() || c == '_') =>
+                {
+                    AccessKind::Env
+                }
+                _ => continue,
+            };
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            if is_comment(&source[line_start..start]) {
+                continue;
+            }
+            let line = before.matches('\n').count();
+            readers.push(ConfigReader {
+                symbol: enclosing_symbol(state, file, line),
+                file: relative(state, file),
+                line,
+                matched: name.clone(),
+                access,
+            });
+        }
+    }
+    readers
+}
+
+/// Whether a match preceded by `prefix` on its line sits in a line comment.
+fn is_comment(prefix: &str) -> bool {
+    let prefix = prefix.trim_start();
+    ["//", "/*", "* ", "# "]
+        .iter()
+        .any(|c| prefix.starts_with(c))
+        || prefix.contains(" // ")
+}
+
+/// Scoped name of the innermost symbol spanning `line` in `file`.
+fn enclosing_symbol(state: &OciState, file: &Path, line: usize) -> Option<String> {
+    let id = *state.file_ids.get(file)?;
+    let scoped = state.file_symbols.get(&id)?;
+    scoped
+        .iter()
+        .filter_map(|&s| state.get_symbol(s))
+        .filter(|s| s.location.start_line <= line && line <= s.location.end_line)
+        .min_by_key(|s| s.location.end_byte - s.location.start_byte)
+        .map(|s| state.resolve(s.scoped_name).to_string())
+}
+
+fn file_source(state: &OciState, file: &Path) -> Option<Arc<str>> {
+    if let Some(overlay) = state.overlays.get(file) {
+        return Some(overlay.contents.clone());
+    }
+    if let Some(contents) = state.file_contents.get(file) {
+        return Some(contents.clone());
+    }
+    std::fs::read_to_string(file).ok().map(Arc::from)
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_links_config_keys_to_env_and_literal_readers() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("config.toml"),
+            "[oci]\nworkspace = \"/srv/oci\"\nthreads = 4\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/settings.rs"),
+            "/// Reads `OCI_WORKSPACE`.\npub fn workspace() -> String {\n    std::env::var(\"OCI_WORKSPACE\").unwrap_or_default()\n}\n\npub fn lookup(table: &Table) -> Option<&str> {\n    table.get(\"workspace\")\n}\n\npub fn unrelated() -> &'static str {\n    \"OCI_WORKSPACE_DIR\"\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/client.ts"),
+            "export function workspaceDir(): string {\n  return process.env.OCI_WORKSPACE ?? \".\";\n}\n",
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let refs = find_config_refs(&state, "OCI_WORKSPACE").unwrap();
+        assert_eq!(refs.definitions.len(), 1);
+        assert_eq!(refs.definitions[0].key, "oci.workspace");
+        assert_eq!(refs.definitions[0].file, PathBuf::from("config.toml"));
+        assert_eq!(refs.definitions[0].value.as_deref(), Some("/srv/oci"));
+
+        let summary: Vec<(&str, usize, AccessKind)> = refs
+            .readers
+            .iter()
+            .map(|r| (r.file.to_str().unwrap(), r.line, r.access))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/client.ts", 1, AccessKind::Env),
+                ("src/settings.rs", 2, AccessKind::Env),
+            ]
+        );
+        assert_eq!(refs.readers[1].symbol.as_deref(), Some("crate::workspace"));
+
+        // The dotted spelling also finds readers of the leaf field
+        let refs = find_config_refs(&state, "oci.workspace").unwrap();
+        assert_eq!(refs.definitions.len(), 1);
+        assert_eq!(refs.readers.len(), 3);
+        let literal = refs
+            .readers
+            .iter()
+            .find(|r| r.access == AccessKind::Literal)
+            .unwrap();
+        assert_eq!(literal.matched, "workspace");
+        assert_eq!(literal.symbol.as_deref(), Some("crate::lookup"));
+
+        assert!(
+            find_config_refs(&state, "MISSING_KEY")
+                .unwrap()
+                .readers
+                .is_empty()
+        );
+    }
+}
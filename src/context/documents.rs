@@ -0,0 +1,457 @@
+//! Virtual context documents.
+//!
+//! Markdown pages generated on demand from the index: an architecture
+//! overview, one page per module, and the riskiest files. The MCP server
+//! serves them as `oci://` resources, so they are rebuilt from the current
+//! index on every read instead of going stale like hand-written docs.
+
+use super::module_docs::{calling_files, public_symbols, relative, summary_line};
+use crate::reexports::module_path;
+use crate::state::OciState;
+use crate::types::{ModuleRole, TopologyEdge, TopologyNode};
+use petgraph::visit::EdgeRef;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// URI of the architecture overview.
+pub const ARCHITECTURE_URI: &str = "oci://architecture";
+/// URI of the hotspot report.
+pub const HOTSPOTS_URI: &str = "oci://hotspots";
+/// Prefix of module pages; the rest of the URI is the file path relative to
+/// the workspace root.
+pub const MODULE_URI_PREFIX: &str = "oci://modules/";
+
+/// Module pages listed by [`list_documents`], most central first. Any other
+/// indexed file can still be read through [`MODULE_URI_PREFIX`].
+const MAX_LISTED_MODULES: usize = 50;
+/// Files named per role in the architecture overview.
+const MAX_PER_ROLE: usize = 10;
+/// Files in the architecture overview's centrality ranking.
+const MAX_CENTRAL: usize = 15;
+/// Files in the hotspot report.
+#[cfg(feature = "analysis")]
+const MAX_HOTSPOTS: usize = 20;
+
+/// A virtual document the index can generate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VirtualDocument {
+    Architecture,
+    Hotspots,
+    /// Page for one indexed file, relative to the workspace root
+    Module(PathBuf),
+}
+
+impl VirtualDocument {
+    /// Parse an `oci://` URI. Returns `None` for anything else.
+    pub fn parse(uri: &str) -> Option<Self> {
+        match uri {
+            ARCHITECTURE_URI => Some(Self::Architecture),
+            HOTSPOTS_URI => Some(Self::Hotspots),
+            _ => {
+                let path = uri.strip_prefix(MODULE_URI_PREFIX)?;
+                (!path.is_empty()).then(|| Self::Module(PathBuf::from(path)))
+            }
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match self {
+            Self::Architecture => ARCHITECTURE_URI.to_string(),
+            Self::Hotspots => HOTSPOTS_URI.to_string(),
+            Self::Module(path) => format!("{}{}", MODULE_URI_PREFIX, path.display()),
+        }
+    }
+}
+
+/// A listed document with a display name and description.
+#[derive(Debug, Clone)]
+pub struct DocumentEntry {
+    pub document: VirtualDocument,
+    pub name: String,
+    pub description: String,
+}
+
+/// The documents worth listing: the architecture overview, the hotspot
+/// report (with the `analysis` feature), and pages for the most central
+/// non-test modules.
+pub fn list_documents(state: &OciState) -> Vec<DocumentEntry> {
+    let mut entries = vec![DocumentEntry {
+        document: VirtualDocument::Architecture,
+        name: "Architecture".to_string(),
+        description: "Crates, modules by role, and the most central files".to_string(),
+    }];
+    #[cfg(feature = "analysis")]
+    entries.push(DocumentEntry {
+        document: VirtualDocument::Hotspots,
+        name: "Hotspots".to_string(),
+        description: "Files that change often and are complex, riskiest first".to_string(),
+    });
+    for (file, _) in central_files(state).into_iter().take(MAX_LISTED_MODULES) {
+        let rel = relative(state, &file);
+        entries.push(DocumentEntry {
+            name: display_module(&rel),
+            description: format!("Module page for {}", rel.display()),
+            document: VirtualDocument::Module(rel),
+        });
+    }
+    entries
+}
+
+/// Render `document`, or `None` if it names a file that is not indexed.
+///
+/// The hotspot report shells out to git; call it off the async runtime's
+/// worker threads if that matters.
+pub async fn render(state: &OciState, document: &VirtualDocument) -> Option<String> {
+    match document {
+        VirtualDocument::Architecture => Some(architecture(state)),
+        VirtualDocument::Hotspots => Some(hotspots(state)),
+        VirtualDocument::Module(path) => module(state, path).await,
+    }
+}
+
+/// Architecture overview: crates and their dependencies, files grouped by
+/// role, and the files PageRank ranks most central.
+pub fn architecture(state: &OciState) -> String {
+    let stats = state.stats();
+    let mut doc = String::from("# Architecture\n\n");
+    let _ = writeln!(
+        doc,
+        "{} files, {} symbols, {} call edges.\n",
+        stats.file_count, stats.symbol_count, stats.call_edge_count
+    );
+
+    let crates = crates(state);
+    if crates.len() > 1 {
+        doc.push_str("## Crates\n\n");
+        for (name, path, deps) in &crates {
+            let _ = write!(doc, "- `{}` ({})", name, relative(state, path).display());
+            if !deps.is_empty() {
+                let _ = write!(doc, ", depends on {}", code_list(deps));
+            }
+            doc.push('\n');
+        }
+        doc.push('\n');
+    }
+
+    let central = central_files(state);
+    let mut by_role: BTreeMap<&'static str, Vec<PathBuf>> = BTreeMap::new();
+    for (file, _) in &central {
+        let role = state.file_role(file);
+        by_role
+            .entry(role.as_str())
+            .or_default()
+            .push(relative(state, file));
+    }
+    if !by_role.is_empty() {
+        doc.push_str("## Modules by role\n\n");
+        for (role, files) in &by_role {
+            let _ = writeln!(doc, "### {} ({})\n", role, files.len());
+            for rel in files.iter().take(MAX_PER_ROLE) {
+                let _ = writeln!(doc, "- `{}` ({})", display_module(rel), rel.display());
+            }
+            if files.len() > MAX_PER_ROLE {
+                let _ = writeln!(doc, "- ... and {} more", files.len() - MAX_PER_ROLE);
+            }
+            doc.push('\n');
+        }
+    }
+
+    if !central.is_empty() {
+        doc.push_str("## Most central files\n\n");
+        for (i, (file, score)) in central.iter().take(MAX_CENTRAL).enumerate() {
+            let rel = relative(state, file);
+            let _ = writeln!(
+                doc,
+                "{}. [{}]({}{}) (PageRank {:.4}, {} public symbols)",
+                i + 1,
+                rel.display(),
+                MODULE_URI_PREFIX,
+                rel.display(),
+                score,
+                public_symbols(state, file).len()
+            );
+        }
+    }
+    doc
+}
+
+/// Page for one file: its summary, public API, imports, and the files that
+/// call into it. `None` if `rel` is not indexed.
+pub async fn module(state: &OciState, rel: &Path) -> Option<String> {
+    let file = state.root_path.join(rel);
+    state.file_ids.get(&file)?;
+    let role = state.file_role(&file);
+    let name = display_module(rel);
+
+    let mut doc = format!("# {}\n\n", name);
+    let _ = writeln!(
+        doc,
+        "`{}` · role: {} · PageRank {:.4}\n",
+        rel.display(),
+        role.as_str(),
+        state.relevance_of_file(&file)
+    );
+    let summary = match state.get_file_contents(&file).await {
+        Some(source) => leading_docs(&source),
+        None => None,
+    };
+    doc.push_str(&summary.unwrap_or_else(|| summary_line(&name, role)));
+    doc.push_str("\n\n");
+
+    let symbols = public_symbols(state, &file);
+    if !symbols.is_empty() {
+        doc.push_str("## Public API\n\n");
+        for symbol in &symbols {
+            let _ = write!(
+                doc,
+                "- {} `{}` (line {})",
+                symbol.kind.as_str(),
+                symbol.display_name(state),
+                symbol.location.start_line + 1
+            );
+            if let Some(first) = symbol.doc_comment.as_deref().and_then(first_doc_line) {
+                let _ = write!(doc, ": {}", first);
+            }
+            doc.push('\n');
+        }
+        doc.push('\n');
+    }
+
+    let imports: Vec<String> = state
+        .file_ids
+        .get(&file)
+        .and_then(|id| state.imports.get(&*id).map(|i| i.clone()))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|imp| format!("{}{}", imp.path, if imp.is_glob { "::*" } else { "" }))
+        .collect();
+    if !imports.is_empty() {
+        doc.push_str("## Imports\n\n");
+        for import in &imports {
+            let _ = writeln!(doc, "- `{}`", import);
+        }
+        doc.push('\n');
+    }
+
+    let mut callers: Vec<(PathBuf, usize)> = calling_files(state, &file, &symbols)
+        .into_iter()
+        .map(|(caller, calls)| (relative(state, &caller), calls))
+        .collect();
+    callers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if !callers.is_empty() {
+        doc.push_str("## Used by\n\n");
+        for (caller, calls) in &callers {
+            let _ = writeln!(
+                doc,
+                "- [{}]({}{}) ({} call{})",
+                caller.display(),
+                MODULE_URI_PREFIX,
+                caller.display(),
+                calls,
+                if *calls == 1 { "" } else { "s" }
+            );
+        }
+    }
+    Some(doc)
+}
+
+/// Hotspot report: files that change often and are complex, weighted by
+/// centrality. Explains itself if there is no git history.
+#[cfg(feature = "analysis")]
+pub fn hotspots(state: &OciState) -> String {
+    let analyzer = crate::analysis::HotspotAnalyzer::new().with_limit(MAX_HOTSPOTS);
+    let report = match analyzer.analyze(state, &state.root_path) {
+        Ok(report) => report,
+        Err(e) => return format!("# Hotspots\n\nNo git history to rank hotspots: {}\n", e),
+    };
+    let mut doc = format!(
+        "# Hotspots\n\nLast {} days, riskiest first.\n\n",
+        report.days
+    );
+    if report.files.is_empty() {
+        doc.push_str("No indexed file changed in that window.\n");
+        return doc;
+    }
+    doc.push_str("| File | Commits | Complexity | Centrality | Risk |\n");
+    doc.push_str("|------|---------|------------|------------|------|\n");
+    for hotspot in &report.files {
+        let _ = writeln!(
+            doc,
+            "| [{}]({}{}) | {} | {} | {:.2} | {:.1} |",
+            hotspot.file,
+            MODULE_URI_PREFIX,
+            hotspot.file,
+            hotspot.commits,
+            hotspot.complexity,
+            hotspot.centrality,
+            hotspot.risk
+        );
+    }
+    doc
+}
+
+#[cfg(not(feature = "analysis"))]
+pub fn hotspots(_state: &OciState) -> String {
+    "# Hotspots\n\nHotspots require the 'analysis' feature.\n".to_string()
+}
+
+/// Indexed non-test, non-generated files, most central first.
+fn central_files(state: &OciState) -> Vec<(PathBuf, f64)> {
+    let mut files: Vec<(PathBuf, f64)> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| {
+            !matches!(
+                state.file_role(f),
+                ModuleRole::Tests | ModuleRole::Generated
+            )
+        })
+        .map(|f| {
+            let score = state.relevance_of_file(&f);
+            (f, score)
+        })
+        .collect();
+    files.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files
+}
+
+/// Crate nodes with the names of the crates they depend on.
+fn crates(state: &OciState) -> Vec<(String, PathBuf, Vec<String>)> {
+    let graph = state.topology.read();
+    let mut crates = Vec::new();
+    for idx in graph.node_indices() {
+        let TopologyNode::Crate { name, path, .. } = &graph[idx] else {
+            continue;
+        };
+        let mut deps: Vec<String> = graph
+            .edges(idx)
+            .filter(|e| matches!(e.weight(), TopologyEdge::DependsOn))
+            .filter_map(|e| match &graph[e.target()] {
+                TopologyNode::Crate { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        deps.sort();
+        crates.push((name.clone(), path.clone(), deps));
+    }
+    crates.sort_by(|a, b| a.1.cmp(&b.1));
+    crates
+}
+
+/// Rust module path for `.rs` files, the path itself for anything else.
+fn display_module(rel: &Path) -> String {
+    if rel.extension().is_some_and(|ext| ext == "rs") {
+        module_path(rel)
+    } else {
+        rel.display().to_string()
+    }
+}
+
+/// The leading `//!` docs of `source` as plain text, after any shebang,
+/// inner attributes, and plain comments.
+fn leading_docs(source: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix("//!") {
+            lines.push(text.strip_prefix(' ').unwrap_or(text));
+            continue;
+        }
+        let skippable = lines.is_empty()
+            && (line.is_empty()
+                || line.starts_with("#!")
+                || (line.starts_with("//") && !line.starts_with("///")));
+        if !skippable {
+            break;
+        }
+    }
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// First non-empty line of a doc comment, without comment markers.
+fn first_doc_line(doc: &str) -> Option<&str> {
+    doc.lines()
+        .map(|l| l.trim().trim_start_matches(['/', '*', '!', '#']).trim())
+        .find(|l| !l.is_empty())
+}
+
+fn code_list(items: &[String]) -> String {
+    let items: Vec<String> = items.iter().map(|i| format!("`{i}`")).collect();
+    items.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[test]
+    fn test_parse_uri() {
+        assert_eq!(
+            VirtualDocument::parse("oci://architecture"),
+            Some(VirtualDocument::Architecture)
+        );
+        let module = VirtualDocument::parse("oci://modules/src/codec.rs").unwrap();
+        assert_eq!(
+            module,
+            VirtualDocument::Module(PathBuf::from("src/codec.rs"))
+        );
+        assert_eq!(module.uri(), "oci://modules/src/codec.rs");
+        assert_eq!(VirtualDocument::parse("oci://modules/"), None);
+        assert_eq!(VirtualDocument::parse("file:///src/lib.rs"), None);
+        assert_eq!(
+            leading_docs("// License\n//! Frames.\n//!\n//! More.\nuse x;\n").as_deref(),
+            Some("Frames.\n\nMore.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_renders_module_and_architecture_docs() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let files = [
+            ("src/lib.rs", "//! Frames.\npub mod codec;\npub mod api;\n"),
+            (
+                "src/codec.rs",
+                "use std::io::Read;\n\n/// A decoded frame.\npub struct Frame;\n\npub fn decode_frame() -> Frame {\n    Frame\n}\n",
+            ),
+            (
+                "src/api.rs",
+                "pub fn handle() {\n    crate::codec::decode_frame();\n}\n",
+            ),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let codec = module(&state, Path::new("src/codec.rs")).await.unwrap();
+        assert!(codec.starts_with("# crate::codec\n"));
+        assert!(codec.contains("- struct `Frame` (line 4): A decoded frame.\n"));
+        assert!(codec.contains("- `std::io::Read`\n"));
+        assert!(codec.contains("- [src/api.rs](oci://modules/src/api.rs) (1 call)\n"));
+        assert!(module(&state, Path::new("src/missing.rs")).await.is_none());
+
+        let overview = architecture(&state);
+        assert!(overview.contains("3 files"));
+        assert!(overview.contains("(src/codec.rs)"));
+
+        let listed = list_documents(&state);
+        assert_eq!(listed[0].document, VirtualDocument::Architecture);
+        assert!(
+            listed
+                .iter()
+                .any(|e| e.document == VirtualDocument::Module(PathBuf::from("src/api.rs")))
+        );
+    }
+}
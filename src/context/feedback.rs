@@ -0,0 +1,132 @@
+//! Relevance feedback on delivered context.
+//!
+//! Agents mark the chunks `get_context` gave them as useful or not (the MCP
+//! `context_feedback` tool). [`ContextFeedback`] counts the marks per file
+//! and symbol for the session, and the synthesizer adjusts its ranking with
+//! them: candidates from a file or symbol rejected [`SUPPRESS_AFTER`] times
+//! more often than found useful are left out, and ones found useful are
+//! boosted by [`BOOST_STEP`] per mark, up to [`MAX_BOOST`].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Net rejections after which a file or symbol is suppressed.
+pub const SUPPRESS_AFTER: u32 = 2;
+/// Score added per useful mark.
+pub const BOOST_STEP: f64 = 0.05;
+/// Upper bound on the score added by useful marks.
+pub const MAX_BOOST: f64 = 0.2;
+
+/// Marks given to one file or symbol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct FeedbackCounts {
+    pub useful: u32,
+    pub not_useful: u32,
+}
+
+impl FeedbackCounts {
+    fn record(&mut self, useful: bool) {
+        if useful {
+            self.useful += 1;
+        } else {
+            self.not_useful += 1;
+        }
+    }
+
+    /// Rejections not offset by useful marks.
+    fn net_rejections(&self) -> u32 {
+        self.not_useful.saturating_sub(self.useful)
+    }
+}
+
+/// Feedback on delivered chunks for one session.
+#[derive(Debug, Clone, Default)]
+pub struct ContextFeedback {
+    files: HashMap<PathBuf, FeedbackCounts>,
+    /// Keyed by scoped name, which outlives re-indexing
+    symbols: HashMap<String, FeedbackCounts>,
+}
+
+impl ContextFeedback {
+    /// Record a mark for a file.
+    pub fn record_file(&mut self, file: PathBuf, useful: bool) {
+        self.files.entry(file).or_default().record(useful);
+    }
+
+    /// Record a mark for a symbol (scoped name).
+    pub fn record_symbol(&mut self, symbol: String, useful: bool) {
+        self.symbols.entry(symbol).or_default().record(useful);
+    }
+
+    pub fn file(&self, file: &Path) -> FeedbackCounts {
+        self.files.get(file).copied().unwrap_or_default()
+    }
+
+    pub fn symbol(&self, symbol: &str) -> FeedbackCounts {
+        self.symbols.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Marked files, in path order.
+    pub fn files(&self) -> Vec<(&Path, FeedbackCounts)> {
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(file, counts)| (file.as_path(), *counts))
+            .collect();
+        files.sort_by_key(|(file, _)| *file);
+        files
+    }
+
+    /// Marked symbols, in name order.
+    pub fn symbols(&self) -> Vec<(&str, FeedbackCounts)> {
+        let mut symbols: Vec<_> = self
+            .symbols
+            .iter()
+            .map(|(symbol, counts)| (symbol.as_str(), *counts))
+            .collect();
+        symbols.sort_by_key(|(symbol, _)| *symbol);
+        symbols
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.symbols.is_empty()
+    }
+
+    /// Score to add to a candidate for `symbol` defined in `file`, or `None`
+    /// if either has been rejected too often to be shown.
+    pub fn adjustment(&self, file: &Path, symbol: &str) -> Option<f64> {
+        let (file, symbol) = (self.file(file), self.symbol(symbol));
+        if file.net_rejections() >= SUPPRESS_AFTER || symbol.net_rejections() >= SUPPRESS_AFTER {
+            return None;
+        }
+        Some((BOOST_STEP * (file.useful + symbol.useful) as f64).min(MAX_BOOST))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejections_suppress_and_marks_boost() {
+        let mut feedback = ContextFeedback::default();
+        let (noisy, handy) = (Path::new("/repo/gen.rs"), Path::new("/repo/auth.rs"));
+        assert_eq!(feedback.adjustment(noisy, "crate::generated"), Some(0.0));
+
+        feedback.record_file(noisy.to_path_buf(), false);
+        assert!(feedback.adjustment(noisy, "crate::generated").is_some());
+        feedback.record_file(noisy.to_path_buf(), false);
+        assert!(feedback.adjustment(noisy, "crate::generated").is_none());
+        // A useful mark offsets a rejection
+        feedback.record_symbol("crate::generated".to_string(), true);
+        feedback.record_file(noisy.to_path_buf(), true);
+        assert!(feedback.adjustment(noisy, "crate::generated").is_some());
+
+        for _ in 0..10 {
+            feedback.record_symbol("crate::login".to_string(), true);
+        }
+        assert_eq!(feedback.adjustment(handy, "crate::login"), Some(MAX_BOOST));
+        assert_eq!(feedback.symbols().len(), 2);
+        assert_eq!(feedback.files()[0].1.not_useful, 2);
+    }
+}
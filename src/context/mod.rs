@@ -3,16 +3,93 @@
 //! Auto-generates architectural context documents by intelligently assembling
 //! relevant code snippets based on call graphs, type relationships, and PageRank scores.
 
+pub mod documents;
+pub mod feedback;
+pub mod module_docs;
+
+pub use documents::VirtualDocument;
+pub use feedback::ContextFeedback;
+pub use module_docs::{ModuleDocSuggestion, suggest_module_docs};
+
+use crate::budget::Budget;
 use crate::state::OciState;
-use crate::types::{InternedString, SymbolKind};
+use crate::types::{InternedString, ModuleRole, SymbolKind};
 use anyhow::{Context as _, Result};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 // ============================================================================
 // Public Types
 // ============================================================================
 
+/// Order of chunks within the primary and related sections.
+///
+/// Chunks are always *selected* by relevance; the ordering only changes how
+/// the selected chunks are presented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChunkOrdering {
+    /// Most relevant first
+    #[default]
+    Relevance,
+    /// Definitions before the code that calls them; chunks without a symbol
+    /// (e.g. the query location) come last
+    Topological,
+    /// Grouped by file, in source order within each file
+    FileGrouped,
+}
+
+impl FromStr for ChunkOrdering {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "relevance" => Ok(Self::Relevance),
+            "topological" | "call-order" => Ok(Self::Topological),
+            "file" | "file-grouped" => Ok(Self::FileGrouped),
+            other => anyhow::bail!(
+                "Unknown chunk ordering: {}. Valid: relevance, topological, file-grouped",
+                other
+            ),
+        }
+    }
+}
+
+/// Share of `max_tokens` reserved for pinned items. Whatever pinned items
+/// leave unused goes to regular chunks.
+pub const PINNED_BUDGET_SHARE: f64 = 0.3;
+
+/// Maximum candidates gathered per relation of the symbol at the query
+/// location, before ranking against the token budget. `usize::MAX` disables
+/// a cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelationCaps {
+    /// Callers, most call sites first (ties by the caller file's PageRank)
+    pub callers: usize,
+    /// Callees, most call sites first
+    pub callees: usize,
+    /// Imports of the query file, in source order
+    pub imports: usize,
+    /// Types named in the symbol's signature
+    pub types: usize,
+    /// Symbols that git history shows changing with the query file, most
+    /// shared commits first (needs [`OciState::churn`])
+    pub co_changes: usize,
+}
+
+impl Default for RelationCaps {
+    fn default() -> Self {
+        Self {
+            callers: 5,
+            callees: usize::MAX,
+            imports: 5,
+            types: usize::MAX,
+            co_changes: 3,
+        }
+    }
+}
+
 /// Query for context assembly.
 #[derive(Debug, Clone)]
 pub struct ContextQuery {
@@ -26,6 +103,20 @@ pub struct ContextQuery {
     pub intent: Option<String>,
     /// Maximum tokens to include in the result
     pub max_tokens: usize,
+    /// How chunks are ordered in the result
+    pub ordering: ChunkOrdering,
+    /// Symbols included in every response regardless of relevance
+    pub pinned_symbols: Vec<InternedString>,
+    /// Files included in every response regardless of relevance
+    pub pinned_files: Vec<PathBuf>,
+    /// Per-relation candidate caps
+    pub caps: RelationCaps,
+    /// Stop gathering and return partial context after this long
+    pub timeout: Option<Duration>,
+    /// Stop after this many call edges, imports, and chunks are processed
+    pub max_work: Option<usize>,
+    /// Session feedback on earlier chunks, adjusting the ranking
+    pub feedback: ContextFeedback,
 }
 
 impl ContextQuery {
@@ -37,6 +128,13 @@ impl ContextQuery {
             surrounding_lines: 5,
             intent: None,
             max_tokens: 4000,
+            ordering: ChunkOrdering::default(),
+            pinned_symbols: Vec::new(),
+            pinned_files: Vec::new(),
+            caps: RelationCaps::default(),
+            timeout: None,
+            max_work: None,
+            feedback: ContextFeedback::default(),
         }
     }
 
@@ -57,32 +155,81 @@ impl ContextQuery {
         self.max_tokens = max_tokens;
         self
     }
+
+    /// Set the chunk ordering strategy.
+    pub fn with_ordering(mut self, ordering: ChunkOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Pin symbols and files into the result (see [`PINNED_BUDGET_SHARE`]).
+    pub fn with_pinned(mut self, symbols: Vec<InternedString>, files: Vec<PathBuf>) -> Self {
+        self.pinned_symbols = symbols;
+        self.pinned_files = files;
+        self
+    }
+
+    /// Set the per-relation candidate caps.
+    pub fn with_caps(mut self, caps: RelationCaps) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    /// Bound the work done for this query (see [`crate::budget::Budget`]).
+    pub fn with_limits(mut self, timeout: Option<Duration>, max_work: Option<usize>) -> Self {
+        self.timeout = timeout;
+        self.max_work = max_work;
+        self
+    }
+
+    /// Adjust the ranking by session feedback (see [`feedback`]).
+    pub fn with_feedback(mut self, feedback: ContextFeedback) -> Self {
+        self.feedback = feedback;
+        self
+    }
+
+    fn has_pins(&self) -> bool {
+        !self.pinned_symbols.is_empty() || !self.pinned_files.is_empty()
+    }
 }
 
 /// Result of context assembly.
 #[derive(Debug, Clone)]
 pub struct ContextResult {
+    /// Pinned chunks - always included, within the reserved budget
+    pub pinned: Vec<ContextChunk>,
     /// Primary chunks - most relevant to the query
     pub primary: Vec<ContextChunk>,
     /// Related chunks - less relevant but still useful
     pub related: Vec<ContextChunk>,
     /// Total estimated tokens in the result
     pub total_tokens: usize,
+    /// Whether the query's time or work limit cut assembly short
+    pub truncated: bool,
+    /// Candidates left out because feedback rejected them
+    pub suppressed: usize,
 }
 
 impl ContextResult {
     /// Create an empty context result.
     pub fn empty() -> Self {
         Self {
+            pinned: Vec::new(),
             primary: Vec::new(),
             related: Vec::new(),
             total_tokens: 0,
+            truncated: false,
+            suppressed: 0,
         }
     }
 
-    /// Get all chunks in priority order (primary first, then related).
+    /// Get all chunks in priority order (pinned, primary, then related).
     pub fn all_chunks(&self) -> Vec<&ContextChunk> {
-        self.primary.iter().chain(self.related.iter()).collect()
+        self.pinned
+            .iter()
+            .chain(self.primary.iter())
+            .chain(self.related.iter())
+            .collect()
     }
 }
 
@@ -93,6 +240,8 @@ pub struct ContextChunk {
     pub symbol: Option<InternedString>,
     /// File containing this chunk
     pub file: PathBuf,
+    /// Line the chunk starts at
+    pub line: usize,
     /// The actual content
     pub content: String,
     /// Relevance score (0.0 - 1.0)
@@ -128,6 +277,8 @@ impl ContextSynthesizer {
         state: &OciState,
         query: &ContextQuery,
     ) -> Result<ContextResult> {
+        let budget = Budget::new(query.timeout, query.max_work);
+
         // Step 1: Find the symbol at the query location
         let symbol_at_location = self.find_symbol_at_location(state, &query.file, query.line);
 
@@ -139,24 +290,41 @@ impl ContextSynthesizer {
             candidates.push((current_symbol, 1.0, "Current location".to_string()));
 
             // Find callees (functions this symbol calls)
-            let callees = state.find_callees(current_symbol);
-            for call_edge in callees {
-                // Try to resolve the callee to a scoped name
-                if let Some(resolved) = self.resolve_callee(state, &call_edge.callee_name) {
-                    candidates.push((
-                        resolved,
-                        0.8,
-                        format!("Called by {}", state.resolve(current_symbol)),
-                    ));
-                }
+            let callees = state
+                .find_callees(current_symbol)
+                .iter()
+                .take_while(|_| budget.charge(1))
+                .filter_map(|edge| self.resolve_callee(state, &edge.callee_name))
+                .collect();
+            for (callee, sites) in rank_by_call_sites(state, callees, query.caps.callees) {
+                candidates.push((
+                    callee,
+                    0.8,
+                    format!(
+                        "Called by {}{}",
+                        state.resolve(current_symbol),
+                        call_site_suffix(sites)
+                    ),
+                ));
             }
 
             // Find callers (functions that call this symbol)
             let current_name = state.resolve(current_symbol);
-            let callers = state.find_callers(current_name);
-            for call_edge in callers.iter().take(5) {
-                // Limit callers to avoid explosion
-                candidates.push((call_edge.caller, 0.6, format!("Calls {}", current_name)));
+            let simple_name = state
+                .get_symbol(current_symbol)
+                .map_or(current_name, |s| state.resolve(s.name));
+            let callers = state
+                .find_callers(simple_name)
+                .iter()
+                .take_while(|_| budget.charge(1))
+                .map(|edge| edge.caller)
+                .collect();
+            for (caller, sites) in rank_by_call_sites(state, callers, query.caps.callers) {
+                candidates.push((
+                    caller,
+                    0.6,
+                    format!("Calls {}{}", current_name, call_site_suffix(sites)),
+                ));
             }
 
             // Find related types (from signatures)
@@ -164,7 +332,7 @@ impl ContextSynthesizer {
                 if let Some(sig) = &symbol_def.signature {
                     // Extract types from parameters and return type
                     let types = self.extract_types_from_signature(sig);
-                    for type_name in types {
+                    for type_name in types.into_iter().take(query.caps.types) {
                         if let Some(type_symbol) = self.find_type_symbol(state, &type_name) {
                             candidates.push((
                                 type_symbol,
@@ -185,8 +353,11 @@ impl ContextSynthesizer {
         // Find relevant imports
         let file_id = state.get_or_create_file_id(&query.file);
         if let Some(imports) = state.imports.get(&file_id) {
-            for import in imports.iter().take(5) {
-                // Limit imports
+            for import in imports
+                .iter()
+                .take(query.caps.imports)
+                .take_while(|_| budget.charge(1))
+            {
                 // Try to find symbols matching the imported names
                 if let Some(import_symbol) = self.find_symbol_by_name(state, &import.name) {
                     candidates.push((import_symbol, 0.4, format!("Imported: {}", import.name)));
@@ -194,14 +365,38 @@ impl ContextSynthesizer {
             }
         }
 
-        // Step 3: Rank all candidates
+        // Code that history shows changing along with this file
+        #[cfg(feature = "analysis")]
+        if let Some(churn) = state.churn.read().as_ref() {
+            let file = query.file.to_string_lossy();
+            if let Some(report) =
+                crate::analysis::CoChangeAnalyzer::from_churn(state, &state.root_path, churn, &file)
+            {
+                for co_change in report.symbols.iter().take(query.caps.co_changes) {
+                    if let Some(symbol) = state.lookup(&co_change.symbol) {
+                        candidates.push((
+                            symbol,
+                            0.5 * co_change.confidence,
+                            format!(
+                                "Changed with {} in {} of {} commits",
+                                report.target, co_change.together, report.commits
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Step 3: Rank all candidates, then adjust by session feedback
         let ranked = self.rank_symbols_with_reasons(state, candidates);
+        let (ranked, suppressed) = self.apply_feedback(state, query, ranked);
 
-        // Step 4: Build chunks with token budget
+        // Step 4: Build chunks with token budget, pinned items first
         let mut primary_chunks = Vec::new();
         let mut related_chunks = Vec::new();
-        let mut total_tokens = 0;
         let mut seen_symbols = HashSet::new();
+        let pinned_chunks = self.pinned_chunks(state, query, &mut seen_symbols).await;
+        let mut total_tokens: usize = pinned_chunks.iter().map(|c| c.estimate_tokens()).sum();
 
         // First, add the query location itself
         if let Ok(location_chunk) = self
@@ -214,7 +409,7 @@ impl ContextSynthesizer {
 
         // Then add ranked symbols
         for (symbol, score, reason) in ranked {
-            if total_tokens >= query.max_tokens {
+            if total_tokens >= query.max_tokens || !budget.charge(1) {
                 break;
             }
 
@@ -244,13 +439,176 @@ impl ContextSynthesizer {
             }
         }
 
+        self.order_chunks(state, &mut primary_chunks, query.ordering);
+        self.order_chunks(state, &mut related_chunks, query.ordering);
+
         Ok(ContextResult {
+            pinned: pinned_chunks,
             primary: primary_chunks,
             related: related_chunks,
             total_tokens,
+            truncated: budget.is_exhausted(),
+            suppressed,
         })
     }
 
+    /// Chunks for the query's pinned symbols and files, within the reserved
+    /// share of the budget. Pins that do not fit are skipped; files are
+    /// truncated to the remaining reservation.
+    async fn pinned_chunks(
+        &self,
+        state: &OciState,
+        query: &ContextQuery,
+        seen_symbols: &mut HashSet<InternedString>,
+    ) -> Vec<ContextChunk> {
+        let mut chunks = Vec::new();
+        if !query.has_pins() {
+            return chunks;
+        }
+        let budget = (query.max_tokens as f64 * PINNED_BUDGET_SHARE) as usize;
+        let mut used = 0;
+
+        for &symbol in &query.pinned_symbols {
+            if seen_symbols.contains(&symbol) {
+                continue;
+            }
+            let Ok(chunk) = self
+                .create_symbol_chunk(state, symbol, 1.0, "Pinned".to_string())
+                .await
+            else {
+                continue;
+            };
+            if used + chunk.estimate_tokens() > budget {
+                continue;
+            }
+            used += chunk.estimate_tokens();
+            seen_symbols.insert(symbol);
+            chunks.push(chunk);
+        }
+
+        for file in &query.pinned_files {
+            let Some(contents) = state.get_file_contents(file).await else {
+                continue;
+            };
+            // ~4 chars per token, as in `ContextChunk::estimate_tokens`
+            let mut remaining = budget.saturating_sub(used) * 4;
+            let mut lines = Vec::new();
+            for line in contents.lines() {
+                if line.len() + 1 > remaining {
+                    break;
+                }
+                remaining -= line.len() + 1;
+                lines.push(line);
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            let truncated = lines.len() < contents.lines().count();
+            let chunk = ContextChunk {
+                symbol: None,
+                file: file.clone(),
+                line: 1,
+                content: lines.join("\n"),
+                relevance: 1.0,
+                reason: with_role(
+                    state,
+                    file,
+                    if truncated {
+                        format!("Pinned file (first {} lines)", lines.len())
+                    } else {
+                        "Pinned file".to_string()
+                    },
+                ),
+            };
+            used += chunk.estimate_tokens();
+            chunks.push(chunk);
+        }
+
+        chunks
+    }
+
+    /// Reorder chunks (given in relevance order) by the requested strategy.
+    pub fn order_chunks(
+        &self,
+        state: &OciState,
+        chunks: &mut Vec<ContextChunk>,
+        ordering: ChunkOrdering,
+    ) {
+        match ordering {
+            ChunkOrdering::Relevance => {}
+            ChunkOrdering::FileGrouped => {
+                // Files appear in order of their most relevant chunk
+                let mut file_rank: HashMap<PathBuf, usize> = HashMap::new();
+                for (i, chunk) in chunks.iter().enumerate() {
+                    file_rank.entry(chunk.file.clone()).or_insert(i);
+                }
+                chunks.sort_by_key(|c| (file_rank[&c.file], c.line));
+            }
+            ChunkOrdering::Topological => {
+                let order = self.topological_order(state, chunks);
+                let mut slots: Vec<Option<ContextChunk>> =
+                    std::mem::take(chunks).into_iter().map(Some).collect();
+                chunks.extend(order.into_iter().filter_map(|i| slots[i].take()));
+            }
+        }
+    }
+
+    /// Indices of `chunks` with callees before callers.
+    ///
+    /// Ties (and cycles) fall back to relevance order.
+    fn topological_order(&self, state: &OciState, chunks: &[ContextChunk]) -> Vec<usize> {
+        let index_of: HashMap<InternedString, usize> = chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.symbol.map(|s| (s, i)))
+            .collect();
+
+        // callers[i] = chunks that call chunk i; pending[i] = callees not yet placed
+        let mut callers: Vec<Vec<usize>> = vec![Vec::new(); chunks.len()];
+        let mut pending = vec![0usize; chunks.len()];
+        for (i, chunk) in chunks.iter().enumerate() {
+            let Some(symbol) = chunk.symbol else {
+                continue;
+            };
+            let mut callees = HashSet::new();
+            for edge in state.find_callees(symbol) {
+                for target in state.call_targets(&edge) {
+                    if let Some(&j) = index_of.get(&target.scoped_name) {
+                        if j != i && callees.insert(j) {
+                            callers[j].push(i);
+                            pending[i] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut placed = vec![false; chunks.len()];
+        let mut order = Vec::with_capacity(chunks.len());
+        let symbol_chunks: Vec<usize> = (0..chunks.len())
+            .filter(|&i| chunks[i].symbol.is_some())
+            .collect();
+        while order.len() < symbol_chunks.len() {
+            // Most relevant ready chunk, or the most relevant remaining one to break a cycle
+            let next = symbol_chunks
+                .iter()
+                .copied()
+                .find(|&i| !placed[i] && pending[i] == 0)
+                .or_else(|| symbol_chunks.iter().copied().find(|&i| !placed[i]));
+            let Some(next) = next else {
+                break;
+            };
+            placed[next] = true;
+            order.push(next);
+            for &caller in &callers[next] {
+                pending[caller] = pending[caller].saturating_sub(1);
+            }
+        }
+
+        order.extend((0..chunks.len()).filter(|&i| chunks[i].symbol.is_none()));
+        order
+    }
+
     /// Rank symbols by relevance, returning (symbol, score) pairs.
     pub fn rank_symbols(
         &self,
@@ -396,7 +754,7 @@ impl ContextSynthesizer {
         let mut scored: Vec<_> = candidates
             .into_iter()
             .map(|(symbol, base_score, reason)| {
-                let pagerank_score = self.get_pagerank_score(state, symbol);
+                let pagerank_score = state.relevance_of_symbol(symbol);
                 let combined_score = base_score * 0.7 + pagerank_score * 0.3;
                 (symbol, combined_score, reason)
             })
@@ -408,26 +766,42 @@ impl ContextSynthesizer {
         scored
     }
 
-    /// Get the PageRank score for a symbol.
-    fn get_pagerank_score(&self, state: &OciState, symbol: InternedString) -> f64 {
-        // Get the symbol definition
-        let symbol_def = match state.get_symbol(symbol) {
-            Some(s) => s,
-            None => return 0.0,
-        };
-
-        // Find the topology node for this file
-        let node_idx = match state.path_to_node.get(&symbol_def.location.file) {
-            Some(idx) => *idx,
-            None => return 0.0,
-        };
-
-        // Get the metrics for this node
-        state
-            .topology_metrics
-            .get(&node_idx)
-            .map(|m| m.relevance_score)
-            .unwrap_or(0.0)
+    /// Drop ranked candidates that feedback rejected and boost the ones
+    /// marked useful, re-sorting by the adjusted score. The query's own file
+    /// is never suppressed. Returns the candidates and how many were
+    /// dropped.
+    fn apply_feedback(
+        &self,
+        state: &OciState,
+        query: &ContextQuery,
+        ranked: Vec<(InternedString, f64, String)>,
+    ) -> (Vec<(InternedString, f64, String)>, usize) {
+        if query.feedback.is_empty() {
+            return (ranked, 0);
+        }
+        let total = ranked.len();
+        let mut adjusted: Vec<_> = ranked
+            .into_iter()
+            .filter_map(|(symbol, score, reason)| {
+                let Some(def) = state.get_symbol(symbol) else {
+                    return Some((symbol, score, reason));
+                };
+                let file = &def.location.file;
+                match query.feedback.adjustment(file, state.resolve(symbol)) {
+                    Some(boost) if boost > 0.0 => Some((
+                        symbol,
+                        (score + boost).min(1.0),
+                        format!("{} (marked useful)", reason),
+                    )),
+                    Some(_) => Some((symbol, score, reason)),
+                    None if *file == query.file => Some((symbol, score, reason)),
+                    None => None,
+                }
+            })
+            .collect();
+        adjusted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let suppressed = total - adjusted.len();
+        (adjusted, suppressed)
     }
 
     /// Create a chunk for a specific file location.
@@ -454,9 +828,10 @@ impl ContextSynthesizer {
         Ok(ContextChunk {
             symbol: None,
             file: file.clone(),
+            line: line as usize,
             content,
             relevance: 1.0,
-            reason: format!("Query location at line {}", line),
+            reason: with_role(state, file, format!("Query location at line {}", line)),
         })
     }
 
@@ -477,28 +852,71 @@ impl ContextSynthesizer {
             .await
             .context("Failed to read file")?;
 
-        let lines: Vec<&str> = contents.lines().collect();
-
-        // Extract the symbol's definition
-        let start_line = symbol_def.location.start_line.saturating_sub(1);
-        let end_line = symbol_def.location.end_line;
-
-        if start_line >= lines.len() || end_line > lines.len() {
-            anyhow::bail!("Symbol location out of bounds");
-        }
-
-        let content = lines[start_line..end_line].join("\n");
+        // The symbol's definition with its doc comments and attributes, by
+        // byte offset since line numbers are 0- or 1-based depending on the
+        // parser
+        let location = symbol_def.full_location();
+        let content = contents
+            .get(location.start_byte..location.end_byte)
+            .context("Symbol location out of bounds")?
+            .to_string();
 
         Ok(ContextChunk {
             symbol: Some(symbol),
             file: symbol_def.location.file.clone(),
+            line: location.start_line,
             content,
             relevance,
-            reason,
+            reason: with_role(state, &symbol_def.location.file, reason),
         })
     }
 }
 
+/// Label a chunk reason with the file's module role, unless it is plain
+/// implementation code.
+fn with_role(state: &OciState, file: &Path, reason: String) -> String {
+    match state.file_role(file) {
+        ModuleRole::Implementation => reason,
+        role => format!("{} [{}]", reason, role.as_str()),
+    }
+}
+
+/// Distinct symbols of `symbols` (one entry per call site) with their call
+/// site counts, most called first, ties broken by the PageRank of the
+/// symbol's file, truncated to `cap`.
+fn rank_by_call_sites(
+    state: &OciState,
+    symbols: Vec<InternedString>,
+    cap: usize,
+) -> Vec<(InternedString, usize)> {
+    let mut counts: HashMap<InternedString, usize> = HashMap::new();
+    for symbol in symbols {
+        *counts.entry(symbol).or_default() += 1;
+    }
+    let mut ranked: Vec<(InternedString, usize, f64)> = counts
+        .into_iter()
+        .map(|(symbol, sites)| (symbol, sites, state.relevance_of_symbol(symbol)))
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| b.2.total_cmp(&a.2))
+            .then_with(|| state.resolve(a.0).cmp(state.resolve(b.0)))
+    });
+    ranked
+        .into_iter()
+        .take(cap)
+        .map(|(symbol, sites, _)| (symbol, sites))
+        .collect()
+}
+
+fn call_site_suffix(sites: usize) -> String {
+    if sites > 1 {
+        format!(" ({} call sites)", sites)
+    } else {
+        String::new()
+    }
+}
+
 impl Default for ContextSynthesizer {
     fn default() -> Self {
         Self::new()
@@ -531,6 +949,196 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_pinned_items_within_reserved_budget() {
+        let temp = TempDir::new().unwrap();
+        let main = temp.path().join("main.rs");
+        let notes = temp.path().join("notes.rs");
+        std::fs::write(&main, "fn main() {\n    run();\n}\nfn run() {}\n").unwrap();
+        std::fs::write(&notes, "fn unrelated() {\n    let x = 1;\n}\n").unwrap();
+        let long = temp.path().join("long.rs");
+        std::fs::write(&long, "// filler line for the budget\n".repeat(200)).unwrap();
+
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        let unrelated = state.find_by_name("unrelated")[0].scoped_name;
+
+        let query = ContextQuery::new(main.clone(), 1)
+            .with_max_tokens(400)
+            .with_pinned(vec![unrelated], vec![long.clone()]);
+        let result = ContextSynthesizer::new()
+            .build_context(&state, &query)
+            .await
+            .unwrap();
+
+        assert_eq!(result.pinned.len(), 2);
+        assert_eq!(result.pinned[0].symbol, Some(unrelated));
+        assert_eq!(result.pinned[1].file, long);
+        assert!(result.pinned[1].reason.starts_with("Pinned file (first"));
+        let pinned_tokens: usize = result.pinned.iter().map(|c| c.estimate_tokens()).sum();
+        assert!(pinned_tokens <= (400.0 * PINNED_BUDGET_SHARE) as usize);
+        // Pinned symbols are not repeated among regular chunks
+        assert!(
+            result
+                .primary
+                .iter()
+                .chain(&result.related)
+                .all(|c| c.symbol != Some(unrelated))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_callers_ranked_by_call_sites_before_cap() {
+        let temp = TempDir::new().unwrap();
+        let lib = temp.path().join("lib.rs");
+        std::fs::write(
+            &lib,
+            "fn target() {}\nfn a_once() { target(); }\nfn b_once() { target(); }\nfn z_busy() { target(); target(); target(); }\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let state = &state;
+        let callers = |caps: RelationCaps| {
+            let query = ContextQuery::new(lib.clone(), 0).with_caps(caps);
+            async move {
+                let result = ContextSynthesizer::new()
+                    .build_context(state, &query)
+                    .await
+                    .unwrap();
+                result
+                    .all_chunks()
+                    .into_iter()
+                    .filter(|c| c.reason.starts_with("Calls"))
+                    .map(|c| c.reason.clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let top = callers(RelationCaps {
+            callers: 1,
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(top, vec!["Calls crate::target (3 call sites) [api]"]);
+        let all = callers(RelationCaps::default()).await;
+        assert_eq!(all.len(), 3);
+
+        // Out of budget: partial context, flagged as truncated
+        let query = ContextQuery::new(lib.clone(), 0).with_limits(None, Some(2));
+        let result = ContextSynthesizer::new()
+            .build_context(state, &query)
+            .await
+            .unwrap();
+        assert!(result.truncated);
+        assert!(result.all_chunks().len() < 5);
+    }
+
+    #[cfg(feature = "analysis")]
+    #[tokio::test]
+    async fn test_co_changed_symbols_are_related() {
+        use crate::analysis::ChurnAnalyzer;
+
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let git = |args: &[&str]| {
+            let output = ChurnAnalyzer::git_cmd(root)
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--initial-branch=main"]);
+        for fields in ["id", "id, name"] {
+            std::fs::write(
+                root.join("model.rs"),
+                format!("pub struct User;\n\nconst FIELDS: &str = \"{fields}\";\n"),
+            )
+            .unwrap();
+            std::fs::write(
+                root.join("serialize.rs"),
+                format!("pub fn to_json() -> &'static str {{\n    \"{fields}\"\n}}\n"),
+            )
+            .unwrap();
+            git(&["add", "."]);
+            git(&["commit", "--no-gpg-sign", "-m", fields]);
+        }
+        let state = create_state(root.to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let query = ContextQuery::new(root.join("model.rs"), 0);
+        let reasons = || async {
+            ContextSynthesizer::new()
+                .build_context(&state, &query)
+                .await
+                .unwrap()
+                .related
+                .iter()
+                .map(|c| c.reason.clone())
+                .collect::<Vec<_>>()
+        };
+        // Without mined history there is nothing to go on
+        assert!(reasons().await.is_empty());
+
+        ChurnAnalyzer::analyze_index(&state, root, 365).unwrap();
+        assert_eq!(reasons().await, ["Changed with model.rs in 2 of 2 commits"]);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_suppresses_rejected_files_and_boosts_useful() {
+        let temp = TempDir::new().unwrap();
+        let main = temp.path().join("main.rs");
+        let noisy = temp.path().join("noisy.rs");
+        std::fs::write(&main, "fn main() {\n    noise();\n    help();\n}\n").unwrap();
+        std::fs::write(&noisy, "pub fn noise() {}\n").unwrap();
+        std::fs::write(temp.path().join("help.rs"), "pub fn help() {}\n").unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let reasons = |feedback: ContextFeedback| {
+            let query = ContextQuery::new(main.clone(), 1).with_feedback(feedback);
+            let state = &state;
+            async move {
+                let result = ContextSynthesizer::new()
+                    .build_context(state, &query)
+                    .await
+                    .unwrap();
+                let reasons: Vec<String> = result
+                    .all_chunks()
+                    .into_iter()
+                    .filter(|c| c.reason.starts_with("Called by"))
+                    .map(|c| c.reason.clone())
+                    .collect();
+                (reasons, result.suppressed)
+            }
+        };
+        let (before, suppressed) = reasons(ContextFeedback::default()).await;
+        assert_eq!(before.len(), 2);
+        assert_eq!(suppressed, 0);
+
+        let mut feedback = ContextFeedback::default();
+        feedback.record_file(noisy.clone(), false);
+        feedback.record_file(noisy, false);
+        feedback.record_symbol("crate::help".to_string(), true);
+        let (after, suppressed) = reasons(feedback).await;
+        assert_eq!(suppressed, 1);
+        assert_eq!(after, vec!["Called by crate::main (marked useful)"]);
+    }
+
     #[test]
     fn test_rank_symbols_empty() {
         let temp = TempDir::new().unwrap();
@@ -565,6 +1173,7 @@ mod tests {
         let chunk = ContextChunk {
             symbol: None,
             file: PathBuf::from("test.rs"),
+            line: 1,
             content: "a".repeat(400), // 400 chars
             relevance: 1.0,
             reason: "Test".to_string(),
@@ -594,6 +1203,7 @@ mod tests {
         result.primary.push(ContextChunk {
             symbol: None,
             file: PathBuf::from("a.rs"),
+            line: 1,
             content: "primary".to_string(),
             relevance: 1.0,
             reason: "test".to_string(),
@@ -602,6 +1212,7 @@ mod tests {
         result.related.push(ContextChunk {
             symbol: None,
             file: PathBuf::from("b.rs"),
+            line: 1,
             content: "related".to_string(),
             relevance: 0.5,
             reason: "test".to_string(),
@@ -612,4 +1223,93 @@ mod tests {
         assert_eq!(all[0].content, "primary");
         assert_eq!(all[1].content, "related");
     }
+
+    fn chunk(symbol: Option<InternedString>, file: &str, line: usize) -> ContextChunk {
+        ContextChunk {
+            symbol,
+            file: PathBuf::from(file),
+            line,
+            content: String::new(),
+            relevance: 1.0,
+            reason: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_topological_ordering_puts_callees_first() {
+        use crate::types::{CallEdge, Location, SymbolDef, Visibility};
+
+        let temp = TempDir::new().unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        let mut scoped = Vec::new();
+        for name in ["top", "middle", "leaf"] {
+            let scoped_name = state.intern(&format!("crate::{}", name));
+            state.add_symbol(SymbolDef {
+                name: state.intern(name),
+                scoped_name,
+                kind: SymbolKind::Function,
+                location: Location::new(PathBuf::from("a.rs"), 0, 10),
+                extended_location: None,
+                signature: None,
+                visibility: Visibility::Private,
+                attributes: vec![],
+                doc_comment: None,
+                parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
+            });
+            scoped.push(scoped_name);
+        }
+        for (caller, callee) in [(scoped[0], "middle"), (scoped[1], "leaf")] {
+            state.add_call_edge(CallEdge {
+                caller,
+                callee_name: callee.to_string(),
+                location: Location::new(PathBuf::from("a.rs"), 0, 1),
+                is_method_call: false,
+                via_trait: None,
+                is_approximate: false,
+                type_args: None,
+                is_test_context: false,
+                resolved_callee: None,
+            });
+        }
+
+        let mut chunks = vec![
+            chunk(None, "a.rs", 1),
+            chunk(Some(scoped[0]), "a.rs", 1),
+            chunk(Some(scoped[1]), "a.rs", 5),
+            chunk(Some(scoped[2]), "a.rs", 9),
+        ];
+        ContextSynthesizer::new().order_chunks(&state, &mut chunks, ChunkOrdering::Topological);
+
+        let order: Vec<_> = chunks.iter().map(|c| c.symbol).collect();
+        assert_eq!(
+            order,
+            vec![Some(scoped[2]), Some(scoped[1]), Some(scoped[0]), None]
+        );
+    }
+
+    #[test]
+    fn test_file_grouped_ordering() {
+        let temp = TempDir::new().unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        let mut chunks = vec![
+            chunk(None, "x.rs", 10),
+            chunk(None, "y.rs", 5),
+            chunk(None, "x.rs", 2),
+        ];
+        ContextSynthesizer::new().order_chunks(&state, &mut chunks, ChunkOrdering::FileGrouped);
+
+        let order: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.file.to_str().unwrap(), c.line))
+            .collect();
+        assert_eq!(order, vec![("x.rs", 2), ("x.rs", 10), ("y.rs", 5)]);
+        assert_eq!(
+            "call-order".parse::<ChunkOrdering>().unwrap(),
+            ChunkOrdering::Topological
+        );
+        assert!("random".parse::<ChunkOrdering>().is_err());
+    }
 }
@@ -0,0 +1,371 @@
+//! Module doc suggestions.
+//!
+//! Drafts a `//!` header for Rust modules that lack one, from what the index
+//! already knows: the module's role, its public symbols, and which other
+//! modules call into it. The output is template-based and deterministic, so
+//! agents can turn it into a patch proposal and a human can edit from there.
+
+use crate::reexports::module_path;
+use crate::state::OciState;
+use crate::types::{ModuleRole, SymbolDef, SymbolKind, Visibility};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Public types and functions named in a suggestion, each.
+const MAX_LISTED: usize = 5;
+/// Calling modules named in a suggestion.
+const MAX_USED_BY: usize = 4;
+/// Line width of the generated comment, including the `//! ` prefix.
+const DOC_WIDTH: usize = 80;
+
+/// A suggested `//!` header for one module.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ModuleDocSuggestion {
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    /// Rust module path, e.g. `crate::parsing::rust`
+    pub module: String,
+    pub role: &'static str,
+    /// The suggested comment, one `//!` line per line, to insert at
+    /// `insert_line`
+    pub doc: String,
+    /// 1-based line to insert `doc` before
+    pub insert_line: usize,
+    /// Public types and functions the doc mentions
+    pub public_symbols: Vec<String>,
+    /// Modules that call into this one, most calls first
+    pub used_by: Vec<String>,
+}
+
+/// Suggestions for the modules matching `filter` that have no `//!` docs.
+///
+/// `filter` is a file or directory (relative to the root or absolute) or a
+/// module path (`crate::parsing`, or just `parsing`); `None` covers every
+/// indexed Rust file. Tests and generated files are skipped. Also returns
+/// how many matching modules were skipped because they are documented.
+pub async fn suggest_module_docs(
+    state: &OciState,
+    filter: Option<&str>,
+) -> (Vec<ModuleDocSuggestion>, usize) {
+    let mut files: Vec<PathBuf> = state
+        .file_ids
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|f| f.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|f| filter.is_none_or(|filter| matches_filter(state, f, filter)))
+        .collect();
+    files.sort();
+
+    let mut suggestions = Vec::new();
+    let mut documented = 0;
+    for file in files {
+        let role = state.file_role(&file);
+        if matches!(role, ModuleRole::Tests | ModuleRole::Generated) {
+            continue;
+        }
+        let Some(source) = state.get_file_contents(&file).await else {
+            continue;
+        };
+        if has_module_docs(&source) {
+            documented += 1;
+            continue;
+        }
+        suggestions.push(suggest_for_file(state, &file, role));
+    }
+    (suggestions, documented)
+}
+
+fn suggest_for_file(state: &OciState, file: &Path, role: ModuleRole) -> ModuleDocSuggestion {
+    let rel = relative(state, file);
+    let module = module_path(&rel);
+    let symbols = public_symbols(state, file);
+    let types: Vec<String> = symbols
+        .iter()
+        .filter(|s| is_type(s.kind))
+        .take(MAX_LISTED)
+        .map(|s| s.display_name(state).to_string())
+        .collect();
+    let functions: Vec<String> = symbols
+        .iter()
+        .filter(|s| s.kind == SymbolKind::Function)
+        .take(MAX_LISTED)
+        .map(|s| s.display_name(state).to_string())
+        .collect();
+    let used_by = calling_modules(state, file, &symbols);
+
+    let mut paragraphs = vec![summary_line(&module, role)];
+    let mut body = Vec::new();
+    if !types.is_empty() {
+        body.push(format!("Defines {}.", link_list(&types)));
+    }
+    if !functions.is_empty() {
+        let label = if types.is_empty() {
+            "Provides"
+        } else {
+            "Main functions:"
+        };
+        body.push(format!("{} {}.", label, link_list(&functions)));
+    }
+    if !used_by.is_empty() {
+        let modules: Vec<String> = used_by.iter().map(|m| format!("`{m}`")).collect();
+        body.push(format!("Used by {}.", join_list(&modules)));
+    }
+    if !body.is_empty() {
+        paragraphs.push(body.join(" "));
+    }
+
+    ModuleDocSuggestion {
+        file: rel,
+        module,
+        role: role.as_str(),
+        doc: render_doc(&paragraphs),
+        insert_line: 1,
+        public_symbols: types.into_iter().chain(functions).collect(),
+        used_by,
+    }
+}
+
+/// Whether `source` opens with `//!` or `/*!` docs, after any shebang,
+/// inner attributes, and plain comments (e.g. license headers).
+fn has_module_docs(source: &str) -> bool {
+    for line in source.lines() {
+        let line = line.trim();
+        if line.starts_with("//!") || line.starts_with("/*!") {
+            return true;
+        }
+        let skippable = line.is_empty()
+            || line.starts_with("#!")
+            || (line.starts_with("//") && !line.starts_with("///"));
+        if !skippable {
+            return false;
+        }
+    }
+    false
+}
+
+/// Top-level public items of `file` in source order (no methods, fields,
+/// variants, or impls).
+pub(super) fn public_symbols(state: &OciState, file: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+        return Vec::new();
+    };
+    let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+        return Vec::new();
+    };
+    let mut symbols: Vec<SymbolDef> = scoped_names
+        .into_iter()
+        .filter_map(|scoped| state.get_symbol(scoped))
+        .filter(|s| {
+            s.location.file == file
+                && s.visibility == Visibility::Public
+                && s.parent.is_none()
+                && (is_type(s.kind) || s.kind == SymbolKind::Function)
+        })
+        .collect();
+    symbols.sort_by_key(|s| s.location.start_byte);
+    symbols
+}
+
+fn is_type(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Trait | SymbolKind::TypeAlias
+    )
+}
+
+/// Non-test modules outside `file` that call its public symbols, most
+/// calls first.
+///
+/// Only calls that resolve to a single definition count, so common names
+/// (`new`, `load`) defined in several places don't inflate the list.
+fn calling_modules(state: &OciState, file: &Path, symbols: &[SymbolDef]) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (caller, calls) in calling_files(state, file, symbols) {
+        *counts
+            .entry(module_path(&relative(state, &caller)))
+            .or_default() += calls;
+    }
+    let mut modules: Vec<(String, usize)> = counts.into_iter().collect();
+    modules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    modules
+        .into_iter()
+        .take(MAX_USED_BY)
+        .map(|(m, _)| m)
+        .collect()
+}
+
+/// Calls into `symbols` from each non-test file other than `file`, counted
+/// the same way as [`calling_modules`].
+pub(super) fn calling_files(
+    state: &OciState,
+    file: &Path,
+    symbols: &[SymbolDef],
+) -> HashMap<PathBuf, usize> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for symbol in symbols {
+        for edge in state.find_callers(state.resolve(symbol.name)) {
+            if edge.location.file == file
+                || state.file_role(&edge.location.file) == ModuleRole::Tests
+            {
+                continue;
+            }
+            let targets = state.call_targets(&edge);
+            if targets.len() != 1 || targets[0].location.file != file {
+                continue;
+            }
+            *counts.entry(edge.location.file.clone()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+pub(super) fn summary_line(module: &str, role: ModuleRole) -> String {
+    let name = module.rsplit("::").next().unwrap_or(module);
+    let topic = name.replace('_', " ");
+    match role {
+        ModuleRole::Entrypoint => "Program entry point.".to_string(),
+        ModuleRole::ApiSurface => "Public API of the crate.".to_string(),
+        ModuleRole::DataModel => format!("Data types for {topic}."),
+        ModuleRole::Utils => format!("Helpers for {topic}."),
+        ModuleRole::Config => format!("Configuration for {topic}."),
+        _ if name == "crate" => "Crate root.".to_string(),
+        _ => {
+            let mut chars = topic.chars();
+            match chars.next() {
+                Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+/// Render paragraphs as a `//!` comment wrapped to [`DOC_WIDTH`].
+fn render_doc(paragraphs: &[String]) -> String {
+    let mut doc = String::new();
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        if i > 0 {
+            doc.push_str("//!\n");
+        }
+        let mut line = String::from("//!");
+        for word in paragraph.split_whitespace() {
+            if line.len() > 3 && line.len() + 1 + word.len() > DOC_WIDTH {
+                doc.push_str(&line);
+                doc.push('\n');
+                line = String::from("//!");
+            }
+            line.push(' ');
+            line.push_str(word);
+        }
+        doc.push_str(&line);
+        doc.push('\n');
+    }
+    doc
+}
+
+fn link_list(names: &[String]) -> String {
+    let links: Vec<String> = names.iter().map(|n| format!("[`{n}`]")).collect();
+    join_list(&links)
+}
+
+/// `a`, `a and b`, `a, b, and c`.
+fn join_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [a, b] => format!("{a} and {b}"),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+fn matches_filter(state: &OciState, file: &Path, filter: &str) -> bool {
+    let path = Path::new(filter);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        state.root_path.join(path)
+    };
+    if file.starts_with(&path) {
+        return true;
+    }
+    let module = module_path(&relative(state, file));
+    let wanted = if filter.starts_with("crate") {
+        filter.to_string()
+    } else {
+        format!("crate::{filter}")
+    };
+    module == wanted || module.starts_with(&format!("{wanted}::"))
+}
+
+pub(super) fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[test]
+    fn test_module_path() {
+        assert_eq!(module_path(Path::new("src/lib.rs")), "crate");
+        assert_eq!(
+            module_path(Path::new("src/parsing/mod.rs")),
+            "crate::parsing"
+        );
+        assert_eq!(
+            module_path(Path::new("core/src/parsing/rust.rs")),
+            "crate::parsing::rust"
+        );
+        assert!(has_module_docs(
+            "#![allow(dead_code)]\n// License\n//! Docs\n"
+        ));
+        assert!(!has_module_docs("/// Item docs\npub fn a() {}\n"));
+    }
+
+    #[tokio::test]
+    async fn test_suggests_docs_for_undocumented_modules() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let files = [
+            ("src/lib.rs", "//! Frames.\npub mod codec;\npub mod api;\n"),
+            (
+                "src/codec.rs",
+                "pub struct Frame;\n\npub fn decode_frame() -> Frame {\n    Frame\n}\n\nfn helper() {}\n",
+            ),
+            (
+                "src/api.rs",
+                "//! HTTP API.\n\npub fn handle() {\n    crate::codec::decode_frame();\n}\n",
+            ),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let (suggestions, documented) = suggest_module_docs(&state, None).await;
+        assert_eq!(documented, 2);
+        assert_eq!(suggestions.len(), 1);
+        let codec = &suggestions[0];
+        assert_eq!(codec.file, PathBuf::from("src/codec.rs"));
+        assert_eq!(codec.module, "crate::codec");
+        assert_eq!(codec.public_symbols, vec!["Frame", "decode_frame"]);
+        assert_eq!(codec.used_by, vec!["crate::api"]);
+        assert_eq!(
+            codec.doc,
+            "//! Codec.\n//!\n//! Defines [`Frame`]. Main functions: [`decode_frame`]. Used by `crate::api`.\n"
+        );
+
+        let (filtered, _) = suggest_module_docs(&state, Some("api")).await;
+        assert!(filtered.is_empty());
+    }
+}
@@ -1,7 +1,11 @@
 //! File discovery module.
 //!
-//! Discovers source files in a repository while respecting .gitignore rules.
+//! Discovers source files in a repository while respecting .gitignore rules,
+//! and the package roots nested inside it (Cargo packages and workspaces,
+//! npm packages, git submodules) so monorepos can be modelled per package.
+//! [`PackageScope`] narrows a command to the files of one of them.
 
+use crate::types::{PackageKind, PackageRoot};
 use anyhow::Result;
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
@@ -122,6 +126,48 @@ impl FileDiscovery {
         Ok(files)
     }
 
+    /// Discover package roots under `root`, including `root` itself.
+    ///
+    /// A directory is a package root if it holds a `Cargo.toml` or
+    /// `package.json`, or is a nested git repository or submodule. Roots are
+    /// sorted by path, so enclosing packages come before nested ones.
+    pub fn discover_packages(&self, root: &Path) -> Result<Vec<PackageRoot>> {
+        let default_excludes = if self.default_excludes {
+            build_globset(default_exclude_patterns())?
+        } else {
+            GlobSetBuilder::new().build()?
+        };
+        let user_excludes = build_globset(self.exclude_patterns.iter().map(|s| s.as_str()))?;
+        let user_includes = build_globset(self.include_patterns.iter().map(|s| s.as_str()))?;
+
+        let walker = WalkBuilder::new(root)
+            .hidden(!self.include_hidden)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .require_git(false)
+            .build();
+
+        let mut packages = Vec::new();
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_some_and(|t| t.is_dir()) {
+                continue;
+            }
+            let dir = entry.path();
+            // Match directories the way their contents would be matched
+            let rel = dir.strip_prefix(root).unwrap_or(dir).join("");
+            if dir != root && is_excluded(&rel, &default_excludes, &user_excludes, &user_includes) {
+                continue;
+            }
+            if let Some(package) = package_at(dir, dir == root) {
+                packages.push(package);
+            }
+        }
+
+        packages.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(packages)
+    }
+
     /// Check if a file should be included based on extension.
     pub fn should_include(&self, path: &Path) -> bool {
         if self.include_large {
@@ -134,6 +180,168 @@ impl FileDiscovery {
     }
 }
 
+/// The files of one package: those under its root that no package nested
+/// inside it claims.
+#[derive(Debug, Clone)]
+pub struct PackageScope {
+    pub package: PackageRoot,
+    nested: Vec<PathBuf>,
+}
+
+impl PackageScope {
+    /// The scope of the package named `name` (manifest name, or directory
+    /// name for packages without one) among `packages`, as returned by
+    /// [`FileDiscovery::discover_packages`]. The outermost package wins if
+    /// several share the name.
+    pub fn find(packages: &[PackageRoot], name: &str) -> Option<Self> {
+        let package = packages.iter().find(|p| p.name == name)?.clone();
+        let nested = packages
+            .iter()
+            .filter(|p| p.path != package.path && p.path.starts_with(&package.path))
+            .map(|p| p.path.clone())
+            .collect();
+        Some(Self { package, nested })
+    }
+
+    /// Whether `file` (absolute, under the workspace root) belongs to the
+    /// package.
+    pub fn contains_file(&self, file: &Path) -> bool {
+        file.starts_with(&self.package.path) && !self.nested.iter().any(|n| file.starts_with(n))
+    }
+
+    /// Whether `file`, relative to `root` and `/`-separated as in search
+    /// docs and analysis reports, belongs to the package.
+    pub fn contains_relative(&self, root: &Path, file: &str) -> bool {
+        self.contains_file(&root.join(file))
+    }
+
+    /// The package directory relative to `root` (`.` for the root itself).
+    pub fn relative_dir(&self, root: &Path) -> String {
+        match self.package.path.strip_prefix(root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().replace('\\', "/"),
+            _ => ".".to_string(),
+        }
+    }
+}
+
+/// The package rooted at `dir`, if any. Nested git repositories only count
+/// below the workspace root.
+fn package_at(dir: &Path, is_root: bool) -> Option<PackageRoot> {
+    let dir_name = || {
+        dir.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("root")
+            .to_string()
+    };
+
+    let cargo = dir.join("Cargo.toml");
+    if cargo.is_file() {
+        let manifest = read_manifest(&cargo, |s| toml::from_str::<toml::Value>(s).ok());
+        let name = manifest
+            .as_ref()
+            .and_then(|m| m.get("package")?.get("name")?.as_str().map(str::to_string));
+        return Some(PackageRoot {
+            name: name.unwrap_or_else(dir_name),
+            path: dir.to_path_buf(),
+            kind: PackageKind::Cargo,
+            is_workspace: manifest
+                .as_ref()
+                .is_some_and(|m| m.get("workspace").is_some()),
+            dependencies: manifest
+                .as_ref()
+                .map(cargo_dependencies)
+                .unwrap_or_default(),
+        });
+    }
+
+    let npm = dir.join("package.json");
+    if npm.is_file() {
+        let manifest = read_manifest(&npm, |s| serde_json::from_str::<serde_json::Value>(s).ok());
+        let name = manifest
+            .as_ref()
+            .and_then(|m| m.get("name")?.as_str().map(str::to_string));
+        return Some(PackageRoot {
+            name: name.unwrap_or_else(dir_name),
+            path: dir.to_path_buf(),
+            kind: PackageKind::Npm,
+            is_workspace: manifest
+                .as_ref()
+                .is_some_and(|m| m.get("workspaces").is_some())
+                || dir.join("pnpm-workspace.yaml").is_file(),
+            dependencies: manifest.as_ref().map(npm_dependencies).unwrap_or_default(),
+        });
+    }
+
+    // Submodules have a `.git` file pointing into the parent's git dir
+    if !is_root && dir.join(".git").exists() {
+        return Some(PackageRoot {
+            name: dir_name(),
+            path: dir.to_path_buf(),
+            kind: PackageKind::Git,
+            is_workspace: false,
+            dependencies: Vec::new(),
+        });
+    }
+    None
+}
+
+/// Package names from `[dependencies]`, `[dev-dependencies]`, and
+/// `[build-dependencies]`, including `[target.*.…]` tables. Renamed
+/// dependencies (`alias = { package = "real" }`) report the real name.
+fn cargo_dependencies(manifest: &toml::Value) -> Vec<String> {
+    const TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+    let mut tables: Vec<&toml::Value> = TABLES.iter().filter_map(|t| manifest.get(t)).collect();
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            tables.extend(TABLES.iter().filter_map(|t| target.get(t)));
+        }
+    }
+
+    let mut names: Vec<String> = tables
+        .into_iter()
+        .filter_map(|t| t.as_table())
+        .flat_map(|t| t.iter())
+        .map(|(key, spec)| {
+            spec.get("package")
+                .and_then(|p| p.as_str())
+                .unwrap_or(key)
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Package names from `dependencies`, `devDependencies`,
+/// `peerDependencies`, and `optionalDependencies`.
+fn npm_dependencies(manifest: &serde_json::Value) -> Vec<String> {
+    const FIELDS: [&str; 4] = [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ];
+    let mut names: Vec<String> = FIELDS
+        .iter()
+        .filter_map(|f| manifest.get(f)?.as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Read and parse a manifest, warning (rather than failing discovery) if it
+/// is unreadable or malformed.
+fn read_manifest<T>(path: &Path, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let parsed = fs::read_to_string(path).ok().and_then(|s| parse(&s));
+    if parsed.is_none() {
+        tracing::warn!("Ignoring unreadable manifest {}", path.display());
+    }
+    parsed
+}
+
 fn default_exclude_patterns() -> Vec<&'static str> {
     vec![
         "**/.git/**",
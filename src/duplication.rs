@@ -0,0 +1,227 @@
+//! Duplication pressure: a cheap, always-available health signal.
+//!
+//! Once the semantic index is built, every symbol vector is compared with its
+//! nearest neighbours, and pairs closer than [`DUPLICATE_SIMILARITY`] (cosine)
+//! are counted and grouped into clusters. The summary is cached in
+//! `.omni/duplication.json`, so `omni stats` and the MCP `index` status
+//! report it without loading embeddings or running the full clone analysis.
+
+use crate::cache::{duplication_path, ensure_cache_dir};
+use crate::error::{OmniError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Cosine similarity above which two symbols count as near duplicates.
+pub const DUPLICATE_SIMILARITY: f32 = 0.92;
+/// Clusters kept in the summary, largest first.
+pub const MAX_CLUSTERS: usize = 5;
+/// Symbols listed per cluster.
+const MAX_CLUSTER_SYMBOLS: usize = 10;
+
+/// Near-duplicate symbols connected through high-similarity pairs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DuplicateCluster {
+    /// Symbols in the cluster
+    pub size: usize,
+    /// Scoped names, sorted, at most ten
+    pub symbols: Vec<String>,
+    /// Highest similarity of a pair in the cluster
+    pub max_similarity: f32,
+}
+
+/// High-similarity pairs among the embedded symbols.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DuplicationPressure {
+    /// Symbols compared
+    pub symbols: usize,
+    /// Pairs above [`DUPLICATE_SIMILARITY`]
+    pub pairs: usize,
+    /// Symbols in at least one such pair
+    pub duplicated_symbols: usize,
+    /// Largest clusters first, at most [`MAX_CLUSTERS`]
+    pub clusters: Vec<DuplicateCluster>,
+    /// Unix time (seconds) of the semantic build it was computed from
+    pub computed_at: u64,
+}
+
+impl DuplicationPressure {
+    /// Summarize the near-duplicate `pairs` (scoped names and similarity)
+    /// found among `symbols` compared symbols. Pairs may be listed in both
+    /// orders.
+    pub fn from_pairs(symbols: usize, pairs: &[(String, String, f32)]) -> Self {
+        let mut unique: HashMap<(&str, &str), f32> = HashMap::new();
+        for (a, b, similarity) in pairs {
+            if a == b {
+                continue;
+            }
+            let key = if a < b {
+                (a.as_str(), b.as_str())
+            } else {
+                (b.as_str(), a.as_str())
+            };
+            let entry = unique.entry(key).or_insert(*similarity);
+            *entry = entry.max(*similarity);
+        }
+
+        // Union-find over the symbols of the pairs
+        let mut parent: HashMap<&str, &str> = HashMap::new();
+        fn find<'a>(parent: &mut HashMap<&'a str, &'a str>, symbol: &'a str) -> &'a str {
+            let mut root = symbol;
+            while let Some(&next) = parent.get(root).filter(|&&next| next != root) {
+                root = next;
+            }
+            parent.insert(symbol, root);
+            root
+        }
+        for &(a, b) in unique.keys() {
+            parent.entry(a).or_insert(a);
+            parent.entry(b).or_insert(b);
+            let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+            if ra != rb {
+                let (keep, merge) = if ra < rb { (ra, rb) } else { (rb, ra) };
+                parent.insert(merge, keep);
+            }
+        }
+
+        let members: Vec<&str> = parent.keys().copied().collect();
+        let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for symbol in &members {
+            let root = find(&mut parent, symbol);
+            groups.entry(root).or_default().push(symbol);
+        }
+        let mut best: HashMap<&str, f32> = HashMap::new();
+        for (&(a, _), &similarity) in &unique {
+            let root = find(&mut parent, a);
+            let entry = best.entry(root).or_insert(similarity);
+            *entry = entry.max(similarity);
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = groups
+            .into_iter()
+            .map(|(root, mut symbols)| {
+                symbols.sort_unstable();
+                DuplicateCluster {
+                    size: symbols.len(),
+                    symbols: symbols
+                        .into_iter()
+                        .take(MAX_CLUSTER_SYMBOLS)
+                        .map(str::to_string)
+                        .collect(),
+                    max_similarity: best.get(root).copied().unwrap_or(0.0),
+                }
+            })
+            .collect();
+        clusters.sort_by(|a, b| {
+            b.size
+                .cmp(&a.size)
+                .then_with(|| b.max_similarity.total_cmp(&a.max_similarity))
+                .then_with(|| a.symbols.cmp(&b.symbols))
+        });
+        clusters.truncate(MAX_CLUSTERS);
+
+        let duplicated: HashSet<&str> = unique.keys().flat_map(|&(a, b)| [a, b]).collect();
+        Self {
+            symbols,
+            pairs: unique.len(),
+            duplicated_symbols: duplicated.len(),
+            clusters,
+            computed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Share of compared symbols with a near duplicate, in `[0, 1]`.
+    pub fn pressure(&self) -> f64 {
+        if self.symbols == 0 {
+            return 0.0;
+        }
+        self.duplicated_symbols as f64 / self.symbols as f64
+    }
+
+    /// One-line summary, e.g. `4.1% of 1200 symbols (31 pairs, largest
+    /// cluster 6)`.
+    pub fn summary(&self) -> String {
+        let largest = match self.clusters.first() {
+            Some(cluster) => format!(", largest cluster {}", cluster.size),
+            None => String::new(),
+        };
+        format!(
+            "{:.1}% of {} symbols ({} pairs{})",
+            self.pressure() * 100.0,
+            self.symbols,
+            self.pairs,
+            largest
+        )
+    }
+
+    /// The summary cached by the last semantic build, if any.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = duplication_path(root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| OmniError::corrupt(&path, e))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        ensure_cache_dir(root)?;
+        let path = duplication_path(root);
+        let data = serde_json::to_vec_pretty(self).map_err(|e| OmniError::corrupt(&path, e))?;
+        fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(a: &str, b: &str, similarity: f32) -> (String, String, f32) {
+        (a.to_string(), b.to_string(), similarity)
+    }
+
+    #[test]
+    fn test_pairs_group_into_clusters() {
+        let pressure = DuplicationPressure::from_pairs(
+            20,
+            &[
+                pair("crate::load_a", "crate::load_b", 0.95),
+                // Found from both ends
+                pair("crate::load_b", "crate::load_a", 0.95),
+                pair("crate::load_b", "crate::load_c", 0.93),
+                pair("crate::draw", "crate::paint", 0.99),
+                pair("crate::same", "crate::same", 1.0),
+            ],
+        );
+        assert_eq!(pressure.pairs, 3);
+        assert_eq!(pressure.duplicated_symbols, 5);
+        assert_eq!(pressure.pressure(), 0.25);
+        assert_eq!(pressure.clusters.len(), 2);
+        assert_eq!(
+            pressure.clusters[0].symbols,
+            ["crate::load_a", "crate::load_b", "crate::load_c"]
+        );
+        assert_eq!(pressure.clusters[0].max_similarity, 0.95);
+        assert_eq!(pressure.clusters[1].size, 2);
+        assert_eq!(
+            pressure.summary(),
+            "25.0% of 20 symbols (3 pairs, largest cluster 3)"
+        );
+
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(DuplicationPressure::load(temp.path()).unwrap(), None);
+        pressure.save(temp.path()).unwrap();
+        assert_eq!(
+            DuplicationPressure::load(temp.path()).unwrap(),
+            Some(pressure)
+        );
+        assert_eq!(DuplicationPressure::default().pressure(), 0.0);
+    }
+}
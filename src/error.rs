@@ -0,0 +1,111 @@
+//! Typed errors for the library API.
+//!
+//! Public indexing, search, cache, and embedding functions return
+//! [`OmniError`] so embedders can react to specific failures (e.g. rebuild on
+//! [`OmniError::Corrupt`], prompt for `omni index` on
+//! [`OmniError::IndexMissing`]). `OmniError` converts into `anyhow::Error`,
+//! so the binaries keep using `?`; recover it with
+//! `err.downcast_ref::<OmniError>()`.
+
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Result alias for the library API.
+pub type Result<T> = std::result::Result<T, OmniError>;
+
+/// Errors returned by the library API.
+#[derive(Debug, Error)]
+pub enum OmniError {
+    /// Filesystem error on `path`
+    #[error("I/O error on {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Source could not be parsed
+    #[error("Failed to parse {}: {message}", .path.display())]
+    Parse { path: PathBuf, message: String },
+    /// No persisted index under the given root
+    #[error("Index not found under {}; run `omni index`", .0.display())]
+    IndexMissing(PathBuf),
+    /// No parser for a file or language name
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+    /// The embedding model failed to load or run
+    #[error("Embedding backend error: {0}")]
+    EmbeddingBackend(String),
+    /// Persisted index data could not be decoded or written
+    #[error("Corrupt index data in {}: {message}", .path.display())]
+    Corrupt { path: PathBuf, message: String },
+    /// Another process (pid) holds the index writer lock
+    #[error("Index is written by the omni server running as pid {0}; use its index tool instead")]
+    IndexLocked(u32),
+    /// Errors not classified above
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl OmniError {
+    pub fn io(path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn parse(path: &Path, message: impl ToString) -> Self {
+        Self::Parse {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn corrupt(path: &Path, message: impl ToString) -> Self {
+        Self::Corrupt {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn embedding(context: &str, err: impl std::fmt::Display) -> Self {
+        Self::EmbeddingBackend(format!("{context}: {err}"))
+    }
+
+    /// Stable machine-readable code, as used in CLI JSON errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Parse { .. } => "parse",
+            Self::IndexMissing(_) => "index_missing",
+            Self::UnsupportedLanguage(_) => "unsupported_language",
+            Self::EmbeddingBackend(_) => "embedding_backend",
+            Self::Corrupt { .. } => "corrupt",
+            Self::IndexLocked(_) => "index_locked",
+            Self::Other(_) => "internal",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrips_through_anyhow() {
+        let err: anyhow::Error = OmniError::IndexMissing(PathBuf::from("/repo")).into();
+        let typed = err.downcast_ref::<OmniError>().unwrap();
+        assert_eq!(typed.code(), "index_missing");
+        assert!(err.to_string().contains("/repo"));
+    }
+
+    #[test]
+    fn test_io_keeps_source() {
+        let err = OmniError::io(
+            Path::new("/repo/a.rs"),
+            std::io::Error::new(std::io::ErrorKind::NotFound, "gone"),
+        );
+        assert_eq!(err.to_string(), "I/O error on /repo/a.rs: gone");
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
@@ -0,0 +1,713 @@
+//! Evidence dossier for one symbol.
+//!
+//! `omni explain <symbol>` gathers what the index knows about a definition
+//! before an agent edits it, without any model in the loop: the definition
+//! and its docs, its most frequent callers and callees, the types it works
+//! with, the PageRank standing of its file, the tests that reach it, and the
+//! git history and owners of its lines.
+//!
+//! Owners come from a `CODEOWNERS` file (`CODEOWNERS`, `.github/`, or
+//! `docs/`) when one matches the file, and otherwise from the authors of the
+//! commits that touched the symbol.
+
+use crate::call_resolution::resolve_callers;
+use crate::state::OciState;
+use crate::test_impact::{AffectedTest, affected_tests};
+use crate::types::{SymbolDef, SymbolKind, Visibility};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Callers, callees, and tests listed when no limit is given.
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// Recent commits listed in the history.
+const RECENT_COMMITS: usize = 5;
+
+/// Longest signature echoed, in characters.
+const MAX_SIGNATURE_CHARS: usize = 240;
+
+/// Where `CODEOWNERS` may live, in the order GitHub looks.
+const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Everything the index knows about a symbol.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Explanation {
+    /// Scoped name of the explained definition
+    pub symbol: String,
+    pub kind: String,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    pub end_line: usize,
+    pub visibility: String,
+    /// Definition head up to its body, whitespace collapsed
+    pub signature: Option<String>,
+    pub doc: Option<String>,
+    pub attributes: Vec<String>,
+    /// Other definitions sharing the simple name
+    pub other_definitions: Vec<String>,
+    /// Call sites attributed to this definition
+    pub caller_count: usize,
+    /// Callers with the most call sites first
+    pub callers: Vec<Neighbor>,
+    /// Call sites inside this definition
+    pub callee_count: usize,
+    /// Most called first
+    pub callees: Vec<Neighbor>,
+    pub types: Vec<RelatedType>,
+    pub standing: Standing,
+    pub tests: TestEvidence,
+    /// Line coverage from a coverage report, when one was given
+    pub coverage: Option<CoverageSummary>,
+    /// `None` outside a git repository
+    pub history: Option<History>,
+    pub owners: Owners,
+}
+
+/// A caller or callee, with the number of call sites between the two.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Neighbor {
+    pub symbol: String,
+    /// `None` for callees outside the workspace
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub calls: usize,
+    /// Defined in test code
+    pub is_test: bool,
+}
+
+/// A type the symbol works with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct RelatedType {
+    pub symbol: String,
+    pub kind: String,
+    /// `parent`, `signature`, `implements`, or `implemented by`
+    pub relation: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// PageRank standing of the symbol's file.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Standing {
+    pub file_relevance: f64,
+    /// 1-based position among indexed files, most relevant first
+    pub file_rank: usize,
+    pub files: usize,
+    pub role: String,
+}
+
+/// Tests that reach the symbol through calls.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TestEvidence {
+    pub total: usize,
+    /// Nearest first
+    pub nearest: Vec<AffectedTest>,
+}
+
+/// Coverage of the symbol's lines in a coverage report.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CoverageSummary {
+    pub lines_covered: u32,
+    pub lines_total: u32,
+    pub branches_covered: u32,
+    pub branches_total: u32,
+}
+
+/// Commits that touched the symbol's lines (`git log -L`).
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct History {
+    pub commits: usize,
+    /// Date of the oldest commit, `YYYY-MM-DD`
+    pub first_changed: Option<String>,
+    /// Date of the newest commit, `YYYY-MM-DD`
+    pub last_changed: Option<String>,
+    /// Newest first
+    pub recent: Vec<Commit>,
+    /// Most commits first
+    pub authors: Vec<Author>,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Author {
+    pub name: String,
+    pub commits: usize,
+}
+
+/// Who to ask about the symbol.
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct Owners {
+    /// `CODEOWNERS`, `git history`, or `none`
+    pub source: String,
+    pub owners: Vec<String>,
+}
+
+/// Explain `spec`, a scoped or simple symbol name. A simple name shared by
+/// several definitions picks the production, public, first-in-file-order
+/// one and lists the others. Returns `None` if nothing by that name is
+/// indexed.
+pub fn explain(state: &OciState, spec: &str, limit: usize) -> Option<Explanation> {
+    let (symbol, others) = resolve(state, spec)?;
+    let scoped = state.resolve(symbol.scoped_name).to_string();
+    let name = state.resolve(symbol.name).to_string();
+    let file = relative(state, &symbol.location.file);
+    let source = std::fs::read_to_string(&symbol.location.file).ok();
+
+    let (caller_count, callers) = callers(state, &symbol, &scoped, &name, &file, limit);
+    let (callee_count, callees) = callees(state, &symbol, limit);
+    let tests = affected_tests(state, &scoped, limit)
+        .map(|affected| TestEvidence {
+            total: affected.total,
+            nearest: affected.tests,
+        })
+        .unwrap_or(TestEvidence {
+            total: 0,
+            nearest: Vec::new(),
+        });
+
+    let history = source
+        .as_deref()
+        .and_then(|source| history(&symbol, source));
+    let owners = match codeowners(&state.root_path, &file) {
+        Some(owners) => Owners {
+            source: "CODEOWNERS".to_string(),
+            owners,
+        },
+        None => match &history {
+            Some(history) if !history.authors.is_empty() => Owners {
+                source: "git history".to_string(),
+                owners: history
+                    .authors
+                    .iter()
+                    .take(3)
+                    .map(|a| a.name.clone())
+                    .collect(),
+            },
+            _ => Owners {
+                source: "none".to_string(),
+                owners: Vec::new(),
+            },
+        },
+    };
+
+    Some(Explanation {
+        kind: symbol.kind.as_str().to_string(),
+        line: symbol.location.start_line,
+        end_line: symbol.location.end_line,
+        visibility: symbol.visibility.as_str().to_string(),
+        signature: source.as_deref().and_then(|s| signature(&symbol, s)),
+        doc: symbol.doc_comment.clone(),
+        attributes: symbol.attributes.iter().map(|a| a.to_string()).collect(),
+        other_definitions: others
+            .iter()
+            .map(|s| state.resolve(s.scoped_name).to_string())
+            .collect(),
+        caller_count,
+        callers,
+        callee_count,
+        callees,
+        types: related_types(state, &symbol, &name),
+        standing: standing(state, &symbol.location.file),
+        tests,
+        coverage: None,
+        history,
+        owners,
+        symbol: scoped,
+        file,
+    })
+}
+
+/// The definition `spec` names and the other definitions sharing its name.
+fn resolve(state: &OciState, spec: &str) -> Option<(SymbolDef, Vec<SymbolDef>)> {
+    let exact = state.lookup(spec).and_then(|key| state.get_symbol(key));
+    let name = match &exact {
+        Some(symbol) => state.resolve(symbol.name).to_string(),
+        None => spec.to_string(),
+    };
+    let mut candidates = state.find_by_name(&name);
+    candidates.sort_by(|a, b| {
+        a.is_test_context
+            .cmp(&b.is_test_context)
+            .then_with(|| {
+                (a.visibility != Visibility::Public).cmp(&(b.visibility != Visibility::Public))
+            })
+            .then_with(|| a.location.file.cmp(&b.location.file))
+            .then_with(|| a.location.start_byte.cmp(&b.location.start_byte))
+    });
+    let symbol = match exact {
+        Some(symbol) => symbol,
+        None => candidates.first()?.clone(),
+    };
+    candidates.retain(|s| s.scoped_name != symbol.scoped_name);
+    candidates.truncate(DEFAULT_LIMIT);
+    Some((symbol, candidates))
+}
+
+/// Call sites attributed to the definition, grouped by calling symbol.
+fn callers(
+    state: &OciState,
+    symbol: &SymbolDef,
+    scoped: &str,
+    name: &str,
+    file: &Path,
+    limit: usize,
+) -> (usize, Vec<Neighbor>) {
+    if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) {
+        return (0, Vec::new());
+    }
+    let Some(candidate) = resolve_callers(state, name)
+        .into_iter()
+        .find(|c| c.callee == scoped && c.file == file)
+    else {
+        return (0, Vec::new());
+    };
+
+    let mut grouped: HashMap<String, Neighbor> = HashMap::new();
+    for caller in &candidate.callers {
+        grouped
+            .entry(caller.caller.clone())
+            .or_insert_with(|| {
+                let def = state
+                    .lookup(&caller.caller)
+                    .and_then(|key| state.get_symbol(key));
+                Neighbor {
+                    symbol: caller.caller.clone(),
+                    file: Some(caller.file.clone()),
+                    line: Some(def.map_or(caller.line, |d| d.location.start_line)),
+                    calls: 0,
+                    is_test: caller.is_test_context,
+                }
+            })
+            .calls += 1;
+    }
+    (candidate.callers.len(), ranked(grouped, limit))
+}
+
+/// Calls made inside the definition, grouped by callee.
+fn callees(state: &OciState, symbol: &SymbolDef, limit: usize) -> (usize, Vec<Neighbor>) {
+    let edges = state.find_callees(symbol.scoped_name);
+    let mut grouped: HashMap<String, Neighbor> = HashMap::new();
+    for edge in &edges {
+        let def = match edge.resolved_callee {
+            Some(resolved) => state.get_symbol(resolved),
+            None => {
+                let mut defs = state.find_by_name(&edge.callee_name);
+                (defs.len() == 1).then(|| defs.remove(0))
+            }
+        };
+        let key = def.as_ref().map_or(edge.callee_name.clone(), |d| {
+            state.resolve(d.scoped_name).to_string()
+        });
+        grouped
+            .entry(key.clone())
+            .or_insert_with(|| Neighbor {
+                symbol: key,
+                file: def.as_ref().map(|d| relative(state, &d.location.file)),
+                line: def.as_ref().map(|d| d.location.start_line),
+                calls: 0,
+                is_test: def.as_ref().is_some_and(|d| d.is_test_context),
+            })
+            .calls += 1;
+    }
+    (edges.len(), ranked(grouped, limit))
+}
+
+fn ranked(grouped: HashMap<String, Neighbor>, limit: usize) -> Vec<Neighbor> {
+    let mut neighbors: Vec<Neighbor> = grouped.into_values().collect();
+    neighbors.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.symbol.cmp(&b.symbol)));
+    neighbors.truncate(limit);
+    neighbors
+}
+
+/// The parent type and signature types of a function, or the trait impls
+/// of a type.
+fn related_types(state: &OciState, symbol: &SymbolDef, name: &str) -> Vec<RelatedType> {
+    let mut types: Vec<RelatedType> = Vec::new();
+    let mut push = |def: SymbolDef, relation: &str| {
+        let related = RelatedType {
+            symbol: state.resolve(def.scoped_name).to_string(),
+            kind: def.kind.as_str().to_string(),
+            relation: relation.to_string(),
+            file: relative(state, &def.location.file),
+            line: def.location.start_line,
+        };
+        if related.symbol != state.resolve(symbol.scoped_name)
+            && !types.iter().any(|t| t.symbol == related.symbol)
+        {
+            types.push(related);
+        }
+    };
+
+    let impls: Vec<crate::types::TraitImpl> = state
+        .trait_impls
+        .iter()
+        .flat_map(|entry| entry.value().clone())
+        .collect();
+    match symbol.kind {
+        SymbolKind::Function | SymbolKind::Method => {
+            if let Some(parent) = symbol.parent.and_then(|p| state.get_symbol(p)) {
+                let parent_name = state.resolve(parent.name).to_string();
+                // Methods hang off their impl; report the type itself
+                match type_named(state, &parent_name) {
+                    Some(def) => push(def, "parent"),
+                    None => push(parent, "parent"),
+                }
+            }
+            if let Some(sig) = &symbol.signature {
+                let text = sig
+                    .params
+                    .iter()
+                    .chain(&sig.return_type)
+                    .chain(&sig.generics)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                for word in type_words(&text) {
+                    if let Some(def) = type_named(state, &word) {
+                        push(def, "signature");
+                    }
+                }
+            }
+            for imp in impls
+                .iter()
+                .filter(|i| i.methods.contains(&symbol.scoped_name))
+            {
+                if let Some(def) = type_named(state, &imp.trait_name) {
+                    push(def, "implements");
+                }
+            }
+        }
+        SymbolKind::Struct | SymbolKind::Enum | SymbolKind::TypeAlias => {
+            for imp in impls.iter().filter(|i| i.type_name == name) {
+                if let Some(def) = type_named(state, &imp.trait_name) {
+                    push(def, "implements");
+                }
+            }
+        }
+        SymbolKind::Trait => {
+            for imp in impls.iter().filter(|i| i.trait_name == name) {
+                if let Some(def) = type_named(state, &imp.type_name) {
+                    push(def, "implemented by");
+                }
+            }
+        }
+        _ => {}
+    }
+    types
+}
+
+/// Capitalized identifiers in a type expression.
+fn type_words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_uppercase()) && w != &"Self")
+        .map(str::to_string)
+        .collect()
+}
+
+/// The indexed type (struct, enum, trait, alias) by simple name, preferring
+/// production code.
+fn type_named(state: &OciState, name: &str) -> Option<SymbolDef> {
+    state
+        .find_by_name(name)
+        .into_iter()
+        .filter(|s| {
+            matches!(
+                s.kind,
+                SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Trait | SymbolKind::TypeAlias
+            )
+        })
+        .min_by(|a, b| {
+            a.is_test_context
+                .cmp(&b.is_test_context)
+                .then_with(|| a.location.file.cmp(&b.location.file))
+        })
+}
+
+fn standing(state: &OciState, file: &Path) -> Standing {
+    let relevance = state.relevance_of_file(file);
+    let scores: Vec<f64> = state
+        .file_ids
+        .iter()
+        .map(|entry| state.relevance_of_file(entry.key()))
+        .collect();
+    Standing {
+        file_relevance: relevance,
+        file_rank: scores.iter().filter(|&&s| s > relevance).count() + 1,
+        files: scores.len(),
+        role: state.file_role(file).as_str().to_string(),
+    }
+}
+
+/// Definition head: the source from the symbol's start to its body or the
+/// end of its first statement.
+fn signature(symbol: &SymbolDef, source: &str) -> Option<String> {
+    let text = source.get(symbol.location.start_byte..symbol.location.end_byte)?;
+    let head = text.find(['{', ';', '=']).map_or(text, |end| &text[..end]);
+    let head = head.split_whitespace().collect::<Vec<_>>().join(" ");
+    if head.is_empty() {
+        return None;
+    }
+    Some(match head.char_indices().nth(MAX_SIGNATURE_CHARS) {
+        Some((cut, _)) => format!("{}...", &head[..cut]),
+        None => head,
+    })
+}
+
+/// Commits touching the symbol's lines, docs and attributes included.
+fn history(symbol: &SymbolDef, source: &str) -> Option<History> {
+    let location = symbol.full_location();
+    // Lines from byte offsets, since `start_line` is 0- or 1-based
+    // depending on the parser
+    let line_of = |byte: usize| source.get(..byte).map(|b| b.matches('\n').count() + 1);
+    let start = line_of(location.start_byte)?;
+    let end = line_of(location.end_byte)?.max(start);
+    let file = &location.file;
+
+    let mut cmd = Command::new("git");
+    cmd.current_dir(file.parent()?);
+    // Hooks export GIT_DIR and friends, which would point at another repo
+    for (key, _) in std::env::vars_os() {
+        if key.to_str().is_some_and(|k| k.starts_with("GIT_")) {
+            cmd.env_remove(&key);
+        }
+    }
+    let output = cmd
+        .arg("log")
+        .arg("--format=%x1e%h%x1f%an%x1f%as%x1f%s")
+        .arg("-s")
+        .arg(format!(
+            "-L{},{}:{}",
+            start,
+            end,
+            file.file_name()?.to_string_lossy()
+        ))
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+
+    let commits: Vec<Commit> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.strip_prefix('\x1e')?.splitn(4, '\x1f');
+            Some(Commit {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect();
+
+    let mut authors: HashMap<&str, usize> = HashMap::new();
+    for commit in &commits {
+        *authors.entry(&commit.author).or_default() += 1;
+    }
+    let mut authors: Vec<Author> = authors
+        .into_iter()
+        .map(|(name, commits)| Author {
+            name: name.to_string(),
+            commits,
+        })
+        .collect();
+    authors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+
+    Some(History {
+        commits: commits.len(),
+        first_changed: commits.last().map(|c| c.date.clone()),
+        last_changed: commits.first().map(|c| c.date.clone()),
+        recent: commits.into_iter().take(RECENT_COMMITS).collect(),
+        authors,
+    })
+}
+
+/// Owners of a root-relative file from the first `CODEOWNERS` found; the
+/// last matching rule wins. `None` without a file or a matching rule.
+fn codeowners(root: &Path, file: &Path) -> Option<Vec<String>> {
+    let contents = CODEOWNERS_PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(root.join(path)).ok())?;
+    let file = file.to_string_lossy().replace('\\', "/");
+    let mut owners = None;
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut fields = line.split_whitespace();
+        let Some(pattern) = fields.next() else {
+            continue;
+        };
+        if codeowners_matchers(pattern)
+            .iter()
+            .any(|m| m.is_match(&file))
+        {
+            owners = Some(fields.map(str::to_string).collect());
+        }
+    }
+    owners
+}
+
+/// Globs for a `CODEOWNERS` (gitignore-style) pattern: unanchored patterns
+/// match at any depth, and a pattern matching a directory covers its
+/// contents.
+fn codeowners_matchers(pattern: &str) -> Vec<GlobMatcher> {
+    let anchored = pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/');
+    let mut base = pattern
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_string();
+    if !anchored {
+        base = format!("**/{}", base);
+    }
+    [base.clone(), format!("{}/**", base)]
+        .iter()
+        .filter_map(|glob| {
+            GlobBuilder::new(glob)
+                .literal_separator(true)
+                .build()
+                .ok()
+                .map(|g| g.compile_matcher())
+        })
+        .collect()
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    fn git(repo: &Path, args: &[&str]) {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(repo);
+        for (key, _) in std::env::vars_os() {
+            if key.to_str().is_some_and(|k| k.starts_with("GIT_")) {
+                cmd.env_remove(&key);
+            }
+        }
+        let output = cmd
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    }
+
+    #[tokio::test]
+    async fn test_explains_symbol_from_index_evidence() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            r#"pub struct Config {
+    pub path: String,
+}
+
+/// Reads the config file.
+#[inline]
+pub fn load(path: &str) -> Config {
+    let path = normalize(path);
+    Config { path }
+}
+
+fn normalize(path: &str) -> String {
+    path.trim().to_string()
+}
+
+pub fn start() {
+    load("a");
+    load("b");
+}
+
+#[test]
+fn test_load() {
+    load("c");
+}
+"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("CODEOWNERS"), "*.rs @core\n/src/ @parsing-team\n").unwrap();
+        git(&root, &["init", "-q"]);
+        git(&root, &["add", "-A"]);
+        git(&root, &["commit", "-qm", "Add config loading"]);
+
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let explanation = explain(&state, "load", DEFAULT_LIMIT).unwrap();
+        assert_eq!(explanation.symbol, "crate::load");
+        assert_eq!(explanation.file, PathBuf::from("src/lib.rs"));
+        assert_eq!(
+            explanation.signature.as_deref(),
+            Some("pub fn load(path: &str) -> Config")
+        );
+        assert_eq!(
+            explanation.doc.as_deref(),
+            Some("/// Reads the config file.")
+        );
+        assert_eq!(explanation.attributes, vec!["inline"]);
+
+        assert_eq!(explanation.caller_count, 3);
+        assert_eq!(explanation.callers[0].symbol, "crate::start");
+        assert_eq!(explanation.callers[0].calls, 2);
+        assert!(explanation.callers.iter().any(|c| c.is_test));
+        assert_eq!(explanation.callees[0].symbol, "crate::normalize");
+        assert_eq!(
+            explanation.callees[0].file,
+            Some(PathBuf::from("src/lib.rs"))
+        );
+
+        assert!(
+            explanation
+                .types
+                .iter()
+                .any(|t| t.symbol == "crate::Config" && t.relation == "signature")
+        );
+        assert_eq!(explanation.standing.files, 1);
+        assert_eq!(explanation.standing.file_rank, 1);
+        assert_eq!(explanation.tests.total, 1);
+        assert_eq!(explanation.tests.nearest[0].symbol, "crate::test_load");
+
+        let history = explanation.history.unwrap();
+        assert_eq!(history.commits, 1);
+        assert_eq!(history.recent[0].subject, "Add config loading");
+        assert_eq!(history.authors[0].name, "Ada");
+        assert_eq!(explanation.owners.source, "CODEOWNERS");
+        assert_eq!(explanation.owners.owners, vec!["@parsing-team"]);
+
+        assert!(explain(&state, "missing", DEFAULT_LIMIT).is_none());
+    }
+
+    #[test]
+    fn test_codeowners_patterns() {
+        let matches = |pattern: &str, file: &str| {
+            codeowners_matchers(pattern)
+                .iter()
+                .any(|m| m.is_match(file))
+        };
+        assert!(matches("*.rs", "src/deep/lib.rs"));
+        assert!(matches("/src/", "src/deep/lib.rs"));
+        assert!(matches("docs", "a/docs/readme.md"));
+        assert!(!matches("/docs", "a/docs/readme.md"));
+        assert!(matches("src/*.rs", "src/lib.rs"));
+        assert!(!matches("src/*.rs", "src/deep/lib.rs"));
+    }
+}
@@ -1,27 +1,37 @@
 //! Export utilities for downstream tools (e.g., Engram).
+//!
+//! - [`export_engram_memory`]: a compact context summary
+//! - [`export_jsonl_symbols`]: every symbol as one JSON object per line, for
+//!   offline analysis (e.g. loading into pandas or DuckDB)
+//! - [`export_ctags`] / [`export_etags`]: tags files for editors (vim,
+//!   Emacs) and other tools that read them
 
+use crate::stack::StackReport;
 use crate::state::OciState;
-use crate::types::{SymbolDef, TopologyNode};
+use crate::types::{SymbolDef, SymbolKind, TopologyNode};
 use anyhow::Result;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct ExportStats {
     pub files: u32,
     pub symbols: u32,
     pub calls: u32,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct ExportFile {
     pub path: String,
     pub relevance: f64,
+    pub role: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct ExportSymbol {
     pub name: String,
     pub kind: String,
@@ -29,18 +39,21 @@ pub struct ExportSymbol {
     pub line: usize,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct EngramMemoryExport {
     pub content: String,
     pub metadata: EngramMetadata,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct EngramMetadata {
     pub source: String,
     pub workspace: String,
     pub generated_at_unix: u64,
     pub stats: ExportStats,
+    /// Detected languages, frameworks, and tooling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<StackReport>,
     pub top_files: Vec<ExportFile>,
     pub top_symbols: Vec<ExportSymbol>,
 }
@@ -72,6 +85,7 @@ pub fn export_engram_memory(
                 symbols: stats.symbol_count,
                 calls: stats.call_edge_count,
             },
+            stack: stats.stack.clone(),
             top_files,
             top_symbols,
         },
@@ -89,6 +103,7 @@ fn collect_top_files(state: &OciState, max_files: usize) -> Vec<ExportFile> {
             files.push(ExportFile {
                 path: path.display().to_string(),
                 relevance: metrics.relevance_score,
+                role: metrics.role.as_str().to_string(),
             });
         }
     }
@@ -123,6 +138,328 @@ fn to_export_symbol(state: &OciState, symbol: &SymbolDef) -> ExportSymbol {
     }
 }
 
+/// One line of the `jsonl-symbols` export.
+#[derive(Debug, Serialize)]
+pub struct JsonlSymbol<'a> {
+    /// Stable id: hash of file, scoped name, and start byte
+    pub id: String,
+    pub scoped_name: &'a str,
+    pub name: &'a str,
+    pub kind: &'static str,
+    /// Path relative to the workspace root
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// Start of the attached doc comments and attributes, when there are any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended_start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended_start_byte: Option<usize>,
+    pub visibility: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<JsonlSignature<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<&'a str>,
+    pub metrics: JsonlMetrics,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonlSignature<'a> {
+    pub params: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generics: Option<&'a str>,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    pub is_const: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonlMetrics {
+    pub lines: usize,
+    pub bytes: usize,
+    /// Call sites naming this symbol (matched by simple name)
+    pub callers: usize,
+    /// Call sites inside this symbol
+    pub callees: usize,
+    /// PageRank of the containing file
+    pub file_relevance: f64,
+    pub role: &'static str,
+}
+
+/// Stream every symbol to `out` as JSON Lines, sorted by file and position.
+///
+/// Returns the number of symbols written.
+pub fn export_jsonl_symbols(state: &OciState, root: &Path, mut out: impl Write) -> Result<usize> {
+    let mut callers: HashMap<String, usize> = HashMap::new();
+    let mut callees: HashMap<crate::types::InternedString, usize> = HashMap::new();
+    for edge in state.call_edges.read().iter() {
+        *callers.entry(edge.callee_name.clone()).or_default() += 1;
+        *callees.entry(edge.caller).or_default() += 1;
+    }
+
+    let symbols = sorted_symbols(state);
+
+    let mut relevance: HashMap<&Path, (f64, &'static str)> = HashMap::new();
+    for symbol in &symbols {
+        let file = symbol.location.file.as_path();
+        let (file_relevance, role) = *relevance.entry(file).or_insert_with(|| {
+            (
+                state.relevance_of_file(file),
+                state.file_role(file).as_str(),
+            )
+        });
+        let rel = file
+            .strip_prefix(root)
+            .unwrap_or(file)
+            .display()
+            .to_string();
+        let scoped_name = state.resolve(symbol.scoped_name);
+        let name = state.resolve(symbol.name);
+        let id = crate::cache::content_hash(
+            format!("{}\0{}\0{}", rel, scoped_name, symbol.location.start_byte).as_bytes(),
+        );
+
+        let record = JsonlSymbol {
+            id: format!("{:016x}", id),
+            scoped_name,
+            name,
+            kind: symbol.kind.as_str(),
+            file: rel,
+            start_line: symbol.location.start_line,
+            end_line: symbol.location.end_line,
+            start_byte: symbol.location.start_byte,
+            end_byte: symbol.location.end_byte,
+            extended_start_line: symbol.extended_location.as_ref().map(|l| l.start_line),
+            extended_start_byte: symbol.extended_location.as_ref().map(|l| l.start_byte),
+            visibility: symbol.visibility.as_str(),
+            parent: symbol.parent.map(|p| state.resolve(p)),
+            signature: symbol.signature.as_ref().map(|sig| JsonlSignature {
+                params: &sig.params,
+                return_type: sig.return_type.as_deref(),
+                generics: sig.generics.as_deref(),
+                is_async: sig.is_async,
+                is_unsafe: sig.is_unsafe,
+                is_const: sig.is_const,
+            }),
+            attributes: symbol.attributes.iter().map(|a| a.to_string()).collect(),
+            doc: symbol.doc_comment.as_deref(),
+            deprecated: symbol.deprecation(),
+            metrics: JsonlMetrics {
+                lines: symbol
+                    .location
+                    .end_line
+                    .saturating_sub(symbol.location.start_line)
+                    + 1,
+                bytes: symbol
+                    .location
+                    .end_byte
+                    .saturating_sub(symbol.location.start_byte),
+                callers: callers.get(name).copied().unwrap_or(0),
+                callees: callees.get(&symbol.scoped_name).copied().unwrap_or(0),
+                file_relevance,
+                role,
+            },
+        };
+        serde_json::to_writer(&mut out, &record)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(symbols.len())
+}
+
+/// A symbol as written to a tags file.
+struct Tag<'a> {
+    name: &'a str,
+    /// Path relative to the workspace root
+    file: String,
+    /// 1-based line of the definition
+    line: usize,
+    byte: usize,
+    /// The definition's source line, for etags search patterns
+    text: String,
+    symbol: &'a SymbolDef,
+}
+
+/// Every symbol as a [`Tag`], ordered by file and position.
+///
+/// Lines come from the byte offset rather than `start_line`, whose base
+/// differs between parsers.
+fn collect_tags<'a>(state: &'a OciState, root: &Path, symbols: &'a [SymbolDef]) -> Vec<Tag<'a>> {
+    let mut sources: HashMap<&Path, Option<String>> = HashMap::new();
+    symbols
+        .iter()
+        .map(|symbol| {
+            let file = symbol.location.file.as_path();
+            let source = sources
+                .entry(file)
+                .or_insert_with(|| std::fs::read_to_string(file).ok());
+            let byte = symbol.location.start_byte;
+            let (line, text) = match source
+                .as_deref()
+                .and_then(|s| s.get(..byte).map(|b| (s, b)))
+            {
+                Some((source, before)) => {
+                    let start = before.rfind('\n').map_or(0, |i| i + 1);
+                    let end = source[byte..].find('\n').map_or(source.len(), |i| byte + i);
+                    (
+                        before.matches('\n').count() + 1,
+                        source[start..end].trim_end_matches('\r').to_string(),
+                    )
+                }
+                None => (symbol.location.start_line + 1, String::new()),
+            };
+            Tag {
+                name: state.resolve(symbol.name),
+                file: file
+                    .strip_prefix(root)
+                    .unwrap_or(file)
+                    .display()
+                    .to_string(),
+                line,
+                byte,
+                text,
+                symbol,
+            }
+        })
+        .collect()
+}
+
+fn sorted_symbols(state: &OciState) -> Vec<SymbolDef> {
+    let mut symbols: Vec<SymbolDef> = state.symbols.iter().map(|e| e.value().clone()).collect();
+    symbols.sort_by(|a, b| {
+        a.location
+            .file
+            .cmp(&b.location.file)
+            .then(a.location.start_byte.cmp(&b.location.start_byte))
+    });
+    symbols
+}
+
+/// Write a ctags file (extended format, sorted by name) to `out`.
+///
+/// Each tag is addressed by line number and carries `kind`, `line`,
+/// `scope` (the parent symbol, or the enclosing module), `signature`,
+/// `typeref` (return type), and `access` fields. Returns the number of
+/// tags written.
+pub fn export_ctags(state: &OciState, root: &Path, mut out: impl Write) -> Result<usize> {
+    let symbols = sorted_symbols(state);
+    let mut tags = collect_tags(state, root, &symbols);
+    tags.sort_by(|a, b| {
+        a.name
+            .cmp(b.name)
+            .then_with(|| a.file.cmp(&b.file))
+            .then(a.line.cmp(&b.line))
+    });
+
+    writeln!(
+        out,
+        "!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/"
+    )?;
+    writeln!(
+        out,
+        "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/"
+    )?;
+    writeln!(out, "!_TAG_PROGRAM_NAME\tomni\t//")?;
+    writeln!(
+        out,
+        "!_TAG_PROGRAM_VERSION\t{}\t//",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    for tag in &tags {
+        let symbol = tag.symbol;
+        write!(
+            out,
+            "{}\t{}\t{};\"\tkind:{}\tline:{}",
+            tag.name,
+            tag.file,
+            tag.line,
+            symbol.kind.as_str(),
+            tag.line
+        )?;
+        if let Some((kind, scope)) = tag_scope(state, symbol) {
+            write!(out, "\t{}:{}", kind, escape_tag_field(&scope))?;
+        }
+        if let Some(sig) = &symbol.signature {
+            write!(
+                out,
+                "\tsignature:({})",
+                escape_tag_field(&sig.params.join(", "))
+            )?;
+            if let Some(ret) = &sig.return_type {
+                write!(out, "\ttyperef:typename:{}", escape_tag_field(ret))?;
+            }
+        }
+        writeln!(out, "\taccess:{}", symbol.visibility.as_str())?;
+    }
+    out.flush()?;
+    Ok(tags.len())
+}
+
+/// Write an Emacs TAGS file to `out`: one section per file, each tag
+/// pointing at its definition line. Returns the number of tags written.
+pub fn export_etags(state: &OciState, root: &Path, mut out: impl Write) -> Result<usize> {
+    let symbols = sorted_symbols(state);
+    let tags = collect_tags(state, root, &symbols);
+
+    let mut start = 0;
+    while start < tags.len() {
+        let file = &tags[start].file;
+        let end = tags[start..]
+            .iter()
+            .position(|t| &t.file != file)
+            .map_or(tags.len(), |i| start + i);
+        let mut section = String::new();
+        for tag in &tags[start..end] {
+            section.push_str(&format!(
+                "{}\x7f{}\x01{},{}\n",
+                tag.text, tag.name, tag.line, tag.byte
+            ));
+        }
+        write!(out, "\x0c\n{},{}\n{}", file, section.len(), section)?;
+        start = end;
+    }
+    out.flush()?;
+    Ok(tags.len())
+}
+
+/// ctags scope field: the parent symbol (`struct:Config`), the type a
+/// method is implemented on (`impl:Config`), or the module a top-level item
+/// lives in (`module:parsing::rust`).
+fn tag_scope(state: &OciState, symbol: &SymbolDef) -> Option<(&'static str, String)> {
+    let unprefixed = |scoped: &str| scoped.strip_prefix("crate::").unwrap_or(scoped).to_string();
+    if let Some(parent) = symbol.parent.and_then(|p| state.get_symbol(p)) {
+        return Some((
+            parent.kind.as_str(),
+            unprefixed(state.resolve(parent.scoped_name)),
+        ));
+    }
+    let scoped = state.resolve(symbol.scoped_name);
+    let (owner, _) = scoped.rsplit_once("::")?;
+    if symbol.kind == SymbolKind::Method {
+        return Some(("impl", unprefixed(owner)));
+    }
+    (owner != "crate").then(|| ("module", unprefixed(owner)))
+}
+
+/// Escape a ctags field value (tabs, newlines, and backslashes).
+fn escape_tag_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
 fn format_engram_content(
     workspace: &Path,
     stats: &crate::state::IndexStats,
@@ -136,11 +473,17 @@ fn format_engram_content(
         "Indexed files: {}, symbols: {}, call edges: {}\n",
         stats.file_count, stats.symbol_count, stats.call_edge_count
     ));
+    if let Some(stack) = &stats.stack {
+        content.push_str(&format!("Stack: {}\n", stack.summary()));
+    }
 
     if !top_files.is_empty() {
         content.push_str("\nTop files by relevance:\n");
         for file in top_files {
-            content.push_str(&format!("- {} (score {:.4})\n", file.path, file.relevance));
+            content.push_str(&format!(
+                "- {} ({}, score {:.4})\n",
+                file.path, file.role, file.relevance
+            ));
         }
     }
 
@@ -0,0 +1,152 @@
+//! Focus paths: the files an agent's current task is about.
+//!
+//! An agent can hint the indexer with paths taken from its task description
+//! (`omni index --focus src/codec`, or `focus` on the MCP `index` tool). On a
+//! build, those files are parsed, published to the search index, and embedded
+//! before everything else, so lookups in them work while the rest of a cold
+//! index is still being built. For [`FOCUS_TTL`] afterwards their symbols also
+//! get a [`FOCUS_BOOST`] in search ranking.
+//!
+//! The hint is persisted in `.omni/focus.json` and expires on its own; like
+//! the rest of `.omni/`, `omni index --force` clears it unless given again.
+
+use crate::cache::{ensure_cache_dir, focus_path};
+use crate::error::{OmniError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Score multiplier for search results in a focus path.
+pub const FOCUS_BOOST: f32 = 1.3;
+/// How long a focus hint lasts.
+pub const FOCUS_TTL: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Root-relative files or directories to prioritize, and when that stops.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Focus {
+    pub paths: Vec<String>,
+    /// Unix time (seconds) after which the hint no longer applies
+    pub expires_at: u64,
+}
+
+impl Focus {
+    /// A hint on `paths` lasting [`FOCUS_TTL`]. Paths are normalized to
+    /// root-relative form without `./` or trailing slashes.
+    pub fn new(root: &Path, paths: &[String]) -> Self {
+        let paths = paths
+            .iter()
+            .map(|p| {
+                let path = Path::new(p);
+                let rel = path.strip_prefix(root).unwrap_or(path);
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                rel.trim_start_matches("./")
+                    .trim_end_matches('/')
+                    .to_string()
+            })
+            .filter(|p| !p.is_empty())
+            .collect();
+        Self {
+            paths,
+            expires_at: unix_now() + FOCUS_TTL.as_secs(),
+        }
+    }
+
+    /// Load the current hint, or an empty one if none was set or it expired.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = focus_path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+        let focus: Self =
+            serde_json::from_slice(&data).map_err(|e| OmniError::corrupt(&path, e))?;
+        Ok(if focus.is_active() {
+            focus
+        } else {
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        ensure_cache_dir(root)?;
+        let path = focus_path(root);
+        let data = serde_json::to_vec_pretty(self).map_err(|e| OmniError::corrupt(&path, e))?;
+        fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+    }
+
+    /// Whether the hint names any paths and has not expired.
+    pub fn is_active(&self) -> bool {
+        !self.paths.is_empty() && unix_now() < self.expires_at
+    }
+
+    /// Whether the root-relative `file` is, or is under, a focus path.
+    pub fn contains(&self, file: &str) -> bool {
+        self.paths.iter().any(|p| {
+            file.strip_prefix(p.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    /// Score multiplier for a result in the root-relative `file`.
+    pub fn boost(&self, file: &str) -> f32 {
+        if self.is_active() && self.contains(file) {
+            FOCUS_BOOST
+        } else {
+            1.0
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_matches_files_and_directories() {
+        let root = Path::new("/workspace");
+        let focus = Focus::new(
+            root,
+            &[
+                "./src/codec/".to_string(),
+                "/workspace/src/main.rs".to_string(),
+            ],
+        );
+        assert_eq!(focus.paths, vec!["src/codec", "src/main.rs"]);
+        assert!(focus.contains("src/codec/frame.rs"));
+        assert!(focus.contains("src/main.rs"));
+        assert!(!focus.contains("src/codec_utils.rs"));
+        assert_eq!(focus.boost("src/codec/frame.rs"), FOCUS_BOOST);
+        assert_eq!(focus.boost("src/lib.rs"), 1.0);
+
+        let expired = Focus {
+            expires_at: 0,
+            ..focus
+        };
+        assert_eq!(expired.boost("src/main.rs"), 1.0);
+    }
+
+    #[test]
+    fn test_expired_focus_loads_empty() {
+        let temp = tempfile::tempdir().unwrap();
+        Focus::new(temp.path(), &["src".to_string()])
+            .save(temp.path())
+            .unwrap();
+        assert!(Focus::load(temp.path()).unwrap().is_active());
+
+        Focus {
+            paths: vec!["src".to_string()],
+            expires_at: 0,
+        }
+        .save(temp.path())
+        .unwrap();
+        assert!(!Focus::load(temp.path()).unwrap().is_active());
+    }
+}
@@ -0,0 +1,150 @@
+//! Change impact across workspace crates.
+//!
+//! Answers "which crates rebuild (and which tests should run) if this file
+//! changes": the crate that owns the file plus every crate that depends on it,
+//! directly or transitively, following [`TopologyEdge::DependsOn`] edges
+//! built from package manifests.
+
+use crate::state::OciState;
+use crate::types::{TopologyEdge, TopologyNode};
+use petgraph::Direction;
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// A crate affected by a change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct ImpactedCrate {
+    pub name: String,
+    pub path: PathBuf,
+    /// Dependency hops from the changed crate (0 for the crate itself)
+    pub depth: usize,
+}
+
+/// Crates affected by changing one file.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct CrateImpact {
+    /// The crate that owns the file
+    pub owner: ImpactedCrate,
+    /// Crates that depend on the owner, nearest first
+    pub dependents: Vec<ImpactedCrate>,
+}
+
+impl CrateImpact {
+    /// Names of every crate to rebuild: the owner, then its dependents.
+    pub fn crate_names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.owner.name.as_str())
+            .chain(self.dependents.iter().map(|c| c.name.as_str()))
+    }
+}
+
+/// Crate-level impact of changing `file` (absolute, or relative to the
+/// workspace root). Returns `None` if no crate in the topology contains it.
+pub fn crate_impact(state: &OciState, file: &Path) -> Option<CrateImpact> {
+    let file = if file.is_absolute() {
+        file.to_path_buf()
+    } else {
+        state.root_path.join(file)
+    };
+    let graph = state.topology.read();
+
+    // The innermost crate whose directory contains the file
+    let owner = graph
+        .node_indices()
+        .filter_map(|idx| match &graph[idx] {
+            TopologyNode::Crate { path, .. } if file.starts_with(path) => {
+                Some((idx, path.components().count()))
+            }
+            _ => None,
+        })
+        .max_by_key(|(_, depth)| *depth)
+        .map(|(idx, _)| idx)?;
+
+    let describe = |idx: NodeIndex, depth: usize| match &graph[idx] {
+        TopologyNode::Crate { name, path, .. } => Some(ImpactedCrate {
+            name: name.clone(),
+            path: path.clone(),
+            depth,
+        }),
+        _ => None,
+    };
+
+    let mut seen = HashSet::from([owner]);
+    let mut queue = VecDeque::from([(owner, 0)]);
+    let mut dependents = Vec::new();
+    while let Some((idx, depth)) = queue.pop_front() {
+        for edge in graph.edges_directed(idx, Direction::Incoming) {
+            if !matches!(edge.weight(), TopologyEdge::DependsOn) {
+                continue;
+            }
+            let dependent = edge.source();
+            if seen.insert(dependent) {
+                dependents.extend(describe(dependent, depth + 1));
+                queue.push_back((dependent, depth + 1));
+            }
+        }
+    }
+    dependents.sort_by(|a, b| a.depth.cmp(&b.depth).then_with(|| a.name.cmp(&b.name)));
+
+    Some(CrateImpact {
+        owner: describe(owner, 0)?,
+        dependents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::create_state;
+    use crate::topology::TopologyBuilder;
+
+    #[test]
+    fn test_transitive_dependents() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let files = [
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"core\", \"api\", \"cli\", \"docs\"]\n",
+            ),
+            ("core/Cargo.toml", "[package]\nname = \"core\"\n"),
+            ("core/src/lib.rs", "pub fn parse() {}\n"),
+            (
+                "api/Cargo.toml",
+                "[package]\nname = \"api\"\n[dependencies]\ncore = { path = \"../core\" }\n",
+            ),
+            ("api/src/lib.rs", "pub fn serve() {}\n"),
+            (
+                "cli/Cargo.toml",
+                "[package]\nname = \"cli\"\n[dev-dependencies]\napi = { path = \"../api\" }\n",
+            ),
+            ("cli/src/main.rs", "fn main() {}\n"),
+            (
+                "docs/Cargo.toml",
+                "[package]\nname = \"docs\"\n[dependencies]\nserde = \"1\"\n",
+            ),
+            ("docs/src/lib.rs", "pub fn render() {}\n"),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let state = create_state(root.clone());
+        TopologyBuilder::new().build(&state, &root).unwrap();
+
+        let impact = crate_impact(&state, Path::new("core/src/lib.rs")).unwrap();
+        assert_eq!(impact.owner.name, "core");
+        let dependents: Vec<(&str, usize)> = impact
+            .dependents
+            .iter()
+            .map(|c| (c.name.as_str(), c.depth))
+            .collect();
+        assert_eq!(dependents, vec![("api", 1), ("cli", 2)]);
+
+        let impact = crate_impact(&state, &root.join("docs/src/lib.rs")).unwrap();
+        assert_eq!(impact.crate_names().collect::<Vec<_>>(), vec!["docs"]);
+    }
+}
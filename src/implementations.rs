@@ -0,0 +1,218 @@
+//! Trait implementation queries.
+//!
+//! Built on the `impl Trait for Type` blocks the Rust parser records in
+//! [`OciState::trait_impls`]: [`implementations`] answers "what implements
+//! `Shape`?", and [`trait_origins`] answers "which trait does
+//! `Circle::area` come from?".
+//!
+//! Traits are matched by their last path segment, as impl headers name
+//! them, so `fmt::Display` and `Display` are one trait, and same-named
+//! traits from different modules are listed together.
+
+use crate::state::OciState;
+use crate::types::{SymbolDef, SymbolKind, TraitImpl};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One `impl Trait for Type` block.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TraitImplementation {
+    /// Implementing type, as the impl header names it
+    pub type_name: String,
+    /// Scoped name of the type's workspace definition, if unique
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_symbol: Option<String>,
+    /// Scoped names of the methods defined in the block
+    pub methods: Vec<String>,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    /// The block is in test code
+    pub is_test: bool,
+}
+
+/// The implementations of a trait.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Implementations {
+    pub trait_name: String,
+    /// Scoped names of the workspace traits with that name; empty for a
+    /// trait from a dependency (`Display`)
+    pub definitions: Vec<String>,
+    /// Impl blocks, sorted by file and line
+    pub implementations: Vec<TraitImplementation>,
+}
+
+/// The trait a method is defined for.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TraitOrigin {
+    /// Scoped name of the method
+    pub method: String,
+    pub trait_name: String,
+    /// Scoped name of the trait's workspace definition, if unique
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trait_symbol: Option<String>,
+    #[serde(flatten)]
+    pub implementation: TraitImplementation,
+}
+
+/// Impl blocks of `spec`, a trait name or path. `None` if the workspace
+/// neither defines nor implements it.
+pub fn implementations(state: &OciState, spec: &str) -> Option<Implementations> {
+    let trait_name = spec.rsplit("::").next().unwrap_or(spec);
+    let trait_name = crate::parsing::normalize_identifier(trait_name).into_owned();
+    let definitions = definitions(state, &trait_name, &[SymbolKind::Trait]);
+
+    let mut impls = trait_impls(state, |imp| imp.trait_name == trait_name);
+    if definitions.is_empty() && impls.is_empty() {
+        return None;
+    }
+    impls.sort_by(|a, b| {
+        (&a.location.file, a.location.start_line).cmp(&(&b.location.file, b.location.start_line))
+    });
+    Some(Implementations {
+        implementations: impls.iter().map(|imp| implementation(state, imp)).collect(),
+        trait_name,
+        definitions,
+    })
+}
+
+/// The traits the methods named `spec` (scoped or simple) are defined for.
+/// `None` if no method matches; inherent methods have no origin.
+pub fn trait_origins(state: &OciState, spec: &str) -> Option<Vec<TraitOrigin>> {
+    let exact = state.lookup(spec).and_then(|key| state.get_symbol(key));
+    let methods: Vec<SymbolDef> = match exact {
+        Some(symbol) => vec![symbol],
+        None => state.find_by_name(spec),
+    }
+    .into_iter()
+    .filter(|d| matches!(d.kind, SymbolKind::Method | SymbolKind::Function))
+    .collect();
+    if methods.is_empty() {
+        return None;
+    }
+
+    let mut origins = Vec::new();
+    for method in &methods {
+        for imp in trait_impls(state, |imp| imp.methods.contains(&method.scoped_name)) {
+            let trait_symbol = unique(state, &imp.trait_name, &[SymbolKind::Trait]);
+            origins.push(TraitOrigin {
+                method: state.resolve(method.scoped_name).to_string(),
+                trait_name: imp.trait_name.clone(),
+                trait_symbol,
+                implementation: implementation(state, &imp),
+            });
+        }
+    }
+    origins.sort_by(|a, b| (&a.method, &a.trait_name).cmp(&(&b.method, &b.trait_name)));
+    Some(origins)
+}
+
+fn trait_impls(state: &OciState, keep: impl Fn(&TraitImpl) -> bool) -> Vec<TraitImpl> {
+    state
+        .trait_impls
+        .iter()
+        .flat_map(|entry| entry.value().clone())
+        .filter(|imp| keep(imp))
+        .collect()
+}
+
+fn implementation(state: &OciState, imp: &TraitImpl) -> TraitImplementation {
+    let file = relative(state, &imp.location.file);
+    TraitImplementation {
+        type_name: imp.type_name.clone(),
+        // Impl blocks share the scoped name of their type and can shadow
+        // its definition in the symbol table
+        type_symbol: unique(
+            state,
+            &imp.type_name,
+            &[
+                SymbolKind::Struct,
+                SymbolKind::Enum,
+                SymbolKind::TypeAlias,
+                SymbolKind::Impl,
+            ],
+        ),
+        methods: imp
+            .methods
+            .iter()
+            .map(|&m| state.resolve(m).to_string())
+            .collect(),
+        is_test: crate::topology::is_test_path(&file),
+        file,
+        line: imp.location.start_line,
+    }
+}
+
+/// Distinct scoped names of the definitions of `name` of one of `kinds`.
+fn definitions(state: &OciState, name: &str, kinds: &[SymbolKind]) -> Vec<String> {
+    let mut scoped: Vec<String> = state
+        .find_by_name(name)
+        .into_iter()
+        .filter(|d| kinds.contains(&d.kind))
+        .map(|d| state.resolve(d.scoped_name).to_string())
+        .collect();
+    scoped.sort();
+    scoped.dedup();
+    scoped
+}
+
+/// Scoped name of the only workspace definition of `name` of one of
+/// `kinds`.
+fn unique(state: &OciState, name: &str, kinds: &[SymbolKind]) -> Option<String> {
+    let mut scoped = definitions(state, name, kinds);
+    if scoped.len() == 1 {
+        scoped.pop()
+    } else {
+        None
+    }
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_implementations_and_trait_origins() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub trait Shape {\n    fn area(&self) -> f64;\n}\n\npub struct Square(f64);\n\nimpl Square {\n    pub fn side(&self) -> f64 {\n        self.0\n    }\n}\n\nimpl Shape for Square {\n    fn area(&self) -> f64 {\n        self.0 * self.0\n    }\n}\n\npub struct Unit;\n\nimpl std::fmt::Display for Unit {\n    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n        f.write_str(\"unit\")\n    }\n}\n",
+        )
+        .unwrap();
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let shape = implementations(&state, "crate::Shape").unwrap();
+        assert_eq!(shape.definitions, vec!["crate::Shape"]);
+        assert_eq!(shape.implementations.len(), 1);
+        let square = &shape.implementations[0];
+        assert_eq!(square.type_symbol.as_deref(), Some("crate::Square"));
+        assert_eq!(square.methods, vec!["crate::Square::area"]);
+
+        // A dependency's trait has impls but no definition
+        let display = implementations(&state, "fmt::Display").unwrap();
+        assert!(display.definitions.is_empty());
+        assert_eq!(display.implementations[0].type_name, "Unit");
+        assert!(implementations(&state, "Missing").is_none());
+
+        let origins = trait_origins(&state, "crate::Square::area").unwrap();
+        assert_eq!(origins.len(), 1);
+        assert_eq!(origins[0].trait_name, "Shape");
+        assert_eq!(origins[0].trait_symbol.as_deref(), Some("crate::Shape"));
+        assert!(trait_origins(&state, "side").unwrap().is_empty());
+        assert!(trait_origins(&state, "missing").is_none());
+    }
+}
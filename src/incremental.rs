@@ -2,20 +2,37 @@
 //!
 //! Handles efficient updates when files change, avoiding full re-indexing.
 
-use crate::cache::{FileFingerprint, IndexManifest, load_manifest};
+use crate::branch_cache::BranchSnapshot;
+use crate::cache::{FileFingerprint, IndexManifest};
+use crate::error::{OmniError, Result};
+use crate::focus::Focus;
 use crate::parsing;
-use crate::query::{SearchDoc, SearchState, rebuild_bm25, save_bm25, save_search_state};
-use crate::state::OciState;
+use crate::parsing::config::{ConfigFormat, extract_config_keys};
+use crate::parsing::edits::{HotTree, TreeCache};
+use crate::parsing::sql::{extract_schema_changes, is_sql_file, sql_symbols};
+use crate::query::{DocKind, SearchDoc, SearchState, rebuild_bm25, save_bm25, save_search_state};
+use crate::state::{FileBatch, OciState, Overlay};
 use crate::topology::TopologyBuilder;
-use anyhow::{Context, Result};
+use crate::types::ModuleRole;
+use anyhow::Context;
+use parking_lot::Mutex;
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::UNIX_EPOCH;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tree_sitter::Parser;
 
 /// Incremental indexer that updates the state when files change.
 pub struct IncrementalIndexer {
     topology_builder: TopologyBuilder,
+    /// Trees of hot files for incremental re-parsing, if enabled
+    trees: Option<Mutex<TreeCache>>,
+    /// Attach to the segment of the workspace's writer (see [`crate::shared`])
+    shared: bool,
+    /// Language overrides for every parse; [`IndexOptions::language_overrides`]
+    /// are added on top for one run
+    overrides: parsing::LanguageOverrides,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +44,14 @@ pub struct IndexOptions {
     pub include_hidden: bool,
     pub include_large: bool,
     pub max_file_size: u64,
+    /// `GLOB=LANGUAGE` rules forcing a parser for matching files.
+    pub language_overrides: Vec<String>,
+    /// Files or directories the current task is about; parsed and made
+    /// searchable first, then boosted in ranking (see [`crate::focus`]).
+    pub focus: Vec<String>,
+    /// Days to keep records of deleted symbols; `None` keeps the window
+    /// last set (see [`crate::tombstones`]).
+    pub tombstone_retention_days: Option<u64>,
 }
 
 impl Default for IndexOptions {
@@ -39,6 +64,9 @@ impl Default for IndexOptions {
             include_hidden: false,
             include_large: false,
             max_file_size: 2 * 1024 * 1024,
+            language_overrides: Vec::new(),
+            focus: Vec::new(),
+            tombstone_retention_days: None,
         }
     }
 }
@@ -48,8 +76,120 @@ pub struct IndexReport {
     pub total_files: usize,
     pub parsed_files: usize,
     pub skipped_files: usize,
+    /// Skipped files whose mtime or size changed but whose content hash did
+    /// not (e.g. touched by an editor); included in `skipped_files`
+    pub unchanged_files: usize,
     pub removed_files: usize,
+    /// Changed files whose docs were taken from the checked-out branch's
+    /// snapshot instead of re-parsed (see [`crate::branch_cache`])
+    pub reused_files: usize,
     pub docs_indexed: usize,
+    /// Wall time per phase, in execution order
+    pub phases: Vec<(IndexPhase, Duration)>,
+}
+
+/// A stage of [`IncrementalIndexer::index`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexPhase {
+    /// Walk the tree for indexable files
+    Discover,
+    /// Compare files against the cache manifest
+    Fingerprint,
+    /// Parse changed files into symbols, calls, and search docs
+    Parse,
+    /// Link dispatch, rebuild BM25, and persist the index
+    Finalize,
+    /// Build or update the semantic index; not part of the index itself,
+    /// run afterwards by `omni index` with the `semantic` feature
+    Embed,
+}
+
+impl IndexPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Discover => "discover",
+            Self::Fingerprint => "fingerprint",
+            Self::Parse => "parse",
+            Self::Finalize => "finalize",
+            Self::Embed => "embed",
+        }
+    }
+}
+
+/// Progress reported by [`IncrementalIndexer::index_with_progress`].
+///
+/// `Progress` is emitted per file during the fingerprint and parse phases,
+/// and per batch of symbols, with an empty `file`, during the embed phase.
+///
+/// Serializes as `{"event": "phase_started", ...}` for line-delimited
+/// progress streams.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum IndexEvent {
+    /// A phase began with `total` work items (files; 0 if unknown)
+    PhaseStarted { phase: IndexPhase, total: usize },
+    /// A file of the current phase finished
+    Progress {
+        phase: IndexPhase,
+        done: usize,
+        total: usize,
+        file: String,
+    },
+    /// A phase ended
+    PhaseFinished { phase: IndexPhase, elapsed_ms: u64 },
+    /// The `files` changed focus files were parsed and published to the
+    /// search index ahead of the rest
+    FocusReady { files: usize },
+}
+
+/// Emits phase events and records phase timings.
+struct PhaseTracker<F: FnMut(&IndexEvent)> {
+    on_event: F,
+    current: Option<(IndexPhase, Instant)>,
+    timings: Vec<(IndexPhase, Duration)>,
+}
+
+impl<F: FnMut(&IndexEvent)> PhaseTracker<F> {
+    fn new(on_event: F) -> Self {
+        Self {
+            on_event,
+            current: None,
+            timings: Vec::new(),
+        }
+    }
+
+    fn start(&mut self, phase: IndexPhase, total: usize) {
+        self.finish();
+        (self.on_event)(&IndexEvent::PhaseStarted { phase, total });
+        self.current = Some((phase, Instant::now()));
+    }
+
+    fn progress(&mut self, done: usize, total: usize, file: &str) {
+        if let Some((phase, _)) = self.current {
+            (self.on_event)(&IndexEvent::Progress {
+                phase,
+                done,
+                total,
+                file: file.to_string(),
+            });
+        }
+    }
+
+    fn emit(&mut self, event: IndexEvent) {
+        (self.on_event)(&event);
+    }
+
+    fn finish(&mut self) {
+        if let Some((phase, started)) = self.current.take() {
+            let elapsed = started.elapsed();
+            self.timings.push((phase, elapsed));
+            (self.on_event)(&IndexEvent::PhaseFinished {
+                phase,
+                elapsed_ms: elapsed.as_millis() as u64,
+            });
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -57,40 +197,137 @@ struct ParsedFile {
     symbols: Vec<crate::types::SymbolDef>,
     calls: Vec<crate::types::CallEdge>,
     imports: Vec<crate::types::ImportInfo>,
+    trait_impls: Vec<crate::types::TraitImpl>,
+    type_refs: Vec<crate::types::TypeRef>,
     docs: Vec<SearchDoc>,
+    content_hash: Option<u64>,
+    /// See [`crate::parsing::LanguageParser::first_line`]
+    first_line: usize,
 }
 
 impl IncrementalIndexer {
     pub fn new() -> Self {
         Self {
             topology_builder: TopologyBuilder::new(),
+            trees: None,
+            shared: false,
+            overrides: parsing::LanguageOverrides::default(),
         }
     }
 
+    /// Keep the trees of recently parsed files and re-parse them
+    /// incrementally from edit deltas when they change (see
+    /// [`parsing::edits`]). Worth enabling in long-running processes that
+    /// re-index the same files repeatedly.
+    pub fn with_incremental_parsing(mut self, enabled: bool) -> Self {
+        self.trees = enabled.then(|| Mutex::new(TreeCache::default()));
+        self
+    }
+
+    /// Restore [`full_index_cached`](Self::full_index_cached) from the
+    /// index published by the process holding the workspace's writer lock,
+    /// when there is one, instead of checking fingerprints and parsing (see
+    /// [`crate::shared`]).
+    pub fn with_shared(mut self, enabled: bool) -> Self {
+        self.shared = enabled;
+        self
+    }
+
+    /// Force parsers for matching files in every index, update and overlay,
+    /// e.g. the `[index] language_overrides` of `omni.toml`.
+    pub fn with_language_overrides(mut self, overrides: parsing::LanguageOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
     /// Perform a full index of the repository.
     pub async fn full_index(&self, state: &OciState, root: &Path) -> Result<()> {
+        let files = source_files(root, &self.overrides)?;
+        self.full_index_files(state, root, &files).await
+    }
+
+    /// [`full_index`](Self::full_index), reusing the snapshot in
+    /// `.omni/oci.bin` when no source file changed since it was written.
+    ///
+    /// Returns whether the snapshot was used. Otherwise the fresh index is
+    /// saved as the new snapshot; failing to write it is only logged.
+    pub async fn full_index_cached(&self, state: &OciState, root: &Path) -> Result<bool> {
+        let fresh = state.overlays.is_empty() && state.interner.is_empty();
+        if self.shared && fresh {
+            match crate::shared::attach(state, root) {
+                Ok(true) => {
+                    tracing::info!("Attached to shared index of {}", root.display());
+                    return Ok(true);
+                }
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Ignoring shared index: {}", e),
+            }
+        }
+        let files = source_files(root, &self.overrides)?;
+        let fingerprints = fingerprints(root, &files)?;
+        let path = crate::cache::oci_path(root);
+        if fresh {
+            match state.load_snapshot(&path, &fingerprints) {
+                Ok(true) => {
+                    tracing::info!("Loaded index snapshot for {}", root.display());
+                    return Ok(true);
+                }
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Ignoring index snapshot: {}", e),
+            }
+        }
+
+        self.full_index_files(state, root, &files).await?;
+        // Overlays stand in for files on disk, which the fingerprints
+        // describe; a live writer owns the snapshot
+        if state.overlays.is_empty()
+            && crate::shared::check_writable(root).is_ok()
+            && let Err(e) = state.save_snapshot(&path, &fingerprints)
+        {
+            tracing::warn!("Failed to save index snapshot: {}", e);
+        }
+        Ok(false)
+    }
+
+    /// Restore the snapshot in `.omni/oci.bin` into a fresh `state` if it
+    /// matches the source files on disk. Nothing is parsed either way.
+    pub fn load_snapshot(&self, state: &OciState, root: &Path) -> Result<bool> {
+        let fingerprints = fingerprints(root, &source_files(root, &self.overrides)?)?;
+        state.load_snapshot(&crate::cache::oci_path(root), &fingerprints)
+    }
+
+    /// Save `state` as the snapshot in `.omni/oci.bin`. The state should
+    /// hold every source file as it is on disk, e.g. after a forced index.
+    pub fn save_snapshot(&self, state: &OciState, root: &Path) -> Result<()> {
+        let fingerprints = fingerprints(root, &source_files(root, &self.overrides)?)?;
+        state.save_snapshot(&crate::cache::oci_path(root), &fingerprints)
+    }
+
+    async fn full_index_files(
+        &self,
+        state: &OciState,
+        root: &Path,
+        files: &[PathBuf],
+    ) -> Result<()> {
         tracing::info!("Starting full index of {}", root.display());
         state.reset();
 
-        // Discover files
-        let discovery = crate::discovery::FileDiscovery::new();
-        let files = discovery.discover(root)?;
-        let files: Vec<PathBuf> = files
-            .into_iter()
-            .filter(|path| parsing::parser_for_file(path).is_some())
-            .collect();
-
         tracing::info!("Discovered {} files", files.len());
 
         // Index each file
-        for file in &files {
+        for file in files {
             if let Err(e) = self.index_file(state, file, root).await {
                 tracing::warn!("Failed to index {}: {}", file.display(), e);
             }
         }
+        self.restore_overlays(state, root, &self.overrides).await;
+
+        state.link_dyn_dispatch();
+        crate::call_resolution::resolve_call_edges(state);
 
         // Build topology
         self.topology_builder.build(state, root)?;
+        *state.stack.write() = Some(crate::stack::detect_stack(state, root, files));
 
         // Update metadata
         *state.last_indexed.write() = Some(std::time::Instant::now());
@@ -111,47 +348,94 @@ impl IncrementalIndexer {
         state: &OciState,
         root: &Path,
         options: &IndexOptions,
+    ) -> Result<IndexReport> {
+        self.index_with_progress(state, root, options, |_| {}).await
+    }
+
+    /// [`index`](Self::index), reporting phases and per-file progress to
+    /// `on_event`.
+    pub async fn index_with_progress(
+        &self,
+        state: &OciState,
+        root: &Path,
+        options: &IndexOptions,
+        on_event: impl FnMut(&IndexEvent),
     ) -> Result<IndexReport> {
         tracing::info!("Starting incremental index of {}", root.display());
+        crate::shared::check_writable(root)?;
+        let mut phases = PhaseTracker::new(on_event);
+        phases.start(IndexPhase::Discover, 0);
 
         if options.force {
             crate::cache::clear_cache(root)?;
             state.reset();
         }
+        let focus = Focus::new(root, &options.focus);
+        if focus.is_active() {
+            focus.save(root)?;
+        }
 
+        let mut overrides = self.overrides.clone();
+        overrides.add_rules(&options.language_overrides)?;
         let discovery = build_discovery(options);
         let files = discovery.discover(root)?;
         let files: Vec<PathBuf> = files
             .into_iter()
-            .filter(|path| parsing::parser_for_file(path).is_some())
+            .filter(|path| {
+                overrides.parser_for_file(path, root).is_some()
+                    || ConfigFormat::from_path(path).is_some()
+                    || is_sql_file(path)
+            })
             .collect();
 
         let mut report = IndexReport {
             total_files: files.len(),
             ..Default::default()
         };
+        phases.start(IndexPhase::Fingerprint, files.len());
 
         let (mut manifest, reset_state) = load_or_init_manifest(root, options.force)?;
         if reset_state && !options.force {
             state.reset();
         }
         let mut docs = load_or_init_docs(root, &manifest, options.force)?;
+        let sibling = switch_branch(root, &mut manifest);
 
         let mut seen = HashSet::new();
         let mut changed_files = HashSet::new();
         let mut removed_files = HashSet::new();
+        let mut reused_docs: HashMap<String, Vec<SearchDoc>> = HashMap::new();
 
-        for file in &files {
+        for (done, file) in files.iter().enumerate() {
             let rel = relative_path(root, file)?;
+            phases.progress(done + 1, files.len(), &rel);
             seen.insert(rel.clone());
 
-            let fingerprint = fingerprint(file)?;
+            let mut fingerprint = fingerprint(file)?;
             match manifest.files.get(&rel) {
-                Some(prev) if *prev == fingerprint => {
+                Some(prev) if prev.same_metadata(&fingerprint) => {
                     report.skipped_files += 1;
                 }
-                _ => {
-                    changed_files.insert(rel.clone());
+                prev => {
+                    fingerprint.content_hash = file_hash(file)?;
+                    let unchanged = prev.is_some_and(|p| {
+                        p.content_hash != 0 && p.content_hash == fingerprint.content_hash
+                    });
+                    // Files this process already holds in memory are re-parsed
+                    // to keep the symbol graph current
+                    let reusable = sibling
+                        .as_ref()
+                        .filter(|_| !state.file_hashes.contains_key(file))
+                        .and_then(|snapshot| snapshot.docs_for(&rel, fingerprint.content_hash));
+                    if unchanged {
+                        report.skipped_files += 1;
+                        report.unchanged_files += 1;
+                    } else if let Some(file_docs) = reusable {
+                        report.reused_files += 1;
+                        reused_docs.insert(rel.clone(), file_docs.to_vec());
+                    } else {
+                        changed_files.insert(rel.clone());
+                    }
                     manifest.files.insert(rel, fingerprint);
                 }
             }
@@ -169,32 +453,95 @@ impl IncrementalIndexer {
         }
 
         report.removed_files = removed_files.len();
+        state
+            .files_skipped
+            .fetch_add(report.skipped_files, Ordering::SeqCst);
 
         let mut drop_docs_for = HashSet::new();
         drop_docs_for.extend(changed_files.iter().cloned());
         drop_docs_for.extend(removed_files.iter().cloned());
+        drop_docs_for.extend(reused_docs.keys().cloned());
+
+        // Docs of changed files before re-parsing, to spot renamed symbols
+        let old_changed_docs: Vec<SearchDoc> = docs
+            .iter()
+            .filter(|doc| changed_files.contains(&doc.file))
+            .cloned()
+            .collect();
+        let old_removed_docs: Vec<SearchDoc> = docs
+            .iter()
+            .filter(|doc| removed_files.contains(&doc.file))
+            .cloned()
+            .collect();
+        let mut new_changed_docs = Vec::new();
 
         if !drop_docs_for.is_empty() {
             docs = crate::query::prune_docs_for_files(&docs, &drop_docs_for);
         }
+        report.docs_indexed += reused_docs.values().map(Vec::len).sum::<usize>();
+        docs.extend(reused_docs.into_values().flatten());
 
         for rel in &removed_files {
             let path = root.join(rel);
             self.remove_file(state, &path);
         }
 
-        for rel in &changed_files {
+        // Focus files first, then the rest in path order
+        let mut parse_order: Vec<&String> = changed_files.iter().collect();
+        parse_order.sort_by_key(|rel| (!focus.contains(rel), *rel));
+        let focus_files = parse_order
+            .iter()
+            .take_while(|rel| focus.contains(rel))
+            .count();
+
+        phases.start(IndexPhase::Parse, parse_order.len());
+        for (done, rel) in parse_order.iter().enumerate() {
             let path = root.join(rel);
-            match self.update_file(state, &path, root).await {
+            state.clear_file(&path);
+            match self.index_file_with(state, &path, root, &overrides).await {
                 Ok(file_docs) => {
                     report.parsed_files += 1;
                     report.docs_indexed += file_docs.len();
+                    new_changed_docs.extend(file_docs.iter().cloned());
                     docs.extend(file_docs);
                 }
                 Err(e) => {
                     tracing::warn!("Failed to index {}: {}", path.display(), e);
                 }
             }
+            phases.progress(done + 1, parse_order.len(), rel);
+
+            // Make the focus files searchable while the rest are parsed
+            if done + 1 == focus_files && focus_files < parse_order.len() {
+                let bm25 = rebuild_bm25(&docs);
+                save_search_state(root, &SearchState { docs: docs.clone() })?;
+                save_bm25(root, &bm25)?;
+                *state.bm25_index.write() = Some(bm25);
+                phases.emit(IndexEvent::FocusReady { files: focus_files });
+            }
+        }
+
+        self.restore_overlays(state, root, &overrides).await;
+
+        phases.start(IndexPhase::Finalize, 0);
+        state.link_dyn_dispatch();
+        crate::call_resolution::resolve_call_edges(state);
+        let parsed: Vec<PathBuf> = parse_order.iter().map(|rel| root.join(rel)).collect();
+        sync_semantic(state, &parsed);
+        *state.stack.write() = Some(crate::stack::detect_stack(state, root, &files));
+        let renames = crate::renames::detect_renames(&old_changed_docs, &new_changed_docs);
+        if let Err(e) = crate::renames::record_renames(root, &renames) {
+            tracing::warn!("Failed to record renames: {}", e);
+        }
+        let old_docs = [old_changed_docs, old_removed_docs].concat();
+        let deleted = crate::tombstones::detect_deletions(&old_docs, &docs, &renames);
+        if let Err(e) = crate::tombstones::record_deletions(
+            root,
+            &deleted,
+            &docs,
+            options.tombstone_retention_days,
+        ) {
+            tracing::warn!("Failed to record deleted symbols: {}", e);
         }
 
         let bm25 = rebuild_bm25(&docs);
@@ -208,72 +555,227 @@ impl IncrementalIndexer {
         crate::cache::save_manifest(root, &manifest)?;
         *state.last_indexed.write() = Some(std::time::Instant::now());
 
+        phases.finish();
+        report.phases = phases.timings;
         Ok(report)
     }
 
-    async fn parse_file(&self, state: &OciState, path: &Path, root: &Path) -> Result<ParsedFile> {
-        let lang_parser = match parsing::parser_for_file(path) {
+    async fn parse_file(
+        &self,
+        state: &OciState,
+        path: &Path,
+        root: &Path,
+        overrides: &parsing::LanguageOverrides,
+        contents: Option<String>,
+    ) -> Result<ParsedFile> {
+        let lang_parser = match overrides.parser_for_file(path, root) {
             Some(p) => p,
+            None if ConfigFormat::from_path(path).is_some() => {
+                return self.parse_config_file(path, root, contents).await;
+            }
+            None if is_sql_file(path) => {
+                return self.parse_sql_file(state, path, root, contents).await;
+            }
             None => {
                 return Ok(ParsedFile {
                     symbols: Vec::new(),
                     calls: Vec::new(),
                     imports: Vec::new(),
+                    trait_impls: Vec::new(),
+                    type_refs: Vec::new(),
                     docs: Vec::new(),
+                    content_hash: None,
+                    first_line: 0,
                 });
             }
         };
 
-        let contents = tokio::fs::read_to_string(path)
-            .await
-            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let contents = match contents {
+            Some(contents) => contents,
+            None => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| OmniError::io(path, e))?,
+        };
 
+        let language = lang_parser.language();
         let mut parser = Parser::new();
         parser
-            .set_language(&lang_parser.language())
-            .context("Failed to set parser language")?;
-
-        let tree = parser
-            .parse(&contents, None)
-            .context("Failed to parse file")?;
-
-        let symbols = lang_parser.extract_symbols(&tree, &contents, path, &state.interner)?;
-        let calls = lang_parser.extract_calls(&tree, &contents, path, &state.interner)?;
-        let imports = lang_parser.extract_imports(&tree, &contents, path)?;
+            .set_language(&language)
+            .map_err(|e| OmniError::parse(path, e))?;
+
+        let parse_err = |e: anyhow::Error| OmniError::parse(path, format!("{e:#}"));
+        let hot = self
+            .trees
+            .as_ref()
+            .and_then(|trees| trees.lock().take(path))
+            .filter(|hot| hot.language == language);
+        let (tree, extracted) = match hot {
+            Some(hot) => {
+                let reparsed = parsing::edits::reparse(
+                    &mut parser,
+                    lang_parser.as_ref(),
+                    hot,
+                    &contents,
+                    path,
+                    &state.interner,
+                )
+                .map_err(parse_err)?;
+                (reparsed.tree, reparsed.extracted)
+            }
+            None => {
+                let tree = parser
+                    .parse(&contents, None)
+                    .ok_or_else(|| OmniError::parse(path, "tree-sitter produced no tree"))?;
+                (tree, None)
+            }
+        };
 
-        let docs = build_search_docs(path, root, &contents, &symbols, state)?;
+        let (mut symbols, mut calls) = match extracted {
+            Some(extracted) => extracted,
+            None => (
+                lang_parser
+                    .extract_symbols(&tree, &contents, path, &state.interner)
+                    .map_err(parse_err)?,
+                lang_parser
+                    .extract_calls(&tree, &contents, path, &state.interner)
+                    .map_err(parse_err)?,
+            ),
+        };
+        let imports = lang_parser
+            .extract_imports(&tree, &contents, path)
+            .map_err(parse_err)?;
+        let trait_impls = lang_parser
+            .extract_trait_impls(&tree, &contents, path, &state.interner)
+            .map_err(parse_err)?;
+        let type_refs = lang_parser
+            .extract_type_refs(&tree, &contents, path)
+            .map_err(parse_err)?;
+
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        let role = crate::topology::classify_role(rel, &contents, &symbols, &imports);
+        parsing::mark_test_context(&mut symbols, &mut calls, crate::topology::is_test_path(rel));
+        let docs = build_search_docs(path, root, &contents, &symbols, role, state)?;
+        let content_hash = crate::cache::content_hash(contents.as_bytes());
+
+        if let Some(trees) = &self.trees {
+            trees.lock().insert(
+                path.to_path_buf(),
+                HotTree {
+                    language,
+                    source: contents,
+                    tree,
+                    symbols: symbols.clone(),
+                    calls: calls.clone(),
+                },
+            );
+        }
 
         Ok(ParsedFile {
             symbols,
             calls,
             imports,
+            trait_impls,
+            type_refs,
             docs,
+            content_hash: Some(content_hash),
+            first_line: lang_parser.first_line(),
         })
     }
 
-    fn apply_parsed(&self, state: &OciState, path: &Path, parsed: &ParsedFile) {
-        if parsed.symbols.is_empty() && parsed.calls.is_empty() && parsed.imports.is_empty() {
-            return;
-        }
-
-        let file_id = state.get_or_create_file_id(&path.to_path_buf());
-
-        let mut file_symbol_names = Vec::with_capacity(parsed.symbols.len());
-        for symbol in parsed.symbols.iter().cloned() {
-            file_symbol_names.push(symbol.scoped_name);
-            state.add_symbol(symbol);
-        }
-        if !file_symbol_names.is_empty() {
-            state.file_symbols.insert(file_id, file_symbol_names);
-        }
+    /// Config files only contribute search docs, one per key.
+    async fn parse_config_file(
+        &self,
+        path: &Path,
+        root: &Path,
+        contents: Option<String>,
+    ) -> Result<ParsedFile> {
+        let contents = match contents {
+            Some(contents) => contents,
+            None => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| OmniError::io(path, e))?,
+        };
+        // Config, unless it is a test fixture or generated
+        let role = match crate::topology::classify_role(
+            path.strip_prefix(root).unwrap_or(path),
+            &contents,
+            &[],
+            &[],
+        ) {
+            role @ (ModuleRole::Tests | ModuleRole::Generated) => role,
+            _ => ModuleRole::Config,
+        };
+        let docs = match ConfigFormat::from_path(path) {
+            Some(format) => build_config_docs(path, root, &contents, format, role)?,
+            None => Vec::new(),
+        };
+        Ok(ParsedFile {
+            symbols: Vec::new(),
+            calls: Vec::new(),
+            imports: Vec::new(),
+            trait_impls: Vec::new(),
+            type_refs: Vec::new(),
+            docs,
+            content_hash: Some(crate::cache::content_hash(contents.as_bytes())),
+            first_line: 0,
+        })
+    }
 
-        for call in parsed.calls.iter().cloned() {
-            state.add_call_edge(call);
+    /// SQL files contribute their tables and columns, as symbols and
+    /// `type:schema` search docs.
+    async fn parse_sql_file(
+        &self,
+        state: &OciState,
+        path: &Path,
+        root: &Path,
+        contents: Option<String>,
+    ) -> Result<ParsedFile> {
+        let contents = match contents {
+            Some(contents) => contents,
+            None => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| OmniError::io(path, e))?,
+        };
+        let changes = extract_schema_changes(&contents);
+        let symbols = sql_symbols(&changes, &contents, path, &state.interner);
+        let role = crate::topology::classify_role(
+            path.strip_prefix(root).unwrap_or(path),
+            &contents,
+            &symbols,
+            &[],
+        );
+        let mut docs = build_search_docs(path, root, &contents, &symbols, role, state)?;
+        for doc in &mut docs {
+            doc.kind = DocKind::Schema;
         }
+        Ok(ParsedFile {
+            symbols,
+            calls: Vec::new(),
+            imports: Vec::new(),
+            trait_impls: Vec::new(),
+            type_refs: Vec::new(),
+            docs,
+            content_hash: Some(crate::cache::content_hash(contents.as_bytes())),
+            first_line: 0,
+        })
+    }
 
-        if !parsed.imports.is_empty() {
-            state.imports.insert(file_id, parsed.imports.clone());
+    fn apply_parsed(&self, state: &OciState, path: &Path, parsed: &ParsedFile) {
+        if let Some(hash) = parsed.content_hash {
+            state.file_hashes.insert(path.to_path_buf(), hash);
+            state.files_parsed.fetch_add(1, Ordering::SeqCst);
         }
+        state.commit_file(
+            path,
+            FileBatch {
+                symbols: parsed.symbols.clone(),
+                calls: parsed.calls.clone(),
+                imports: parsed.imports.clone(),
+                trait_impls: parsed.trait_impls.clone(),
+                type_refs: parsed.type_refs.clone(),
+                first_line: parsed.first_line,
+            },
+        );
     }
 
     /// Index a single file.
@@ -283,32 +785,158 @@ impl IncrementalIndexer {
         path: &Path,
         root: &Path,
     ) -> Result<Vec<SearchDoc>> {
-        let parsed = self.parse_file(state, path, root).await?;
+        self.index_file_with(state, path, root, &self.overrides)
+            .await
+    }
+
+    async fn index_file_with(
+        &self,
+        state: &OciState,
+        path: &Path,
+        root: &Path,
+        overrides: &parsing::LanguageOverrides,
+    ) -> Result<Vec<SearchDoc>> {
+        let parsed = self.parse_file(state, path, root, overrides, None).await?;
         self.apply_parsed(state, path, &parsed);
         Ok(parsed.docs)
     }
 
+    /// Index `contents` as the current text of `path` without writing it,
+    /// e.g. an editor buffer with unsaved changes. The overlay shadows the
+    /// file on disk for symbols, calls, context and (via
+    /// [`crate::query::apply_overlays`]) search until
+    /// [`clear_overlay`](Self::clear_overlay) is called, surviving re-indexes.
+    /// Persisted index files always reflect disk. The file need not exist.
+    pub async fn overlay_file(
+        &self,
+        state: &OciState,
+        path: &Path,
+        root: &Path,
+        contents: String,
+    ) -> Result<Vec<SearchDoc>> {
+        self.overlay_file_with(state, path, root, &self.overrides, contents)
+            .await
+    }
+
+    async fn overlay_file_with(
+        &self,
+        state: &OciState,
+        path: &Path,
+        root: &Path,
+        overrides: &parsing::LanguageOverrides,
+        contents: String,
+    ) -> Result<Vec<SearchDoc>> {
+        let parsed = self
+            .parse_file(state, path, root, overrides, Some(contents.clone()))
+            .await?;
+        state.clear_file(&path.to_path_buf());
+        self.apply_parsed(state, path, &parsed);
+        state.overlays.insert(
+            path.to_path_buf(),
+            Overlay {
+                contents: contents.into(),
+                docs: parsed.docs.clone(),
+            },
+        );
+        sync_semantic(state, &[path.to_path_buf()]);
+        Ok(parsed.docs)
+    }
+
+    /// Drop the overlay for `path` and re-index it from disk (or forget it,
+    /// if it only ever existed in memory). Returns whether it was overlaid.
+    pub async fn clear_overlay(&self, state: &OciState, path: &Path, root: &Path) -> Result<bool> {
+        if state.overlays.remove(path).is_none() {
+            return Ok(false);
+        }
+        if path.exists() {
+            state.clear_file(&path.to_path_buf());
+            self.index_file(state, path, root).await?;
+            sync_semantic(state, &[path.to_path_buf()]);
+        } else {
+            self.remove_file(state, path);
+        }
+        Ok(true)
+    }
+
+    /// Re-parse every overlay over whatever the last index run loaded from
+    /// disk.
+    async fn restore_overlays(
+        &self,
+        state: &OciState,
+        root: &Path,
+        overrides: &parsing::LanguageOverrides,
+    ) {
+        let overlays: Vec<(PathBuf, String)> = state
+            .overlays
+            .iter()
+            .map(|o| (o.key().clone(), o.contents.to_string()))
+            .collect();
+        for (path, contents) in overlays {
+            if let Err(e) = self
+                .overlay_file_with(state, &path, root, overrides, contents)
+                .await
+            {
+                tracing::warn!("Failed to restore overlay {}: {}", path.display(), e);
+            }
+        }
+    }
+
     /// Update a single file (clear old data, re-index).
+    ///
+    /// Returns `None` without re-parsing if the content hash matches the
+    /// last parse, e.g. when an editor saved the file without changes, or
+    /// if the file is overlaid (see [`overlay_file`](Self::overlay_file)).
     pub async fn update_file(
         &self,
         state: &OciState,
         path: &Path,
         root: &Path,
-    ) -> Result<Vec<SearchDoc>> {
+    ) -> Result<Option<Vec<SearchDoc>>> {
+        // The overlay wins until cleared; disk changes are picked up then
+        if state.overlays.contains_key(path) {
+            return Ok(None);
+        }
+        let hash = file_hash(path)?;
+        if state.file_hashes.get(path).is_some_and(|h| *h == hash) {
+            state.files_skipped.fetch_add(1, Ordering::SeqCst);
+            return Ok(None);
+        }
+
         // Clear existing data for this file
         state.clear_file(&path.to_path_buf());
 
         // Re-index
-        self.index_file(state, path, root).await
+        let docs = self.index_file(state, path, root).await?;
+        sync_semantic(state, &[path.to_path_buf()]);
+        Ok(Some(docs))
     }
 
     /// Remove a file from the index.
     pub fn remove_file(&self, state: &OciState, path: &Path) {
+        if let Some(trees) = &self.trees {
+            trees.lock().remove(path);
+        }
         state.clear_file(&path.to_path_buf());
         self.topology_builder.remove_file(state, path).ok();
+        sync_semantic(state, &[path.to_path_buf()]);
+    }
+}
+
+/// Re-embed what changed in `files` if a semantic index is loaded.
+#[cfg(feature = "semantic")]
+fn sync_semantic(state: &OciState, files: &[PathBuf]) {
+    let Some(index) = state.semantic_index.get() else {
+        return;
+    };
+    match index.sync_files(state, files) {
+        Ok(embedded) => tracing::debug!("Re-embedded {} symbols", embedded),
+        Err(e) => tracing::warn!("Failed to update the semantic index: {}", e),
     }
 }
 
+#[cfg(not(feature = "semantic"))]
+fn sync_semantic(_state: &OciState, _files: &[PathBuf]) {}
+
 impl Default for IncrementalIndexer {
     fn default() -> Self {
         Self::new()
@@ -343,17 +971,39 @@ fn relative_path(root: &Path, path: &Path) -> Result<String> {
     Ok(rel.to_string_lossy().to_string())
 }
 
+/// Files [`IncrementalIndexer::full_index`] parses: source code and SQL.
+fn source_files(root: &Path, overrides: &parsing::LanguageOverrides) -> Result<Vec<PathBuf>> {
+    let files = crate::discovery::FileDiscovery::new().discover(root)?;
+    Ok(files
+        .into_iter()
+        .filter(|path| overrides.parser_for_file(path, root).is_some() || is_sql_file(path))
+        .collect())
+}
+
+/// Metadata fingerprints of `files`, keyed by path relative to `root`.
+fn fingerprints(root: &Path, files: &[PathBuf]) -> Result<HashMap<String, FileFingerprint>> {
+    files
+        .iter()
+        .map(|file| Ok((relative_path(root, file)?, fingerprint(file)?)))
+        .collect()
+}
+
 fn fingerprint(path: &Path) -> Result<FileFingerprint> {
-    let metadata =
-        std::fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+    let metadata = std::fs::metadata(path).map_err(|e| OmniError::io(path, e))?;
     let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
     let duration = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
     Ok(FileFingerprint {
         mtime_ms: duration.as_millis() as u64,
         size_bytes: metadata.len(),
+        content_hash: 0,
     })
 }
 
+fn file_hash(path: &Path) -> Result<u64> {
+    let bytes = std::fs::read(path).map_err(|e| OmniError::io(path, e))?;
+    Ok(crate::cache::content_hash(&bytes))
+}
+
 fn load_or_init_manifest(root: &Path, force: bool) -> Result<(IndexManifest, bool)> {
     let version = env!("CARGO_PKG_VERSION").to_string();
     let root_path = root
@@ -363,26 +1013,53 @@ fn load_or_init_manifest(root: &Path, force: bool) -> Result<(IndexManifest, boo
         .to_string();
 
     if !force {
-        if let Some(manifest) = load_manifest(root)? {
-            if manifest.tool_version == version
-                && manifest.root.as_deref() == Some(root_path.as_str())
-            {
+        match crate::migrations::migrate(root) {
+            Ok(Some(mut manifest)) if manifest.root.as_deref() == Some(root_path.as_str()) => {
+                manifest.tool_version = version;
                 return Ok((manifest, false));
             }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Rebuilding index: {:#}", e),
         }
     }
 
     crate::cache::clear_cache(root)?;
     Ok((
         IndexManifest {
+            schema_version: crate::migrations::SCHEMA_VERSION,
             tool_version: version,
             root: Some(root_path),
+            branch: None,
             files: HashMap::new(),
         },
         true,
     ))
 }
 
+/// Record the current git branch in `manifest`. On a switch, snapshot the
+/// outgoing branch's cache and return the incoming branch's snapshot, if any
+/// (see [`crate::branch_cache`]).
+fn switch_branch(root: &Path, manifest: &mut IndexManifest) -> Option<BranchSnapshot> {
+    let branch = crate::branch_cache::current_branch(root);
+    if manifest.branch == branch {
+        return None;
+    }
+    if let Some(previous) = &manifest.branch
+        && let Err(e) = crate::branch_cache::save_snapshot(root, previous)
+    {
+        tracing::warn!("Failed to snapshot index of branch {}: {}", previous, e);
+    }
+    manifest.branch = branch;
+    let branch = manifest.branch.as_deref()?;
+    match crate::branch_cache::load_snapshot(root, branch) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            tracing::warn!("Ignoring index snapshot of branch {}: {}", branch, e);
+            None
+        }
+    }
+}
+
 fn load_or_init_docs(
     root: &Path,
     _manifest: &IndexManifest,
@@ -401,6 +1078,7 @@ fn build_search_docs(
     root: &Path,
     contents: &str,
     symbols: &[crate::types::SymbolDef],
+    role: crate::types::ModuleRole,
     state: &OciState,
 ) -> Result<Vec<SearchDoc>> {
     let rel_path = relative_path(root, path)?;
@@ -433,12 +1111,53 @@ fn build_search_docs(
             end_col: symbol.location.end_col,
             preview,
             indexed_text,
+            role: role.as_str().to_string(),
+            deprecated: symbol.deprecation().map(str::to_string),
+            kind: DocKind::Symbol,
+            is_test_context: symbol.is_test_context,
         });
     }
 
     Ok(docs)
 }
 
+fn build_config_docs(
+    path: &Path,
+    root: &Path,
+    contents: &str,
+    format: ConfigFormat,
+    role: crate::types::ModuleRole,
+) -> Result<Vec<SearchDoc>> {
+    let rel_path = relative_path(root, path)?;
+    let docs = extract_config_keys(format, contents)
+        .into_iter()
+        .map(|key| {
+            let line = slice_utf8(contents, key.start_byte, key.end_byte);
+            let indexed_text = match &key.value {
+                Some(value) => format!("{} {} {}", key.path, value, line),
+                None => format!("{} {}", key.path, line),
+            };
+            SearchDoc {
+                symbol: key.path,
+                file: rel_path.clone(),
+                start_byte: key.start_byte,
+                end_byte: key.end_byte,
+                start_line: key.line,
+                end_line: key.line,
+                start_col: 0,
+                end_col: line.chars().count(),
+                preview: make_preview(line),
+                indexed_text: truncate_to_len(&indexed_text, 4000),
+                role: role.as_str().to_string(),
+                deprecated: None,
+                kind: DocKind::Config,
+                is_test_context: crate::topology::is_test_path(Path::new(&rel_path)),
+            }
+        })
+        .collect();
+    Ok(docs)
+}
+
 fn slice_utf8(contents: &str, start: usize, end: usize) -> &str {
     let len = contents.len();
     let mut s = start.min(len);
@@ -3,10 +3,56 @@
 //! Provides real-time duplicate detection and suggestions before code is written.
 //! This module implements the "killer feature" of the OCI - active intervention to
 //! prevent code duplication before it happens.
+//!
+//! Existing symbols annotated `omni:ignore duplication` are never offered as
+//! duplicates or alternatives, and `omni:ignore naming` exempts them from
+//! naming conflict checks.
+//!
+//! Deprecated symbols are down-ranked, and `suggest_alternatives` offers the
+//! replacement named in the deprecation note ahead of them.
+//!
+//! `check_error_patterns` flags proposed error mapping that already repeats
+//! across the codebase, before it is pasted once more.
+//!
+//! `check_duplication` turns the matches of `detect_duplication` into
+//! interventions like the other checks.
+//!
+//! Signatures only match when names and types are alike. With the `semantic`
+//! feature, `detect_semantic_duplication` embeds the proposed signature and
+//! doc text and looks it up in the semantic index, catching
+//! `fetch_user_record` vs `load_user_by_id`; `check_duplication_semantic`
+//! reports both kinds of matches.
+//!
+//! `check_file` runs the duplication, reuse, naming and placement checks
+//! over every symbol of a whole proposed file at once.
 
+use crate::analysis::error_patterns::{self, ErrorPatternAnalyzer, ErrorPatternFix};
+use crate::error::OmniError;
+use crate::parsing::parser_for_file;
+use crate::query::DEPRECATED_SCORE_FACTOR;
 use crate::state::OciState;
 use crate::types::*;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Similarity at or above which a duplicate blocks rather than warns.
+const BLOCK_SIMILARITY: f32 = 0.95;
+
+/// Duplicates reported per proposed function.
+const MAX_DUPLICATES: usize = 3;
+
+/// Embedding similarity from which an existing function counts as a
+/// semantic duplicate of a proposed one.
+#[cfg(feature = "semantic")]
+pub const SEMANTIC_DUPLICATE_SIMILARITY: f32 = 0.8;
+
+/// Nearest neighbours of a proposal searched for semantic duplicates.
+#[cfg(feature = "semantic")]
+const SEMANTIC_CANDIDATES: usize = 20;
+
+/// Calls into the workspace a proposed function needs before its placement
+/// is judged.
+const MIN_PLACEMENT_CALLS: usize = 3;
 
 /// Parsed signature components for comparison.
 #[derive(Debug)]
@@ -67,7 +113,9 @@ impl InterventionEngine {
             let symbol = entry.value();
 
             // Only check functions and methods
-            if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) {
+            if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+                || symbol.is_ignored(IGNORE_DUPLICATION)
+            {
                 continue;
             }
 
@@ -78,7 +126,10 @@ impl InterventionEngine {
             };
 
             // Calculate similarity score
-            let score = Self::calculate_signature_similarity(&parsed, symbol, sig, state);
+            let mut score = Self::calculate_signature_similarity(&parsed, symbol, sig, state);
+            if symbol.deprecation().is_some() {
+                score *= DEPRECATED_SCORE_FACTOR;
+            }
 
             // Only include matches with meaningful similarity (> 0.3)
             if score > 0.3 {
@@ -101,6 +152,108 @@ impl InterventionEngine {
         matches
     }
 
+    /// Interventions for existing functions duplicating a proposed
+    /// signature: [`detect_duplication`](Self::detect_duplication) matches
+    /// at or above the engine's threshold, at most [`MAX_DUPLICATES`],
+    /// blocking from [`BLOCK_SIMILARITY`] up.
+    pub fn check_duplication(
+        &self,
+        state: &OciState,
+        proposed_signature: &str,
+    ) -> Vec<Intervention> {
+        let name = Self::parse_signature(proposed_signature)
+            .map(|parsed| parsed.name)
+            .unwrap_or_default();
+        Self::detect_duplication(state, proposed_signature)
+            .into_iter()
+            .filter(|m| m.score >= self.threshold)
+            .take(MAX_DUPLICATES)
+            .map(|m| Self::duplicate_intervention(state, m, &name))
+            .collect()
+    }
+
+    /// Detect existing functions that do what a proposed one would,
+    /// whatever they are called.
+    ///
+    /// The proposed signature and `doc` text are embedded like indexed
+    /// symbols (see [`crate::semantic::proposal_embedding_text`]) and looked
+    /// up in `index`. Functions and methods at or above
+    /// [`SEMANTIC_DUPLICATE_SIMILARITY`] are returned, most similar first,
+    /// with the cosine similarity as score (down-ranked when deprecated).
+    #[cfg(feature = "semantic")]
+    pub fn detect_semantic_duplication(
+        state: &OciState,
+        index: &crate::semantic::SemanticIndex,
+        proposed_signature: &str,
+        doc: Option<&str>,
+    ) -> crate::error::Result<Vec<SimilarityMatch>> {
+        let name = Self::parse_signature(proposed_signature)
+            .map(|parsed| parsed.name)
+            .unwrap_or_default();
+        let text = crate::semantic::proposal_embedding_text(&name, proposed_signature, doc);
+        let mut matches: Vec<SimilarityMatch> = index
+            .search(&text, SEMANTIC_CANDIDATES)?
+            .into_iter()
+            .filter_map(|(scoped_name, similarity)| {
+                let symbol = state.get_symbol(scoped_name)?;
+                if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+                    || symbol.is_ignored(IGNORE_DUPLICATION)
+                {
+                    return None;
+                }
+                let mut score = similarity;
+                if symbol.deprecation().is_some() {
+                    score *= DEPRECATED_SCORE_FACTOR;
+                }
+                (score >= SEMANTIC_DUPLICATE_SIMILARITY).then(|| SimilarityMatch {
+                    symbol: scoped_name,
+                    location: symbol.location.clone(),
+                    score,
+                    kind: symbol.kind,
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(matches)
+    }
+
+    /// [`check_duplication`](Self::check_duplication) joined with the
+    /// matches of [`detect_semantic_duplication`](Self::detect_semantic_duplication):
+    /// a function found both ways is reported once, with the higher score.
+    #[cfg(feature = "semantic")]
+    pub fn check_duplication_semantic(
+        &self,
+        state: &OciState,
+        index: &crate::semantic::SemanticIndex,
+        proposed_signature: &str,
+        doc: Option<&str>,
+    ) -> crate::error::Result<Vec<Intervention>> {
+        let name = Self::parse_signature(proposed_signature)
+            .map(|parsed| parsed.name)
+            .unwrap_or_default();
+        let mut best: HashMap<InternedString, SimilarityMatch> = HashMap::new();
+        let signature_matches = Self::detect_duplication(state, proposed_signature)
+            .into_iter()
+            .filter(|m| m.score >= self.threshold);
+        let semantic_matches =
+            Self::detect_semantic_duplication(state, index, proposed_signature, doc)?;
+        for m in signature_matches.chain(semantic_matches) {
+            match best.get(&m.symbol) {
+                Some(existing) if existing.score >= m.score => {}
+                _ => {
+                    best.insert(m.symbol, m);
+                }
+            }
+        }
+        let mut matches: Vec<SimilarityMatch> = best.into_values().collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(matches
+            .into_iter()
+            .take(MAX_DUPLICATES)
+            .map(|m| Self::duplicate_intervention(state, m, &name))
+            .collect())
+    }
+
     /// Suggest existing code alternatives for a given name.
     ///
     /// This searches for existing symbols that could potentially be reused instead
@@ -120,7 +273,10 @@ impl InterventionEngine {
 
         // Search for exact matches
         let exact_matches = state.find_by_name(name);
-        for symbol in exact_matches {
+        for symbol in exact_matches
+            .into_iter()
+            .filter(|s| !s.is_ignored(IGNORE_DUPLICATION))
+        {
             interventions.push(Intervention {
                 severity: InterventionSeverity::Warning,
                 message: format!(
@@ -147,7 +303,7 @@ impl InterventionEngine {
             let symbol_name_lower = symbol_name.to_lowercase();
 
             // Skip exact matches (already handled)
-            if symbol_name == name {
+            if symbol_name == name || symbol.is_ignored(IGNORE_DUPLICATION) {
                 continue;
             }
 
@@ -185,6 +341,8 @@ impl InterventionEngine {
             }
         }
 
+        Self::prefer_replacements(state, &mut interventions);
+
         // Sort by similarity score
         interventions.sort_by(|a, b| {
             b.similarity_score
@@ -195,6 +353,83 @@ impl InterventionEngine {
         interventions
     }
 
+    /// Annotate and down-rank deprecated suggestions, offering the documented
+    /// replacement (see [`SymbolDef::deprecation_replacement`]) at the
+    /// deprecated symbol's original score.
+    fn prefer_replacements(state: &OciState, interventions: &mut Vec<Intervention>) {
+        let mut replacements: Vec<Intervention> = Vec::new();
+        for intervention in interventions.iter_mut() {
+            let Some(symbol) = state.get_symbol(intervention.existing_symbol) else {
+                continue;
+            };
+            let Some(note) = symbol.deprecation() else {
+                continue;
+            };
+            let name = state.resolve(symbol.name);
+            if note.is_empty() {
+                intervention.message.push_str(" (deprecated)");
+            } else {
+                intervention
+                    .message
+                    .push_str(&format!(" (deprecated: {})", note));
+            }
+
+            let replacement = symbol
+                .deprecation_replacement()
+                .and_then(|path| Self::resolve_replacement(state, path, &symbol));
+            if let Some(replacement) = replacement {
+                let replacement_name = state.resolve(replacement.name);
+                intervention.recommendation = format!(
+                    "'{}' is deprecated; use '{}' instead",
+                    name, replacement_name
+                );
+                replacements.push(Intervention {
+                    severity: intervention.severity,
+                    message: format!(
+                        "'{}' replaces deprecated '{}' at {}:{}",
+                        replacement_name,
+                        name,
+                        replacement.location.file.display(),
+                        replacement.location.start_line
+                    ),
+                    existing_symbol: replacement.scoped_name,
+                    existing_location: replacement.location.clone(),
+                    similarity_score: intervention.similarity_score,
+                    recommendation: format!(
+                        "Consider reusing '{}' instead of the deprecated '{}'",
+                        replacement_name, name
+                    ),
+                });
+            }
+            intervention.similarity_score *= DEPRECATED_SCORE_FACTOR;
+        }
+
+        for replacement in replacements {
+            match interventions
+                .iter_mut()
+                .find(|i| i.existing_symbol == replacement.existing_symbol)
+            {
+                Some(existing) if existing.similarity_score >= replacement.similarity_score => {}
+                Some(existing) => *existing = replacement,
+                None => interventions.push(replacement),
+            }
+        }
+    }
+
+    /// Find the live symbol a deprecation note points at, preferring ones near
+    /// the deprecated symbol.
+    fn resolve_replacement(
+        state: &OciState,
+        path: &str,
+        deprecated: &SymbolDef,
+    ) -> Option<SymbolDef> {
+        let name = path.rsplit(['#', '.', ':']).next().unwrap_or(path);
+        state
+            .find_by_name_near(name, &deprecated.location.file)
+            .into_iter()
+            .find(|s| s.scoped_name != deprecated.scoped_name && s.deprecation().is_none())
+    }
+
     /// Check for naming conflicts in a specific file context.
     ///
     /// This checks if a proposed name would cause conflicts:
@@ -222,6 +457,9 @@ impl InterventionEngine {
         if let Some(file_symbols) = state.file_symbols.get(&file_id) {
             for scoped_name in file_symbols.iter() {
                 if let Some(symbol) = state.symbols.get(scoped_name) {
+                    if symbol.is_ignored(IGNORE_NAMING) {
+                        continue;
+                    }
                     let symbol_name = state.resolve(symbol.name);
 
                     // Exact match in same file
@@ -284,16 +522,23 @@ impl InterventionEngine {
             }
         }
 
-        // Check imports for conflicts
+        // Check imports for conflicts against their local bindings, so
+        // `use a::B as C` and `import { B as C }` reserve `C`, not `B`
         if let Some(imports) = state.imports.get(&file_id) {
             for import in imports.iter() {
-                // Check if importing a symbol with the same name
+                if import.is_glob && import.name == "*" {
+                    continue;
+                }
                 if import.name == name {
+                    let imported = match &import.original_name {
+                        Some(original) => format!("'{}' (aliased as '{}')", original, name),
+                        None => format!("'{}'", name),
+                    };
                     interventions.push(Intervention {
                         severity: InterventionSeverity::Warning,
                         message: format!(
-                            "Name '{}' conflicts with import from '{}' at line {}",
-                            name, import.path, import.location.start_line
+                            "Name '{}' conflicts with import of {} from '{}' at line {}",
+                            name, imported, import.path, import.location.start_line
                         ),
                         existing_symbol: state.intern(&import.path),
                         existing_location: import.location.clone(),
@@ -307,14 +552,182 @@ impl InterventionEngine {
             }
         }
 
-        // Sort by severity (Block > Warning > Info) then by score
-        interventions.sort_by(|a, b| {
-            let severity_order = |s: &InterventionSeverity| match s {
-                InterventionSeverity::Block => 0,
-                InterventionSeverity::Warning => 1,
-                InterventionSeverity::Info => 2,
+        Self::sort_by_severity(&mut interventions);
+        interventions
+    }
+
+    /// Check proposed code for error mapping that already repeats across the
+    /// codebase.
+    ///
+    /// Each `.map_err(...)`, `.ok_or_else(...)` or `.ok_or(...)` in `code` is
+    /// normalized as in [`crate::analysis::error_patterns`] and compared with
+    /// the existing call sites. A mapping found at two or more sites is a
+    /// warning (a helper or `From` impl is overdue); a single earlier site is
+    /// informational.
+    ///
+    /// # Arguments
+    /// * `state` - The OCI state
+    /// * `code` - The Rust code about to be written
+    ///
+    /// # Returns
+    /// One intervention per repeated mapping, most existing sites first
+    pub fn check_error_patterns(state: &OciState, code: &str) -> Vec<Intervention> {
+        let mut shapes = error_patterns::snippet_shapes(code);
+        shapes.sort();
+        shapes.dedup();
+        if shapes.is_empty() {
+            return Vec::new();
+        }
+
+        let report = ErrorPatternAnalyzer::new()
+            .with_min_sites(1)
+            .analyze(state, &state.root_path);
+        let mut interventions = Vec::new();
+        for pattern in report.patterns {
+            if shapes.binary_search(&pattern.shape).is_err() {
+                continue;
+            }
+            let Some(first) = pattern.sites.first() else {
+                continue;
+            };
+            let severity = if pattern.count >= 2 {
+                InterventionSeverity::Warning
+            } else {
+                InterventionSeverity::Info
             };
+            let recommendation = match pattern.fix {
+                ErrorPatternFix::FromImpl => {
+                    "Implement `From` for the wrapped error type and use `?` here and at the existing sites"
+                }
+                ErrorPatternFix::Helper => {
+                    "Extract a helper function for this mapping and call it here and at the existing sites"
+                }
+            };
+            interventions.push(Intervention {
+                severity,
+                message: format!(
+                    "Error mapping `{}` already appears at {} call site(s), first in {} ({}:{})",
+                    pattern.shape, pattern.count, first.symbol, first.file, first.line
+                ),
+                existing_symbol: state.intern(&first.symbol),
+                existing_location: Location::new(
+                    state.root_path.join(&first.file),
+                    first.start_byte,
+                    first.end_byte,
+                )
+                .with_positions(first.line, 0, first.line, 0),
+                similarity_score: 1.0,
+                recommendation: recommendation.to_string(),
+            });
+        }
+        interventions
+    }
 
+    /// Check a whole proposed file before it is written.
+    ///
+    /// `content` is parsed with the parser for `file`'s language, and each
+    /// extracted symbol is checked against the index:
+    /// - Functions and methods for duplicates at or above the engine's
+    ///   threshold (see [`Self::detect_duplication`])
+    /// - Top-level items for existing symbols of the same name (see
+    ///   [`Self::suggest_alternatives`])
+    /// - Every item for a second definition of its name in the proposed
+    ///   file, and top-level items for an import binding their name
+    /// - Functions and methods whose calls mostly resolve into one other
+    ///   directory, as a placement hint
+    ///
+    /// Symbols already indexed from `file` are ignored, so checking a
+    /// rewrite of an existing file does not flag its current version.
+    ///
+    /// # Arguments
+    /// * `state` - The OCI state
+    /// * `file` - Where the file would be written
+    /// * `content` - The proposed file content
+    ///
+    /// # Returns
+    /// The symbols with findings, in source order, with lines in `content`
+    pub fn check_file(
+        &self,
+        state: &OciState,
+        file: &Path,
+        content: &str,
+    ) -> anyhow::Result<Vec<SymbolInterventions>> {
+        let parser = parser_for_file(file)
+            .ok_or_else(|| OmniError::UnsupportedLanguage(file.display().to_string()))?;
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language())?;
+        let tree = ts
+            .parse(content, None)
+            .ok_or_else(|| OmniError::parse(file, "no syntax tree"))?;
+        let mut symbols = parser.extract_symbols(&tree, content, file, &state.interner)?;
+        let calls = parser.extract_calls(&tree, content, file, &state.interner)?;
+        let imports = parser.extract_imports(&tree, content, file)?;
+        symbols.retain(|s| {
+            !matches!(
+                s.kind,
+                SymbolKind::Impl | SymbolKind::Module | SymbolKind::Field | SymbolKind::Variant
+            )
+        });
+        symbols.sort_by_key(|s| s.location.start_byte);
+
+        let mut report = Vec::new();
+        for symbol in &symbols {
+            let name = state.resolve(symbol.name);
+            let callable = matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method);
+            let mut interventions = Vec::new();
+            if !symbol.is_ignored(IGNORE_NAMING) {
+                interventions.extend(Self::proposed_naming_conflicts(
+                    state, symbol, &symbols, &imports,
+                ));
+            }
+            if !symbol.is_ignored(IGNORE_DUPLICATION) {
+                if let Some(sig) = symbol.signature.as_ref().filter(|_| callable) {
+                    interventions.extend(self.proposed_duplicates(state, symbol, sig, file));
+                }
+                if symbol.parent.is_none() {
+                    interventions.extend(
+                        Self::suggest_alternatives(state, name)
+                            .into_iter()
+                            .filter(|i| {
+                                i.severity != InterventionSeverity::Info
+                                    && i.existing_location.file != file
+                            }),
+                    );
+                }
+            }
+            if callable {
+                interventions.extend(Self::placement(state, symbol, &calls, file));
+            }
+            if interventions.is_empty() {
+                continue;
+            }
+
+            Self::sort_by_severity(&mut interventions);
+            let mut seen = HashSet::new();
+            interventions
+                .retain(|i| seen.insert((i.existing_symbol, i.existing_location.file.clone())));
+            report.push(SymbolInterventions {
+                symbol: symbol.scoped_name,
+                kind: symbol.kind,
+                line: symbol.location.start_line,
+                interventions,
+            });
+        }
+        Ok(report)
+    }
+
+    // ========================================================================
+    // Internal Helper Methods
+    // ========================================================================
+
+    /// Sort by severity (Block > Warning > Info), then by score.
+    fn sort_by_severity(interventions: &mut [Intervention]) {
+        let severity_order = |s: &InterventionSeverity| match s {
+            InterventionSeverity::Block => 0,
+            InterventionSeverity::Warning => 1,
+            InterventionSeverity::Info => 2,
+        };
+        interventions.sort_by(|a, b| {
             severity_order(&a.severity)
                 .cmp(&severity_order(&b.severity))
                 .then_with(|| {
@@ -323,13 +736,169 @@ impl InterventionEngine {
                         .unwrap_or(std::cmp::Ordering::Equal)
                 })
         });
+    }
 
+    /// Existing functions outside `file` similar to a proposed one.
+    fn proposed_duplicates(
+        &self,
+        state: &OciState,
+        symbol: &SymbolDef,
+        sig: &Signature,
+        file: &Path,
+    ) -> Vec<Intervention> {
+        let name = state.resolve(symbol.name);
+        let proposed = format!(
+            "fn {}({}){}",
+            name,
+            sig.params.join(", "),
+            sig.return_type
+                .as_ref()
+                .map(|ret| format!(" -> {}", ret))
+                .unwrap_or_default()
+        );
+        Self::detect_duplication(state, &proposed)
+            .into_iter()
+            .filter(|m| m.score >= self.threshold && m.location.file != file)
+            .take(MAX_DUPLICATES)
+            .map(|m| Self::duplicate_intervention(state, m, name))
+            .collect()
+    }
+
+    /// Intervention for an existing function matching proposed `name`.
+    fn duplicate_intervention(state: &OciState, m: SimilarityMatch, name: &str) -> Intervention {
+        let existing = state.resolve(m.symbol);
+        Intervention {
+            severity: if m.score >= BLOCK_SIMILARITY {
+                InterventionSeverity::Block
+            } else {
+                InterventionSeverity::Warning
+            },
+            message: format!(
+                "Similar {} '{}' exists at {}:{} ({:.0}% similar)",
+                m.kind.as_str(),
+                existing,
+                m.location.file.display(),
+                m.location.start_line,
+                m.score * 100.0
+            ),
+            existing_symbol: m.symbol,
+            existing_location: m.location,
+            similarity_score: m.score,
+            recommendation: format!("Reuse '{}' instead of adding '{}'", existing, name),
+        }
+    }
+
+    /// Conflicts of a proposed symbol with the rest of the proposed file:
+    /// an earlier definition under the same scoped name, or, for top-level
+    /// items, an import binding the same name.
+    fn proposed_naming_conflicts(
+        state: &OciState,
+        symbol: &SymbolDef,
+        symbols: &[SymbolDef],
+        imports: &[ImportInfo],
+    ) -> Vec<Intervention> {
+        let name = state.resolve(symbol.name);
+        let mut interventions = Vec::new();
+        if let Some(earlier) = symbols.iter().find(|s| {
+            s.scoped_name == symbol.scoped_name
+                && s.location.start_byte < symbol.location.start_byte
+        }) {
+            interventions.push(Intervention {
+                severity: InterventionSeverity::Block,
+                message: format!(
+                    "'{}' is already defined at line {} of the proposed file",
+                    name, earlier.location.start_line
+                ),
+                existing_symbol: earlier.scoped_name,
+                existing_location: earlier.location.clone(),
+                similarity_score: 1.0,
+                recommendation: "Remove one definition or choose a different name".to_string(),
+            });
+        }
+        if symbol.parent.is_none() {
+            for import in imports.iter().filter(|i| !i.is_glob && i.name == name) {
+                interventions.push(Intervention {
+                    severity: InterventionSeverity::Warning,
+                    message: format!(
+                        "Name '{}' conflicts with import from '{}' at line {}",
+                        name, import.path, import.location.start_line
+                    ),
+                    existing_symbol: state.intern(&import.path),
+                    existing_location: import.location.clone(),
+                    similarity_score: 1.0,
+                    recommendation: format!(
+                        "Rename to avoid shadowing the imported '{}' or use a qualified path",
+                        import.name
+                    ),
+                });
+            }
+        }
         interventions
     }
 
-    // ========================================================================
-    // Internal Helper Methods
-    // ========================================================================
+    /// Suggest the directory most of a proposed function's calls resolve
+    /// into, when that is not the directory of `file`.
+    ///
+    /// Only calls naming exactly one indexed function outside `file` count,
+    /// and the function needs [`MIN_PLACEMENT_CALLS`] of them, a majority
+    /// going to the one directory.
+    fn placement(
+        state: &OciState,
+        symbol: &SymbolDef,
+        calls: &[CallEdge],
+        file: &Path,
+    ) -> Option<Intervention> {
+        let mut by_dir: HashMap<PathBuf, (usize, SymbolDef)> = HashMap::new();
+        let mut resolved = 0;
+        for call in calls.iter().filter(|c| c.caller == symbol.scoped_name) {
+            let targets: Vec<SymbolDef> = state
+                .find_by_name(&call.callee_name)
+                .into_iter()
+                .filter(|t| {
+                    t.location.file != file
+                        && matches!(t.kind, SymbolKind::Function | SymbolKind::Method)
+                })
+                .collect();
+            let [target] = targets.as_slice() else {
+                continue;
+            };
+            let Some(dir) = target.location.file.parent() else {
+                continue;
+            };
+            resolved += 1;
+            by_dir
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| (0, target.clone()))
+                .0 += 1;
+        }
+        let (dir, (count, example)) = by_dir
+            .into_iter()
+            .max_by(|a, b| a.1.0.cmp(&b.1.0).then_with(|| b.0.cmp(&a.0)))?;
+        if resolved < MIN_PLACEMENT_CALLS || count * 2 <= resolved || file.parent() == Some(&dir) {
+            return None;
+        }
+
+        let name = state.resolve(symbol.name);
+        let rel = dir.strip_prefix(&state.root_path).unwrap_or(&dir);
+        Some(Intervention {
+            severity: InterventionSeverity::Info,
+            message: format!(
+                "{} of the {} workspace calls made by '{}' go to {}/",
+                count,
+                resolved,
+                name,
+                rel.display()
+            ),
+            existing_symbol: example.scoped_name,
+            existing_location: example.location,
+            similarity_score: count as f32 / resolved as f32,
+            recommendation: format!(
+                "Consider placing '{}' in {}/ next to the code it calls",
+                name,
+                rel.display()
+            ),
+        })
+    }
 
     /// Parse a function signature string into components.
     ///
@@ -567,4 +1136,297 @@ mod tests {
         );
         assert_eq!(interventions.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_ignored_symbols_are_not_suggested() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "// omni:ignore duplication, naming\npub fn parse_config() {}\n\npub fn parse_configs() {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let ignored = state.intern("crate::parse_config");
+        let alternatives = InterventionEngine::suggest_alternatives(&state, "parse_config");
+        let conflicts = InterventionEngine::check_naming_conflicts(
+            &state,
+            "parse_config",
+            &temp.path().join("lib.rs"),
+        );
+        // Only the unannotated near-duplicate is offered
+        for interventions in [alternatives, conflicts] {
+            assert!(!interventions.is_empty());
+            assert!(interventions.iter().all(|i| i.existing_symbol != ignored));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_duplication_grades_matches() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "pub fn parse_config(path: &str) -> Config {\n    todo!()\n}\n\npub fn render(page: u32) {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let engine = InterventionEngine::new();
+        let interventions =
+            engine.check_duplication(&state, "fn parse_config(path: &str) -> Config");
+        assert_eq!(interventions.len(), 1);
+        let duplicate = &interventions[0];
+        assert_eq!(
+            duplicate.existing_symbol,
+            state.intern("crate::parse_config")
+        );
+        assert_eq!(duplicate.severity, InterventionSeverity::Block);
+        assert!(
+            duplicate
+                .recommendation
+                .contains("Reuse 'crate::parse_config'")
+        );
+
+        assert!(
+            engine
+                .check_duplication(&state, "fn shutdown(force: bool) -> Result<()>")
+                .is_empty()
+        );
+        assert!(
+            engine
+                .check_duplication(&state, "not a signature")
+                .is_empty()
+        );
+    }
+
+    #[cfg(feature = "semantic")]
+    #[tokio::test]
+    async fn test_semantic_duplication_matches_other_names() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "/// Load a user record from the database by its id.\npub fn load_user_by_id(db: &Db, id: u64) -> User {\n    todo!()\n}\n\n/// Draw a circle onto the canvas.\npub fn draw_circle(canvas: &mut Canvas, radius: f32) {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+        let index = match crate::semantic::build_index(&state) {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Skipping semantic test: {err}");
+                return;
+            }
+        };
+
+        let proposed = "fn fetch_user_record(db: &Db, id: u64) -> User";
+        let doc = Some("Fetch a user record from the database by its id.");
+        // Names too far apart for the signature comparison alone
+        let engine = InterventionEngine::new();
+        assert!(engine.check_duplication(&state, proposed).is_empty());
+
+        let matches =
+            InterventionEngine::detect_semantic_duplication(&state, &index, proposed, doc).unwrap();
+        let load = state.intern("crate::load_user_by_id");
+        assert_eq!(matches.first().map(|m| m.symbol), Some(load));
+        assert!(
+            matches
+                .iter()
+                .all(|m| m.symbol != state.intern("crate::draw_circle"))
+        );
+
+        let interventions = engine
+            .check_duplication_semantic(&state, &index, proposed, doc)
+            .unwrap();
+        assert_eq!(interventions[0].existing_symbol, load);
+    }
+
+    #[tokio::test]
+    async fn test_suggest_alternatives_prefers_documented_replacement() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "#[deprecated(note = \"use `read_settings` instead\")]\npub fn load_settings() {}\n\npub fn read_settings() {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let alternatives = InterventionEngine::suggest_alternatives(&state, "load_settings");
+        assert_eq!(alternatives.len(), 2);
+        assert_eq!(
+            state.resolve(alternatives[0].existing_symbol),
+            "crate::read_settings"
+        );
+        assert_eq!(alternatives[0].similarity_score, 1.0);
+        assert!(
+            alternatives[0]
+                .message
+                .contains("replaces deprecated 'load_settings'")
+        );
+        assert_eq!(
+            state.resolve(alternatives[1].existing_symbol),
+            "crate::load_settings"
+        );
+        assert!(
+            alternatives[1]
+                .message
+                .contains("(deprecated: use `read_settings` instead)")
+        );
+        assert!(alternatives[1].similarity_score < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_check_naming_conflicts_uses_alias_bindings() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@app/*": ["src/*"] } } }"#,
+        )
+        .unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let rs = root.join("src/lib.rs");
+        std::fs::write(&rs, "use std::collections::HashMap as Map;\nfn f() {}\n").unwrap();
+        let ts = root.join("src/app.ts");
+        std::fs::write(&ts, "import { Widget as Gadget } from \"@app/widgets\";\n").unwrap();
+
+        let state = crate::state::create_state(root.to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        for (file, alias, original) in [(&rs, "Map", "HashMap"), (&ts, "Gadget", "Widget")] {
+            let conflicts = InterventionEngine::check_naming_conflicts(&state, alias, file);
+            assert_eq!(conflicts.len(), 1, "{alias}");
+            assert!(conflicts[0].message.contains(original));
+            // The original name is not bound locally
+            assert!(InterventionEngine::check_naming_conflicts(&state, original, file).is_empty());
+        }
+        assert!(
+            InterventionEngine::check_naming_conflicts(&state, "Gadget", &ts)[0]
+                .message
+                .contains("'src/widgets'")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_error_patterns_flags_repeated_mapping() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("lib.rs"),
+            "pub fn load(p: &str) -> Result<String, AppError> {\n    std::fs::read_to_string(p).map_err(|e| AppError::Io(e))\n}\n\npub fn save(p: &str) -> Result<(), AppError> {\n    std::fs::write(p, \"\").map_err(|e| AppError::Io(e))\n}\n\npub fn port(raw: &str) -> Result<u16, AppError> {\n    raw.parse().map_err(|_| AppError::Port)\n}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(root.to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let proposed = "fn remove(path: &str) -> Result<(), AppError> {\n    std::fs::remove_file(path).map_err(|err| AppError::Io(err))\n}\n";
+        let interventions = InterventionEngine::check_error_patterns(&state, proposed);
+        assert_eq!(interventions.len(), 1);
+        assert_eq!(interventions[0].severity, InterventionSeverity::Warning);
+        assert!(interventions[0].message.contains("2 call site(s)"));
+        assert!(interventions[0].recommendation.contains("From"));
+        assert_eq!(
+            interventions[0].existing_symbol,
+            state.intern("crate::load")
+        );
+
+        // A single earlier site is only worth a note
+        let once =
+            InterventionEngine::check_error_patterns(&state, "x.map_err(|_| AppError::Port)");
+        assert_eq!(once.len(), 1);
+        assert_eq!(once[0].severity, InterventionSeverity::Info);
+        let fresh = "x.ok_or_else(|| AppError::Missing)";
+        assert!(InterventionEngine::check_error_patterns(&state, fresh).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_file_groups_findings_per_symbol() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src/store")).unwrap();
+        std::fs::write(
+            root.join("src/store/db.rs"),
+            "pub struct Settings;\n\npub fn open_db(path: &str) -> Result<Db, Error> {\n    todo!()\n}\n\npub fn query_rows(db: &Db, sql: &str) -> Vec<Row> {\n    todo!()\n}\n\npub fn close_db(db: Db) {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(root.to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let proposed = "use crate::store::Pool;\n\npub struct Pool;\n\npub fn open_db(path: &str) -> Result<Db, Error> {\n    todo!()\n}\n\npub fn sync(path: &str) {\n    let db = open_db(path);\n    query_rows(&db, \"select 1\");\n    close_db(db);\n}\n\npub fn sync() {}\n\npub fn fresh_name() {}\n";
+        let file = root.join("src/api/handler.rs");
+        let report = InterventionEngine::new()
+            .check_file(&state, &file, proposed)
+            .unwrap();
+        let found = |name: &str| {
+            report
+                .iter()
+                .find(|s| s.symbol == state.intern(name))
+                .map(|s| (s.line, s.interventions.clone()))
+        };
+
+        let (line, pool) = found("crate::Pool").unwrap();
+        assert_eq!(line, 2);
+        assert!(pool[0].message.contains("import"));
+
+        // The proposed copy of open_db is an exact duplicate
+        let (line, open_db) = found("crate::open_db").unwrap();
+        assert_eq!(line, 4);
+        assert_eq!(open_db[0].severity, InterventionSeverity::Block);
+        assert_eq!(
+            open_db[0].existing_location.file,
+            root.join("src/store/db.rs")
+        );
+
+        // Both definitions of sync call into src/store, the second is a redefinition
+        let entries: Vec<_> = report
+            .iter()
+            .filter(|s| s.symbol == state.intern("crate::sync"))
+            .collect();
+        assert_eq!(entries.len(), 2);
+        let placement = entries[0]
+            .interventions
+            .iter()
+            .find(|i| i.severity == InterventionSeverity::Info)
+            .unwrap();
+        assert!(placement.recommendation.contains("src/store/"));
+        assert_eq!(entries[1].line, 14);
+        assert_eq!(
+            entries[1].interventions[0].severity,
+            InterventionSeverity::Block
+        );
+        assert!(entries[1].interventions[0].message.contains("line 8"));
+
+        assert!(found("crate::fresh_name").is_none());
+        // Symbols come back in source order
+        assert!(report.windows(2).all(|w| w[0].line <= w[1].line));
+
+        assert!(
+            InterventionEngine::new()
+                .check_file(&state, Path::new("notes.unknown"), "")
+                .is_err()
+        );
+    }
 }
@@ -48,17 +48,45 @@
 //! ```
 
 // Core modules (always available)
+pub mod bench;
+pub mod branch_cache;
+pub mod budget;
 pub mod cache;
+pub mod call_groups;
+pub mod call_resolution;
+pub mod call_tree;
+pub mod completion;
+pub mod config;
+pub mod config_refs;
 pub mod discovery;
+pub mod duplication;
+pub mod error;
+pub mod explain;
 pub mod export;
+pub mod focus;
 pub mod fold;
+pub mod impact;
+pub mod implementations;
 pub mod incremental;
+pub mod migrations;
+pub mod output;
 pub mod parsing;
+pub mod popularity;
 pub mod query;
+pub mod reachability;
+pub mod reexports;
+pub mod refactor;
+pub mod renames;
 pub mod search;
+pub mod shared;
+pub mod sql_refs;
+pub mod stack;
 pub mod state;
+pub mod test_impact;
+pub mod tombstones;
 pub mod topology;
 pub mod types;
+pub mod verify;
 
 // Optional modules (feature-gated)
 #[cfg(feature = "analysis")]
@@ -73,23 +101,31 @@ pub mod mcp;
 pub mod semantic;
 
 // Re-exports (core - always available)
+pub use budget::Budget;
 pub use cache::{FileFingerprint, IndexManifest};
-pub use discovery::FileDiscovery;
+pub use completion::Completion;
+pub use discovery::{FileDiscovery, PackageScope};
+pub use error::OmniError;
 pub use fold::{FunctionSignature, fold_to_signatures, parse_single_file};
 pub use incremental::IncrementalIndexer;
-pub use incremental::{IndexOptions, IndexReport};
+pub use incremental::{IndexEvent, IndexOptions, IndexPhase, IndexReport};
 pub use query::{QueryFilters, QueryResponse, QueryResult, SearchDoc, SearchIndex, SearchState};
+pub use reachability::{ReachableSet, reachable_from};
 pub use search::{
-    Bm25Index, HybridSearch, HybridSearchConfig, HybridSearchResult, SearchQualityMetrics,
+    Bm25Index, HybridSearch, HybridSearchConfig, HybridSearchResult, QueryKind,
+    SearchQualityMetrics, WeightDecision, classify_query,
 };
-pub use state::{IndexStats, OciState, SharedState, create_state};
+pub use stack::StackReport;
+pub use state::{FileBatch, IndexStats, OciState, Overlay, SharedState, create_state};
 pub use types::*;
 
 // Re-exports (feature-gated)
 #[cfg(feature = "analysis")]
-pub use analysis::DeadCodeAnalyzer;
+pub use analysis::{BinaryAnalyzer, DeadCodeAnalyzer};
 #[cfg(feature = "context")]
-pub use context::{ContextChunk, ContextQuery, ContextResult, ContextSynthesizer};
+pub use context::{
+    ChunkOrdering, ContextChunk, ContextQuery, ContextResult, ContextSynthesizer, RelationCaps,
+};
 #[cfg(feature = "intervention")]
 pub use intervention::InterventionEngine;
 
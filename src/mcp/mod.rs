@@ -2,20 +2,43 @@
 //!
 //! Exposes OCI functionality via Model Context Protocol.
 
+use crate::budget::Budget;
+use crate::call_groups::{CallGroup, CallGrouping, CallSide, group_calls};
+use crate::call_resolution::{CallerCandidate, resolve_callers};
+use crate::call_tree::call_tree;
+use crate::completion::DEFAULT_COMPLETION_LIMIT;
+use crate::config_refs::find_config_refs;
+use crate::duplication::DuplicationPressure;
+use crate::implementations::{implementations, trait_origins};
 use crate::incremental::{IncrementalIndexer, IndexOptions};
-use crate::query::{execute_query, load_search_index, load_search_state, parse_query_filters};
+use crate::output::{
+    CallResult, ReferenceResult, SCHEMA_VERSION, SymbolResult, downgrade, success_json,
+};
+use crate::popularity::record_selection;
+use crate::query::{
+    SearchIndex, SearchType, SemanticHits, apply_overlays, load_search_index, load_search_state,
+    parse_query_filters, search_with_fallback, semantic_query,
+};
+use crate::reachability::reachable_from;
+use crate::refactor::rename_impact;
+use crate::renames::RenameLog;
+use crate::sql_refs::find_sql_refs;
 use crate::state::{SharedState, create_state};
+use crate::tombstones::TombstoneLog;
 use crate::topology::TopologyBuilder;
+use crate::types::{Attribute, SemanticStatus, SymbolDef, TestSplit};
 use anyhow::Result;
 use petgraph::visit::EdgeRef;
 use rmcp::handler::server::{router::tool::ToolRouter, tool::Parameters};
 use rmcp::model::{ErrorData as McpError, *};
 use rmcp::transport::stdio;
 use rmcp::{ServerHandler, ServiceExt, schemars, tool, tool_handler, tool_router};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 pub const SERVER_NAME: &str = "omni-index";
@@ -27,15 +50,81 @@ pub struct OciServerState {
     pub indexer: IncrementalIndexer,
     pub topology: TopologyBuilder,
     pub workspace_root: PathBuf,
+    /// Items pinned into every context response for this session
+    pub pins: PinnedSet,
+    /// Feedback on context chunks given by the `context_feedback` tool
+    #[cfg(feature = "context")]
+    pub feedback: crate::context::ContextFeedback,
+    /// `oci://` resources the client subscribed to
+    #[cfg(feature = "context")]
+    pub subscriptions: ResourceSubscriptions,
+}
+
+/// Symbols (scoped names) and files pinned by the `pin` tool.
+#[derive(Debug, Clone, Default)]
+pub struct PinnedSet {
+    pub symbols: BTreeSet<String>,
+    pub files: BTreeSet<PathBuf>,
+}
+
+impl PinnedSet {
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty() && self.files.is_empty()
+    }
+}
+
+/// Resource URIs to announce as updated after each index build, and the
+/// client to announce them to.
+#[cfg(feature = "context")]
+#[derive(Default)]
+pub struct ResourceSubscriptions {
+    pub uris: BTreeSet<String>,
+    pub peer: Option<rmcp::Peer<rmcp::RoleServer>>,
+}
+
+#[cfg(feature = "context")]
+impl ResourceSubscriptions {
+    /// Tell the subscribed client its resources changed. Sends in the
+    /// background so the index build that triggered it isn't held up.
+    pub fn notify(&self) {
+        let Some(peer) = self.peer.clone() else {
+            return;
+        };
+        let uris: Vec<String> = self.uris.iter().cloned().collect();
+        if uris.is_empty() {
+            return;
+        }
+        tokio::spawn(async move {
+            for uri in uris {
+                if let Err(e) = peer
+                    .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                    .await
+                {
+                    tracing::debug!("Failed to notify resource update: {}", e);
+                }
+            }
+        });
+    }
 }
 
 impl OciServerState {
     pub fn new(workspace_root: PathBuf) -> Self {
+        let overrides = crate::config::IndexConfig::load(&workspace_root)
+            .and_then(|config| config.overrides())
+            .inspect_err(|e| tracing::warn!("{:#}", e))
+            .unwrap_or_default();
         Self {
             oci_state: create_state(workspace_root.clone()),
-            indexer: IncrementalIndexer::new(),
+            indexer: IncrementalIndexer::new()
+                .with_incremental_parsing(true)
+                .with_language_overrides(overrides),
             topology: TopologyBuilder::new(),
             workspace_root,
+            pins: PinnedSet::default(),
+            #[cfg(feature = "context")]
+            feedback: Default::default(),
+            #[cfg(feature = "context")]
+            subscriptions: Default::default(),
         }
     }
 }
@@ -54,6 +143,112 @@ impl OciServer {
             tool_router: Self::tool_router(),
         }
     }
+
+    /// Build the semantic index on first use, indexing the workspace first
+    /// if nothing is loaded. Returns whether the index is ready.
+    ///
+    /// Loads already in flight or known to fail are left to the background
+    /// loader (see [`crate::semantic::spawn_loader`]) instead of blocking
+    /// every search on them.
+    #[cfg(feature = "semantic")]
+    async fn ensure_semantic_index(&self) -> bool {
+        let oci = {
+            let state = self.state.read().await;
+            if state.oci_state.semantic_index.get().is_some() {
+                return true;
+            }
+            if !matches!(
+                *state.oci_state.semantic_status.read(),
+                SemanticStatus::NotBuilt
+            ) {
+                return false;
+            }
+            Arc::clone(&state.oci_state)
+        };
+        if let Err(e) = self.ensure_indexed().await {
+            tracing::warn!("Failed to index for semantic search: {}", e);
+            return false;
+        }
+        // Embedding is CPU bound; run it off the async runtime.
+        let loaded = tokio::task::spawn_blocking(move || {
+            crate::semantic::load(&oci, crate::semantic::EmbeddingPoolConfig::default())
+        })
+        .await;
+        match loaded {
+            Ok(Ok(loaded)) => loaded,
+            Ok(Err(e)) => {
+                tracing::warn!("Semantic search unavailable, using BM25 only: {}", e);
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Semantic index build panicked: {}", e);
+                false
+            }
+        }
+    }
+
+    #[cfg(not(feature = "semantic"))]
+    async fn ensure_semantic_index(&self) -> bool {
+        false
+    }
+
+    /// Index the workspace if nothing is loaded yet, reusing the snapshot
+    /// in `.omni/oci.bin` when it is current.
+    async fn ensure_indexed(&self) -> Result<()> {
+        if !self.state.read().await.oci_state.symbols.is_empty() {
+            return Ok(());
+        }
+        let state = self.state.write().await;
+        if state.oci_state.symbols.is_empty() {
+            state
+                .indexer
+                .full_index_cached(&state.oci_state, &state.workspace_root)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Mine git history once per session so `get_context` can suggest code
+    /// that usually changes with the query file. Workspaces without history
+    /// go without.
+    #[cfg(all(feature = "analysis", feature = "context"))]
+    async fn ensure_history(&self) {
+        let (oci, root) = {
+            let state = self.state.read().await;
+            (state.oci_state.clone(), state.workspace_root.clone())
+        };
+        if oci.churn.read().is_some() {
+            return;
+        }
+        // Shells out to git; keep it off the async runtime.
+        let mined = tokio::task::spawn_blocking(move || {
+            crate::analysis::ChurnAnalyzer::analyze_index(
+                &oci,
+                &root,
+                crate::analysis::cochange::DEFAULT_DAYS,
+            )
+        })
+        .await;
+        if let Ok(Err(e)) = mined {
+            tracing::debug!("No git history for co-change hints: {}", e);
+        }
+    }
+
+    /// Restore the index snapshot left in `.omni/oci.bin` by an earlier run,
+    /// if no source file changed since. Returns whether it was restored.
+    pub async fn restore_snapshot(&self) -> bool {
+        let state = self.state.read().await;
+        match state
+            .indexer
+            .load_snapshot(&state.oci_state, &state.workspace_root)
+        {
+            Ok(restored) => restored,
+            Err(e) => {
+                tracing::warn!("Ignoring index snapshot: {}", e);
+                false
+            }
+        }
+    }
 }
 
 // ============================================================================
@@ -62,42 +257,131 @@ impl OciServer {
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct IndexRequest {
-    #[schemars(description = "Operation: build, rebuild, status")]
+    #[schemars(description = "Operation: build, rebuild, status, reembed")]
     pub op: String,
     #[schemars(description = "Force full rebuild even if index exists")]
     #[serde(default)]
     pub force: bool,
+    #[schemars(
+        description = "Files or directories from the current task; indexed first and boosted in search results for the next few hours"
+    )]
+    #[serde(default)]
+    pub focus: Vec<String>,
+    #[schemars(
+        description = "Build the semantic index after a build if it isn't loaded yet (default: `semantic` under `[index]` in omni.toml, else true)"
+    )]
+    pub semantic: Option<bool>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct SymbolRequest {
-    #[schemars(description = "Symbol name to search for")]
+    #[schemars(description = "Symbol name to search for; may be empty when attributes are given")]
+    #[serde(default)]
     pub name: String,
     #[schemars(description = "Whether to search by scoped name (e.g., 'crate::module::Foo')")]
     #[serde(default)]
     pub scoped: bool,
     #[schemars(description = "Maximum number of results")]
     pub max_results: Option<usize>,
+    #[schemars(
+        description = "File the lookup is made from; ranks matches in the same file, module, and crate first"
+    )]
+    pub context_file: Option<String>,
+    #[schemars(
+        description = "Also list recently deleted symbols of that name with their last location"
+    )]
+    #[serde(default)]
+    pub include_deleted: bool,
+    #[schemars(
+        description = "Only symbols carrying all of these attributes/annotations, e.g. 'deprecated', 'tokio::main', 'derive(Serialize)', 'Test'"
+    )]
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CompleteSymbolRequest {
+    #[schemars(
+        description = "Name prefix, case-insensitive; include '::' to complete scoped names (e.g. 'crate::engine::')"
+    )]
+    pub prefix: String,
+    #[schemars(description = "Maximum number of completions (default 20)")]
+    pub limit: Option<usize>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CallGraphRequest {
-    #[schemars(description = "Operation: callers, callees")]
+    #[schemars(description = "Operation: callers, callees, binary (name = binary target)")]
     pub op: String,
-    #[schemars(description = "Symbol name to find callers/callees for")]
+    #[schemars(
+        description = "Symbol name to find callers/callees for, or binary name for op=binary. A scoped name (crate::json::Json::parse) lists only the callers resolved to that definition"
+    )]
     pub name: String,
-    #[schemars(description = "Maximum depth to traverse (default: 1)")]
+    #[schemars(
+        description = "Maximum depth to traverse (default: 1). Above 1, callers/callees are returned as a tree of transitive calls"
+    )]
     pub depth: Option<u32>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape) instead of text; above depth 1, the transitive call tree"
+    )]
+    #[serde(default)]
+    pub json: bool,
+    #[schemars(description = "Inline the call line ±2 lines for each call site (first 20)")]
+    #[serde(default)]
+    pub include_snippets: bool,
+    #[schemars(
+        description = "Roll up callers/callees into counts per module, crate, or file, with a few examples each, instead of listing every call"
+    )]
+    pub group_by: Option<String>,
+    #[schemars(description = "Leave out calls made from test code (callers only)")]
+    #[serde(default)]
+    pub exclude_tests: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct AnalysisRequest {
-    #[schemars(description = "Analysis type: dead_code, coverage, churn, hotspots")]
+    #[schemars(
+        description = "Analysis type: dead_code, coverage, churn, hotspots, clones, co_changes, or the name of a registered custom analysis"
+    )]
     pub analysis: String,
-    #[schemars(description = "Path to coverage JSON file (for coverage analysis)")]
+    #[schemars(
+        description = "File (relative to the workspace root) or symbol to find co-changes for (required for co_changes)"
+    )]
+    pub target: Option<String>,
+    #[schemars(
+        description = "Path to an llvm-cov or tarpaulin JSON report, relative to the workspace root (required for coverage, optional for hotspots)"
+    )]
     pub coverage_file: Option<String>,
-    #[schemars(description = "Number of days to analyze (for churn analysis)")]
+    #[schemars(
+        description = "Number of days of git history to analyze (for churn and hotspots, default 30; co_changes, default 180)"
+    )]
     pub days: Option<u32>,
+    #[schemars(description = "Stop after this many milliseconds and return partial results")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Stop after scanning this many candidates and return partial results"
+    )]
+    pub max_work: Option<usize>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -106,10 +390,33 @@ pub struct SearchRequest {
     pub query: String,
     #[schemars(description = "Maximum number of results")]
     pub top_k: Option<usize>,
+    #[schemars(
+        description = "Search type: hybrid (default; semantic fused with BM25, BM25 alone while no semantic index is available), bm25, or semantic. Semantic types cover the workspace root only"
+    )]
+    pub search_type: Option<String>,
     #[schemars(description = "Optional root path override")]
     pub root: Option<String>,
-    #[schemars(description = "Optional filters (path:..., ext:..., -path:...)")]
+    #[schemars(
+        description = "Optional filters (path:..., ext:..., -path:..., type:config|symbol)"
+    )]
     pub filters: Option<Vec<String>>,
+    #[schemars(
+        description = "Only return code reachable (via calls or imports) from this file or symbol"
+    )]
+    pub reachable_from: Option<String>,
+    #[schemars(description = "Leave out results from test code")]
+    #[serde(default)]
+    pub exclude_tests: bool,
+    #[schemars(description = "Stop after this many milliseconds and return partial results")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Stop after scanning this many candidates and return partial results"
+    )]
+    pub max_work: Option<usize>,
+    #[schemars(
+        description = "JSON output version to emit, for clients of an older format (default: current)"
+    )]
+    pub schema_version: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -124,28 +431,322 @@ pub struct ContextRequest {
     pub intent: Option<String>,
     #[schemars(description = "Maximum tokens in response")]
     pub max_tokens: Option<usize>,
+    #[schemars(
+        description = "Chunk ordering: relevance (default), topological (definitions before callers), file-grouped"
+    )]
+    pub ordering: Option<String>,
+    #[schemars(description = "Stop after this many milliseconds and return partial results")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Stop after scanning this many candidates and return partial results"
+    )]
+    pub max_work: Option<usize>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PinRequest {
+    #[schemars(description = "Operation: pin, unpin, list, clear")]
+    pub op: String,
+    #[schemars(description = "Symbol to (un)pin: scoped name, or simple name to pin all matches")]
+    pub symbol: Option<String>,
+    #[schemars(description = "File to (un)pin, absolute or relative to the workspace root")]
+    pub file: Option<String>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ContextFeedbackRequest {
+    #[schemars(description = "Operation: useful, not_useful, list, clear")]
+    pub op: String,
+    #[schemars(
+        description = "Symbol of the chunk: scoped name, or simple name to mark all matches (their files are marked too)"
+    )]
+    pub symbol: Option<String>,
+    #[schemars(description = "File of the chunk, absolute or relative to the workspace root")]
+    pub file: Option<String>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct OverlayRequest {
+    #[schemars(description = "Operation: set, clear, list, clear_all")]
+    pub op: String,
+    #[schemars(
+        description = "File to overlay or clear, absolute or relative to the workspace root"
+    )]
+    pub file: Option<String>,
+    #[schemars(description = "Unsaved contents of the file (required for set)")]
+    pub content: Option<String>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FeedbackRequest {
+    #[schemars(
+        description = "Scoped name of the search result that was used (e.g. 'crate::module::Foo')"
+    )]
+    pub symbol: String,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImpactRequest {
+    #[schemars(
+        description = "Symbol to rename or change the signature of: a scoped name narrows to one definition, a simple name covers every definition sharing it"
+    )]
+    pub symbol: String,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ReferencesRequest {
+    #[schemars(
+        description = "Type, trait, or function name; a scoped name is matched by its last segment"
+    )]
+    pub name: String,
+    #[schemars(description = "Leave out usages in test code")]
+    #[serde(default)]
+    pub exclude_tests: bool,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ImplementationsRequest {
+    #[schemars(
+        description = "Trait name or path (e.g. 'Shape', 'fmt::Display'); with method=true, a method (scoped or simple name)"
+    )]
+    pub name: String,
+    #[schemars(
+        description = "Show which trait the method comes from instead of listing implementations"
+    )]
+    #[serde(default)]
+    pub method: bool,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ConfigRefsRequest {
+    #[schemars(
+        description = "Dotted config key (e.g. 'oci.workspace') or environment variable (e.g. 'OCI_WORKSPACE')"
+    )]
+    pub key: String,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SqlRefsRequest {
+    #[schemars(
+        description = "SQL table (e.g. 'users'), column (e.g. 'users.email'), or a column name only one table has"
+    )]
+    pub name: String,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct InterventionRequest {
-    #[schemars(description = "Check type: duplication, naming, alternatives")]
+    #[schemars(
+        description = "Check type: duplication, naming, alternatives, error_patterns, file"
+    )]
     pub check: String,
     #[schemars(description = "Proposed function signature (for duplication check)")]
     pub signature: Option<String>,
+    #[schemars(
+        description = "Also find functions that do the same under other names, by embedding the signature and doc (for duplication check; needs the semantic index)"
+    )]
+    pub semantic: Option<bool>,
+    #[schemars(description = "What the proposed function does (for semantic duplication check)")]
+    pub doc: Option<String>,
     #[schemars(description = "Proposed name (for naming/alternatives check)")]
     pub name: Option<String>,
-    #[schemars(description = "File path context")]
+    #[schemars(
+        description = "File the proposed symbol goes in, relative to the workspace root (for naming and file checks)"
+    )]
     pub file: Option<String>,
+    #[schemars(
+        description = "Proposed Rust code (for error_patterns check), or whole file content (for file check)"
+    )]
+    pub code: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct TopologyRequest {
-    #[schemars(description = "Operation: modules, imports, pagerank, dependencies")]
+    #[schemars(description = "Operation: modules, imports, pagerank, dependencies, roles")]
     pub op: String,
     #[schemars(description = "File or module path to query")]
     pub path: Option<String>,
     #[schemars(description = "Maximum results")]
     pub max_results: Option<usize>,
+    #[schemars(
+        description = "Return JSON (the `omni --json` shape, where the CLI has one) instead of text"
+    )]
+    #[serde(default)]
+    pub json: bool,
+}
+
+/// Call sites that get an inline snippet when `include_snippets` is set.
+const MAX_CALL_SNIPPETS: usize = 20;
+
+/// Lines of context on each side of a call site snippet.
+const CALL_SNIPPET_CONTEXT: usize = 2;
+
+/// Built-in analyses of the `analyze` tool.
+const ANALYSES: &[&str] = &[
+    "dead_code",
+    "coverage",
+    "churn",
+    "hotspots",
+    "clones",
+    "co_changes",
+];
+
+/// Entries listed by the `analyze` tool before the rest are summarized.
+#[cfg(feature = "analysis")]
+const MAX_ANALYSIS_RESULTS: usize = 50;
+
+/// Render call-graph rollups: one line per group, then its examples.
+fn format_call_groups(groups: &[CallGroup]) -> String {
+    let mut output = String::new();
+    for group in groups {
+        output.push_str(&format!("- {}: {} calls\n", group.group, group.count));
+        for example in &group.examples {
+            output.push_str(&format!(
+                "    e.g. {} -> {} at {}:{}\n",
+                example.caller,
+                example.callee,
+                example.file.display(),
+                example.line
+            ));
+        }
+    }
+    output
+}
+
+/// Render callers split across same-named definitions: one line per
+/// definition, then its callers with their confidence.
+fn format_caller_candidates(candidates: &[CallerCandidate]) -> String {
+    let mut output = String::new();
+    for candidate in candidates {
+        output.push_str(&format!(
+            "- {} at {}:{} ({} callers)\n",
+            candidate.callee,
+            candidate.file.display(),
+            candidate.line,
+            candidate.callers.len()
+        ));
+        for caller in &candidate.callers {
+            output.push_str(&format!(
+                "    {} at {}:{} ({:.0}%, {}){}\n",
+                caller.caller,
+                caller.file.display(),
+                caller.line,
+                caller.confidence * 100.0,
+                caller.resolution.as_str(),
+                if caller.is_test_context {
+                    " [test]"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
+    output
+}
+
+/// Call site snippets of one listing, at most [`MAX_CALL_SNIPPETS`].
+#[derive(Default)]
+struct CallSnippets {
+    rendered: usize,
+}
+
+impl CallSnippets {
+    fn is_full(&self) -> bool {
+        self.rendered >= MAX_CALL_SNIPPETS
+    }
+
+    /// Render the lines around the call at byte `offset` of `contents`,
+    /// indented for a listing, or `None` once the listing has its share.
+    ///
+    /// Uses the byte offset since line numbers are 0- or 1-based depending
+    /// on the parser.
+    fn render(&mut self, contents: &str, offset: usize) -> Option<String> {
+        if self.is_full() {
+            return None;
+        }
+        let offset = offset.min(contents.len());
+        let call_line = contents.as_bytes()[..offset]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        if call_line >= lines.len() {
+            return None;
+        }
+        let start = call_line.saturating_sub(CALL_SNIPPET_CONTEXT);
+        let end = (call_line + CALL_SNIPPET_CONTEXT + 1).min(lines.len());
+
+        let mut snippet = String::new();
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            let marker = if i == call_line { '>' } else { ' ' };
+            snippet.push_str(&format!("    {} {:>4} | {}\n", marker, i + 1, line));
+        }
+        self.rendered += 1;
+        Some(snippet)
+    }
+}
+
+/// Snippet of the call at `location`, while `snippets` has room for one.
+async fn call_snippet(
+    oci: &crate::state::OciState,
+    snippets: &mut CallSnippets,
+    location: &crate::types::Location,
+) -> Option<String> {
+    if snippets.is_full() {
+        return None;
+    }
+    let contents = oci.get_file_contents(&location.file).await?;
+    snippets.render(&contents, location.start_byte)
 }
 
 // ============================================================================
@@ -154,7 +755,9 @@ pub struct TopologyRequest {
 
 #[tool_router]
 impl OciServer {
-    #[tool(description = "Build or rebuild the code index. Operations: build, rebuild, status")]
+    #[tool(
+        description = "Build or rebuild the code index. Operations: build, rebuild, status, reembed (re-embed vectors from an older model)"
+    )]
     async fn index(
         &self,
         Parameters(req): Parameters<IndexRequest>,
@@ -168,60 +771,215 @@ impl OciServer {
                 let force = req.force || req.op == "rebuild";
                 drop(state);
                 let state = self.state.write().await;
+                // A partial index (nothing loaded, unchanged files skipped)
+                // must not replace the published one
+                let complete = !state.oci_state.symbols.is_empty();
                 let options = IndexOptions {
                     force,
+                    focus: req.focus.clone(),
                     ..Default::default()
                 };
-                match state.indexer.index(&state.oci_state, &root, &options).await {
-                    Ok(report) => {
-                        let docs_total = load_search_state(&root)
-                            .ok()
-                            .and_then(|s| s.map(|s| s.docs.len()))
-                            .unwrap_or(0);
-                        Ok(CallToolResult::success(vec![Content::text(format!(
-                            "Index built successfully:\n- {} files\n- {} symbols\n- {} parsed\n- {} skipped\n- {} removed",
-                            report.total_files,
-                            docs_total,
-                            report.parsed_files,
-                            report.skipped_files,
-                            report.removed_files
-                        ))]))
+                let report = match state.indexer.index(&state.oci_state, &root, &options).await {
+                    Ok(report) => report,
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Index build failed: {}",
+                            e
+                        ))]));
                     }
-                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Index build failed: {}",
-                        e
-                    ))])),
+                };
+                // A forced index re-parsed everything, so it can replace the
+                // snapshot restored on the next start; the writer of a
+                // shared index publishes every build
+                if (force || complete && crate::shared::is_writer(&root))
+                    && state.oci_state.overlays.is_empty()
+                    && let Err(e) = state.indexer.save_snapshot(&state.oci_state, &root)
+                {
+                    tracing::warn!("Failed to save index snapshot: {}", e);
+                }
+                #[cfg(feature = "context")]
+                state.subscriptions.notify();
+                let oci = Arc::clone(&state.oci_state);
+                drop(state);
+
+                // A loaded semantic index was already updated with the
+                // changed files; otherwise build it, reusing cached vectors
+                let embed = req.semantic.unwrap_or_else(|| {
+                    crate::config::IndexConfig::load(&root)
+                        .inspect_err(|e| tracing::warn!("{}", e))
+                        .unwrap_or_default()
+                        .semantic
+                });
+                if embed {
+                    self.ensure_semantic_index().await;
+                }
+                let semantic = oci.semantic_status.read().clone();
+
+                let docs_total = load_search_state(&root)
+                    .ok()
+                    .and_then(|s| s.map(|s| s.docs.len()))
+                    .unwrap_or(0);
+                if req.json {
+                    return Ok(json_result(
+                        "index",
+                        serde_json::json!({
+                            "files": report.total_files,
+                            "symbols": docs_total,
+                            "parsed": report.parsed_files,
+                            "skipped": report.skipped_files,
+                            "unchanged": report.unchanged_files,
+                            "reused": report.reused_files,
+                            "removed": report.removed_files,
+                            "root": root.display().to_string(),
+                            "semantic": semantic.as_str(),
+                        }),
+                    ));
                 }
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Index built successfully:\n- {} files\n- {} symbols\n- {} parsed\n- {} skipped ({} touched but unchanged)\n- {} reused from branch snapshot\n- {} removed\n- Semantic index: {}",
+                    report.total_files,
+                    docs_total,
+                    report.parsed_files,
+                    report.skipped_files,
+                    report.unchanged_files,
+                    report.reused_files,
+                    report.removed_files,
+                    semantic_line(&semantic)
+                ))]))
             }
             "status" => {
                 let stats = oci.stats();
+                let duplication = DuplicationPressure::load(&root).ok().flatten();
+                if req.json {
+                    return Ok(json_result(
+                        "stats",
+                        serde_json::json!({
+                            "files": stats.file_count,
+                            "symbols": stats.symbol_count,
+                            "call_edges": stats.call_edge_count,
+                            "topology_nodes": stats.topology_node_count,
+                            "duplication": duplication,
+                            "files_parsed": stats.files_parsed,
+                            "files_skipped": stats.files_skipped,
+                            "semantic_status": stats.semantic_status.as_str(),
+                            "bm25_index": stats.has_bm25_index,
+                            "stack": stats.stack,
+                            "embedding_drift": stats.embedding_drift,
+                        }),
+                    ));
+                }
+                let drift = match &stats.embedding_drift {
+                    Some(report) if report.has_drift() => format!(
+                        "\n- Embedding drift: {} of {} vectors not from {} ({}); run index op=reembed",
+                        report.stale_vectors,
+                        report.vectors_by_model.values().sum::<usize>(),
+                        report.current_model,
+                        report
+                            .vectors_by_model
+                            .iter()
+                            .map(|(model, count)| format!("{model}: {count}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    Some(report) => format!("\n- Embedding model: {}", report.current_model),
+                    None => String::new(),
+                };
+                let stack = match &stats.stack {
+                    Some(stack) => format!("\n- Stack: {}", stack.summary()),
+                    None => String::new(),
+                };
+                let duplication = match &duplication {
+                    Some(pressure) => {
+                        let mut line = format!("\n- Duplication pressure: {}", pressure.summary());
+                        for cluster in &pressure.clusters {
+                            line.push_str(&format!(
+                                "\n  - {} symbols, up to {:.2}: {}",
+                                cluster.size,
+                                cluster.max_similarity,
+                                cluster.symbols.join(", ")
+                            ));
+                        }
+                        line
+                    }
+                    None => String::new(),
+                };
                 Ok(CallToolResult::success(vec![Content::text(format!(
-                    "Index Status:\n- Files: {}\n- Symbols: {}\n- Call edges: {}\n- Topology nodes: {}\n- Semantic index: {}\n- BM25 index: {}",
+                    "Index Status:\n- Files: {}\n- Symbols: {}\n- Call edges: {}\n- Topology nodes: {}\n- Files parsed: {}, skipped as unchanged: {}\n- Semantic index: {}\n- BM25 index: {}{}{}{}",
                     stats.file_count,
                     stats.symbol_count,
                     stats.call_edge_count,
                     stats.topology_node_count,
-                    if stats.has_semantic_index {
-                        "ready"
-                    } else {
-                        "not built"
-                    },
+                    stats.files_parsed,
+                    stats.files_skipped,
+                    semantic_line(&stats.semantic_status),
                     if stats.has_bm25_index {
                         "ready"
                     } else {
                         "not built"
+                    },
+                    stack,
+                    duplication,
+                    drift
+                ))]))
+            }
+            #[cfg(feature = "semantic")]
+            "reembed" => {
+                let Some(index) = oci.semantic_index.get() else {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Semantic index {}",
+                        oci.semantic_status.read().as_str()
+                    ))]));
+                };
+                let report = index.drift_report();
+                if !report.has_drift() {
+                    if req.json {
+                        return Ok(json_result(
+                            "reembed",
+                            serde_json::json!({ "drift": report, "started": false }),
+                        ));
+                    }
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No embedding drift: all vectors use {}",
+                        report.current_model
+                    ))]));
+                }
+
+                // Re-embedding is CPU bound; run it off the async runtime.
+                let oci = Arc::clone(oci);
+                tokio::task::spawn_blocking(move || {
+                    let Some(index) = oci.semantic_index.get() else {
+                        return;
+                    };
+                    match index.reembed(&oci) {
+                        Ok(count) => tracing::info!("Re-embedded {} vectors", count),
+                        Err(e) => tracing::warn!("Re-embedding failed: {}", e),
                     }
+                });
+
+                if req.json {
+                    return Ok(json_result(
+                        "reembed",
+                        serde_json::json!({ "drift": report, "started": true }),
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Re-embedding {} stale vectors with {} in the background",
+                    report.stale_vectors, report.current_model
                 ))]))
             }
+            #[cfg(not(feature = "semantic"))]
+            "reembed" => Ok(CallToolResult::error(vec![Content::text(
+                "Re-embedding requires the 'semantic' feature",
+            )])),
             _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Unknown operation: {}. Valid: build, rebuild, status",
+                "Unknown operation: {}. Valid: build, rebuild, status, reembed",
                 req.op
             ))])),
         }
     }
 
     #[tool(
-        description = "Find symbols by name. Returns definitions with locations and signatures."
+        description = "Find symbols by name and/or attribute. Returns definitions with locations and signatures."
     )]
     async fn find_symbol(
         &self,
@@ -231,14 +989,46 @@ impl OciServer {
         let oci = &state.oci_state;
 
         let max = req.max_results.unwrap_or(10);
+        let mut queries = Vec::with_capacity(req.attributes.len());
+        for text in &req.attributes {
+            match Attribute::query(text) {
+                Some(query) => queries.push(query),
+                None => {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "Invalid attribute: {}",
+                        text
+                    ))]));
+                }
+            }
+        }
+        if req.name.is_empty() && queries.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "Give a symbol name, attributes, or both",
+            )]));
+        }
+        if req.json {
+            return Ok(json_result(
+                "symbols",
+                symbols_json(oci, &state.workspace_root, &req, &queries, max),
+            ));
+        }
+        let deleted = if req.include_deleted {
+            deletion_lines(&state.workspace_root, &req.name)
+        } else {
+            String::new()
+        };
 
         if req.scoped {
             // Search by scoped name
-            let key = oci.interner.get(&req.name);
+            let key = oci.lookup(&req.name);
             if let Some(key) = key {
-                if let Some(sym) = oci.get_symbol(key) {
-                    let name = oci.resolve(sym.name);
+                if let Some(sym) = oci.get_symbol(key).filter(|s| s.has_attributes(&queries)) {
+                    let name = sym.display_name(oci);
                     let scoped = oci.resolve(sym.scoped_name);
+                    // An exact lookup is a selection, like `feedback`
+                    if let Err(e) = record_selection(&state.workspace_root, scoped) {
+                        tracing::warn!("Failed to record selection of {}: {}", scoped, e);
+                    }
                     let sig = sym
                         .signature
                         .as_ref()
@@ -252,28 +1042,50 @@ impl OciServer {
                         .unwrap_or_default();
 
                     return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Found: {} ({})\n  Kind: {:?}\n  Location: {}:{}\n  Signature: {}\n  Visibility: {:?}",
+                        "Found: {} ({})\n  Kind: {:?}\n  Location: {}:{}\n  Signature: {}\n  Visibility: {:?}{}{}",
                         scoped,
                         name,
                         sym.kind,
                         sym.location.file.display(),
                         sym.location.start_line,
                         sig,
-                        sym.visibility
+                        sym.visibility,
+                        deprecation_line(&sym),
+                        deleted
                     ))]));
                 }
             }
             Ok(CallToolResult::success(vec![Content::text(format!(
-                "No symbol found with scoped name: {}",
-                req.name
+                "No symbol found with scoped name: {}{}{}",
+                req.name,
+                rename_lines(&state.workspace_root, &req.name),
+                deleted
             ))]))
         } else {
             // Search by simple name
-            let symbols = oci.find_by_name(&req.name);
+            let mut symbols = match &req.context_file {
+                _ if req.name.is_empty() => oci.find_by_attributes(&queries),
+                Some(file) => oci.find_by_name_near(&req.name, std::path::Path::new(file)),
+                None => oci.find_by_name(&req.name),
+            };
+            symbols.retain(|s| s.has_attributes(&queries));
             if symbols.is_empty() {
+                let query = match (req.name.is_empty(), req.attributes.is_empty()) {
+                    (_, true) => format!("name: {}", req.name),
+                    (true, false) => format!("attributes: {}", req.attributes.join(", ")),
+                    (false, false) => {
+                        format!(
+                            "name: {} and attributes: {}",
+                            req.name,
+                            req.attributes.join(", ")
+                        )
+                    }
+                };
                 return Ok(CallToolResult::success(vec![Content::text(format!(
-                    "No symbols found with name: {}",
-                    req.name
+                    "No symbols found with {}{}{}",
+                    query,
+                    rename_lines(&state.workspace_root, &req.name),
+                    deleted
                 ))]));
             }
 
@@ -291,49 +1103,252 @@ impl OciServer {
                         )
                     })
                     .unwrap_or_default();
+                let import = oci
+                    .canonical_path(sym)
+                    .map(|path| format!("\n  path: {}", path))
+                    .unwrap_or_default();
+                let attributes = if queries.is_empty() {
+                    String::new()
+                } else {
+                    let matched: Vec<String> = sym
+                        .attributes
+                        .iter()
+                        .filter(|a| queries.iter().any(|q| a.matches(q)))
+                        .map(ToString::to_string)
+                        .collect();
+                    format!("\n  Attributes: {}", matched.join(", "))
+                };
                 output.push_str(&format!(
-                    "- {} [{:?}]\n  {}:{}\n  {}\n\n",
+                    "- {} [{:?}]\n  {}:{}{}\n  {}{}{}\n\n",
                     scoped,
                     sym.kind,
                     sym.location.file.display(),
                     sym.location.start_line,
-                    sig
+                    import,
+                    sig,
+                    attributes,
+                    deprecation_line(sym)
                 ));
             }
+            output.push_str(deleted.trim_start());
 
             Ok(CallToolResult::success(vec![Content::text(output)]))
         }
     }
 
-    #[tool(description = "Query the call graph. Find callers or callees of a symbol.")]
-    async fn call_graph(
+    #[tool(
+        description = "Complete a symbol name prefix. Returns matching names in sorted order with their definition counts and kinds, for building exact find_symbol or call_graph queries."
+    )]
+    async fn complete_symbol(
         &self,
-        Parameters(req): Parameters<CallGraphRequest>,
+        Parameters(req): Parameters<CompleteSymbolRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let state = self.state.read().await;
+        if req.prefix.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "prefix parameter required",
+            )]));
+        }
+        let state = self.state.read().await;
+        let completions = state
+            .oci_state
+            .complete_symbol(&req.prefix, req.limit.unwrap_or(DEFAULT_COMPLETION_LIMIT));
+        if req.json {
+            return Ok(json_result(
+                "completions",
+                serde_json::json!({ "prefix": req.prefix, "results": completions }),
+            ));
+        }
+        if completions.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No symbols start with: {}",
+                req.prefix
+            ))]));
+        }
+        let mut output = String::new();
+        for c in &completions {
+            output.push_str(&format!("{} [{}]", c.name, c.kinds.join("/")));
+            if c.definitions > 1 {
+                output.push_str(&format!(" ({} definitions)", c.definitions));
+            }
+            output.push('\n');
+        }
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "Query the call graph. Find callers or callees of a symbol, transitively with depth above 1."
+    )]
+    async fn call_graph(
+        &self,
+        Parameters(req): Parameters<CallGraphRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let state = self.state.read().await;
         let oci = &state.oci_state;
+        let grouping = match req.group_by.as_deref().map(str::parse::<CallGrouping>) {
+            Some(Err(e)) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+            Some(Ok(grouping)) => Some(grouping),
+            None => None,
+        };
+        let side = match req.op.as_str() {
+            "callers" => Some(CallSide::Caller),
+            "callees" => Some(CallSide::Callee),
+            _ => None,
+        };
+        if let Some(side) = side.filter(|_| grouping.is_none() && req.depth.unwrap_or(1) > 1) {
+            let depth = req.depth.unwrap_or(1);
+            let tree = call_tree(oci, &req.name, side, depth, req.exclude_tests);
+            if req.json {
+                #[derive(Serialize)]
+                struct Tree<'a> {
+                    direction: &'a str,
+                    #[serde(flatten)]
+                    tree: &'a crate::call_tree::CallTree,
+                }
+                let direction = &req.op;
+                return Ok(json_result(
+                    "calltree",
+                    Tree {
+                        direction,
+                        tree: &tree,
+                    },
+                ));
+            }
+            if tree.children.is_empty() {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "No {} found for: {}",
+                    req.op, req.name
+                ))]));
+            }
+            let output = format!(
+                "{} of '{}' within {} hops ({} symbols):\n\n{}",
+                if side == CallSide::Caller {
+                    "Transitive callers"
+                } else {
+                    "Transitive callees"
+                },
+                req.name,
+                depth,
+                tree.symbols,
+                tree.render()
+            );
+            return Ok(CallToolResult::success(vec![Content::text(output)]));
+        }
 
         match req.op.as_str() {
             "callers" => {
-                let callers = oci.find_callers(&req.name);
+                // A scoped name asks for the calls bound to that definition
+                let bound = req
+                    .name
+                    .contains("::")
+                    .then(|| oci.lookup(&req.name))
+                    .flatten()
+                    .filter(|key| oci.symbols.contains_key(key));
+                let mut callers = match bound {
+                    Some(key) => oci.find_resolved_callers(key),
+                    None => oci.find_callers(&req.name),
+                };
+                if req.json {
+                    return Ok(json_result(
+                        "calls",
+                        calls_json(
+                            oci,
+                            &req,
+                            callers,
+                            CallSide::Caller,
+                            grouping,
+                            bound.is_none(),
+                        ),
+                    ));
+                }
                 if callers.is_empty() {
                     return Ok(CallToolResult::success(vec![Content::text(format!(
                         "No callers found for: {}",
                         req.name
                     ))]));
                 }
+                let counts = TestSplit::count(callers.iter().map(|c| c.is_test_context));
+                if req.exclude_tests {
+                    callers.retain(|c| !c.is_test_context);
+                }
+                let split = format!(
+                    "{} from production code, {} from tests{}",
+                    counts.production,
+                    counts.tests,
+                    if req.exclude_tests {
+                        " (tests excluded)"
+                    } else {
+                        ""
+                    }
+                );
+                if let Some(grouping) = grouping {
+                    let groups = group_calls(oci, &callers, grouping, CallSide::Caller);
+                    let output = format!(
+                        "Found {} call sites for '{}' in {} {}s ({}):\n\n{}",
+                        callers.len(),
+                        req.name,
+                        groups.len(),
+                        grouping.as_str(),
+                        split,
+                        format_call_groups(&groups)
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(output)]));
+                }
+
+                let mut candidates = match bound {
+                    Some(_) => Vec::new(),
+                    None => resolve_callers(oci, &req.name),
+                };
+                if candidates.len() > 1 {
+                    if req.exclude_tests {
+                        for c in &mut candidates {
+                            c.callers.retain(|r| !r.is_test_context);
+                        }
+                        candidates.sort_by_key(|c| std::cmp::Reverse(c.callers.len()));
+                    }
+                    let resolved: usize = candidates.iter().map(|c| c.callers.len()).sum();
+                    let mut output = format!(
+                        "Found {} call sites for '{}' across {} definitions ({}), split by likely target:\n\n{}",
+                        resolved,
+                        req.name,
+                        candidates.len(),
+                        split,
+                        format_caller_candidates(&candidates)
+                    );
+                    if resolved < callers.len() {
+                        output.push_str(&format!(
+                            "\n{} more call sites call '{}' on types outside the workspace\n",
+                            callers.len() - resolved,
+                            req.name
+                        ));
+                    }
+                    return Ok(CallToolResult::success(vec![Content::text(output)]));
+                }
 
-                let mut output =
-                    format!("Found {} call sites for '{}':\n\n", callers.len(), req.name);
+                let mut output = format!(
+                    "Found {} call sites for '{}' ({}):\n\n",
+                    callers.len(),
+                    req.name,
+                    split
+                );
+                let mut snippets = CallSnippets::default();
                 for call in &callers {
                     let caller_name = oci.resolve(call.caller);
                     output.push_str(&format!(
-                        "- {} calls {} at {}:{}\n",
+                        "- {} calls {} at {}:{}{}\n",
                         caller_name,
-                        call.callee_name,
+                        call.resolved_callee
+                            .map_or(call.callee_name.as_str(), |key| oci.resolve(key)),
                         call.location.file.display(),
-                        call.location.start_line
+                        call.location.start_line,
+                        if call.is_test_context { " [test]" } else { "" }
                     ));
+                    if req.include_snippets {
+                        if let Some(snippet) =
+                            call_snippet(oci, &mut snippets, &call.location).await
+                        {
+                            output.push_str(&snippet);
+                        }
+                    }
                 }
 
                 Ok(CallToolResult::success(vec![Content::text(output)]))
@@ -341,6 +1356,16 @@ impl OciServer {
             "callees" => {
                 // Find the symbol first
                 let symbols = oci.find_by_name(&req.name);
+                if req.json {
+                    let callees = symbols
+                        .iter()
+                        .flat_map(|sym| oci.find_callees(sym.scoped_name))
+                        .collect();
+                    return Ok(json_result(
+                        "calls",
+                        calls_json(oci, &req, callees, CallSide::Callee, grouping, false),
+                    ));
+                }
                 if symbols.is_empty() {
                     return Ok(CallToolResult::success(vec![Content::text(format!(
                         "No symbol found: {}",
@@ -348,7 +1373,25 @@ impl OciServer {
                     ))]));
                 }
 
+                if let Some(grouping) = grouping {
+                    let callees: Vec<_> = symbols
+                        .iter()
+                        .flat_map(|sym| oci.find_callees(sym.scoped_name))
+                        .collect();
+                    let groups = group_calls(oci, &callees, grouping, CallSide::Callee);
+                    let output = format!(
+                        "'{}' makes {} calls into {} {}s:\n\n{}",
+                        req.name,
+                        callees.len(),
+                        groups.len(),
+                        grouping.as_str(),
+                        format_call_groups(&groups)
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(output)]));
+                }
+
                 let mut output = String::new();
+                let mut snippets = CallSnippets::default();
                 for sym in &symbols {
                     let callees = oci.find_callees(sym.scoped_name);
                     let scoped = oci.resolve(sym.scoped_name);
@@ -363,9 +1406,23 @@ impl OciServer {
                         ));
                         for call in &callees {
                             output.push_str(&format!(
-                                "  - {} at line {}\n",
-                                call.callee_name, call.location.start_line
+                                "  - {} at line {}{}\n",
+                                call.resolved_callee
+                                    .map_or(call.callee_name.as_str(), |key| oci.resolve(key)),
+                                call.location.start_line,
+                                if call.is_approximate {
+                                    " (approximate, dyn dispatch)"
+                                } else {
+                                    ""
+                                }
                             ));
+                            if req.include_snippets {
+                                if let Some(snippet) =
+                                    call_snippet(oci, &mut snippets, &call.location).await
+                                {
+                                    output.push_str(&snippet);
+                                }
+                            }
                         }
                     }
                     output.push('\n');
@@ -373,153 +1430,1524 @@ impl OciServer {
 
                 Ok(CallToolResult::success(vec![Content::text(output)]))
             }
+            #[cfg(feature = "analysis")]
+            "binary" => {
+                let targets = match crate::analysis::discover_binaries(&state.workspace_root) {
+                    Ok(targets) => targets,
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Failed to read Cargo manifests: {}",
+                            e
+                        ))]));
+                    }
+                };
+                let Some(target) = targets.iter().find(|t| t.name == req.name) else {
+                    let available: Vec<&str> = targets.iter().map(|t| t.name.as_str()).collect();
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Unknown binary: {}. Available: {}",
+                        req.name,
+                        available.join(", ")
+                    ))]));
+                };
+
+                let reach = crate::analysis::BinaryAnalyzer::new().reachability(oci, target);
+                let max_depth = req.depth.unwrap_or(u32::MAX);
+                if req.json {
+                    let symbols: Vec<crate::output::ReachableResult> = reach
+                        .reachable
+                        .iter()
+                        .filter(|(_, depth)| *depth <= max_depth)
+                        .filter_map(|&(scoped, depth)| {
+                            let symbol = SymbolResult::new(oci, &oci.get_symbol(scoped)?);
+                            Some(crate::output::ReachableResult { symbol, depth })
+                        })
+                        .collect();
+                    return Ok(json_result(
+                        "binarygraph",
+                        serde_json::json!({
+                            "binary": target.name,
+                            "entry": reach.entry.map(|e| oci.resolve(e).to_string()),
+                            "symbols": symbols,
+                        }),
+                    ));
+                }
+                let mut output = format!(
+                    "Binary '{}' ({}) reaches {} symbols from {}:\n\n",
+                    target.name,
+                    target.kind.as_str(),
+                    reach.reachable.len(),
+                    target.path.display()
+                );
+                for &(scoped, depth) in reach.reachable.iter().filter(|(_, d)| *d <= max_depth) {
+                    output.push_str(&format!("- [{}] {}\n", depth, oci.resolve(scoped)));
+                }
+
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
             _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Unknown operation: {}. Valid: callers, callees",
+                "Unknown operation: {}. Valid: callers, callees, binary",
                 req.op
             ))])),
         }
     }
 
-    #[tool(description = "Run analysis: dead_code, coverage, churn, hotspots")]
-    async fn analyze(
+    #[tool(
+        description = "Find every usage of a type, trait, or function: calls, struct literals, type annotations, trait bounds, and impl blocks (find_callers covers calls only)"
+    )]
+    async fn find_references(
         &self,
-        Parameters(req): Parameters<AnalysisRequest>,
+        Parameters(req): Parameters<ReferencesRequest>,
     ) -> Result<CallToolResult, McpError> {
+        if let Err(e) = self.ensure_indexed().await {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to index workspace: {}",
+                e
+            ))]));
+        }
         let state = self.state.read().await;
-        let _oci = &state.oci_state;
+        let oci = &state.oci_state;
+        let mut refs = oci.find_references(&req.name);
+        let counts = TestSplit::count(refs.iter().map(|r| r.is_test_context));
+        if req.exclude_tests {
+            refs.retain(|r| !r.is_test_context);
+        }
+        if req.json {
+            let results: Vec<ReferenceResult> =
+                refs.iter().map(|r| ReferenceResult::new(oci, r)).collect();
+            return Ok(json_result(
+                "references",
+                serde_json::json!({
+                    "name": req.name,
+                    "counts": counts,
+                    "results": results,
+                }),
+            ));
+        }
+        if refs.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No references found for: {}",
+                req.name
+            ))]));
+        }
+
+        let mut output = format!(
+            "Found {} references to '{}' ({} from production code, {} from tests{}):\n\n",
+            refs.len(),
+            req.name,
+            counts.production,
+            counts.tests,
+            if req.exclude_tests {
+                ", tests excluded"
+            } else {
+                ""
+            }
+        );
+        for r in &refs {
+            output.push_str(&format!(
+                "- {} in {} at {}:{}{}\n",
+                r.kind.as_str(),
+                r.from.map_or("<top level>", |key| oci.resolve(key)),
+                r.location.file.display(),
+                r.location.start_line,
+                if r.is_test_context { " [test]" } else { "" }
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "List the types implementing a trait (impl Trait for Type blocks, with their methods), or with method=true which trait a method comes from"
+    )]
+    async fn implementations(
+        &self,
+        Parameters(req): Parameters<ImplementationsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Err(e) = self.ensure_indexed().await {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to index workspace: {}",
+                e
+            ))]));
+        }
+        let state = self.state.read().await;
+        let oci = &state.oci_state;
+
+        if req.method {
+            let Some(origins) = trait_origins(oci, &req.name) else {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "No method found: {}",
+                    req.name
+                ))]));
+            };
+            if req.json {
+                return Ok(json_result(
+                    "traitorigins",
+                    serde_json::json!({
+                        "method": req.name,
+                        "origins": origins,
+                    }),
+                ));
+            }
+            if origins.is_empty() {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{} is not a trait method",
+                    req.name
+                ))]));
+            }
+            let output: String = origins
+                .iter()
+                .map(|o| {
+                    format!(
+                        "- {} implements {} for {} at {}:{}\n",
+                        o.method,
+                        o.trait_symbol.as_deref().unwrap_or(&o.trait_name),
+                        o.implementation.type_name,
+                        o.implementation.file.display(),
+                        o.implementation.line
+                    )
+                })
+                .collect();
+            return Ok(CallToolResult::success(vec![Content::text(output)]));
+        }
+
+        let Some(found) = implementations(oci, &req.name) else {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No trait defined or implemented: {}",
+                req.name
+            ))]));
+        };
+        if req.json {
+            return Ok(json_result("implementations", found));
+        }
+        let mut output = format!(
+            "Trait {}: {} implementations{}\n\n",
+            found.trait_name,
+            found.implementations.len(),
+            match found.definitions.as_slice() {
+                [] => " (defined outside the workspace)".to_string(),
+                defs => format!(", defined as {}", defs.join(", ")),
+            }
+        );
+        for imp in &found.implementations {
+            output.push_str(&format!(
+                "- {} at {}:{}{}\n",
+                imp.type_symbol.as_deref().unwrap_or(&imp.type_name),
+                imp.file.display(),
+                imp.line,
+                if imp.is_test { " [test]" } else { "" }
+            ));
+            for method in &imp.methods {
+                output.push_str(&format!("  - {}\n", method));
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "Find where a config key or environment variable is defined (TOML/YAML/JSON) and read in code (env accessors, string literals)"
+    )]
+    async fn config_refs(
+        &self,
+        Parameters(req): Parameters<ConfigRefsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let state = self.state.read().await;
+        let refs = match find_config_refs(&state.oci_state, &req.key) {
+            Ok(refs) => refs,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to scan config files: {}",
+                    e
+                ))]));
+            }
+        };
+        if req.json {
+            return Ok(json_result("configrefs", refs));
+        }
+        if refs.definitions.is_empty() && refs.readers.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No definitions or readers found for: {}",
+                refs.key
+            ))]));
+        }
+
+        let mut output = format!(
+            "Config key '{}': {} definitions, {} readers\n\n",
+            refs.key,
+            refs.definitions.len(),
+            refs.readers.len()
+        );
+        for d in &refs.definitions {
+            output.push_str(&format!(
+                "- defined {} at {}:{}",
+                d.key,
+                d.file.display(),
+                d.line
+            ));
+            if let Some(value) = &d.value {
+                output.push_str(&format!(" = {}", value));
+            }
+            output.push('\n');
+        }
+        for r in &refs.readers {
+            output.push_str(&format!(
+                "- {} \"{}\" in {} at {}:{}\n",
+                r.access.as_str(),
+                r.matched,
+                r.symbol.as_deref().unwrap_or("<top level>"),
+                r.file.display(),
+                r.line
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
 
+    #[tool(
+        description = "List every definition, call site, import, and re-export a rename or signature change of a symbol would touch, grouped by file"
+    )]
+    async fn impact(
+        &self,
+        Parameters(req): Parameters<ImpactRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Err(e) = self.ensure_indexed().await {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to index workspace: {}",
+                e
+            ))]));
+        }
+        let state = self.state.read().await;
+        let Some(impact) = rename_impact(&state.oci_state, &req.symbol) else {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No symbol found: {}",
+                req.symbol
+            ))]));
+        };
+        if req.json {
+            return Ok(json_result("renameimpact", impact));
+        }
+
+        let mut output = format!(
+            "Changing {}: {} definitions, {} calls, {} imports, {} re-exports in {} files\n",
+            impact.name,
+            impact.definitions.len(),
+            impact.calls,
+            impact.imports,
+            impact.reexports,
+            impact.files.len()
+        );
+        for file in &impact.files {
+            output.push_str(&format!("\n{}:\n", file.file.display()));
+            for site in &file.sites {
+                output.push_str(&format!(
+                    "- {}: {} {}{}\n",
+                    site.line,
+                    site.kind.as_str(),
+                    site.detail,
+                    if site.is_test { " [test]" } else { "" }
+                ));
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "Show a SQL table's current columns (replaying migrations in path order) and the code whose queries name it"
+    )]
+    async fn sql_refs(
+        &self,
+        Parameters(req): Parameters<SqlRefsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let state = self.state.read().await;
+        let Some(refs) = find_sql_refs(&state.oci_state, &req.name) else {
+            let message = format!("No table or column in the SQL schema: {}", req.name);
+            // Like `omni sql-refs --json`, a JSON caller gets an error
+            return Ok(if req.json {
+                CallToolResult::error(vec![Content::text(message)])
+            } else {
+                CallToolResult::success(vec![Content::text(message)])
+            });
+        };
+        if req.json {
+            return Ok(json_result("sqlrefs", refs));
+        }
+
+        let table = &refs.table;
+        let mut output = format!(
+            "Table '{}' ({}:{}), {} queries\n\n",
+            table.name,
+            table.file.display(),
+            table.line,
+            refs.queries.len()
+        );
+        for c in &table.columns {
+            if refs.column.as_ref().is_some_and(|name| *name != c.name) {
+                continue;
+            }
+            output.push_str(&format!(
+                "- column {} {} at {}:{}\n",
+                c.name,
+                c.data_type.as_deref().unwrap_or("-"),
+                c.file.display(),
+                c.line
+            ));
+        }
+        for q in &refs.queries {
+            output.push_str(&format!(
+                "- query in {} at {}:{}: {}\n",
+                q.symbol.as_deref().unwrap_or("<top level>"),
+                q.file.display(),
+                q.line,
+                q.query
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "Run analysis: dead_code (unreachable symbols with why), coverage (untested public functions and least covered functions from an llvm-cov or tarpaulin report), churn (files and symbols by commits from git log), hotspots (files and functions by risk: churn x cyclomatic complexity x untested share x PageRank centrality, with coverage_file optional), clones (functions with duplicated or near-duplicated bodies), co_changes (files and symbols that historically change in the same commits as target), or any custom analysis registered by the server's embedding crate"
+    )]
+    async fn analyze(
+        &self,
+        Parameters(req): Parameters<AnalysisRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        // Built-in analyses take precedence over custom ones
+        #[cfg(feature = "analysis")]
+        let custom = crate::analysis::custom::find(&req.analysis)
+            .filter(|_| !ANALYSES.contains(&req.analysis.as_str()));
+        #[cfg(feature = "analysis")]
+        if (ANALYSES.contains(&req.analysis.as_str()) || custom.is_some())
+            && let Err(e) = self.ensure_indexed().await
+        {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to index workspace: {}",
+                e
+            ))]));
+        }
+        #[cfg(feature = "analysis")]
+        let budget = request_budget(req.timeout_ms, req.max_work);
+        #[cfg(feature = "analysis")]
+        if let Some(analysis) = custom {
+            let state = self.state.read().await;
+            let findings = match analysis.run_within(&state.oci_state, &budget) {
+                Ok(findings) => findings,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "{} failed: {}",
+                        req.analysis, e
+                    ))]));
+                }
+            };
+            let truncated = budget.was_cut_short();
+            if req.json {
+                return Ok(json_result(
+                    "custom",
+                    serde_json::json!({
+                        "analysis": req.analysis,
+                        "truncated": truncated,
+                        "findings": findings,
+                    }),
+                ));
+            }
+            let output = format!(
+                "{}: {} findings\n{}\n{}",
+                req.analysis,
+                findings.len(),
+                truncated_note(truncated),
+                crate::analysis::problems::render_problems(&findings)
+            );
+            return Ok(CallToolResult::success(vec![Content::text(output)]));
+        }
         match req.analysis.as_str() {
+            #[cfg(feature = "analysis")]
             "dead_code" => {
-                // TODO: Integrate with dead_code analysis module
-                Ok(CallToolResult::success(vec![Content::text(
-                    "Dead code analysis not yet implemented. Will analyze reachability from entry points.",
-                )]))
+                let state = self.state.read().await;
+                let oci = &state.oci_state;
+                let report = crate::analysis::DeadCodeAnalyzer::new().analyze_within(oci, &budget);
+                let truncated = budget.was_cut_short();
+                let mut symbols = Vec::new();
+                for &name in &report.dead_symbols {
+                    if let Some(def) = oci.symbols.get(&name) {
+                        symbols.push((name, def.value().clone()));
+                    }
+                }
+                symbols.sort_by(|a, b| {
+                    a.1.location
+                        .file
+                        .cmp(&b.1.location.file)
+                        .then(a.1.location.start_line.cmp(&b.1.location.start_line))
+                });
+                if req.json {
+                    let results: Vec<crate::output::DeadResult> = symbols
+                        .iter()
+                        .take(MAX_ANALYSIS_RESULTS)
+                        .map(|(name, def)| {
+                            crate::output::DeadResult::new(
+                                oci,
+                                SymbolResult::new(oci, def),
+                                report.explanations.get(name),
+                            )
+                        })
+                        .collect();
+                    return Ok(json_result(
+                        "deadcode",
+                        serde_json::json!({
+                            "dead_count": symbols.len(),
+                            "counts": TestSplit::count(symbols.iter().map(|(_, def)| def.is_test_context)),
+                            "truncated": truncated,
+                            "symbols": results,
+                        }),
+                    ));
+                }
+
+                let mut output = format!(
+                    "Found {} potentially dead symbols ({} entry points)\n{}\n",
+                    report.dead_symbols.len(),
+                    report.entry_points.len(),
+                    truncated_note(truncated)
+                );
+                for (name, def) in symbols.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} ({:?}) at {}:{}\n",
+                        oci.resolve(*name),
+                        def.kind,
+                        def.location
+                            .file
+                            .strip_prefix(&state.workspace_root)
+                            .unwrap_or(&def.location.file)
+                            .display(),
+                        def.location.start_line + 1
+                    ));
+                    if let Some(explanation) = report.explanations.get(name) {
+                        for step in explanation.steps(oci) {
+                            output.push_str(&format!("    {}\n", step));
+                        }
+                    }
+                }
+                if symbols.len() > MAX_ANALYSIS_RESULTS {
+                    output.push_str(&format!(
+                        "... and {} more\n",
+                        symbols.len() - MAX_ANALYSIS_RESULTS
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
             }
+            #[cfg(feature = "analysis")]
             "coverage" => {
-                match &req.coverage_file {
-                    Some(_path) => {
-                        // TODO: Integrate with coverage analysis module
-                        Ok(CallToolResult::success(vec![Content::text(
-                            "Coverage analysis not yet implemented. Will parse LLVM/tarpaulin JSON.",
-                        )]))
-                    }
-                    None => Ok(CallToolResult::error(vec![Content::text(
+                let state = self.state.read().await;
+                use crate::analysis::CoverageAnalyzer;
+
+                let Some(path) = &req.coverage_file else {
+                    return Ok(CallToolResult::error(vec![Content::text(
                         "coverage_file parameter required for coverage analysis",
-                    )])),
+                    )]));
+                };
+                let path = state.workspace_root.join(path);
+                let coverage = match CoverageAnalyzer::load(&path) {
+                    Ok(coverage) => coverage,
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Failed to load coverage from {}: {}",
+                            path.display(),
+                            e
+                        ))]));
+                    }
+                };
+                let report = CoverageAnalyzer::report_within(
+                    &state.oci_state,
+                    &coverage,
+                    &state.workspace_root,
+                    &budget,
+                );
+                let truncated = budget.was_cut_short();
+                if req.json {
+                    return Ok(json_result("coverage", with_truncated(&report, truncated)));
+                }
+
+                let mut output = format!(
+                    "Coverage: {} functions, {}/{} lines ({:.1}%)\n{}\nUntested public functions ({}):\n",
+                    report.functions,
+                    report.lines_covered,
+                    report.lines_total,
+                    if report.lines_total == 0 {
+                        0.0
+                    } else {
+                        report.lines_covered as f32 * 100.0 / report.lines_total as f32
+                    },
+                    truncated_note(truncated),
+                    report.untested_public.len()
+                );
+                for f in report.untested_public.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} at {}:{} ({} lines)\n",
+                        f.symbol,
+                        f.file,
+                        f.line + 1,
+                        f.lines_total
+                    ));
+                }
+                if report.untested_public.len() > MAX_ANALYSIS_RESULTS {
+                    output.push_str(&format!(
+                        "... and {} more\n",
+                        report.untested_public.len() - MAX_ANALYSIS_RESULTS
+                    ));
+                }
+                output.push_str("\nLeast covered:\n");
+                for f in report.least_covered.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} at {}:{}: {:.1}% ({}/{} lines",
+                        f.symbol,
+                        f.file,
+                        f.line + 1,
+                        f.coverage * 100.0,
+                        f.lines_covered,
+                        f.lines_total
+                    ));
+                    if f.branches_total > 0 {
+                        output.push_str(&format!(
+                            ", {}/{} branches",
+                            f.branches_covered, f.branches_total
+                        ));
+                    }
+                    output.push_str(")\n");
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(feature = "analysis")]
+            "churn" => {
+                let days = req.days.unwrap_or(crate::analysis::hotspots::DEFAULT_DAYS);
+                let (oci, root) = {
+                    let state = self.state.read().await;
+                    (state.oci_state.clone(), state.workspace_root.clone())
+                };
+                // Shells out to git; keep it off the async runtime.
+                let (report, truncated) = tokio::task::spawn_blocking(move || {
+                    let report = crate::analysis::ChurnAnalyzer::analyze_index_within(
+                        &oci, &root, days, &budget,
+                    );
+                    (report, budget.was_cut_short())
+                })
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                let mut report = match report {
+                    Ok(report) => report,
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Churn analysis failed: {}",
+                            e
+                        ))]));
+                    }
+                };
+                if req.json {
+                    let files_changed = report.file_churn.len();
+                    report.file_churn.truncate(MAX_ANALYSIS_RESULTS);
+                    report.symbols.truncate(MAX_ANALYSIS_RESULTS);
+                    let mut data = with_truncated(&report, truncated);
+                    data["files_changed"] = files_changed.into();
+                    return Ok(json_result("churn", data));
+                }
+
+                let files = report.file_churn;
+                let mut output = format!(
+                    "{} files changed in the last {} days\n{}\n",
+                    files.len(),
+                    days,
+                    truncated_note(truncated)
+                );
+                for f in files.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {}: {} commits, +{} -{} lines, {} authors (last {})\n",
+                        f.path.display(),
+                        f.commits,
+                        f.lines_added,
+                        f.lines_removed,
+                        f.authors.len(),
+                        f.last_modified
+                    ));
+                }
+                if !report.symbols.is_empty() {
+                    output.push_str("\nMost changed symbols:\n");
+                }
+                for s in report.symbols.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} ({}) at {}:{}: {} commits, {} lines changed\n",
+                        s.symbol,
+                        s.kind,
+                        s.file.display(),
+                        s.line + 1,
+                        s.commits,
+                        s.lines_changed
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(feature = "analysis")]
+            "hotspots" => {
+                let state = self.state.read().await;
+                let mut analyzer = crate::analysis::HotspotAnalyzer::new();
+                if let Some(days) = req.days {
+                    analyzer = analyzer.with_days(days);
+                }
+                if let Some(path) = &req.coverage_file {
+                    let path = state.workspace_root.join(path);
+                    match crate::analysis::CoverageAnalyzer::load(&path) {
+                        Ok(coverage) => analyzer = analyzer.with_coverage(coverage),
+                        Err(e) => {
+                            return Ok(CallToolResult::error(vec![Content::text(format!(
+                                "Failed to load coverage from {}: {}",
+                                path.display(),
+                                e
+                            ))]));
+                        }
+                    }
+                }
+                let report =
+                    match analyzer.analyze_within(&state.oci_state, &state.workspace_root, &budget)
+                    {
+                        Ok(report) => report,
+                        Err(e) => {
+                            return Ok(CallToolResult::error(vec![Content::text(format!(
+                                "Hotspot analysis failed: {}",
+                                e
+                            ))]));
+                        }
+                    };
+                let truncated = budget.was_cut_short();
+                if req.json {
+                    return Ok(json_result("hotspots", with_truncated(&report, truncated)));
+                }
+
+                let mut output = format!(
+                    "{} hotspots (risk = commits x cyclomatic complexity x (2 - coverage) x (1 + centrality))\n{}\n",
+                    report.files.len(),
+                    truncated_note(truncated)
+                );
+                for h in &report.files {
+                    output.push_str(&format!(
+                        "- {}: risk {:.1} ({} commits, complexity {}, {}centrality {:.2}, {} lines changed)\n",
+                        h.file,
+                        h.risk,
+                        h.commits,
+                        h.complexity,
+                        h.coverage
+                            .map(|c| format!("{:.0}% covered, ", c * 100.0))
+                            .unwrap_or_default(),
+                        h.centrality,
+                        h.lines_changed
+                    ));
+                    for f in &h.functions {
+                        output.push_str(&format!(
+                            "    {} (line {}): complexity {}\n",
+                            f.symbol,
+                            f.line + 1,
+                            f.complexity
+                        ));
+                    }
+                }
+                if !report.symbols.is_empty() {
+                    output.push_str("\nRiskiest functions:\n");
+                }
+                for s in &report.symbols {
+                    output.push_str(&format!(
+                        "- {} at {}:{}: risk {:.1} ({} commits, complexity {}{})\n",
+                        s.symbol,
+                        s.file,
+                        s.line + 1,
+                        s.risk,
+                        s.commits,
+                        s.complexity,
+                        s.coverage
+                            .map(|c| format!(", {:.0}% covered", c * 100.0))
+                            .unwrap_or_default()
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(feature = "analysis")]
+            "clones" => {
+                let state = self.state.read().await;
+                let groups = crate::analysis::CloneAnalyzer::new().analyze_within(
+                    &state.oci_state,
+                    &state.workspace_root,
+                    &budget,
+                );
+                let truncated = budget.was_cut_short();
+                if req.json {
+                    let groups = &groups[..groups.len().min(MAX_ANALYSIS_RESULTS)];
+                    return Ok(json_result(
+                        "clones",
+                        serde_json::json!({ "truncated": truncated, "groups": groups }),
+                    ));
+                }
+
+                let mut output = format!(
+                    "Found {} groups of functions with duplicated bodies\n{}\n",
+                    groups.len(),
+                    truncated_note(truncated)
+                );
+                for g in groups.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} functions, {:.0}% similar, {} duplicated tokens\n",
+                        g.functions.len(),
+                        g.similarity * 100.0,
+                        g.duplicated_tokens
+                    ));
+                    for f in &g.functions {
+                        output.push_str(&format!(
+                            "    {} at {}:{}-{}\n",
+                            f.symbol,
+                            f.file,
+                            f.line + 1,
+                            f.end_line + 1
+                        ));
+                    }
+                }
+                if groups.len() > MAX_ANALYSIS_RESULTS {
+                    output.push_str(&format!(
+                        "... and {} more\n",
+                        groups.len() - MAX_ANALYSIS_RESULTS
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(feature = "analysis")]
+            "co_changes" => {
+                let Some(target) = req.target.clone() else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "target parameter required for co_changes",
+                    )]));
+                };
+                let days = req.days.unwrap_or(crate::analysis::cochange::DEFAULT_DAYS);
+                let (oci, root) = {
+                    let state = self.state.read().await;
+                    (state.oci_state.clone(), state.workspace_root.clone())
+                };
+                // Shells out to git; keep it off the async runtime.
+                let (report, truncated) = tokio::task::spawn_blocking(move || {
+                    let report = crate::analysis::CoChangeAnalyzer::analyze_within(
+                        &oci, &root, &target, days, &budget,
+                    );
+                    (report, budget.was_cut_short())
+                })
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                let mut report = match report {
+                    Ok(Some(report)) => report,
+                    Ok(None) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Unknown co-change target: {} (expected an indexed file or symbol)",
+                            req.target.unwrap_or_default()
+                        ))]));
+                    }
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Co-change analysis failed: {}",
+                            e
+                        ))]));
+                    }
+                };
+                if req.json {
+                    report.files.truncate(MAX_ANALYSIS_RESULTS);
+                    report.symbols.truncate(MAX_ANALYSIS_RESULTS);
+                    return Ok(json_result("cochanges", with_truncated(&report, truncated)));
+                }
+
+                let mut output = format!(
+                    "{} changed in {} commits in the last {} days\n{}",
+                    report.target,
+                    report.commits,
+                    days,
+                    truncated_note(truncated)
+                );
+                if !report.files.is_empty() {
+                    output.push_str("\nFiles usually changed with it:\n");
+                }
+                for f in report.files.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {}: {} of {} commits ({:.0}%)\n",
+                        f.file,
+                        f.together,
+                        report.commits,
+                        f.confidence * 100.0
+                    ));
+                }
+                if !report.symbols.is_empty() {
+                    output.push_str("\nSymbols usually changed with it:\n");
+                }
+                for s in report.symbols.iter().take(MAX_ANALYSIS_RESULTS) {
+                    output.push_str(&format!(
+                        "- {} ({}) at {}:{}: {} of {} commits ({:.0}%)\n",
+                        s.symbol,
+                        s.kind,
+                        s.file,
+                        s.line + 1,
+                        s.together,
+                        report.commits,
+                        s.confidence * 100.0
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(not(feature = "analysis"))]
+            name if ANALYSES.contains(&name) => Ok(CallToolResult::error(vec![Content::text(
+                "Analysis requires omni to be built with the `analysis` feature",
+            )])),
+            _ => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Unknown analysis: {}. Valid: {}{}",
+                req.analysis,
+                ANALYSES.join(", "),
+                custom_analysis_names()
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Search the codebase: BM25 over symbol spans, semantic similarity, or both fused (hybrid)"
+    )]
+    async fn search(
+        &self,
+        Parameters(req): Parameters<SearchRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let search_type = match req.search_type.as_deref() {
+            None => SearchType::default(),
+            Some(name) => match SearchType::parse(name) {
+                Some(search_type) => search_type,
+                None => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Unknown search type: {}. Valid: hybrid, bm25, semantic",
+                        name
+                    ))]));
+                }
+            },
+        };
+
+        let state = self.state.read().await;
+        let root = req
+            .root
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| state.workspace_root.clone());
+        // The semantic index covers the workspace state only
+        let use_semantic = search_type.uses_semantic() && root == state.workspace_root;
+        let top_k = req.top_k.unwrap_or(10);
+        let filters = req.filters.clone().unwrap_or_default();
+        let (query_text, mut parsed_filters) = parse_query_filters(&req.query, &filters);
+        parsed_filters.exclude_tests = req.exclude_tests;
+
+        if query_text.trim().is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Query must include search terms.",
+            )]));
+        }
+
+        if let Some(spec) = &req.reachable_from {
+            let Some(slice) = reachable_from(&state.oci_state, spec) else {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Unknown reachability root: {}",
+                    spec
+                ))]));
+            };
+            parsed_filters.files = Some(slice.relative_files(&root));
+        }
+
+        drop(state);
+
+        let mut index = match load_search_index(&root) {
+            Ok(idx) => idx,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to load index: {}",
+                    e
+                ))]));
+            }
+        };
+        if index.is_none() {
+            let state = self.state.write().await;
+            let _ = state
+                .indexer
+                .index(&state.oci_state, &root, &IndexOptions::default())
+                .await;
+            index = match load_search_index(&root) {
+                Ok(idx) => idx,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Failed to load index: {}",
+                        e
+                    ))]));
+                }
+            };
+        }
+
+        let Some(mut index) = index else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Index not found; run omni index first.",
+            )]));
+        };
+        if use_semantic {
+            self.ensure_semantic_index().await;
+        }
+
+        let budget = request_budget(req.timeout_ms, req.max_work);
+        let mut response = {
+            let state = self.state.read().await;
+            if root == state.workspace_root {
+                apply_overlays(&mut index, &state.oci_state);
+            }
+            let semantic = use_semantic
+                .then(|| semantic_doc_hits(&state.oci_state, &index, &query_text, top_k))
+                .flatten();
+            match (search_type, semantic) {
+                (SearchType::Semantic, Some(semantic)) => semantic_query(
+                    &index,
+                    &query_text,
+                    top_k,
+                    &parsed_filters,
+                    semantic,
+                    &budget,
+                ),
+                (SearchType::Semantic, None) => {
+                    let reason = if use_semantic {
+                        format!(
+                            "semantic index {}",
+                            state.oci_state.semantic_status.read().as_str()
+                        )
+                    } else {
+                        "it only covers the workspace root".to_string()
+                    };
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Semantic search unavailable: {}. Use search_type=bm25 or hybrid",
+                        reason
+                    ))]));
+                }
+                (_, semantic) => search_with_fallback(
+                    &index,
+                    &query_text,
+                    top_k,
+                    &parsed_filters,
+                    semantic,
+                    &budget,
+                ),
+            }
+        };
+        response.query = req.query.clone();
+        let mut payload = serde_json::json!({
+            "ok": true,
+            "schema_version": SCHEMA_VERSION,
+            "type": "query",
+            "root": response.root,
+            "query": response.query,
+            "top_k": response.top_k,
+            "strategy": response.strategy,
+            "truncated": response.truncated,
+            "results": response.results,
+        });
+        if let Err(e) = downgrade(&mut payload, req.schema_version.unwrap_or(SCHEMA_VERSION)) {
+            return Ok(CallToolResult::error(vec![Content::text(e.to_string())]));
+        }
+        let json = serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Get smart context for a location. Includes callers, callees, related types."
+    )]
+    async fn get_context(
+        &self,
+        Parameters(req): Parameters<ContextRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        #[cfg(feature = "context")]
+        {
+            use crate::context::{ChunkOrdering, ContextQuery, ContextSynthesizer};
+
+            let ordering = match req.ordering.as_deref().map(str::parse::<ChunkOrdering>) {
+                Some(Err(e)) => {
+                    return Ok(CallToolResult::error(vec![Content::text(e.to_string())]));
+                }
+                Some(Ok(ordering)) => ordering,
+                None => ChunkOrdering::default(),
+            };
+
+            #[cfg(feature = "analysis")]
+            self.ensure_history().await;
+            let state = self.state.read().await;
+            let oci = &state.oci_state;
+            let pinned_symbols = state
+                .pins
+                .symbols
+                .iter()
+                .filter_map(|name| oci.lookup(name))
+                .collect();
+            let pinned_files = state.pins.files.iter().cloned().collect();
+
+            let mut query = ContextQuery::new(state.workspace_root.join(&req.file), req.line)
+                .with_surrounding_lines(req.surrounding.unwrap_or(10))
+                .with_max_tokens(req.max_tokens.unwrap_or(4000))
+                .with_ordering(ordering)
+                .with_limits(req.timeout_ms.map(Duration::from_millis), req.max_work)
+                .with_pinned(pinned_symbols, pinned_files)
+                .with_feedback(state.feedback.clone());
+            if let Some(intent) = &req.intent {
+                query = query.with_intent(intent.clone());
+            }
+
+            let result = match ContextSynthesizer::new().build_context(oci, &query).await {
+                Ok(result) => result,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Context synthesis failed: {}",
+                        e
+                    ))]));
+                }
+            };
+
+            if req.json {
+                let sections = [
+                    ("pinned", &result.pinned),
+                    ("primary", &result.primary),
+                    ("related", &result.related),
+                ];
+                let chunks: Vec<serde_json::Value> = sections
+                    .into_iter()
+                    .flat_map(|(section, chunks)| chunks.iter().map(move |c| (section, c)))
+                    .map(|(section, chunk)| {
+                        let file = chunk
+                            .file
+                            .strip_prefix(&state.workspace_root)
+                            .unwrap_or(&chunk.file);
+                        serde_json::json!({
+                            "section": section,
+                            "file": file.display().to_string(),
+                            "line": chunk.line,
+                            "symbol": chunk.symbol.map(|s| oci.resolve(s).to_string()),
+                            "relevance": chunk.relevance,
+                            "reason": chunk.reason,
+                            "content": chunk.content,
+                        })
+                    })
+                    .collect();
+                return Ok(json_result(
+                    "context",
+                    serde_json::json!({
+                        "file": req.file,
+                        "line": req.line,
+                        "total_tokens": result.total_tokens,
+                        "truncated": result.truncated,
+                        "suppressed": result.suppressed,
+                        "chunks": chunks,
+                    }),
+                ));
+            }
+
+            let mut output = format!(
+                "Context for {}:{} (~{} tokens)\n",
+                req.file, req.line, result.total_tokens
+            );
+            if result.truncated {
+                output.push_str("truncated: true (timeout_ms or max_work reached)\n");
+            }
+            if result.suppressed > 0 {
+                output.push_str(&format!(
+                    "suppressed: {} (marked not useful; context_feedback op=clear resets)\n",
+                    result.suppressed
+                ));
+            }
+            for chunk in result.all_chunks() {
+                let file = chunk
+                    .file
+                    .strip_prefix(&state.workspace_root)
+                    .unwrap_or(&chunk.file);
+                output.push_str(&format!(
+                    "\n## {}:{} - {}\n```\n{}\n```\n",
+                    file.display(),
+                    chunk.line,
+                    chunk.reason,
+                    chunk.content
+                ));
+            }
+            Ok(CallToolResult::success(vec![Content::text(output)]))
+        }
+
+        #[cfg(not(feature = "context"))]
+        Ok(CallToolResult::error(vec![Content::text(format!(
+            "Context synthesis for {}:{} requires the 'context' feature",
+            req.file, req.line
+        ))]))
+    }
+
+    #[tool(
+        description = "Pin symbols or files into every get_context response for this session. Operations: pin, unpin, list, clear"
+    )]
+    async fn pin(
+        &self,
+        Parameters(req): Parameters<PinRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut state = self.state.write().await;
+        let root = state.workspace_root.clone();
+        let file = req.file.as_ref().map(|f| root.join(f));
+
+        match req.op.as_str() {
+            "pin" => {
+                if req.symbol.is_none() && file.is_none() {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "symbol or file parameter required for pin",
+                    )]));
+                }
+                let mut pinned = Vec::new();
+                if let Some(name) = &req.symbol {
+                    let oci = &state.oci_state;
+                    let scoped: Vec<String> =
+                        match oci.lookup(name).filter(|key| oci.symbols.contains_key(key)) {
+                            Some(_) => vec![name.clone()],
+                            None => oci
+                                .find_by_name(name)
+                                .iter()
+                                .map(|s| oci.resolve(s.scoped_name).to_string())
+                                .collect(),
+                        };
+                    if scoped.is_empty() {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Symbol not found: {}. Run index build first.",
+                            name
+                        ))]));
+                    }
+                    pinned.extend(scoped.iter().cloned());
+                    state.pins.symbols.extend(scoped);
+                }
+                if let Some(file) = file {
+                    if !file.is_file() {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "File not found: {}",
+                            file.display()
+                        ))]));
+                    }
+                    pinned.push(file.display().to_string());
+                    state.pins.files.insert(file);
+                }
+                if req.json {
+                    return Ok(pins_json(&state.pins, &root, &req.op, &pinned));
+                }
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Pinned: {}",
+                    pinned.join(", ")
+                ))]))
+            }
+            "unpin" => {
+                let mut removed = Vec::new();
+                if let Some(name) = &req.symbol {
+                    // Unpin by scoped name, or every pin with this simple name
+                    let matches: Vec<String> = state
+                        .pins
+                        .symbols
+                        .iter()
+                        .filter(|s| *s == name || s.rsplit("::").next() == Some(name.as_str()))
+                        .cloned()
+                        .collect();
+                    for symbol in matches {
+                        state.pins.symbols.remove(&symbol);
+                        removed.push(symbol);
+                    }
+                }
+                if let Some(file) = file {
+                    if state.pins.files.remove(&file) {
+                        removed.push(file.display().to_string());
+                    }
+                }
+                if req.json {
+                    return Ok(pins_json(&state.pins, &root, &req.op, &removed));
+                }
+                if removed.is_empty() {
+                    Ok(CallToolResult::success(vec![Content::text(
+                        "Nothing matching was pinned.",
+                    )]))
+                } else {
+                    Ok(CallToolResult::success(vec![Content::text(format!(
+                        "Unpinned: {}",
+                        removed.join(", ")
+                    ))]))
+                }
+            }
+            "list" => {
+                if req.json {
+                    return Ok(pins_json(&state.pins, &root, &req.op, &[]));
+                }
+                if state.pins.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text("No pins.")]));
+                }
+                let mut output = String::from("Pinned items:\n");
+                for symbol in &state.pins.symbols {
+                    output.push_str(&format!("  symbol: {}\n", symbol));
+                }
+                for file in &state.pins.files {
+                    let file = file.strip_prefix(&root).unwrap_or(file);
+                    output.push_str(&format!("  file: {}\n", file.display()));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            "clear" => {
+                state.pins = PinnedSet::default();
+                if req.json {
+                    return Ok(pins_json(&state.pins, &root, &req.op, &[]));
+                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    "Cleared all pins.",
+                )]))
+            }
+            _ => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Unknown operation: {}. Valid: pin, unpin, list, clear",
+                req.op
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Mark a get_context chunk as useful or not for this session. Files and symbols rejected repeatedly are left out of later context; useful ones rank higher. Operations: useful, not_useful, list, clear"
+    )]
+    async fn context_feedback(
+        &self,
+        Parameters(req): Parameters<ContextFeedbackRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        #[cfg(feature = "context")]
+        {
+            let mut state = self.state.write().await;
+            let root = state.workspace_root.clone();
+            let file = req.file.as_ref().map(|f| root.join(f));
+
+            match req.op.as_str() {
+                "useful" | "not_useful" => {
+                    let useful = req.op == "useful";
+                    if req.symbol.is_none() && file.is_none() {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "symbol or file parameter required for {}",
+                            req.op
+                        ))]));
+                    }
+                    let mut marked = Vec::new();
+                    if let Some(name) = &req.symbol {
+                        let oci = &state.oci_state;
+                        let symbols: Vec<(String, PathBuf)> =
+                            match oci.lookup(name).and_then(|key| oci.get_symbol(key)) {
+                                Some(def) => vec![(name.clone(), def.location.file.clone())],
+                                None => oci
+                                    .find_by_name(name)
+                                    .iter()
+                                    .map(|s| {
+                                        (
+                                            oci.resolve(s.scoped_name).to_string(),
+                                            s.location.file.clone(),
+                                        )
+                                    })
+                                    .collect(),
+                            };
+                        if symbols.is_empty() {
+                            return Ok(CallToolResult::error(vec![Content::text(format!(
+                                "Symbol not found: {}. Run index build first.",
+                                name
+                            ))]));
+                        }
+                        for (symbol, symbol_file) in symbols {
+                            state.feedback.record_file(symbol_file, useful);
+                            state.feedback.record_symbol(symbol.clone(), useful);
+                            marked.push(symbol);
+                        }
+                    }
+                    if let Some(file) = file {
+                        marked.push(file.display().to_string());
+                        state.feedback.record_file(file, useful);
+                    }
+                    if req.json {
+                        return Ok(context_feedback_json(
+                            &state.feedback,
+                            &root,
+                            &req.op,
+                            &marked,
+                        ));
+                    }
+                    Ok(CallToolResult::success(vec![Content::text(format!(
+                        "Marked {}: {}",
+                        req.op.replace('_', " "),
+                        marked.join(", ")
+                    ))]))
+                }
+                "list" => {
+                    if req.json {
+                        return Ok(context_feedback_json(&state.feedback, &root, &req.op, &[]));
+                    }
+                    if state.feedback.is_empty() {
+                        return Ok(CallToolResult::success(vec![Content::text("No feedback.")]));
+                    }
+                    let mut output = String::from("Context feedback (useful / not useful):\n");
+                    for (symbol, counts) in state.feedback.symbols() {
+                        output.push_str(&format!(
+                            "  symbol: {} ({} / {})\n",
+                            symbol, counts.useful, counts.not_useful
+                        ));
+                    }
+                    for (file, counts) in state.feedback.files() {
+                        let file = file.strip_prefix(&root).unwrap_or(file);
+                        output.push_str(&format!(
+                            "  file: {} ({} / {})\n",
+                            file.display(),
+                            counts.useful,
+                            counts.not_useful
+                        ));
+                    }
+                    Ok(CallToolResult::success(vec![Content::text(output)]))
+                }
+                "clear" => {
+                    state.feedback = Default::default();
+                    if req.json {
+                        return Ok(context_feedback_json(&state.feedback, &root, &req.op, &[]));
+                    }
+                    Ok(CallToolResult::success(vec![Content::text(
+                        "Cleared context feedback.",
+                    )]))
+                }
+                _ => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Unknown operation: {}. Valid: useful, not_useful, list, clear",
+                    req.op
+                ))])),
+            }
+        }
+
+        #[cfg(not(feature = "context"))]
+        Ok(CallToolResult::error(vec![Content::text(format!(
+            "Context feedback ({}) requires the 'context' feature",
+            req.op
+        ))]))
+    }
+
+    #[tool(
+        description = "Overlay unsaved file contents over the workspace so search, symbols and context reflect pending edits without writing files. Operations: set, clear, list, clear_all"
+    )]
+    async fn overlay(
+        &self,
+        Parameters(req): Parameters<OverlayRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let state = self.state.write().await;
+        let root = state.workspace_root.clone();
+        let file = req.file.as_ref().map(|f| root.join(f));
+        let oci = &state.oci_state;
+
+        match req.op.as_str() {
+            "set" => {
+                let (Some(file), Some(content)) = (file, req.content) else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "file and content parameters required for set",
+                    )]));
+                };
+                match state.indexer.overlay_file(oci, &file, &root, content).await {
+                    Ok(_) if req.json => Ok(overlays_json(oci, &root, &req.op, &[file])),
+                    Ok(docs) => Ok(CallToolResult::success(vec![Content::text(format!(
+                        "Overlaid {} ({} symbols)",
+                        file.display(),
+                        docs.len()
+                    ))])),
+                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Failed to overlay {}: {}",
+                        file.display(),
+                        e
+                    ))])),
+                }
+            }
+            "clear" => {
+                let Some(file) = file else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "file parameter required for clear",
+                    )]));
+                };
+                match state.indexer.clear_overlay(oci, &file, &root).await {
+                    Ok(cleared) if req.json => {
+                        let changed: &[PathBuf] = if cleared { &[file] } else { &[] };
+                        Ok(overlays_json(oci, &root, &req.op, changed))
+                    }
+                    Ok(true) => Ok(CallToolResult::success(vec![Content::text(format!(
+                        "Cleared overlay: {}",
+                        file.display()
+                    ))])),
+                    Ok(false) => Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No overlay for {}",
+                        file.display()
+                    ))])),
+                    Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Failed to re-index {}: {}",
+                        file.display(),
+                        e
+                    ))])),
                 }
             }
-            "churn" => {
-                let _days = req.days.unwrap_or(30);
-                // TODO: Integrate with churn analysis module
-                Ok(CallToolResult::success(vec![Content::text(
-                    "Churn analysis not yet implemented. Will analyze git history.",
-                )]))
+            "list" => {
+                if req.json {
+                    return Ok(overlays_json(oci, &root, &req.op, &[]));
+                }
+                if oci.overlays.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text("No overlays.")]));
+                }
+                let mut files: Vec<PathBuf> =
+                    oci.overlays.iter().map(|o| o.key().clone()).collect();
+                files.sort();
+                let mut output = String::from("Overlaid files:\n");
+                for file in &files {
+                    let file = file.strip_prefix(&root).unwrap_or(file);
+                    output.push_str(&format!("  {}\n", file.display()));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
             }
-            "hotspots" => {
-                // TODO: Combine churn + complexity metrics
-                Ok(CallToolResult::success(vec![Content::text(
-                    "Hotspot analysis not yet implemented. Will combine churn frequency with complexity.",
-                )]))
+            "clear_all" => {
+                let files: Vec<PathBuf> = oci.overlays.iter().map(|o| o.key().clone()).collect();
+                for file in &files {
+                    if let Err(e) = state.indexer.clear_overlay(oci, file, &root).await {
+                        tracing::warn!("Failed to re-index {}: {}", file.display(), e);
+                    }
+                }
+                if req.json {
+                    return Ok(overlays_json(oci, &root, &req.op, &files));
+                }
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Cleared {} overlays.",
+                    files.len()
+                ))]))
             }
             _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Unknown analysis: {}. Valid: dead_code, coverage, churn, hotspots",
-                req.analysis
+                "Unknown operation: {}. Valid: set, clear, list, clear_all",
+                req.op
             ))])),
         }
     }
 
-    #[tool(description = "Search the codebase using BM25 over symbol spans")]
-    async fn search(
+    #[tool(
+        description = "Report which search result was actually used. Popular symbols rank slightly higher in later searches."
+    )]
+    async fn feedback(
         &self,
-        Parameters(req): Parameters<SearchRequest>,
+        Parameters(req): Parameters<FeedbackRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let state = self.state.read().await;
-        let root = req
-            .root
-            .as_ref()
-            .map(PathBuf::from)
-            .unwrap_or_else(|| state.workspace_root.clone());
-        let top_k = req.top_k.unwrap_or(10);
-        let filters = req.filters.clone().unwrap_or_default();
-        let (query_text, parsed_filters) = parse_query_filters(&req.query, &filters);
-
-        if query_text.trim().is_empty() {
+        let symbol = req.symbol.trim();
+        if symbol.is_empty() {
             return Ok(CallToolResult::error(vec![Content::text(
-                "Query must include search terms.",
+                "symbol parameter required",
             )]));
         }
-
-        drop(state);
-
-        let mut index = match load_search_index(&root) {
-            Ok(idx) => idx,
-            Err(e) => {
-                return Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Failed to load index: {}",
-                    e
-                ))]));
-            }
-        };
-        if index.is_none() {
-            let state = self.state.write().await;
-            let _ = state
-                .indexer
-                .index(&state.oci_state, &root, &IndexOptions::default())
-                .await;
-            index = match load_search_index(&root) {
-                Ok(idx) => idx,
-                Err(e) => {
-                    return Ok(CallToolResult::error(vec![Content::text(format!(
-                        "Failed to load index: {}",
-                        e
-                    ))]));
-                }
-            };
-        }
-
-        let Some(index) = index else {
-            return Ok(CallToolResult::error(vec![Content::text(
-                "Index not found; run omni index first.",
-            )]));
-        };
-
-        let mut response = execute_query(&index, &query_text, top_k, &parsed_filters);
-        response.query = req.query.clone();
-        let payload = serde_json::json!({
-            "ok": true,
-            "type": "query",
-            "root": response.root,
-            "query": response.query,
-            "top_k": response.top_k,
-            "results": response.results,
-        });
-        let json = serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "{}".to_string());
-        Ok(CallToolResult::success(vec![Content::text(json)]))
-    }
-
-    #[tool(
-        description = "Get smart context for a location. Includes callers, callees, related types."
-    )]
-    async fn get_context(
-        &self,
-        Parameters(req): Parameters<ContextRequest>,
-    ) -> Result<CallToolResult, McpError> {
         let state = self.state.read().await;
-        let _oci = &state.oci_state;
-        let _file = PathBuf::from(&req.file);
-        let _line = req.line;
-        let _surrounding = req.surrounding.unwrap_or(10);
-        let _max_tokens = req.max_tokens.unwrap_or(4000);
-
-        // TODO: Integrate with context synthesis
-        Ok(CallToolResult::success(vec![Content::text(format!(
-            "Context synthesis not yet implemented for {}:{}.\nWill include callers, callees, types, and imports ranked by PageRank.",
-            req.file, req.line
-        ))]))
+        match record_selection(&state.workspace_root, symbol) {
+            Ok(count) if req.json => Ok(json_result(
+                "feedback",
+                serde_json::json!({ "symbol": symbol, "selections": count }),
+            )),
+            Ok(count) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Recorded selection of {} ({} total)",
+                symbol, count
+            ))])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to record feedback: {}",
+                e
+            ))])),
+        }
     }
 
     #[tool(
-        description = "Check for potential issues before writing code: duplication, naming conflicts"
+        description = "Check for potential issues before writing code: duplication, naming conflicts, repeated error mapping, or a whole proposed file"
     )]
     async fn intervene(
         &self,
@@ -529,53 +2957,130 @@ impl OciServer {
         let _oci = &state.oci_state;
 
         match req.check.as_str() {
+            #[cfg(feature = "intervention")]
             "duplication" => {
-                match &req.signature {
-                    Some(_sig) => {
-                        // TODO: Integrate with intervention engine
-                        Ok(CallToolResult::success(vec![Content::text(
-                            "Duplication check not yet implemented. Will find similar signatures.",
-                        )]))
-                    }
-                    None => Ok(CallToolResult::error(vec![Content::text(
+                let Some(signature) = &req.signature else {
+                    return Ok(CallToolResult::error(vec![Content::text(
                         "signature parameter required for duplication check",
-                    )])),
-                }
+                    )]));
+                };
+                let engine = crate::intervention::InterventionEngine::new();
+                let interventions = if req.semantic.unwrap_or(false) {
+                    match semantic_duplicates(&engine, _oci, signature, req.doc.as_deref()) {
+                        Ok(interventions) => interventions,
+                        Err(message) => {
+                            return Ok(CallToolResult::error(vec![Content::text(message)]));
+                        }
+                    }
+                } else {
+                    engine.check_duplication(_oci, signature)
+                };
+                Ok(CallToolResult::success(vec![Content::text(
+                    interventions_json(_oci, "duplication", signature, &interventions),
+                )]))
             }
+            #[cfg(feature = "intervention")]
             "naming" => {
-                match &req.name {
-                    Some(_name) => {
-                        // TODO: Integrate with intervention engine
-                        Ok(CallToolResult::success(vec![Content::text(
-                            "Naming conflict check not yet implemented. Will check for conflicts.",
-                        )]))
-                    }
-                    None => Ok(CallToolResult::error(vec![Content::text(
-                        "name parameter required for naming check",
-                    )])),
-                }
+                let (Some(name), Some(file)) = (&req.name, &req.file) else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "name and file parameters required for naming check",
+                    )]));
+                };
+                let interventions = crate::intervention::InterventionEngine::check_naming_conflicts(
+                    _oci,
+                    name,
+                    &_oci.root_path.join(file),
+                );
+                Ok(CallToolResult::success(vec![Content::text(
+                    interventions_json(_oci, "naming", name, &interventions),
+                )]))
             }
+            #[cfg(feature = "intervention")]
             "alternatives" => {
-                match &req.name {
-                    Some(_name) => {
-                        // TODO: Integrate with intervention engine
-                        Ok(CallToolResult::success(vec![Content::text(
-                            "Alternatives suggestion not yet implemented. Will find reusable code.",
-                        )]))
-                    }
-                    None => Ok(CallToolResult::error(vec![Content::text(
+                let Some(name) = &req.name else {
+                    return Ok(CallToolResult::error(vec![Content::text(
                         "name parameter required for alternatives check",
-                    )])),
+                    )]));
+                };
+                let interventions =
+                    crate::intervention::InterventionEngine::suggest_alternatives(_oci, name);
+                Ok(CallToolResult::success(vec![Content::text(
+                    interventions_json(_oci, "alternatives", name, &interventions),
+                )]))
+            }
+            #[cfg(feature = "intervention")]
+            "error_patterns" => {
+                let Some(code) = &req.code else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "code parameter required for error_patterns check",
+                    )]));
+                };
+                let interventions =
+                    crate::intervention::InterventionEngine::check_error_patterns(_oci, code);
+                if interventions.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        "No repeated error mapping found.",
+                    )]));
+                }
+                let mut output = String::new();
+                for i in &interventions {
+                    output.push_str(&format!(
+                        "[{:?}] {}\n  {}\n",
+                        i.severity, i.message, i.recommendation
+                    ));
+                }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
+            #[cfg(feature = "intervention")]
+            "file" => {
+                let (Some(file), Some(code)) = (&req.file, &req.code) else {
+                    return Ok(CallToolResult::error(vec![Content::text(
+                        "file and code parameters required for file check",
+                    )]));
+                };
+                let path = _oci.root_path.join(file);
+                let report = match crate::intervention::InterventionEngine::new()
+                    .check_file(_oci, &path, code)
+                {
+                    Ok(report) => report,
+                    Err(e) => {
+                        return Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Failed to check {}: {}",
+                            file, e
+                        ))]));
+                    }
+                };
+                if report.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(format!(
+                        "No findings for {}.",
+                        file
+                    ))]));
+                }
+                let mut output = format!("{} symbols with findings in {}:\n", report.len(), file);
+                for entry in &report {
+                    output.push_str(&format!(
+                        "\n{} {} (line {}):\n",
+                        entry.kind.as_str(),
+                        _oci.resolve(entry.symbol),
+                        entry.line
+                    ));
+                    for i in &entry.interventions {
+                        output.push_str(&format!(
+                            "  [{:?}] {}\n    {}\n",
+                            i.severity, i.message, i.recommendation
+                        ));
+                    }
                 }
+                Ok(CallToolResult::success(vec![Content::text(output)]))
             }
             _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Unknown check: {}. Valid: duplication, naming, alternatives",
+                "Unknown check: {}. Valid: duplication, naming, alternatives, error_patterns, file",
                 req.check
             ))])),
         }
     }
 
-    #[tool(description = "Query module topology: modules, imports, pagerank, dependencies")]
+    #[tool(description = "Query module topology: modules, imports, pagerank, dependencies, roles")]
     async fn topology(
         &self,
         Parameters(req): Parameters<TopologyRequest>,
@@ -591,9 +3096,28 @@ impl OciServer {
 
                 for idx in graph.node_indices() {
                     if let crate::types::TopologyNode::Module { name, path, .. } = &graph[idx] {
-                        modules.push(format!("- {} ({})", name, path.display()));
+                        modules.push((name, path));
                     }
                 }
+                if req.json {
+                    let modules: Vec<serde_json::Value> = modules
+                        .iter()
+                        .map(|(name, path)| {
+                            serde_json::json!({
+                                "name": name,
+                                "path": relative_display(path, &state.workspace_root),
+                            })
+                        })
+                        .collect();
+                    return Ok(json_result(
+                        "topology",
+                        serde_json::json!({ "op": req.op, "modules": modules }),
+                    ));
+                }
+                let modules: Vec<String> = modules
+                    .iter()
+                    .map(|(name, path)| format!("- {} ({})", name, path.display()))
+                    .collect();
 
                 if modules.is_empty() {
                     Ok(CallToolResult::success(vec![Content::text(
@@ -607,6 +3131,32 @@ impl OciServer {
                     ))]))
                 }
             }
+            "imports" if req.json => {
+                let mut files = Vec::new();
+                for entry in oci.imports.iter() {
+                    if files.len() >= max {
+                        break;
+                    }
+                    let file_id = *entry.key();
+                    if let Some(path_entry) = oci.file_ids.iter().find(|e| *e.value() == file_id) {
+                        let imports: Vec<String> = entry
+                            .value()
+                            .iter()
+                            .map(|imp| {
+                                format!("{}{}", imp.path, if imp.is_glob { "::*" } else { "" })
+                            })
+                            .collect();
+                        files.push(serde_json::json!({
+                            "file": relative_display(path_entry.key(), &state.workspace_root),
+                            "imports": imports,
+                        }));
+                    }
+                }
+                Ok(json_result(
+                    "topology",
+                    serde_json::json!({ "op": req.op, "files": files }),
+                ))
+            }
             "imports" => {
                 let mut output = String::from("File imports:\n\n");
                 let mut count = 0;
@@ -647,6 +3197,32 @@ impl OciServer {
                 scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
                 let graph = oci.topology.read();
+                if req.json {
+                    let nodes: Vec<serde_json::Value> = scores
+                        .iter()
+                        .take(max)
+                        .filter_map(|(idx, score)| {
+                            Some(match graph.node_weight(*idx)? {
+                                crate::types::TopologyNode::Crate { name, .. } => {
+                                    serde_json::json!({ "kind": "crate", "name": name, "score": score })
+                                }
+                                crate::types::TopologyNode::Module { name, .. } => {
+                                    serde_json::json!({ "kind": "module", "name": name, "score": score })
+                                }
+                                crate::types::TopologyNode::File { path, .. } => serde_json::json!({
+                                    "kind": "file",
+                                    "name": relative_display(path, &state.workspace_root),
+                                    "score": score,
+                                    "role": oci.file_role(path).as_str(),
+                                }),
+                            })
+                        })
+                        .collect();
+                    return Ok(json_result(
+                        "topology",
+                        serde_json::json!({ "op": req.op, "nodes": nodes }),
+                    ));
+                }
                 let mut output = String::from("Top nodes by PageRank:\n\n");
 
                 for (idx, score) in scores.iter().take(max) {
@@ -658,10 +3234,13 @@ impl OciServer {
                             crate::types::TopologyNode::Module { name, .. } => {
                                 format!("mod:{}", name)
                             }
-                            crate::types::TopologyNode::File { path, .. } => path
-                                .file_name()
-                                .map(|n| n.to_string_lossy().to_string())
-                                .unwrap_or_default(),
+                            crate::types::TopologyNode::File { path, .. } => format!(
+                                "{} [{}]",
+                                path.file_name()
+                                    .map(|n| n.to_string_lossy().to_string())
+                                    .unwrap_or_default(),
+                                oci.file_role(path).as_str()
+                            ),
                         };
                         output.push_str(&format!("{:.4}  {}\n", score, name));
                     }
@@ -685,9 +3264,21 @@ impl OciServer {
                                     crate::types::TopologyNode::Module { name, .. } => name.clone(),
                                     crate::types::TopologyNode::Crate { name, .. } => name.clone(),
                                 };
-                                deps.push(format!("  -> {}", name));
+                                deps.push(name);
                             }
                         }
+                        if req.json {
+                            return Ok(json_result(
+                                "topology",
+                                serde_json::json!({
+                                    "op": req.op,
+                                    "path": path.display().to_string(),
+                                    "dependencies": deps,
+                                }),
+                            ));
+                        }
+                        let deps: Vec<String> =
+                            deps.iter().map(|name| format!("  -> {}", name)).collect();
 
                         Ok(CallToolResult::success(vec![Content::text(format!(
                             "Dependencies of {}:\n{}",
@@ -705,14 +3296,489 @@ impl OciServer {
                     "path parameter required for dependencies query",
                 )])),
             },
+            "roles" => {
+                // Repo map: files grouped by role, most relevant first
+                let graph = oci.topology.read();
+                let mut by_role: BTreeMap<&'static str, Vec<(f64, String)>> = BTreeMap::new();
+                for entry in oci.topology_metrics.iter() {
+                    if let Some(crate::types::TopologyNode::File { path, .. }) =
+                        graph.node_weight(*entry.key())
+                    {
+                        let path = path.strip_prefix(&state.workspace_root).unwrap_or(path);
+                        by_role
+                            .entry(entry.value().role.as_str())
+                            .or_default()
+                            .push((entry.value().relevance_score, path.display().to_string()));
+                    }
+                }
+
+                for files in by_role.values_mut() {
+                    files.sort_by(|a, b| {
+                        b.0.partial_cmp(&a.0)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                            .then_with(|| a.1.cmp(&b.1))
+                    });
+                }
+                if req.json {
+                    let roles: Vec<serde_json::Value> = by_role
+                        .iter()
+                        .map(|(role, files)| {
+                            let top: Vec<&str> = files
+                                .iter()
+                                .take(max)
+                                .map(|(_, path)| path.as_str())
+                                .collect();
+                            serde_json::json!({ "role": role, "count": files.len(), "files": top })
+                        })
+                        .collect();
+                    return Ok(json_result(
+                        "topology",
+                        serde_json::json!({
+                            "op": req.op,
+                            "stack": *oci.stack.read(),
+                            "roles": roles,
+                        }),
+                    ));
+                }
+                if by_role.is_empty() {
+                    return Ok(CallToolResult::success(vec![Content::text(
+                        "No files found. Run index build first.",
+                    )]));
+                }
+
+                let mut output = match oci.stack.read().as_ref() {
+                    Some(stack) => format!("Stack: {}\n\n", stack.summary()),
+                    None => String::new(),
+                };
+                output.push_str("Files by role:\n");
+                for (role, files) in by_role {
+                    output.push_str(&format!("\n{} ({}):\n", role, files.len()));
+                    for (_, path) in files.iter().take(max) {
+                        output.push_str(&format!("  - {}\n", path));
+                    }
+                    if files.len() > max {
+                        output.push_str(&format!("  ... and {} more\n", files.len() - max));
+                    }
+                }
+
+                Ok(CallToolResult::success(vec![Content::text(output)]))
+            }
             _ => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Unknown operation: {}. Valid: modules, imports, pagerank, dependencies",
+                "Unknown operation: {}. Valid: modules, imports, pagerank, dependencies, roles",
                 req.op
             ))])),
         }
     }
 }
 
+/// Budget for a request's `timeout_ms` and `max_work` parameters.
+fn request_budget(timeout_ms: Option<u64>, max_work: Option<usize>) -> Budget {
+    Budget::new(timeout_ms.map(Duration::from_millis), max_work)
+}
+
+/// Line flagging text output cut short by the request's budget.
+#[cfg(feature = "analysis")]
+fn truncated_note(truncated: bool) -> &'static str {
+    if truncated {
+        "truncated: true (timeout_ms or max_work reached)\n"
+    } else {
+        ""
+    }
+}
+
+/// `data` as a JSON object with a `truncated` field.
+#[cfg(feature = "analysis")]
+fn with_truncated(data: impl Serialize, truncated: bool) -> serde_json::Value {
+    let mut value = serde_json::to_value(data).unwrap_or_default();
+    value["truncated"] = truncated.into();
+    value
+}
+
+/// Findings of an `intervene` check as JSON: one object per intervention
+/// with its severity, the existing symbol and its location (1-based line),
+/// the similarity score and a recommendation, most severe first.
+#[cfg(feature = "intervention")]
+fn interventions_json(
+    oci: &crate::state::OciState,
+    check: &str,
+    subject: &str,
+    interventions: &[crate::types::Intervention],
+) -> String {
+    let findings: Vec<serde_json::Value> = interventions
+        .iter()
+        .map(|i| {
+            let file = i.existing_location.file.as_path();
+            serde_json::json!({
+                "severity": i.severity.as_str(),
+                "message": i.message,
+                "symbol": oci.resolve(i.existing_symbol),
+                "file": file.strip_prefix(&oci.root_path).unwrap_or(file),
+                "line": i.existing_location.start_line + 1,
+                "similarity": i.similarity_score,
+                "recommendation": i.recommendation,
+            })
+        })
+        .collect();
+    let payload = serde_json::json!({
+        "check": check,
+        "subject": subject,
+        "findings": findings,
+    });
+    serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// `data` as a `json: true` response: what `omni --json` prints for the
+/// same query, tagged with its `kind`.
+fn json_result(kind: &str, data: impl Serialize) -> CallToolResult {
+    match success_json(kind, data) {
+        Ok(payload) => CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&payload).unwrap_or_else(|_| "{}".to_string()),
+        )]),
+        Err(e) => CallToolResult::error(vec![Content::text(e.to_string())]),
+    }
+}
+
+/// Semantic layer status for `index op=status`, noting the BM25-only
+/// fallback while the model is unavailable.
+fn semantic_line(status: &SemanticStatus) -> String {
+    match status {
+        SemanticStatus::Unavailable { reason } => format!(
+            "{}; search is BM25-only until it loads ({})",
+            status.as_str(),
+            reason
+        ),
+        _ => status.as_str().to_string(),
+    }
+}
+
+/// Trailing `Deprecated:` line for symbol listings, empty if not deprecated.
+fn deprecation_line(sym: &SymbolDef) -> String {
+    match sym.deprecation() {
+        Some("") => "\n  Deprecated".to_string(),
+        Some(note) => format!("\n  Deprecated: {}", note),
+        None => String::new(),
+    }
+}
+
+/// `Renamed:` lines for former names matching `name`, so agents can follow a
+/// refactor instead of hitting a dead end.
+fn rename_lines(root: &std::path::Path, name: &str) -> String {
+    RenameLog::load(root)
+        .unwrap_or_default()
+        .lookup(name)
+        .iter()
+        .map(|alias| format!("\n  Renamed: {}", alias.note()))
+        .collect()
+}
+
+/// Registered custom analyses for the `analyze` tool's unknown-analysis
+/// error, each prefixed with `, `.
+fn custom_analysis_names() -> String {
+    #[cfg(feature = "analysis")]
+    return crate::analysis::custom::registered()
+        .iter()
+        .map(|a| format!(", {}", a.name()))
+        .collect();
+    #[cfg(not(feature = "analysis"))]
+    String::new()
+}
+
+/// `path` relative to the workspace `root`, for JSON responses.
+fn relative_display(path: &std::path::Path, root: &std::path::Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// A `pin` response: what the operation `changed`, then every pin.
+fn pins_json(
+    pins: &PinnedSet,
+    root: &std::path::Path,
+    op: &str,
+    changed: &[String],
+) -> CallToolResult {
+    let changed: Vec<String> = changed
+        .iter()
+        .map(|c| relative_display(std::path::Path::new(c), root))
+        .collect();
+    let files: Vec<String> = pins
+        .files
+        .iter()
+        .map(|f| relative_display(f, root))
+        .collect();
+    json_result(
+        "pins",
+        serde_json::json!({
+            "op": op,
+            "changed": changed,
+            "symbols": pins.symbols,
+            "files": files,
+        }),
+    )
+}
+
+/// A `context_feedback` response: what the operation marked, then the
+/// useful/not useful counts of every file and symbol.
+#[cfg(feature = "context")]
+fn context_feedback_json(
+    feedback: &crate::context::ContextFeedback,
+    root: &std::path::Path,
+    op: &str,
+    changed: &[String],
+) -> CallToolResult {
+    let changed: Vec<String> = changed
+        .iter()
+        .map(|c| relative_display(std::path::Path::new(c), root))
+        .collect();
+    let symbols: Vec<serde_json::Value> = feedback
+        .symbols()
+        .into_iter()
+        .map(|(symbol, counts)| serde_json::json!({ "symbol": symbol, "counts": counts }))
+        .collect();
+    let files: Vec<serde_json::Value> = feedback
+        .files()
+        .into_iter()
+        .map(|(file, counts)| {
+            serde_json::json!({ "file": relative_display(file, root), "counts": counts })
+        })
+        .collect();
+    json_result(
+        "contextfeedback",
+        serde_json::json!({
+            "op": op,
+            "changed": changed,
+            "symbols": symbols,
+            "files": files,
+        }),
+    )
+}
+
+/// An `overlay` response: the files the operation `changed`, then every
+/// overlaid file.
+fn overlays_json(
+    oci: &crate::state::OciState,
+    root: &std::path::Path,
+    op: &str,
+    changed: &[PathBuf],
+) -> CallToolResult {
+    let mut overlays: Vec<String> = oci
+        .overlays
+        .iter()
+        .map(|o| relative_display(o.key(), root))
+        .collect();
+    overlays.sort();
+    let changed: Vec<String> = changed.iter().map(|f| relative_display(f, root)).collect();
+    json_result(
+        "overlays",
+        serde_json::json!({ "op": op, "changed": changed, "overlays": overlays }),
+    )
+}
+
+/// A `call_graph` callers or callees query in the shape of `omni calls
+/// --json`: call sites, or their groups with `group_by`, or, for a name
+/// several definitions share, callers split by their likely target.
+fn calls_json(
+    oci: &crate::state::OciState,
+    req: &CallGraphRequest,
+    mut edges: Vec<crate::types::CallEdge>,
+    side: CallSide,
+    grouping: Option<CallGrouping>,
+    split_by_target: bool,
+) -> serde_json::Value {
+    let counts = TestSplit::count(edges.iter().map(|e| e.is_test_context));
+    let exclude_tests = req.exclude_tests && side == CallSide::Caller;
+    if exclude_tests {
+        edges.retain(|e| !e.is_test_context);
+    }
+    let mut results = Vec::new();
+    let mut groups = Vec::new();
+    let mut candidates = Vec::new();
+    if let Some(grouping) = grouping {
+        groups = group_calls(oci, &edges, grouping, side);
+    } else {
+        if split_by_target {
+            candidates = resolve_callers(oci, &req.name);
+        }
+        if candidates.len() > 1 {
+            if exclude_tests {
+                for c in &mut candidates {
+                    c.callers.retain(|r| !r.is_test_context);
+                }
+                candidates.sort_by_key(|c| std::cmp::Reverse(c.callers.len()));
+            }
+        } else {
+            candidates.clear();
+            results = edges.iter().map(|e| CallResult::new(oci, e)).collect();
+        }
+    }
+    serde_json::json!({
+        "symbol": req.name,
+        "direction": req.op,
+        "counts": counts,
+        "results": results,
+        "groups": groups,
+        "candidates": candidates,
+    })
+}
+
+/// A `find_symbol` lookup in the shape of `omni symbol --json`: matches
+/// (or, for a name that no longer exists, what it was renamed to) and,
+/// with `include_deleted`, tombstones of that name.
+fn symbols_json(
+    oci: &crate::state::OciState,
+    root: &std::path::Path,
+    req: &SymbolRequest,
+    queries: &[Attribute],
+    max: usize,
+) -> serde_json::Value {
+    let mut symbols: Vec<SymbolDef> = match &req.context_file {
+        _ if req.scoped => oci
+            .lookup(&req.name)
+            .and_then(|key| oci.get_symbol(key))
+            .into_iter()
+            .collect(),
+        _ if req.name.is_empty() => oci.find_by_attributes(queries),
+        Some(file) => oci.find_by_name_near(&req.name, std::path::Path::new(file)),
+        None => oci.find_by_name(&req.name),
+    };
+    symbols.retain(|s| s.has_attributes(queries));
+    if req.scoped
+        && let Some(sym) = symbols.first()
+    {
+        let scoped = oci.resolve(sym.scoped_name);
+        if let Err(e) = record_selection(root, scoped) {
+            tracing::warn!("Failed to record selection of {}: {}", scoped, e);
+        }
+    }
+    let rename_log = RenameLog::load(root).unwrap_or_default();
+    let renames = if symbols.is_empty() && !req.name.is_empty() {
+        rename_log.lookup(&req.name)
+    } else {
+        Vec::new()
+    };
+    let mut results: Vec<SymbolResult> = symbols
+        .iter()
+        .take(max)
+        .map(|s| SymbolResult {
+            attributes: s
+                .attributes
+                .iter()
+                .filter(|a| queries.iter().any(|q| a.matches(q)))
+                .map(ToString::to_string)
+                .collect(),
+            ..SymbolResult::new(oci, s)
+        })
+        .collect();
+    results.extend(renames.iter().filter_map(|alias| {
+        let symbol = oci.get_symbol(oci.lookup(&alias.new_name)?)?;
+        Some(SymbolResult {
+            renamed: Some(alias.note()),
+            ..SymbolResult::new(oci, &symbol)
+        })
+    }));
+    let tombstones = TombstoneLog::load(root).unwrap_or_default();
+    let deleted = if req.include_deleted {
+        tombstones.lookup(&req.name)
+    } else {
+        Vec::new()
+    };
+    serde_json::json!({
+        "query": req.name,
+        "attributes": req.attributes,
+        "results": results,
+        "deleted": deleted,
+    })
+}
+
+/// `Deleted:` lines for recently deleted symbols matching `name`, with
+/// where they were last defined.
+fn deletion_lines(root: &std::path::Path, name: &str) -> String {
+    TombstoneLog::load(root)
+        .unwrap_or_default()
+        .lookup(name)
+        .iter()
+        .map(|t| format!("\n  Deleted: {}", t.note()))
+        .collect()
+}
+
+/// Semantic neighbours of `query` as search doc ids, for the hybrid stage of
+/// [`search_with_fallback`]. `None` when no semantic index is loaded.
+#[cfg(feature = "semantic")]
+fn semantic_doc_hits<'a>(
+    oci: &'a crate::state::OciState,
+    index: &'a SearchIndex,
+    query: &str,
+    top_k: usize,
+) -> Option<SemanticHits<'a>> {
+    let semantic = oci.semantic_index.get()?;
+    let hits = match semantic.search(query, top_k.saturating_mul(4)) {
+        Ok(hits) => hits,
+        Err(e) => {
+            tracing::warn!("Semantic search failed, using BM25: {}", e);
+            return None;
+        }
+    };
+    let doc_ids: std::collections::HashMap<&str, u32> = index
+        .docs
+        .iter()
+        .enumerate()
+        .map(|(doc_id, doc)| (doc.symbol.as_str(), doc_id as u32))
+        .collect();
+    let hits = hits
+        .into_iter()
+        .filter_map(|(symbol, score)| {
+            let doc_id = doc_ids.get(oci.resolve(symbol))?;
+            Some((*doc_id, score))
+        })
+        .collect();
+    // Near-identical results (e.g. a function and its re-export) collapse
+    Some(SemanticHits::new(hits).with_embeddings(move |doc_id| {
+        let doc = index.docs.get(doc_id as usize)?;
+        semantic.embedding(oci.interner.get(&doc.symbol)?)
+    }))
+}
+
+#[cfg(not(feature = "semantic"))]
+fn semantic_doc_hits(
+    _oci: &crate::state::OciState,
+    _index: &SearchIndex,
+    _query: &str,
+    _top_k: usize,
+) -> Option<SemanticHits<'static>> {
+    None
+}
+
+/// Signature and embedding matches for the `intervene` duplication check,
+/// or why there are none.
+#[cfg(all(feature = "intervention", feature = "semantic"))]
+fn semantic_duplicates(
+    engine: &crate::intervention::InterventionEngine,
+    oci: &crate::state::OciState,
+    signature: &str,
+    doc: Option<&str>,
+) -> std::result::Result<Vec<crate::types::Intervention>, String> {
+    let Some(index) = oci.semantic_index.get() else {
+        return Err(format!(
+            "Semantic index {}; retry without semantic",
+            oci.semantic_status.read().as_str()
+        ));
+    };
+    engine
+        .check_duplication_semantic(oci, index, signature, doc)
+        .map_err(|e| format!("Semantic duplication check failed: {}", e))
+}
+
+#[cfg(all(feature = "intervention", not(feature = "semantic")))]
+fn semantic_duplicates(
+    _engine: &crate::intervention::InterventionEngine,
+    _oci: &crate::state::OciState,
+    _signature: &str,
+    _doc: Option<&str>,
+) -> std::result::Result<Vec<crate::types::Intervention>, String> {
+    Err("Semantic duplication check requires the 'semantic' feature".to_string())
+}
+
 // ============================================================================
 // MCP Server Handler Implementation
 // ============================================================================
@@ -722,6 +3788,13 @@ impl ServerHandler for OciServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
+            #[cfg(feature = "context")]
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .build(),
+            #[cfg(not(feature = "context"))]
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(format!(
@@ -730,6 +3803,122 @@ impl ServerHandler for OciServer {
             )),
         }
     }
+
+    #[cfg(feature = "context")]
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let state = self.state.read().await;
+        let resources = crate::context::documents::list_documents(&state.oci_state)
+            .into_iter()
+            .map(|entry| {
+                let mut resource = RawResource::new(entry.document.uri(), entry.name);
+                resource.description = Some(entry.description);
+                resource.mime_type = Some("text/markdown".to_string());
+                resource.no_annotation()
+            })
+            .collect();
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    #[cfg(feature = "context")]
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let template = RawResourceTemplate {
+            uri_template: format!("{}{{path}}", crate::context::documents::MODULE_URI_PREFIX),
+            name: "Module".to_string(),
+            description: Some(
+                "Summary, public API, imports, and callers of an indexed file (path relative to the workspace root)"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            template.no_annotation(),
+        ]))
+    }
+
+    #[cfg(feature = "context")]
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        use crate::context::VirtualDocument;
+
+        let Some(document) = VirtualDocument::parse(&request.uri) else {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource: {}", request.uri),
+                None,
+            ));
+        };
+        if let Err(e) = self.ensure_indexed().await {
+            return Err(McpError::internal_error(
+                format!("Failed to build index: {}", e),
+                None,
+            ));
+        }
+        let oci = Arc::clone(&self.state.read().await.oci_state);
+        let text = if document == VirtualDocument::Hotspots {
+            // Shells out to git; keep it off the async runtime.
+            tokio::task::spawn_blocking(move || crate::context::documents::hotspots(&oci))
+                .await
+                .ok()
+        } else {
+            crate::context::documents::render(&oci, &document).await
+        };
+        let Some(text) = text else {
+            return Err(McpError::resource_not_found(
+                format!("Not an indexed file: {}", request.uri),
+                None,
+            ));
+        };
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: Some("text/markdown".to_string()),
+                text,
+            }],
+        })
+    }
+
+    #[cfg(feature = "context")]
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<(), McpError> {
+        if crate::context::VirtualDocument::parse(&request.uri).is_none() {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource: {}", request.uri),
+                None,
+            ));
+        }
+        let mut state = self.state.write().await;
+        state.subscriptions.uris.insert(request.uri);
+        state.subscriptions.peer = Some(context.peer);
+        Ok(())
+    }
+
+    #[cfg(feature = "context")]
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<(), McpError> {
+        self.state
+            .write()
+            .await
+            .subscriptions
+            .uris
+            .remove(&request.uri);
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -749,10 +3938,71 @@ pub async fn run_server(workspace_root: PathBuf) -> Result<()> {
     tracing::info!("Starting {} v{}", SERVER_NAME, SERVER_VERSION);
     tracing::info!("Workspace root: {}", workspace_root.display());
 
+    let _writer = if std::env::var("OMNI_SHARED_INDEX").is_ok_and(|v| v == "1") {
+        match crate::shared::WriterLock::acquire(&workspace_root)? {
+            Some(lock) => {
+                tracing::info!("Writing the shared index");
+                Some(lock)
+            }
+            None => {
+                tracing::warn!(
+                    "Shared index already written by pid {}",
+                    crate::shared::writer(&workspace_root).unwrap_or_default()
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
     let server = OciServer::new(workspace_root);
+    if server.restore_snapshot().await {
+        tracing::info!("Restored index snapshot");
+    }
+    #[cfg(feature = "semantic")]
+    {
+        let oci = Arc::clone(&server.state.read().await.oci_state);
+        crate::semantic::spawn_loader(oci, crate::semantic::LoaderConfig::default())?;
+    }
     let service = server.serve(stdio()).await?;
     service.waiting().await?;
 
     tracing::info!("Server shutdown");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_snippet_window() {
+        let contents = "first();\na\nb\nc\nd\nlast();\n";
+        let mut snippets = CallSnippets::default();
+
+        // Clamped at the start of the file
+        assert_eq!(
+            snippets.render(contents, 0).unwrap(),
+            "    >    1 | first();\n         2 | a\n         3 | b\n"
+        );
+        // Clamped at the end, found by byte offset
+        let last = contents.find("last").unwrap();
+        assert_eq!(
+            snippets.render(contents, last + 2).unwrap(),
+            "         4 | c\n         5 | d\n    >    6 | last();\n"
+        );
+        // Past the end of the file
+        assert!(snippets.render(contents, contents.len()).is_none());
+    }
+
+    #[test]
+    fn test_call_snippets_are_capped() {
+        let contents = "call();\n".repeat(MAX_CALL_SNIPPETS + 5);
+        let mut snippets = CallSnippets::default();
+        let rendered = (0..MAX_CALL_SNIPPETS + 5)
+            .filter_map(|i| snippets.render(&contents, i * "call();\n".len()))
+            .count();
+        assert_eq!(rendered, MAX_CALL_SNIPPETS);
+        assert!(snippets.is_full());
+    }
+}
@@ -0,0 +1,251 @@
+//! Versioned migrations for the persisted `.omni/` cache.
+//!
+//! Every manifest records the schema version it was written with. On load,
+//! older caches are upgraded one step at a time after the previous files are
+//! copied to `.omni/backups/v<N>/`. Caches from a newer schema cannot be
+//! migrated and are rebuilt instead.
+//!
+//! To change the persisted format, bump [`SCHEMA_VERSION`] and append a step
+//! to [`MIGRATIONS`]. A step that cannot convert data in place can clear
+//! `manifest.files`, which makes the next index re-parse every file.
+
+use crate::cache::{self, IndexManifest};
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Current schema version of the persisted cache.
+pub const SCHEMA_VERSION: u32 = 7;
+
+/// Directory (inside the cache dir) holding pre-migration backups.
+pub const BACKUP_DIR: &str = "backups";
+
+/// A single upgrade step from `from` to `from + 1`.
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&Path, &mut IndexManifest) -> Result<()>,
+}
+
+/// Ordered migration steps; `MIGRATIONS[i].from == i`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "record schema version in manifest",
+        apply: |_, _| Ok(()),
+    },
+    Migration {
+        from: 1,
+        description: "add module roles to search docs",
+        apply: drop_search_state,
+    },
+    Migration {
+        from: 2,
+        description: "positional BM25 postings",
+        apply: drop_bm25,
+    },
+    Migration {
+        from: 3,
+        description: "add deprecation notes to search docs",
+        apply: drop_search_state,
+    },
+    Migration {
+        from: 4,
+        description: "add doc kinds and config key docs",
+        apply: drop_search_state,
+    },
+    Migration {
+        from: 5,
+        description: "mark search docs from test code",
+        apply: drop_search_state,
+    },
+    Migration {
+        from: 6,
+        description: "split BM25 terms at acronyms and digits, drop stop words",
+        apply: drop_bm25,
+    },
+];
+
+/// Bring the cache under `root` up to [`SCHEMA_VERSION`].
+///
+/// Returns the (possibly migrated) manifest, or `None` if there is no cache.
+/// Fails if the cache was written by a newer schema or a step fails; the
+/// backup of the previous version is left in place either way.
+pub fn migrate(root: &Path) -> Result<Option<IndexManifest>> {
+    let Some(mut manifest) = cache::load_manifest(root)? else {
+        return Ok(None);
+    };
+
+    if manifest.schema_version == SCHEMA_VERSION {
+        return Ok(Some(manifest));
+    }
+    if manifest.schema_version > SCHEMA_VERSION {
+        bail!(
+            "Index schema v{} is newer than supported v{}",
+            manifest.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+
+    let backup = backup_cache(root, manifest.schema_version)?;
+    tracing::info!(
+        "Migrating index schema v{} -> v{} (backup: {})",
+        manifest.schema_version,
+        SCHEMA_VERSION,
+        backup.display()
+    );
+
+    let cache_dir = cache::cache_dir(root);
+    while manifest.schema_version < SCHEMA_VERSION {
+        let step = MIGRATIONS
+            .iter()
+            .find(|m| m.from == manifest.schema_version)
+            .with_context(|| format!("No migration from schema v{}", manifest.schema_version))?;
+        (step.apply)(&cache_dir, &mut manifest).with_context(|| {
+            format!(
+                "Migration v{} -> v{} ({}) failed",
+                step.from,
+                step.from + 1,
+                step.description
+            )
+        })?;
+        manifest.schema_version = step.from + 1;
+    }
+
+    cache::save_manifest(root, &manifest)?;
+    Ok(Some(manifest))
+}
+
+/// Discard the search docs and force every file to be re-parsed, for steps
+/// that change the binary encoding of [`crate::query::SearchDoc`].
+fn drop_search_state(cache_dir: &Path, manifest: &mut IndexManifest) -> Result<()> {
+    for file in [cache::STATE_FILE, cache::BM25_FILE] {
+        let path = cache_dir.join(file);
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    manifest.files.clear();
+    Ok(())
+}
+
+/// Discard the BM25 index only; it is rebuilt from the search docs on the
+/// next index.
+fn drop_bm25(cache_dir: &Path, _manifest: &mut IndexManifest) -> Result<()> {
+    let path = cache_dir.join(cache::BM25_FILE);
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Path of the backup directory for a given schema version.
+pub fn backup_path(root: &Path, version: u32) -> PathBuf {
+    cache::cache_dir(root)
+        .join(BACKUP_DIR)
+        .join(format!("v{version}"))
+}
+
+/// Copy the top-level cache files into the backup directory for `version`.
+fn backup_cache(root: &Path, version: u32) -> Result<PathBuf> {
+    let dir = backup_path(root, version);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup dir: {}", dir.display()))?;
+
+    for entry in fs::read_dir(cache::cache_dir(root))? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let dest = dir.join(entry.file_name());
+        fs::copy(entry.path(), &dest)
+            .with_context(|| format!("Failed to back up {}", entry.path().display()))?;
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrates_legacy_manifest_with_backup() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let dir = cache::ensure_cache_dir(root).unwrap();
+        fs::write(
+            dir.join(cache::MANIFEST_FILE),
+            r#"{"tool_version":"0.1.0","root":null,"files":{"a.rs":{"mtime_ms":1,"size_bytes":2}}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join(cache::BM25_FILE), b"bm25").unwrap();
+
+        let manifest = migrate(root).unwrap().unwrap();
+        assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+        // v1 -> v2 changed the search doc encoding, so files are re-parsed
+        assert!(manifest.files.is_empty());
+        assert!(!dir.join(cache::BM25_FILE).exists());
+
+        let backup = backup_path(root, 0);
+        assert!(backup.join(cache::MANIFEST_FILE).exists());
+        assert_eq!(fs::read(backup.join(cache::BM25_FILE)).unwrap(), b"bm25");
+
+        // Persisted manifest is now current
+        let reloaded = cache::load_manifest(root).unwrap().unwrap();
+        assert_eq!(reloaded.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_tokenizer_change_drops_only_bm25() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let dir = cache::ensure_cache_dir(root).unwrap();
+        let mut manifest = IndexManifest {
+            schema_version: 6,
+            ..Default::default()
+        };
+        manifest.files.insert(
+            "a.rs".into(),
+            cache::FileFingerprint {
+                mtime_ms: 1,
+                size_bytes: 2,
+                content_hash: 3,
+            },
+        );
+        cache::save_manifest(root, &manifest).unwrap();
+        fs::write(dir.join(cache::BM25_FILE), b"bm25").unwrap();
+
+        let manifest = migrate(root).unwrap().unwrap();
+        // Search docs are still valid; only the BM25 terms are rebuilt
+        assert_eq!(manifest.files.len(), 1);
+        assert!(!dir.join(cache::BM25_FILE).exists());
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let manifest = IndexManifest {
+            schema_version: SCHEMA_VERSION + 1,
+            ..Default::default()
+        };
+        cache::save_manifest(root, &manifest).unwrap();
+
+        assert!(migrate(root).is_err());
+        assert!(
+            migrate(tempfile::tempdir().unwrap().path())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_migrations_are_contiguous() {
+        for (i, step) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(step.from, i as u32);
+        }
+        assert_eq!(MIGRATIONS.len() as u32, SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,300 @@
+//! JSON emitted by the CLI (`--json`) and the MCP server (`json: true`):
+//! the response envelope, the result rows both share, and versioning.
+//!
+//! Every JSON response carries a `schema_version`. A consumer that is not
+//! ready for the current shape asks for an older one (`omni --json
+//! --schema-version 1`), and the current response is rewritten down one
+//! version at a time by the steps in [`DOWNGRADES`].
+//!
+//! To rename a field or change a shape, bump [`SCHEMA_VERSION`] and append a
+//! step that turns the new shape back into the previous one. Keep at least
+//! one prior version; raise [`MIN_SCHEMA_VERSION`] to retire older ones.
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::state::OciState;
+use crate::types::{CallEdge, Reference, ReferenceKind, SymbolDef};
+#[cfg(feature = "analysis")]
+use crate::types::{DeadExplanation, DeadReason};
+
+/// Current version of the JSON output.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Oldest version that can still be requested.
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// A rewrite of a response from `to + 1` to `to`.
+struct Downgrade {
+    to: u32,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+/// Ordered downgrade steps; `DOWNGRADES[i].to == MIN_SCHEMA_VERSION + i`.
+const DOWNGRADES: &[Downgrade] = &[Downgrade {
+    to: 1,
+    description: "unversioned output",
+    apply: |response| {
+        response.remove("schema_version");
+    },
+}];
+
+/// Rewrite a current-version `response` (a JSON object) into `version`.
+pub fn downgrade(response: &mut Value, version: u32) -> Result<()> {
+    if !(MIN_SCHEMA_VERSION..=SCHEMA_VERSION).contains(&version) {
+        bail!(
+            "Unsupported output schema v{} (supported: v{}..=v{})",
+            version,
+            MIN_SCHEMA_VERSION,
+            SCHEMA_VERSION
+        );
+    }
+    let Value::Object(fields) = response else {
+        bail!("Output schema versions apply to JSON objects only");
+    };
+    for step in DOWNGRADES.iter().rev().filter(|step| step.to >= version) {
+        tracing::debug!(
+            "Output schema v{} -> v{}: {}",
+            step.to + 1,
+            step.to,
+            step.description
+        );
+        (step.apply)(fields);
+    }
+    if let Some(v) = fields.get_mut("schema_version") {
+        *v = Value::from(version);
+    }
+    Ok(())
+}
+
+/// A successful response: `data`'s fields next to `ok` and the version.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SuccessResponse<T> {
+    pub ok: bool,
+    /// Version of the output format (see `--schema-version`)
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> SuccessResponse<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            ok: true,
+            schema_version: SCHEMA_VERSION,
+            data,
+        }
+    }
+}
+
+/// A successful response tagged with `kind`, the `type` the CLI gives the
+/// same command's output (`symbols`, `calls`, `deadcode`, ...).
+pub fn success_json(kind: &str, data: impl Serialize) -> Result<Value> {
+    let mut response = serde_json::to_value(SuccessResponse::new(data))?;
+    let Value::Object(fields) = &mut response else {
+        bail!("{} output is not a JSON object", kind);
+    };
+    fields.insert("type".to_string(), Value::from(kind));
+    Ok(response)
+}
+
+/// A symbol definition in `symbol` and analysis results.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SymbolResult {
+    pub name: String,
+    /// Name as written in source when it was normalized (e.g. `r#type`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_name: Option<String>,
+    pub kind: String,
+    pub file: String,
+    pub line: usize,
+    /// Deprecation note, present (possibly empty) for deprecated symbols
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Set when the lookup matched a former name of this symbol
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed: Option<String>,
+    /// Shortest path to import a Rust symbol by, through `pub use`
+    /// re-exports (`omni_index::InterventionEngine`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_path: Option<String>,
+    /// Attributes that matched an `--attr` filter
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+}
+
+impl SymbolResult {
+    pub fn new(state: &OciState, symbol: &SymbolDef) -> Self {
+        Self {
+            name: state.resolve(symbol.scoped_name).to_string(),
+            raw_name: symbol.raw_name.clone(),
+            kind: format!("{:?}", symbol.kind),
+            file: symbol.location.file.display().to_string(),
+            line: symbol.location.start_line,
+            deprecated: symbol.deprecation().map(str::to_string),
+            renamed: None,
+            canonical_path: state.canonical_path(symbol),
+            attributes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "analysis")]
+/// A possibly dead symbol, with why nothing live reaches it.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct DeadResult {
+    #[serde(flatten)]
+    pub symbol: SymbolResult,
+    /// `no_callers`, `dead_callers`, `cfg_only`, or `test_only`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+    /// Dead callers from the symbol up to a dead root, for `dead_callers`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<String>,
+    /// Human-readable explanation path
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub why: Vec<String>,
+}
+
+#[cfg(feature = "analysis")]
+impl DeadResult {
+    pub fn new(
+        state: &OciState,
+        symbol: SymbolResult,
+        explanation: Option<&DeadExplanation>,
+    ) -> Self {
+        let Some(explanation) = explanation else {
+            return Self {
+                symbol,
+                reason: None,
+                chain: Vec::new(),
+                why: Vec::new(),
+            };
+        };
+        let chain = match &explanation.reason {
+            DeadReason::DeadCallers { chain, .. } => chain
+                .iter()
+                .map(|&s| state.resolve(s).to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            symbol,
+            reason: Some(explanation.reason.as_str()),
+            chain,
+            why: explanation.steps(state),
+        }
+    }
+}
+
+#[cfg(feature = "analysis")]
+/// A symbol a binary reaches, `depth` calls from its entry point.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct ReachableResult {
+    #[serde(flatten)]
+    pub symbol: SymbolResult,
+    pub depth: u32,
+}
+
+/// One call site in `calls` results.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct CallResult {
+    pub caller: String,
+    pub callee: String,
+    /// Definition the call was bound to from its qualifier and imports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    pub file: String,
+    pub line: usize,
+    /// The call is made from test code
+    pub is_test_context: bool,
+}
+
+impl CallResult {
+    pub fn new(state: &OciState, edge: &CallEdge) -> Self {
+        Self {
+            caller: state.resolve(edge.caller).to_string(),
+            callee: edge.callee_name.clone(),
+            resolved: edge
+                .resolved_callee
+                .map(|key| state.resolve(key).to_string()),
+            file: edge.location.file.display().to_string(),
+            line: edge.location.start_line,
+            is_test_context: edge.is_test_context,
+        }
+    }
+}
+
+/// One usage in `references` results.
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct ReferenceResult {
+    pub kind: ReferenceKind,
+    /// Symbol containing the usage (the caller, for calls)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub file: String,
+    pub line: usize,
+    /// The usage is in test code
+    pub is_test_context: bool,
+}
+
+impl ReferenceResult {
+    pub fn new(state: &OciState, reference: &Reference) -> Self {
+        Self {
+            kind: reference.kind,
+            from: reference.from.map(|key| state.resolve(key).to_string()),
+            file: reference.location.file.display().to_string(),
+            line: reference.location.start_line,
+            is_test_context: reference.is_test_context,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_downgrade_steps_are_contiguous() {
+        for (i, step) in DOWNGRADES.iter().enumerate() {
+            assert_eq!(step.to, MIN_SCHEMA_VERSION + i as u32);
+        }
+        assert_eq!(MIN_SCHEMA_VERSION + DOWNGRADES.len() as u32, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_downgrade_to_unversioned_output() {
+        let current =
+            json!({"ok": true, "schema_version": SCHEMA_VERSION, "type": "search", "results": []});
+
+        let mut same = current.clone();
+        downgrade(&mut same, SCHEMA_VERSION).unwrap();
+        assert_eq!(same, current);
+
+        let mut v1 = current.clone();
+        downgrade(&mut v1, 1).unwrap();
+        assert_eq!(v1, json!({"ok": true, "type": "search", "results": []}));
+
+        assert!(downgrade(&mut current.clone(), 0).is_err());
+        assert!(downgrade(&mut current.clone(), SCHEMA_VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn test_success_json_tags_the_payload() {
+        let response = success_json("completions", json!({"prefix": "par", "results": []}));
+        assert_eq!(
+            response.unwrap(),
+            json!({
+                "ok": true,
+                "schema_version": SCHEMA_VERSION,
+                "type": "completions",
+                "prefix": "par",
+                "results": [],
+            })
+        );
+        assert!(success_json("completions", vec!["par"]).is_err());
+    }
+}
@@ -0,0 +1,536 @@
+//! Structural key extraction for TOML, YAML and JSON config files.
+//!
+//! Config keys (feature flags, service names, limits) are often what an agent
+//! searches for. [`extract_config_keys`] returns every key of a config file
+//! with its dotted path, line and scalar value, which the indexer turns into
+//! `type:config` search docs. The scanners follow nesting (tables,
+//! indentation, braces) but do not validate syntax, so a half-edited file
+//! still yields its keys.
+
+use std::path::Path;
+
+/// Keys kept per file; generated configs can be huge.
+pub const MAX_CONFIG_KEYS: usize = 5000;
+
+/// Values longer than this are truncated in the index.
+const MAX_VALUE_LEN: usize = 120;
+
+/// A supported config file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format of a config file by extension, or `None` for other files and
+    /// for lockfiles, which are generated and not worth searching.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        if name.ends_with("-lock.json") || name.ends_with(".lock.json") {
+            return None;
+        }
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// One key in a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigKey {
+    /// Dotted path from the document root (`database.pool.max_size`)
+    pub path: String,
+    /// 0-based line of the key
+    pub line: usize,
+    /// Byte range of that line
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// Scalar value with quotes removed (arrays and inline tables as
+    /// written); `None` for tables, objects and block scalars
+    pub value: Option<String>,
+}
+
+/// Extract the keys of a config file, in document order.
+pub fn extract_config_keys(format: ConfigFormat, source: &str) -> Vec<ConfigKey> {
+    let mut keys = match format {
+        ConfigFormat::Toml => toml_keys(source),
+        ConfigFormat::Yaml => yaml_keys(source),
+        ConfigFormat::Json => json_keys(source),
+    };
+    keys.truncate(MAX_CONFIG_KEYS);
+    keys
+}
+
+/// Lines with their starting byte offsets, without line terminators.
+fn lines_with_offsets(source: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+    source.split('\n').enumerate().map(move |(i, line)| {
+        let start = offset;
+        offset += line.len() + 1;
+        (i, start, line.strip_suffix('\r').unwrap_or(line))
+    })
+}
+
+fn key(path: String, line: usize, start: usize, text: &str, value: Option<String>) -> ConfigKey {
+    ConfigKey {
+        path,
+        line,
+        start_byte: start,
+        end_byte: start + text.len(),
+        value,
+    }
+}
+
+fn join(prefix: &[String], rest: &[String]) -> String {
+    prefix
+        .iter()
+        .chain(rest)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Byte index of the first `c` outside quotes.
+fn find_unquoted(s: &str, c: char) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, ch) in s.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' && q == '"' {
+                    escaped = true;
+                } else if ch == q {
+                    quote = None;
+                }
+            }
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None if ch == c => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Drop a trailing `#` comment.
+fn strip_comment(s: &str) -> &str {
+    find_unquoted(s, '#').map_or(s, |i| &s[..i]).trim()
+}
+
+/// Remove matching surrounding quotes.
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    for q in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    s
+}
+
+fn scalar(value: &str) -> Option<String> {
+    let value = unquote(value);
+    (!value.is_empty()).then(|| value.chars().take(MAX_VALUE_LEN).collect())
+}
+
+/// Split a dotted TOML key (`a."b.c".d`) into its parts.
+fn split_dotted(s: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let end = find_unquoted(rest, '.').unwrap_or(rest.len());
+        parts.push(unquote(&rest[..end]).to_string());
+        rest = rest.get(end + 1..).unwrap_or("").trim();
+    }
+    parts
+}
+
+/// Net `[`/`{` nesting opened by `s`, outside strings and comments.
+fn bracket_depth(s: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for ch in s.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' && q == '"' {
+                    escaped = true;
+                } else if ch == q {
+                    quote = None;
+                }
+            }
+            None => match ch {
+                '"' | '\'' => quote = Some(ch),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                '#' => break,
+                _ => {}
+            },
+        }
+    }
+    depth
+}
+
+fn toml_keys(source: &str) -> Vec<ConfigKey> {
+    let mut keys = Vec::new();
+    let mut table: Vec<String> = Vec::new();
+    let mut multiline_string: Option<&str> = None;
+    let mut open_brackets = 0;
+
+    for (line_no, start, line) in lines_with_offsets(source) {
+        let trimmed = line.trim();
+        if let Some(delim) = multiline_string {
+            if trimmed.contains(delim) {
+                multiline_string = None;
+            }
+            continue;
+        }
+        if open_brackets > 0 {
+            open_brackets += bracket_depth(trimmed);
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if trimmed.starts_with('[') {
+            let header = strip_comment(trimmed);
+            let name = header.trim_start_matches('[').trim_end_matches(']').trim();
+            table = split_dotted(name);
+            keys.push(key(join(&table, &[]), line_no, start, line, None));
+            continue;
+        }
+
+        let Some(eq) = find_unquoted(trimmed, '=') else {
+            continue;
+        };
+        let parts = split_dotted(&trimmed[..eq]);
+        let raw = strip_comment(&trimmed[eq + 1..]);
+        for delim in ["\"\"\"", "'''"] {
+            if raw.starts_with(delim) && raw.matches(delim).count() == 1 {
+                multiline_string = Some(delim);
+            }
+        }
+        if multiline_string.is_none() && raw.starts_with('[') {
+            open_brackets = bracket_depth(raw).max(0);
+        }
+        let path = join(&table, &parts);
+        let value = match multiline_string {
+            Some(_) => None,
+            None if open_brackets > 0 => None,
+            None => scalar(raw),
+        };
+
+        // Inline tables: index their keys too
+        let inline = raw
+            .strip_prefix('{')
+            .and_then(|r| r.strip_suffix('}'))
+            .map(str::to_string);
+        keys.push(key(path.clone(), line_no, start, line, value));
+        if let Some(inline) = inline {
+            for field in inline.split(',') {
+                let Some(eq) = find_unquoted(field, '=') else {
+                    continue;
+                };
+                let child = format!("{}.{}", path, join(&[], &split_dotted(&field[..eq])));
+                keys.push(key(child, line_no, start, line, scalar(&field[eq + 1..])));
+            }
+        }
+    }
+    keys
+}
+
+fn yaml_keys(source: &str) -> Vec<ConfigKey> {
+    let mut keys = Vec::new();
+    // (indent, key) of the enclosing mappings
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut block_scalar: Option<usize> = None;
+
+    for (line_no, start, line) in lines_with_offsets(source) {
+        let trimmed = line.trim_start();
+        let mut indent = line.len() - trimmed.len();
+        if let Some(block_indent) = block_scalar {
+            if trimmed.is_empty() || indent > block_indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("---")
+            || trimmed.starts_with("...")
+        {
+            continue;
+        }
+
+        // `- key: value` starts a mapping inside a sequence item
+        let mut content = trimmed;
+        while let Some(rest) = content.strip_prefix("- ") {
+            let rest_trimmed = rest.trim_start();
+            indent += content.len() - rest_trimmed.len();
+            content = rest_trimmed;
+        }
+
+        let Some(colon) = yaml_key_colon(content) else {
+            continue;
+        };
+        let name = unquote(&content[..colon]).to_string();
+        let raw = strip_comment(&content[colon + 1..]);
+
+        while stack.last().is_some_and(|(i, _)| *i >= indent) {
+            stack.pop();
+        }
+        let parents: Vec<String> = stack.iter().map(|(_, k)| k.clone()).collect();
+        let path = join(&parents, std::slice::from_ref(&name));
+
+        let value = if raw.starts_with('|') || raw.starts_with('>') {
+            block_scalar = Some(indent);
+            None
+        } else {
+            scalar(raw)
+        };
+        keys.push(key(path, line_no, start, line, value));
+        stack.push((indent, name));
+    }
+    keys
+}
+
+/// Byte index of the `:` ending a mapping key (followed by a space or the
+/// end of the line), outside quotes.
+fn yaml_key_colon(s: &str) -> Option<usize> {
+    let mut offset = 0;
+    loop {
+        let i = offset + find_unquoted(&s[offset..], ':')?;
+        if s[i + 1..].is_empty() || s[i + 1..].starts_with([' ', '\t']) {
+            return (i > 0 && !s.starts_with(['{', '['])).then_some(i);
+        }
+        offset = i + 1;
+    }
+}
+
+fn json_keys(source: &str) -> Vec<ConfigKey> {
+    struct Frame {
+        path: Vec<String>,
+        is_object: bool,
+    }
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |byte: usize| line_starts.partition_point(|&s| s <= byte) - 1;
+    let line_span = |line: usize| {
+        let start = line_starts[line];
+        let end = line_starts
+            .get(line + 1)
+            .map_or(source.len(), |next| next - 1);
+        let text = source[start..end]
+            .strip_suffix('\r')
+            .unwrap_or(&source[start..end]);
+        (start, text)
+    };
+
+    let mut keys: Vec<ConfigKey> = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    // Key waiting for its value, as (index into `keys`, path)
+    let mut pending: Option<(usize, Vec<String>)> = None;
+    let mut expect_key = false;
+    let bytes = source.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => {
+                let is_object = bytes[i] == b'{';
+                let path = match pending.take() {
+                    Some((_, path)) => path,
+                    None => stack.last().map(|f| f.path.clone()).unwrap_or_default(),
+                };
+                stack.push(Frame { path, is_object });
+                expect_key = is_object;
+                i += 1;
+            }
+            b'}' | b']' => {
+                stack.pop();
+                expect_key = false;
+                i += 1;
+            }
+            b',' => {
+                expect_key = stack.last().is_some_and(|f| f.is_object);
+                i += 1;
+            }
+            b'"' => {
+                let begin = i;
+                let mut end = i + 1;
+                while end < bytes.len() && bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                let text = source.get(begin + 1..end.min(bytes.len())).unwrap_or("");
+                i = end + 1;
+                if expect_key {
+                    let mut path = stack.last().map(|f| f.path.clone()).unwrap_or_default();
+                    path.push(text.replace("\\\"", "\""));
+                    let line = line_of(begin);
+                    let (start, line_text) = line_span(line);
+                    keys.push(key(path.join("."), line, start, line_text, None));
+                    pending = Some((keys.len() - 1, path));
+                    expect_key = false;
+                } else if let Some((index, _)) = pending.take() {
+                    keys[index].value = scalar(text);
+                }
+            }
+            b':' => i += 1,
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                // Number, true, false, null
+                let end = bytes[i..]
+                    .iter()
+                    .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+                    .map_or(bytes.len(), |n| i + n);
+                if let Some((index, _)) = pending.take() {
+                    keys[index].value = scalar(&source[i..end]);
+                }
+                i = end.max(i + 1);
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(keys: &[ConfigKey]) -> Vec<(&str, Option<&str>, usize)> {
+        keys.iter()
+            .map(|k| (k.path.as_str(), k.value.as_deref(), k.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_toml_keys() {
+        let source = r#"
+name = "svc" # trailing comment
+[retry]
+max_attempts = 5
+backoff = { base_ms = 100, jitter = true }
+hosts = [
+  "a",
+  "b",
+]
+notes = """
+key = not a key
+"""
+[server."tls.v2"]
+enabled = false
+"#;
+        assert_eq!(
+            paths(&extract_config_keys(ConfigFormat::Toml, source)),
+            vec![
+                ("name", Some("svc"), 1),
+                ("retry", None, 2),
+                ("retry.max_attempts", Some("5"), 3),
+                ("retry.backoff", Some("{ base_ms = 100, jitter = true }"), 4),
+                ("retry.backoff.base_ms", Some("100"), 4),
+                ("retry.backoff.jitter", Some("true"), 4),
+                ("retry.hosts", None, 5),
+                ("retry.notes", None, 9),
+                ("server.tls.v2", None, 12),
+                ("server.tls.v2.enabled", Some("false"), 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yaml_keys() {
+        let source = "\
+# service config
+service:
+  name: api
+  retry_max_attempts: 3 # per request
+  description: |
+    multi: line
+    text
+  replicas:
+    - name: primary
+      zone: 'us-east'
+    - plain item
+ports: [80, 443]
+";
+        assert_eq!(
+            paths(&extract_config_keys(ConfigFormat::Yaml, source)),
+            vec![
+                ("service", None, 1),
+                ("service.name", Some("api"), 2),
+                ("service.retry_max_attempts", Some("3"), 3),
+                ("service.description", None, 4),
+                ("service.replicas", None, 7),
+                ("service.replicas.name", Some("primary"), 8),
+                ("service.replicas.zone", Some("us-east"), 9),
+                ("ports", Some("[80, 443]"), 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_keys() {
+        let source = r#"{
+  "name": "web",
+  "features": {"dark_mode": true, "beta": null},
+  "servers": [
+    {"host": "a.example", "port": 8080}
+  ],
+  "quote\"d": "x"
+}"#;
+        let keys = extract_config_keys(ConfigFormat::Json, source);
+        assert_eq!(
+            paths(&keys),
+            vec![
+                ("name", Some("web"), 1),
+                ("features", None, 2),
+                ("features.dark_mode", Some("true"), 2),
+                ("features.beta", Some("null"), 2),
+                ("servers", None, 3),
+                ("servers.host", Some("a.example"), 4),
+                ("servers.port", Some("8080"), 4),
+                ("quote\"d", Some("x"), 6),
+            ]
+        );
+        assert_eq!(
+            &source[keys[0].start_byte..keys[0].end_byte],
+            r#"  "name": "web","#
+        );
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("deploy/app.yml")),
+            Some(ConfigFormat::Yaml)
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("Cargo.toml")),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("package-lock.json")),
+            None
+        );
+        assert_eq!(ConfigFormat::from_path(Path::new("src/main.rs")), None);
+    }
+}
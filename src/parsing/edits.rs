@@ -0,0 +1,464 @@
+//! Incremental re-parsing from edit deltas.
+//!
+//! The indexer can keep the parsed tree of recently indexed ("hot") files.
+//! When such a file changes, the difference from its previous contents
+//! becomes a tree-sitter [`InputEdit`]; the old tree is edited and handed
+//! back to the parser, which then only re-parses the changed region. For
+//! languages that implement [`LanguageParser::extract_item`], only the
+//! top-level items the edit touched are re-extracted, and the file's other
+//! symbols and calls are carried over with their locations shifted.
+
+use super::LanguageParser;
+use crate::types::{CallEdge, Location, SymbolDef};
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tree_sitter::{InputEdit, Language, Node, Parser, Point, Tree};
+
+/// Hot files kept by default when incremental parsing is enabled.
+pub const HOT_TREE_CAPACITY: usize = 64;
+
+/// Node kinds that attach to the following item (attributes, doc comments,
+/// `omni:ignore` directives), so editing them re-extracts that item.
+const LEADING_KINDS: &[&str] = &[
+    "attribute_item",
+    "inner_attribute_item",
+    "line_comment",
+    "block_comment",
+    "comment",
+];
+
+/// A file's last parse: its source, tree, and extracted symbols and calls.
+pub struct HotTree {
+    pub language: Language,
+    pub source: String,
+    pub tree: Tree,
+    pub symbols: Vec<SymbolDef>,
+    pub calls: Vec<CallEdge>,
+}
+
+/// Least-recently-used cache of [`HotTree`]s by file.
+pub struct TreeCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<PathBuf, (u64, HotTree)>,
+}
+
+impl TreeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Remove and return the entry for `path`.
+    pub fn take(&mut self, path: &Path) -> Option<HotTree> {
+        self.entries.remove(path).map(|(_, hot)| hot)
+    }
+
+    /// Store `hot` for `path`, evicting the least recently stored file when
+    /// full.
+    pub fn insert(&mut self, path: PathBuf, hot: HotTree) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        self.entries.insert(path, (self.tick, hot));
+        if self.entries.len() > self.capacity {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (tick, _))| *tick)
+                .map(|(path, _)| path.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for TreeCache {
+    fn default() -> Self {
+        Self::new(HOT_TREE_CAPACITY)
+    }
+}
+
+/// The single edit turning `old` into `new`: everything between their common
+/// prefix and common suffix. `None` if they are identical.
+pub fn diff_edit(old: &str, new: &str) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+    let mut start = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(start) || !new.is_char_boundary(start) {
+        start -= 1;
+    }
+    let max_suffix = old.len().min(new.len()) - start;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    let old_end_byte = old.len() - suffix;
+    let new_end_byte = new.len() - suffix;
+    Some(InputEdit {
+        start_byte: start,
+        old_end_byte,
+        new_end_byte,
+        start_position: point_at(old, start),
+        old_end_position: point_at(old, old_end_byte),
+        new_end_position: point_at(new, new_end_byte),
+    })
+}
+
+/// Row and byte column of `byte` in `source`.
+fn point_at(source: &str, byte: usize) -> Point {
+    let before = &source.as_bytes()[..byte];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    Point::new(row, byte - line_start)
+}
+
+/// Result of [`reparse`].
+pub struct Reparsed {
+    pub tree: Tree,
+    /// Spliced symbols and calls, or `None` if the caller must extract them
+    /// from `tree` (unsupported language or an edit spanning items)
+    pub extracted: Option<(Vec<SymbolDef>, Vec<CallEdge>)>,
+}
+
+/// Re-parse `source` by applying its diff from `hot.source` to `hot.tree`.
+pub fn reparse(
+    parser: &mut Parser,
+    lang: &dyn LanguageParser,
+    mut hot: HotTree,
+    source: &str,
+    file: &Path,
+    interner: &lasso::ThreadedRodeo,
+) -> Result<Reparsed> {
+    let Some(edit) = diff_edit(&hot.source, source) else {
+        return Ok(Reparsed {
+            tree: hot.tree,
+            extracted: Some((hot.symbols, hot.calls)),
+        });
+    };
+    hot.tree.edit(&edit);
+    let tree = parser
+        .parse(source, Some(&hot.tree))
+        .ok_or_else(|| anyhow!("tree-sitter produced no tree"))?;
+    let extracted = splice(lang, &hot, &tree, source, &edit, file, interner);
+    Ok(Reparsed { tree, extracted })
+}
+
+/// Re-extract the top-level items of `tree` touched by `edit` and carry over
+/// everything else from `hot`.
+fn splice(
+    lang: &dyn LanguageParser,
+    hot: &HotTree,
+    tree: &Tree,
+    source: &str,
+    edit: &InputEdit,
+    file: &Path,
+    interner: &lasso::ThreadedRodeo,
+) -> Option<(Vec<SymbolDef>, Vec<CallEdge>)> {
+    let items = touched_items(tree.root_node(), edit);
+    let region_start = items
+        .iter()
+        .map(|n| n.start_byte())
+        .fold(edit.start_byte, usize::min);
+    let region_end = items
+        .iter()
+        .map(|n| n.end_byte())
+        .fold(edit.new_end_byte, usize::max);
+    // The same region before the edit
+    let old_region_end = region_end + edit.old_end_byte - edit.new_end_byte;
+
+    let mut symbols = Vec::new();
+    let mut calls = Vec::new();
+    for item in &items {
+        let (item_symbols, item_calls) = lang.extract_item(*item, source, file, interner)?;
+        symbols.extend(item_symbols);
+        calls.extend(item_calls);
+    }
+
+    let shift = Shift::new(&hot.source, edit, old_region_end);
+    let mut kept_symbols = Vec::with_capacity(hot.symbols.len());
+    for symbol in &hot.symbols {
+        let location = shift.carry(&symbol.location, region_start, old_region_end)?;
+        if let Some(location) = location {
+            let extended_location = match &symbol.extended_location {
+                Some(extended) => Some(shift.carry(extended, region_start, old_region_end)??),
+                None => None,
+            };
+            kept_symbols.push(SymbolDef {
+                location,
+                extended_location,
+                ..symbol.clone()
+            });
+        }
+    }
+    let mut kept_calls = Vec::with_capacity(hot.calls.len());
+    for call in &hot.calls {
+        if let Some(location) = shift.carry(&call.location, region_start, old_region_end)? {
+            kept_calls.push(CallEdge {
+                location,
+                ..call.clone()
+            });
+        }
+    }
+
+    kept_symbols.extend(symbols);
+    kept_symbols.sort_by_key(|s| s.location.start_byte);
+    kept_calls.extend(calls);
+    kept_calls.sort_by_key(|c| c.location.start_byte);
+    Some((kept_symbols, kept_calls))
+}
+
+/// Top-level children of `root` overlapping the edited range, plus the item
+/// right after it: an item reads its attributes, doc comments, and ignore
+/// directives from the nodes above it, which the edit may have changed.
+fn touched_items<'t>(root: Node<'t>, edit: &InputEdit) -> Vec<Node<'t>> {
+    let mut cursor = root.walk();
+    let children: Vec<Node<'t>> = root.named_children(&mut cursor).collect();
+    let mut items = Vec::new();
+    let mut include_next = true;
+    for child in children {
+        if child.end_byte() < edit.start_byte {
+            continue;
+        }
+        let touched = child.start_byte() <= edit.new_end_byte;
+        if !touched && !include_next {
+            break;
+        }
+        items.push(child);
+        include_next = touched || LEADING_KINDS.contains(&child.kind());
+    }
+    items
+}
+
+/// Maps locations after the edited region from old to new coordinates.
+struct Shift {
+    bytes: isize,
+    lines: isize,
+    /// Old row on which the region ends; locations starting there would need
+    /// column adjustments, so splicing gives up on them
+    region_end_row: usize,
+}
+
+impl Shift {
+    fn new(old_source: &str, edit: &InputEdit, old_region_end: usize) -> Self {
+        Self {
+            bytes: edit.new_end_byte as isize - edit.old_end_byte as isize,
+            lines: edit.new_end_position.row as isize - edit.old_end_position.row as isize,
+            region_end_row: point_at(old_source, old_region_end).row,
+        }
+    }
+
+    /// `Some(Some(loc))` to keep a location (shifted if after the region),
+    /// `Some(None)` to drop one inside the region, and `None` if it straddles
+    /// the region and splicing must be abandoned.
+    fn carry(&self, loc: &Location, start: usize, old_end: usize) -> Option<Option<Location>> {
+        if loc.end_byte <= start {
+            return Some(Some(loc.clone()));
+        }
+        if loc.start_byte >= old_end {
+            if loc.start_line == self.region_end_row {
+                return None;
+            }
+            let shift = |v: usize, by: isize| (v as isize + by) as usize;
+            return Some(Some(Location {
+                start_byte: shift(loc.start_byte, self.bytes),
+                end_byte: shift(loc.end_byte, self.bytes),
+                start_line: shift(loc.start_line, self.lines),
+                end_line: shift(loc.end_line, self.lines),
+                ..loc.clone()
+            }));
+        }
+        if loc.start_byte >= start && loc.end_byte <= old_end {
+            return Some(None);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::rust::RustParser;
+
+    fn parse_full(
+        lang: &RustParser,
+        source: &str,
+        interner: &lasso::ThreadedRodeo,
+    ) -> (Tree, Vec<SymbolDef>, Vec<CallEdge>) {
+        let mut parser = Parser::new();
+        parser.set_language(&lang.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let file = Path::new("lib.rs");
+        let symbols = lang.extract_symbols(&tree, source, file, interner).unwrap();
+        let calls = lang.extract_calls(&tree, source, file, interner).unwrap();
+        (tree, symbols, calls)
+    }
+
+    fn summary(
+        symbols: &[SymbolDef],
+        calls: &[CallEdge],
+        interner: &lasso::ThreadedRodeo,
+    ) -> Vec<String> {
+        let mut out: Vec<String> = symbols
+            .iter()
+            .map(|s| {
+                let l = &s.location;
+                format!(
+                    "{} {:?} {}..{} {}:{}-{}:{} {:?}",
+                    interner.resolve(&s.scoped_name),
+                    s.attributes,
+                    l.start_byte,
+                    l.end_byte,
+                    l.start_line,
+                    l.start_col,
+                    l.end_line,
+                    l.end_col,
+                    s.ignores
+                )
+            })
+            .chain(calls.iter().map(|c| {
+                format!(
+                    "{} -> {} at {}..{} {}:{}",
+                    interner.resolve(&c.caller),
+                    c.callee_name,
+                    c.location.start_byte,
+                    c.location.end_byte,
+                    c.location.start_line,
+                    c.location.start_col
+                )
+            }))
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn test_diff_edit() {
+        let edit = diff_edit("fn a() {}\nfn b() {}\n", "fn a() {}\nfn bc() {}\n").unwrap();
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (14, 14, 15)
+        );
+        assert_eq!(edit.start_position, Point::new(1, 4));
+        assert!(diff_edit("same", "same").is_none());
+
+        // Never splits a multi-byte character
+        let edit = diff_edit("é", "è").unwrap();
+        assert_eq!((edit.start_byte, edit.old_end_byte), (0, 2));
+    }
+
+    #[test]
+    fn test_reparse_matches_full_extraction() {
+        let lang = RustParser::new();
+        let interner = lasso::ThreadedRodeo::default();
+        let original = "use std::fmt;\n\nfn first() { second(); }\n\n/// Docs\n#[inline]\nfn second() { third(); }\n\nmod inner {\n    pub fn third() {}\n}\n\nstruct Point { x: i32 }\n\nimpl Point {\n    fn norm(&self) -> i32 { self.x }\n}\n";
+        let edits = [
+            // Rename inside one item, changing its length
+            original.replace(
+                "fn second() { third(); }",
+                "fn second_renamed() { third(); }",
+            ),
+            // Add lines inside a nested module
+            original.replace(
+                "    pub fn third() {}\n",
+                "    pub fn third() {}\n\n    fn extra() { third(); }\n",
+            ),
+            // Edit an attribute, which belongs to the following item
+            original.replace("#[inline]", "#[inline(always)]\n// omni:ignore dead_code"),
+            // Insert a new item between others
+            original.replace("struct Point", "fn added() {}\n\nstruct Point"),
+            // Delete an item
+            original.replace("fn first() { second(); }\n\n", ""),
+            // Edit spanning two items
+            original.replace(
+                "second(); }\n\n/// Docs\n#[inline]\nfn second",
+                "fourth(); }\n\nfn second",
+            ),
+        ];
+
+        for edited in edits {
+            let (tree, symbols, calls) = parse_full(&lang, original, &interner);
+            let hot = HotTree {
+                language: lang.language(),
+                source: original.to_string(),
+                tree,
+                symbols,
+                calls,
+            };
+            let mut parser = Parser::new();
+            parser.set_language(&lang.language()).unwrap();
+            let reparsed = reparse(
+                &mut parser,
+                &lang,
+                hot,
+                &edited,
+                Path::new("lib.rs"),
+                &interner,
+            )
+            .unwrap();
+            let (spliced_symbols, spliced_calls) = reparsed.extracted.unwrap();
+            let (_, symbols, calls) = parse_full(&lang, &edited, &interner);
+            assert_eq!(
+                summary(&spliced_symbols, &spliced_calls, &interner),
+                summary(&symbols, &calls, &interner),
+                "edited source:\n{}",
+                edited
+            );
+        }
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recent() {
+        let lang = RustParser::new();
+        let interner = lasso::ThreadedRodeo::default();
+        let hot = || {
+            let (tree, symbols, calls) = parse_full(&lang, "fn a() {}\n", &interner);
+            HotTree {
+                language: lang.language(),
+                source: "fn a() {}\n".to_string(),
+                tree,
+                symbols,
+                calls,
+            }
+        };
+        let mut cache = TreeCache::new(2);
+        cache.insert(PathBuf::from("a.rs"), hot());
+        cache.insert(PathBuf::from("b.rs"), hot());
+        cache.insert(PathBuf::from("c.rs"), hot());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.take(Path::new("a.rs")).is_none());
+        assert!(cache.take(Path::new("c.rs")).is_some());
+    }
+}
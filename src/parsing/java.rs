@@ -0,0 +1,655 @@
+//! Java language parser using tree-sitter.
+//!
+//! Covers classes, interfaces, enums, records, methods, fields, imports and
+//! call expressions. Generics are kept as signature text, not modeled.
+
+use super::{LanguageParser, intern_identifier, normalize_identifier, raw_spelling};
+use crate::types::*;
+use anyhow::Result;
+use lasso::ThreadedRodeo;
+use std::path::Path;
+use tree_sitter::{Language, Node, Tree};
+
+/// Java source code parser.
+pub struct JavaParser {}
+
+impl JavaParser {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for JavaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageParser for JavaParser {
+    fn language(&self) -> Language {
+        tree_sitter_java::LANGUAGE.into()
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["java"]
+    }
+
+    fn first_line(&self) -> usize {
+        1
+    }
+
+    fn extract_symbols(
+        &self,
+        tree: &Tree,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Result<Vec<SymbolDef>> {
+        let bytes = source.as_bytes();
+        let root = tree.root_node();
+        let mut symbols = Vec::new();
+        let mut scope_stack = vec![package_scope(bytes, root, file, "package_declaration")];
+
+        walk_java_symbols(
+            root,
+            bytes,
+            file,
+            &mut scope_stack,
+            None,
+            interner,
+            &mut symbols,
+        );
+
+        super::attach_ignore_directives(&mut symbols, source);
+        super::attach_extended_locations(&mut symbols, source);
+        Ok(symbols)
+    }
+
+    fn extract_calls(
+        &self,
+        tree: &Tree,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Result<Vec<CallEdge>> {
+        let bytes = source.as_bytes();
+        let root = tree.root_node();
+        let mut calls = Vec::new();
+        let mut scope_stack = vec![package_scope(bytes, root, file, "package_declaration")];
+        let mut fn_stack = Vec::<String>::new();
+
+        walk_java_calls(
+            root,
+            bytes,
+            file,
+            &mut scope_stack,
+            &mut fn_stack,
+            interner,
+            &mut calls,
+        );
+
+        Ok(calls)
+    }
+
+    fn extract_imports(&self, tree: &Tree, source: &str, file: &Path) -> Result<Vec<ImportInfo>> {
+        let bytes = source.as_bytes();
+        let mut imports = Vec::new();
+
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        for node in root.children(&mut cursor) {
+            if node.kind() != "import_declaration" {
+                continue;
+            }
+            let mut inner = node.walk();
+            let Some(path_node) = node
+                .named_children(&mut inner)
+                .find(|n| matches!(n.kind(), "scoped_identifier" | "identifier"))
+            else {
+                continue;
+            };
+            let Some(path) = text_of(bytes, path_node) else {
+                continue;
+            };
+            let is_glob = text_of(bytes, node).is_some_and(|t| t.contains('*'));
+            let name = if is_glob {
+                "*".to_string()
+            } else {
+                path.rsplit('.').next().unwrap_or(&path).to_string()
+            };
+
+            imports.push(ImportInfo {
+                path,
+                name,
+                original_name: None,
+                is_reexport: false,
+                is_glob,
+                location: location_for(node, file),
+            });
+        }
+
+        Ok(imports)
+    }
+}
+
+// ============================================================================
+// Helper Functions (shared with the Kotlin parser)
+// ============================================================================
+
+pub(super) fn location_for(node: Node, file: &Path) -> Location {
+    let start = node.start_position();
+    let end = node.end_position();
+    Location::new(file.to_path_buf(), node.start_byte(), node.end_byte()).with_positions(
+        start.row + 1,
+        start.column + 1,
+        end.row + 1,
+        end.column + 1,
+    )
+}
+
+pub(super) fn text_of(bytes: &[u8], node: Node) -> Option<String> {
+    std::str::from_utf8(&bytes[node.start_byte()..node.end_byte()])
+        .ok()
+        .map(|s| s.to_string())
+}
+
+pub(super) fn field_text(bytes: &[u8], node: Node, field: &str) -> Option<String> {
+    node.child_by_field_name(field)
+        .and_then(|n| text_of(bytes, n))
+}
+
+/// Root scope for a JVM file: its package, or a file scope when absent.
+pub(super) fn package_scope(bytes: &[u8], root: Node, file: &Path, package_kind: &str) -> String {
+    let mut cursor = root.walk();
+    let package = root
+        .children(&mut cursor)
+        .find(|n| n.kind() == package_kind)
+        .and_then(|n| {
+            let mut inner = n.walk();
+            n.named_children(&mut inner)
+                .find(|c| c.kind().ends_with("identifier"))
+                .and_then(|c| text_of(bytes, c))
+        });
+    package.unwrap_or_else(|| super::typescript::file_scope_for(file))
+}
+
+pub(super) fn make_scoped_name(scope_stack: &[String], name: &str) -> String {
+    let mut full = scope_stack.join("::");
+    if !full.is_empty() {
+        full.push_str("::");
+    }
+    full.push_str(name);
+    full
+}
+
+/// KDoc/Javadoc comment directly preceding a declaration.
+pub(super) fn doc_comment_before(bytes: &[u8], node: Node) -> Option<String> {
+    let prev = node.prev_named_sibling()?;
+    if prev.kind() != "block_comment" {
+        return None;
+    }
+    let text = text_of(bytes, prev)?;
+    text.starts_with("/**").then(|| text.trim().to_string())
+}
+
+/// Last identifier in a type expression, ignoring generics
+/// (e.g. `java.util.List<String>` -> `List`).
+pub(super) fn type_name_of(text: &str) -> Option<String> {
+    let base = text.split('<').next()?.trim();
+    let name = base
+        .rsplit(|c: char| c == '.' || c.is_whitespace())
+        .next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn modifiers_of(node: Node) -> Option<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).find(|c| c.kind() == "modifiers")
+}
+
+fn has_modifier(bytes: &[u8], node: Node, keyword: &str) -> bool {
+    modifiers_of(node)
+        .and_then(|m| text_of(bytes, m))
+        .is_some_and(|t| t.split_whitespace().any(|w| w == keyword))
+}
+
+fn java_visibility(bytes: &[u8], node: Node) -> Visibility {
+    if has_modifier(bytes, node, "public") {
+        Visibility::Public
+    } else if has_modifier(bytes, node, "protected") {
+        Visibility::Restricted
+    } else if has_modifier(bytes, node, "private") {
+        Visibility::Private
+    } else {
+        // Package-private
+        Visibility::Crate
+    }
+}
+
+fn java_annotations(bytes: &[u8], node: Node) -> Vec<Attribute> {
+    let Some(modifiers) = modifiers_of(node) else {
+        return Vec::new();
+    };
+    let mut cursor = modifiers.walk();
+    modifiers
+        .named_children(&mut cursor)
+        .filter(|c| matches!(c.kind(), "marker_annotation" | "annotation"))
+        .filter_map(|c| text_of(bytes, c))
+        .filter_map(|text| Attribute::parse(&text))
+        .collect()
+}
+
+fn java_signature(bytes: &[u8], node: Node) -> Signature {
+    let mut sig = Signature::default();
+    if let Some(params) = node.child_by_field_name("parameters") {
+        let mut cursor = params.walk();
+        for param in params.named_children(&mut cursor) {
+            if matches!(param.kind(), "formal_parameter" | "spread_parameter") {
+                if let Some(text) = text_of(bytes, param) {
+                    sig.params.push(text.trim().to_string());
+                }
+            }
+        }
+    }
+    sig.return_type = field_text(bytes, node, "type");
+    sig.generics = field_text(bytes, node, "type_parameters");
+    sig
+}
+
+fn java_symbol(
+    bytes: &[u8],
+    file: &Path,
+    node: Node,
+    scope_stack: &[String],
+    name: &str,
+    kind: SymbolKind,
+    parent: Option<InternedString>,
+    interner: &ThreadedRodeo,
+) -> SymbolDef {
+    let signature = matches!(kind, SymbolKind::Method | SymbolKind::Function)
+        .then(|| java_signature(bytes, node));
+    SymbolDef {
+        name: intern_identifier(interner, name),
+        scoped_name: intern_identifier(interner, &make_scoped_name(scope_stack, name)),
+        kind,
+        location: location_for(node, file),
+        extended_location: None,
+        signature,
+        visibility: java_visibility(bytes, node),
+        attributes: java_annotations(bytes, node),
+        doc_comment: doc_comment_before(bytes, node),
+        parent,
+        ignores: Vec::new(),
+        raw_name: raw_spelling(name),
+        is_test_context: false,
+    }
+}
+
+// ============================================================================
+// Symbol Extraction Walker
+// ============================================================================
+
+fn walk_java_symbols(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    owner: Option<InternedString>,
+    interner: &ThreadedRodeo,
+    symbols: &mut Vec<SymbolDef>,
+) {
+    let kind = node.kind();
+
+    let type_kind = match kind {
+        "class_declaration" | "record_declaration" => Some(SymbolKind::Struct),
+        "interface_declaration" | "annotation_type_declaration" => Some(SymbolKind::Trait),
+        "enum_declaration" => Some(SymbolKind::Enum),
+        _ => None,
+    };
+    if let Some(type_kind) = type_kind {
+        if let Some(name) = field_text(bytes, node, "name") {
+            let symbol = java_symbol(
+                bytes,
+                file,
+                node,
+                scope_stack,
+                &name,
+                type_kind,
+                owner,
+                interner,
+            );
+            let scoped = symbol.scoped_name;
+            symbols.push(symbol);
+
+            scope_stack.push(name);
+            walk_java_children(
+                node,
+                bytes,
+                file,
+                scope_stack,
+                Some(scoped),
+                interner,
+                symbols,
+            );
+            scope_stack.pop();
+            return;
+        }
+    }
+
+    match kind {
+        "method_declaration" | "constructor_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                let symbol_kind = if owner.is_some() {
+                    SymbolKind::Method
+                } else {
+                    SymbolKind::Function
+                };
+                symbols.push(java_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    symbol_kind,
+                    owner,
+                    interner,
+                ));
+
+                // Local and anonymous classes live under the method scope
+                scope_stack.push(name);
+                walk_java_children(node, bytes, file, scope_stack, None, interner, symbols);
+                scope_stack.pop();
+                return;
+            }
+        }
+        "field_declaration" | "constant_declaration" => {
+            let is_const = kind == "constant_declaration"
+                || (has_modifier(bytes, node, "static") && has_modifier(bytes, node, "final"));
+            let symbol_kind = if is_const {
+                SymbolKind::Const
+            } else {
+                SymbolKind::Field
+            };
+            let mut cursor = node.walk();
+            for declarator in node.children_by_field_name("declarator", &mut cursor) {
+                if let Some(name) = field_text(bytes, declarator, "name") {
+                    symbols.push(java_symbol(
+                        bytes,
+                        file,
+                        node,
+                        scope_stack,
+                        &name,
+                        symbol_kind,
+                        owner,
+                        interner,
+                    ));
+                }
+            }
+        }
+        "enum_constant" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                symbols.push(java_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    SymbolKind::Variant,
+                    owner,
+                    interner,
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    walk_java_children(node, bytes, file, scope_stack, owner, interner, symbols);
+}
+
+fn walk_java_children(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    owner: Option<InternedString>,
+    interner: &ThreadedRodeo,
+    symbols: &mut Vec<SymbolDef>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk_java_symbols(child, bytes, file, scope_stack, owner, interner, symbols);
+    }
+}
+
+// ============================================================================
+// Call Extraction Walker
+// ============================================================================
+
+fn walk_java_calls(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    fn_stack: &mut Vec<String>,
+    interner: &ThreadedRodeo,
+    calls: &mut Vec<CallEdge>,
+) {
+    match node.kind() {
+        "class_declaration"
+        | "record_declaration"
+        | "interface_declaration"
+        | "enum_declaration"
+        | "annotation_type_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                scope_stack.push(name);
+                walk_java_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+                scope_stack.pop();
+                return;
+            }
+        }
+        "method_declaration" | "constructor_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                fn_stack.push(make_scoped_name(scope_stack, &name));
+                scope_stack.push(name);
+                walk_java_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+                scope_stack.pop();
+                fn_stack.pop();
+                return;
+            }
+        }
+        "method_invocation" => {
+            if let Some(callee_name) = field_text(bytes, node, "name") {
+                let is_method_call = node.child_by_field_name("object").is_some();
+                push_call(
+                    node,
+                    file,
+                    scope_stack,
+                    fn_stack,
+                    interner,
+                    calls,
+                    callee_name,
+                    is_method_call,
+                );
+            }
+        }
+        "object_creation_expression" => {
+            if let Some(callee_name) =
+                field_text(bytes, node, "type").and_then(|t| type_name_of(&t))
+            {
+                push_call(
+                    node,
+                    file,
+                    scope_stack,
+                    fn_stack,
+                    interner,
+                    calls,
+                    callee_name,
+                    false,
+                );
+            }
+        }
+        _ => {}
+    }
+
+    walk_java_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+}
+
+/// Record a call edge from the innermost function (or type scope).
+pub(super) fn push_call(
+    node: Node,
+    file: &Path,
+    scope_stack: &[String],
+    fn_stack: &[String],
+    interner: &ThreadedRodeo,
+    calls: &mut Vec<CallEdge>,
+    callee_name: String,
+    is_method_call: bool,
+) {
+    let caller_name = fn_stack
+        .last()
+        .cloned()
+        .unwrap_or_else(|| scope_stack.join("::"));
+    calls.push(CallEdge {
+        caller: intern_identifier(interner, &caller_name),
+        callee_name: normalize_identifier(&callee_name).into_owned(),
+        location: location_for(node, file),
+        is_method_call,
+        via_trait: None,
+        is_approximate: false,
+        type_args: None,
+        is_test_context: false,
+        resolved_callee: None,
+    });
+}
+
+fn walk_java_children_calls(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    fn_stack: &mut Vec<String>,
+    interner: &ThreadedRodeo,
+    calls: &mut Vec<CallEdge>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk_java_calls(child, bytes, file, scope_stack, fn_stack, interner, calls);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    const SOURCE: &str = r#"
+package com.acme.billing;
+
+import java.util.List;
+import com.acme.core.*;
+
+/** Computes invoices. */
+@Service
+public class InvoiceService {
+    private final List<String> items = new ArrayList<>();
+    public static final int MAX = 10;
+
+    public InvoiceService(Repo repo) { this.repo = repo; }
+
+    @Override
+    public Invoice compute(String id, int n) {
+        Invoice inv = repo.load(id);
+        helper();
+        return new Invoice(inv.total());
+    }
+}
+
+enum Color { RED, GREEN }
+"#;
+
+    fn parse(parser: &JavaParser) -> Tree {
+        let mut ts = Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        ts.parse(SOURCE, None).unwrap()
+    }
+
+    #[test]
+    fn test_java_symbols() {
+        let parser = JavaParser::new();
+        let tree = parse(&parser);
+        let interner = ThreadedRodeo::default();
+        let symbols = parser
+            .extract_symbols(&tree, SOURCE, Path::new("Invoice.java"), &interner)
+            .unwrap();
+
+        let find = |scoped: &str| {
+            symbols
+                .iter()
+                .find(|s| interner.resolve(&s.scoped_name) == scoped)
+                .unwrap_or_else(|| panic!("missing {scoped}"))
+        };
+
+        let class = find("com.acme.billing::InvoiceService");
+        assert_eq!(class.kind, SymbolKind::Struct);
+        assert_eq!(class.visibility, Visibility::Public);
+        assert!(
+            class
+                .doc_comment
+                .as_deref()
+                .unwrap()
+                .contains("Computes invoices")
+        );
+        assert_eq!(class.attributes, vec![Attribute::new("Service", None)]);
+
+        let method = find("com.acme.billing::InvoiceService::compute");
+        assert_eq!(method.kind, SymbolKind::Method);
+        assert_eq!(method.parent, Some(class.scoped_name));
+        let sig = method.signature.as_ref().unwrap();
+        assert_eq!(sig.params, vec!["String id", "int n"]);
+        assert_eq!(sig.return_type.as_deref(), Some("Invoice"));
+
+        assert_eq!(
+            find("com.acme.billing::InvoiceService::items").kind,
+            SymbolKind::Field
+        );
+        assert_eq!(
+            find("com.acme.billing::InvoiceService::MAX").kind,
+            SymbolKind::Const
+        );
+        assert_eq!(
+            find("com.acme.billing::Color::RED").kind,
+            SymbolKind::Variant
+        );
+    }
+
+    #[test]
+    fn test_java_calls_and_imports() {
+        let parser = JavaParser::new();
+        let tree = parse(&parser);
+        let interner = ThreadedRodeo::default();
+        let file = Path::new("Invoice.java");
+
+        let calls = parser
+            .extract_calls(&tree, SOURCE, file, &interner)
+            .unwrap();
+        let load = calls.iter().find(|c| c.callee_name == "load").unwrap();
+        assert!(load.is_method_call);
+        assert_eq!(
+            interner.resolve(&load.caller),
+            "com.acme.billing::InvoiceService::compute"
+        );
+        assert!(
+            calls
+                .iter()
+                .any(|c| c.callee_name == "helper" && !c.is_method_call)
+        );
+        assert!(calls.iter().any(|c| c.callee_name == "Invoice"));
+        assert!(calls.iter().any(|c| c.callee_name == "ArrayList"));
+
+        let imports = parser.extract_imports(&tree, SOURCE, file).unwrap();
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[0].path, "java.util.List");
+        assert_eq!(imports[0].name, "List");
+        assert!(imports[1].is_glob);
+    }
+}
@@ -0,0 +1,659 @@
+//! Kotlin language parser using tree-sitter.
+//!
+//! Mirrors the Java parser: classes, objects, interfaces, functions,
+//! properties, imports and call expressions.
+
+use super::java::{
+    doc_comment_before, field_text, location_for, make_scoped_name, package_scope, push_call,
+    text_of,
+};
+use super::{LanguageParser, intern_identifier, raw_spelling};
+use crate::types::*;
+use anyhow::Result;
+use lasso::ThreadedRodeo;
+use std::path::Path;
+use tree_sitter::{Language, Node, Tree};
+
+/// Node kinds that denote a type annotation.
+const TYPE_KINDS: &[&str] = &[
+    "user_type",
+    "nullable_type",
+    "non_nullable_type",
+    "function_type",
+    "parenthesized_type",
+];
+
+/// Kotlin source code parser.
+pub struct KotlinParser {}
+
+impl KotlinParser {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for KotlinParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageParser for KotlinParser {
+    fn language(&self) -> Language {
+        tree_sitter_kotlin_ng::LANGUAGE.into()
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["kt", "kts"]
+    }
+
+    fn first_line(&self) -> usize {
+        1
+    }
+
+    fn extract_symbols(
+        &self,
+        tree: &Tree,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Result<Vec<SymbolDef>> {
+        let bytes = source.as_bytes();
+        let root = tree.root_node();
+        let mut symbols = Vec::new();
+        let mut scope_stack = vec![package_scope(bytes, root, file, "package_header")];
+
+        walk_kotlin_symbols(
+            root,
+            bytes,
+            file,
+            &mut scope_stack,
+            None,
+            interner,
+            &mut symbols,
+        );
+
+        super::attach_ignore_directives(&mut symbols, source);
+        super::attach_extended_locations(&mut symbols, source);
+        Ok(symbols)
+    }
+
+    fn extract_calls(
+        &self,
+        tree: &Tree,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Result<Vec<CallEdge>> {
+        let bytes = source.as_bytes();
+        let root = tree.root_node();
+        let mut calls = Vec::new();
+        let mut scope_stack = vec![package_scope(bytes, root, file, "package_header")];
+        let mut fn_stack = Vec::<String>::new();
+
+        walk_kotlin_calls(
+            root,
+            bytes,
+            file,
+            &mut scope_stack,
+            &mut fn_stack,
+            interner,
+            &mut calls,
+        );
+
+        Ok(calls)
+    }
+
+    fn extract_imports(&self, tree: &Tree, source: &str, file: &Path) -> Result<Vec<ImportInfo>> {
+        let bytes = source.as_bytes();
+        let mut imports = Vec::new();
+
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        for node in root.children(&mut cursor) {
+            if node.kind() != "import" {
+                continue;
+            }
+            let mut inner = node.walk();
+            let named: Vec<Node> = node.named_children(&mut inner).collect();
+            let Some(path) = named
+                .iter()
+                .find(|n| n.kind() == "qualified_identifier")
+                .and_then(|n| text_of(bytes, *n))
+            else {
+                continue;
+            };
+            let is_glob = text_of(bytes, node).is_some_and(|t| t.trim_end().ends_with('*'));
+            // `import a.b.C as D` binds `D`
+            let alias = named
+                .iter()
+                .find(|n| n.kind() == "identifier")
+                .and_then(|n| text_of(bytes, *n));
+            let imported = path.rsplit('.').next().unwrap_or(&path).to_string();
+            let (name, original_name) = match alias {
+                _ if is_glob => ("*".to_string(), None),
+                Some(alias) => (alias, Some(imported)),
+                None => (imported, None),
+            };
+
+            imports.push(ImportInfo {
+                path,
+                name,
+                original_name,
+                is_reexport: false,
+                is_glob,
+                location: location_for(node, file),
+            });
+        }
+
+        Ok(imports)
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+fn has_token(node: Node, token: &str) -> bool {
+    let mut cursor = node.walk();
+    node.children(&mut cursor).any(|c| c.kind() == token)
+}
+
+fn modifier_words(bytes: &[u8], node: Node) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .find(|c| c.kind() == "modifiers")
+        .and_then(|m| text_of(bytes, m))
+        .map(|t| t.split_whitespace().map(|w| w.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn kotlin_visibility(bytes: &[u8], node: Node) -> Visibility {
+    let words = modifier_words(bytes, node);
+    if words.iter().any(|w| w == "private") {
+        Visibility::Private
+    } else if words.iter().any(|w| w == "protected") {
+        Visibility::Restricted
+    } else if words.iter().any(|w| w == "internal") {
+        Visibility::Crate
+    } else {
+        Visibility::Public
+    }
+}
+
+fn kotlin_annotations(bytes: &[u8], node: Node) -> Vec<Attribute> {
+    let mut cursor = node.walk();
+    let Some(modifiers) = node.children(&mut cursor).find(|c| c.kind() == "modifiers") else {
+        return Vec::new();
+    };
+    let mut inner = modifiers.walk();
+    modifiers
+        .named_children(&mut inner)
+        .filter(|c| c.kind() == "annotation")
+        .filter_map(|c| text_of(bytes, c))
+        .filter_map(|text| Attribute::parse(&text))
+        .collect()
+}
+
+fn kotlin_signature(bytes: &[u8], node: Node) -> Signature {
+    let mut sig = Signature::default();
+    let mut seen_params = false;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "function_value_parameters" => {
+                seen_params = true;
+                let mut inner = child.walk();
+                for param in child.named_children(&mut inner) {
+                    if param.kind() == "parameter" {
+                        if let Some(text) = text_of(bytes, param) {
+                            sig.params.push(text.trim().to_string());
+                        }
+                    }
+                }
+            }
+            "type_parameters" => sig.generics = text_of(bytes, child),
+            "type_constraints" => sig.where_clause = text_of(bytes, child),
+            kind if seen_params && sig.return_type.is_none() && TYPE_KINDS.contains(&kind) => {
+                sig.return_type = text_of(bytes, child);
+            }
+            _ => {}
+        }
+    }
+    sig.is_async = modifier_words(bytes, node).iter().any(|w| w == "suspend");
+    sig
+}
+
+fn kotlin_symbol(
+    bytes: &[u8],
+    file: &Path,
+    node: Node,
+    scope_stack: &[String],
+    name: &str,
+    kind: SymbolKind,
+    parent: Option<InternedString>,
+    interner: &ThreadedRodeo,
+) -> SymbolDef {
+    let signature = matches!(kind, SymbolKind::Method | SymbolKind::Function)
+        .then(|| kotlin_signature(bytes, node));
+    SymbolDef {
+        name: intern_identifier(interner, name),
+        scoped_name: intern_identifier(interner, &make_scoped_name(scope_stack, name)),
+        kind,
+        location: location_for(node, file),
+        extended_location: None,
+        signature,
+        visibility: kotlin_visibility(bytes, node),
+        attributes: kotlin_annotations(bytes, node),
+        doc_comment: doc_comment_before(bytes, node),
+        parent,
+        ignores: Vec::new(),
+        raw_name: raw_spelling(name),
+        is_test_context: false,
+    }
+}
+
+/// First direct `identifier` child (used where the grammar has no name field).
+fn first_ident(bytes: &[u8], node: Node) -> Option<String> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .find(|c| c.kind() == "identifier")
+        .and_then(|c| text_of(bytes, c))
+}
+
+/// Names bound by a property declaration (`val a`, `val (a, b)`).
+fn property_names(bytes: &[u8], node: Node) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "variable_declaration" => names.extend(first_ident(bytes, child)),
+            "multi_variable_declaration" => {
+                let mut inner = child.walk();
+                for var in child.named_children(&mut inner) {
+                    names.extend(first_ident(bytes, var));
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+// ============================================================================
+// Symbol Extraction Walker
+// ============================================================================
+
+fn walk_kotlin_symbols(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    owner: Option<InternedString>,
+    interner: &ThreadedRodeo,
+    symbols: &mut Vec<SymbolDef>,
+) {
+    match node.kind() {
+        "class_declaration" | "object_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                let kind = if has_token(node, "interface") {
+                    SymbolKind::Trait
+                } else if modifier_words(bytes, node).iter().any(|w| w == "enum") {
+                    SymbolKind::Enum
+                } else {
+                    SymbolKind::Struct
+                };
+                let symbol =
+                    kotlin_symbol(bytes, file, node, scope_stack, &name, kind, owner, interner);
+                let scoped = symbol.scoped_name;
+                symbols.push(symbol);
+
+                scope_stack.push(name);
+                walk_kotlin_children(
+                    node,
+                    bytes,
+                    file,
+                    scope_stack,
+                    Some(scoped),
+                    interner,
+                    symbols,
+                );
+                scope_stack.pop();
+                return;
+            }
+        }
+        "companion_object" => {
+            let name = field_text(bytes, node, "name").unwrap_or_else(|| "Companion".to_string());
+            scope_stack.push(name);
+            walk_kotlin_children(node, bytes, file, scope_stack, owner, interner, symbols);
+            scope_stack.pop();
+            return;
+        }
+        "function_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                let kind = if owner.is_some() {
+                    SymbolKind::Method
+                } else {
+                    SymbolKind::Function
+                };
+                symbols.push(kotlin_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    kind,
+                    owner,
+                    interner,
+                ));
+
+                scope_stack.push(name);
+                walk_kotlin_children(node, bytes, file, scope_stack, None, interner, symbols);
+                scope_stack.pop();
+                return;
+            }
+        }
+        "property_declaration" => {
+            let kind = if modifier_words(bytes, node).iter().any(|w| w == "const") {
+                SymbolKind::Const
+            } else if owner.is_some() {
+                SymbolKind::Field
+            } else {
+                SymbolKind::Static
+            };
+            // Locals (inside function bodies) are not symbols
+            let is_local = owner.is_none() && scope_stack.len() > 1;
+            if !is_local {
+                for name in property_names(bytes, node) {
+                    symbols.push(kotlin_symbol(
+                        bytes,
+                        file,
+                        node,
+                        scope_stack,
+                        &name,
+                        kind,
+                        owner,
+                        interner,
+                    ));
+                }
+            }
+        }
+        // Only `val`/`var` constructor parameters declare properties
+        "class_parameter" if has_token(node, "val") || has_token(node, "var") => {
+            if let Some(name) = first_ident(bytes, node) {
+                symbols.push(kotlin_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    SymbolKind::Field,
+                    owner,
+                    interner,
+                ));
+            }
+        }
+        "enum_entry" => {
+            if let Some(name) = first_ident(bytes, node) {
+                symbols.push(kotlin_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    SymbolKind::Variant,
+                    owner,
+                    interner,
+                ));
+            }
+        }
+        "type_alias" => {
+            if let Some(name) = field_text(bytes, node, "type") {
+                symbols.push(kotlin_symbol(
+                    bytes,
+                    file,
+                    node,
+                    scope_stack,
+                    &name,
+                    SymbolKind::TypeAlias,
+                    owner,
+                    interner,
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    walk_kotlin_children(node, bytes, file, scope_stack, owner, interner, symbols);
+}
+
+fn walk_kotlin_children(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    owner: Option<InternedString>,
+    interner: &ThreadedRodeo,
+    symbols: &mut Vec<SymbolDef>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk_kotlin_symbols(child, bytes, file, scope_stack, owner, interner, symbols);
+    }
+}
+
+// ============================================================================
+// Call Extraction Walker
+// ============================================================================
+
+fn walk_kotlin_calls(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    fn_stack: &mut Vec<String>,
+    interner: &ThreadedRodeo,
+    calls: &mut Vec<CallEdge>,
+) {
+    match node.kind() {
+        "class_declaration" | "object_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                scope_stack.push(name);
+                walk_kotlin_children_calls(
+                    node,
+                    bytes,
+                    file,
+                    scope_stack,
+                    fn_stack,
+                    interner,
+                    calls,
+                );
+                scope_stack.pop();
+                return;
+            }
+        }
+        "companion_object" => {
+            let name = field_text(bytes, node, "name").unwrap_or_else(|| "Companion".to_string());
+            scope_stack.push(name);
+            walk_kotlin_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+            scope_stack.pop();
+            return;
+        }
+        "function_declaration" => {
+            if let Some(name) = field_text(bytes, node, "name") {
+                fn_stack.push(make_scoped_name(scope_stack, &name));
+                scope_stack.push(name);
+                walk_kotlin_children_calls(
+                    node,
+                    bytes,
+                    file,
+                    scope_stack,
+                    fn_stack,
+                    interner,
+                    calls,
+                );
+                scope_stack.pop();
+                fn_stack.pop();
+                return;
+            }
+        }
+        "call_expression" => {
+            if let Some(callee) = node.named_child(0) {
+                let (callee_name, is_method_call) = match callee.kind() {
+                    "identifier" => (text_of(bytes, callee), false),
+                    "navigation_expression" => {
+                        let last = callee.named_child(callee.named_child_count().saturating_sub(1));
+                        (last.and_then(|n| text_of(bytes, n)), true)
+                    }
+                    _ => (None, false),
+                };
+                if let Some(callee_name) = callee_name {
+                    push_call(
+                        node,
+                        file,
+                        scope_stack,
+                        fn_stack,
+                        interner,
+                        calls,
+                        callee_name,
+                        is_method_call,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+
+    walk_kotlin_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+}
+
+fn walk_kotlin_children_calls(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    scope_stack: &mut Vec<String>,
+    fn_stack: &mut Vec<String>,
+    interner: &ThreadedRodeo,
+    calls: &mut Vec<CallEdge>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk_kotlin_calls(child, bytes, file, scope_stack, fn_stack, interner, calls);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    const SOURCE: &str = r#"
+package com.acme.billing
+
+import kotlin.collections.List
+import com.acme.core.*
+import com.acme.util.Helper as H
+
+/** Kotlin service. */
+class InvoiceService(private val repo: Repo, flag: Boolean) : Base(), Api {
+    private var count = 0
+
+    override suspend fun compute(id: String, n: Int): Invoice {
+        val inv = repo.load(id)
+        helper()
+        return Invoice(inv.total())
+    }
+
+    companion object {
+        const val MAX = 10
+    }
+}
+
+@Deprecated("Use InvoiceApi, it batches", ReplaceWith("InvoiceApi"))
+interface Api { fun compute(id: String, n: Int): Invoice }
+"#;
+
+    fn parse(parser: &KotlinParser) -> Tree {
+        let mut ts = Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        ts.parse(SOURCE, None).unwrap()
+    }
+
+    #[test]
+    fn test_kotlin_symbols() {
+        let parser = KotlinParser::new();
+        let tree = parse(&parser);
+        let interner = ThreadedRodeo::default();
+        let symbols = parser
+            .extract_symbols(&tree, SOURCE, Path::new("Invoice.kt"), &interner)
+            .unwrap();
+
+        let find = |scoped: &str| {
+            symbols
+                .iter()
+                .find(|s| interner.resolve(&s.scoped_name) == scoped)
+                .unwrap_or_else(|| panic!("missing {scoped}"))
+        };
+
+        let class = find("com.acme.billing::InvoiceService");
+        assert_eq!(class.kind, SymbolKind::Struct);
+        assert!(class.doc_comment.is_some());
+
+        let method = find("com.acme.billing::InvoiceService::compute");
+        assert_eq!(method.kind, SymbolKind::Method);
+        assert_eq!(method.parent, Some(class.scoped_name));
+        let sig = method.signature.as_ref().unwrap();
+        assert!(sig.is_async);
+        assert_eq!(sig.params, vec!["id: String", "n: Int"]);
+        assert_eq!(sig.return_type.as_deref(), Some("Invoice"));
+
+        assert_eq!(
+            find("com.acme.billing::InvoiceService::repo").kind,
+            SymbolKind::Field
+        );
+        assert_eq!(
+            find("com.acme.billing::InvoiceService::count").visibility,
+            Visibility::Private
+        );
+        assert_eq!(
+            find("com.acme.billing::InvoiceService::Companion::MAX").kind,
+            SymbolKind::Const
+        );
+        let api = find("com.acme.billing::Api");
+        assert_eq!(api.kind, SymbolKind::Trait);
+        assert_eq!(api.deprecation(), Some("Use InvoiceApi, it batches"));
+        assert!(!symbols.iter().any(|s| interner.resolve(&s.name) == "flag"));
+        assert!(!symbols.iter().any(|s| interner.resolve(&s.name) == "inv"));
+    }
+
+    #[test]
+    fn test_kotlin_calls_and_imports() {
+        let parser = KotlinParser::new();
+        let tree = parse(&parser);
+        let interner = ThreadedRodeo::default();
+        let file = Path::new("Invoice.kt");
+
+        let calls = parser
+            .extract_calls(&tree, SOURCE, file, &interner)
+            .unwrap();
+        let load = calls.iter().find(|c| c.callee_name == "load").unwrap();
+        assert!(load.is_method_call);
+        assert_eq!(
+            interner.resolve(&load.caller),
+            "com.acme.billing::InvoiceService::compute"
+        );
+        assert!(
+            calls
+                .iter()
+                .any(|c| c.callee_name == "helper" && !c.is_method_call)
+        );
+        assert!(calls.iter().any(|c| c.callee_name == "Invoice"));
+
+        let imports = parser.extract_imports(&tree, SOURCE, file).unwrap();
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[0].name, "List");
+        assert!(imports[1].is_glob);
+        assert_eq!(imports[2].path, "com.acme.util.Helper");
+        assert_eq!(imports[2].name, "H");
+    }
+}
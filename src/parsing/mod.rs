@@ -2,13 +2,23 @@
 //!
 //! Uses tree-sitter for incremental, error-tolerant parsing.
 
+pub mod config;
+pub mod edits;
+pub mod java;
+pub mod kotlin;
 pub mod rust;
+pub mod sql;
 pub mod typescript;
 
+use crate::error::OmniError;
 use crate::types::*;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use std::borrow::Cow;
+use std::io::Read;
 use std::path::Path;
-use tree_sitter::{Language, Tree};
+use tree_sitter::{Language, Node, Tree};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
 
 /// Trait for language-specific parsers.
 pub trait LanguageParser: Send + Sync {
@@ -18,6 +28,12 @@ pub trait LanguageParser: Send + Sync {
     /// File extensions this parser handles.
     fn extensions(&self) -> &[&str];
 
+    /// Number of the first line in the locations this parser reports:
+    /// 0 for tree-sitter rows as they are, 1 for parsers that add one.
+    fn first_line(&self) -> usize {
+        0
+    }
+
     /// Extract symbol definitions from a parsed tree.
     fn extract_symbols(
         &self,
@@ -38,15 +54,567 @@ pub trait LanguageParser: Send + Sync {
 
     /// Extract import information from a parsed tree.
     fn extract_imports(&self, tree: &Tree, source: &str, file: &Path) -> Result<Vec<ImportInfo>>;
+
+    /// Extract trait implementations (`impl Trait for Type`).
+    ///
+    /// Languages without nominal trait impls return nothing.
+    fn extract_trait_impls(
+        &self,
+        _tree: &Tree,
+        _source: &str,
+        _file: &Path,
+        _interner: &lasso::ThreadedRodeo,
+    ) -> Result<Vec<TraitImpl>> {
+        Ok(Vec::new())
+    }
+
+    /// Extract references to types and traits other than calls: struct
+    /// literals, type annotations, trait bounds, and `impl` targets.
+    ///
+    /// Languages without a usage pass return nothing.
+    fn extract_type_refs(&self, _tree: &Tree, _source: &str, _file: &Path) -> Result<Vec<TypeRef>> {
+        Ok(Vec::new())
+    }
+
+    /// Extract the symbols and calls of one top-level `item` (a child of the
+    /// tree's root), scoped exactly as a full extraction would scope them.
+    ///
+    /// Lets [`edits::reparse`] re-extract only the items an edit touched.
+    /// Languages whose scoping depends on sibling items return `None`.
+    fn extract_item(
+        &self,
+        _item: Node,
+        _source: &str,
+        _file: &Path,
+        _interner: &lasso::ThreadedRodeo,
+    ) -> Option<(Vec<SymbolDef>, Vec<CallEdge>)> {
+        None
+    }
+}
+
+/// Canonical form of an identifier or path (`a::b`, `a.b`) for interning
+/// and lookup: Rust raw identifier prefixes (`r#type`) and Kotlin backtick
+/// escapes (`` `is` ``) are stripped from every segment and the text is
+/// NFC-normalized, so `r#type` matches `type` and precomposed and decomposed
+/// spellings of `café` are one symbol.
+pub fn normalize_identifier(name: &str) -> Cow<'_, str> {
+    let escaped = name.contains("r#") || name.contains('`');
+    let nfc = is_nfc(name);
+    if !escaped && nfc {
+        return Cow::Borrowed(name);
+    }
+    let mut normalized = if escaped {
+        strip_escapes(name)
+    } else {
+        name.to_string()
+    };
+    if !nfc {
+        normalized = normalized.nfc().collect();
+    }
+    Cow::Owned(normalized)
+}
+
+/// Intern the [normalized](normalize_identifier) form of `name`.
+pub fn intern_identifier(interner: &lasso::ThreadedRodeo, name: &str) -> InternedString {
+    interner.get_or_intern(normalize_identifier(name))
+}
+
+/// `name` as written in source, if it differs from its normalized form.
+pub fn raw_spelling(name: &str) -> Option<String> {
+    match normalize_identifier(name) {
+        Cow::Owned(normalized) if normalized != name => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// Drop backticks, and `r#` where it starts a path segment
+/// (`r#mod::r#type` -> `mod::type`).
+fn strip_escapes(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut rest = name;
+    let mut at_segment_start = true;
+    while let Some(c) = rest.chars().next() {
+        if at_segment_start && rest.starts_with("r#") {
+            rest = &rest[2..];
+            at_segment_start = false;
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        if c == '`' {
+            continue;
+        }
+        at_segment_start = !(c.is_alphanumeric() || c == '_');
+        out.push(c);
+    }
+    out
+}
+
+/// Flag symbols and call edges that belong to test code.
+///
+/// Everything in a test file (`in_test_file`, from
+/// [`crate::topology::classify_role`]) is test context, as is anything
+/// inside a symbol carrying a test marker or `#[cfg(test)]`
+/// ([`SymbolDef::is_test`]), such as a `mod tests` block or a `@Test` method.
+pub fn mark_test_context(symbols: &mut [SymbolDef], calls: &mut [CallEdge], in_test_file: bool) {
+    let tests: Vec<(usize, usize)> = symbols
+        .iter()
+        .filter(|s| s.is_test())
+        .map(|s| (s.location.start_byte, s.location.end_byte))
+        .collect();
+    let in_test = |byte: usize| {
+        in_test_file
+            || tests
+                .iter()
+                .any(|&(start, end)| start <= byte && byte < end)
+    };
+    for symbol in symbols.iter_mut() {
+        symbol.is_test_context = in_test(symbol.location.start_byte);
+    }
+    for call in calls {
+        call.is_test_context = in_test(call.location.start_byte);
+    }
 }
 
-/// Get a parser for a file based on its extension.
+/// Marker for inline suppression comments, e.g. `// omni:ignore dead_code`.
+pub const IGNORE_DIRECTIVE: &str = "omni:ignore";
+
+/// Attach `omni:ignore` directives to the symbols they annotate.
+///
+/// A directive applies to a symbol when it sits in the comment/attribute
+/// block directly above it or trails the symbol's first line. It lists rule
+/// names (e.g. `dead_code`, `duplication`, `naming`) separated by commas or
+/// spaces; anything after `--` is a free-form reason. A bare `omni:ignore`
+/// suppresses every rule ([`crate::types::IGNORE_ALL`]).
+pub fn attach_ignore_directives(symbols: &mut [SymbolDef], source: &str) {
+    if !source.contains(IGNORE_DIRECTIVE) {
+        return;
+    }
+    for symbol in symbols {
+        symbol.ignores = ignores_before(source, symbol.location.start_byte);
+    }
+}
+
+fn ignores_before(source: &str, start_byte: usize) -> Vec<String> {
+    let Some(before) = source.get(..start_byte) else {
+        return Vec::new();
+    };
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start_byte..]
+        .find('\n')
+        .map_or(source.len(), |i| start_byte + i);
+
+    let mut rules = Vec::new();
+    parse_ignore_directive(&source[line_start..line_end], &mut rules);
+    for line in source[..line_start].lines().rev() {
+        let line = line.trim();
+        if !["//", "/*", "*", "#[", "@"]
+            .iter()
+            .any(|p| line.starts_with(p))
+        {
+            break;
+        }
+        parse_ignore_directive(line, &mut rules);
+    }
+    rules.sort();
+    rules.dedup();
+    rules
+}
+
+fn parse_ignore_directive(line: &str, rules: &mut Vec<String>) {
+    let Some(idx) = line.find(IGNORE_DIRECTIVE) else {
+        return;
+    };
+    let rest = &line[idx + IGNORE_DIRECTIVE.len()..];
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return;
+    }
+    let rest = rest.split("--").next().unwrap_or("");
+    let before = rules.len();
+    rules.extend(
+        rest.trim_end()
+            .trim_end_matches("*/")
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_ascii_lowercase().replace('-', "_")),
+    );
+    if rules.len() == before {
+        rules.push(IGNORE_ALL.to_string());
+    }
+}
+
+/// Lines of a multi-line attribute (`#[instrument(\n...\n)]`) followed
+/// upward before giving up on finding its opening line.
+const MAX_ATTRIBUTE_LINES: usize = 32;
+
+/// Set each symbol's [`SymbolDef::extended_location`]: its location widened
+/// upward to the start of the doc comments, comments and attributes (Rust
+/// `#[...]`, decorators and annotations `@...`) directly above it.
+///
+/// The block ends at a blank line or any other code. Multi-line attributes
+/// are followed to their opening line. Symbols whose first line has other
+/// code before them get no extended location.
+pub fn attach_extended_locations(symbols: &mut [SymbolDef], source: &str) {
+    for symbol in symbols {
+        let start = attached_start(source, symbol.location.start_byte);
+        symbol.extended_location = (start < symbol.location.start_byte).then(|| {
+            let lines_above = source[start..symbol.location.start_byte]
+                .matches('\n')
+                .count();
+            Location {
+                start_byte: start,
+                start_line: symbol.location.start_line.saturating_sub(lines_above),
+                start_col: 0,
+                ..symbol.location.clone()
+            }
+        });
+    }
+}
+
+/// Start of the comment/attribute block attached above the line holding
+/// `start_byte`, or `start_byte` itself if there is none.
+fn attached_start(source: &str, start_byte: usize) -> usize {
+    let Some(before) = source.get(..start_byte) else {
+        return start_byte;
+    };
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    if !before[line_start..].trim().is_empty() {
+        return start_byte;
+    }
+
+    let mut start = start_byte;
+    let mut cursor = line_start;
+    // Unclosed brackets and lines read of a multi-line attribute whose
+    // closing line has been seen but not yet its opening line
+    let mut open_attribute: Option<(isize, usize)> = None;
+    while cursor > 0 {
+        let line_end = cursor - 1;
+        let line_begin = source[..line_end].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[line_begin..line_end].trim();
+        cursor = line_begin;
+
+        if let Some((depth, read)) = open_attribute.as_mut() {
+            *depth += bracket_balance(line);
+            *read += 1;
+            if *depth < 0 {
+                if line.is_empty() || *read > MAX_ATTRIBUTE_LINES {
+                    break;
+                }
+                continue;
+            }
+            if !(line.starts_with("#[") || line.starts_with('@')) {
+                break;
+            }
+            open_attribute = None;
+            start = line_begin;
+        } else if ["//", "/*", "*"].iter().any(|p| line.starts_with(p))
+            || ((line.starts_with("#[") || line.starts_with('@')) && bracket_balance(line) >= 0)
+        {
+            start = line_begin;
+        } else if (line.ends_with(']') || line.ends_with(')')) && bracket_balance(line) < 0 {
+            open_attribute = Some((bracket_balance(line), 0));
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+/// Opening minus closing brackets on a line.
+fn bracket_balance(line: &str) -> isize {
+    line.chars()
+        .map(|c| match c {
+            '(' | '[' | '{' => 1,
+            ')' | ']' | '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Languages that can be named in overrides.
+pub const SUPPORTED_LANGUAGES: &[&str] =
+    &["rust", "typescript", "tsx", "javascript", "java", "kotlin"];
+
+/// Suffixes stripped before extension lookup (e.g. `build.rs.in`).
+const TEMPLATE_SUFFIXES: &[&str] = &["in", "tmpl", "template"];
+
+/// Get a parser for a file based on its extension, falling back to the
+/// shebang line for extensionless scripts.
 pub fn parser_for_file(path: &Path) -> Option<Box<dyn LanguageParser>> {
-    let ext = path.extension()?.to_str()?;
-    match ext.to_lowercase().as_str() {
-        "rs" => Some(Box::new(rust::RustParser::new())),
-        "ts" | "mts" | "cts" => Some(Box::new(typescript::TypeScriptParser::new_typescript())),
+    parser_for_language(language_for_file(path)?)
+}
+
+/// Get a parser by language name (see [`SUPPORTED_LANGUAGES`]).
+pub fn parser_for_language(language: &str) -> Option<Box<dyn LanguageParser>> {
+    match language.to_lowercase().as_str() {
+        "rust" | "rs" => Some(Box::new(rust::RustParser::new())),
+        "typescript" | "ts" => Some(Box::new(typescript::TypeScriptParser::new_typescript())),
         "tsx" => Some(Box::new(typescript::TypeScriptParser::new_tsx())),
+        "javascript" | "js" | "jsx" => {
+            Some(Box::new(typescript::TypeScriptParser::new_javascript()))
+        }
+        "java" => Some(Box::new(java::JavaParser::new())),
+        "kotlin" | "kt" => Some(Box::new(kotlin::KotlinParser::new())),
+        _ => None,
+    }
+}
+
+/// Detect the language of a file from its extension or shebang.
+pub fn language_for_file(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts: Vec<&str> = name.split('.').skip(1).collect();
+    if parts.len() > 1
+        && TEMPLATE_SUFFIXES.contains(&parts.last().copied().unwrap_or("").to_lowercase().as_str())
+    {
+        parts.pop();
+    }
+
+    match parts.last() {
+        Some(ext) => language_for_extension(ext),
+        None => language_from_shebang(path),
+    }
+}
+
+fn language_for_extension(ext: &str) -> Option<&'static str> {
+    match ext.to_lowercase().as_str() {
+        "rs" => Some("rust"),
+        "ts" | "mts" | "cts" => Some("typescript"),
+        "tsx" => Some("tsx"),
+        "js" | "jsx" | "mjs" | "cjs" => Some("javascript"),
+        "java" => Some("java"),
+        "kt" | "kts" => Some("kotlin"),
         _ => None,
     }
 }
+
+/// Sniff the interpreter from a `#!` line.
+fn language_from_shebang(path: &Path) -> Option<&'static str> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut buf = [0u8; 256];
+    let n = file.read(&mut buf).ok()?;
+    let head = std::str::from_utf8(&buf[..n]).ok()?;
+    let line = head.lines().next()?.strip_prefix("#!")?;
+
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+
+    match program {
+        "rust-script" | "run-cargo-script" | "cargo-eval" | "cargo" => Some("rust"),
+        "ts-node" | "tsx" | "deno" | "bun" => Some("typescript"),
+        "node" | "nodejs" => Some("javascript"),
+        "java" => Some("java"),
+        "kotlin" | "kscript" => Some("kotlin"),
+        _ => None,
+    }
+}
+
+/// Glob-based language overrides, matched against root-relative paths.
+///
+/// Rules are written as `GLOB=LANGUAGE`, e.g. `scripts/*=typescript` or
+/// `**/*.rs.in=rust`.
+#[derive(Debug, Clone, Default)]
+pub struct LanguageOverrides {
+    rules: Vec<(GlobMatcher, &'static str)>,
+}
+
+impl LanguageOverrides {
+    /// Parse a list of `GLOB=LANGUAGE` rules.
+    pub fn from_rules(rules: &[String]) -> crate::error::Result<Self> {
+        let mut overrides = Self::default();
+        overrides.add_rules(rules)?;
+        Ok(overrides)
+    }
+
+    /// Add a list of `GLOB=LANGUAGE` rules, taking precedence over the
+    /// existing ones.
+    pub fn add_rules(&mut self, rules: &[String]) -> crate::error::Result<()> {
+        for rule in rules {
+            let (pattern, language) = rule.rsplit_once('=').with_context(|| {
+                format!("Invalid language override '{rule}': expected GLOB=LANGUAGE")
+            })?;
+            self.add(pattern.trim(), language.trim())?;
+        }
+        Ok(())
+    }
+
+    /// Add a single override rule.
+    pub fn add(&mut self, pattern: &str, language: &str) -> crate::error::Result<()> {
+        let Some(language) = SUPPORTED_LANGUAGES
+            .iter()
+            .copied()
+            .find(|l| l.eq_ignore_ascii_case(language))
+        else {
+            return Err(OmniError::UnsupportedLanguage(format!(
+                "'{language}' (expected one of: {})",
+                SUPPORTED_LANGUAGES.join(", ")
+            )));
+        };
+        let matcher = Glob::new(pattern)
+            .with_context(|| format!("Invalid language override pattern '{pattern}'"))?
+            .compile_matcher();
+        self.rules.push((matcher, language));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Language forced for a root-relative path, if any rule matches.
+    /// Later rules win over earlier ones.
+    pub fn language_for(&self, rel: &Path) -> Option<&'static str> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.is_match(rel))
+            .map(|(_, language)| *language)
+    }
+
+    /// Resolve a parser for `path`, consulting overrides before detection.
+    pub fn parser_for_file(&self, path: &Path, root: &Path) -> Option<Box<dyn LanguageParser>> {
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        match self.language_for(rel) {
+            Some(language) => parser_for_language(language),
+            None => parser_for_file(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_ignore_directives_attach_to_annotated_symbols() {
+        let source = "// omni:ignore dead_code -- kept for FFI\n#[inline]\nfn a() {}\n\nfn b() {} // omni:ignore duplication, naming\n\n// omni:ignore\nfn c() {}\n\n// omni:ignored is not a directive\nfn d() {}\n";
+        let path = Path::new("lib.rs");
+        let parser = parser_for_file(path).unwrap();
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        let interner = lasso::ThreadedRodeo::default();
+        let symbols = parser
+            .extract_symbols(&tree, source, path, &interner)
+            .unwrap();
+
+        let ignores = |name: &str| {
+            symbols
+                .iter()
+                .find(|s| interner.resolve(&s.name) == name)
+                .unwrap()
+                .ignores
+                .clone()
+        };
+        assert_eq!(ignores("a"), vec!["dead_code"]);
+        assert_eq!(ignores("b"), vec!["duplication", "naming"]);
+        assert_eq!(ignores("c"), vec![IGNORE_ALL]);
+        assert!(ignores("d").is_empty());
+    }
+
+    #[test]
+    fn test_extended_locations_cover_docs_and_attributes() {
+        let source = "use std::fmt;\n\n/// Loads the config.\n#[tracing::instrument(\n    skip(self),\n    fields(path = %path)\n)]\n#[inline]\npub fn load() {}\n\n// unrelated\n\nfn bare() {}\nconst X: [u8; 1] = [\n    0,\n];\nfn after_const() {}\n";
+        let path = Path::new("lib.rs");
+        let parser = parser_for_file(path).unwrap();
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        let interner = lasso::ThreadedRodeo::default();
+        let symbols = parser
+            .extract_symbols(&tree, source, path, &interner)
+            .unwrap();
+        let symbol = |name: &str| {
+            symbols
+                .iter()
+                .find(|s| interner.resolve(&s.name) == name)
+                .unwrap()
+        };
+
+        let load = symbol("load");
+        let extended = load.extended_location.as_ref().unwrap();
+        assert!(source[extended.start_byte..].starts_with("/// Loads the config."));
+        assert_eq!(extended.start_line, 2);
+        assert_eq!(extended.end_byte, load.location.end_byte);
+        assert_eq!(load.full_location(), extended);
+        // Detached by a blank line
+        assert!(symbol("bare").extended_location.is_none());
+        // A closing bracket that does not belong to an attribute
+        assert!(symbol("after_const").extended_location.is_none());
+    }
+
+    #[test]
+    fn test_normalize_identifier() {
+        assert_eq!(normalize_identifier("r#type"), "type");
+        assert_eq!(
+            normalize_identifier("crate::r#mod::r#type"),
+            "crate::mod::type"
+        );
+        assert_eq!(normalize_identifier("`is valid`"), "is valid");
+        assert_eq!(normalize_identifier("cafe\u{301}"), "caf\u{e9}");
+        assert!(matches!(
+            normalize_identifier("plain_name"),
+            Cow::Borrowed(_)
+        ));
+
+        assert_eq!(raw_spelling("r#type").as_deref(), Some("r#type"));
+        assert_eq!(raw_spelling("caf\u{e9}"), None);
+        assert_eq!(raw_spelling("plain_name"), None);
+    }
+
+    #[test]
+    fn test_language_for_extension_and_template() {
+        assert_eq!(language_for_file(Path::new("src/lib.rs")), Some("rust"));
+        assert_eq!(language_for_file(Path::new("build.rs.in")), Some("rust"));
+        assert_eq!(language_for_file(Path::new("app.tsx.tmpl")), Some("tsx"));
+        for js in ["app.js", "App.jsx", "server.mjs", "config.cjs"] {
+            assert_eq!(language_for_file(Path::new(js)), Some("javascript"));
+        }
+        assert_eq!(language_for_file(Path::new("notes.in")), None);
+        assert_eq!(language_for_file(Path::new("README.md")), None);
+    }
+
+    #[test]
+    fn test_shebang_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let rust_script = dir.path().join("tool");
+        fs::write(&rust_script, "#!/usr/bin/env rust-script\nfn main() {}\n").unwrap();
+        let ts_script = dir.path().join("deploy");
+        fs::write(&ts_script, "#!/usr/bin/env -S deno run\nconsole.log(1);\n").unwrap();
+        let shell = dir.path().join("run");
+        fs::write(&shell, "#!/bin/sh\necho hi\n").unwrap();
+
+        assert_eq!(language_for_file(&rust_script), Some("rust"));
+        assert_eq!(language_for_file(&ts_script), Some("typescript"));
+        assert_eq!(language_for_file(&shell), None);
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let overrides = LanguageOverrides::from_rules(&[
+            "scripts/*=typescript".to_string(),
+            "**/*.txt=rust".to_string(),
+        ])
+        .unwrap();
+        let root = Path::new("/repo");
+
+        assert_eq!(
+            overrides.language_for(Path::new("scripts/deploy")),
+            Some("typescript")
+        );
+        assert!(
+            overrides
+                .parser_for_file(Path::new("/repo/a/b.txt"), root)
+                .is_some()
+        );
+        assert!(
+            overrides
+                .parser_for_file(Path::new("/repo/a/b.md"), root)
+                .is_none()
+        );
+        assert!(LanguageOverrides::from_rules(&["*.x=cobol".to_string()]).is_err());
+        assert!(LanguageOverrides::from_rules(&["*.x".to_string()]).is_err());
+    }
+}
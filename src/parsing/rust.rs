@@ -1,9 +1,10 @@
 //! Rust language parser using tree-sitter.
 
-use super::LanguageParser;
+use super::{LanguageParser, intern_identifier, normalize_identifier, raw_spelling};
 use crate::types::*;
 use anyhow::Result;
 use lasso::ThreadedRodeo;
+use std::collections::HashMap;
 use std::path::Path;
 use tree_sitter::{Language, Node, Tree};
 
@@ -57,6 +58,8 @@ impl LanguageParser for RustParser {
             &mut symbols,
         );
 
+        super::attach_ignore_directives(&mut symbols, source);
+        super::attach_extended_locations(&mut symbols, source);
         Ok(symbols)
     }
 
@@ -74,6 +77,7 @@ impl LanguageParser for RustParser {
         let mut module_stack = vec!["crate".to_string()];
         let mut impl_type_stack = Vec::<String>::new();
         let mut fn_scope_stack = Vec::<String>::new();
+        let mut dyn_bindings = Vec::<HashMap<String, String>>::new();
 
         walk_rust_calls(
             root,
@@ -82,6 +86,7 @@ impl LanguageParser for RustParser {
             &mut module_stack,
             &mut impl_type_stack,
             &mut fn_scope_stack,
+            &mut dyn_bindings,
             interner,
             &mut calls,
         );
@@ -98,22 +103,98 @@ impl LanguageParser for RustParser {
 
         Ok(imports)
     }
+
+    fn extract_trait_impls(
+        &self,
+        tree: &Tree,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Result<Vec<TraitImpl>> {
+        let bytes = source.as_bytes();
+        let root = tree.root_node();
+
+        let mut impls = Vec::new();
+        let mut module_stack = vec!["crate".to_string()];
+        walk_rust_trait_impls(root, bytes, file, &mut module_stack, interner, &mut impls);
+
+        Ok(impls)
+    }
+
+    fn extract_type_refs(&self, tree: &Tree, source: &str, file: &Path) -> Result<Vec<TypeRef>> {
+        let mut refs = Vec::new();
+        walk_rust_type_refs(
+            tree.root_node(),
+            source.as_bytes(),
+            file,
+            ReferenceKind::TypeAnnotation,
+            &mut refs,
+        );
+        Ok(refs)
+    }
+
+    fn extract_item(
+        &self,
+        item: Node,
+        source: &str,
+        file: &Path,
+        interner: &ThreadedRodeo,
+    ) -> Option<(Vec<SymbolDef>, Vec<CallEdge>)> {
+        let bytes = source.as_bytes();
+
+        // Top-level items only see the crate scope
+        let mut symbols = Vec::new();
+        walk_rust_symbols(
+            item,
+            bytes,
+            file,
+            &mut vec!["crate".to_string()],
+            &mut Vec::new(),
+            interner,
+            &mut symbols,
+        );
+        super::attach_ignore_directives(&mut symbols, source);
+        super::attach_extended_locations(&mut symbols, source);
+
+        let mut calls = Vec::new();
+        walk_rust_calls(
+            item,
+            bytes,
+            file,
+            &mut vec!["crate".to_string()],
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            interner,
+            &mut calls,
+        );
+
+        Some((symbols, calls))
+    }
 }
 
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
-/// Extract the last identifier from a node's text.
+/// Extract the last identifier from a node's text, normalized (see
+/// [`normalize_identifier`]).
 fn last_ident_of(bytes: &[u8], node: Node) -> Option<String> {
     let text = std::str::from_utf8(&bytes[node.start_byte()..node.end_byte()]).ok()?;
     let mut best = None;
-    for part in text.split(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+    // Split on ASCII separators only, so combining marks stay attached
+    for part in text.split(|c: char| (c.is_ascii_punctuation() && c != '_') || c.is_whitespace()) {
         if !part.is_empty() {
             best = Some(part);
         }
     }
-    best.map(|s| s.to_string())
+    best.map(|s| normalize_identifier(s).into_owned())
+}
+
+/// A name node's text if it is spelled differently from its normalized
+/// form, e.g. `r#type`.
+fn raw_ident_of(bytes: &[u8], node: Node) -> Option<String> {
+    raw_spelling(std::str::from_utf8(&bytes[node.byte_range()]).ok()?.trim())
 }
 
 /// Create a Location from a tree-sitter node.
@@ -141,6 +222,51 @@ fn current_impl_type(impl_stack: &[String]) -> Option<&str> {
     impl_stack.last().map(|s| s.as_str())
 }
 
+/// Extract the trait name from a type containing `dyn Trait`
+/// (e.g. `Box<dyn io::Write + Send>` -> `Write`).
+fn dyn_trait_of(type_text: &str) -> Option<String> {
+    let idx = type_text.find("dyn ")?;
+    let rest = type_text[idx + 4..].trim_start();
+    let path: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == ':')
+        .collect();
+    let name = path.rsplit("::").next()?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Collect parameters typed as `dyn Trait` (behind any pointer).
+fn dyn_params(bytes: &[u8], fn_node: Node) -> HashMap<String, String> {
+    let mut bindings = HashMap::new();
+    let Some(params) = fn_node.child_by_field_name("parameters") else {
+        return bindings;
+    };
+    let mut cursor = params.walk();
+    for param in params.children(&mut cursor) {
+        if param.kind() != "parameter" {
+            continue;
+        }
+        if let Some(binding) = dyn_binding(bytes, param) {
+            bindings.insert(binding.0, binding.1);
+        }
+    }
+    bindings
+}
+
+/// Binding name and trait for a `pattern: <dyn type>` node (parameter or let).
+fn dyn_binding(bytes: &[u8], node: Node) -> Option<(String, String)> {
+    let pattern = node.child_by_field_name("pattern")?;
+    let ty = node.child_by_field_name("type")?;
+    let type_text = std::str::from_utf8(&bytes[ty.start_byte()..ty.end_byte()]).ok()?;
+    let trait_name = dyn_trait_of(type_text)?;
+    let name = last_ident_of(bytes, pattern)?;
+    Some((name, trait_name))
+}
+
 /// Extract the type identifier for an impl item.
 fn impl_type_ident(bytes: &[u8], impl_node: Node) -> Option<String> {
     if impl_node.kind() != "impl_item" {
@@ -184,13 +310,13 @@ fn has_test_attr(bytes: &[u8], fn_node: Node) -> bool {
 }
 
 /// Extract attributes from preceding siblings.
-fn extract_attributes(bytes: &[u8], node: Node) -> Vec<String> {
+fn extract_attributes(bytes: &[u8], node: Node) -> Vec<Attribute> {
     let mut attrs = Vec::new();
     let mut cur = node.prev_sibling();
     while let Some(sib) = cur {
         if sib.kind() == "attribute_item" {
             if let Ok(text) = std::str::from_utf8(&bytes[sib.start_byte()..sib.end_byte()]) {
-                attrs.push(text.trim().to_string());
+                attrs.extend(Attribute::parse(text));
             }
             cur = sib.prev_sibling();
         } else {
@@ -332,15 +458,19 @@ fn walk_rust_symbols(
                     // Record module as a symbol
                     let scoped = join_scope(module_stack);
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Module,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -361,15 +491,19 @@ fn walk_rust_symbols(
             scoped.push_str(&ty);
 
             let symbol = SymbolDef {
-                name: interner.get_or_intern(&ty),
-                scoped_name: interner.get_or_intern(&scoped),
+                name: intern_identifier(interner, &ty),
+                scoped_name: intern_identifier(interner, &scoped),
                 kind: SymbolKind::Impl,
                 location: location_for(node, file),
+                extended_location: None,
                 signature: None,
                 visibility: Visibility::Private, // impls don't have visibility
                 attributes: extract_attributes(bytes, node),
                 doc_comment: extract_doc_comments(bytes, node),
                 parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
             };
             symbols.push(symbol);
         } else {
@@ -390,7 +524,7 @@ fn walk_rust_symbols(
                         if !ty.is_empty() && ty != "_" {
                             scoped.push_str("::");
                             scoped.push_str(ty);
-                            Some(interner.get_or_intern(ty))
+                            Some(intern_identifier(interner, ty))
                         } else {
                             None
                         }
@@ -411,21 +545,25 @@ fn walk_rust_symbols(
 
                     // Check if this is a test function and add special attribute if so
                     let is_test = has_test_attr(bytes, node);
-                    if is_test && !attrs.iter().any(|a| a.contains("test")) {
+                    if is_test && !attrs.iter().any(Attribute::is_test_marker) {
                         // Ensure test attribute is included
-                        attrs.push("#[test]".to_string());
+                        attrs.push(Attribute::new("test", None));
                     }
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&fn_name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &fn_name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: symbol_kind,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: Some(extract_signature(bytes, node)),
                         visibility: extract_visibility(bytes, node),
                         attributes: attrs,
                         doc_comment: extract_doc_comments(bytes, node),
                         parent,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -440,15 +578,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Struct,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -463,15 +605,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Enum,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -486,15 +632,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Trait,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -510,7 +660,7 @@ fn walk_rust_symbols(
                         if !ty.is_empty() && ty != "_" {
                             scoped.push_str("::");
                             scoped.push_str(ty);
-                            Some(interner.get_or_intern(ty))
+                            Some(intern_identifier(interner, ty))
                         } else {
                             None
                         }
@@ -522,15 +672,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Const,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -545,15 +699,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::Static,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -568,15 +726,19 @@ fn walk_rust_symbols(
                     scoped.push_str(&name);
 
                     let symbol = SymbolDef {
-                        name: interner.get_or_intern(&name),
-                        scoped_name: interner.get_or_intern(&scoped),
+                        name: intern_identifier(interner, &name),
+                        scoped_name: intern_identifier(interner, &scoped),
                         kind: SymbolKind::TypeAlias,
                         location: location_for(node, file),
+                        extended_location: None,
                         signature: None,
                         visibility: extract_visibility(bytes, node),
                         attributes: extract_attributes(bytes, node),
                         doc_comment: extract_doc_comments(bytes, node),
                         parent: None,
+                        ignores: Vec::new(),
+                        raw_name: raw_ident_of(bytes, name_node),
+                        is_test_context: false,
                     };
                     symbols.push(symbol);
                 }
@@ -621,6 +783,7 @@ fn walk_rust_calls(
     module_stack: &mut Vec<String>,
     impl_type_stack: &mut Vec<String>,
     fn_scope_stack: &mut Vec<String>,
+    dyn_bindings: &mut Vec<HashMap<String, String>>,
     interner: &ThreadedRodeo,
     calls: &mut Vec<CallEdge>,
 ) {
@@ -671,14 +834,34 @@ fn walk_rust_calls(
                 scoped.push_str(&fn_name);
 
                 fn_scope_stack.push(scoped);
+                dyn_bindings.push(dyn_params(bytes, node));
                 entered_fn = true;
             }
         }
     }
 
+    // Track `let x: Box<dyn Trait> = ...` bindings
+    if kind == "let_declaration" {
+        if let (Some(binding), Some(scope)) = (dyn_binding(bytes, node), dyn_bindings.last_mut()) {
+            scope.insert(binding.0, binding.1);
+        }
+    }
+
     // Extract call expressions
     if kind == "call_expression" {
-        if let Some(fun) = node.child_by_field_name("function") {
+        if let Some(call_fn) = node.child_by_field_name("function") {
+            // Turbofish calls wrap the callee: `foo::<T>()`, `x.collect::<T>()`
+            let (fun, type_args) = if call_fn.kind() == "generic_function" {
+                (
+                    call_fn.child_by_field_name("function").unwrap_or(call_fn),
+                    call_fn
+                        .child_by_field_name("type_arguments")
+                        .and_then(|args| std::str::from_utf8(&bytes[args.byte_range()]).ok())
+                        .map(|args| args.to_string()),
+                )
+            } else {
+                (call_fn, None)
+            };
             if let Some(callee) = last_ident_of(bytes, fun) {
                 let caller_scoped = fn_scope_stack
                     .last()
@@ -688,11 +871,27 @@ fn walk_rust_calls(
                 // Check if it's a method call (has receiver)
                 let is_method_call = fun.kind() == "field_expression";
 
+                // Receiver bound to a `dyn Trait` resolves at runtime
+                let via_trait = if is_method_call {
+                    fun.child_by_field_name("value")
+                        .filter(|recv| recv.kind() == "identifier")
+                        .and_then(|recv| std::str::from_utf8(&bytes[recv.byte_range()]).ok())
+                        .and_then(|recv| dyn_bindings.last().and_then(|b| b.get(recv)))
+                        .cloned()
+                } else {
+                    None
+                };
+
                 let call = CallEdge {
-                    caller: interner.get_or_intern(&caller_scoped),
+                    caller: intern_identifier(interner, &caller_scoped),
                     callee_name: callee,
                     location: location_for(node, file),
                     is_method_call,
+                    via_trait,
+                    is_approximate: false,
+                    type_args,
+                    is_test_context: false,
+                    resolved_callee: None,
                 };
                 calls.push(call);
             }
@@ -709,6 +908,7 @@ fn walk_rust_calls(
                 module_stack,
                 impl_type_stack,
                 fn_scope_stack,
+                dyn_bindings,
                 interner,
                 calls,
             );
@@ -718,6 +918,7 @@ fn walk_rust_calls(
     // Exit scopes
     if entered_fn {
         fn_scope_stack.pop();
+        dyn_bindings.pop();
     }
     if entered_impl {
         impl_type_stack.pop();
@@ -727,6 +928,154 @@ fn walk_rust_calls(
     }
 }
 
+// ============================================================================
+// Trait Impl Extraction Walker
+// ============================================================================
+
+fn walk_rust_trait_impls(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    module_stack: &mut Vec<String>,
+    interner: &ThreadedRodeo,
+    impls: &mut Vec<TraitImpl>,
+) {
+    let kind = node.kind();
+
+    if kind == "impl_item" {
+        if let (Some(trait_node), Some(type_name)) = (
+            node.child_by_field_name("trait"),
+            impl_type_ident(bytes, node),
+        ) {
+            if let Some(trait_name) = trait_name_of(bytes, trait_node) {
+                let mut methods = Vec::new();
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut cursor = body.walk();
+                    for item in body.children(&mut cursor) {
+                        if item.kind() != "function_item" {
+                            continue;
+                        }
+                        if let Some(fn_name) = item
+                            .child_by_field_name("name")
+                            .and_then(|n| last_ident_of(bytes, n))
+                        {
+                            let scoped =
+                                format!("{}::{}::{}", join_scope(module_stack), type_name, fn_name);
+                            methods.push(intern_identifier(interner, &scoped));
+                        }
+                    }
+                }
+                impls.push(TraitImpl {
+                    trait_name,
+                    type_name,
+                    methods,
+                    location: location_for(node, file),
+                });
+            }
+        }
+        return;
+    }
+
+    let mut entered_mod = false;
+    if kind == "mod_item" && node.child_by_field_name("body").is_some() {
+        if let Some(name) = node
+            .child_by_field_name("name")
+            .and_then(|n| last_ident_of(bytes, n))
+        {
+            module_stack.push(name);
+            entered_mod = true;
+        }
+    }
+
+    for i in 0..node.child_count() {
+        if let Some(child) = node.child(i) {
+            walk_rust_trait_impls(child, bytes, file, module_stack, interner, impls);
+        }
+    }
+
+    if entered_mod {
+        module_stack.pop();
+    }
+}
+
+// ============================================================================
+// Type Reference Extraction Walker
+// ============================================================================
+
+/// Record the type names under `node` as [`TypeRef`]s of `kind`, refining
+/// it on entering struct literal names, `impl` headers, and trait bounds.
+fn walk_rust_type_refs(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    kind: ReferenceKind,
+    refs: &mut Vec<TypeRef>,
+) {
+    let kind = match node.kind() {
+        "type_identifier" => {
+            if let Ok(text) = node.utf8_text(bytes)
+                && text != "Self"
+                && !is_type_definition_name(node)
+            {
+                refs.push(TypeRef {
+                    name: normalize_identifier(text).into_owned(),
+                    kind,
+                    location: location_for(node, file),
+                });
+            }
+            return;
+        }
+        // Generic arguments are plain type uses, whatever they qualify
+        "type_arguments" => ReferenceKind::TypeAnnotation,
+        "trait_bounds" | "dynamic_type" | "abstract_type" => ReferenceKind::TraitBound,
+        _ => kind,
+    };
+
+    let mut cursor = node.walk();
+    if !cursor.goto_first_child() {
+        return;
+    }
+    loop {
+        let child_kind = match (node.kind(), cursor.field_name()) {
+            ("impl_item", Some("type" | "trait")) => ReferenceKind::ImplTarget,
+            ("struct_expression", Some("name")) => ReferenceKind::StructLiteral,
+            _ => kind,
+        };
+        walk_rust_type_refs(cursor.node(), bytes, file, child_kind, refs);
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+}
+
+/// Whether a `type_identifier` names the type or type parameter being
+/// declared rather than using one.
+fn is_type_definition_name(node: Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        "type_parameters" => true,
+        "constrained_type_parameter" => parent.child_by_field_name("left") == Some(node),
+        "struct_item" | "enum_item" | "union_item" | "type_item" | "trait_item"
+        | "associated_type" | "type_parameter" => parent.child_by_field_name("name") == Some(node),
+        _ => false,
+    }
+}
+
+/// Trait name of an impl's `trait` field, ignoring path and generics
+/// (e.g. `fmt::Display` -> `Display`, `From<u8>` -> `From`).
+fn trait_name_of(bytes: &[u8], trait_node: Node) -> Option<String> {
+    let text = std::str::from_utf8(&bytes[trait_node.start_byte()..trait_node.end_byte()]).ok()?;
+    let path = text.split('<').next()?.trim();
+    let name = path.rsplit("::").next()?.trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
 // ============================================================================
 // Import Extraction Walker
 // ============================================================================
@@ -737,7 +1086,18 @@ fn walk_rust_imports(node: Node, bytes: &[u8], file: &Path, imports: &mut Vec<Im
     if kind == "use_declaration" {
         // Extract the full use path
         if let Some(arg_node) = node.child_by_field_name("argument") {
+            let start = imports.len();
             extract_use_tree(arg_node, bytes, file, "", imports);
+            // Only a bare `pub` makes the item reachable from outside the crate
+            let is_pub = (0..node.named_child_count())
+                .filter_map(|i| node.named_child(i))
+                .any(|c| {
+                    c.kind() == "visibility_modifier"
+                        && bytes.get(c.start_byte()..c.end_byte()) == Some(b"pub".as_slice())
+                });
+            for import in &mut imports[start..] {
+                import.is_reexport = is_pub;
+            }
         }
     }
 
@@ -775,6 +1135,8 @@ fn extract_use_tree(
                     path: full_path.clone(),
                     name,
                     is_glob: false,
+                    original_name: None,
+                    is_reexport: false,
                     location: location_for(node, file),
                 });
             }
@@ -796,10 +1158,13 @@ fn extract_use_tree(
                                 format!("{}::{}", prefix, path_text)
                             };
 
+                            let original = path_text.rsplit("::").next().unwrap_or(path_text);
                             imports.push(ImportInfo {
                                 path: full_path,
                                 name: alias_text.to_string(),
                                 is_glob: false,
+                                original_name: Some(original.to_string()),
+                                is_reexport: false,
                                 location: location_for(node, file),
                             });
                         }
@@ -831,6 +1196,8 @@ fn extract_use_tree(
                     path: full_path,
                     name: "*".to_string(),
                     is_glob: true,
+                    original_name: None,
+                    is_reexport: false,
                     location: location_for(node, file),
                 });
                 path_found = true;
@@ -857,6 +1224,8 @@ fn extract_use_tree(
                                             path: full_path,
                                             name: "*".to_string(),
                                             is_glob: true,
+                                            original_name: None,
+                                            is_reexport: false,
                                             location: location_for(node, file),
                                         });
                                         path_found = true;
@@ -881,6 +1250,8 @@ fn extract_use_tree(
                                     path: full_path,
                                     name: "*".to_string(),
                                     is_glob: true,
+                                    original_name: None,
+                                    is_reexport: false,
                                     location: location_for(node, file),
                                 });
                                 path_found = true;
@@ -896,6 +1267,8 @@ fn extract_use_tree(
                     path: prefix.to_string(),
                     name: "*".to_string(),
                     is_glob: true,
+                    original_name: None,
+                    is_reexport: false,
                     location: location_for(node, file),
                 });
             }
@@ -1015,7 +1388,109 @@ pub fn public_function(x: i32) -> i32 {
         });
         assert!(test_fn.is_some());
         let test_fn = test_fn.unwrap();
-        assert!(test_fn.attributes.iter().any(|a| a.contains("test")));
+        assert!(test_fn.is_test());
+    }
+
+    #[test]
+    fn test_structured_attributes() {
+        let source = r#"
+#[derive(Debug,
+    Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg(all(unix, feature = "fast"))]
+pub struct Config;
+
+#[deprecated(since = "0.2", note = "use `load_all`, it is faster")]
+#[unsafe(no_mangle)]
+pub fn load() {}
+
+#[tokio::test]
+async fn loads() {}
+"#;
+        let rust_parser = RustParser::new();
+        let mut parser = Parser::new();
+        parser.set_language(&rust_parser.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let interner = ThreadedRodeo::default();
+        let symbols = rust_parser
+            .extract_symbols(&tree, source, Path::new("test.rs"), &interner)
+            .unwrap();
+        let find = |name: &str| {
+            symbols
+                .iter()
+                .find(|s| interner.resolve(&s.name) == name)
+                .unwrap()
+        };
+
+        let config = find("Config");
+        assert_eq!(
+            config.derives().collect::<Vec<_>>(),
+            vec!["Debug", "Clone", "serde::Serialize"]
+        );
+        assert_eq!(
+            config.cfgs().collect::<Vec<_>>(),
+            vec![r#"all(unix, feature = "fast")"#]
+        );
+        assert_eq!(
+            config.attribute("serde").unwrap().to_string(),
+            r#"serde(rename_all = "camelCase")"#
+        );
+        assert!(!config.is_test());
+        assert_eq!(config.deprecation(), None);
+
+        let load = find("load");
+        assert_eq!(load.deprecation(), Some("use `load_all`, it is faster"));
+        assert!(load.attribute("no_mangle").is_some());
+
+        let loads = find("loads");
+        assert!(loads.is_test());
+        assert_eq!(loads.attributes, vec![Attribute::new("tokio::test", None)]);
+    }
+
+    #[test]
+    fn test_attribute_queries() {
+        let source = r#"
+#[derive(Debug, serde::Serialize)]
+pub struct Config;
+
+#[derive(Debug)]
+pub struct Plain;
+
+#[tokio::main]
+async fn main() {}
+"#;
+        let rust_parser = RustParser::new();
+        let mut parser = Parser::new();
+        parser.set_language(&rust_parser.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let interner = ThreadedRodeo::default();
+        let symbols = rust_parser
+            .extract_symbols(&tree, source, Path::new("test.rs"), &interner)
+            .unwrap();
+        let matching = |queries: &[&str]| {
+            let queries: Vec<Attribute> = queries
+                .iter()
+                .map(|q| Attribute::query(q).unwrap())
+                .collect();
+            let mut names: Vec<&str> = symbols
+                .iter()
+                .filter(|s| s.has_attributes(&queries))
+                .map(|s| interner.resolve(&s.name))
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(matching(&["derive(Serialize)"]), vec!["Config"]);
+        assert_eq!(matching(&["derive(serde::Serialize)"]), vec!["Config"]);
+        assert_eq!(matching(&["#[derive(debug)]"]), vec!["Config", "Plain"]);
+        assert_eq!(matching(&["derive(Debug, Serialize)"]), vec!["Config"]);
+        assert_eq!(matching(&["tokio::main"]), vec!["main"]);
+        assert_eq!(matching(&["main"]), vec!["main"]);
+        // Whole segments only
+        assert!(matching(&["derive(Serial)"]).is_empty());
+        assert!(matching(&["derive", "tokio::main"]).is_empty());
+        assert!(Attribute::query("derive(Debug").is_none());
     }
 
     #[test]
@@ -1107,4 +1582,151 @@ pub mod my_module {
         let scoped = interner.resolve(&bar_method.unwrap().scoped_name);
         assert_eq!(scoped, "crate::my_module::Foo::bar");
     }
+
+    #[test]
+    fn test_raw_and_unicode_identifiers() {
+        // `caf\u{e9}` is defined precomposed and called decomposed
+        let source = "pub mod r#async {\n    pub fn r#type() {}\n    pub fn caf\u{e9}() {}\n}\n\nfn main() {\n    r#async::r#type();\n    cafe\u{301}();\n}\n";
+
+        let rust_parser = RustParser::new();
+        let mut parser = Parser::new();
+        parser.set_language(&rust_parser.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let interner = ThreadedRodeo::default();
+        let path = Path::new("test.rs");
+
+        let symbols = rust_parser
+            .extract_symbols(&tree, source, path, &interner)
+            .unwrap();
+        let raw_type = symbols
+            .iter()
+            .find(|s| interner.resolve(&s.name) == "type")
+            .unwrap();
+        assert_eq!(
+            interner.resolve(&raw_type.scoped_name),
+            "crate::async::type"
+        );
+        assert_eq!(raw_type.raw_name.as_deref(), Some("r#type"));
+        let cafe = symbols
+            .iter()
+            .find(|s| interner.resolve(&s.name) == "caf\u{e9}")
+            .unwrap();
+        assert_eq!(cafe.raw_name, None);
+
+        let calls = rust_parser
+            .extract_calls(&tree, source, path, &interner)
+            .unwrap();
+        let callees: Vec<&str> = calls.iter().map(|c| c.callee_name.as_str()).collect();
+        assert!(callees.contains(&"type"));
+        assert!(callees.contains(&"caf\u{e9}"));
+    }
+
+    #[test]
+    fn test_dyn_dispatch_calls_and_trait_impls() {
+        let source = r#"
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+mod shapes {
+    pub struct Circle;
+
+    impl super::Shape for Circle {
+        fn area(&self) -> f64 { 3.14 }
+    }
+}
+
+fn total(items: &[i32], shape: Box<dyn Shape + Send>) -> f64 {
+    let other: &dyn Shape = &shapes::Circle;
+    shape.area() + other.area() + items.len() as f64
+}
+"#;
+
+        let rust_parser = RustParser::new();
+        let mut parser = Parser::new();
+        parser.set_language(&rust_parser.language()).unwrap();
+
+        let tree = parser.parse(source, None).unwrap();
+        let interner = ThreadedRodeo::default();
+
+        let calls = rust_parser
+            .extract_calls(&tree, source, Path::new("test.rs"), &interner)
+            .unwrap();
+        let dyn_calls: Vec<_> = calls.iter().filter(|c| c.via_trait.is_some()).collect();
+        assert_eq!(dyn_calls.len(), 2);
+        assert!(
+            dyn_calls
+                .iter()
+                .all(|c| c.via_trait.as_deref() == Some("Shape"))
+        );
+        assert!(
+            calls
+                .iter()
+                .any(|c| c.callee_name == "len" && c.via_trait.is_none())
+        );
+
+        let impls = rust_parser
+            .extract_trait_impls(&tree, source, Path::new("test.rs"), &interner)
+            .unwrap();
+        assert_eq!(impls.len(), 1);
+        assert_eq!(impls[0].trait_name, "Shape");
+        assert_eq!(impls[0].type_name, "Circle");
+        assert_eq!(
+            interner.resolve(&impls[0].methods[0]),
+            "crate::shapes::Circle::area"
+        );
+    }
+
+    #[test]
+    fn test_type_refs() {
+        let source = r#"
+struct Config<T> {
+    inner: Option<T>,
+}
+
+trait Load: Sized {}
+
+impl<T: Clone> Load for Config<T> where T: Default {}
+
+fn build(loader: impl Load) -> Config<u8> {
+    let config: Config<u8> = Config { inner: None };
+    config
+}
+"#;
+
+        let rust_parser = RustParser::new();
+        let mut parser = Parser::new();
+        parser.set_language(&rust_parser.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let refs = rust_parser
+            .extract_type_refs(&tree, source, Path::new("test.rs"))
+            .unwrap();
+        let kinds = |name: &str| -> Vec<ReferenceKind> {
+            refs.iter()
+                .filter(|r| r.name == name)
+                .map(|r| r.kind)
+                .collect()
+        };
+        assert_eq!(
+            kinds("Config"),
+            vec![
+                ReferenceKind::ImplTarget,
+                ReferenceKind::TypeAnnotation,
+                ReferenceKind::TypeAnnotation,
+                ReferenceKind::StructLiteral,
+            ]
+        );
+        assert_eq!(
+            kinds("Load"),
+            vec![ReferenceKind::ImplTarget, ReferenceKind::TraitBound]
+        );
+        assert_eq!(kinds("Sized"), vec![ReferenceKind::TraitBound]);
+        assert_eq!(kinds("Clone"), vec![ReferenceKind::TraitBound]);
+        assert_eq!(kinds("Default"), vec![ReferenceKind::TraitBound]);
+        // Declaring `T` is not a use; `Option<T>`, `Config<T>` and the
+        // `where` clause are
+        assert_eq!(kinds("T").len(), 3);
+        assert_eq!(kinds("Option"), vec![ReferenceKind::TypeAnnotation]);
+    }
 }
@@ -0,0 +1,743 @@
+//! Table and column extraction for SQL migration files.
+//!
+//! Schemas usually live in `migrations/*.sql`, and an agent writing a query
+//! needs the current columns of a table. [`extract_schema_changes`] scans a
+//! SQL file for the DDL that shapes a schema (`CREATE TABLE`, `ALTER TABLE
+//! ... ADD/DROP/RENAME/ALTER COLUMN`, `ALTER TABLE ... RENAME TO`, `DROP
+//! TABLE`), and [`sql_symbols`] turns the definitions among them into
+//! `Table` and `Column` symbols (`users`, `users.email`).
+//!
+//! Like the config scanners, this follows statement structure without
+//! validating it: comments, quoted identifiers and string literals are
+//! honoured, dialect details (types, constraints) are kept as written.
+//! Unquoted identifiers are folded to lowercase, as Postgres and SQLite
+//! treat them.
+
+use crate::types::{Location, SymbolDef, SymbolKind, Visibility};
+use std::path::Path;
+
+/// Whether `path` is a SQL file.
+pub fn is_sql_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sql"))
+}
+
+/// A table created by a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlTableDef {
+    pub name: String,
+    /// 0-based line of the statement
+    pub line: usize,
+    /// Byte range of the statement
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+/// A column definition (`email TEXT NOT NULL`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlColumnDef {
+    pub name: String,
+    /// Type as written (`VARCHAR(255)`), if any
+    pub data_type: Option<String>,
+    /// 0-based line of the definition
+    pub line: usize,
+    /// Byte range of the definition
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+/// One schema-changing statement, in file order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    CreateTable {
+        table: SqlTableDef,
+        columns: Vec<SqlColumnDef>,
+    },
+    AddColumn {
+        table: String,
+        column: SqlColumnDef,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    RenameColumn {
+        table: String,
+        from: String,
+        to: SqlColumnDef,
+    },
+    AlterColumnType {
+        table: String,
+        column: String,
+        data_type: String,
+    },
+    RenameTable {
+        from: String,
+        to: SqlTableDef,
+    },
+    DropTable {
+        table: String,
+    },
+}
+
+/// Words ending a column's type and starting its constraints.
+const CONSTRAINT_WORDS: &[&str] = &[
+    "not",
+    "null",
+    "default",
+    "primary",
+    "references",
+    "unique",
+    "check",
+    "constraint",
+    "generated",
+    "collate",
+    "auto_increment",
+    "autoincrement",
+    "identity",
+    "comment",
+    "on",
+];
+
+/// First words of table-level constraints in a `CREATE TABLE` body.
+const TABLE_CONSTRAINTS: &[&str] = &[
+    "constraint",
+    "primary",
+    "foreign",
+    "unique",
+    "check",
+    "index",
+    "key",
+    "exclude",
+    "fulltext",
+    "spatial",
+    "like",
+    "period",
+];
+
+/// Extract the schema changes of a SQL file, in file order.
+pub fn extract_schema_changes(source: &str) -> Vec<SchemaChange> {
+    let tokens = tokenize(source);
+    let lines = LineIndex::new(source);
+    let mut changes = Vec::new();
+    for statement in tokens.split(|t| t.kind == TokenKind::Semicolon) {
+        if statement.is_empty() {
+            continue;
+        }
+        let words: Vec<String> = statement.iter().take(8).map(|t| t.word()).collect();
+        let starts = |prefix: &[&str]| {
+            words
+                .iter()
+                .map(String::as_str)
+                .take(prefix.len())
+                .eq(prefix.iter().copied())
+        };
+        if starts(&["create"]) {
+            changes.extend(create_table(statement, &lines));
+        } else if starts(&["alter", "table"]) {
+            changes.extend(alter_table(&statement[2..], &lines));
+        } else if starts(&["drop", "table"]) {
+            changes.extend(drop_tables(&statement[2..]));
+        }
+    }
+    changes
+}
+
+/// `Table` and `Column` symbols for the definitions in `changes`: created
+/// tables with their columns, added columns, and the new names of renamed
+/// tables and columns.
+pub fn sql_symbols(
+    changes: &[SchemaChange],
+    source: &str,
+    file: &Path,
+    interner: &lasso::ThreadedRodeo,
+) -> Vec<SymbolDef> {
+    let lines = LineIndex::new(source);
+    let symbol = |kind, name: &str, scoped: &str, parent: Option<&str>, start, end| {
+        let (start_line, start_col) = lines.position(start);
+        let (end_line, end_col) = lines.position(end);
+        SymbolDef {
+            name: interner.get_or_intern(name),
+            raw_name: None,
+            scoped_name: interner.get_or_intern(scoped),
+            kind,
+            location: Location::new(file.to_path_buf(), start, end)
+                .with_positions(start_line, start_col, end_line, end_col),
+            extended_location: None,
+            signature: None,
+            visibility: Visibility::Public,
+            attributes: Vec::new(),
+            ignores: Vec::new(),
+            doc_comment: None,
+            parent: parent.map(|p| interner.get_or_intern(p)),
+            is_test_context: false,
+        }
+    };
+    let column = |table: &str, c: &SqlColumnDef| {
+        symbol(
+            SymbolKind::Column,
+            &c.name,
+            &format!("{}.{}", table, c.name),
+            Some(table),
+            c.start_byte,
+            c.end_byte,
+        )
+    };
+    let table = |t: &SqlTableDef| {
+        symbol(
+            SymbolKind::Table,
+            &t.name,
+            &t.name,
+            None,
+            t.start_byte,
+            t.end_byte,
+        )
+    };
+
+    let mut symbols = Vec::new();
+    for change in changes {
+        match change {
+            SchemaChange::CreateTable { table: t, columns } => {
+                symbols.push(table(t));
+                symbols.extend(columns.iter().map(|c| column(&t.name, c)));
+            }
+            SchemaChange::AddColumn { table, column: c } => symbols.push(column(table, c)),
+            SchemaChange::RenameColumn { table, to, .. } => symbols.push(column(table, to)),
+            SchemaChange::RenameTable { to, .. } => symbols.push(table(to)),
+            _ => {}
+        }
+    }
+    symbols
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    /// `"name"`, `` `name` `` or `[name]`
+    Quoted,
+    /// `'text'`, `$$text$$`
+    Literal,
+    Open,
+    Close,
+    Comma,
+    Dot,
+    Semicolon,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl Token<'_> {
+    /// Lowercased word, for keyword matching.
+    fn word(&self) -> String {
+        match self.kind {
+            TokenKind::Word => self.text.to_ascii_lowercase(),
+            _ => String::new(),
+        }
+    }
+
+    /// Identifier text: quotes removed, unquoted names lowercased.
+    fn ident(&self) -> Option<String> {
+        match self.kind {
+            TokenKind::Word => Some(self.text.to_ascii_lowercase()),
+            TokenKind::Quoted => self.text.get(1..self.text.len() - 1).map(str::to_string),
+            _ => None,
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let b = bytes[i];
+        let kind = match b {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = find_from(source, i, "\n").unwrap_or(bytes.len());
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = find_from(source, i + 2, "*/").map_or(bytes.len(), |end| end + 2);
+                continue;
+            }
+            b'\'' => {
+                i = quoted_end(bytes, i, b'\'');
+                TokenKind::Literal
+            }
+            b'"' | b'`' => {
+                i = quoted_end(bytes, i, b);
+                TokenKind::Quoted
+            }
+            b'[' => {
+                i = find_from(source, i, "]").map_or(bytes.len(), |end| end + 1);
+                TokenKind::Quoted
+            }
+            b'$' => {
+                // Dollar-quoted body (`$$ ... $$`, `$fn$ ... $fn$`)
+                let tag_end = source[i + 1..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .map(|n| i + 1 + n);
+                match tag_end.filter(|&e| bytes[e] == b'$') {
+                    Some(e) => {
+                        let tag = &source[i..=e];
+                        i = find_from(source, e + 1, tag)
+                            .map_or(bytes.len(), |end| end + tag.len());
+                        TokenKind::Literal
+                    }
+                    None => {
+                        i += 1;
+                        TokenKind::Other
+                    }
+                }
+            }
+            b'(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            b')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            b',' => {
+                i += 1;
+                TokenKind::Comma
+            }
+            b'.' => {
+                i += 1;
+                TokenKind::Dot
+            }
+            b';' => {
+                i += 1;
+                TokenKind::Semicolon
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80 => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                i += 1;
+                TokenKind::Other
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &source[start..i],
+            start,
+            end: i,
+        });
+    }
+    tokens
+}
+
+fn find_from(source: &str, from: usize, needle: &str) -> Option<usize> {
+    source.get(from..)?.find(needle).map(|n| from + n)
+}
+
+/// End of a quoted run starting at `start`, where a doubled quote escapes.
+fn quoted_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Skip the keywords `words` (in order) if they come next.
+fn skip_words<'a, 'b>(tokens: &'a [Token<'b>], words: &[&str]) -> &'a [Token<'b>] {
+    let matches =
+        tokens.len() >= words.len() && tokens.iter().zip(words).all(|(t, w)| t.word() == *w);
+    if matches {
+        &tokens[words.len()..]
+    } else {
+        tokens
+    }
+}
+
+/// A possibly schema-qualified name (`public.users`): the last part, and
+/// the tokens after it.
+fn qualified_name<'a, 'b>(tokens: &'a [Token<'b>]) -> Option<(String, &'a [Token<'b>])> {
+    let mut name = tokens.first()?.ident()?;
+    let mut rest = &tokens[1..];
+    while rest.len() >= 2 && rest[0].kind == TokenKind::Dot {
+        name = rest[1].ident()?;
+        rest = &rest[2..];
+    }
+    Some((name, rest))
+}
+
+/// Split tokens at top-level commas.
+fn split_commas<'a, 'b>(tokens: &'a [Token<'b>]) -> Vec<&'a [Token<'b>]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        match t.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            TokenKind::Comma if depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+fn create_table(statement: &[Token], lines: &LineIndex) -> Option<SchemaChange> {
+    let mut rest = skip_words(&statement[1..], &["or", "replace"]);
+    while let Some(t) = rest.first() {
+        match t.word().as_str() {
+            "temp" | "temporary" | "unlogged" | "global" | "local" | "virtual" => rest = &rest[1..],
+            _ => break,
+        }
+    }
+    if rest.first()?.word() != "table" {
+        return None;
+    }
+    let rest = skip_words(&rest[1..], &["if", "not", "exists"]);
+    let (name, rest) = qualified_name(rest)?;
+
+    // `CREATE VIRTUAL TABLE t USING fts5(...)`, `CREATE TABLE t AS SELECT`
+    // have no column list of their own
+    let body = match rest.first() {
+        Some(t) if t.kind == TokenKind::Open => body_of(rest),
+        _ => &[],
+    };
+    let columns = split_commas(body)
+        .into_iter()
+        .filter(|def| !TABLE_CONSTRAINTS.contains(&def[0].word().as_str()))
+        .filter_map(|def| column_def(def, lines))
+        .collect();
+
+    let start = statement[0].start;
+    let end = statement.last()?.end;
+    Some(SchemaChange::CreateTable {
+        table: SqlTableDef {
+            name,
+            line: lines.position(start).0,
+            start_byte: start,
+            end_byte: end,
+        },
+        columns,
+    })
+}
+
+/// Tokens inside the parenthesized group opening at `tokens[0]`.
+fn body_of<'a, 'b>(tokens: &'a [Token<'b>]) -> &'a [Token<'b>] {
+    let mut depth = 0usize;
+    for (i, t) in tokens.iter().enumerate() {
+        match t.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => {
+                depth -= 1;
+                if depth == 0 {
+                    return &tokens[1..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    &tokens[1..]
+}
+
+/// `name type constraints...`
+fn column_def(def: &[Token], lines: &LineIndex) -> Option<SqlColumnDef> {
+    let name = def.first()?.ident()?;
+    let start = def[0].start;
+    let end = def.last()?.end;
+    Some(SqlColumnDef {
+        name,
+        data_type: data_type(&def[1..]),
+        line: lines.position(start).0,
+        start_byte: start,
+        end_byte: end,
+    })
+}
+
+/// The type at the start of `tokens`, up to the first constraint.
+fn data_type(tokens: &[Token]) -> Option<String> {
+    let mut data_type = String::new();
+    let mut depth = 0usize;
+    for t in tokens {
+        if depth == 0 && t.kind == TokenKind::Word && CONSTRAINT_WORDS.contains(&t.word().as_str())
+        {
+            break;
+        }
+        match t.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        let glued = matches!(
+            t.kind,
+            TokenKind::Open | TokenKind::Close | TokenKind::Comma
+        ) || data_type.is_empty()
+            || data_type.ends_with(['(', ' ']);
+        if !glued {
+            data_type.push(' ');
+        }
+        data_type.push_str(t.text);
+        if t.kind == TokenKind::Comma {
+            data_type.push(' ');
+        }
+    }
+    (!data_type.is_empty()).then_some(data_type)
+}
+
+fn alter_table(tokens: &[Token], lines: &LineIndex) -> Vec<SchemaChange> {
+    let rest = skip_words(tokens, &["if", "exists"]);
+    let rest = skip_words(rest, &["only"]);
+    let Some((table, rest)) = qualified_name(rest) else {
+        return Vec::new();
+    };
+    split_commas(rest)
+        .into_iter()
+        .filter_map(|action| alter_action(&table, action, lines))
+        .collect()
+}
+
+fn alter_action(table: &str, action: &[Token], lines: &LineIndex) -> Option<SchemaChange> {
+    let table = table.to_string();
+    match action.first()?.word().as_str() {
+        "add" => {
+            let rest = skip_words(&action[1..], &["column"]);
+            let rest = skip_words(rest, &["if", "not", "exists"]);
+            if TABLE_CONSTRAINTS.contains(&rest.first()?.word().as_str()) {
+                return None;
+            }
+            Some(SchemaChange::AddColumn {
+                table,
+                column: column_def(rest, lines)?,
+            })
+        }
+        "drop" => {
+            let rest = skip_words(&action[1..], &["column"]);
+            let rest = skip_words(rest, &["if", "exists"]);
+            if TABLE_CONSTRAINTS.contains(&rest.first()?.word().as_str()) {
+                return None;
+            }
+            Some(SchemaChange::DropColumn {
+                table,
+                column: rest.first()?.ident()?,
+            })
+        }
+        "rename" => {
+            let rest = &action[1..];
+            if rest.first()?.word() == "to" {
+                let to = rest.get(1)?;
+                return Some(SchemaChange::RenameTable {
+                    from: table,
+                    to: SqlTableDef {
+                        name: to.ident()?,
+                        line: lines.position(to.start).0,
+                        start_byte: action[0].start,
+                        end_byte: action.last()?.end,
+                    },
+                });
+            }
+            let rest = skip_words(rest, &["column"]);
+            let (from, to) = match rest {
+                [from, kw, to, ..] if kw.word() == "to" => (from.ident()?, to),
+                _ => return None,
+            };
+            Some(SchemaChange::RenameColumn {
+                table,
+                from,
+                to: SqlColumnDef {
+                    name: to.ident()?,
+                    data_type: None,
+                    line: lines.position(to.start).0,
+                    start_byte: action[0].start,
+                    end_byte: action.last()?.end,
+                },
+            })
+        }
+        // `ALTER COLUMN c TYPE t`, `ALTER COLUMN c SET DATA TYPE t`,
+        // `MODIFY COLUMN c t` (MySQL)
+        "alter" | "modify" => {
+            let rest = skip_words(&action[1..], &["column"]);
+            let column = rest.first()?.ident()?;
+            let rest = skip_words(&rest[1..], &["set", "data"]);
+            let rest = skip_words(rest, &["type"]);
+            Some(SchemaChange::AlterColumnType {
+                table,
+                column,
+                data_type: data_type(rest)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn drop_tables(tokens: &[Token]) -> Vec<SchemaChange> {
+    let rest = skip_words(tokens, &["if", "exists"]);
+    split_commas(rest)
+        .into_iter()
+        .filter_map(|name| qualified_name(name).map(|(table, _)| SchemaChange::DropTable { table }))
+        .collect()
+}
+
+/// Byte offset to 0-based (line, column) lookup.
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { starts }
+    }
+
+    fn position(&self, byte: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&s| s <= byte) - 1;
+        (line, byte - self.starts[line])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIGRATION: &str = r#"-- users and their sessions
+CREATE TABLE IF NOT EXISTS public.users (
+    id BIGSERIAL PRIMARY KEY,
+    "Email" VARCHAR(255) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT now(),
+    CONSTRAINT email_format CHECK ("Email" LIKE '%@%;%')
+);
+
+/* sessions; expire after a day */
+CREATE TABLE sessions (id INTEGER, user_id BIGINT REFERENCES users (id), price NUMERIC(10, 2));
+
+ALTER TABLE users ADD COLUMN name TEXT, DROP COLUMN IF EXISTS legacy;
+ALTER TABLE ONLY users RENAME COLUMN name TO display_name;
+ALTER TABLE users ALTER COLUMN display_name TYPE VARCHAR(80);
+ALTER TABLE sessions ADD CONSTRAINT sessions_pk PRIMARY KEY (id);
+ALTER TABLE sessions RENAME TO user_sessions;
+DROP TABLE IF EXISTS old_logs, audit;
+CREATE INDEX users_email ON users ("Email");
+"#;
+
+    #[test]
+    fn test_extracts_schema_changes() {
+        let changes = extract_schema_changes(MIGRATION);
+        let SchemaChange::CreateTable { table, columns } = &changes[0] else {
+            panic!("expected CREATE TABLE, got {:?}", changes[0]);
+        };
+        assert_eq!(table.name, "users");
+        assert_eq!(table.line, 1);
+        let columns: Vec<(&str, Option<&str>, usize)> = columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.as_deref(), c.line))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", Some("BIGSERIAL"), 2),
+                ("Email", Some("VARCHAR(255)"), 3),
+                ("created_at", Some("TIMESTAMP WITH TIME ZONE"), 4),
+            ]
+        );
+
+        let SchemaChange::CreateTable { columns, .. } = &changes[1] else {
+            panic!("expected CREATE TABLE, got {:?}", changes[1]);
+        };
+        let types: Vec<Option<&str>> = columns.iter().map(|c| c.data_type.as_deref()).collect();
+        assert_eq!(
+            types,
+            vec![Some("INTEGER"), Some("BIGINT"), Some("NUMERIC(10, 2)")]
+        );
+
+        let rest: Vec<String> = changes[2..]
+            .iter()
+            .map(|c| match c {
+                SchemaChange::AddColumn { table, column } => format!("add {table}.{}", column.name),
+                SchemaChange::DropColumn { table, column } => format!("drop {table}.{column}"),
+                SchemaChange::RenameColumn { table, from, to } => {
+                    format!("rename {table}.{from} {}", to.name)
+                }
+                SchemaChange::AlterColumnType {
+                    table,
+                    column,
+                    data_type,
+                } => format!("type {table}.{column} {data_type}"),
+                SchemaChange::RenameTable { from, to } => format!("rename {from} {}", to.name),
+                SchemaChange::DropTable { table } => format!("drop {table}"),
+                SchemaChange::CreateTable { table, .. } => format!("create {}", table.name),
+            })
+            .collect();
+        assert_eq!(
+            rest,
+            vec![
+                "add users.name",
+                "drop users.legacy",
+                "rename users.name display_name",
+                "type users.display_name VARCHAR(80)",
+                "rename sessions user_sessions",
+                "drop old_logs",
+                "drop audit",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbols_for_tables_and_columns() {
+        let interner = lasso::ThreadedRodeo::default();
+        let changes = extract_schema_changes(MIGRATION);
+        let symbols = sql_symbols(&changes, MIGRATION, Path::new("m.sql"), &interner);
+        let names: Vec<(&str, SymbolKind)> = symbols
+            .iter()
+            .map(|s| (interner.resolve(&s.scoped_name), s.kind))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("users", SymbolKind::Table),
+                ("users.id", SymbolKind::Column),
+                ("users.Email", SymbolKind::Column),
+                ("users.created_at", SymbolKind::Column),
+                ("sessions", SymbolKind::Table),
+                ("sessions.id", SymbolKind::Column),
+                ("sessions.user_id", SymbolKind::Column),
+                ("sessions.price", SymbolKind::Column),
+                ("users.name", SymbolKind::Column),
+                ("users.display_name", SymbolKind::Column),
+                ("user_sessions", SymbolKind::Table),
+            ]
+        );
+        let email = &symbols[2];
+        assert_eq!(interner.resolve(&email.name), "Email");
+        assert_eq!(email.parent.map(|p| interner.resolve(&p)), Some("users"));
+        assert_eq!(email.location.start_line, 3);
+        assert_eq!(email.location.start_col, 4);
+    }
+}
@@ -1,10 +1,14 @@
 //! TypeScript/TSX language parser using tree-sitter.
 
-use super::LanguageParser;
+use super::{LanguageParser, intern_identifier, normalize_identifier, raw_spelling};
 use crate::types::*;
 use anyhow::Result;
 use lasso::ThreadedRodeo;
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 use tree_sitter::{Language, Node, Tree};
 
 /// TypeScript/TSX source code parser.
@@ -27,6 +31,15 @@ impl TypeScriptParser {
             extensions: &["tsx"],
         }
     }
+
+    /// JavaScript, parsed with the TSX grammar: it accepts plain JS and the
+    /// JSX that React projects also write in `.js` files.
+    pub fn new_javascript() -> Self {
+        Self {
+            language: tree_sitter_typescript::LANGUAGE_TSX.into(),
+            extensions: &["js", "jsx", "mjs", "cjs"],
+        }
+    }
 }
 
 impl LanguageParser for TypeScriptParser {
@@ -38,6 +51,10 @@ impl LanguageParser for TypeScriptParser {
         self.extensions
     }
 
+    fn first_line(&self) -> usize {
+        1
+    }
+
     fn extract_symbols(
         &self,
         tree: &Tree,
@@ -52,6 +69,11 @@ impl LanguageParser for TypeScriptParser {
 
         walk_ts_symbols(root, bytes, file, &mut scope_stack, interner, &mut symbols);
 
+        for symbol in &mut symbols {
+            symbol.doc_comment = jsdoc_before(source, symbol.location.start_byte);
+        }
+        super::attach_ignore_directives(&mut symbols, source);
+        super::attach_extended_locations(&mut symbols, source);
         Ok(symbols)
     }
 
@@ -85,8 +107,9 @@ impl LanguageParser for TypeScriptParser {
         let bytes = source.as_bytes();
         let root = tree.root_node();
         let mut imports = Vec::new();
+        let aliases = PathAliases::for_file(file);
 
-        walk_ts_imports(root, bytes, file, &mut imports);
+        walk_ts_imports(root, bytes, file, aliases.as_deref(), &mut imports);
 
         Ok(imports)
     }
@@ -107,6 +130,23 @@ fn location_for(node: Node, file: &Path) -> Location {
     )
 }
 
+/// The `/** .. */` comment directly above a declaration, looking past
+/// `export`/`export default` since declarations start after them.
+fn jsdoc_before(source: &str, start_byte: usize) -> Option<String> {
+    let before = source.get(..start_byte)?.trim_end();
+    let before = ["export default", "export"]
+        .iter()
+        .find_map(|kw| before.strip_suffix(kw))
+        .unwrap_or(before)
+        .trim_end();
+    let body = before.strip_suffix("*/")?;
+    let open = body.rfind("/**")?;
+    if body[open..].contains("*/") {
+        return None;
+    }
+    Some(before[open..].to_string())
+}
+
 fn text_of(bytes: &[u8], node: Node) -> Option<String> {
     std::str::from_utf8(&bytes[node.start_byte()..node.end_byte()])
         .ok()
@@ -118,7 +158,7 @@ fn strip_quotes(s: &str) -> String {
         .to_string()
 }
 
-fn file_scope_for(file: &Path) -> String {
+pub(super) fn file_scope_for(file: &Path) -> String {
     let root = find_workspace_root(file).unwrap_or_else(|| {
         file.parent()
             .map(|p| p.to_path_buf())
@@ -231,6 +271,34 @@ fn extract_callee_name(bytes: &[u8], node: Node) -> Option<String> {
     }
 }
 
+/// Name of a class field initialized with an arrow function or function
+/// expression (`handler = () => {...}`).
+fn arrow_property_name(bytes: &[u8], node: Node) -> Option<String> {
+    let value = node.child_by_field_name("value")?;
+    if !matches!(
+        value.kind(),
+        "arrow_function" | "function_expression" | "function"
+    ) {
+        return None;
+    }
+    text_of(bytes, node.child_by_field_name("name")?)
+}
+
+/// Make the symbol just added for a class member `node` a member of the
+/// class it is declared in, the innermost scope.
+fn set_class_parent(
+    symbols: &mut [SymbolDef],
+    interner: &ThreadedRodeo,
+    node: Node,
+    scope_stack: &[String],
+) {
+    if node.parent().is_some_and(|p| p.kind() == "class_body")
+        && let (Some(class), Some(symbol)) = (scope_stack.last(), symbols.last_mut())
+    {
+        symbol.parent = Some(intern_identifier(interner, class));
+    }
+}
+
 fn make_scoped_name(scope_stack: &[String], name: &str) -> String {
     let mut full = scope_stack.join("::");
     if !full.is_empty() {
@@ -251,15 +319,19 @@ fn add_symbol(
 ) {
     let scoped_name = make_scoped_name(scope_stack, name);
     symbols.push(SymbolDef {
-        name: interner.get_or_intern(name),
-        scoped_name: interner.get_or_intern(&scoped_name),
+        name: intern_identifier(interner, name),
+        scoped_name: intern_identifier(interner, &scoped_name),
         kind,
         location: location_for(node, file),
+        extended_location: None,
         signature: None,
         visibility: Visibility::Private,
         attributes: Vec::new(),
         doc_comment: None,
         parent: None,
+        ignores: Vec::new(),
+        raw_name: raw_spelling(name),
+        is_test_context: false,
     });
 }
 
@@ -311,6 +383,7 @@ fn walk_ts_symbols(
                         file,
                         node,
                     );
+                    set_class_parent(symbols, interner, node, scope_stack);
                     scope_stack.push(name);
                     walk_children(
                         node,
@@ -326,7 +399,7 @@ fn walk_ts_symbols(
                 }
             }
         }
-        "class_declaration" => {
+        "class_declaration" | "abstract_class_declaration" => {
             if let Some(name_node) = node.child_by_field_name("name") {
                 if let Some(name) = text_of(bytes, name_node) {
                     add_symbol(
@@ -429,6 +502,21 @@ fn walk_ts_symbols(
                 }
             }
         }
+        "public_field_definition" => {
+            // `handler = () => {...}`: a method bound to the instance
+            if let Some(name) = arrow_property_name(bytes, node) {
+                add_symbol(
+                    symbols,
+                    interner,
+                    scope_stack,
+                    &name,
+                    SymbolKind::Method,
+                    file,
+                    node,
+                );
+                set_class_parent(symbols, interner, node, scope_stack);
+            }
+        }
         _ => {}
     }
 
@@ -466,7 +554,7 @@ fn walk_ts_calls(
                 }
             }
         }
-        "class_declaration" | "interface_declaration" => {
+        "class_declaration" | "abstract_class_declaration" | "interface_declaration" => {
             if let Some(name_node) = node.child_by_field_name("name") {
                 if let Some(name) = text_of(bytes, name_node) {
                     scope_stack.push(name);
@@ -491,6 +579,39 @@ fn walk_ts_calls(
                 }
             }
         }
+        "public_field_definition" => {
+            if let Some(name) = arrow_property_name(bytes, node) {
+                let scoped = make_scoped_name(scope_stack, &name);
+                fn_stack.push(scoped);
+                walk_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
+                fn_stack.pop();
+                return;
+            }
+        }
+        // Rendering a component (`<Button />`) calls it
+        "jsx_opening_element" | "jsx_self_closing_element" => {
+            if let Some(component) = node
+                .child_by_field_name("name")
+                .and_then(|name| extract_callee_name(bytes, name))
+                .filter(|name| name.starts_with(|c: char| c.is_ascii_uppercase()))
+            {
+                let caller_name = fn_stack
+                    .last()
+                    .cloned()
+                    .unwrap_or_else(|| scope_stack.join("::"));
+                calls.push(CallEdge {
+                    caller: intern_identifier(interner, &caller_name),
+                    callee_name: normalize_identifier(&component).into_owned(),
+                    location: location_for(node, file),
+                    is_method_call: false,
+                    via_trait: None,
+                    is_approximate: false,
+                    type_args: None,
+                    is_test_context: false,
+                    resolved_callee: None,
+                });
+            }
+        }
         "call_expression" => {
             if let Some(callee_node) = node
                 .child_by_field_name("function")
@@ -506,10 +627,15 @@ fn walk_ts_calls(
                         "member_expression" | "optional_chain" | "subscript_expression"
                     );
                     calls.push(CallEdge {
-                        caller: interner.get_or_intern(&caller_name),
-                        callee_name,
+                        caller: intern_identifier(interner, &caller_name),
+                        callee_name: normalize_identifier(&callee_name).into_owned(),
                         location: location_for(node, file),
                         is_method_call,
+                        via_trait: None,
+                        is_approximate: false,
+                        type_args: None,
+                        is_test_context: false,
+                        resolved_callee: None,
                     });
                 }
             }
@@ -520,29 +646,325 @@ fn walk_ts_calls(
     walk_children_calls(node, bytes, file, scope_stack, fn_stack, interner, calls);
 }
 
-fn walk_ts_imports(node: Node, bytes: &[u8], file: &Path, imports: &mut Vec<ImportInfo>) {
+fn walk_ts_imports(
+    node: Node,
+    bytes: &[u8],
+    file: &Path,
+    aliases: Option<&PathAliases>,
+    imports: &mut Vec<ImportInfo>,
+) {
     if node.kind() == "import_statement" {
         let source_node = node.child_by_field_name("source");
-        let path = source_node
+        let specifier = source_node
             .and_then(|n| text_of(bytes, n))
             .map(|s| strip_quotes(&s))
             .unwrap_or_else(|| "".to_string());
-        let name = first_identifier(bytes, node).unwrap_or_else(|| path.clone());
-        let text = text_of(bytes, node).unwrap_or_default();
-        let is_glob = text.contains('*');
+        let path = aliases
+            .and_then(|a| a.resolve(&specifier))
+            .unwrap_or(specifier);
+
+        // One entry per local binding: (name, original name, is_glob)
+        let mut bindings: Vec<(String, Option<String>, bool)> = Vec::new();
+        let mut cursor = node.walk();
+        let clause = node
+            .named_children(&mut cursor)
+            .find(|c| c.kind() == "import_clause");
+        if let Some(clause) = clause {
+            let mut cursor = clause.walk();
+            for child in clause.named_children(&mut cursor) {
+                match child.kind() {
+                    // import Foo from "x"
+                    "identifier" => {
+                        bindings.extend(text_of(bytes, child).map(|n| (n, None, false)))
+                    }
+                    // import * as ns from "x"
+                    "namespace_import" => {
+                        bindings.extend(first_identifier(bytes, child).map(|n| (n, None, true)))
+                    }
+                    // import { a, b as c } from "x"
+                    "named_imports" => {
+                        let mut cursor = child.walk();
+                        for spec in child.named_children(&mut cursor) {
+                            if spec.kind() != "import_specifier" {
+                                continue;
+                            }
+                            let name = spec
+                                .child_by_field_name("name")
+                                .and_then(|n| text_of(bytes, n));
+                            let alias = spec
+                                .child_by_field_name("alias")
+                                .and_then(|n| text_of(bytes, n));
+                            match (name, alias) {
+                                (Some(name), Some(alias)) => {
+                                    bindings.push((alias, Some(name), false))
+                                }
+                                (Some(name), None) => bindings.push((name, None, false)),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // Side-effect import: import "x"
+        if bindings.is_empty() {
+            bindings.push((path.clone(), None, false));
+        }
+
+        for (name, original_name, is_glob) in bindings {
+            imports.push(ImportInfo {
+                path: path.clone(),
+                name,
+                original_name,
+                is_reexport: false,
+                is_glob,
+                location: location_for(node, file),
+            });
+        }
+    }
 
-        imports.push(ImportInfo {
-            path,
-            name,
-            is_glob,
-            location: location_for(node, file),
-        });
+    // CommonJS: const x = require("x"), const { a, b: c } = require("x"),
+    // or a bare require("x") for its side effects
+    if let Some(specifier) = require_specifier(node, bytes) {
+        let path = aliases
+            .and_then(|a| a.resolve(&specifier))
+            .unwrap_or(specifier);
+        let mut bindings: Vec<(String, Option<String>)> = Vec::new();
+        let parent = node.parent();
+        match parent.and_then(|p| p.child_by_field_name("name")) {
+            Some(name) if parent.is_some_and(|p| p.kind() == "variable_declarator") => {
+                match name.kind() {
+                    "identifier" => bindings.extend(text_of(bytes, name).map(|n| (n, None))),
+                    "object_pattern" => {
+                        let mut cursor = name.walk();
+                        for prop in name.named_children(&mut cursor) {
+                            match prop.kind() {
+                                "shorthand_property_identifier_pattern" => {
+                                    bindings.extend(text_of(bytes, prop).map(|n| (n, None)))
+                                }
+                                "pair_pattern" => {
+                                    let key = prop
+                                        .child_by_field_name("key")
+                                        .and_then(|n| text_of(bytes, n));
+                                    let value = prop
+                                        .child_by_field_name("value")
+                                        .and_then(|n| text_of(bytes, n));
+                                    if let (Some(key), Some(value)) = (key, value) {
+                                        bindings.push((value, Some(key)));
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ if parent.is_some_and(|p| p.kind() == "expression_statement") => {
+                bindings.push((path.clone(), None));
+            }
+            _ => {}
+        }
+        for (name, original_name) in bindings {
+            imports.push(ImportInfo {
+                path: path.clone(),
+                name,
+                original_name,
+                is_reexport: false,
+                is_glob: false,
+                location: location_for(node, file),
+            });
+        }
     }
 
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
-        walk_ts_imports(child, bytes, file, imports);
+        walk_ts_imports(child, bytes, file, aliases, imports);
+    }
+}
+
+/// The module named by a `require("...")` call.
+fn require_specifier(node: Node, bytes: &[u8]) -> Option<String> {
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let function = node.child_by_field_name("function")?;
+    if function.kind() != "identifier" || text_of(bytes, function)? != "require" {
+        return None;
+    }
+    let arguments = node.child_by_field_name("arguments")?;
+    let mut cursor = arguments.walk();
+    let mut args = arguments.named_children(&mut cursor);
+    let first = args.next()?;
+    if first.kind() != "string" || args.next().is_some() {
+        return None;
+    }
+    Some(strip_quotes(&text_of(bytes, first)?))
+}
+
+/// `compilerOptions.paths` aliases from the nearest `tsconfig.json`.
+///
+/// Aliased specifiers (`@app/models`) are normalized to paths relative to the
+/// tsconfig directory (`src/app/models`) so imports of the same module
+/// compare equal however they are written. `extends` is not followed.
+#[derive(Debug, Default)]
+struct PathAliases {
+    /// Directory containing the tsconfig
+    dir: PathBuf,
+    /// `baseUrl`, resolved against `dir`
+    base_url: PathBuf,
+    /// Pattern (at most one `*`) and its first substitution
+    patterns: Vec<(String, String)>,
+}
+
+type AliasCache = HashMap<PathBuf, (Option<SystemTime>, Option<Arc<PathAliases>>)>;
+
+static ALIAS_CACHE: OnceLock<Mutex<AliasCache>> = OnceLock::new();
+
+impl PathAliases {
+    /// Aliases for the tsconfig governing `file`, cached per tsconfig until
+    /// its mtime changes.
+    fn for_file(file: &Path) -> Option<Arc<Self>> {
+        let tsconfig = file
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("tsconfig.json"))
+            .find(|p| p.is_file())?;
+        let mtime = fs::metadata(&tsconfig).and_then(|m| m.modified()).ok();
+
+        let mut cache = ALIAS_CACHE
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_mtime, aliases)) = cache.get(&tsconfig) {
+            if *cached_mtime == mtime {
+                return aliases.clone();
+            }
+        }
+        let aliases = fs::read_to_string(&tsconfig)
+            .ok()
+            .and_then(|content| Self::parse(tsconfig.parent()?, &content))
+            .map(Arc::new);
+        cache.insert(tsconfig, (mtime, aliases.clone()));
+        aliases
+    }
+
+    fn parse(dir: &Path, content: &str) -> Option<Self> {
+        let config: serde_json::Value = serde_json::from_str(&strip_jsonc(content)).ok()?;
+        let options = config.get("compilerOptions")?;
+        let paths = options.get("paths")?.as_object()?;
+        let base_url = options
+            .get("baseUrl")
+            .and_then(|b| b.as_str())
+            .unwrap_or(".");
+
+        let patterns = paths
+            .iter()
+            .filter_map(|(pattern, targets)| {
+                let target = targets.as_array()?.first()?.as_str()?;
+                Some((pattern.clone(), target.to_string()))
+            })
+            .collect();
+
+        Some(Self {
+            dir: dir.to_path_buf(),
+            base_url: dir.join(base_url),
+            patterns,
+        })
+    }
+
+    /// Resolve an aliased specifier, or `None` if no pattern matches.
+    ///
+    /// Like TypeScript, the pattern with the longest prefix wins.
+    fn resolve(&self, specifier: &str) -> Option<String> {
+        let mut best: Option<(usize, String)> = None;
+        for (pattern, target) in &self.patterns {
+            let resolved = match pattern.split_once('*') {
+                Some((prefix, suffix)) => {
+                    if specifier.len() < prefix.len() + suffix.len()
+                        || !specifier.starts_with(prefix)
+                        || !specifier.ends_with(suffix)
+                    {
+                        continue;
+                    }
+                    let captured = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                    (prefix.len(), target.replacen('*', captured, 1))
+                }
+                None if pattern == specifier => (usize::MAX, target.clone()),
+                None => continue,
+            };
+            if best.as_ref().is_none_or(|(len, _)| resolved.0 > *len) {
+                best = Some(resolved);
+            }
+        }
+
+        let (_, target) = best?;
+        let mut normalized = PathBuf::new();
+        for component in self.base_url.join(target).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                other => normalized.push(other),
+            }
+        }
+        let relative = normalized.strip_prefix(&self.dir).unwrap_or(&normalized);
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// Strip comments and trailing commas so tsconfig parses as JSON.
+fn strip_jsonc(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (',', _) => {
+                // Drop the comma if only whitespace separates it from a closer
+                let rest: String = chars.clone().take_while(|c| c.is_whitespace()).collect();
+                let next = chars.clone().nth(rest.chars().count());
+                if !matches!(next, Some('}') | Some(']')) {
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
     }
+    out
 }
 
 fn walk_children(
@@ -0,0 +1,96 @@
+//! Per-symbol search popularity.
+//!
+//! Records which symbols agents actually pick out of search results (via the
+//! MCP `feedback` tool and scoped `find_symbol` lookups) and persists the
+//! counts in `.omni/popularity.json`. [`crate::query::execute_query`] blends
+//! them into ranking as a small multiplicative prior, so frequently used
+//! symbols win close calls without overriding text relevance.
+//!
+//! Like the rest of `.omni/`, the counts are reset by `omni index --force`.
+
+use crate::cache::{ensure_cache_dir, popularity_path};
+use crate::error::{OmniError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Boost per doubling of selections (roughly), before the cap.
+pub const POPULARITY_WEIGHT: f32 = 0.1;
+/// Largest score multiplier popularity can contribute.
+pub const MAX_POPULARITY_BOOST: f32 = 1.25;
+
+/// Selection counts by scoped symbol name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Popularity {
+    pub selections: HashMap<String, u32>,
+}
+
+impl Popularity {
+    /// Load the counts, or empty counts if none were recorded yet.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = popularity_path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+        serde_json::from_slice(&data).map_err(|e| OmniError::corrupt(&path, e))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        ensure_cache_dir(root)?;
+        let path = popularity_path(root);
+        let data = serde_json::to_vec_pretty(self).map_err(|e| OmniError::corrupt(&path, e))?;
+        fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+    }
+
+    /// Count one selection of `symbol`; returns its new total.
+    pub fn record(&mut self, symbol: &str) -> u32 {
+        let count = self.selections.entry(symbol.to_string()).or_default();
+        *count = count.saturating_add(1);
+        *count
+    }
+
+    /// Score multiplier for `symbol`: 1.0 if never selected, growing
+    /// logarithmically up to [`MAX_POPULARITY_BOOST`].
+    pub fn boost(&self, symbol: &str) -> f32 {
+        let count = self.selections.get(symbol).copied().unwrap_or(0);
+        (1.0 + POPULARITY_WEIGHT * (count as f32).ln_1p()).min(MAX_POPULARITY_BOOST)
+    }
+}
+
+/// Record a selection of `symbol` in the workspace's persisted counts.
+pub fn record_selection(root: &Path, symbol: &str) -> Result<u32> {
+    let mut popularity = Popularity::load(root)?;
+    let count = popularity.record(symbol);
+    popularity.save(root)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boost_grows_and_caps() {
+        let mut popularity = Popularity::default();
+        assert_eq!(popularity.boost("crate::a"), 1.0);
+        popularity.record("crate::a");
+        let once = popularity.boost("crate::a");
+        assert!(once > 1.0);
+        for _ in 0..1000 {
+            popularity.record("crate::a");
+        }
+        assert!(popularity.boost("crate::a") > once);
+        assert_eq!(popularity.boost("crate::a"), MAX_POPULARITY_BOOST);
+    }
+
+    #[test]
+    fn test_persists_selections() {
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(record_selection(temp.path(), "crate::a").unwrap(), 1);
+        assert_eq!(record_selection(temp.path(), "crate::a").unwrap(), 2);
+        let loaded = Popularity::load(temp.path()).unwrap();
+        assert_eq!(loaded.selections["crate::a"], 2);
+    }
+}
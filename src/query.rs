@@ -1,6 +1,10 @@
+use crate::budget::Budget;
 use crate::cache::{bm25_path, state_path};
+use crate::error::{OmniError, Result};
+use crate::focus::Focus;
+use crate::popularity::Popularity;
+use crate::renames::RenameLog;
 use crate::search::{Bm25Index, Bm25Params, FieldWeights};
-use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
@@ -18,6 +22,56 @@ pub struct SearchDoc {
     pub end_col: usize,
     pub preview: String,
     pub indexed_text: String,
+    /// Module role of the containing file (see [`crate::types::ModuleRole`])
+    pub role: String,
+    /// Deprecation note of the symbol, if deprecated (see
+    /// [`crate::types::SymbolDef::deprecation`])
+    pub deprecated: Option<String>,
+    /// What the doc describes; filter with `type:symbol` / `type:config` /
+    /// `type:schema`
+    pub kind: DocKind,
+    /// From test code (see [`crate::types::SymbolDef::is_test_context`])
+    pub is_test_context: bool,
+}
+
+/// What a [`SearchDoc`] describes.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DocKind {
+    /// A code symbol
+    #[default]
+    Symbol,
+    /// A key in a TOML/YAML/JSON config file (see [`crate::parsing::config`])
+    Config,
+    /// A table or column defined in a SQL file (see [`crate::parsing::sql`])
+    Schema,
+}
+
+impl DocKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Symbol => "symbol",
+            Self::Config => "config",
+            Self::Schema => "schema",
+        }
+    }
+}
+
+impl std::str::FromStr for DocKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "symbol" => Ok(Self::Symbol),
+            "config" => Ok(Self::Config),
+            "schema" => Ok(Self::Schema),
+            other => Err(format!(
+                "Unknown doc type: {other} (expected symbol, config or schema)"
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -30,6 +84,12 @@ pub struct SearchIndex {
     pub root: PathBuf,
     pub docs: Vec<SearchDoc>,
     pub bm25: Bm25Index,
+    /// Historical names, so queries for an old name find the renamed symbol
+    pub renames: RenameLog,
+    /// How often agents selected each symbol, used as a ranking prior
+    pub popularity: Popularity,
+    /// Files the current task is about, boosted while the hint lasts
+    pub focus: Focus,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -38,9 +98,16 @@ pub struct QueryFilters {
     pub exclude_paths: Vec<String>,
     pub include_exts: Vec<String>,
     pub exclude_exts: Vec<String>,
+    /// Restrict results to these root-relative files (e.g. a
+    /// [`crate::reachability`] slice)
+    pub files: Option<HashSet<String>>,
+    pub include_kinds: Vec<DocKind>,
+    pub exclude_kinds: Vec<DocKind>,
+    /// Leave out docs from test code
+    pub exclude_tests: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
 pub struct QueryResult {
     pub doc_id: u32,
     pub symbol: String,
@@ -53,33 +120,139 @@ pub struct QueryResult {
     pub end_col: usize,
     pub score: f32,
     pub preview: String,
+    pub role: String,
+    pub kind: DocKind,
+    /// From test code (see [`crate::types::SymbolDef::is_test_context`])
+    pub is_test_context: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Set when the query named this symbol's former name (see
+    /// [`crate::renames`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed: Option<String>,
+    /// Near-identical results collapsed into this one (hybrid search only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternates: Vec<QueryAlternate>,
+}
+
+/// A near-duplicate folded into a [`QueryResult`].
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct QueryAlternate {
+    pub symbol: String,
+    pub file: String,
+    pub start_line: usize,
+}
+
+/// Semantic candidates for [`search_with_fallback`].
+#[derive(Default)]
+pub struct SemanticHits<'a> {
+    /// `(doc_id, similarity)` pairs, best first
+    pub hits: Vec<(u32, f32)>,
+    /// Embedding of a doc, used to collapse near-duplicate results
+    embedding: Option<Box<EmbeddingLookup<'a>>>,
+}
+
+type EmbeddingLookup<'a> = dyn Fn(u32) -> Option<Vec<f32>> + 'a;
+
+impl<'a> SemanticHits<'a> {
+    pub fn new(hits: Vec<(u32, f32)>) -> Self {
+        Self {
+            hits,
+            embedding: None,
+        }
+    }
+
+    /// Collapse fused results whose embeddings are near-identical (see
+    /// [`crate::search::HybridSearch::search_deduplicated`]).
+    pub fn with_embeddings(mut self, embedding: impl Fn(u32) -> Option<Vec<f32>> + 'a) -> Self {
+        self.embedding = Some(Box::new(embedding));
+        self
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl QueryResult {
+    fn from_doc(doc_id: u32, doc: &SearchDoc, score: f32) -> Self {
+        Self {
+            doc_id,
+            symbol: doc.symbol.clone(),
+            file: doc.file.clone(),
+            start_byte: doc.start_byte,
+            end_byte: doc.end_byte,
+            start_line: doc.start_line + 1,
+            end_line: doc.end_line + 1,
+            start_col: doc.start_col + 1,
+            end_col: doc.end_col + 1,
+            score,
+            preview: doc.preview.clone(),
+            role: doc.role.clone(),
+            kind: doc.kind,
+            is_test_context: doc.is_test_context,
+            deprecated: doc.deprecated.clone(),
+            renamed: None,
+            alternates: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
 pub struct QueryResponse {
     pub root: String,
     pub query: String,
     pub top_k: usize,
     pub results: Vec<QueryResult>,
+    /// Retrieval strategy that produced the results
+    pub strategy: SearchStrategy,
+    /// Whether the [`Budget`] ran out before every candidate was scanned
+    pub truncated: bool,
+}
+
+/// Stage of the search fallback chain (see [`search_with_fallback`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStrategy {
+    /// Semantic candidates fused with BM25
+    Hybrid,
+    /// Semantic candidates alone (see [`semantic_query`])
+    Semantic,
+    /// BM25 over symbol spans
+    Bm25,
+    /// Edit-distance match against symbol names
+    FuzzySymbol,
+    /// Case-insensitive substring match over indexed text
+    Substring,
+}
+
+impl SearchStrategy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hybrid => "hybrid",
+            Self::Semantic => "semantic",
+            Self::Bm25 => "bm25",
+            Self::FuzzySymbol => "fuzzy_symbol",
+            Self::Substring => "substring",
+        }
+    }
 }
 
 pub fn load_search_state(root: &Path) -> Result<Option<SearchState>> {
-    let path = state_path(root);
+    load_search_state_from(&state_path(root))
+}
+
+pub(crate) fn load_search_state_from(path: &Path) -> Result<Option<SearchState>> {
     if !path.exists() {
         return Ok(None);
     }
-    let data =
-        fs::read(&path).with_context(|| format!("Failed to read state: {}", path.display()))?;
-    let state: SearchState = bincode::deserialize(&data)
-        .with_context(|| format!("Failed to decode state: {}", path.display()))?;
+    let data = fs::read(path).map_err(|e| OmniError::io(path, e))?;
+    let state: SearchState =
+        bincode::deserialize(&data).map_err(|e| OmniError::corrupt(path, e))?;
     Ok(Some(state))
 }
 
 pub fn save_search_state(root: &Path, state: &SearchState) -> Result<()> {
     crate::cache::ensure_cache_dir(root)?;
     let path = state_path(root);
-    let data = bincode::serialize(state)?;
-    fs::write(&path, data).with_context(|| format!("Failed to write state: {}", path.display()))?;
+    let data = bincode::serialize(state).map_err(|e| OmniError::corrupt(&path, e))?;
+    fs::write(&path, data).map_err(|e| OmniError::io(&path, e))?;
     Ok(())
 }
 
@@ -88,35 +261,58 @@ pub fn load_bm25(root: &Path) -> Result<Option<Bm25Index>> {
     if !path.exists() {
         return Ok(None);
     }
-    let data =
-        fs::read(&path).with_context(|| format!("Failed to read BM25: {}", path.display()))?;
-    let index: Bm25Index = bincode::deserialize(&data)
-        .with_context(|| format!("Failed to decode BM25: {}", path.display()))?;
+    let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+    let index: Bm25Index = bincode::deserialize(&data).map_err(|e| OmniError::corrupt(&path, e))?;
     Ok(Some(index))
 }
 
 pub fn save_bm25(root: &Path, index: &Bm25Index) -> Result<()> {
     crate::cache::ensure_cache_dir(root)?;
     let path = bm25_path(root);
-    let data = bincode::serialize(index)?;
-    fs::write(&path, data).with_context(|| format!("Failed to write BM25: {}", path.display()))?;
+    let data = bincode::serialize(index).map_err(|e| OmniError::corrupt(&path, e))?;
+    fs::write(&path, data).map_err(|e| OmniError::io(&path, e))?;
     Ok(())
 }
 
 pub fn load_search_index(root: &Path) -> Result<Option<SearchIndex>> {
+    if let Err(e) = crate::migrations::migrate(root) {
+        tracing::warn!("Index cache unusable, needs rebuild: {:#}", e);
+        return Ok(None);
+    }
     let Some(state) = load_search_state(root)? else {
         return Ok(None);
     };
     let Some(bm25) = load_bm25(root)? else {
         return Ok(None);
     };
+    let renames = RenameLog::load(root).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable rename log: {}", e);
+        RenameLog::default()
+    });
+    let popularity = Popularity::load(root).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable popularity counts: {}", e);
+        Popularity::default()
+    });
+    let focus = Focus::load(root).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable focus paths: {}", e);
+        Focus::default()
+    });
     Ok(Some(SearchIndex {
         root: root.to_path_buf(),
         docs: state.docs,
         bm25,
+        renames,
+        popularity,
+        focus,
     }))
 }
 
+/// Load the persisted search index, failing with
+/// [`OmniError::IndexMissing`] if `root` has not been indexed.
+pub fn require_search_index(root: &Path) -> Result<SearchIndex> {
+    load_search_index(root)?.ok_or_else(|| OmniError::IndexMissing(root.to_path_buf()))
+}
+
 pub fn parse_query_filters(query: &str, extra_filters: &[String]) -> (String, QueryFilters) {
     let mut filters = QueryFilters::default();
     let mut terms = Vec::new();
@@ -138,6 +334,16 @@ pub fn parse_query_filters(query: &str, extra_filters: &[String]) -> (String, Qu
             }
             return;
         }
+        if let Some(kind) = rest.strip_prefix("type:") {
+            if let Ok(kind) = kind.parse::<DocKind>() {
+                if negated {
+                    filters.exclude_kinds.push(kind);
+                } else {
+                    filters.include_kinds.push(kind);
+                }
+                return;
+            }
+        }
         if let Some(ext) = rest.strip_prefix("ext:") {
             let ext = ext.trim_start_matches('.');
             if negated {
@@ -161,13 +367,33 @@ pub fn parse_query_filters(query: &str, extra_filters: &[String]) -> (String, Qu
     (terms.join(" "), filters)
 }
 
+/// Score multiplier for deprecated symbols, so live APIs rank first.
+pub const DEPRECATED_SCORE_FACTOR: f32 = 0.5;
+
 pub fn execute_query(
     index: &SearchIndex,
     query: &str,
     top_k: usize,
     filters: &QueryFilters,
 ) -> QueryResponse {
-    let search_k = top_k.saturating_mul(5).max(top_k).min(1000);
+    execute_query_within(index, query, top_k, filters, &Budget::unlimited())
+}
+
+/// [`execute_query`], charging `budget` one unit per candidate scanned and
+/// returning the results so far (`truncated`) once it runs out.
+pub fn execute_query_within(
+    index: &SearchIndex,
+    query: &str,
+    top_k: usize,
+    filters: &QueryFilters,
+    budget: &Budget,
+) -> QueryResponse {
+    // A file allowlist can reject most hits, so rank every match
+    let search_k = if filters.files.is_some() {
+        index.docs.len()
+    } else {
+        top_k.saturating_mul(5).max(top_k).min(1000)
+    };
     let results = index.bm25.search(
         query,
         &FieldWeights::default(),
@@ -176,8 +402,13 @@ pub fn execute_query(
     );
 
     let mut filtered = Vec::new();
+    let mut truncated = false;
 
     for result in results {
+        if !budget.charge(1) {
+            truncated = true;
+            break;
+        }
         let doc_id = result.doc_id as usize;
         if doc_id >= index.docs.len() {
             continue;
@@ -186,28 +417,18 @@ pub fn execute_query(
         if !matches_filters(doc, filters) {
             continue;
         }
-        filtered.push(QueryResult {
-            doc_id: result.doc_id,
-            symbol: doc.symbol.clone(),
-            file: doc.file.clone(),
-            start_byte: doc.start_byte,
-            end_byte: doc.end_byte,
-            start_line: doc.start_line + 1,
-            end_line: doc.end_line + 1,
-            start_col: doc.start_col + 1,
-            end_col: doc.end_col + 1,
-            score: result.score,
-            preview: doc.preview.clone(),
-        });
+        let score = if doc.deprecated.is_some() {
+            result.score * DEPRECATED_SCORE_FACTOR
+        } else {
+            result.score
+        } * index.popularity.boost(&doc.symbol)
+            * index.focus.boost(&doc.file);
+        filtered.push(QueryResult::from_doc(result.doc_id, doc, score));
     }
 
-    filtered.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then_with(|| a.file.cmp(&b.file))
-            .then_with(|| a.start_byte.cmp(&b.start_byte))
-    });
+    sort_results(&mut filtered);
+
+    apply_renames(index, query, filters, &mut filtered);
 
     if filtered.len() > top_k {
         filtered.truncate(top_k);
@@ -218,10 +439,326 @@ pub fn execute_query(
         query: query.to_string(),
         top_k,
         results: filtered,
+        strategy: SearchStrategy::Bm25,
+        truncated,
+    }
+}
+
+/// Search type requested by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchType {
+    /// Semantic hits fused with BM25 when a semantic index is available,
+    /// otherwise the BM25 fallback chain ([`search_with_fallback`])
+    #[default]
+    Hybrid,
+    /// The fallback chain without semantic hits
+    Bm25,
+    /// Semantic hits alone ([`semantic_query`])
+    Semantic,
+}
+
+impl SearchType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hybrid" => Some(Self::Hybrid),
+            "bm25" => Some(Self::Bm25),
+            "semantic" => Some(Self::Semantic),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hybrid => "hybrid",
+            Self::Bm25 => "bm25",
+            Self::Semantic => "semantic",
+        }
+    }
+
+    /// Whether this search type uses a semantic index.
+    pub fn uses_semantic(self) -> bool {
+        self != Self::Bm25
+    }
+}
+
+/// Rank docs by semantic similarity alone, keeping those that pass
+/// `filters`. Charges `budget` one unit per hit.
+pub fn semantic_query(
+    index: &SearchIndex,
+    query: &str,
+    top_k: usize,
+    filters: &QueryFilters,
+    semantic: SemanticHits,
+    budget: &Budget,
+) -> QueryResponse {
+    let mut results = Vec::new();
+    let mut truncated = false;
+    for (doc_id, score) in semantic.hits {
+        if !budget.charge(1) {
+            truncated = true;
+            break;
+        }
+        let Some(doc) = index.docs.get(doc_id as usize) else {
+            continue;
+        };
+        if matches_filters(doc, filters) {
+            results.push(QueryResult::from_doc(doc_id, doc, score));
+        }
+    }
+    sort_results(&mut results);
+    results.truncate(top_k);
+
+    QueryResponse {
+        root: index.root.display().to_string(),
+        query: query.to_string(),
+        top_k,
+        results,
+        strategy: SearchStrategy::Semantic,
+        truncated,
+    }
+}
+
+/// Minimum name similarity (1 - normalized edit distance) for a fuzzy
+/// symbol match.
+pub const FUZZY_SYMBOL_THRESHOLD: f32 = 0.7;
+
+/// Search with a fallback chain, so a query only comes back empty if no
+/// strategy finds anything:
+///
+/// 1. hybrid: `semantic` hits (doc id, similarity) fused with BM25, if any
+/// 2. BM25 ([`execute_query`])
+/// 3. fuzzy symbol: names within [`FUZZY_SYMBOL_THRESHOLD`] of a query term
+/// 4. substring: docs whose text contains every query term
+///
+/// The stage that produced the results is reported in
+/// [`QueryResponse::strategy`]. Every stage charges `budget` per candidate
+/// scanned; once it runs out, later stages are skipped and the response is
+/// marked `truncated`.
+pub fn search_with_fallback(
+    index: &SearchIndex,
+    query: &str,
+    top_k: usize,
+    filters: &QueryFilters,
+    semantic: Option<SemanticHits>,
+    budget: &Budget,
+) -> QueryResponse {
+    let mut response = execute_query_within(index, query, top_k, filters, budget);
+    if let Some(semantic) = semantic.filter(|semantic| !semantic.hits.is_empty()) {
+        response.results = fuse_semantic(index, query, top_k, filters, semantic, response.results);
+        response.strategy = SearchStrategy::Hybrid;
+    }
+    if !response.results.is_empty() {
+        return response;
+    }
+
+    for strategy in [SearchStrategy::FuzzySymbol, SearchStrategy::Substring] {
+        if budget.is_exhausted() {
+            response.truncated = true;
+            break;
+        }
+        let hits = match strategy {
+            SearchStrategy::FuzzySymbol => fuzzy_symbol_hits(index, query, budget),
+            _ => substring_hits(index, query, budget),
+        };
+        response.truncated |= budget.is_exhausted();
+        let mut results: Vec<QueryResult> = hits
+            .into_iter()
+            .filter(|(doc_id, _)| matches_filters(&index.docs[*doc_id as usize], filters))
+            .map(|(doc_id, score)| {
+                QueryResult::from_doc(doc_id, &index.docs[doc_id as usize], score)
+            })
+            .collect();
+        if results.is_empty() {
+            continue;
+        }
+        sort_results(&mut results);
+        results.truncate(top_k);
+        response.results = results;
+        response.strategy = strategy;
+        break;
+    }
+    response
+}
+
+/// Fuse semantic hits with BM25 results using [`crate::search::HybridSearch`],
+/// collapsing near-duplicates when embeddings are available.
+fn fuse_semantic(
+    index: &SearchIndex,
+    query: &str,
+    top_k: usize,
+    filters: &QueryFilters,
+    semantic: SemanticHits,
+    bm25: Vec<QueryResult>,
+) -> Vec<QueryResult> {
+    let hits: Vec<(u32, f32)> = semantic
+        .hits
+        .into_iter()
+        .filter(|(doc_id, _)| {
+            index
+                .docs
+                .get(*doc_id as usize)
+                .is_some_and(|doc| matches_filters(doc, filters))
+        })
+        .collect();
+    let bm25_scores: Vec<(u32, f32)> = bm25.iter().map(|r| (r.doc_id, r.score)).collect();
+    let mut by_doc: std::collections::HashMap<u32, QueryResult> =
+        bm25.into_iter().map(|r| (r.doc_id, r)).collect();
+
+    let hybrid = crate::search::HybridSearch::new(crate::search::HybridSearchConfig {
+        final_top_k: top_k,
+        ..Default::default()
+    });
+    let fused = match &semantic.embedding {
+        Some(embedding) => hybrid.search_deduplicated(query, hits, bm25_scores, embedding),
+        None => hybrid.search(query, hits, bm25_scores),
+    };
+    fused
+        .into_iter()
+        .map(|hit| {
+            let mut result = by_doc.remove(&hit.doc_id).unwrap_or_else(|| {
+                QueryResult::from_doc(hit.doc_id, &index.docs[hit.doc_id as usize], 0.0)
+            });
+            result.score = hit.score;
+            result.alternates = hit
+                .alternates
+                .iter()
+                .map(|&doc_id| {
+                    let doc = &index.docs[doc_id as usize];
+                    QueryAlternate {
+                        symbol: doc.symbol.clone(),
+                        file: doc.file.clone(),
+                        start_line: doc.start_line + 1,
+                    }
+                })
+                .collect();
+            result
+        })
+        .collect()
+}
+
+/// Docs whose simple symbol name is close to a query term (or the whole query).
+fn fuzzy_symbol_hits(index: &SearchIndex, query: &str, budget: &Budget) -> Vec<(u32, f32)> {
+    let query = query.to_lowercase();
+    let mut terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.len() > 1 {
+        terms.push(query.as_str());
+    }
+    index
+        .docs
+        .iter()
+        .enumerate()
+        .take_while(|_| budget.charge(1))
+        .filter_map(|(doc_id, doc)| {
+            let name = doc
+                .symbol
+                .rsplit([':', '.'])
+                .next()
+                .unwrap_or(&doc.symbol)
+                .to_lowercase();
+            let best = terms
+                .iter()
+                .map(|term| name_similarity(term, &name))
+                .fold(0.0f32, f32::max);
+            (best >= FUZZY_SYMBOL_THRESHOLD).then_some((doc_id as u32, best))
+        })
+        .collect()
+}
+
+/// Docs whose indexed text contains every query term, scored by occurrences.
+fn substring_hits(index: &SearchIndex, query: &str, budget: &Budget) -> Vec<(u32, f32)> {
+    let query = query.to_lowercase();
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    index
+        .docs
+        .iter()
+        .enumerate()
+        .take_while(|_| budget.charge(1))
+        .filter_map(|(doc_id, doc)| {
+            let text = doc.indexed_text.to_lowercase();
+            let counts: Vec<usize> = terms.iter().map(|t| text.matches(t).count()).collect();
+            (!counts.contains(&0)).then(|| (doc_id as u32, counts.iter().sum::<usize>() as f32))
+        })
+        .collect()
+}
+
+/// 1 - Levenshtein distance / longer length, over chars.
+fn name_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    1.0 - prev[b.len()] as f32 / longest as f32
+}
+
+/// Best score first; ties by file and position for stable output.
+fn sort_results(results: &mut [QueryResult]) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.start_byte.cmp(&b.start_byte))
+    });
+}
+
+/// Put symbols renamed away from a query term first, with a note saying so.
+fn apply_renames(
+    index: &SearchIndex,
+    query: &str,
+    filters: &QueryFilters,
+    results: &mut Vec<QueryResult>,
+) {
+    let top_score = results.first().map(|r| r.score).unwrap_or(1.0);
+    let aliases = query
+        .split_whitespace()
+        .flat_map(|term| index.renames.lookup(term))
+        .collect::<Vec<_>>();
+    for alias in aliases.into_iter().rev() {
+        let Some((doc_id, doc)) = index
+            .docs
+            .iter()
+            .enumerate()
+            .filter(|(_, doc)| doc.symbol == alias.new_name)
+            .min_by_key(|(_, doc)| doc.file != alias.file)
+        else {
+            continue;
+        };
+        if !matches_filters(doc, filters) {
+            continue;
+        }
+        let mut result = match results.iter().position(|r| r.doc_id == doc_id as u32) {
+            Some(pos) => results.remove(pos),
+            None => QueryResult::from_doc(doc_id as u32, doc, top_score),
+        };
+        result.score = result.score.max(top_score);
+        result.renamed = Some(alias.note());
+        results.insert(0, result);
     }
 }
 
 fn matches_filters(doc: &SearchDoc, filters: &QueryFilters) -> bool {
+    if filters
+        .files
+        .as_ref()
+        .is_some_and(|files| !files.contains(&doc.file))
+    {
+        return false;
+    }
+
     if !filters.include_paths.is_empty() {
         let mut matched = false;
         for pat in &filters.include_paths {
@@ -241,6 +778,16 @@ fn matches_filters(doc: &SearchDoc, filters: &QueryFilters) -> bool {
         }
     }
 
+    if !filters.include_kinds.is_empty() && !filters.include_kinds.contains(&doc.kind) {
+        return false;
+    }
+    if filters.exclude_kinds.contains(&doc.kind) {
+        return false;
+    }
+    if filters.exclude_tests && doc.is_test_context {
+        return false;
+    }
+
     let ext = Path::new(&doc.file)
         .extension()
         .and_then(|e| e.to_str())
@@ -259,7 +806,7 @@ fn matches_filters(doc: &SearchDoc, filters: &QueryFilters) -> bool {
 }
 
 pub fn rebuild_bm25(docs: &[SearchDoc]) -> Bm25Index {
-    let mut index = Bm25Index::new();
+    let mut index = Bm25Index::new().with_positions(true);
 
     for (doc_id, doc) in docs.iter().enumerate() {
         let doc_id = doc_id as u32;
@@ -287,3 +834,25 @@ pub fn prune_docs_for_files(docs: &[SearchDoc], files: &HashSet<String>) -> Vec<
         .cloned()
         .collect()
 }
+
+/// Swap the docs of files with in-memory overlays (see
+/// [`crate::state::OciState::overlays`]) into `index` and rebuild its BM25
+/// index, so searches see unsaved edits. Only the in-memory index changes.
+pub fn apply_overlays(index: &mut SearchIndex, state: &crate::state::OciState) {
+    if state.overlays.is_empty() {
+        return;
+    }
+    let mut files = HashSet::new();
+    let mut overlay_docs = Vec::new();
+    for overlay in state.overlays.iter() {
+        let Ok(rel) = overlay.key().strip_prefix(&index.root) else {
+            continue;
+        };
+        files.insert(rel.to_string_lossy().to_string());
+        overlay_docs.extend(overlay.docs.iter().cloned());
+    }
+    let mut docs = prune_docs_for_files(&index.docs, &files);
+    docs.extend(overlay_docs);
+    index.bm25 = rebuild_bm25(&docs);
+    index.docs = docs;
+}
@@ -0,0 +1,196 @@
+//! Reachability slices: "only code reachable from X".
+//!
+//! Starting from a root (a file, or a symbol such as a binary's `main` or an
+//! HTTP handler), walks call edges and imports to collect the files and
+//! symbols the root can reach. Search, dead code, and symbol lookups use the
+//! slice as a filter so agents can focus on the part of a monorepo that is
+//! relevant to their task.
+//!
+//! Reaching a file (as the root or through an import of its items) reaches
+//! every symbol defined in it; reaching a symbol reaches its callees and
+//! records its file without pulling in the file's other symbols.
+
+use crate::state::OciState;
+use crate::types::{InternedString, SymbolDef};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Files and symbols reachable from a root.
+#[derive(Debug, Clone, Default)]
+pub struct ReachableSet {
+    /// Absolute paths of files containing reachable code
+    pub files: HashSet<PathBuf>,
+    /// Scoped names of reachable symbols
+    pub symbols: HashSet<InternedString>,
+}
+
+impl ReachableSet {
+    pub fn contains_file(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    pub fn contains_symbol(&self, scoped_name: InternedString) -> bool {
+        self.symbols.contains(&scoped_name)
+    }
+
+    /// Reachable files relative to `root`, as used by search docs.
+    pub fn relative_files(&self, root: &Path) -> HashSet<String> {
+        self.files
+            .iter()
+            .filter_map(|f| f.strip_prefix(root).ok())
+            .map(|f| f.to_string_lossy().to_string())
+            .collect()
+    }
+}
+
+enum Node {
+    File(PathBuf),
+    Symbol(Box<SymbolDef>),
+}
+
+/// Compute the slice reachable from `root_spec`.
+///
+/// The spec is resolved, in order, as an indexed file path (absolute or
+/// relative to the workspace root), a scoped symbol name, or a simple symbol
+/// name (every definition is a root). Returns `None` if nothing matches.
+pub fn reachable_from(state: &OciState, root_spec: &str) -> Option<ReachableSet> {
+    let roots = resolve_roots(state, root_spec);
+    if roots.is_empty() {
+        return None;
+    }
+
+    let mut set = ReachableSet::default();
+    let mut queue: VecDeque<Node> = roots.into();
+    while let Some(node) = queue.pop_front() {
+        match node {
+            Node::File(path) => {
+                if !set.files.insert(path.clone()) {
+                    continue;
+                }
+                for symbol in file_symbols(state, &path) {
+                    queue.push_back(Node::Symbol(Box::new(symbol)));
+                }
+                for target in imported_symbols(state, &path) {
+                    queue.push_back(Node::File(target.location.file.clone()));
+                }
+            }
+            Node::Symbol(symbol) => {
+                if !set.symbols.insert(symbol.scoped_name) {
+                    continue;
+                }
+                set.files.insert(symbol.location.file.clone());
+                for edge in state.find_callees(symbol.scoped_name) {
+                    for target in state.call_targets(&edge) {
+                        if !set.symbols.contains(&target.scoped_name) {
+                            queue.push_back(Node::Symbol(Box::new(target)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Some(set)
+}
+
+fn resolve_roots(state: &OciState, spec: &str) -> Vec<Node> {
+    let path = Path::new(spec);
+    for candidate in [path.to_path_buf(), state.root_path.join(path)] {
+        let candidate = candidate.canonicalize().unwrap_or(candidate);
+        if state.file_ids.contains_key(&candidate) {
+            return vec![Node::File(candidate)];
+        }
+    }
+
+    if let Some(symbol) = state.lookup(spec).and_then(|key| state.get_symbol(key)) {
+        return vec![Node::Symbol(Box::new(symbol))];
+    }
+    state
+        .find_by_name(spec)
+        .into_iter()
+        .map(|symbol| Node::Symbol(Box::new(symbol)))
+        .collect()
+}
+
+/// Symbols defined in `path`. Rust scoped names are not file-qualified, so
+/// entries that were overwritten by another file's definition are skipped.
+fn file_symbols(state: &OciState, path: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(path).map(|id| *id) else {
+        return Vec::new();
+    };
+    state
+        .file_symbols
+        .get(&file_id)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| state.get_symbol(*name))
+                .filter(|symbol| symbol.location.file == path)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Nearest definition of each item imported by `path`.
+fn imported_symbols(state: &OciState, path: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(path).map(|id| *id) else {
+        return Vec::new();
+    };
+    let Some(imports) = state.imports.get(&file_id).map(|i| i.clone()) else {
+        return Vec::new();
+    };
+    imports
+        .iter()
+        .filter(|import| !import.is_glob)
+        .filter_map(|import| {
+            let name = import.original_name.as_deref().unwrap_or(&import.name);
+            state
+                .find_by_name_near(name, path)
+                .into_iter()
+                .find(|symbol| symbol.location.file != path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_slice_follows_calls_and_imports() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "use crate::config::Settings;\nfn main() { serve(); }\nfn serve() {}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/config.rs"), "pub struct Settings;\n").unwrap();
+        std::fs::write(
+            root.join("src/admin.rs"),
+            "pub fn purge() { audit(); }\nfn audit() {}\n",
+        )
+        .unwrap();
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let from_main = reachable_from(&state, "src/main.rs").unwrap();
+        let mut files: Vec<String> = from_main.relative_files(&root).into_iter().collect();
+        files.sort();
+        assert_eq!(files, vec!["src/config.rs", "src/main.rs"]);
+        assert!(from_main.contains_symbol(state.intern("crate::serve")));
+        assert!(!from_main.contains_symbol(state.intern("crate::purge")));
+
+        let from_purge = reachable_from(&state, "purge").unwrap();
+        assert!(from_purge.contains_symbol(state.intern("crate::audit")));
+        assert!(from_purge.contains_file(&root.join("src/admin.rs")));
+        assert!(!from_purge.contains_file(&root.join("src/main.rs")));
+
+        assert!(reachable_from(&state, "no_such_thing").is_none());
+    }
+}
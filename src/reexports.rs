@@ -0,0 +1,276 @@
+//! Public re-export paths of Rust items.
+//!
+//! An item's defining path follows its file (`omni_index::intervention::
+//! InterventionEngine` for `src/intervention/mod.rs`), but crates re-export
+//! items from facades closer to the root (`pub use
+//! intervention::InterventionEngine` in `lib.rs`), and that shorter path is
+//! the one to import. [`ReexportGraph`] collects the `pub use` declarations
+//! of the indexed Rust files, and [`ReexportGraph::canonical_path`] follows
+//! them from a symbol's defining path to the shortest path it is reachable
+//! under. The graph is built on first use and dropped whenever imports or
+//! the topology change (see [`OciState::canonical_path`]).
+
+use crate::state::OciState;
+use crate::types::{SymbolDef, TopologyNode};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Upper bound on the paths explored per symbol, against re-export cycles.
+const MAX_PATHS: usize = 256;
+
+/// `pub use` declarations of a workspace, as edges between paths.
+#[derive(Debug, Default)]
+pub struct ReexportGraph {
+    /// Package directories and their crate names, deepest first
+    crates: Vec<(PathBuf, String)>,
+    /// Re-exported path -> paths re-exporting it
+    items: HashMap<String, Vec<String>>,
+    /// Glob re-exported module -> modules re-exporting all of its items
+    globs: HashMap<String, Vec<String>>,
+}
+
+impl ReexportGraph {
+    /// Snapshot the re-exports currently in `state`.
+    pub fn build(state: &OciState) -> Self {
+        let mut crates: Vec<(PathBuf, String)> = state
+            .topology
+            .read()
+            .node_weights()
+            .filter_map(|node| match node {
+                TopologyNode::Crate { name, path, .. } => {
+                    Some((path.clone(), name.replace('-', "_")))
+                }
+                _ => None,
+            })
+            .collect();
+        crates.sort_by_key(|(path, _)| std::cmp::Reverse(path.components().count()));
+        let mut graph = Self {
+            crates,
+            ..Self::default()
+        };
+        let crate_names: HashSet<String> =
+            graph.crates.iter().map(|(_, name)| name.clone()).collect();
+
+        for entry in state.file_ids.iter() {
+            let file = entry.key();
+            if file.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            let Some(imports) = state.imports.get(entry.value()) else {
+                continue;
+            };
+            let module = graph.module_of(file);
+            for import in imports.iter().filter(|i| i.is_reexport) {
+                let Some(target) = resolve_use_path(&import.path, &module, &crate_names) else {
+                    continue;
+                };
+                if import.is_glob {
+                    graph.globs.entry(target).or_default().push(module.clone());
+                } else {
+                    graph
+                        .items
+                        .entry(target)
+                        .or_default()
+                        .push(format!("{}::{}", module, import.name));
+                }
+            }
+        }
+        graph
+    }
+
+    /// Path of a symbol's definition, from its crate name and file
+    /// (`omni_index::intervention::InterventionEngine`). `None` outside Rust.
+    pub fn defining_path(&self, state: &OciState, symbol: &SymbolDef) -> Option<String> {
+        let file = &symbol.location.file;
+        if file.extension().is_none_or(|ext| ext != "rs") {
+            return None;
+        }
+        let tail = state.resolve(symbol.scoped_name).strip_prefix("crate::")?;
+        Some(format!("{}::{}", self.module_of(file), tail))
+    }
+
+    /// Shortest path a symbol is reachable under through `pub use`
+    /// re-exports, or its defining path when nothing shorter re-exports it.
+    /// Ties go to the shorter, then alphabetically first, spelling.
+    pub fn canonical_path(&self, state: &OciState, symbol: &SymbolDef) -> Option<String> {
+        let start = self.defining_path(state, symbol)?;
+        let mut best = start.clone();
+        let mut seen = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(path) = queue.pop_front() {
+            for alias in self.aliases(&path) {
+                if seen.len() >= MAX_PATHS {
+                    return Some(best);
+                }
+                if seen.insert(alias.clone()) {
+                    if path_key(&alias) < path_key(&best) {
+                        best = alias.clone();
+                    }
+                    queue.push_back(alias);
+                }
+            }
+        }
+        Some(best)
+    }
+
+    /// Paths `path` is also reachable under through one re-export of it or
+    /// of a module or type containing it.
+    fn aliases(&self, path: &str) -> Vec<String> {
+        let segments: Vec<&str> = path.split("::").collect();
+        let mut aliases = Vec::new();
+        for split in 1..=segments.len() {
+            let prefix = segments[..split].join("::");
+            let rest = &segments[split..];
+            for alias in self.items.get(&prefix).into_iter().flatten() {
+                aliases.push(join(alias, rest));
+            }
+            if split < segments.len() {
+                for module in self.globs.get(&prefix).into_iter().flatten() {
+                    aliases.push(join(module, rest));
+                }
+            }
+        }
+        aliases
+    }
+
+    /// Module path of a Rust file with its crate's name in place of `crate`.
+    fn module_of(&self, file: &Path) -> String {
+        let Some((root, name)) = self.crates.iter().find(|(root, _)| file.starts_with(root)) else {
+            return module_path(file);
+        };
+        let module = module_path(file.strip_prefix(root).unwrap_or(file));
+        match module.strip_prefix("crate") {
+            Some(rest) => format!("{}{}", name, rest),
+            None => module,
+        }
+    }
+}
+
+/// Module path of a root-relative Rust file: the path below its `src`
+/// directory, with `mod.rs`, `lib.rs`, and `main.rs` naming their directory.
+pub fn module_path(rel: &Path) -> String {
+    let parts: Vec<String> = rel
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let start = parts.iter().rposition(|p| p == "src").map_or(0, |i| i + 1);
+    let mut segments = parts[start..].to_vec();
+    if segments
+        .last()
+        .is_some_and(|last| matches!(last.as_str(), "mod" | "lib" | "main"))
+    {
+        segments.pop();
+    }
+    if segments.is_empty() {
+        "crate".to_string()
+    } else {
+        format!("crate::{}", segments.join("::"))
+    }
+}
+
+/// Absolute path of a `use` path written in `module`. Paths not starting
+/// with `crate`, `self`, `super`, or a workspace crate are taken as relative
+/// to `module`, which is right for child modules and harmless for external
+/// crates, under which no indexed item lives.
+fn resolve_use_path(path: &str, module: &str, crate_names: &HashSet<String>) -> Option<String> {
+    let mut segments: Vec<&str> = path.trim_start_matches("::").split("::").collect();
+    let mut base: Vec<&str> = module.split("::").collect();
+    match segments.first().copied()? {
+        "crate" => {
+            base.truncate(1);
+            segments.remove(0);
+        }
+        "self" => {
+            segments.remove(0);
+        }
+        "super" => {
+            while segments.first() == Some(&"super") {
+                if base.len() <= 1 {
+                    return None;
+                }
+                base.pop();
+                segments.remove(0);
+            }
+        }
+        first if crate_names.contains(first) => base.clear(),
+        _ => {}
+    }
+    base.extend(segments);
+    Some(base.join("::"))
+}
+
+fn join(base: &str, rest: &[&str]) -> String {
+    if rest.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}::{}", base, rest.join("::"))
+    }
+}
+
+/// Ordering of candidate paths: fewest segments, then shortest, then
+/// alphabetical.
+fn path_key(path: &str) -> (usize, usize, &str) {
+    (path.split("::").count(), path.len(), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_canonical_path_follows_pub_use_facades() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src/engine/parts")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo-app\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub mod engine;\npub mod util;\npub use engine::Engine;\npub use crate::engine::prelude::*;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/engine/mod.rs"),
+            "pub mod parts;\npub mod prelude;\npub use self::parts::gear::Engine;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/engine/prelude.rs"),
+            "pub use super::parts::gear::Gear as Cog;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/engine/parts/mod.rs"), "pub mod gear;\n").unwrap();
+        std::fs::write(
+            root.join("src/engine/parts/gear.rs"),
+            "pub struct Engine;\n\nimpl Engine {\n    pub fn start(&self) {}\n}\n\npub struct Gear;\n\npub(crate) use std::fmt::Debug;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/util.rs"), "pub fn helper() {}\n").unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let canonical = |name: &str| {
+            let symbol = state.get_symbol(state.lookup(name).unwrap()).unwrap();
+            state.canonical_path(&symbol)
+        };
+        assert_eq!(
+            canonical("crate::Engine::start").as_deref(),
+            Some("demo_app::Engine::start")
+        );
+        // Renamed in a prelude that the root glob re-exports
+        assert_eq!(canonical("crate::Gear").as_deref(), Some("demo_app::Cog"));
+        assert_eq!(
+            canonical("crate::helper").as_deref(),
+            Some("demo_app::util::helper")
+        );
+    }
+}
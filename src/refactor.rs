@@ -0,0 +1,250 @@
+//! Rename and signature-change impact for one symbol.
+//!
+//! `omni impact <symbol>` lists every place a rename or signature change
+//! has to touch: the definitions, the call sites attributed to them, and
+//! the imports and `pub use` re-exports that name the symbol, grouped by
+//! file so an agent can plan the edit one file at a time.
+//!
+//! A scoped name (`crate::Json::decode`) narrows the change to that
+//! definition, and calls are only counted against it when
+//! [`resolve_callers`] attributes them to it; a simple name covers every
+//! definition sharing it. Imports and re-exports are matched by the name
+//! they import, so an unrelated item with the same name in another module
+//! is listed too.
+
+use crate::call_resolution::resolve_callers;
+use crate::state::OciState;
+use crate::types::{SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// What a site does with the symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteKind {
+    Definition,
+    Call,
+    Import,
+    /// `pub use`, re-exporting the symbol under the importing module
+    Reexport,
+}
+
+impl SiteKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Definition => "definition",
+            Self::Call => "call",
+            Self::Import => "import",
+            Self::Reexport => "re-export",
+        }
+    }
+}
+
+/// One place that changes with the symbol.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ImpactSite {
+    pub kind: SiteKind,
+    pub line: usize,
+    /// Scoped name of the definition or calling symbol, or the imported
+    /// path
+    pub detail: String,
+    /// The site is in test code
+    pub is_test: bool,
+}
+
+/// The sites in one file, in source order.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FileImpact {
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub sites: Vec<ImpactSite>,
+}
+
+/// Everything a rename or signature change of a symbol affects.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct RenameImpact {
+    /// Simple name being changed
+    pub name: String,
+    /// Scoped names of the definitions changing
+    pub definitions: Vec<String>,
+    pub calls: usize,
+    pub imports: usize,
+    pub reexports: usize,
+    /// Files with at least one site, sorted by path
+    pub files: Vec<FileImpact>,
+}
+
+/// Sites affected by changing `spec`, a scoped or simple name. `None` if no
+/// definition matches.
+pub fn rename_impact(state: &OciState, spec: &str) -> Option<RenameImpact> {
+    let exact = state.lookup(spec).and_then(|key| state.get_symbol(key));
+    let definitions: Vec<SymbolDef> = match exact {
+        Some(symbol) => vec![symbol],
+        None => state.find_by_name(spec),
+    };
+    let name = state.resolve(definitions.first()?.name).to_string();
+    let scoped: HashSet<String> = definitions
+        .iter()
+        .map(|d| state.resolve(d.scoped_name).to_string())
+        .collect();
+
+    let mut files: BTreeMap<PathBuf, Vec<ImpactSite>> = BTreeMap::new();
+    for def in &definitions {
+        files
+            .entry(relative(state, &def.location.file))
+            .or_default()
+            .push(ImpactSite {
+                kind: SiteKind::Definition,
+                line: def.location.start_line,
+                detail: state.resolve(def.scoped_name).to_string(),
+                is_test: def.is_test_context,
+            });
+    }
+
+    let mut calls = 0;
+    for candidate in resolve_callers(state, &name) {
+        if !scoped.contains(&candidate.callee) {
+            continue;
+        }
+        for caller in candidate.callers {
+            calls += 1;
+            files.entry(caller.file).or_default().push(ImpactSite {
+                kind: SiteKind::Call,
+                line: caller.line,
+                detail: caller.caller,
+                is_test: caller.is_test_context,
+            });
+        }
+    }
+    // Types have no candidates above, but tuple structs and variants are
+    // called as constructors
+    if !definitions
+        .iter()
+        .any(|d| matches!(d.kind, SymbolKind::Function | SymbolKind::Method))
+    {
+        for edge in state.find_callers(&name) {
+            calls += 1;
+            let is_test = state
+                .get_symbol(edge.caller)
+                .is_some_and(|s| s.is_test_context);
+            files
+                .entry(relative(state, &edge.location.file))
+                .or_default()
+                .push(ImpactSite {
+                    kind: SiteKind::Call,
+                    line: edge.location.start_line,
+                    detail: state.resolve(edge.caller).to_string(),
+                    is_test,
+                });
+        }
+    }
+
+    let (mut imports, mut reexports) = (0, 0);
+    for entry in state.imports.iter() {
+        for import in entry.value() {
+            if import.is_glob || import.original_name.as_deref().unwrap_or(&import.name) != name {
+                continue;
+            }
+            let kind = if import.is_reexport {
+                reexports += 1;
+                SiteKind::Reexport
+            } else {
+                imports += 1;
+                SiteKind::Import
+            };
+            let file = relative(state, &import.location.file);
+            let is_test = crate::topology::is_test_path(&file);
+            files.entry(file).or_default().push(ImpactSite {
+                kind,
+                line: import.location.start_line,
+                detail: import.path.clone(),
+                is_test,
+            });
+        }
+    }
+
+    let files = files
+        .into_iter()
+        .map(|(file, mut sites)| {
+            sites.sort_by(|a, b| a.line.cmp(&b.line).then(a.kind.cmp(&b.kind)));
+            FileImpact { file, sites }
+        })
+        .collect();
+    let mut definitions: Vec<String> = scoped.into_iter().collect();
+    definitions.sort();
+    Some(RenameImpact {
+        name,
+        definitions,
+        calls,
+        imports,
+        reexports,
+        files,
+    })
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_rename_impact_groups_sites_by_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let files = [
+            (
+                "src/lib.rs",
+                "pub mod codec;\npub mod api;\npub use codec::decode;\n",
+            ),
+            (
+                "src/codec.rs",
+                "pub fn decode() -> u8 {\n    0\n}\n\npub struct Json;\n\nimpl Json {\n    pub fn decode(&self) -> u8 {\n        1\n    }\n}\n",
+            ),
+            (
+                "src/api.rs",
+                "use crate::codec::decode;\n\npub fn handle() -> u8 {\n    decode()\n}\n",
+            ),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let impact = rename_impact(&state, "crate::decode").unwrap();
+        assert_eq!(impact.definitions, vec!["crate::decode"]);
+        assert_eq!((impact.calls, impact.imports, impact.reexports), (1, 1, 1));
+        let api = impact
+            .files
+            .iter()
+            .find(|f| f.file == Path::new("src/api.rs"))
+            .unwrap();
+        let kinds: Vec<SiteKind> = api.sites.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SiteKind::Import, SiteKind::Call]);
+        assert_eq!(api.sites[1].detail, "crate::handle");
+        let lib = impact
+            .files
+            .iter()
+            .find(|f| f.file == Path::new("src/lib.rs"))
+            .unwrap();
+        assert_eq!(lib.sites[0].kind, SiteKind::Reexport);
+
+        // A simple name covers every definition sharing it
+        let all = rename_impact(&state, "decode").unwrap();
+        assert_eq!(all.definitions.len(), 2);
+        assert!(rename_impact(&state, "missing").is_none());
+    }
+}
@@ -0,0 +1,311 @@
+//! Rename tracking.
+//!
+//! When an incremental index sees a symbol disappear from a file while a
+//! symbol with a different name but the same body appears in that file, it
+//! records an alias from the old name to the new one. Searches and symbol
+//! lookups for the old name then find the new symbol with a "renamed to X"
+//! note instead of coming up empty after a refactor.
+//!
+//! Aliases are persisted in `.omni/renames.json` and survive incremental
+//! runs; `omni index --force` clears them with the rest of the cache.
+
+use crate::cache::{ensure_cache_dir, renames_path};
+use crate::error::{OmniError, Result};
+use crate::query::{DocKind, SearchDoc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A detected rename from `old_name` to `new_name`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameAlias {
+    /// Scoped name before the rename
+    pub old_name: String,
+    /// Scoped name after the rename (updated if renamed again)
+    pub new_name: String,
+    /// Root-relative file containing the symbol
+    pub file: String,
+    /// Date the rename was detected (`YYYY-MM-DD`, UTC)
+    pub date: String,
+    /// Short HEAD commit when the rename was detected, if in a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl RenameAlias {
+    /// Human-readable note, e.g.
+    /// `crate::ld was renamed to crate::load on 2024-05-01 (at 1a2b3c4)`.
+    pub fn note(&self) -> String {
+        match &self.commit {
+            Some(commit) => format!(
+                "{} was renamed to {} on {} (at {})",
+                self.old_name, self.new_name, self.date, commit
+            ),
+            None => format!(
+                "{} was renamed to {} on {}",
+                self.old_name, self.new_name, self.date
+            ),
+        }
+    }
+
+    /// Whether a lookup for `name` (scoped or simple) refers to the old name.
+    pub fn matches(&self, name: &str) -> bool {
+        self.old_name == name || simple_name(&self.old_name) == name
+    }
+}
+
+/// Persisted rename aliases for a workspace.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenameLog {
+    pub aliases: Vec<RenameAlias>,
+}
+
+impl RenameLog {
+    /// Load the log, or an empty one if none was written yet.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = renames_path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+        serde_json::from_slice(&data).map_err(|e| OmniError::corrupt(&path, e))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        ensure_cache_dir(root)?;
+        let path = renames_path(root);
+        let data = serde_json::to_vec_pretty(self).map_err(|e| OmniError::corrupt(&path, e))?;
+        fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+    }
+
+    /// Record a rename, following chains so every historical name points at
+    /// the current one (`a -> b` then `b -> c` gives `a -> c` and `b -> c`).
+    pub fn record(&mut self, alias: RenameAlias) {
+        for existing in &mut self.aliases {
+            if existing.new_name == alias.old_name && existing.file == alias.file {
+                existing.new_name = alias.new_name.clone();
+            }
+        }
+        // A name that is live again (e.g. renamed back) is no longer an alias
+        self.aliases.retain(|a| {
+            a.old_name != a.new_name
+                && !(a.old_name == alias.new_name && a.file == alias.file)
+                && !(a.old_name == alias.old_name && a.file == alias.file)
+        });
+        self.aliases.push(alias);
+    }
+
+    /// Aliases whose old name matches `name` (scoped or simple).
+    pub fn lookup(&self, name: &str) -> Vec<&RenameAlias> {
+        self.aliases.iter().filter(|a| a.matches(name)).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+/// Pair symbols removed from a file with symbols added to it whose bodies
+/// match once their own names are blanked out.
+///
+/// `old_docs` and `new_docs` are a file set's search docs before and after
+/// re-indexing. Only one-to-one matches within the same file count, so two
+/// identical stubs appearing at once are not guessed at.
+pub fn detect_renames(
+    old_docs: &[SearchDoc],
+    new_docs: &[SearchDoc],
+) -> Vec<(String, String, String)> {
+    let names = |docs: &[SearchDoc]| -> HashSet<(String, String)> {
+        docs.iter()
+            .map(|doc| (doc.file.clone(), doc.symbol.clone()))
+            .collect()
+    };
+    let old_names = names(old_docs);
+    let new_names = names(new_docs);
+
+    let mut removed: HashMap<(String, u64), Vec<&SearchDoc>> = HashMap::new();
+    for doc in old_docs.iter().filter(|d| d.kind == DocKind::Symbol) {
+        if !new_names.contains(&(doc.file.clone(), doc.symbol.clone())) {
+            removed.entry(body_key(doc)).or_default().push(doc);
+        }
+    }
+    let mut added: HashMap<(String, u64), Vec<&SearchDoc>> = HashMap::new();
+    for doc in new_docs.iter().filter(|d| d.kind == DocKind::Symbol) {
+        if !old_names.contains(&(doc.file.clone(), doc.symbol.clone())) {
+            added.entry(body_key(doc)).or_default().push(doc);
+        }
+    }
+
+    let mut renames: Vec<(String, String, String)> = removed
+        .iter()
+        .filter_map(
+            |(key, old)| match (old.as_slice(), added.get(key)?.as_slice()) {
+                ([old], [new]) => Some((old.symbol.clone(), new.symbol.clone(), old.file.clone())),
+                _ => None,
+            },
+        )
+        .collect();
+    renames.sort();
+    renames
+}
+
+/// Record `renames` (from [`detect_renames`]) in the workspace's log.
+pub fn record_renames(root: &Path, renames: &[(String, String, String)]) -> Result<()> {
+    if renames.is_empty() {
+        return Ok(());
+    }
+    let mut log = RenameLog::load(root)?;
+    let date = today();
+    let commit = head_commit(root);
+    for (old_name, new_name, file) in renames {
+        tracing::info!("Detected rename {} -> {} in {}", old_name, new_name, file);
+        log.record(RenameAlias {
+            old_name: old_name.clone(),
+            new_name: new_name.clone(),
+            file: file.clone(),
+            date: date.clone(),
+            commit: commit.clone(),
+        });
+    }
+    log.save(root)
+}
+
+/// File plus hash of the symbol's text with its own name removed.
+fn body_key(doc: &SearchDoc) -> (String, u64) {
+    let name = simple_name(&doc.symbol);
+    let body = if name.is_empty() {
+        doc.indexed_text.clone()
+    } else {
+        doc.indexed_text.replace(name, "")
+    };
+    (
+        doc.file.clone(),
+        crate::cache::content_hash(body.as_bytes()),
+    )
+}
+
+pub(crate) fn simple_name(scoped: &str) -> &str {
+    scoped.rsplit([':', '.']).next().unwrap_or(scoped)
+}
+
+pub(crate) fn head_commit(root: &Path) -> Option<String> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(root).args(["rev-parse", "--short", "HEAD"]);
+    // Hooks export GIT_DIR and friends, which would point at another repo
+    for (key, _) in std::env::vars_os() {
+        if key.to_str().is_some_and(|k| k.starts_with("GIT_")) {
+            cmd.env_remove(&key);
+        }
+    }
+    let output = cmd.output().ok().filter(|o| o.status.success())?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+/// Current UTC date as `YYYY-MM-DD`.
+pub(crate) fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date (Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(file: &str, symbol: &str, text: &str) -> SearchDoc {
+        SearchDoc {
+            symbol: symbol.to_string(),
+            file: file.to_string(),
+            start_byte: 0,
+            end_byte: text.len(),
+            start_line: 0,
+            end_line: 0,
+            start_col: 0,
+            end_col: 0,
+            preview: text.to_string(),
+            indexed_text: text.to_string(),
+            role: "core".to_string(),
+            deprecated: None,
+            kind: DocKind::Symbol,
+            is_test_context: false,
+        }
+    }
+
+    #[test]
+    fn test_detects_same_body_renames() {
+        let old = vec![
+            doc("src/a.rs", "crate::load_cfg", "fn load_cfg() { read(); }"),
+            doc("src/a.rs", "crate::keep", "fn keep() {}"),
+            doc("src/a.rs", "crate::gone", "fn gone() { x(); }"),
+        ];
+        let new = vec![
+            doc(
+                "src/a.rs",
+                "crate::load_config",
+                "fn load_config() { read(); }",
+            ),
+            doc("src/a.rs", "crate::keep", "fn keep() {}"),
+            doc("src/a.rs", "crate::fresh", "fn fresh() { y(); }"),
+        ];
+        assert_eq!(
+            detect_renames(&old, &new),
+            vec![(
+                "crate::load_cfg".to_string(),
+                "crate::load_config".to_string(),
+                "src/a.rs".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_record_follows_chains() {
+        let alias = |old: &str, new: &str| RenameAlias {
+            old_name: old.to_string(),
+            new_name: new.to_string(),
+            file: "src/a.rs".to_string(),
+            date: "2024-01-01".to_string(),
+            commit: None,
+        };
+        let mut log = RenameLog::default();
+        log.record(alias("crate::a", "crate::b"));
+        log.record(alias("crate::b", "crate::c"));
+        assert_eq!(log.lookup("a")[0].new_name, "crate::c");
+        assert_eq!(log.lookup("crate::b")[0].new_name, "crate::c");
+        assert_eq!(
+            log.lookup("a")[0].note(),
+            "crate::a was renamed to crate::c on 2024-01-01"
+        );
+
+        // Renamed back: `c` is live again and `a`/`b` now point at `a`
+        log.record(alias("crate::c", "crate::a"));
+        assert!(log.lookup("a").is_empty());
+        assert_eq!(log.lookup("b")[0].new_name, "crate::a");
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
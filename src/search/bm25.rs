@@ -6,6 +6,10 @@
 //! - Doc comments
 //! - String literals
 //! - General code tokens
+//!
+//! Indexes built [`with_positions`](Bm25Index::with_positions) also record
+//! token positions, so multi-word queries can favour documents where the
+//! terms appear adjacent and in query order (e.g. `lock poisoned`).
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -62,19 +66,33 @@ pub struct Bm25Params {
     pub k1: f32,
     /// Length normalization parameter (typically 0.75).
     pub b: f32,
+    /// Scale of the proximity bonus for consecutive query terms that occur
+    /// in order within [`PROXIMITY_WINDOW`] tokens. `0.0` disables it; it has
+    /// no effect on indexes built without positions.
+    pub proximity_weight: f32,
 }
 
 impl Default for Bm25Params {
     fn default() -> Self {
-        Self { k1: 1.2, b: 0.75 }
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            proximity_weight: 1.0,
+        }
     }
 }
 
+/// Maximum token distance between consecutive query terms that still earns
+/// a proximity bonus.
+pub const PROXIMITY_WINDOW: u32 = 8;
+
 /// A posting entry for a term.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Posting {
     doc_id: u32,
     tf_by_field: [u32; 5],
+    /// Token positions in the document; empty unless the index is positional.
+    positions: Vec<u32>,
 }
 
 /// Document statistics.
@@ -94,6 +112,8 @@ pub struct Bm25Index {
     avg_len_by_field: [f32; 5],
     /// Document frequency per term.
     df: HashMap<String, u32>,
+    /// Whether postings record token positions.
+    positional: bool,
 }
 
 /// Search result from BM25.
@@ -108,6 +128,20 @@ impl Bm25Index {
         Self::default()
     }
 
+    /// Record token positions for phrase/proximity scoring.
+    ///
+    /// Must be set before documents are added.
+    pub fn with_positions(mut self, enabled: bool) -> Self {
+        debug_assert!(self.docs.is_empty());
+        self.positional = enabled;
+        self
+    }
+
+    /// Whether the index records token positions.
+    pub fn has_positions(&self) -> bool {
+        self.positional
+    }
+
     /// Add a document (symbol) to the index.
     pub fn add_document(
         &mut self,
@@ -123,18 +157,25 @@ impl Bm25Index {
             panic!("Bm25Index::add_document expects sequential doc_id assignment");
         }
         let mut lens = [0u32; 5];
+        let positional = self.positional;
+        // Keep fields apart so terms never look adjacent across a boundary
+        let gap = PROXIMITY_WINDOW + 1;
 
-        // Helper to add tokens
+        // Helper to add tokens; fields are added in `Field::index` order
         let mut add = |field: Field, token: &str| {
             if token.is_empty() {
                 return;
             }
+            let position = lens.iter().sum::<u32>() + field.index() as u32 * gap;
             let term = token.to_ascii_lowercase();
             let postings = self.inv.entry(term).or_default();
 
             match postings.last_mut() {
                 Some(last) if last.doc_id == doc_id => {
                     last.tf_by_field[field.index()] += 1;
+                    if positional {
+                        last.positions.push(position);
+                    }
                 }
                 _ => {
                     let mut tf = [0u32; 5];
@@ -142,6 +183,11 @@ impl Bm25Index {
                     postings.push(Posting {
                         doc_id,
                         tf_by_field: tf,
+                        positions: if positional {
+                            vec![position]
+                        } else {
+                            Vec::new()
+                        },
                     });
                 }
             }
@@ -210,16 +256,19 @@ impl Bm25Index {
             weights.code,
         ];
 
+        let terms: Vec<String> = tokenize(query).map(|t| t.to_ascii_lowercase()).collect();
+        let idf = |term: &str| {
+            let df = *self.df.get(term).unwrap_or(&1) as f32;
+            // BM25 IDF: ln((N - df + 0.5) / (df + 0.5) + 1)
+            ((n_docs - df + 0.5) / (df + 0.5) + 1.0).ln()
+        };
+
         // Score each query term
-        for term in tokenize(query) {
-            let term_lower = term.to_ascii_lowercase();
-            let Some(postings) = self.inv.get(&term_lower) else {
+        for term_lower in &terms {
+            let Some(postings) = self.inv.get(term_lower) else {
                 continue;
             };
-
-            let df = *self.df.get(&term_lower).unwrap_or(&1) as f32;
-            // BM25 IDF: ln((N - df + 0.5) / (df + 0.5) + 1)
-            let idf = ((n_docs - df + 0.5) / (df + 0.5) + 1.0).ln();
+            let idf = idf(term_lower);
 
             for posting in postings {
                 let doc = &self.docs[posting.doc_id as usize];
@@ -249,6 +298,23 @@ impl Bm25Index {
             }
         }
 
+        // Reward consecutive query terms that appear in order close together
+        if self.positional && params.proximity_weight > 0.0 {
+            for pair in terms.windows(2) {
+                let (Some(first), Some(second)) = (self.inv.get(&pair[0]), self.inv.get(&pair[1]))
+                else {
+                    continue;
+                };
+                let pair_idf = (idf(&pair[0]) + idf(&pair[1])) / 2.0;
+                for (doc_id, gap) in ordered_gaps(first, second) {
+                    if gap <= PROXIMITY_WINDOW {
+                        *scores.entry(doc_id).or_default() +=
+                            params.proximity_weight * pair_idf / gap as f32;
+                    }
+                }
+            }
+        }
+
         // Sort by score and return top-k
         let mut results: Vec<_> = scores
             .into_iter()
@@ -275,50 +341,98 @@ impl Bm25Index {
     }
 }
 
+/// For documents containing both terms, the smallest distance from an
+/// occurrence of `first` to a later occurrence of `second`.
+fn ordered_gaps<'a>(
+    first: &'a [Posting],
+    second: &'a [Posting],
+) -> impl Iterator<Item = (u32, u32)> + 'a {
+    // Postings are sorted by doc_id, so merge-join them
+    let mut j = 0;
+    first.iter().filter_map(move |a| {
+        while j < second.len() && second[j].doc_id < a.doc_id {
+            j += 1;
+        }
+        let b = second.get(j).filter(|b| b.doc_id == a.doc_id)?;
+        let mut gap = None;
+        let mut k = 0;
+        for &p in &a.positions {
+            while k < b.positions.len() && b.positions[k] <= p {
+                k += 1;
+            }
+            let Some(&q) = b.positions.get(k) else {
+                break;
+            };
+            gap = Some(gap.map_or(q - p, |g: u32| g.min(q - p)));
+        }
+        gap.map(|g| (a.doc_id, g))
+    })
+}
+
 /// Simple tokenizer for code.
 ///
-/// Splits on non-word characters and handles camelCase/snake_case.
+/// Splits on non-word characters and handles camelCase/snake_case,
+/// acronyms, and digits (see [`split_identifier`]).
 pub fn tokenize(text: &str) -> impl Iterator<Item = &str> {
     text.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
         .filter(|s| !s.is_empty())
         .flat_map(split_identifier)
 }
 
-/// Split an identifier into sub-tokens (camelCase, snake_case).
-fn split_identifier(s: &str) -> Vec<&str> {
-    if s.is_empty() {
-        return Vec::new();
-    }
+/// Words too common to be worth a token of their own when they are only
+/// part of an identifier (`to_string`, `getTheValue`).
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "at", "by", "in", "of", "on", "or", "the", "to",
+];
 
-    let mut tokens = Vec::new();
+/// Split an identifier into sub-tokens, followed by the identifier itself
+/// when it has more than one part.
+///
+/// Parts break at underscores, lowercase to uppercase (`parseConfig`), the
+/// end of an acronym (`HTTPResponse` -> `HTTP`, `Response`), and letter to
+/// digit and back (`sha256sum` -> `sha`, `256`, `sum`). Parts that are
+/// [`STOP_WORDS`] are dropped; the identifier still matches as a whole.
+fn split_identifier(s: &str) -> Vec<&str> {
     let bytes = s.as_bytes();
+    let mut parts = Vec::new();
     let mut start = 0;
 
     for i in 1..bytes.len() {
-        let prev = bytes[i - 1] as char;
-        let curr = bytes[i] as char;
-
-        // Split on: underscore, lowercase->uppercase transition
-        let boundary = curr == '_' || (prev.is_ascii_lowercase() && curr.is_ascii_uppercase());
+        let (prev, curr) = (bytes[i - 1], bytes[i]);
+        let next = bytes.get(i + 1).copied();
+
+        let boundary = curr == b'_'
+            || prev == b'_'
+            || (prev.is_ascii_lowercase() && curr.is_ascii_uppercase())
+            || (prev.is_ascii_uppercase()
+                && curr.is_ascii_uppercase()
+                && next.is_some_and(|n| n.is_ascii_lowercase()))
+            || (prev.is_ascii_alphabetic() && curr.is_ascii_digit())
+            || (prev.is_ascii_digit() && curr.is_ascii_alphabetic());
 
         if boundary {
             if start < i && bytes[start] != b'_' {
-                tokens.push(&s[start..i]);
+                parts.push(&s[start..i]);
             }
-            start = if curr == '_' { i + 1 } else { i };
+            start = i;
         }
     }
 
     if start < s.len() && bytes[start] != b'_' {
-        tokens.push(&s[start..]);
+        parts.push(&s[start..]);
     }
 
-    // Also add the full identifier as a token
-    if tokens.len() > 1 {
-        tokens.push(s);
+    let whole = parts.len() > 1;
+    if whole {
+        parts.retain(|part| {
+            !STOP_WORDS
+                .iter()
+                .any(|stop| part.eq_ignore_ascii_case(stop))
+        });
+        parts.push(s);
     }
 
-    tokens
+    parts
 }
 
 /// Extract tokens from a file path.
@@ -429,6 +543,64 @@ mod tests {
         assert!(tokens.contains(&"World"));
     }
 
+    #[test]
+    fn test_split_identifier_acronyms_and_digits() {
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "parseHTTP2Response",
+                &["parse", "HTTP", "2", "Response", "parseHTTP2Response"],
+            ),
+            ("sha256sum", &["sha", "256", "sum", "sha256sum"]),
+            ("HTTPServer", &["HTTP", "Server", "HTTPServer"]),
+            (
+                "XMLHttpRequest",
+                &["XML", "Http", "Request", "XMLHttpRequest"],
+            ),
+            ("getURL", &["get", "URL", "getURL"]),
+            ("IOError", &["IO", "Error", "IOError"]),
+            ("x86_64", &["x", "86", "64", "x86_64"]),
+            ("utf8_decode", &["utf", "8", "decode", "utf8_decode"]),
+            ("__init__", &["init"]),
+            ("to_string", &["string", "to_string"]),
+            ("getTheValue", &["get", "Value", "getTheValue"]),
+            ("API", &["API"]),
+            ("value", &["value"]),
+            ("the", &["the"]),
+            ("_", &[]),
+        ];
+        for (identifier, expected) in cases {
+            assert_eq!(split_identifier(identifier), *expected, "{}", identifier);
+        }
+    }
+
+    #[test]
+    fn test_acronym_split_improves_recall() {
+        let mut index = Bm25Index::new();
+        index.add_document(
+            0,
+            vec!["client"],
+            vec!["parseHTTP2Response"],
+            Vec::<&str>::new(),
+            Vec::<&str>::new(),
+            "fn parseHTTP2Response(bytes: &[u8]) -> Response { todo!() }",
+        );
+        index.add_document(
+            1,
+            vec!["digest"],
+            vec!["sha256sum"],
+            Vec::<&str>::new(),
+            Vec::<&str>::new(),
+            "fn sha256sum(data: &[u8]) -> String { todo!() }",
+        );
+        index.finalize();
+
+        let search =
+            |query| index.search(query, &FieldWeights::default(), Bm25Params::default(), 10);
+        assert_eq!(search("http response")[0].doc_id, 0);
+        assert_eq!(search("sha sum")[0].doc_id, 1);
+        assert_eq!(search("sha256sum")[0].doc_id, 1);
+    }
+
     #[test]
     fn test_bm25_basic() {
         let mut index = Bm25Index::new();
@@ -463,6 +635,104 @@ mod tests {
         assert_eq!(results[0].doc_id, 0);
     }
 
+    #[test]
+    fn test_proximity_prefers_adjacent_terms_in_order() {
+        let build = |positional: bool| {
+            let mut index = Bm25Index::new().with_positions(positional);
+            let none = Vec::<&str>::new;
+            index.add_document(
+                0,
+                none(),
+                none(),
+                none(),
+                none(),
+                "poisoned data behind the lock",
+            );
+            index.add_document(
+                1,
+                none(),
+                none(),
+                none(),
+                none(),
+                "the lock is poisoned already",
+            );
+            index.add_document(
+                2,
+                none(),
+                none(),
+                none(),
+                none(),
+                "lock poisoned while held here",
+            );
+            index.finalize();
+            index
+        };
+        let search = |index: &Bm25Index| {
+            index
+                .search(
+                    "lock poisoned",
+                    &FieldWeights::default(),
+                    Bm25Params::default(),
+                    10,
+                )
+                .into_iter()
+                .map(|r| r.doc_id)
+                .collect::<Vec<_>>()
+        };
+
+        let index = build(true);
+        assert!(index.has_positions());
+        assert_eq!(search(&index), vec![2, 1, 0]);
+
+        // Without positions every document scores the same
+        let plain = build(false);
+        let results = plain.search(
+            "lock poisoned",
+            &FieldWeights::default(),
+            Bm25Params::default(),
+            10,
+        );
+        assert_eq!(results.len(), 3);
+        assert!(
+            results
+                .iter()
+                .all(|r| (r.score - results[0].score).abs() < 1e-6)
+        );
+
+        // A disabled weight falls back to plain BM25
+        let params = Bm25Params {
+            proximity_weight: 0.0,
+            ..Bm25Params::default()
+        };
+        let scores: Vec<f32> = index
+            .search("lock poisoned", &FieldWeights::default(), params, 10)
+            .iter()
+            .map(|r| r.score)
+            .collect();
+        assert!(scores.iter().all(|s| (s - scores[0]).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_proximity_ignores_field_boundaries() {
+        let mut index = Bm25Index::new().with_positions(true);
+        index.add_document(
+            0,
+            vec!["lock"],
+            vec!["poisoned"],
+            Vec::<&str>::new(),
+            Vec::<&str>::new(),
+            "",
+        );
+        index.finalize();
+        let first = &index.inv["lock"];
+        let second = &index.inv["poisoned"];
+        assert_eq!(
+            ordered_gaps(first, second).next(),
+            Some((0, PROXIMITY_WINDOW + 2))
+        );
+        assert_eq!(ordered_gaps(second, first).next(), None);
+    }
+
     #[test]
     fn test_extract_doc_comments() {
         let code = r#"
@@ -4,6 +4,20 @@
 //! 1. Semantic search (embeddings) for broad recall - fixes BM25's synonym blindness
 //! 2. BM25 re-ranking of top candidates - protects against junk semantic matches
 //! 3. Reciprocal Rank Fusion (RRF) to combine rankings
+//!
+//! [`HybridSearch::search_coarse_to_fine`] adds a first stage that ranks
+//! coarse scopes (file and module summaries) and keeps only semantic
+//! candidates inside the best ones.
+//!
+//! With [`adaptive_weights`](HybridSearchConfig::adaptive_weights), each query
+//! is classified before fusion: identifier-like queries (`parse_config`,
+//! `state::OciState`, `HybridSearch`) lean on BM25, natural-language queries
+//! ("where do we retry failed uploads") lean on embeddings.
+//! [`HybridSearch::explain`] reports the decision.
+//!
+//! [`HybridSearch::search_deduplicated`] collapses results whose embeddings
+//! are nearly identical (a function and its re-export, say) into one result
+//! listing the others as alternates, before truncating to the final count.
 
 mod bm25;
 
@@ -13,6 +27,7 @@ pub use bm25::{
 };
 
 use std::collections::HashMap;
+use std::fmt;
 
 /// Configuration for hybrid search.
 #[derive(Debug, Clone)]
@@ -31,6 +46,16 @@ pub struct HybridSearchConfig {
     pub rrf_k: f32,
     /// Whether to use RRF (true) or weighted score combination (false).
     pub use_rrf: bool,
+    /// Number of coarse scopes (files/modules) kept by the first stage of
+    /// coarse-to-fine search.
+    pub coarse_top_k: usize,
+    /// Pick semantic/BM25 weights per query from its [`QueryKind`]. Mixed
+    /// queries keep the configured weights.
+    pub adaptive_weights: bool,
+    /// Cosine similarity above which
+    /// [`search_deduplicated`](HybridSearch::search_deduplicated) collapses
+    /// two results into one.
+    pub dedup_threshold: f32,
 }
 
 impl Default for HybridSearchConfig {
@@ -43,10 +68,101 @@ impl Default for HybridSearchConfig {
             bm25_weight: 0.6,
             rrf_k: 60.0,
             use_rrf: true, // RRF typically works better
+            coarse_top_k: 5,
+            adaptive_weights: true,
+            dedup_threshold: 0.95,
+        }
+    }
+}
+
+/// Semantic weight for identifier-like queries; exact tokens matter most.
+const IDENTIFIER_SEMANTIC_WEIGHT: f32 = 0.2;
+/// Semantic weight for natural-language queries; wording rarely matches code.
+const NATURAL_LANGUAGE_SEMANTIC_WEIGHT: f32 = 0.7;
+
+/// How a query reads, as far as fusion weights are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    /// Code identifiers (`snake_case`, `camelCase`, `a::b`, `obj.method`).
+    Identifier,
+    /// A phrase in plain words.
+    NaturalLanguage,
+    /// Neither dominates (or a single plain word).
+    Mixed,
+}
+
+impl QueryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Identifier => "identifier",
+            Self::NaturalLanguage => "natural-language",
+            Self::Mixed => "mixed",
         }
     }
 }
 
+/// Classify a query by its whitespace-separated terms.
+///
+/// A query is [`Identifier`](QueryKind::Identifier) when at least half its
+/// terms look like code identifiers, and
+/// [`NaturalLanguage`](QueryKind::NaturalLanguage) when it has two or more
+/// terms and none of them do.
+pub fn classify_query(query: &str) -> QueryKind {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    let identifiers = terms.iter().filter(|t| is_identifier_like(t)).count();
+    if identifiers > 0 && identifiers * 2 >= terms.len() {
+        QueryKind::Identifier
+    } else if identifiers == 0 && terms.len() >= 2 {
+        QueryKind::NaturalLanguage
+    } else {
+        QueryKind::Mixed
+    }
+}
+
+/// Whether a term is written like code rather than prose.
+fn is_identifier_like(term: &str) -> bool {
+    let term = term.trim_end_matches("()");
+    if term.contains("::") || term.contains("->") {
+        return true;
+    }
+    let chars: Vec<char> = term.chars().collect();
+    chars.windows(2).any(|w| w[0].is_lowercase() && w[1].is_uppercase())
+        || chars.windows(3).any(|w| {
+            matches!(w[1], '_' | '.') && w[0].is_alphanumeric() && w[2].is_alphanumeric()
+        })
+        // PascalCase with several humps (`HybridSearch`); lone capitals are prose
+        || (chars.first().is_some_and(|c| c.is_uppercase())
+            && chars.iter().skip(1).any(|c| c.is_uppercase())
+            && chars.iter().any(|c| c.is_lowercase()))
+}
+
+/// Fusion weights chosen for a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightDecision {
+    pub kind: QueryKind,
+    pub semantic_weight: f32,
+    pub bm25_weight: f32,
+    /// Whether the weights came from classification rather than the config.
+    pub adapted: bool,
+}
+
+impl fmt::Display for WeightDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} query: semantic {:.2}, bm25 {:.2} ({})",
+            self.kind.as_str(),
+            self.semantic_weight,
+            self.bm25_weight,
+            if self.adapted {
+                "adaptive"
+            } else {
+                "configured"
+            }
+        )
+    }
+}
+
 /// Result from hybrid search.
 #[derive(Debug, Clone)]
 pub struct HybridSearchResult {
@@ -60,6 +176,8 @@ pub struct HybridSearchResult {
     pub bm25_score: Option<f32>,
     /// Which retrieval methods found this result.
     pub found_by: FoundBy,
+    /// Near-duplicates collapsed into this result, best first.
+    pub alternates: Vec<u32>,
 }
 
 /// Tracks which retrieval methods found a result.
@@ -87,32 +205,147 @@ impl HybridSearch {
         Self::new(HybridSearchConfig::default())
     }
 
+    /// The fusion weights [`search`](Self::search) uses for `query`.
+    pub fn explain(&self, query: &str) -> WeightDecision {
+        let kind = classify_query(query);
+        let semantic_weight = match kind {
+            _ if !self.config.adaptive_weights => None,
+            QueryKind::Identifier => Some(IDENTIFIER_SEMANTIC_WEIGHT),
+            QueryKind::NaturalLanguage => Some(NATURAL_LANGUAGE_SEMANTIC_WEIGHT),
+            QueryKind::Mixed => None,
+        };
+        match semantic_weight {
+            Some(semantic_weight) => WeightDecision {
+                kind,
+                semantic_weight,
+                bm25_weight: 1.0 - semantic_weight,
+                adapted: true,
+            },
+            None => WeightDecision {
+                kind,
+                semantic_weight: self.config.semantic_weight,
+                bm25_weight: self.config.bm25_weight,
+                adapted: false,
+            },
+        }
+    }
+
     /// Perform hybrid search.
     ///
     /// Pipeline:
     /// 1. Get semantic candidates (embeddings ANN search)
     /// 2. Get BM25 candidates
-    /// 3. Fuse rankings using RRF or weighted combination
+    /// 3. Fuse rankings using RRF or weighted combination, with weights from
+    ///    [`explain`](Self::explain)
     pub fn search(
         &self,
         query: &str,
         semantic_results: Vec<(u32, f32)>,
         bm25_results: Vec<(u32, f32)>,
     ) -> Vec<HybridSearchResult> {
+        let mut results = self.fuse(query, semantic_results, bm25_results);
+        results.truncate(self.config.final_top_k);
+        results
+    }
+
+    /// [`search`](Self::search), collapsing near-duplicate results.
+    ///
+    /// After fusion, each result whose embedding (from `embedding`) has a
+    /// cosine similarity above
+    /// [`dedup_threshold`](HybridSearchConfig::dedup_threshold) with a
+    /// better-ranked result is folded into that result's
+    /// [`alternates`](HybridSearchResult::alternates), so it no longer takes
+    /// one of the `final_top_k` slots. Results without an embedding are kept
+    /// as they are.
+    pub fn search_deduplicated(
+        &self,
+        query: &str,
+        semantic_results: Vec<(u32, f32)>,
+        bm25_results: Vec<(u32, f32)>,
+        embedding: impl Fn(u32) -> Option<Vec<f32>>,
+    ) -> Vec<HybridSearchResult> {
+        let mut kept: Vec<(HybridSearchResult, Option<Vec<f32>>)> = Vec::new();
+        for result in self.fuse(query, semantic_results, bm25_results) {
+            let vector = embedding(result.doc_id);
+            let duplicate_of = vector.as_ref().and_then(|v| {
+                kept.iter().position(|(_, other)| {
+                    other
+                        .as_ref()
+                        .is_some_and(|o| cosine_similarity(v, o) > self.config.dedup_threshold)
+                })
+            });
+            match duplicate_of {
+                Some(i) => kept[i].0.alternates.push(result.doc_id),
+                None if kept.len() < self.config.final_top_k => kept.push((result, vector)),
+                None => break,
+            }
+        }
+        kept.into_iter().map(|(result, _)| result).collect()
+    }
+
+    /// Fuse both rankings, best first, without truncating.
+    fn fuse(
+        &self,
+        query: &str,
+        semantic_results: Vec<(u32, f32)>,
+        bm25_results: Vec<(u32, f32)>,
+    ) -> Vec<HybridSearchResult> {
+        let weights = self.explain(query);
         if self.config.use_rrf {
-            self.search_rrf(query, semantic_results, bm25_results)
+            self.search_rrf(&weights, semantic_results, bm25_results)
         } else {
-            self.search_weighted(query, semantic_results, bm25_results)
+            self.search_weighted(&weights, semantic_results, bm25_results)
         }
     }
 
+    /// Two-stage coarse-to-fine hybrid search.
+    ///
+    /// `scope_results` ranks coarse scopes (e.g. file and module summaries)
+    /// for the query, and `doc_scopes` lists the scopes a document belongs
+    /// to. Semantic candidates outside the top
+    /// [`coarse_top_k`](HybridSearchConfig::coarse_top_k) scopes are dropped
+    /// before fusion; BM25 candidates are kept, since exact term matches are
+    /// precise on their own. If no semantic candidate falls inside the top
+    /// scopes, this is plain [`search`](Self::search).
+    pub fn search_coarse_to_fine<S>(
+        &self,
+        query: &str,
+        scope_results: &[(u32, f32)],
+        doc_scopes: impl Fn(u32) -> S,
+        semantic_results: Vec<(u32, f32)>,
+        bm25_results: Vec<(u32, f32)>,
+    ) -> Vec<HybridSearchResult>
+    where
+        S: IntoIterator<Item = u32>,
+    {
+        let mut ranked = scope_results.to_vec();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let top: std::collections::HashSet<u32> = ranked
+            .iter()
+            .take(self.config.coarse_top_k)
+            .map(|(scope, _)| *scope)
+            .collect();
+
+        let narrowed: Vec<(u32, f32)> = semantic_results
+            .iter()
+            .filter(|(doc_id, _)| doc_scopes(*doc_id).into_iter().any(|s| top.contains(&s)))
+            .copied()
+            .collect();
+        let semantic_results = if narrowed.is_empty() {
+            semantic_results
+        } else {
+            narrowed
+        };
+        self.search(query, semantic_results, bm25_results)
+    }
+
     /// Reciprocal Rank Fusion (RRF) combining.
     ///
     /// RRF score = sum(1 / (k + rank_i)) for each ranking
     /// This is robust to different score scales and distributions.
     fn search_rrf(
         &self,
-        _query: &str,
+        weights: &WeightDecision,
         semantic_results: Vec<(u32, f32)>,
         bm25_results: Vec<(u32, f32)>,
     ) -> Vec<HybridSearchResult> {
@@ -121,7 +354,7 @@ impl HybridSearch {
 
         // Add semantic results with RRF scoring
         for (rank, (doc_id, sim_score)) in semantic_results.iter().enumerate() {
-            let rrf_score = self.config.semantic_weight / (k + rank as f32 + 1.0);
+            let rrf_score = weights.semantic_weight / (k + rank as f32 + 1.0);
             scores.insert(
                 *doc_id,
                 (rrf_score, Some(*sim_score), None, FoundBy::SemanticOnly),
@@ -130,7 +363,7 @@ impl HybridSearch {
 
         // Add/merge BM25 results with RRF scoring
         for (rank, (doc_id, bm25_score)) in bm25_results.iter().enumerate() {
-            let rrf_score = self.config.bm25_weight / (k + rank as f32 + 1.0);
+            let rrf_score = weights.bm25_weight / (k + rank as f32 + 1.0);
 
             scores
                 .entry(*doc_id)
@@ -152,6 +385,7 @@ impl HybridSearch {
                     semantic_score,
                     bm25_score,
                     found_by,
+                    alternates: Vec::new(),
                 },
             )
             .collect();
@@ -161,7 +395,6 @@ impl HybridSearch {
                 .partial_cmp(&a.score)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        results.truncate(self.config.final_top_k);
         results
     }
 
@@ -170,7 +403,7 @@ impl HybridSearch {
     /// Normalizes scores to [0,1] range and combines with weights.
     fn search_weighted(
         &self,
-        _query: &str,
+        weights: &WeightDecision,
         semantic_results: Vec<(u32, f32)>,
         bm25_results: Vec<(u32, f32)>,
     ) -> Vec<HybridSearchResult> {
@@ -178,7 +411,7 @@ impl HybridSearch {
 
         // Normalize semantic scores (already 0-1 for cosine similarity)
         for (doc_id, sim_score) in semantic_results {
-            let weighted = self.config.semantic_weight * sim_score;
+            let weighted = weights.semantic_weight * sim_score;
             scores.insert(
                 doc_id,
                 (weighted, Some(sim_score), None, FoundBy::SemanticOnly),
@@ -197,7 +430,7 @@ impl HybridSearch {
             } else {
                 0.0
             };
-            let weighted = self.config.bm25_weight * normalized;
+            let weighted = weights.bm25_weight * normalized;
 
             scores
                 .entry(doc_id)
@@ -219,6 +452,7 @@ impl HybridSearch {
                     semantic_score,
                     bm25_score,
                     found_by,
+                    alternates: Vec::new(),
                 },
             )
             .collect();
@@ -228,11 +462,18 @@ impl HybridSearch {
                 .partial_cmp(&a.score)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        results.truncate(self.config.final_top_k);
         results
     }
 }
 
+/// Cosine similarity of two vectors (0.0 if either is zero).
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 { 0.0 } else { dot / denom }
+}
+
 /// Quality metrics for search evaluation.
 #[derive(Debug, Clone, Default)]
 pub struct SearchQualityMetrics {
@@ -361,12 +602,112 @@ mod tests {
         assert!(found_by_both.len() >= 2);
     }
 
+    #[test]
+    fn test_coarse_to_fine_drops_semantic_outside_top_scopes() {
+        let search = HybridSearch::new(HybridSearchConfig {
+            coarse_top_k: 1,
+            ..Default::default()
+        });
+        // Docs 1-2 live in scope 10, doc 3 in scope 20
+        let doc_scopes = |doc_id: u32| Some(if doc_id < 3 { 10 } else { 20 });
+        let scopes = [(20u32, 0.4), (10u32, 0.9)];
+        let semantic = vec![(3u32, 0.95), (1u32, 0.8), (2u32, 0.7)];
+        let bm25 = vec![(4u32, 3.0)];
+
+        let results =
+            search.search_coarse_to_fine("auth", &scopes, doc_scopes, semantic.clone(), bm25);
+        let ids: Vec<u32> = results.iter().map(|r| r.doc_id).collect();
+        assert!(!ids.contains(&3));
+        assert!(ids.contains(&1) && ids.contains(&2) && ids.contains(&4));
+
+        // Nothing inside the top scope: fall back to unfiltered fusion
+        let results =
+            search.search_coarse_to_fine("auth", &[(30, 1.0)], doc_scopes, semantic, Vec::new());
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_classify_query() {
+        assert_eq!(classify_query("parse_config"), QueryKind::Identifier);
+        assert_eq!(classify_query("state::OciState"), QueryKind::Identifier);
+        assert_eq!(classify_query("HybridSearch search"), QueryKind::Identifier);
+        assert_eq!(classify_query("getUserById()"), QueryKind::Identifier);
+        assert_eq!(
+            classify_query("where do we retry failed uploads"),
+            QueryKind::NaturalLanguage
+        );
+        assert_eq!(
+            classify_query("How is Config loaded"),
+            QueryKind::NaturalLanguage
+        );
+        assert_eq!(
+            classify_query("who calls parse_config on startup"),
+            QueryKind::Mixed
+        );
+        assert_eq!(classify_query("parse"), QueryKind::Mixed);
+        assert_eq!(classify_query(""), QueryKind::Mixed);
+    }
+
+    #[test]
+    fn test_adaptive_weights_follow_query_kind() {
+        let search = HybridSearch::with_default_config();
+        let ident = search.explain("parse_config");
+        assert!(ident.adapted && ident.bm25_weight > ident.semantic_weight);
+        let prose = search.explain("where do we retry failed uploads");
+        assert!(prose.adapted && prose.semantic_weight > prose.bm25_weight);
+        assert!(prose.to_string().starts_with("natural-language query"));
+
+        let mixed = search.explain("parse");
+        assert!(!mixed.adapted);
+        assert_eq!(mixed.semantic_weight, 0.4);
+
+        // Identifier query: the BM25-only hit outranks the semantic-only one
+        let results = search.search("parse_config", vec![(1, 0.9)], vec![(2, 5.0)]);
+        assert_eq!(results[0].doc_id, 2);
+        let results = search.search("how are configs parsed", vec![(1, 0.9)], vec![(2, 5.0)]);
+        assert_eq!(results[0].doc_id, 1);
+
+        let fixed = HybridSearch::new(HybridSearchConfig {
+            adaptive_weights: false,
+            ..Default::default()
+        });
+        assert!(!fixed.explain("parse_config").adapted);
+    }
+
+    #[test]
+    fn test_near_duplicates_collapse_before_truncation() {
+        let search = HybridSearch::new(HybridSearchConfig {
+            final_top_k: 2,
+            ..Default::default()
+        });
+        // Doc 2 re-exports doc 1; doc 3 is different; doc 4 has no embedding
+        let embedding = |doc_id: u32| match doc_id {
+            1 => Some(vec![1.0, 0.0]),
+            2 => Some(vec![0.99, 0.05]),
+            3 => Some(vec![0.0, 1.0]),
+            _ => None,
+        };
+        let semantic = vec![(1u32, 0.9), (2u32, 0.89), (3u32, 0.5), (4u32, 0.4)];
+
+        let results = search.search_deduplicated("parse", semantic.clone(), Vec::new(), embedding);
+        let ids: Vec<u32> = results.iter().map(|r| r.doc_id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(results[0].alternates, vec![2]);
+
+        let results = search.search_deduplicated("parse", semantic, Vec::new(), |_| None);
+        assert_eq!(
+            results.iter().map(|r| r.doc_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
     #[test]
     fn test_weighted_combination() {
         let config = HybridSearchConfig {
             use_rrf: false,
             semantic_weight: 0.5,
             bm25_weight: 0.5,
+            adaptive_weights: false,
             ..Default::default()
         };
         let search = HybridSearch::new(config);
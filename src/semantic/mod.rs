@@ -1,15 +1,128 @@
 //! Semantic embedding layer (Layer 3).
 //!
 //! Provides vector embeddings for semantic search and duplicate detection.
-
-use crate::state::OciState;
-use crate::types::InternedString;
-use anyhow::{Context, Result};
+//!
+//! Besides per-symbol vectors, the index keeps coarse summary vectors for
+//! each file and module (directory), built from header docs and top symbols.
+//! Questions about where something lives ("which module handles auth?") are
+//! answered by [`SemanticIndex::coarse_to_fine`]: rank scopes first, then rank
+//! symbols only within the best scopes.
+//!
+//! File updates go through [`SemanticIndex::sync_files`], which re-embeds
+//! only symbols whose embedding text changed. The HNSW graph is not rebuilt
+//! for them: vectors changed since the last build are searched exhaustively
+//! next to it until enough accumulate, and a new graph is then built in the
+//! background.
+//!
+//! Symbol vectors are cached on disk (see [`vector_cache`]), so a restart
+//! only embeds the symbols that changed since the last run.
+//!
+//! The embedding model is downloaded on first use. When it cannot be loaded
+//! the index runs degraded: [`OciState::semantic_status`] reports it as
+//! unavailable, hybrid search uses BM25 alone, and [`spawn_loader`] keeps
+//! retrying in the background until the model is present.
+
+mod vector_cache;
+
+use crate::duplication::{DUPLICATE_SIMILARITY, DuplicationPressure};
+use crate::error::{OmniError, Result};
+use crate::focus::Focus;
+use crate::incremental::{IndexEvent, IndexPhase};
+use crate::state::{OciState, SharedState};
+use crate::types::{
+    EmbeddingDriftReport, EmbeddingModelInfo, InternedString, SemanticStatus, SymbolDef,
+    SymbolKind, Visibility,
+};
 use dashmap::DashMap;
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use instant_distance::{Builder, HnswMap, Point, Search};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use vector_cache::{CachedVector, VectorCache};
+
+/// Name of the embedding model used for new vectors.
+pub const MODEL_NAME: &str = "all-MiniLM-L6-v2";
+/// Version tag of the embedding model; bump when the model or its
+/// preprocessing changes so old vectors are reported as stale.
+pub const MODEL_VERSION: &str = "fastembed-4";
+
+/// Sizing of the embedding session pool.
+///
+/// A single ONNX session serializes embedding work, so batch embedding fans
+/// out over `sessions` independent sessions. At most `sessions` batches of
+/// `batch_size` texts are queued ahead of the workers; the producer blocks
+/// beyond that, which bounds memory on large repositories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingPoolConfig {
+    /// Number of model sessions (each holds its own copy of the model)
+    pub sessions: usize,
+    /// Texts per embedding call
+    pub batch_size: usize,
+}
+
+impl Default for EmbeddingPoolConfig {
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            sessions: cores.clamp(1, 4),
+            batch_size: 64,
+        }
+    }
+}
+
+impl EmbeddingPoolConfig {
+    pub fn with_sessions(mut self, sessions: usize) -> Self {
+        self.sessions = sessions.max(1);
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+/// Symbols included in a file summary.
+const SUMMARY_SYMBOLS: usize = 12;
+/// Header comment lines included in a file summary.
+const SUMMARY_HEADER_LINES: usize = 5;
+/// Files listed in a module summary.
+const SUMMARY_MODULE_FILES: usize = 20;
+/// Vectors changed since the last HNSW build at which a background rebuild
+/// starts, unless a tenth of the graph is more.
+const ANN_REBUILD_CHANGES: usize = 256;
+/// Neighbours of each vector checked for near duplicates.
+const DUPLICATE_NEIGHBORS: usize = 5;
+
+/// A coarse search scope with a summary embedding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SummaryScope {
+    File(PathBuf),
+    /// A directory of indexed files
+    Module(PathBuf),
+}
+
+impl SummaryScope {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::File(path) | Self::Module(path) => path,
+        }
+    }
+
+    /// Whether `file` lies inside this scope.
+    pub fn contains(&self, file: &Path) -> bool {
+        match self {
+            Self::File(path) => path == file,
+            Self::Module(dir) => file.starts_with(dir),
+        }
+    }
+}
 
 /// Wrapper for f32 vector to implement Point trait
 #[derive(Debug, Clone)]
@@ -30,140 +143,738 @@ impl Point for Embedding {
     }
 }
 
+/// An embedding tagged with the model that produced it.
+#[derive(Debug, Clone)]
+struct StoredEmbedding {
+    vector: Embedding,
+    model: Arc<EmbeddingModelInfo>,
+    /// Hash of the embedded text (0 when unknown, e.g. loaded from disk)
+    text_hash: u64,
+}
+
+/// Approximate nearest neighbor search over the symbol vectors.
+///
+/// `instant_distance` graphs cannot be changed once built, so vectors added
+/// or changed since the last build are kept in `delta` and scanned
+/// exhaustively, and graph entries of changed or removed symbols are
+/// skipped. Every change is numbered; a graph built from the vectors as of
+/// change `n` makes the changes up to `n` redundant.
+#[derive(Default)]
+struct Ann {
+    hnsw: Option<HnswMap<Embedding, InternedString>>,
+    /// Vectors in `hnsw`
+    built: usize,
+    /// Symbols changed or removed since their graph entry was built, with
+    /// the number of their latest change
+    outdated: HashMap<InternedString, u64>,
+    /// Vectors added or changed since the graph was built
+    delta: HashMap<InternedString, (Embedding, u64)>,
+    /// Number of the latest change
+    version: u64,
+    /// Graphs built from the vectors as of an older change are discarded
+    floor: u64,
+    /// A background rebuild is running
+    rebuilding: bool,
+}
+
+impl Ann {
+    /// Record a new vector for `symbol`, or its removal.
+    fn change(&mut self, symbol: InternedString, vector: Option<Embedding>) {
+        self.version += 1;
+        self.outdated.insert(symbol, self.version);
+        match vector {
+            Some(vector) => {
+                self.delta.insert(symbol, (vector, self.version));
+            }
+            None => {
+                self.delta.remove(&symbol);
+            }
+        }
+    }
+
+    /// Drop the graph after bulk changes; the next search rebuilds it.
+    fn invalidate(&mut self) {
+        self.version += 1;
+        self.floor = self.version;
+        self.hnsw = None;
+        self.built = 0;
+        self.outdated.clear();
+        self.delta.clear();
+    }
+
+    /// Whether enough changed for a background rebuild to pay off.
+    fn needs_rebuild(&self) -> bool {
+        !self.rebuilding
+            && self.hnsw.is_some()
+            && self.outdated.len() >= ANN_REBUILD_CHANGES.max(self.built / 10)
+    }
+
+    /// Install a graph built from the vectors as of change `version`.
+    fn install(
+        &mut self,
+        hnsw: Option<HnswMap<Embedding, InternedString>>,
+        built: usize,
+        version: u64,
+    ) {
+        if version < self.floor {
+            return;
+        }
+        self.floor = version;
+        self.hnsw = hnsw;
+        self.built = built;
+        self.outdated.retain(|_, changed| *changed > version);
+        self.delta.retain(|_, (_, changed)| *changed > version);
+    }
+
+    /// The `k` nearest symbols from the graph and the changes since.
+    fn search(&self, query: &Embedding, k: usize) -> Vec<(InternedString, f32)> {
+        let mut results: Vec<(InternedString, f32)> = Vec::new();
+        if let Some(hnsw) = &self.hnsw {
+            let mut search = Search::default();
+            results.extend(
+                hnsw.search(query, &mut search)
+                    .filter(|item| !self.outdated.contains_key(item.value))
+                    .take(k)
+                    // Convert distance to similarity (1 - distance for cosine)
+                    .map(|item| (*item.value, 1.0 - item.distance)),
+            );
+        }
+        results.extend(
+            self.delta
+                .iter()
+                .map(|(symbol, (vector, _))| (*symbol, 1.0 - vector.distance(query))),
+        );
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(k);
+        results
+    }
+}
+
+/// Build an HNSW graph over `entries`; `None` when there are none.
+fn build_hnsw(
+    entries: Vec<(Embedding, InternedString)>,
+) -> (Option<HnswMap<Embedding, InternedString>>, usize) {
+    if entries.is_empty() {
+        return (None, 0);
+    }
+    let built = entries.len();
+    let (values, symbols): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+    (Some(Builder::default().build(values, symbols)), built)
+}
+
 /// Semantic index using HNSW for approximate nearest neighbor search
 pub struct SemanticIndex {
-    /// The embedding model
-    model: Arc<TextEmbedding>,
-    /// HNSW index for fast similarity search
-    hnsw: RwLock<Option<HnswMap<Embedding, InternedString>>>,
+    /// Pooled embedding sessions; single texts round-robin over them
+    sessions: Vec<TextEmbedding>,
+    next_session: AtomicUsize,
+    batch_size: usize,
+    /// Identity of `model`, recorded on every embedding it produces
+    model_info: Arc<EmbeddingModelInfo>,
+    /// HNSW graph and the changes since it was built; shared with
+    /// background rebuilds
+    ann: Arc<RwLock<Ann>>,
     /// Map from symbol to embedding (for incremental updates)
-    embeddings: DashMap<InternedString, Embedding>,
-    /// Map from symbol to index in HNSW
-    symbol_to_idx: DashMap<InternedString, usize>,
+    embeddings: DashMap<InternedString, StoredEmbedding>,
+    /// File and module summary vectors for coarse retrieval
+    summaries: DashMap<SummaryScope, StoredEmbedding>,
 }
 
 impl SemanticIndex {
     /// Create a new empty semantic index
     pub fn new() -> Result<Self> {
-        // Initialize the embedding model (all-MiniLM-L6-v2)
-        let model = TextEmbedding::try_new(
-            InitOptions::new(EmbeddingModel::AllMiniLML6V2).with_show_download_progress(false),
-        )
-        .context("Failed to initialize embedding model")?;
+        Self::with_pool(EmbeddingPoolConfig::default())
+    }
+
+    /// Create a new empty semantic index with a sized session pool
+    pub fn with_pool(config: EmbeddingPoolConfig) -> Result<Self> {
+        // Initialize the embedding model (all-MiniLM-L6-v2) once per session
+        let sessions = (0..config.sessions.max(1))
+            .map(|_| {
+                TextEmbedding::try_new(
+                    InitOptions::new(EmbeddingModel::AllMiniLML6V2)
+                        .with_show_download_progress(false),
+                )
+                .map_err(|e| OmniError::embedding("Failed to initialize embedding model", e))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Self {
-            model: Arc::new(model),
-            hnsw: RwLock::new(None),
+            sessions,
+            next_session: AtomicUsize::new(0),
+            batch_size: config.batch_size.max(1),
+            model_info: Arc::new(EmbeddingModelInfo::new(MODEL_NAME, MODEL_VERSION)),
+            ann: Arc::new(RwLock::new(Ann::default())),
             embeddings: DashMap::new(),
-            symbol_to_idx: DashMap::new(),
+            summaries: DashMap::new(),
         })
     }
 
+    /// Number of pooled embedding sessions
+    pub fn pool_size(&self) -> usize {
+        self.sessions.len()
+    }
+
     /// Generate embedding for text
     fn embed_text(&self, text: &str) -> Result<Embedding> {
-        let embeddings = self
-            .model
+        let session = self.next_session.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+        let embeddings = self.sessions[session]
             .embed(vec![text.to_string()], None)
-            .context("Failed to generate embedding")?;
+            .map_err(|e| OmniError::embedding("Failed to generate embedding", e))?;
 
         if embeddings.is_empty() {
-            anyhow::bail!("No embeddings generated");
+            return Err(OmniError::EmbeddingBackend(
+                "No embeddings generated".to_string(),
+            ));
         }
 
         Ok(Embedding(embeddings[0].clone()))
     }
 
-    /// Build the HNSW index from stored embeddings
-    fn rebuild_index(&self) -> Result<()> {
-        let entries: Vec<_> = self
-            .embeddings
-            .iter()
-            .map(|entry| (entry.value().clone(), *entry.key()))
-            .collect();
+    /// Embed and store every `(symbol, text)` pair across the session pool.
+    ///
+    /// `items` is consumed lazily on the calling thread and handed to one
+    /// worker per session through a bounded queue, so texts are only built
+    /// as fast as they are embedded. Returns the number of vectors stored.
+    fn embed_all<I>(&self, items: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = (InternedString, String)>,
+    {
+        let items = items
+            .into_iter()
+            .map(|(symbol, text)| ((symbol, text_hash(&text)), text));
+        let embedded = self.embed_batches(items, |(symbol, hash), vector| {
+            self.embeddings.insert(symbol, self.stored(vector, hash));
+        })?;
+        self.ann.write().invalidate();
+        Ok(embedded)
+    }
 
-        if entries.is_empty() {
-            *self.hnsw.write() = None;
-            self.symbol_to_idx.clear();
-            return Ok(());
-        }
+    /// Embed every `(key, text)` pair across the session pool, handing each
+    /// vector to `store`.
+    fn embed_batches<K, I>(&self, items: I, store: impl Fn(K, Vec<f32>) + Sync) -> Result<usize>
+    where
+        K: Send,
+        I: IntoIterator<Item = (K, String)>,
+    {
+        let (tx, rx) = mpsc::sync_channel::<Vec<(K, String)>>(self.sessions.len());
+        let rx = Mutex::new(rx);
+        let embedded = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .sessions
+                .iter()
+                .map(|session| {
+                    let (rx, embedded, store) = (&rx, &embedded, &store);
+                    scope.spawn(move || -> Result<()> {
+                        loop {
+                            // Release the receiver before embedding so other
+                            // workers can pick up the next batch
+                            let Ok(batch) = rx.lock().recv() else {
+                                break;
+                            };
+                            let (keys, texts): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                            let vectors = session.embed(texts, None).map_err(|e| {
+                                OmniError::embedding("Failed to generate embeddings", e)
+                            })?;
+                            for (key, vector) in keys.into_iter().zip(vectors) {
+                                store(key, vector);
+                                embedded.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+
+            let mut batch = Vec::with_capacity(self.batch_size);
+            for item in items {
+                batch.push(item);
+                if batch.len() == self.batch_size {
+                    let full = std::mem::replace(&mut batch, Vec::with_capacity(self.batch_size));
+                    // Fails only once every worker has exited with an error
+                    if tx.send(full).is_err() {
+                        break;
+                    }
+                }
+            }
+            if !batch.is_empty() {
+                let _ = tx.send(batch);
+            }
+            drop(tx);
+
+            for worker in workers {
+                worker.join().map_err(|_| {
+                    OmniError::EmbeddingBackend("Embedding worker panicked".to_string())
+                })??;
+            }
+            Ok::<_, OmniError>(())
+        })?;
 
-        // Build HNSW index
-        let values: Vec<_> = entries.iter().map(|(emb, _)| emb.clone()).collect();
-        let symbols: Vec<_> = entries.iter().map(|(_, sym)| *sym).collect();
-        let hnsw = Builder::default().build(values, symbols);
+        Ok(embedded.into_inner())
+    }
 
-        // Update symbol to index mapping
-        self.symbol_to_idx.clear();
-        for (idx, (_, symbol)) in entries.iter().enumerate() {
-            self.symbol_to_idx.insert(*symbol, idx);
+    /// Tag a vector as produced by the current model from text hashing to
+    /// `text_hash`.
+    fn stored(&self, vector: Vec<f32>, text_hash: u64) -> StoredEmbedding {
+        StoredEmbedding {
+            vector: Embedding(vector),
+            model: Arc::clone(&self.model_info),
+            text_hash,
         }
+    }
 
-        *self.hnsw.write() = Some(hnsw);
+    /// Tag a vector as produced by `model`, sharing the current model's tag
+    /// when it is the same.
+    fn stored_by(
+        &self,
+        vector: Vec<f32>,
+        text_hash: u64,
+        model: EmbeddingModelInfo,
+    ) -> StoredEmbedding {
+        if model == *self.model_info {
+            return self.stored(vector, text_hash);
+        }
+        StoredEmbedding {
+            vector: Embedding(vector),
+            model: Arc::new(model),
+            text_hash,
+        }
+    }
+
+    /// Vectors of the current model, as HNSW entries.
+    ///
+    /// Mixing vector spaces would make distances meaningless.
+    fn current_entries(&self) -> Vec<(Embedding, InternedString)> {
+        self.embeddings
+            .iter()
+            .filter(|entry| entry.value().model == self.model_info)
+            .map(|entry| (entry.value().vector.clone(), *entry.key()))
+            .collect()
+    }
+
+    /// Build the HNSW index from stored embeddings.
+    fn rebuild_index(&self) -> Result<()> {
+        // Read the change number first: changes racing the snapshot are
+        // numbered after it and stay in the delta
+        let version = self.ann.read().version;
+        let (hnsw, built) = build_hnsw(self.current_entries());
+        self.ann.write().install(hnsw, built, version);
         Ok(())
     }
 
+    /// Rebuild the HNSW index on a background thread once enough vectors
+    /// changed since it was built. Searches keep using the old graph and
+    /// the delta until the new one is installed.
+    fn schedule_rebuild(&self) {
+        let version = {
+            let mut ann = self.ann.write();
+            if !ann.needs_rebuild() {
+                return;
+            }
+            ann.rebuilding = true;
+            ann.version
+        };
+        let entries = self.current_entries();
+        let ann = Arc::clone(&self.ann);
+        let spawned = std::thread::Builder::new()
+            .name("omni-hnsw".to_string())
+            .spawn(move || {
+                let (hnsw, built) = build_hnsw(entries);
+                let mut ann = ann.write();
+                ann.install(hnsw, built, version);
+                ann.rebuilding = false;
+            });
+        if let Err(e) = spawned {
+            tracing::warn!("Failed to start HNSW rebuild: {}", e);
+            self.ann.write().rebuilding = false;
+        }
+    }
+
+    /// Store the vector for `symbol` without rebuilding the HNSW index.
+    fn upsert(&self, symbol: InternedString, stored: StoredEmbedding) {
+        let vector = stored.vector.clone();
+        self.embeddings.insert(symbol, stored);
+        self.ann.write().change(symbol, Some(vector));
+    }
+
     /// Add a symbol to the index
-    pub fn add_symbol(&mut self, symbol: InternedString, text: &str) -> Result<()> {
+    pub fn add_symbol(&self, symbol: InternedString, text: &str) -> Result<()> {
         let embedding = self.embed_text(text)?;
-        self.embeddings.insert(symbol, embedding);
+        self.upsert(symbol, self.stored(embedding.0, text_hash(text)));
+        self.schedule_rebuild();
+        Ok(())
+    }
 
-        // Mark index as needing rebuild
-        // We rebuild lazily on next search for efficiency
-        *self.hnsw.write() = None;
+    /// Add many symbols at once, embedding their texts in batches of the
+    /// configured size across the session pool (see
+    /// [`EmbeddingPoolConfig`]). Much faster than one
+    /// [`add_symbol`](Self::add_symbol) per symbol; the HNSW graph is
+    /// rebuilt on the next search. Returns the number of vectors stored.
+    pub fn add_symbols_batch(&self, items: &[(InternedString, String)]) -> Result<usize> {
+        self.embed_all(items.iter().cloned())
+    }
 
+    /// Remove a symbol from the index
+    pub fn remove_symbol(&self, symbol: InternedString) -> Result<()> {
+        if self.embeddings.remove(&symbol).is_some() {
+            self.ann.write().change(symbol, None);
+            self.schedule_rebuild();
+        }
         Ok(())
     }
 
-    /// Remove a symbol from the index
-    pub fn remove_symbol(&mut self, symbol: InternedString) -> Result<()> {
-        self.embeddings.remove(&symbol);
-        self.symbol_to_idx.remove(&symbol);
+    /// Bring the vectors of `files` up to date after they were re-indexed
+    /// or removed.
+    ///
+    /// Symbols of `files` are re-embedded only when their embedding text
+    /// changed, vectors of symbols no longer in `state` are dropped, and the
+    /// summaries of the files and their directories are rebuilt. Returns the
+    /// number of symbols re-embedded.
+    pub fn sync_files(&self, state: &OciState, files: &[PathBuf]) -> Result<usize> {
+        let removed: Vec<InternedString> = self
+            .embeddings
+            .iter()
+            .map(|entry| *entry.key())
+            .filter(|symbol| !state.symbols.contains_key(symbol))
+            .collect();
+        for symbol in removed {
+            self.embeddings.remove(&symbol);
+            self.ann.write().change(symbol, None);
+        }
 
-        // Mark index as needing rebuild
-        *self.hnsw.write() = None;
+        let mut changed = Vec::new();
+        for file in files {
+            for def in file_symbols(state, file) {
+                let text = build_embedding_text(state, &def);
+                let hash = text_hash(&text);
+                let current = self.embeddings.get(&def.scoped_name).is_some_and(|stored| {
+                    stored.model == self.model_info && stored.text_hash == hash
+                });
+                if !current {
+                    changed.push(((def.scoped_name, hash), text));
+                }
+            }
+        }
+        let embedded = self.embed_batches(changed, |(symbol, hash), vector| {
+            self.upsert(symbol, self.stored(vector, hash));
+        })?;
+        self.persist(state, files);
+
+        self.sync_summaries(state, files)?;
+        self.schedule_rebuild();
+        Ok(embedded)
+    }
 
-        Ok(())
+    /// Write the vectors of the symbols of `files`, of any model, to the
+    /// on-disk cache, replacing their shards. Failures are only logged, and
+    /// nothing is written while another process owns the workspace's index
+    /// (see [`crate::shared`]).
+    fn persist(&self, state: &OciState, files: &[PathBuf]) {
+        let root = &state.root_path;
+        if crate::shared::check_writable(root).is_err() {
+            return;
+        }
+        for file in files {
+            let vectors = file_symbols(state, file)
+                .into_iter()
+                .filter_map(|def| {
+                    let stored = self.embeddings.get(&def.scoped_name)?;
+                    (stored.text_hash != 0).then(|| CachedVector {
+                        symbol: state.resolve(def.scoped_name).to_string(),
+                        text_hash: stored.text_hash,
+                        model: (*stored.model).clone(),
+                        vector: stored.vector.0.clone(),
+                    })
+                })
+                .collect();
+            let rel = relative(root, file);
+            if let Err(e) = vector_cache::save_shard(root, &rel, vectors) {
+                tracing::warn!("Failed to cache embeddings of {}: {}", rel, e);
+            }
+        }
     }
 
     /// Search for k nearest symbols to the query
     pub fn search(&self, query: &str, k: usize) -> Result<Vec<(InternedString, f32)>> {
-        // Rebuild index if needed
-        {
-            let hnsw_guard = self.hnsw.read();
-            if hnsw_guard.is_none() && !self.embeddings.is_empty() {
-                drop(hnsw_guard);
-                self.rebuild_index()?;
+        // Build the graph if there is none yet (or after bulk changes)
+        if self.ann.read().hnsw.is_none() && !self.embeddings.is_empty() {
+            self.rebuild_index()?;
+        }
+
+        let query_emb = self.embed_text(query)?;
+        Ok(self.ann.read().search(&query_emb, k))
+    }
+
+    /// (Re)build summary vectors for every indexed file and module.
+    ///
+    /// Returns the number of summaries embedded.
+    pub fn build_summaries(&self, state: &OciState) -> Result<usize> {
+        self.summaries.clear();
+        let mut files: Vec<PathBuf> = state.file_ids.iter().map(|e| e.key().clone()).collect();
+        files.sort();
+
+        let mut items = Vec::new();
+        let mut modules: BTreeMap<PathBuf, Vec<&Path>> = BTreeMap::new();
+        for file in &files {
+            items.push((
+                SummaryScope::File(file.clone()),
+                file_summary_text(state, file),
+            ));
+            if let Some(dir) = file.parent() {
+                modules.entry(dir.to_path_buf()).or_default().push(file);
             }
         }
+        for (dir, files) in &modules {
+            items.push((
+                SummaryScope::Module(dir.clone()),
+                build_module_summary(state, dir, files),
+            ));
+        }
 
-        let hnsw_guard = self.hnsw.read();
-        let hnsw = match hnsw_guard.as_ref() {
-            Some(h) => h,
-            None => return Ok(Vec::new()), // Empty index
-        };
+        self.embed_summaries(items)
+    }
+
+    /// Rebuild the summaries of `files` and their directories, dropping
+    /// those of files and directories no longer indexed.
+    fn sync_summaries(&self, state: &OciState, files: &[PathBuf]) -> Result<usize> {
+        let mut items = Vec::new();
+        let mut dirs = BTreeSet::new();
+        for file in files {
+            if state.file_ids.contains_key(file) {
+                items.push((
+                    SummaryScope::File(file.clone()),
+                    file_summary_text(state, file),
+                ));
+            } else {
+                self.summaries.remove(&SummaryScope::File(file.clone()));
+            }
+            if let Some(dir) = file.parent() {
+                dirs.insert(dir.to_path_buf());
+            }
+        }
+        for dir in dirs {
+            let mut dir_files: Vec<PathBuf> = state
+                .file_ids
+                .iter()
+                .map(|e| e.key().clone())
+                .filter(|f| f.parent() == Some(dir.as_path()))
+                .collect();
+            if dir_files.is_empty() {
+                self.summaries.remove(&SummaryScope::Module(dir));
+                continue;
+            }
+            dir_files.sort();
+            let dir_files: Vec<&Path> = dir_files.iter().map(PathBuf::as_path).collect();
+            let text = build_module_summary(state, &dir, &dir_files);
+            items.push((SummaryScope::Module(dir), text));
+        }
+        self.embed_summaries(items)
+    }
+
+    fn embed_summaries(&self, items: Vec<(SummaryScope, String)>) -> Result<usize> {
+        let items = items
+            .into_iter()
+            .map(|(scope, text)| ((scope, text_hash(&text)), text));
+        self.embed_batches(items, |(scope, hash), vector| {
+            self.summaries.insert(scope, self.stored(vector, hash));
+        })
+    }
 
-        // Generate query embedding
+    /// Rank files and modules by summary similarity to the query.
+    pub fn search_scopes(&self, query: &str, k: usize) -> Result<Vec<(SummaryScope, f32)>> {
+        if self.summaries.is_empty() {
+            return Ok(Vec::new());
+        }
         let query_emb = self.embed_text(query)?;
+        Ok(self.rank_scopes(&query_emb, k))
+    }
 
-        // Search HNSW
-        let mut search = Search::default();
-        let neighbors = hnsw.search(&query_emb, &mut search);
-
-        // Convert to results with similarity scores
-        let results: Vec<_> = neighbors
-            .take(k)
-            .map(|item| {
-                let symbol = *item.value;
-                let distance = item.distance;
-                // Convert distance to similarity (1 - distance for cosine)
-                let similarity = 1.0 - distance;
-                (symbol, similarity)
-            })
+    /// Two-stage retrieval: keep the `scope_k` best files/modules by summary,
+    /// then rank symbols inside them.
+    ///
+    /// Falls back to plain [`search`](Self::search) when there are no
+    /// summaries or no embedded symbol lies in the selected scopes.
+    pub fn coarse_to_fine(
+        &self,
+        state: &OciState,
+        query: &str,
+        scope_k: usize,
+        k: usize,
+    ) -> Result<Vec<(InternedString, f32)>> {
+        if self.summaries.is_empty() || scope_k == 0 {
+            return self.search(query, k);
+        }
+        let query_emb = self.embed_text(query)?;
+        let scopes: Vec<SummaryScope> = self
+            .rank_scopes(&query_emb, scope_k)
+            .into_iter()
+            .map(|(scope, _)| scope)
             .collect();
 
+        let mut candidates: HashSet<InternedString> = HashSet::new();
+        for entry in state.file_ids.iter() {
+            if !scopes.iter().any(|scope| scope.contains(entry.key())) {
+                continue;
+            }
+            if let Some(symbols) = state.file_symbols.get(entry.value()) {
+                candidates.extend(symbols.iter().copied());
+            }
+        }
+
+        let mut results: Vec<(InternedString, f32)> = candidates
+            .into_iter()
+            .filter_map(|symbol| {
+                let stored = self.embeddings.get(&symbol)?;
+                (stored.model == self.model_info)
+                    .then(|| (symbol, 1.0 - stored.vector.distance(&query_emb)))
+            })
+            .collect();
+        if results.is_empty() {
+            return self.search(query, k);
+        }
+        results.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| state.resolve(a.0).cmp(state.resolve(b.0)))
+        });
+        results.truncate(k);
         Ok(results)
     }
 
+    /// Brute-force ranking of summaries; there is one per file or directory,
+    /// far fewer than symbols.
+    fn rank_scopes(&self, query: &Embedding, k: usize) -> Vec<(SummaryScope, f32)> {
+        let mut scored: Vec<(SummaryScope, f32)> = self
+            .summaries
+            .iter()
+            .filter(|entry| entry.value().model == self.model_info)
+            .map(|entry| {
+                let similarity = 1.0 - entry.value().vector.distance(query);
+                (entry.key().clone(), similarity)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        scored.truncate(k);
+        scored
+    }
+
+    /// Number of file and module summaries.
+    pub fn summary_count(&self) -> usize {
+        self.summaries.len()
+    }
+
+    /// Insert a precomputed vector produced by `model` (e.g. loaded from disk).
+    pub fn insert_embedding(
+        &self,
+        symbol: InternedString,
+        vector: Vec<f32>,
+        model: EmbeddingModelInfo,
+    ) {
+        self.embeddings
+            .insert(symbol, self.stored_by(vector, 0, model));
+        self.ann.write().invalidate();
+    }
+
+    /// Model used for new embeddings.
+    pub fn model_info(&self) -> &EmbeddingModelInfo {
+        &self.model_info
+    }
+
+    /// Stored vector for `symbol`, if it was produced by the current model.
+    pub fn embedding(&self, symbol: InternedString) -> Option<Vec<f32>> {
+        let stored = self.embeddings.get(&symbol)?;
+        (stored.model == self.model_info).then(|| stored.vector.0.clone())
+    }
+
+    /// Model that produced the stored vector for `symbol`.
+    pub fn model_for(&self, symbol: InternedString) -> Option<EmbeddingModelInfo> {
+        self.embeddings
+            .get(&symbol)
+            .map(|entry| (*entry.model).clone())
+    }
+
+    /// Symbols whose vectors were produced by a different model.
+    pub fn stale_symbols(&self) -> Vec<InternedString> {
+        self.embeddings
+            .iter()
+            .filter(|entry| entry.value().model != self.model_info)
+            .map(|entry| *entry.key())
+            .collect()
+    }
+
+    /// Summarize which models produced the stored vectors.
+    pub fn drift_report(&self) -> EmbeddingDriftReport {
+        let mut vectors_by_model = BTreeMap::new();
+        let mut stale_vectors = 0;
+        for entry in self.embeddings.iter() {
+            let model = &entry.value().model;
+            *vectors_by_model.entry(model.to_string()).or_insert(0) += 1;
+            if *model != self.model_info {
+                stale_vectors += 1;
+            }
+        }
+        EmbeddingDriftReport {
+            current_model: self.model_info.to_string(),
+            vectors_by_model,
+            stale_vectors,
+        }
+    }
+
+    /// Re-embed every stale vector with the current model and rewrite the
+    /// cached shards of their files.
+    ///
+    /// Symbols no longer present in `state` are dropped. Returns the number
+    /// of vectors re-embedded.
+    pub fn reembed(&self, state: &OciState) -> Result<usize> {
+        let mut files = BTreeSet::new();
+        let stale = self.stale_symbols().into_iter().filter_map(|symbol| {
+            let Some(def) = state.get_symbol(symbol) else {
+                self.embeddings.remove(&symbol);
+                return None;
+            };
+            files.insert(def.location.file.clone());
+            Some((symbol, build_embedding_text(state, &def)))
+        });
+        let embedded = self.embed_all(stale)?;
+        self.persist(state, &files.into_iter().collect::<Vec<_>>());
+        Ok(embedded)
+    }
+
+    /// Near-duplicate pairs among the current model's vectors, found
+    /// through each vector's [`DUPLICATE_NEIGHBORS`] nearest neighbours
+    /// (see [`crate::duplication`]).
+    pub fn duplication_pressure(&self, state: &OciState) -> Result<DuplicationPressure> {
+        if self.ann.read().hnsw.is_none() && !self.embeddings.is_empty() {
+            self.rebuild_index()?;
+        }
+        let ann = self.ann.read();
+        let mut compared = 0;
+        let mut pairs = Vec::new();
+        for entry in self.embeddings.iter() {
+            if entry.value().model != self.model_info {
+                continue;
+            }
+            compared += 1;
+            let symbol = *entry.key();
+            // The nearest neighbour is the symbol itself
+            for (other, similarity) in ann.search(&entry.value().vector, DUPLICATE_NEIGHBORS + 1) {
+                if other != symbol && similarity > DUPLICATE_SIMILARITY {
+                    pairs.push((
+                        state.resolve(symbol).to_string(),
+                        state.resolve(other).to_string(),
+                        similarity,
+                    ));
+                }
+            }
+        }
+        Ok(DuplicationPressure::from_pairs(compared, &pairs))
+    }
+
     /// Get the number of indexed symbols
     pub fn len(&self) -> usize {
         self.embeddings.len()
@@ -177,26 +888,418 @@ impl SemanticIndex {
 
 /// Build a semantic index from the current state
 pub fn build_index(state: &OciState) -> Result<SemanticIndex> {
-    let mut index = SemanticIndex::new()?;
-
-    // Iterate over all symbols and build embeddings
-    for entry in state.symbols.iter() {
-        let symbol_def = entry.value();
-        let scoped_name = symbol_def.scoped_name;
+    build_index_with(state, EmbeddingPoolConfig::default())
+}
 
-        // Build embedding text from symbol information
-        let embedding_text = build_embedding_text(state, symbol_def);
+/// Build a semantic index from the current state with a sized session pool
+pub fn build_index_with(state: &OciState, config: EmbeddingPoolConfig) -> Result<SemanticIndex> {
+    build_index_with_progress(state, config, |_, _| {})
+}
 
-        // Add to index
-        index.add_symbol(scoped_name, &embedding_text)?;
+/// [`build_index_with`], calling `on_progress(done, total)` after every
+/// batch of symbols embedded or taken from the embedding cache.
+pub fn build_index_with_progress(
+    state: &OciState,
+    config: EmbeddingPoolConfig,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<SemanticIndex> {
+    let index = SemanticIndex::with_pool(config)?;
+
+    // Snapshot the keys so symbol table shards are not locked while the
+    // producer waits on the embedding workers. Symbols in focus paths
+    // (see `crate::focus`) go first.
+    let focus = Focus::load(&state.root_path).unwrap_or_default();
+    let mut symbols: Vec<(bool, InternedString)> = state
+        .symbols
+        .iter()
+        .map(|e| {
+            let file = e.value().location.file.as_path();
+            let rel = file.strip_prefix(&state.root_path).unwrap_or(file);
+            (focus.contains(&rel.to_string_lossy()), *e.key())
+        })
+        .collect();
+    symbols.sort_by_key(|(focused, _)| !focused);
+    let total = symbols.len();
+    let symbols = symbols.into_iter().map(|(_, scoped_name)| scoped_name);
+
+    // Vectors cached by an earlier run are reused while the symbol's text
+    // is unchanged, even if an older model produced them: that drift is
+    // reported until `reembed` migrates them. Files with a symbol to embed
+    // get their shard rewritten.
+    let root = &state.root_path;
+    let mut cache = VectorCache::load(root);
+    let mut dirty = BTreeSet::new();
+    let mut cached = 0;
+    let mut done = 0;
+    let embedded = index.embed_all(symbols.filter_map(|scoped_name| {
+        // The producer waits on the workers, so this trails embedding by
+        // about a batch per session
+        done += 1;
+        if done % config.batch_size.max(1) == 0 {
+            on_progress(done, total);
+        }
+        let symbol_def = state.get_symbol(scoped_name)?;
+        let text = build_embedding_text(state, &symbol_def);
+        let hash = text_hash(&text);
+        if let Some((vector, model)) = cache.take(state.resolve(scoped_name), hash) {
+            index
+                .embeddings
+                .insert(scoped_name, index.stored_by(vector, hash, model));
+            cached += 1;
+            return None;
+        }
+        dirty.insert(symbol_def.location.file.clone());
+        Some((scoped_name, text))
+    }))?;
+    on_progress(total, total);
+    tracing::info!(
+        "Embedded {} symbols, {} reused from the embedding cache",
+        embedded,
+        cached
+    );
+    let live: HashSet<String> = state
+        .file_ids
+        .iter()
+        .map(|entry| relative(root, entry.key()))
+        .collect();
+    dirty.extend(
+        cache
+            .leftover_files()
+            .into_iter()
+            .filter(|file| live.contains(file))
+            .map(|file| root.join(file)),
+    );
+    index.persist(state, &dirty.into_iter().collect::<Vec<_>>());
+    if crate::shared::check_writable(root).is_ok()
+        && let Err(e) = vector_cache::prune(root, &live)
+    {
+        tracing::warn!("Failed to prune the embedding cache: {}", e);
     }
 
     // Build the HNSW index
     index.rebuild_index()?;
+    index.build_summaries(state)?;
+
+    // Cached for `omni stats` and the index status
+    if crate::shared::check_writable(root).is_ok() {
+        let saved = index
+            .duplication_pressure(state)
+            .and_then(|pressure| pressure.save(root));
+        if let Err(e) = saved {
+            tracing::warn!("Failed to cache duplication pressure: {}", e);
+        }
+    }
 
     Ok(index)
 }
 
+/// Which models produced the vectors cached in `.omni/embeddings/` under
+/// `root`, for reporting drift without loading the semantic index. `None`
+/// if nothing is cached.
+pub fn cached_drift(root: &Path) -> Option<EmbeddingDriftReport> {
+    vector_cache::drift(root, &EmbeddingModelInfo::new(MODEL_NAME, MODEL_VERSION))
+}
+
+/// Retry schedule of [`spawn_loader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoaderConfig {
+    pub pool: EmbeddingPoolConfig,
+    /// Delay before the first retry after the model failed to load; doubled
+    /// after every further failure
+    pub retry_after: Duration,
+    /// Upper bound on the retry delay
+    pub max_retry_after: Duration,
+    /// How often to check whether the state has symbols to embed yet
+    pub idle_poll: Duration,
+}
+
+impl Default for LoaderConfig {
+    fn default() -> Self {
+        Self {
+            pool: EmbeddingPoolConfig::default(),
+            retry_after: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(600),
+            idle_poll: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Build the semantic index for `state` and install it, tracking progress
+/// in [`OciState::semantic_status`].
+///
+/// Returns `Ok(false)` without building if another load is in progress. On
+/// failure the status becomes [`SemanticStatus::Unavailable`] and search
+/// keeps working on BM25 alone.
+pub fn load(state: &OciState, pool: EmbeddingPoolConfig) -> Result<bool> {
+    load_with_progress(state, pool, |_, _| {})
+}
+
+/// [`load`], reporting embedding progress as
+/// [`build_index_with_progress`] does.
+pub fn load_with_progress(
+    state: &OciState,
+    pool: EmbeddingPoolConfig,
+    on_progress: impl FnMut(usize, usize),
+) -> Result<bool> {
+    {
+        let mut status = state.semantic_status.write();
+        if *status == SemanticStatus::Building {
+            return Ok(false);
+        }
+        *status = SemanticStatus::Building;
+    }
+    match build_index_with_progress(state, pool, on_progress) {
+        Ok(index) => {
+            // An index installed earlier is just as current
+            let _ = state.semantic_index.set(index);
+            *state.semantic_status.write() = SemanticStatus::Ready;
+            Ok(true)
+        }
+        Err(e) => {
+            *state.semantic_status.write() = SemanticStatus::Unavailable {
+                reason: e.to_string(),
+            };
+            Err(e)
+        }
+    }
+}
+
+/// [`load`] as the [`IndexPhase::Embed`] phase of an index run, emitting
+/// its events through `on_event` with symbols as the work items. Returns
+/// the phase's wall time.
+pub fn load_as_phase(
+    state: &OciState,
+    pool: EmbeddingPoolConfig,
+    mut on_event: impl FnMut(&IndexEvent),
+) -> Result<Duration> {
+    let started = Instant::now();
+    let phase = IndexPhase::Embed;
+    on_event(&IndexEvent::PhaseStarted {
+        phase,
+        total: state.symbols.len(),
+    });
+    let loaded = load_with_progress(state, pool, |done, total| {
+        on_event(&IndexEvent::Progress {
+            phase,
+            done,
+            total,
+            file: String::new(),
+        })
+    });
+    let elapsed = started.elapsed();
+    on_event(&IndexEvent::PhaseFinished {
+        phase,
+        elapsed_ms: elapsed.as_millis() as u64,
+    });
+    loaded.map(|_| elapsed)
+}
+
+/// Build the semantic index on a background thread once `state` has
+/// symbols, retrying with backoff while the embedding model cannot be
+/// loaded (e.g. offline before it was downloaded). Each attempt also
+/// retries the download, so full mode comes back once the model is present.
+pub fn spawn_loader(state: SharedState, config: LoaderConfig) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("omni-semantic".to_string())
+        .spawn(move || {
+            let mut delay = config.retry_after;
+            while state.semantic_index.get().is_none() {
+                if state.symbols.is_empty() {
+                    std::thread::sleep(config.idle_poll);
+                    continue;
+                }
+                match load(&state, config.pool) {
+                    Ok(true) => tracing::info!("Semantic index ready"),
+                    // Another load (e.g. a first search) is in progress
+                    Ok(false) => std::thread::sleep(config.idle_poll),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Semantic search unavailable, using BM25 only (retrying in {}s): {}",
+                            delay.as_secs(),
+                            e
+                        );
+                        std::thread::sleep(delay);
+                        delay = (delay * 2).min(config.max_retry_after);
+                    }
+                }
+            }
+        })
+}
+
+/// Hash of an embedding text, to skip re-embedding unchanged symbols.
+fn text_hash(text: &str) -> u64 {
+    crate::cache::content_hash(text.as_bytes())
+}
+
+/// `file` relative to `root`, as shards name it.
+fn relative(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The symbols currently defined in `file`.
+fn file_symbols(state: &OciState, file: &Path) -> Vec<SymbolDef> {
+    let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+        return Vec::new();
+    };
+    let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+        return Vec::new();
+    };
+    scoped_names
+        .into_iter()
+        .filter_map(|scoped| state.get_symbol(scoped))
+        .filter(|s| s.location.file == file)
+        .collect()
+}
+
+/// The symbols that best describe a file: public before private, types
+/// before functions, then in source order.
+fn top_symbols(state: &OciState, file: &Path) -> Vec<SymbolDef> {
+    let mut symbols: Vec<SymbolDef> = file_symbols(state, file)
+        .into_iter()
+        .filter(|s| {
+            !matches!(
+                s.kind,
+                SymbolKind::Impl | SymbolKind::Field | SymbolKind::Variant
+            )
+        })
+        .collect();
+    let rank = |s: &SymbolDef| {
+        let is_type = matches!(
+            s.kind,
+            SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Trait | SymbolKind::TypeAlias
+        );
+        (
+            s.visibility != Visibility::Public,
+            !is_type,
+            s.location.start_byte,
+        )
+    };
+    symbols.sort_by_key(rank);
+    symbols.truncate(SUMMARY_SYMBOLS);
+    symbols
+}
+
+/// Leading comment lines of a file (module docs, license headers aside).
+fn header_doc(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        let Some(text) = ["//!", "///", "//", "/**", "/*", "*/", "*", "#"]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        else {
+            break;
+        };
+        let text = text.trim().trim_end_matches("*/").trim();
+        let lower = text.to_ascii_lowercase();
+        if text.is_empty()
+            || text.starts_with('[')
+            || text.starts_with('!')
+            || lower.contains("copyright")
+            || lower.contains("license")
+        {
+            continue;
+        }
+        lines.push(text.to_string());
+        if lines.len() == SUMMARY_HEADER_LINES {
+            break;
+        }
+    }
+    lines
+}
+
+/// First sentence of a doc comment.
+fn first_sentence(doc: &str) -> &str {
+    let line = doc
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    line.split_once(". ").map_or(line, |(first, _)| first)
+}
+
+/// Summary text for an indexed file, from its header docs and top symbols.
+fn file_summary_text(state: &OciState, file: &Path) -> String {
+    let symbols = top_symbols(state, file);
+    let header = std::fs::read_to_string(file)
+        .map(|source| header_doc(&source))
+        .unwrap_or_default();
+    build_file_summary(state, file, &header, &symbols)
+}
+
+/// Build the summary text for a file
+fn build_file_summary(
+    state: &OciState,
+    file: &Path,
+    header: &[String],
+    symbols: &[SymbolDef],
+) -> String {
+    let rel = file.strip_prefix(&state.root_path).unwrap_or(file);
+    let mut parts = vec![format!("File: {}", rel.display())];
+    let role = state.file_role(file);
+    parts.push(format!("Role: {}", role.as_str()));
+    if !header.is_empty() {
+        parts.push(format!("Documentation: {}", header.join(" ")));
+    }
+    if !symbols.is_empty() {
+        let listed: Vec<String> = symbols
+            .iter()
+            .map(|s| {
+                let name = state.resolve(s.name);
+                match s.doc_comment.as_deref().map(first_sentence) {
+                    Some(doc) if !doc.is_empty() => {
+                        format!("{} {} ({})", s.kind.as_str(), name, doc)
+                    }
+                    _ => format!("{} {}", s.kind.as_str(), name),
+                }
+            })
+            .collect();
+        parts.push(format!("Symbols: {}", listed.join("; ")));
+    }
+    parts.join("\n")
+}
+
+/// Build the summary text for a directory from its files' top symbols
+fn build_module_summary(state: &OciState, dir: &Path, files: &[&Path]) -> String {
+    let rel = dir.strip_prefix(&state.root_path).unwrap_or(dir);
+    let mut parts = vec![format!("Module: {}", rel.display())];
+    let names: Vec<String> = files
+        .iter()
+        .take(SUMMARY_MODULE_FILES)
+        .filter_map(|f| f.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect();
+    parts.push(format!("Files: {}", names.join(", ")));
+
+    let mut symbols = BTreeSet::new();
+    for file in files {
+        for symbol in top_symbols(state, file)
+            .iter()
+            .filter(|s| s.visibility == Visibility::Public)
+        {
+            if symbols.len() == SUMMARY_SYMBOLS {
+                break;
+            }
+            symbols.insert(state.resolve(symbol.name).to_string());
+        }
+    }
+    if !symbols.is_empty() {
+        parts.push(format!(
+            "Symbols: {}",
+            symbols.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    parts.join("\n")
+}
+
 /// Build the embedding text for a symbol
 fn build_embedding_text(state: &OciState, symbol: &crate::types::SymbolDef) -> String {
     let mut parts = Vec::new();
@@ -259,6 +1362,21 @@ fn build_embedding_text(state: &OciState, symbol: &crate::types::SymbolDef) -> S
     parts.join("\n")
 }
 
+/// Build the embedding text for a function that does not exist yet, laid
+/// out like [`build_embedding_text`] so it lands near existing functions
+/// that do the same.
+pub fn proposal_embedding_text(name: &str, signature: &str, doc: Option<&str>) -> String {
+    let mut parts = vec![
+        format!("Symbol: {}", name),
+        format!("Kind: {}", crate::types::SymbolKind::Function.as_str()),
+    ];
+    if let Some(doc) = doc.map(str::trim).filter(|doc| !doc.is_empty()) {
+        parts.push(format!("Documentation: {}", doc));
+    }
+    parts.push(format!("Signature: {}", signature.trim()));
+    parts.join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,7 +1385,7 @@ mod tests {
 
     #[test]
     fn test_semantic_index_basic() -> Result<()> {
-        let mut index = match SemanticIndex::new() {
+        let index = match SemanticIndex::new() {
             Ok(index) => index,
             Err(err) => {
                 eprintln!("Skipping semantic test: {err}");
@@ -295,7 +1413,7 @@ mod tests {
 
     #[test]
     fn test_add_remove_symbol() -> Result<()> {
-        let mut index = match SemanticIndex::new() {
+        let index = match SemanticIndex::new() {
             Ok(index) => index,
             Err(err) => {
                 eprintln!("Skipping semantic test: {err}");
@@ -314,6 +1432,246 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_ann_searches_changes_until_rebuilt() {
+        let state = OciState::new(PathBuf::from("/test"));
+        let [a, b, c] = ["a", "b", "c"].map(|name| state.intern(name));
+        let unit = |i: usize| {
+            let mut v = vec![0.0; 3];
+            v[i] = 1.0;
+            Embedding(v)
+        };
+
+        let mut ann = Ann::default();
+        ann.change(a, Some(unit(0)));
+        ann.change(b, Some(unit(1)));
+        let version = ann.version;
+        let (hnsw, built) = build_hnsw(vec![(unit(0), a), (unit(1), b)]);
+        ann.install(hnsw, built, version);
+        assert!(ann.delta.is_empty() && ann.outdated.is_empty());
+
+        // b moves next to c's axis, a is removed, c is new
+        ann.change(b, Some(unit(2)));
+        ann.change(a, None);
+        ann.change(c, Some(unit(2)));
+        let near_a: Vec<_> = ann.search(&unit(0), 3).into_iter().map(|r| r.0).collect();
+        assert!(!near_a.contains(&a));
+        let nearest = ann.search(&unit(2), 2);
+        assert!(
+            nearest
+                .iter()
+                .all(|(s, sim)| (*s == b || *s == c) && *sim > 0.99)
+        );
+
+        // A graph from before the changes keeps them in the delta
+        ann.install(build_hnsw(vec![(unit(1), b)]).0, 1, version);
+        assert_eq!(ann.delta.len(), 2);
+        // One built before an invalidation is discarded
+        ann.invalidate();
+        ann.install(build_hnsw(vec![(unit(2), b)]).0, 1, version);
+        assert!(ann.hnsw.is_none());
+    }
+
+    #[test]
+    fn test_drift_report_and_reembed() -> Result<()> {
+        let index = match SemanticIndex::new() {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Skipping semantic test: {err}");
+                return Ok(());
+            }
+        };
+        let state = OciState::new(PathBuf::from("/test"));
+        let orphan = state.intern("gone::symbol");
+
+        index.insert_embedding(orphan, vec![0.1; 384], EmbeddingModelInfo::new("old", "1"));
+        let report = index.drift_report();
+        assert!(report.has_drift());
+        assert_eq!(report.vectors_by_model.get("old@1"), Some(&1));
+
+        // Orphaned stale vectors are dropped rather than re-embedded.
+        assert_eq!(index.reembed(&state)?, 0);
+        assert!(!index.drift_report().has_drift());
+        assert!(index.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drift_from_old_model_shard() -> Result<()> {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::write(root.join("lib.rs"), "pub fn login() {}\n").unwrap();
+        let state = crate::state::create_state(root.clone());
+        crate::IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await?;
+
+        // An older model embedded the symbol's current text
+        let old = EmbeddingModelInfo::new(MODEL_NAME, "old");
+        let def = state.find_by_name("login").remove(0);
+        let text = build_embedding_text(&state, &def);
+        vector_cache::save_shard(
+            &root,
+            "lib.rs",
+            vec![CachedVector {
+                symbol: state.resolve(def.scoped_name).to_string(),
+                text_hash: text_hash(&text),
+                model: old.clone(),
+                vector: vec![0.1; 384],
+            }],
+        )?;
+        assert_eq!(cached_drift(&root).unwrap().stale_vectors, 1);
+        // Reported by stats before the semantic index is loaded
+        assert_eq!(state.stats().embedding_drift.unwrap().stale_vectors, 1);
+
+        let index = match build_index(&state) {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Skipping semantic test: {err}");
+                return Ok(());
+            }
+        };
+        // The old vector is kept, tagged with its model
+        assert_eq!(index.drift_report().stale_vectors, 1);
+        assert_eq!(index.model_for(def.scoped_name), Some(old));
+
+        // Re-embedding migrates it and rewrites the shard
+        assert_eq!(index.reembed(&state)?, 1);
+        assert!(!index.drift_report().has_drift());
+        assert!(!cached_drift(&root).unwrap().has_drift());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pooled_batch_embedding() -> Result<()> {
+        let config = EmbeddingPoolConfig::default()
+            .with_sessions(2)
+            .with_batch_size(3);
+        let index = match SemanticIndex::with_pool(config) {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Skipping semantic test: {err}");
+                return Ok(());
+            }
+        };
+        assert_eq!(index.pool_size(), 2);
+
+        let state = OciState::new(PathBuf::from("/test"));
+        let items: Vec<_> = (0..10)
+            .map(|i| {
+                (
+                    state.intern(&format!("f{i}")),
+                    format!("function number {i}"),
+                )
+            })
+            .collect();
+        assert_eq!(index.embed_all(items)?, 10);
+        assert_eq!(index.len(), 10);
+        assert_eq!(index.search("function number 7", 1)?.len(), 1);
+
+        let more: Vec<_> = (10..14)
+            .map(|i| {
+                (
+                    state.intern(&format!("f{i}")),
+                    format!("function number {i}"),
+                )
+            })
+            .collect();
+        assert_eq!(index.add_symbols_batch(&more)?, 4);
+        assert_eq!(index.len(), 14);
+        assert_eq!(index.search("function number 12", 1)?[0].0, more[2].0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_coarse_to_fine_narrows_to_best_file() -> Result<()> {
+        let temp = tempfile::tempdir().unwrap();
+        let auth = temp.path().join("auth");
+        let render = temp.path().join("render");
+        std::fs::create_dir_all(&auth).unwrap();
+        std::fs::create_dir_all(&render).unwrap();
+        std::fs::write(
+            auth.join("session.rs"),
+            "//! User login, password checks, and session tokens.\n\n/// Verify a password and issue a session token.\npub fn login() {}\npub fn logout() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            render.join("canvas.rs"),
+            "//! Drawing shapes onto a pixel canvas.\n\npub fn draw_circle() {}\npub fn draw_line() {}\n",
+        )
+        .unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await?;
+
+        let index = match build_index(&state) {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Skipping semantic test: {err}");
+                return Ok(());
+            }
+        };
+        // Two files plus two directories
+        assert_eq!(index.summary_count(), 4);
+
+        let scopes = index.search_scopes("which module handles authentication", 1)?;
+        assert!(scopes[0].0.path().starts_with(&auth));
+
+        let results = index.coarse_to_fine(&state, "which module handles authentication", 1, 5)?;
+        assert!(!results.is_empty());
+        for (symbol, _) in results {
+            let file = state.get_symbol(symbol).unwrap().location.file;
+            assert!(file.starts_with(&auth));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_reports_degraded_mode_without_model() -> Result<()> {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("lib.rs"), "pub fn login() {}\n").unwrap();
+        let state = crate::state::create_state(temp.path().to_path_buf());
+        crate::IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await?;
+        assert_eq!(*state.semantic_status.read(), SemanticStatus::NotBuilt);
+
+        // Either outcome is valid here; the status must agree with it
+        match load(&state, EmbeddingPoolConfig::default().with_sessions(1)) {
+            Ok(loaded) => {
+                assert!(loaded);
+                assert!(state.stats().semantic_status.is_ready());
+                assert!(state.semantic_index.get().is_some());
+            }
+            Err(_) => {
+                let status = state.stats().semantic_status;
+                assert_eq!(status.as_str(), "unavailable (model missing)");
+                assert!(state.semantic_index.get().is_none());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_doc_and_scope() {
+        let source = "// Copyright 2024 Example\n//! Handles auth.\n//! Issues tokens.\n\n//! not a header\nfn f() {}\n";
+        assert_eq!(header_doc(source), vec!["Handles auth.", "Issues tokens."]);
+        assert!(header_doc("fn f() {}\n").is_empty());
+
+        let module = SummaryScope::Module(PathBuf::from("/repo/src/auth"));
+        assert!(module.contains(Path::new("/repo/src/auth/login.rs")));
+        assert!(!module.contains(Path::new("/repo/src/render.rs")));
+        assert!(SummaryScope::File(PathBuf::from("/repo/a.rs")).contains(Path::new("/repo/a.rs")));
+        assert_eq!(
+            first_sentence("Verify a password. Then issue."),
+            "Verify a password"
+        );
+    }
+
     #[test]
     fn test_build_embedding_text() {
         let state = OciState::new(PathBuf::from("/test/project"));
@@ -325,6 +1683,7 @@ mod tests {
             scoped_name: scoped,
             kind: SymbolKind::Function,
             location: Location::new(PathBuf::from("/test/project/src/lib.rs"), 0, 100),
+            extended_location: None,
             signature: Some(crate::types::Signature {
                 params: vec!["x: i32".to_string(), "y: i32".to_string()],
                 return_type: Some("i32".to_string()),
@@ -338,6 +1697,9 @@ mod tests {
             attributes: vec![],
             doc_comment: Some("Adds two numbers together".to_string()),
             parent: None,
+            is_test_context: false,
+            ignores: Vec::new(),
+            raw_name: None,
         };
 
         let text = build_embedding_text(&state, &symbol);
@@ -0,0 +1,237 @@
+//! On-disk cache of symbol vectors.
+//!
+//! Embedding every symbol is most of the cost of building the semantic
+//! index. Vectors are kept under `.omni/embeddings/`, one shard per source
+//! file, keyed by scoped name and the hash of the symbol's embedding text
+//! and tagged with the model that produced them. A build takes every vector
+//! whose key still matches from the shards and embeds only the rest.
+//! Vectors of an older model are kept as they are, so the index reports the
+//! drift until `omni reembed` migrates them.
+
+use crate::cache::{content_hash, embeddings_dir, ensure_cache_dir};
+use crate::error::{OmniError, Result};
+use crate::types::{EmbeddingDriftReport, EmbeddingModelInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A cached vector of one symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct CachedVector {
+    /// Scoped name of the symbol
+    pub symbol: String,
+    /// Hash of the text the vector was embedded from
+    pub text_hash: u64,
+    /// Model that produced the vector
+    pub model: EmbeddingModelInfo,
+    pub vector: Vec<f32>,
+}
+
+/// The vectors of one source file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Shard {
+    /// Root-relative file the symbols are defined in
+    file: String,
+    vectors: Vec<CachedVector>,
+}
+
+/// Vectors loaded from every shard, of any model.
+#[derive(Debug, Default)]
+pub(super) struct VectorCache {
+    /// (scoped name, text hash) -> vector, its model, and the file of its
+    /// shard
+    vectors: HashMap<(String, u64), (Vec<f32>, EmbeddingModelInfo, String)>,
+}
+
+impl VectorCache {
+    /// Load the shards under `root`. Unreadable shards are skipped, since
+    /// they are only a cache.
+    pub fn load(root: &Path) -> Self {
+        let mut cache = Self::default();
+        for shard in shards(root) {
+            for cached in shard.vectors {
+                cache.vectors.insert(
+                    (cached.symbol, cached.text_hash),
+                    (cached.vector, cached.model, shard.file.clone()),
+                );
+            }
+        }
+        cache
+    }
+
+    /// Take the vector of `symbol`, and the model that produced it, if it
+    /// was embedded from text hashing to `text_hash`.
+    pub fn take(&mut self, symbol: &str, text_hash: u64) -> Option<(Vec<f32>, EmbeddingModelInfo)> {
+        self.vectors
+            .remove(&(symbol.to_string(), text_hash))
+            .map(|(vector, model, _)| (vector, model))
+    }
+
+    /// Files whose shards still hold vectors not taken, i.e. of symbols
+    /// since changed or removed.
+    pub fn leftover_files(&self) -> HashSet<String> {
+        self.vectors
+            .values()
+            .map(|(_, _, file)| file.clone())
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+}
+
+/// Which models produced the vectors cached under `root`, relative to
+/// `current`, without loading the semantic index. `None` if nothing is
+/// cached.
+pub(super) fn drift(root: &Path, current: &EmbeddingModelInfo) -> Option<EmbeddingDriftReport> {
+    let mut vectors_by_model = BTreeMap::new();
+    let mut stale_vectors = 0;
+    for shard in shards(root) {
+        for cached in &shard.vectors {
+            *vectors_by_model
+                .entry(cached.model.to_string())
+                .or_insert(0) += 1;
+            if cached.model != *current {
+                stale_vectors += 1;
+            }
+        }
+    }
+    (!vectors_by_model.is_empty()).then(|| EmbeddingDriftReport {
+        current_model: current.to_string(),
+        vectors_by_model,
+        stale_vectors,
+    })
+}
+
+/// Replace the shard of `file` (root-relative) with `vectors`, or remove it
+/// when there are none.
+pub(super) fn save_shard(root: &Path, file: &str, vectors: Vec<CachedVector>) -> Result<()> {
+    let path = shard_path(root, file);
+    if vectors.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(OmniError::io(&path, e)),
+            _ => Ok(()),
+        };
+    }
+    ensure_cache_dir(root)?;
+    let dir = embeddings_dir(root);
+    fs::create_dir_all(&dir).map_err(|e| OmniError::io(&dir, e))?;
+    let shard = Shard {
+        file: file.to_string(),
+        vectors,
+    };
+    let data = bincode::serialize(&shard).map_err(|e| OmniError::corrupt(&path, e))?;
+    fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+}
+
+/// Remove the shards of files other than `files` (root-relative).
+pub(super) fn prune(root: &Path, files: &HashSet<String>) -> Result<()> {
+    let dir = embeddings_dir(root);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(());
+    };
+    let keep: HashSet<PathBuf> = files.iter().map(|file| shard_path(root, file)).collect();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !keep.contains(&path) {
+            fs::remove_file(&path).map_err(|e| OmniError::io(&path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Every readable shard under `root`.
+fn shards(root: &Path) -> impl Iterator<Item = Shard> {
+    fs::read_dir(embeddings_dir(root))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let data = fs::read(entry.path()).ok()?;
+            bincode::deserialize::<Shard>(&data).ok()
+        })
+}
+
+fn shard_path(root: &Path, file: &str) -> PathBuf {
+    embeddings_dir(root).join(format!("{:016x}.bin", content_hash(file.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(symbol: &str, text_hash: u64, model: &EmbeddingModelInfo) -> CachedVector {
+        CachedVector {
+            symbol: symbol.to_string(),
+            text_hash,
+            model: model.clone(),
+            vector: vec![0.5; 4],
+        }
+    }
+
+    #[test]
+    fn test_shards_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let model = EmbeddingModelInfo::new("model", "1");
+        save_shard(
+            root,
+            "src/auth.rs",
+            vec![
+                cached("crate::login", 1, &model),
+                cached("crate::logout", 2, &model),
+            ],
+        )
+        .unwrap();
+        save_shard(root, "src/draw.rs", vec![cached("crate::draw", 3, &model)]).unwrap();
+
+        let mut cache = VectorCache::load(root);
+        assert_eq!(cache.len(), 3);
+        // Changed text: not a hit
+        assert!(cache.take("crate::login", 9).is_none());
+        assert_eq!(
+            cache.take("crate::login", 1),
+            Some((vec![0.5; 4], model.clone()))
+        );
+        assert!(cache.take("crate::draw", 3).is_some());
+        assert_eq!(
+            cache.leftover_files(),
+            HashSet::from(["src/auth.rs".to_string()])
+        );
+
+        prune(root, &HashSet::from(["src/auth.rs".to_string()])).unwrap();
+        assert_eq!(VectorCache::load(root).len(), 2);
+        save_shard(root, "src/auth.rs", Vec::new()).unwrap();
+        assert_eq!(VectorCache::load(root).len(), 0);
+    }
+
+    #[test]
+    fn test_drift_of_cached_vectors() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let old = EmbeddingModelInfo::new("model", "1");
+        let current = EmbeddingModelInfo::new("model", "2");
+        assert!(drift(root, &current).is_none());
+
+        save_shard(
+            root,
+            "src/auth.rs",
+            vec![
+                cached("crate::login", 1, &old),
+                cached("crate::logout", 2, &current),
+            ],
+        )
+        .unwrap();
+        let report = drift(root, &current).unwrap();
+        assert!(report.has_drift());
+        assert_eq!(report.stale_vectors, 1);
+        assert_eq!(report.vectors_by_model.get("model@1"), Some(&1));
+
+        // Old vectors are loaded with their model
+        let mut cache = VectorCache::load(root);
+        assert_eq!(cache.take("crate::login", 1), Some((vec![0.5; 4], old)));
+    }
+}
@@ -0,0 +1,184 @@
+//! One index shared between the MCP server and CLI invocations.
+//!
+//! By default every process builds or restores its own copy of the index,
+//! and CLI runs next to a long-running server can disagree with it. In
+//! shared mode the server is the only writer: started with
+//! `OMNI_SHARED_INDEX=1`, it takes the lockfile `.omni/writer.lock` (holding
+//! its pid) for as long as it runs and publishes its index as the segment
+//! file `.omni/oci.bin`. Segments are written aside and renamed into place
+//! (see [`OciState::save_snapshot`]), never modified in place.
+//!
+//! CLI commands run with `--shared` then [`attach`] to the current segment:
+//! they map it read-only and restore from it without parsing anything or
+//! comparing fingerprints, since the server keeps it current. While a live
+//! process holds the lock, no other process writes the cache:
+//! [`check_writable`] fails with [`OmniError::IndexLocked`] for `omni index`,
+//! and snapshots are only saved by the writer.
+//!
+//! A lock left behind by a process that is no longer running is taken over.
+//! Whether the holder still runs is checked through `/proc` on Linux; on
+//! other platforms a stale lock has to be removed by hand.
+
+use crate::cache::{ensure_cache_dir, oci_path, writer_lock_path};
+use crate::error::{OmniError, Result};
+use crate::state::OciState;
+use memmap2::Mmap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// The writer lock of a workspace, released on drop.
+#[derive(Debug)]
+pub struct WriterLock {
+    path: PathBuf,
+}
+
+impl WriterLock {
+    /// Take the writer lock of `root`, replacing a stale one. Returns `None`
+    /// if another live process holds it.
+    pub fn acquire(root: &Path) -> Result<Option<Self>> {
+        ensure_cache_dir(root)?;
+        let path = writer_lock_path(root);
+        for _ in 0..2 {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id()).map_err(|e| OmniError::io(&path, e))?;
+                    return Ok(Some(Self { path }));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if writer(root).is_some() {
+                        return Ok(None);
+                    }
+                    tracing::info!("Replacing stale writer lock {}", path.display());
+                    match fs::remove_file(&path) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == ErrorKind::NotFound => {}
+                        Err(e) => return Err(OmniError::io(&path, e)),
+                    }
+                }
+                Err(e) => return Err(OmniError::io(&path, e)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for WriterLock {
+    fn drop(&mut self) {
+        // Only remove the lock if it is still ours
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Pid of the live process holding the writer lock of `root`, if any.
+pub fn writer(root: &Path) -> Option<u32> {
+    read_pid(&writer_lock_path(root)).filter(|&pid| is_running(pid))
+}
+
+/// Whether this process holds the writer lock of `root`.
+pub fn is_writer(root: &Path) -> bool {
+    writer(root) == Some(std::process::id())
+}
+
+/// Fail with [`OmniError::IndexLocked`] if another live process holds the
+/// writer lock of `root`.
+pub fn check_writable(root: &Path) -> Result<()> {
+    match writer(root) {
+        Some(pid) if pid != std::process::id() => Err(OmniError::IndexLocked(pid)),
+        _ => Ok(()),
+    }
+}
+
+/// Restore `state` from the segment published by the writer of `root`.
+///
+/// Returns `Ok(false)` without touching the state if no live process holds
+/// the writer lock, or it has not published a segment of this format and
+/// tool version yet. Like [`OciState::load_snapshot`], the state must be
+/// fresh.
+pub fn attach(state: &OciState, root: &Path) -> Result<bool> {
+    if writer(root).is_none() {
+        return Ok(false);
+    }
+    let path = oci_path(root);
+    let file = match fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(OmniError::io(&path, e)),
+    };
+    // SAFETY: the writer replaces segments by renaming a new file over them
+    // and never modifies one in place, so the mapped file stays unchanged
+    // while it is read.
+    let map = unsafe { Mmap::map(&file) }.map_err(|e| OmniError::io(&path, e))?;
+    state.load_snapshot_from(&map[..], &path, None)
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_readers_attach_to_the_writer_segment() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn served() {}\n").unwrap();
+
+        // Nobody writes yet: nothing to attach to
+        let reader = create_state(root.to_path_buf());
+        assert!(!attach(&reader, root).unwrap());
+
+        let lock = WriterLock::acquire(root).unwrap().unwrap();
+        assert!(is_writer(root));
+        assert!(WriterLock::acquire(root).unwrap().is_none());
+        let writer_state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index_cached(&writer_state, root)
+            .await
+            .unwrap();
+
+        // The source changes, but the reader takes the writer's view as is
+        std::fs::write(root.join("src/lib.rs"), "pub fn edited() {}\n").unwrap();
+        assert!(attach(&reader, root).unwrap());
+        assert!(reader.lookup("crate::served").is_some());
+
+        drop(lock);
+        assert!(writer(root).is_none());
+        assert!(check_writable(root).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        ensure_cache_dir(root).unwrap();
+        // No process runs under the largest pid
+        fs::write(writer_lock_path(root), u32::MAX.to_string()).unwrap();
+        assert!(check_writable(root).is_ok());
+        let lock = WriterLock::acquire(root).unwrap();
+        assert!(lock.is_some());
+        assert!(is_writer(root));
+    }
+}
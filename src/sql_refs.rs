@@ -0,0 +1,439 @@
+//! The current SQL schema and the code querying it.
+//!
+//! [`current_schema`] replays the schema changes of every SQL file (see
+//! [`crate::parsing::sql`]) in path order, which is the order migration
+//! tools apply `migrations/0001_init.sql`, `migrations/0002_...`. Dropped
+//! columns and tables disappear and renames take effect, so the result is
+//! the schema a new query runs against.
+//!
+//! [`find_sql_refs`] answers "what are the columns of `users`, and which
+//! code queries it?": string literals in indexed source files that read as
+//! SQL (they contain `SELECT`, `INSERT`, `UPDATE`, `DELETE`, ...) and name
+//! the table, or for `users.email` the table and the column, as whole words.
+
+use crate::parsing::sql::{SchemaChange, extract_schema_changes, is_sql_file};
+use crate::state::OciState;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Query text kept per reference.
+const MAX_QUERY_LEN: usize = 160;
+
+/// Words that make a string literal a SQL query.
+const SQL_VERBS: &[&str] = &[
+    "select", "insert", "update", "delete", "upsert", "merge", "from", "join", "into",
+];
+
+/// A column of the current schema.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SqlColumn {
+    pub name: String,
+    pub data_type: Option<String>,
+    /// SQL file that last defined or changed the column, relative to the
+    /// workspace root
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// A table of the current schema.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SqlTable {
+    pub name: String,
+    /// SQL file creating (or last renaming) the table
+    pub file: PathBuf,
+    pub line: usize,
+    /// Columns in definition order
+    pub columns: Vec<SqlColumn>,
+}
+
+/// A string literal in code querying the table.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SqlQueryRef {
+    /// Innermost symbol containing the query, if any
+    pub symbol: Option<String>,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    /// Line naming the table
+    pub line: usize,
+    /// The query with whitespace collapsed, truncated
+    pub query: String,
+}
+
+/// A table (or one of its columns) and the code querying it.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SqlRefs {
+    pub table: SqlTable,
+    /// Set when a single column was asked for
+    pub column: Option<String>,
+    pub queries: Vec<SqlQueryRef>,
+}
+
+/// Replay the schema changes of every SQL file under the workspace root.
+pub fn current_schema(state: &OciState) -> Vec<SqlTable> {
+    let mut files: Vec<PathBuf> = state
+        .file_hashes
+        .iter()
+        .map(|e| e.key().clone())
+        .chain(state.overlays.iter().map(|e| e.key().clone()))
+        .filter(|path| is_sql_file(path))
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let mut tables: Vec<SqlTable> = Vec::new();
+    for file in &files {
+        let Some(source) = file_source(state, file) else {
+            continue;
+        };
+        let rel = relative(state, file);
+        for change in extract_schema_changes(&source) {
+            apply(&mut tables, change, &rel);
+        }
+    }
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+    tables
+}
+
+fn apply(tables: &mut Vec<SqlTable>, change: SchemaChange, file: &Path) {
+    let find = |tables: &mut Vec<SqlTable>, name: &str| {
+        tables
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name))
+    };
+    match change {
+        SchemaChange::CreateTable { table, columns } => {
+            if let Some(i) = find(tables, &table.name) {
+                tables.remove(i);
+            }
+            tables.push(SqlTable {
+                name: table.name,
+                file: file.to_path_buf(),
+                line: table.line,
+                columns: columns
+                    .into_iter()
+                    .map(|c| SqlColumn {
+                        name: c.name,
+                        data_type: c.data_type,
+                        file: file.to_path_buf(),
+                        line: c.line,
+                    })
+                    .collect(),
+            });
+        }
+        SchemaChange::AddColumn { table, column } => {
+            if let Some(i) = find(tables, &table) {
+                let columns = &mut tables[i].columns;
+                columns.retain(|c| !c.name.eq_ignore_ascii_case(&column.name));
+                columns.push(SqlColumn {
+                    name: column.name,
+                    data_type: column.data_type,
+                    file: file.to_path_buf(),
+                    line: column.line,
+                });
+            }
+        }
+        SchemaChange::DropColumn { table, column } => {
+            if let Some(i) = find(tables, &table) {
+                tables[i]
+                    .columns
+                    .retain(|c| !c.name.eq_ignore_ascii_case(&column));
+            }
+        }
+        SchemaChange::RenameColumn { table, from, to } => {
+            if let Some(i) = find(tables, &table)
+                && let Some(c) = tables[i]
+                    .columns
+                    .iter_mut()
+                    .find(|c| c.name.eq_ignore_ascii_case(&from))
+            {
+                c.name = to.name;
+                c.file = file.to_path_buf();
+                c.line = to.line;
+            }
+        }
+        SchemaChange::AlterColumnType {
+            table,
+            column,
+            data_type,
+        } => {
+            if let Some(i) = find(tables, &table)
+                && let Some(c) = tables[i]
+                    .columns
+                    .iter_mut()
+                    .find(|c| c.name.eq_ignore_ascii_case(&column))
+            {
+                c.data_type = Some(data_type);
+            }
+        }
+        SchemaChange::RenameTable { from, to } => {
+            if let Some(i) = find(tables, &from) {
+                let t = &mut tables[i];
+                t.name = to.name;
+                t.file = file.to_path_buf();
+                t.line = to.line;
+            }
+        }
+        SchemaChange::DropTable { table } => {
+            if let Some(i) = find(tables, &table) {
+                tables.remove(i);
+            }
+        }
+    }
+}
+
+/// The current definition of `name` (`users` or `users.email`) and the
+/// queries naming it. Returns `None` if the schema has no such table or
+/// column; a bare column name is accepted when only one table has it.
+pub fn find_sql_refs(state: &OciState, name: &str) -> Option<SqlRefs> {
+    let name = name.trim();
+    let schema = current_schema(state);
+    let has_column = |t: &SqlTable, column: &str| {
+        t.columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(column))
+            .map(|c| c.name.clone())
+    };
+    let (table, column) = match name.split_once('.') {
+        Some((table, column)) => {
+            let table = schema
+                .into_iter()
+                .find(|t| t.name.eq_ignore_ascii_case(table))?;
+            let column = has_column(&table, column)?;
+            (table, Some(column))
+        }
+        None => match schema
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name))
+        {
+            Some(i) => (schema.into_iter().nth(i)?, None),
+            None => {
+                let mut owners: Vec<(SqlTable, String)> = schema
+                    .into_iter()
+                    .filter_map(|t| has_column(&t, name).map(|c| (t, c)))
+                    .collect();
+                if owners.len() != 1 {
+                    return None;
+                }
+                let (table, column) = owners.pop()?;
+                (table, Some(column))
+            }
+        },
+    };
+
+    let mut files: Vec<PathBuf> = state
+        .file_hashes
+        .iter()
+        .map(|e| e.key().clone())
+        .filter(|path| !is_sql_file(path) && state.file_ids.contains_key(path))
+        .collect();
+    files.sort();
+    let mut queries = Vec::new();
+    for file in &files {
+        if let Some(source) = file_source(state, file) {
+            queries.extend(scan_queries(
+                state,
+                file,
+                &source,
+                &table.name,
+                column.as_deref(),
+            ));
+        }
+    }
+
+    Some(SqlRefs {
+        table,
+        column,
+        queries,
+    })
+}
+
+fn scan_queries(
+    state: &OciState,
+    file: &Path,
+    source: &str,
+    table: &str,
+    column: Option<&str>,
+) -> Vec<SqlQueryRef> {
+    let mut queries = Vec::new();
+    for (start, literal) in string_literals(source) {
+        let lower = literal.to_lowercase();
+        if !SQL_VERBS
+            .iter()
+            .any(|verb| find_word(&lower, verb).is_some())
+        {
+            continue;
+        }
+        let Some(at) = find_word(&lower, &table.to_lowercase()) else {
+            continue;
+        };
+        if column.is_some_and(|c| find_word(&lower, &c.to_lowercase()).is_none()) {
+            continue;
+        }
+        let line = source[..start + at].matches('\n').count();
+        let mut query = literal.split_whitespace().collect::<Vec<_>>().join(" ");
+        if query.len() > MAX_QUERY_LEN {
+            let mut end = MAX_QUERY_LEN;
+            while !query.is_char_boundary(end) {
+                end -= 1;
+            }
+            query.truncate(end);
+            query.push_str("...");
+        }
+        queries.push(SqlQueryRef {
+            symbol: enclosing_symbol(state, file, line),
+            file: relative(state, file),
+            line,
+            query,
+        });
+    }
+    queries
+}
+
+/// String literal bodies (`"..."` and `` `...` ``) with their byte offsets.
+fn string_literals(source: &str) -> Vec<(usize, &str)> {
+    let bytes = source.as_bytes();
+    let mut literals = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let quote = bytes[i];
+        if quote != b'"' && quote != b'`' {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != quote {
+            if bytes[end] == b'\\' {
+                end += 1;
+            }
+            end += 1;
+        }
+        let end = end.min(bytes.len());
+        literals.push((start, &source[start..end]));
+        i = end + 1;
+    }
+    literals
+}
+
+/// Offset of `word` in `text` where it is not part of a longer identifier.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).map(|(i, _)| i).find(|&i| {
+        !text[..i].chars().next_back().is_some_and(is_ident)
+            && !text[i + word.len()..].chars().next().is_some_and(is_ident)
+    })
+}
+
+/// Scoped name of the innermost symbol spanning `line` in `file`.
+fn enclosing_symbol(state: &OciState, file: &Path, line: usize) -> Option<String> {
+    let id = *state.file_ids.get(file)?;
+    let scoped = state.file_symbols.get(&id)?;
+    scoped
+        .iter()
+        .filter_map(|&s| state.get_symbol(s))
+        .filter(|s| s.location.start_line <= line && line <= s.location.end_line)
+        .min_by_key(|s| s.location.end_byte - s.location.start_byte)
+        .map(|s| state.resolve(s.scoped_name).to_string())
+}
+
+fn file_source(state: &OciState, file: &Path) -> Option<Arc<str>> {
+    if let Some(overlay) = state.overlays.get(file) {
+        return Some(overlay.contents.clone());
+    }
+    if let Some(contents) = state.file_contents.get(file) {
+        return Some(contents.clone());
+    }
+    std::fs::read_to_string(file).ok().map(Arc::from)
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_replays_migrations_and_finds_queries() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("migrations")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("migrations/0001_init.sql"),
+            "CREATE TABLE users (\n    id BIGINT PRIMARY KEY,\n    name TEXT,\n    legacy_flag BOOLEAN\n);\nCREATE TABLE audit (id INT);\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("migrations/0002_email.sql"),
+            "ALTER TABLE users ADD COLUMN email VARCHAR(255) NOT NULL;\nALTER TABLE users DROP COLUMN legacy_flag;\nALTER TABLE users RENAME COLUMN name TO display_name;\nDROP TABLE audit;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/repo.rs"),
+            "pub fn find_by_email(db: &Db, email: &str) -> User {\n    db.query(\"SELECT id, display_name\n               FROM users WHERE email = $1\", email)\n}\n\npub fn count_users(db: &Db) -> i64 {\n    db.query(\"SELECT count(*) FROM users\")\n}\n\npub fn label() -> &'static str {\n    \"users\"\n}\n",
+        )
+        .unwrap();
+
+        let state = create_state(root.to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+
+        let schema = current_schema(&state);
+        let names: Vec<&str> = schema.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["users"]);
+        let columns: Vec<(&str, Option<&str>)> = schema[0]
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type.as_deref()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id", Some("BIGINT")),
+                ("display_name", Some("TEXT")),
+                ("email", Some("VARCHAR(255)")),
+            ]
+        );
+        assert_eq!(
+            schema[0].columns[1].file,
+            PathBuf::from("migrations/0002_email.sql")
+        );
+
+        // Tables and columns are symbols too
+        assert_eq!(state.find_by_name("users").len(), 1);
+        assert!(state.lookup("users.email").is_some());
+
+        let refs = find_sql_refs(&state, "users").unwrap();
+        let found: Vec<(Option<&str>, usize)> = refs
+            .queries
+            .iter()
+            .map(|q| (q.symbol.as_deref(), q.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some("crate::find_by_email"), 2),
+                (Some("crate::count_users"), 6)
+            ]
+        );
+        assert_eq!(
+            refs.queries[0].query,
+            "SELECT id, display_name FROM users WHERE email = $1"
+        );
+
+        let email = find_sql_refs(&state, "email").unwrap();
+        assert_eq!(email.column.as_deref(), Some("email"));
+        assert_eq!(email.queries.len(), 1);
+        assert!(find_sql_refs(&state, "users.legacy_flag").is_none());
+        assert!(find_sql_refs(&state, "audit").is_none());
+    }
+}
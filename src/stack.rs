@@ -0,0 +1,384 @@
+//! Workspace stack detection.
+//!
+//! Agents starting on an unfamiliar repository first need to know what it is
+//! built with. [`detect_stack`] answers that from what indexing already
+//! sees: source files per language, package manifests and their dependencies
+//! (see [`FileDiscovery::discover_packages`]), build files, lockfiles, and the
+//! imports of parsed files. The result is kept in [`OciState::stack`] and
+//! persisted with the symbol graph snapshot.
+
+use crate::discovery::FileDiscovery;
+use crate::parsing::language_for_file;
+use crate::state::OciState;
+use crate::types::{PackageKind, PackageRoot};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Languages, frameworks, build systems, and test runners of a workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StackReport {
+    /// Indexed source files per language, most files first
+    pub languages: Vec<LanguageUsage>,
+    /// Frameworks and notable libraries (`axum`, `tokio`, `react`, ...)
+    pub frameworks: Vec<String>,
+    /// Build systems and package managers (`cargo`, `pnpm`, `gradle`, ...)
+    pub build_systems: Vec<String>,
+    /// Test runners (`cargo test`, `jest`, `junit`, ...)
+    pub test_runners: Vec<String>,
+}
+
+/// Number of indexed source files in one language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LanguageUsage {
+    pub language: String,
+    pub files: usize,
+}
+
+impl StackReport {
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+            && self.frameworks.is_empty()
+            && self.build_systems.is_empty()
+            && self.test_runners.is_empty()
+    }
+
+    /// One-line summary, e.g.
+    /// `rust (42 files); frameworks: axum, tokio; build: cargo; tests: cargo test`.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.languages.is_empty() {
+            parts.push(
+                self.languages
+                    .iter()
+                    .map(|l| {
+                        let noun = if l.files == 1 { "file" } else { "files" };
+                        format!("{} ({} {})", l.language, l.files, noun)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+        for (label, names) in [
+            ("frameworks", &self.frameworks),
+            ("build", &self.build_systems),
+            ("tests", &self.test_runners),
+        ] {
+            if !names.is_empty() {
+                parts.push(format!("{}: {}", label, names.join(", ")));
+            }
+        }
+        if parts.is_empty() {
+            "unknown".to_string()
+        } else {
+            parts.join("; ")
+        }
+    }
+}
+
+/// A framework or test runner and how to recognize it.
+struct Marker {
+    name: &'static str,
+    /// Cargo or npm package names; Maven group ids for JVM build files
+    packages: &'static [&'static str],
+    /// Import path prefixes (`axum`, `@nestjs`, `org.junit`)
+    imports: &'static [&'static str],
+}
+
+const fn marker(
+    name: &'static str,
+    packages: &'static [&'static str],
+    imports: &'static [&'static str],
+) -> Marker {
+    Marker {
+        name,
+        packages,
+        imports,
+    }
+}
+
+const FRAMEWORKS: &[Marker] = &[
+    // Rust
+    marker("axum", &["axum"], &["axum"]),
+    marker("actix-web", &["actix-web"], &["actix_web"]),
+    marker("rocket", &["rocket"], &["rocket"]),
+    marker("warp", &["warp"], &["warp"]),
+    marker("tonic", &["tonic"], &["tonic"]),
+    marker("tokio", &["tokio"], &["tokio"]),
+    marker("async-std", &["async-std"], &["async_std"]),
+    marker("serde", &["serde"], &["serde"]),
+    marker("clap", &["clap"], &["clap"]),
+    marker("diesel", &["diesel"], &["diesel"]),
+    marker("sqlx", &["sqlx"], &["sqlx"]),
+    marker("tauri", &["tauri"], &["tauri"]),
+    marker("bevy", &["bevy"], &["bevy"]),
+    marker("leptos", &["leptos"], &["leptos"]),
+    marker("yew", &["yew"], &["yew"]),
+    // JavaScript / TypeScript
+    marker("react", &["react"], &["react"]),
+    marker("next", &["next"], &["next"]),
+    marker("vue", &["vue"], &["vue"]),
+    marker("svelte", &["svelte"], &["svelte"]),
+    marker("angular", &["@angular/core"], &["@angular"]),
+    marker("express", &["express"], &["express"]),
+    marker("nestjs", &["@nestjs/core"], &["@nestjs"]),
+    // JVM
+    marker("spring", &["org.springframework"], &["org.springframework"]),
+    marker("ktor", &["io.ktor"], &["io.ktor"]),
+    marker("android", &["com.android"], &["android", "androidx"]),
+];
+
+const TEST_RUNNERS: &[Marker] = &[
+    marker("jest", &["jest"], &["@jest/globals"]),
+    marker("vitest", &["vitest"], &["vitest"]),
+    marker("mocha", &["mocha"], &["mocha"]),
+    marker("playwright", &["@playwright/test"], &["@playwright/test"]),
+    marker("cypress", &["cypress"], &["cypress"]),
+    marker("junit", &["org.junit"], &["org.junit"]),
+    marker("testng", &["org.testng"], &["org.testng"]),
+    marker("kotest", &["io.kotest"], &["io.kotest"]),
+];
+
+/// Build files other than the package manifests discovery understands.
+const BUILD_FILES: &[(&str, &str)] = &[
+    ("pom.xml", "maven"),
+    ("build.gradle", "gradle"),
+    ("build.gradle.kts", "gradle"),
+    ("settings.gradle", "gradle"),
+    ("settings.gradle.kts", "gradle"),
+    ("Makefile", "make"),
+    ("CMakeLists.txt", "cmake"),
+    ("MODULE.bazel", "bazel"),
+    ("WORKSPACE", "bazel"),
+];
+
+/// npm-compatible package managers by lockfile.
+const NPM_LOCKFILES: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+    ("bun.lock", "bun"),
+];
+
+/// Detect the stack of the workspace at `root` from its source `files` and
+/// the imports currently held in `state`.
+pub fn detect_stack(state: &OciState, root: &Path, files: &[PathBuf]) -> StackReport {
+    let mut languages: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for file in files {
+        if let Some(language) = language_for_file(file) {
+            // TSX is TypeScript as far as the stack goes
+            let language = if language == "tsx" {
+                "typescript"
+            } else {
+                language
+            };
+            *languages.entry(language).or_default() += 1;
+        }
+    }
+    let mut languages: Vec<LanguageUsage> = languages
+        .into_iter()
+        .map(|(language, files)| LanguageUsage {
+            language: language.to_string(),
+            files,
+        })
+        .collect();
+    languages.sort_by(|a, b| b.files.cmp(&a.files).then(a.language.cmp(&b.language)));
+
+    let packages = FileDiscovery::new()
+        .discover_packages(root)
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to discover packages for stack detection: {}", e);
+            Vec::new()
+        });
+
+    let mut build_systems = BTreeSet::new();
+    let mut dependencies: HashSet<String> = HashSet::new();
+    for package in &packages {
+        match package.kind {
+            PackageKind::Cargo => {
+                build_systems.insert("cargo");
+            }
+            PackageKind::Npm => {
+                build_systems.insert(npm_package_manager(package, root));
+            }
+            PackageKind::Git => {}
+        }
+        dependencies.extend(package.dependencies.iter().map(|d| normalize(d)));
+    }
+
+    // Build files next to any package or source file, up to the root
+    let mut dirs: BTreeSet<&Path> = BTreeSet::from([root]);
+    dirs.extend(packages.iter().map(|p| p.path.as_path()));
+    for file in files {
+        for dir in file.ancestors().skip(1) {
+            if !dir.starts_with(root) || !dirs.insert(dir) {
+                break;
+            }
+        }
+    }
+    let mut build_text = String::new();
+    for dir in dirs {
+        for (name, system) in BUILD_FILES {
+            let path = dir.join(name);
+            if path.is_file() {
+                build_systems.insert(system);
+                if matches!(*system, "maven" | "gradle") {
+                    build_text.push_str(&std::fs::read_to_string(&path).unwrap_or_default());
+                }
+            }
+        }
+    }
+
+    let imports: HashSet<String> = state
+        .imports
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .value()
+                .iter()
+                .map(|import| import.path.clone())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let found = |markers: &[Marker]| -> BTreeSet<&'static str> {
+        markers
+            .iter()
+            .filter(|m| {
+                m.packages.iter().any(|p| {
+                    dependencies.contains(&normalize(p))
+                        || (p.contains('.') && build_text.contains(p))
+                }) || m
+                    .imports
+                    .iter()
+                    .any(|prefix| imports.iter().any(|i| imports_module(i, prefix)))
+            })
+            .map(|m| m.name)
+            .collect()
+    };
+    let frameworks = found(FRAMEWORKS);
+    let mut test_runners = found(TEST_RUNNERS);
+    if build_systems.contains("cargo") {
+        test_runners.insert("cargo test");
+        if root.join(".config/nextest.toml").is_file() {
+            test_runners.insert("cargo-nextest");
+        }
+    }
+
+    let strings = |set: BTreeSet<&str>| set.into_iter().map(str::to_string).collect();
+    StackReport {
+        languages,
+        frameworks: strings(frameworks),
+        build_systems: strings(build_systems),
+        test_runners: strings(test_runners),
+    }
+}
+
+/// Package manager of an npm package, from the lockfile in its directory or
+/// at the workspace root.
+fn npm_package_manager(package: &PackageRoot, root: &Path) -> &'static str {
+    [package.path.as_path(), root]
+        .iter()
+        .find_map(|dir| {
+            NPM_LOCKFILES
+                .iter()
+                .find(|(lockfile, _)| dir.join(lockfile).is_file())
+                .map(|(_, manager)| *manager)
+        })
+        .unwrap_or("npm")
+}
+
+/// Cargo treats `-` and `_` in package names alike.
+fn normalize(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Whether `import` is `module` or something inside it (`axum::Router`,
+/// `react/jsx-runtime`, `org.junit.jupiter.api.Test`).
+fn imports_module(import: &str, module: &str) -> bool {
+    import
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([':', '.', '/']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::create_state;
+
+    #[tokio::test]
+    async fn test_detects_rust_and_typescript_stack() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n\n[dependencies]\ntokio = \"1\"\n\n[dev-dependencies]\nproptest = \"1\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "use axum::Router;\n\nfn main() { let _ = Router::new(); }\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("web/src")).unwrap();
+        std::fs::write(
+            root.join("web/package.json"),
+            r#"{"name": "web", "dependencies": {"react": "18"}, "devDependencies": {"jest": "29"}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("web/pnpm-lock.yaml"), "lockfileVersion: 9\n").unwrap();
+        std::fs::write(
+            root.join("web/src/App.tsx"),
+            "export const App = () => null;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("web/src/util.ts"), "export const x = 1;\n").unwrap();
+
+        let state = create_state(root.to_path_buf());
+        crate::incremental::IncrementalIndexer::new()
+            .full_index(&state, root)
+            .await
+            .unwrap();
+        let stack = state.stack.read().clone().expect("stack detected");
+
+        assert_eq!(
+            stack.languages,
+            vec![
+                LanguageUsage {
+                    language: "typescript".to_string(),
+                    files: 2
+                },
+                LanguageUsage {
+                    language: "rust".to_string(),
+                    files: 1
+                },
+            ]
+        );
+        // axum only shows up as an import
+        assert_eq!(stack.frameworks, vec!["axum", "react", "tokio"]);
+        assert_eq!(stack.build_systems, vec!["cargo", "pnpm"]);
+        assert_eq!(stack.test_runners, vec!["cargo test", "jest"]);
+        assert_eq!(
+            stack.summary(),
+            "typescript (2 files), rust (1 file); frameworks: axum, react, tokio; build: cargo, pnpm; tests: cargo test, jest"
+        );
+    }
+
+    #[test]
+    fn test_jvm_stack_from_build_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::write(
+            root.join("build.gradle.kts"),
+            "dependencies {\n    implementation(\"io.ktor:ktor-server-core:2.3.0\")\n    testImplementation(\"io.kotest:kotest-runner-junit5:5.8.0\")\n}\n",
+        )
+        .unwrap();
+        let state = create_state(root.to_path_buf());
+        let stack = detect_stack(&state, root, &[]);
+        assert_eq!(stack.frameworks, vec!["ktor"]);
+        assert_eq!(stack.build_systems, vec!["gradle"]);
+        assert_eq!(stack.test_runners, vec!["kotest"]);
+    }
+}
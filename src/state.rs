@@ -3,19 +3,30 @@
 //! The OciState holds all three graph layers and provides thread-safe access
 //! for concurrent queries and updates.
 
+#[cfg(feature = "analysis")]
+use crate::analysis::ChurnReport;
+use crate::cache::FileFingerprint;
+use crate::completion::{Completion, CompletionIndex};
+use crate::error::{OmniError, Result};
+use crate::reexports::ReexportGraph;
 use crate::search::Bm25Index;
 #[cfg(feature = "semantic")]
 use crate::semantic::SemanticIndex;
+use crate::stack::StackReport;
 use crate::types::*;
 use dashmap::DashMap;
-use lasso::ThreadedRodeo;
+use lasso::{Key, ThreadedRodeo};
 use parking_lot::RwLock;
 use petgraph::stable_graph::{NodeIndex, StableGraph};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 #[cfg(feature = "semantic")]
 use std::sync::OnceLock;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::time::Instant;
 
 /// Thread-safe string interner for symbol names.
@@ -46,6 +57,17 @@ pub struct OciState {
     pub call_edges: RwLock<Vec<CallEdge>>,
     /// Import graph
     pub imports: DashMap<FileId, Vec<ImportInfo>>,
+    /// Trait implementations per file
+    pub trait_impls: DashMap<FileId, Vec<TraitImpl>>,
+    /// Non-call type and trait references per file
+    pub type_refs: DashMap<FileId, Vec<TypeRef>>,
+    /// Languages, frameworks, and tooling of the workspace, detected on
+    /// full index (see [`crate::stack::detect_stack`])
+    pub stack: RwLock<Option<StackReport>>,
+    /// File and symbol churn from git history, from the last
+    /// [`crate::analysis::ChurnAnalyzer::analyze_index`]
+    #[cfg(feature = "analysis")]
+    pub churn: RwLock<Option<ChurnReport>>,
 
     // ========================================================================
     // Layer 3: Semantic Embeddings (lazy, requires 'semantic' feature)
@@ -53,6 +75,13 @@ pub struct OciState {
     /// Semantic index, built on demand
     #[cfg(feature = "semantic")]
     pub semantic_index: OnceLock<SemanticIndex>,
+    /// Whether the semantic layer is usable (see
+    /// [`crate::semantic::spawn_loader`])
+    pub semantic_status: RwLock<SemanticStatus>,
+    /// Model breakdown of the vectors cached on disk, read once for
+    /// [`Self::stats`] while no semantic index is loaded
+    #[cfg(feature = "semantic")]
+    cached_drift: OnceLock<Option<EmbeddingDriftReport>>,
 
     // ========================================================================
     // File Management
@@ -63,12 +92,26 @@ pub struct OciState {
     pub file_ids: DashMap<PathBuf, FileId>,
     /// Next file ID counter
     file_id_counter: AtomicU32,
+    /// Content hash of each file as last parsed
+    pub file_hashes: DashMap<PathBuf, u64>,
+    /// Number of the first line in each file's locations, for files whose
+    /// parser counts from 1 (see [`Self::first_line`])
+    pub line_bases: DashMap<PathBuf, usize>,
+    /// Unsaved buffer contents shadowing files on disk (see
+    /// [`crate::incremental::IncrementalIndexer::overlay_file`])
+    pub overlays: DashMap<PathBuf, Overlay>,
 
     // ========================================================================
     // Search Indices (lazy)
     // ========================================================================
     /// BM25 index, built on demand
     pub bm25_index: RwLock<Option<Bm25Index>>,
+    /// Sorted symbol names for prefix completion, built on demand and
+    /// dropped whenever symbols change
+    completion_index: RwLock<Option<Arc<CompletionIndex>>>,
+    /// `pub use` re-exports for canonical paths, built on demand and dropped
+    /// whenever imports or the topology change
+    reexport_graph: RwLock<Option<Arc<ReexportGraph>>>,
 
     // ========================================================================
     // Metadata
@@ -85,6 +128,35 @@ pub struct OciState {
     pub file_count: AtomicU32,
     /// Total number of indexed symbols
     pub symbol_count: AtomicU32,
+    /// Files parsed since the state was created or reset
+    pub files_parsed: AtomicUsize,
+    /// Index/update requests skipped because the file was unchanged
+    pub files_skipped: AtomicUsize,
+}
+
+/// Symbols, call edges, imports, trait impls and type references parsed
+/// from one file, staged to be committed by [`OciState::commit_file`].
+#[derive(Debug, Clone, Default)]
+pub struct FileBatch {
+    pub symbols: Vec<SymbolDef>,
+    pub calls: Vec<CallEdge>,
+    pub imports: Vec<ImportInfo>,
+    pub trait_impls: Vec<TraitImpl>,
+    pub type_refs: Vec<TypeRef>,
+    /// Number of the first line in the locations above (see
+    /// [`crate::parsing::LanguageParser::first_line`])
+    pub first_line: usize,
+}
+
+impl FileBatch {
+    /// Whether there is nothing to commit.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+            && self.calls.is_empty()
+            && self.imports.is_empty()
+            && self.trait_impls.is_empty()
+            && self.type_refs.is_empty()
+    }
 }
 
 impl OciState {
@@ -102,18 +174,34 @@ impl OciState {
             file_symbols: DashMap::new(),
             call_edges: RwLock::new(Vec::new()),
             imports: DashMap::new(),
+            trait_impls: DashMap::new(),
+            type_refs: DashMap::new(),
+            stack: RwLock::new(None),
+            #[cfg(feature = "analysis")]
+            churn: RwLock::new(None),
 
             // Layer 3
             #[cfg(feature = "semantic")]
             semantic_index: OnceLock::new(),
+            #[cfg(feature = "semantic")]
+            semantic_status: RwLock::new(SemanticStatus::NotBuilt),
+            #[cfg(not(feature = "semantic"))]
+            semantic_status: RwLock::new(SemanticStatus::Disabled),
+            #[cfg(feature = "semantic")]
+            cached_drift: OnceLock::new(),
 
             // Files
             file_contents: DashMap::new(),
             file_ids: DashMap::new(),
             file_id_counter: AtomicU32::new(0),
+            file_hashes: DashMap::new(),
+            line_bases: DashMap::new(),
+            overlays: DashMap::new(),
 
             // Search
             bm25_index: RwLock::new(None),
+            completion_index: RwLock::new(None),
+            reexport_graph: RwLock::new(None),
 
             // Metadata
             interner: ThreadedRodeo::default(),
@@ -122,6 +210,8 @@ impl OciState {
             last_indexed: RwLock::new(None),
             file_count: AtomicU32::new(0),
             symbol_count: AtomicU32::new(0),
+            files_parsed: AtomicUsize::new(0),
+            files_skipped: AtomicUsize::new(0),
         }
     }
 
@@ -136,9 +226,15 @@ impl OciState {
         id
     }
 
-    /// Intern a string, returning a handle.
+    /// Intern a name, returning a handle. Names are normalized first (see
+    /// [`crate::parsing::normalize_identifier`]).
     pub fn intern(&self, s: &str) -> InternedString {
-        self.interner.get_or_intern(s)
+        crate::parsing::intern_identifier(&self.interner, s)
+    }
+
+    /// Handle of an already interned name, normalized like [`intern`](Self::intern).
+    pub fn lookup(&self, s: &str) -> Option<InternedString> {
+        self.interner.get(crate::parsing::normalize_identifier(s))
     }
 
     /// Resolve an interned string to its value.
@@ -161,6 +257,7 @@ impl OciState {
             .push(scoped);
 
         self.symbol_count.fetch_add(1, Ordering::SeqCst);
+        *self.completion_index.write() = None;
     }
 
     /// Add a call edge to the graph.
@@ -168,6 +265,63 @@ impl OciState {
         self.call_edges.write().push(edge);
     }
 
+    /// Commit everything parsed from one file. The batch is grouped before
+    /// any lock is taken: the call graph's write lock is held once for all
+    /// its edges, each name's entry and the completion index are touched
+    /// once per file, and symbols only lock their own map shard. This cuts
+    /// the locking readers (e.g. server queries during a full index)
+    /// contend with, but it is not atomic: the maps are updated one after
+    /// another, so a concurrent reader can see a file partly committed.
+    pub fn commit_file(&self, path: &Path, batch: FileBatch) {
+        if batch.is_empty() {
+            return;
+        }
+        let file_id = self.get_or_create_file_id(&path.to_path_buf());
+        if batch.first_line != 0 {
+            self.line_bases.insert(path.to_path_buf(), batch.first_line);
+        }
+
+        let file_symbol_names: Vec<InternedString> =
+            batch.symbols.iter().map(|s| s.scoped_name).collect();
+        let mut by_name: HashMap<InternedString, Vec<InternedString>> = HashMap::new();
+        for symbol in &batch.symbols {
+            by_name
+                .entry(symbol.name)
+                .or_default()
+                .push(symbol.scoped_name);
+        }
+
+        if !file_symbol_names.is_empty() {
+            let count = batch.symbols.len();
+            for symbol in batch.symbols {
+                self.symbols.insert(symbol.scoped_name, symbol);
+            }
+            for (name, scoped) in by_name {
+                self.name_to_scoped.entry(name).or_default().extend(scoped);
+            }
+            self.file_symbols.insert(file_id, file_symbol_names);
+            self.symbol_count.fetch_add(count as u32, Ordering::SeqCst);
+            *self.completion_index.write() = None;
+        }
+
+        if !batch.calls.is_empty() {
+            self.call_edges.write().extend(batch.calls);
+        }
+
+        if !batch.imports.is_empty() {
+            self.imports.insert(file_id, batch.imports);
+            self.invalidate_reexports();
+        }
+
+        if !batch.trait_impls.is_empty() {
+            self.trait_impls.insert(file_id, batch.trait_impls);
+        }
+
+        if !batch.type_refs.is_empty() {
+            self.type_refs.insert(file_id, batch.type_refs);
+        }
+    }
+
     /// Clear all data for a file (for incremental updates).
     pub fn clear_file(&self, path: &PathBuf) {
         // Get file ID
@@ -190,11 +344,18 @@ impl OciState {
             }
         }
 
-        // Remove imports
+        *self.completion_index.write() = None;
+
+        // Remove imports, trait impls and type references
         self.imports.remove(&file_id);
+        self.invalidate_reexports();
+        self.trait_impls.remove(&file_id);
+        self.type_refs.remove(&file_id);
 
         // Remove file contents
         self.file_contents.remove(path);
+        self.file_hashes.remove(path);
+        self.line_bases.remove(path);
 
         // Clear call edges from this file (expensive, but necessary for correctness)
         {
@@ -210,7 +371,7 @@ impl OciState {
 
     /// Find all symbols with a given simple name.
     pub fn find_by_name(&self, name: &str) -> Vec<SymbolDef> {
-        let name_key = match self.interner.get(name) {
+        let name_key = match self.lookup(name) {
             Some(k) => k,
             None => return Vec::new(),
         };
@@ -226,8 +387,145 @@ impl OciState {
             .unwrap_or_default()
     }
 
+    /// Names starting with `prefix` (case-insensitive), in sorted order.
+    ///
+    /// Completes simple names (`eng` -> `engine`), or scoped names when the
+    /// prefix contains `::`. Backed by a [`CompletionIndex`] built on first
+    /// use after symbols change.
+    pub fn complete_symbol(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+        let cached = self.completion_index.read().clone();
+        let index = match cached {
+            Some(index) => index,
+            None => {
+                let index = Arc::new(CompletionIndex::build(self));
+                *self.completion_index.write() = Some(index.clone());
+                index
+            }
+        };
+        let scoped = prefix.contains("::");
+        index
+            .complete(prefix, limit)
+            .into_iter()
+            .map(|name| crate::completion::describe(self, name, scoped))
+            .collect()
+    }
+
+    /// Find symbols with a given simple name, ranked by proximity to `context_file`.
+    ///
+    /// Matches in the same file come first, then the same module (directory),
+    /// then the same crate/package, then everything else. Ties are broken by
+    /// the defining file's PageRank, then by scoped name.
+    pub fn find_by_name_near(&self, name: &str, context_file: &Path) -> Vec<SymbolDef> {
+        let context = if context_file.is_relative() {
+            self.root_path.join(context_file)
+        } else {
+            context_file.to_path_buf()
+        };
+        let mut package_roots: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+        let mut package_of = |file: &Path| {
+            let dir = file.parent().unwrap_or(file).to_path_buf();
+            package_roots
+                .entry(dir)
+                .or_insert_with_key(|dir| package_root(dir, &self.root_path))
+                .clone()
+        };
+        let context_package = package_of(&context);
+
+        let mut ranked: Vec<(u8, f64, SymbolDef)> = self
+            .find_by_name(name)
+            .into_iter()
+            .map(|sym| {
+                let file = sym.location.file.as_path();
+                let tier = if file == context {
+                    0
+                } else if file.parent() == context.parent() {
+                    1
+                } else if context_package.is_some() && package_of(file) == context_package {
+                    2
+                } else {
+                    3
+                };
+                (tier, self.relevance_of_file(file), sym)
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)).then_with(|| {
+                self.resolve(a.2.scoped_name)
+                    .cmp(self.resolve(b.2.scoped_name))
+            })
+        });
+        ranked.into_iter().map(|(_, _, sym)| sym).collect()
+    }
+
+    /// All symbols carrying every attribute in `queries`, ordered by file and line.
+    pub fn find_by_attributes(&self, queries: &[Attribute]) -> Vec<SymbolDef> {
+        let mut found: Vec<SymbolDef> = self
+            .symbols
+            .iter()
+            .filter(|entry| entry.has_attributes(queries))
+            .map(|entry| entry.value().clone())
+            .collect();
+        found.sort_by(|a, b| {
+            a.location
+                .file
+                .cmp(&b.location.file)
+                .then(a.location.start_line.cmp(&b.location.start_line))
+        });
+        found
+    }
+
+    /// PageRank relevance of a file's topology node, or 0.0 if the file is
+    /// not in the topology graph.
+    pub fn relevance_of_file(&self, path: &Path) -> f64 {
+        self.path_to_node
+            .get(path)
+            .and_then(|node| self.topology_metrics.get(&*node).map(|m| m.relevance_score))
+            .unwrap_or(0.0)
+    }
+
+    /// Relevance of a symbol: that of the file defining it, or 0.0 for an
+    /// unknown symbol.
+    pub fn relevance_of_symbol(&self, symbol: InternedString) -> f64 {
+        self.symbols
+            .get(&symbol)
+            .map_or(0.0, |s| self.relevance_of_file(&s.location.file))
+    }
+
+    /// Module role of an indexed file.
+    pub fn file_role(&self, path: &Path) -> ModuleRole {
+        self.path_to_node
+            .get(path)
+            .and_then(|node| self.topology_metrics.get(&*node).map(|m| m.role))
+            .unwrap_or_default()
+    }
+
+    /// Shortest path a Rust symbol can be imported by, following `pub use`
+    /// re-exports from its defining path (`omni_index::InterventionEngine`
+    /// rather than `omni_index::intervention::InterventionEngine`). Backed
+    /// by a [`ReexportGraph`] built on first use after imports change.
+    pub fn canonical_path(&self, symbol: &SymbolDef) -> Option<String> {
+        let cached = self.reexport_graph.read().clone();
+        let graph = match cached {
+            Some(graph) => graph,
+            None => {
+                let graph = Arc::new(ReexportGraph::build(self));
+                *self.reexport_graph.write() = Some(graph.clone());
+                graph
+            }
+        };
+        graph.canonical_path(self, symbol)
+    }
+
+    /// Drop the cached [`ReexportGraph`] after imports or the topology
+    /// change.
+    pub(crate) fn invalidate_reexports(&self) {
+        *self.reexport_graph.write() = None;
+    }
+
     /// Find callers of a symbol (by simple name).
     pub fn find_callers(&self, callee_name: &str) -> Vec<CallEdge> {
+        let callee_name = crate::parsing::normalize_identifier(callee_name);
         let edges = self.call_edges.read();
         edges
             .iter()
@@ -236,6 +534,151 @@ impl OciState {
             .collect()
     }
 
+    /// Find every usage of a name: the calls to it (as
+    /// [`find_callers`](Self::find_callers) finds them) and its non-call
+    /// [`TypeRef`]s, sorted by file and position.
+    ///
+    /// Usages are matched by simple name; a scoped name is reduced to its
+    /// last segment.
+    pub fn find_references(&self, name: &str) -> Vec<Reference> {
+        let name = name.rsplit("::").next().unwrap_or(name);
+        let name = crate::parsing::normalize_identifier(name);
+        let mut refs: Vec<Reference> = self
+            .find_callers(&name)
+            .into_iter()
+            .map(|edge| Reference {
+                kind: ReferenceKind::Call,
+                location: edge.location,
+                from: Some(edge.caller),
+                is_test_context: edge.is_test_context,
+            })
+            .collect();
+
+        for entry in self.type_refs.iter() {
+            let mut used = entry.value().iter().filter(|r| r.name == name).peekable();
+            if used.peek().is_none() {
+                continue;
+            }
+            let defs: Vec<SymbolDef> = self
+                .file_symbols
+                .get(entry.key())
+                .map(|scoped| scoped.iter().filter_map(|&s| self.get_symbol(s)).collect())
+                .unwrap_or_default();
+            for type_ref in used {
+                let at = type_ref.location.start_byte;
+                let from = defs
+                    .iter()
+                    .filter(|d| d.location.start_byte <= at && at < d.location.end_byte)
+                    .min_by_key(|d| d.location.end_byte - d.location.start_byte);
+                let is_test_context = match from {
+                    Some(def) => def.is_test_context,
+                    None => {
+                        let file = &type_ref.location.file;
+                        let rel = file.strip_prefix(&self.root_path).unwrap_or(file);
+                        crate::topology::is_test_path(rel)
+                    }
+                };
+                refs.push(Reference {
+                    kind: type_ref.kind,
+                    location: type_ref.location.clone(),
+                    from: from.map(|d| d.scoped_name),
+                    is_test_context,
+                });
+            }
+        }
+
+        refs.sort_by(|a, b| {
+            (&a.location.file, a.location.start_byte)
+                .cmp(&(&b.location.file, b.location.start_byte))
+        });
+        refs
+    }
+
+    /// Find the calls bound to a definition by
+    /// [`crate::call_resolution::resolve_call_edges`].
+    pub fn find_resolved_callers(&self, callee: InternedString) -> Vec<CallEdge> {
+        let edges = self.call_edges.read();
+        edges
+            .iter()
+            .filter(|e| e.resolved_callee == Some(callee))
+            .cloned()
+            .collect()
+    }
+
+    /// Resolve the symbols a call edge may target.
+    ///
+    /// Approximate edges name a scoped target, and bound edges their
+    /// resolved callee; others are resolved by simple name.
+    pub fn call_targets(&self, edge: &CallEdge) -> Vec<SymbolDef> {
+        if let Some(symbol) = edge.resolved_callee.and_then(|key| self.get_symbol(key)) {
+            return vec![symbol];
+        }
+        if edge.is_approximate {
+            return self
+                .interner
+                .get(&edge.callee_name)
+                .and_then(|key| self.get_symbol(key))
+                .into_iter()
+                .collect();
+        }
+        self.find_by_name(&edge.callee_name)
+    }
+
+    /// Recompute approximate edges for `dyn Trait` method calls.
+    ///
+    /// Each call through a `dyn Trait` receiver gets an edge to every
+    /// in-workspace impl of that trait method. Returns the number of
+    /// approximate edges added.
+    pub fn link_dyn_dispatch(&self) -> usize {
+        let mut impls_by_trait: std::collections::HashMap<&str, Vec<InternedString>> =
+            std::collections::HashMap::new();
+        let trait_impls: Vec<TraitImpl> = self
+            .trait_impls
+            .iter()
+            .flat_map(|entry| entry.value().clone())
+            .collect();
+        for imp in &trait_impls {
+            impls_by_trait
+                .entry(imp.trait_name.as_str())
+                .or_default()
+                .extend(imp.methods.iter().copied());
+        }
+
+        let mut edges = self.call_edges.write();
+        edges.retain(|e| !e.is_approximate);
+
+        let mut approximate = Vec::new();
+        for edge in edges.iter() {
+            let Some(trait_name) = edge.via_trait.as_deref() else {
+                continue;
+            };
+            let Some(methods) = impls_by_trait.get(trait_name) else {
+                continue;
+            };
+            for &method in methods {
+                let scoped = self.resolve(method);
+                if scoped.rsplit("::").next() != Some(edge.callee_name.as_str()) {
+                    continue;
+                }
+                approximate.push(CallEdge {
+                    caller: edge.caller,
+                    callee_name: scoped.to_string(),
+                    location: edge.location.clone(),
+                    is_method_call: true,
+                    via_trait: edge.via_trait.clone(),
+                    is_approximate: true,
+                    type_args: edge.type_args.clone(),
+                    is_test_context: edge.is_test_context,
+                    resolved_callee: None,
+                });
+            }
+        }
+
+        let added = approximate.len();
+        edges.extend(approximate);
+        added
+    }
+
     /// Find callees of a symbol (by scoped name).
     pub fn find_callees(&self, caller_scoped: InternedString) -> Vec<CallEdge> {
         let edges = self.call_edges.read();
@@ -246,8 +689,12 @@ impl OciState {
             .collect()
     }
 
-    /// Get file contents, loading from disk if not cached.
+    /// Get file contents, preferring an overlay and otherwise loading from
+    /// disk if not cached.
     pub async fn get_file_contents(&self, path: &PathBuf) -> Option<Arc<str>> {
+        if let Some(overlay) = self.overlays.get(path) {
+            return Some(overlay.contents.clone());
+        }
         if let Some(contents) = self.file_contents.get(path) {
             return Some(contents.clone());
         }
@@ -263,6 +710,12 @@ impl OciState {
         }
     }
 
+    /// Number of the first line in the locations parsed from `file`: 1 if
+    /// its parser counts from 1, otherwise 0 (tree-sitter rows).
+    pub fn first_line(&self, file: &Path) -> usize {
+        self.line_bases.get(file).map_or(0, |base| *base)
+    }
+
     /// Get statistics about the index.
     pub fn stats(&self) -> IndexStats {
         IndexStats {
@@ -274,11 +727,26 @@ impl OciState {
             has_semantic_index: self.semantic_index.get().is_some(),
             #[cfg(not(feature = "semantic"))]
             has_semantic_index: false,
+            semantic_status: self.semantic_status.read().clone(),
             has_bm25_index: self.bm25_index.read().is_some(),
+            files_parsed: self.files_parsed.load(Ordering::SeqCst),
+            files_skipped: self.files_skipped.load(Ordering::SeqCst),
+            #[cfg(feature = "semantic")]
+            embedding_drift: match self.semantic_index.get() {
+                Some(index) => Some(index.drift_report()),
+                None => self
+                    .cached_drift
+                    .get_or_init(|| crate::semantic::cached_drift(&self.root_path))
+                    .clone(),
+            },
+            #[cfg(not(feature = "semantic"))]
+            embedding_drift: None,
+            stack: self.stack.read().clone(),
         }
     }
 
-    /// Reset all state to empty.
+    /// Reset all state to empty. Overlays are kept: they are the caller's
+    /// unsaved edits, not derived data, and are re-applied on re-index.
     pub fn reset(&self) {
         {
             let mut graph = self.topology.write();
@@ -292,20 +760,246 @@ impl OciState {
         self.file_symbols.clear();
         self.call_edges.write().clear();
         self.imports.clear();
+        self.trait_impls.clear();
+        self.type_refs.clear();
+        *self.stack.write() = None;
+        #[cfg(feature = "analysis")]
+        {
+            *self.churn.write() = None;
+        }
 
         self.file_contents.clear();
         self.file_ids.clear();
         self.file_id_counter.store(0, Ordering::SeqCst);
+        self.file_hashes.clear();
+        self.line_bases.clear();
 
         *self.bm25_index.write() = None;
+        *self.completion_index.write() = None;
+        self.invalidate_reexports();
 
         *self.git_hash.write() = None;
         *self.last_indexed.write() = None;
         self.file_count.store(0, Ordering::SeqCst);
         self.symbol_count.store(0, Ordering::SeqCst);
+        self.files_parsed.store(0, Ordering::SeqCst);
+        self.files_skipped.store(0, Ordering::SeqCst);
     }
 }
 
+/// Version of the [`OciState::save_snapshot`] format. Bump it whenever a
+/// persisted type changes shape; older snapshots are then ignored.
+pub const SNAPSHOT_VERSION: u32 = 8;
+
+/// Leading part of a snapshot, decoded on its own to decide whether the
+/// rest is worth reading.
+#[derive(Serialize, Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+    tool_version: String,
+    /// Fingerprints of the indexed files, keyed by path relative to the root
+    files: HashMap<String, FileFingerprint>,
+}
+
+/// Symbol graph and topology as persisted in `.omni/oci.bin`.
+///
+/// Interned names are stored as interner keys; `strings` lists the interned
+/// values in key order so a fresh interner hands out the same keys again.
+#[derive(Serialize, Deserialize)]
+struct SnapshotBody {
+    strings: Vec<String>,
+    topology: StableGraph<TopologyNode, TopologyEdge>,
+    path_to_node: Vec<(PathBuf, NodeIndex)>,
+    topology_metrics: Vec<(NodeIndex, TopologyMetrics)>,
+    symbols: Vec<SymbolDef>,
+    name_to_scoped: Vec<(InternedString, Vec<InternedString>)>,
+    file_symbols: Vec<(FileId, Vec<InternedString>)>,
+    call_edges: Vec<CallEdge>,
+    imports: Vec<(FileId, Vec<ImportInfo>)>,
+    trait_impls: Vec<(FileId, Vec<TraitImpl>)>,
+    type_refs: Vec<(FileId, Vec<TypeRef>)>,
+    stack: Option<StackReport>,
+    file_ids: Vec<(PathBuf, FileId)>,
+    file_id_counter: u32,
+    file_hashes: Vec<(PathBuf, u64)>,
+    line_bases: Vec<(PathBuf, usize)>,
+    git_hash: Option<String>,
+    file_count: u32,
+    symbol_count: u32,
+}
+
+fn dump<K: Clone + Eq + std::hash::Hash, V: Clone>(map: &DashMap<K, V>) -> Vec<(K, V)> {
+    map.iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect()
+}
+
+fn restore<K: Eq + std::hash::Hash, V>(
+    map: &DashMap<K, V>,
+    entries: impl IntoIterator<Item = (K, V)>,
+) {
+    for (key, value) in entries {
+        map.insert(key, value);
+    }
+}
+
+impl OciState {
+    /// Write the symbol graph, imports, and topology to `path`.
+    ///
+    /// `files` are the fingerprints of the files the state was built from;
+    /// [`load_snapshot`](Self::load_snapshot) only accepts the snapshot while
+    /// they still match. File contents, overlays, and search indices are not
+    /// included.
+    pub fn save_snapshot(
+        &self,
+        path: &Path,
+        files: &HashMap<String, FileFingerprint>,
+    ) -> Result<()> {
+        let mut strings: Vec<(usize, String)> = self
+            .interner
+            .iter()
+            .map(|(key, value)| (key.into_usize(), value.to_string()))
+            .collect();
+        strings.sort_unstable_by_key(|(key, _)| *key);
+        if strings.iter().enumerate().any(|(i, (key, _))| i != *key) {
+            return Err(OmniError::corrupt(path, "interner keys are not contiguous"));
+        }
+
+        let header = SnapshotHeader {
+            version: SNAPSHOT_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            files: files.clone(),
+        };
+        let body = SnapshotBody {
+            strings: strings.into_iter().map(|(_, value)| value).collect(),
+            topology: self.topology.read().clone(),
+            path_to_node: dump(&self.path_to_node),
+            topology_metrics: dump(&self.topology_metrics),
+            symbols: self
+                .symbols
+                .iter()
+                .map(|entry| entry.value().clone())
+                .collect(),
+            name_to_scoped: dump(&self.name_to_scoped),
+            file_symbols: dump(&self.file_symbols),
+            call_edges: self.call_edges.read().clone(),
+            imports: dump(&self.imports),
+            trait_impls: dump(&self.trait_impls),
+            type_refs: dump(&self.type_refs),
+            stack: self.stack.read().clone(),
+            file_ids: dump(&self.file_ids),
+            file_id_counter: self.file_id_counter.load(Ordering::SeqCst),
+            file_hashes: dump(&self.file_hashes),
+            line_bases: dump(&self.line_bases),
+            git_hash: self.git_hash.read().clone(),
+            file_count: self.file_count.load(Ordering::SeqCst),
+            symbol_count: self.symbol_count.load(Ordering::SeqCst),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| OmniError::io(dir, e))?;
+        }
+        // Written aside and renamed into place, so that processes reading or
+        // mapping the previous snapshot (see `crate::shared`) keep a whole one
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        let file = fs::File::create(&partial).map_err(|e| OmniError::io(&partial, e))?;
+        let mut writer = BufWriter::new(file);
+        bincode::serialize_into(&mut writer, &header).map_err(|e| OmniError::corrupt(path, e))?;
+        bincode::serialize_into(&mut writer, &body).map_err(|e| OmniError::corrupt(path, e))?;
+        writer.flush().map_err(|e| OmniError::io(&partial, e))?;
+        drop(writer);
+        fs::rename(&partial, path).map_err(|e| OmniError::io(path, e))
+    }
+
+    /// Restore a snapshot written by [`save_snapshot`](Self::save_snapshot).
+    ///
+    /// Returns `Ok(false)` without touching the state if there is no
+    /// snapshot, it was written by another format or tool version, or its
+    /// fingerprints differ from `files`. The state must not have interned
+    /// anything yet, since the snapshot's keys are only valid in a fresh
+    /// interner.
+    pub fn load_snapshot(
+        &self,
+        path: &Path,
+        files: &HashMap<String, FileFingerprint>,
+    ) -> Result<bool> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(OmniError::io(path, e)),
+        };
+        self.load_snapshot_from(BufReader::new(file), path, Some(files))
+    }
+
+    /// [`load_snapshot`](Self::load_snapshot) from the snapshot bytes in
+    /// `reader`, read from `path`. With `files` of `None` the fingerprints
+    /// are not checked, for snapshots kept current by their writer.
+    pub fn load_snapshot_from(
+        &self,
+        mut reader: impl Read,
+        path: &Path,
+        files: Option<&HashMap<String, FileFingerprint>>,
+    ) -> Result<bool> {
+        let Ok(header) = bincode::deserialize_from::<_, SnapshotHeader>(&mut reader) else {
+            return Ok(false);
+        };
+        if header.version != SNAPSHOT_VERSION
+            || header.tool_version != env!("CARGO_PKG_VERSION")
+            || files.is_some_and(|files| header.files != *files)
+        {
+            return Ok(false);
+        }
+        if !self.interner.is_empty() {
+            return Err(OmniError::corrupt(
+                path,
+                "snapshots can only be loaded into a fresh state",
+            ));
+        }
+        let body: SnapshotBody =
+            bincode::deserialize_from(&mut reader).map_err(|e| OmniError::corrupt(path, e))?;
+
+        for (i, value) in body.strings.iter().enumerate() {
+            if self.interner.get_or_intern(value).into_usize() != i {
+                return Err(OmniError::corrupt(path, "duplicate interned string"));
+            }
+        }
+
+        self.reset();
+        *self.topology.write() = body.topology;
+        restore(&self.path_to_node, body.path_to_node);
+        restore(&self.topology_metrics, body.topology_metrics);
+        restore(
+            &self.symbols,
+            body.symbols.into_iter().map(|sym| (sym.scoped_name, sym)),
+        );
+        restore(&self.name_to_scoped, body.name_to_scoped);
+        restore(&self.file_symbols, body.file_symbols);
+        *self.call_edges.write() = body.call_edges;
+        restore(&self.imports, body.imports);
+        restore(&self.trait_impls, body.trait_impls);
+        restore(&self.type_refs, body.type_refs);
+        *self.stack.write() = body.stack;
+        restore(&self.file_ids, body.file_ids);
+        self.file_id_counter
+            .store(body.file_id_counter, Ordering::SeqCst);
+        restore(&self.file_hashes, body.file_hashes);
+        restore(&self.line_bases, body.line_bases);
+        *self.git_hash.write() = body.git_hash;
+        *self.last_indexed.write() = Some(Instant::now());
+        self.file_count.store(body.file_count, Ordering::SeqCst);
+        self.symbol_count.store(body.symbol_count, Ordering::SeqCst);
+        Ok(true)
+    }
+}
+
+/// In-memory contents of a file, standing in for what is on disk.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub contents: Arc<str>,
+    /// Search docs parsed from `contents`
+    pub docs: Vec<crate::query::SearchDoc>,
+}
+
 /// Statistics about the index.
 #[derive(Debug, Clone)]
 pub struct IndexStats {
@@ -314,7 +1008,41 @@ pub struct IndexStats {
     pub call_edge_count: u32,
     pub topology_node_count: u32,
     pub has_semantic_index: bool,
+    /// Availability of the semantic layer; hybrid search is BM25-only
+    /// unless it is ready
+    pub semantic_status: SemanticStatus,
     pub has_bm25_index: bool,
+    /// Files parsed since the state was created or reset
+    pub files_parsed: usize,
+    /// Index/update requests skipped because the file was unchanged
+    pub files_skipped: usize,
+    /// Embedding model breakdown of the loaded semantic index, or else of
+    /// the cached vectors, if any
+    pub embedding_drift: Option<EmbeddingDriftReport>,
+    /// Detected workspace stack, once indexed
+    pub stack: Option<StackReport>,
+}
+
+/// Manifests that mark the root of a crate or package.
+const PACKAGE_MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+];
+
+/// Nearest ancestor of `dir` (up to `root`) that holds a package manifest.
+pub(crate) fn package_root(dir: &Path, root: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        if PACKAGE_MANIFESTS.iter().any(|m| ancestor.join(m).is_file()) {
+            return Some(ancestor.to_path_buf());
+        }
+        if ancestor == root {
+            break;
+        }
+    }
+    None
 }
 
 /// Thread-safe shared state handle.
@@ -324,3 +1052,176 @@ pub type SharedState = Arc<OciState>;
 pub fn create_state(root_path: PathBuf) -> SharedState {
     Arc::new(OciState::new(root_path))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parser_for_file;
+
+    #[test]
+    fn test_lookups_normalize_raw_and_unicode_names() {
+        let source = "pub fn r#type() {}\npub fn caf\u{e9}() {}\nfn main() {\n    r#type();\n}\n";
+        let path = Path::new("lib.rs");
+        let state = OciState::new(PathBuf::from("/workspace"));
+        let parser = parser_for_file(path).unwrap();
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        for symbol in parser
+            .extract_symbols(&tree, source, path, &state.interner)
+            .unwrap()
+        {
+            state.add_symbol(symbol);
+        }
+        for call in parser
+            .extract_calls(&tree, source, path, &state.interner)
+            .unwrap()
+        {
+            state.add_call_edge(call);
+        }
+
+        let raw = state.find_by_name("r#type");
+        assert_eq!(raw.len(), 1);
+        assert_eq!(state.find_by_name("type").len(), 1);
+        assert_eq!(raw[0].display_name(&state), "r#type");
+        assert_eq!(state.resolve(raw[0].scoped_name), "crate::type");
+        assert_eq!(state.find_callers("r#type").len(), 1);
+
+        // Decomposed input finds the precomposed definition
+        assert_eq!(state.find_by_name("cafe\u{301}").len(), 1);
+        assert!(state.lookup("crate::cafe\u{301}").is_some());
+        assert_eq!(state.intern("cafe\u{301}"), state.intern("caf\u{e9}"));
+    }
+
+    #[test]
+    fn test_commit_file_applies_a_whole_file() {
+        let source = "struct A;\nimpl A {\n    fn new() -> A { A }\n}\nstruct B;\nimpl B {\n    fn new() -> B { B }\n}\nfn main() {\n    A::new();\n    B::new();\n}\n";
+        let path = Path::new("/workspace/lib.rs");
+        let state = OciState::new(PathBuf::from("/workspace"));
+        let parser = parser_for_file(path).unwrap();
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        let batch = FileBatch {
+            symbols: parser
+                .extract_symbols(&tree, source, path, &state.interner)
+                .unwrap(),
+            calls: parser
+                .extract_calls(&tree, source, path, &state.interner)
+                .unwrap(),
+            ..Default::default()
+        };
+        let symbols = batch.symbols.len();
+        state.commit_file(path, batch);
+
+        assert_eq!(state.symbol_count.load(Ordering::SeqCst) as usize, symbols);
+        assert_eq!(state.find_by_name("new").len(), 2);
+        assert_eq!(state.find_callers("new").len(), 2);
+        assert_eq!(state.file_count.load(Ordering::SeqCst), 1);
+
+        state.clear_file(&path.to_path_buf());
+        assert!(state.find_by_name("new").is_empty());
+        assert!(state.find_callers("new").is_empty());
+        assert!(state.symbols.is_empty());
+
+        state.commit_file(path, FileBatch::default());
+        assert!(state.file_symbols.is_empty());
+    }
+
+    #[test]
+    fn test_find_references_merges_calls_and_type_refs() {
+        let source = "struct Point;\nfn make() -> Point {\n    Point\n}\nfn use_it(p: &Point) {\n    make();\n}\n";
+        let path = Path::new("/workspace/lib.rs");
+        let state = OciState::new(PathBuf::from("/workspace"));
+        let parser = parser_for_file(path).unwrap();
+        let mut ts = tree_sitter::Parser::new();
+        ts.set_language(&parser.language()).unwrap();
+        let tree = ts.parse(source, None).unwrap();
+        let batch = FileBatch {
+            symbols: parser
+                .extract_symbols(&tree, source, path, &state.interner)
+                .unwrap(),
+            calls: parser
+                .extract_calls(&tree, source, path, &state.interner)
+                .unwrap(),
+            type_refs: parser.extract_type_refs(&tree, source, path).unwrap(),
+            ..Default::default()
+        };
+        state.commit_file(path, batch);
+
+        let refs = state.find_references("Point");
+        let from: Vec<&str> = refs
+            .iter()
+            .map(|r| r.from.map_or("", |key| state.resolve(key)))
+            .collect();
+        assert_eq!(from, vec!["crate::make", "crate::use_it"]);
+        assert!(refs.iter().all(|r| r.kind == ReferenceKind::TypeAnnotation));
+
+        let calls = state.find_references("crate::make");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].kind, ReferenceKind::Call);
+
+        state.clear_file(&path.to_path_buf());
+        assert!(state.find_references("Point").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trips_until_a_file_changes() {
+        use crate::incremental::IncrementalIndexer;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub mod util;\nuse crate::util::helper;\n\npub fn run() {\n    helper();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/util.rs"), "pub fn helper() {}\n").unwrap();
+        std::fs::write(root.join("src/web.ts"), "export function show() {}\n").unwrap();
+
+        let indexer = IncrementalIndexer::new();
+        let built = create_state(root.to_path_buf());
+        assert!(!indexer.full_index_cached(&built, root).await.unwrap());
+        assert!(crate::cache::oci_path(root).is_file());
+
+        let loaded = create_state(root.to_path_buf());
+        assert!(indexer.full_index_cached(&loaded, root).await.unwrap());
+        let (before, after) = (built.stats(), loaded.stats());
+        assert_eq!(after.symbol_count, before.symbol_count);
+        assert_eq!(after.call_edge_count, before.call_edge_count);
+        assert_eq!(after.topology_node_count, before.topology_node_count);
+        let helper = loaded.find_by_name("helper");
+        assert_eq!(helper.len(), 1);
+        assert_eq!(loaded.resolve(helper[0].scoped_name), "crate::helper");
+        let callers = loaded.find_callers("helper");
+        assert_eq!(callers.len(), 1);
+        assert_eq!(loaded.resolve(callers[0].caller), "crate::run");
+        let lib = loaded.file_ids.get(&root.join("src/lib.rs")).map(|id| *id);
+        assert_eq!(loaded.imports.get(&lib.unwrap()).unwrap()[0].name, "helper");
+        assert_eq!(
+            loaded.relevance_of_file(&root.join("src/util.rs")),
+            built.relevance_of_file(&root.join("src/util.rs"))
+        );
+        assert!(loaded.relevance_of_file(&root.join("src/util.rs")) > 0.0);
+        assert_eq!(
+            loaded.relevance_of_symbol(helper[0].scoped_name),
+            loaded.relevance_of_file(&root.join("src/util.rs"))
+        );
+        assert_eq!(loaded.relevance_of_file(&root.join("src/missing.rs")), 0.0);
+        assert_eq!(loaded.first_line(&root.join("src/web.ts")), 1);
+        assert_eq!(loaded.first_line(&root.join("src/lib.rs")), 0);
+
+        std::fs::write(
+            root.join("src/util.rs"),
+            "pub fn helper() {}\npub fn other() {}\n",
+        )
+        .unwrap();
+        let stale = create_state(root.to_path_buf());
+        assert!(!indexer.load_snapshot(&stale, root).unwrap());
+        assert_eq!(stale.stats().symbol_count, 0);
+        assert!(!indexer.full_index_cached(&stale, root).await.unwrap());
+        assert_eq!(stale.find_by_name("other").len(), 1);
+    }
+}
@@ -0,0 +1,424 @@
+//! Tests affected by a change, ranked for a fast feedback loop.
+//!
+//! Starting from a changed file (every symbol it defines) or a single symbol,
+//! walks call edges backwards to the test functions (`#[test]`,
+//! `#[tokio::test]`, `@Test`, ...) that reach it. Tests are ranked by how
+//! many calls separate them from the change, tests defined in the changed
+//! file first, so the head of the list is what an agent should run before
+//! anything else.
+//!
+//! Each Rust test also gets its cargo target (`--lib`, `--bin NAME`,
+//! `--test NAME`) and its path within that target, which is what
+//! `cargo test -- --exact` and nextest's `test(=...)` filters match.
+//!
+//! Each call is followed only to the definition it most likely targets (see
+//! [`crate::call_resolution`]), so changing one of several `parse` methods
+//! does not pull in the tests of the others.
+
+use crate::call_resolution::{CallerCandidate, resolve_callers};
+use crate::impact::crate_impact;
+use crate::state::OciState;
+use crate::types::{InternedString, SymbolDef, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+/// Tests reported when no limit is given.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// The cargo target a Rust test is compiled into.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, schemars::JsonSchema)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum TestTarget {
+    /// The library's unit tests (`--lib`)
+    Lib,
+    /// A binary's unit tests (`--bin NAME`)
+    Bin(String),
+    /// An integration test under `tests/` (`--test NAME`)
+    Test(String),
+}
+
+impl TestTarget {
+    /// The cargo flag selecting this target.
+    pub fn cargo_arg(&self) -> String {
+        match self {
+            Self::Lib => "--lib".to_string(),
+            Self::Bin(name) => format!("--bin {}", name),
+            Self::Test(name) => format!("--test {}", name),
+        }
+    }
+}
+
+/// A test reaching the change.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct AffectedTest {
+    /// Scoped name of the test function
+    pub symbol: String,
+    /// File relative to the workspace root
+    pub file: PathBuf,
+    pub line: usize,
+    /// Calls between the test and the change (0 for tests in the changed
+    /// file or the changed test itself)
+    pub depth: usize,
+    /// Call chain from the test to the changed symbol
+    pub via: Vec<String>,
+    /// Package owning the test, for `cargo test -p`
+    pub package: Option<String>,
+    /// Cargo target, for Rust tests
+    pub target: Option<TestTarget>,
+    /// Path of the test within its target, as matched by
+    /// `cargo test -- --exact` and nextest `test(=...)`
+    pub filter: Option<String>,
+}
+
+/// Tests affected by a change, most direct first.
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct AffectedTests {
+    /// Scoped names of the changed symbols the walk started from
+    pub changed: Vec<String>,
+    /// Affected tests found before applying the limit
+    pub total: usize,
+    pub tests: Vec<AffectedTest>,
+    /// One `cargo test` invocation per package and target
+    pub commands: Vec<String>,
+    /// nextest filter expression (`cargo nextest run -E '...'`)
+    pub nextest_filter: Option<String>,
+}
+
+/// Rank the tests affected by changing `spec`: a file (absolute or relative
+/// to the workspace root) or a symbol (scoped or simple name). Returns
+/// `None` if `spec` names nothing indexed.
+pub fn affected_tests(state: &OciState, spec: &str, limit: usize) -> Option<AffectedTests> {
+    let (changed_file, seeds) = resolve_seeds(state, spec);
+    if seeds.is_empty() {
+        return None;
+    }
+
+    // Callers of each name, attributed to the definition they most likely call
+    let mut resolved: HashMap<String, Vec<CallerCandidate>> = HashMap::new();
+    let mut parent: HashMap<InternedString, InternedString> = HashMap::new();
+    let mut seen: HashSet<InternedString> = seeds.iter().map(|s| s.scoped_name).collect();
+    let mut queue: VecDeque<(SymbolDef, usize)> = seeds.iter().cloned().map(|s| (s, 0)).collect();
+    let mut found = Vec::new();
+    while let Some((symbol, depth)) = queue.pop_front() {
+        if is_test_function(&symbol) {
+            found.push((symbol, depth));
+            continue;
+        }
+        let name = state.resolve(symbol.name);
+        let candidates = resolved
+            .entry(name.to_string())
+            .or_insert_with(|| resolve_callers(state, name));
+        let scoped = state.resolve(symbol.scoped_name);
+        let file = relative(state, &symbol.location.file);
+        let Some(candidate) = candidates
+            .iter()
+            .find(|c| c.callee == scoped && c.file == file)
+        else {
+            continue;
+        };
+        for caller in &candidate.callers {
+            let Some(def) = state
+                .lookup(&caller.caller)
+                .and_then(|key| state.get_symbol(key))
+            else {
+                continue;
+            };
+            if seen.insert(def.scoped_name) {
+                parent.insert(def.scoped_name, symbol.scoped_name);
+                queue.push_back((def, depth + 1));
+            }
+        }
+    }
+
+    found.sort_by(|(a, da), (b, db)| {
+        let in_changed = |s: &SymbolDef| Some(&s.location.file) != changed_file.as_ref();
+        da.cmp(db)
+            .then_with(|| in_changed(a).cmp(&in_changed(b)))
+            .then_with(|| a.location.file.cmp(&b.location.file))
+            .then_with(|| a.location.start_line.cmp(&b.location.start_line))
+    });
+    let total = found.len();
+    let mut packages: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let tests: Vec<AffectedTest> = found
+        .into_iter()
+        .take(limit)
+        .map(|(symbol, depth)| {
+            let file = &symbol.location.file;
+            let rel = relative(state, file);
+            let package = packages
+                .entry(file.clone())
+                .or_insert_with(|| crate_impact(state, file).map(|i| i.owner.name))
+                .clone();
+            let scoped = state.resolve(symbol.scoped_name);
+            let target = rust_target(&rel);
+            let filter = target.as_ref().map(|(_, module)| {
+                let item = scoped.strip_prefix("crate::").unwrap_or(scoped);
+                if module.is_empty() {
+                    item.to_string()
+                } else {
+                    format!("{}::{}", module, item)
+                }
+            });
+
+            let mut via = Vec::new();
+            let mut current = symbol.scoped_name;
+            while let Some(&next) = parent.get(&current) {
+                via.push(state.resolve(next).to_string());
+                current = next;
+            }
+            AffectedTest {
+                symbol: scoped.to_string(),
+                file: rel,
+                line: symbol.location.start_line,
+                depth,
+                via,
+                package,
+                target: target.map(|(target, _)| target),
+                filter,
+            }
+        })
+        .collect();
+
+    let (commands, nextest_filter) = invocations(&tests);
+    Some(AffectedTests {
+        changed: seeds
+            .iter()
+            .map(|s| state.resolve(s.scoped_name).to_string())
+            .collect(),
+        total,
+        tests,
+        commands,
+        nextest_filter,
+    })
+}
+
+/// The changed file, if `spec` is one, and the symbols to start from.
+fn resolve_seeds(state: &OciState, spec: &str) -> (Option<PathBuf>, Vec<SymbolDef>) {
+    let path = Path::new(spec);
+    for candidate in [path.to_path_buf(), state.root_path.join(path)] {
+        let candidate = candidate.canonicalize().unwrap_or(candidate);
+        let Some(file_id) = state.file_ids.get(&candidate).map(|id| *id) else {
+            continue;
+        };
+        let mut symbols: Vec<SymbolDef> = state
+            .file_symbols
+            .get(&file_id)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| state.get_symbol(*name))
+                    .filter(|symbol| symbol.location.file == candidate)
+                    .collect()
+            })
+            .unwrap_or_default();
+        symbols.sort_by_key(|s| s.location.start_byte);
+        return (Some(candidate), symbols);
+    }
+
+    if let Some(symbol) = state.lookup(spec).and_then(|key| state.get_symbol(key)) {
+        return (None, vec![symbol]);
+    }
+    (None, state.find_by_name(spec))
+}
+
+fn relative(state: &OciState, path: &Path) -> PathBuf {
+    path.strip_prefix(&state.root_path)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+fn is_test_function(symbol: &SymbolDef) -> bool {
+    matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) && symbol.is_test()
+}
+
+/// Cargo target of a root-relative Rust file and the module path of the
+/// file within that target (empty at the target root).
+fn rust_target(rel: &Path) -> Option<(TestTarget, String)> {
+    if rel.extension().is_none_or(|ext| ext != "rs") {
+        return None;
+    }
+    let parts: Vec<String> = rel
+        .with_extension("")
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    // The innermost `src` or `tests` directory is the package's
+    let start = parts[..parts.len() - 1]
+        .iter()
+        .rposition(|p| p == "src" || p == "tests")?;
+    let (kind, rest) = (parts[start].as_str(), &parts[start + 1..]);
+
+    let (target, module): (TestTarget, &[String]) = match (kind, rest) {
+        ("tests", [name, inner @ ..]) => (TestTarget::Test(name.clone()), inner),
+        ("src", [bin, name, inner @ ..]) if bin == "bin" => (TestTarget::Bin(name.clone()), inner),
+        ("src", [main]) if main == "main" => (TestTarget::Bin(String::new()), &[]),
+        ("src", rest) => (TestTarget::Lib, rest),
+        _ => return None,
+    };
+    let mut module = module.to_vec();
+    if module
+        .last()
+        .is_some_and(|last| matches!(last.as_str(), "mod" | "lib" | "main"))
+    {
+        module.pop();
+    }
+    Some((target, module.join("::")))
+}
+
+/// `cargo test` commands grouped by package and target, and the matching
+/// nextest filter expression.
+fn invocations(tests: &[AffectedTest]) -> (Vec<String>, Option<String>) {
+    let mut groups: BTreeMap<(Option<&str>, &TestTarget), Vec<&str>> = BTreeMap::new();
+    for test in tests {
+        if let (Some(target), Some(filter)) = (&test.target, &test.filter) {
+            let filters = groups.entry((test.package.as_deref(), target)).or_default();
+            if !filters.contains(&filter.as_str()) {
+                filters.push(filter);
+            }
+        }
+    }
+
+    let commands = groups
+        .iter()
+        .map(|((package, target), filters)| {
+            let mut command = "cargo test".to_string();
+            if let Some(package) = package {
+                command.push_str(&format!(" -p {}", package));
+            }
+            match target {
+                // `src/main.rs` is named after the package
+                TestTarget::Bin(name) if name.is_empty() => command.push_str(" --bins"),
+                target => command.push_str(&format!(" {}", target.cargo_arg())),
+            }
+            format!("{} -- --exact {}", command, filters.join(" "))
+        })
+        .collect();
+
+    let mut exprs: Vec<String> = groups
+        .values()
+        .flatten()
+        .map(|filter| format!("test(={})", filter))
+        .collect();
+    exprs.dedup();
+    let nextest = (!exprs.is_empty()).then(|| exprs.join(" | "));
+    (commands, nextest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+
+    #[test]
+    fn test_rust_targets() {
+        let cases = [
+            ("src/lib.rs", Some((TestTarget::Lib, ""))),
+            ("src/parsing/mod.rs", Some((TestTarget::Lib, "parsing"))),
+            (
+                "core/src/parsing/rust.rs",
+                Some((TestTarget::Lib, "parsing::rust")),
+            ),
+            (
+                "tests/query_tests.rs",
+                Some((TestTarget::Test("query_tests".into()), "")),
+            ),
+            (
+                "tests/e2e/main.rs",
+                Some((TestTarget::Test("e2e".into()), "")),
+            ),
+            (
+                "src/bin/tool.rs",
+                Some((TestTarget::Bin("tool".into()), "")),
+            ),
+            (
+                "src/bin/tool/args.rs",
+                Some((TestTarget::Bin("tool".into()), "args")),
+            ),
+            ("web/app.ts", None),
+        ];
+        for (path, expected) in cases {
+            let actual = rust_target(Path::new(path));
+            let expected = expected.map(|(t, m)| (t, m.to_string()));
+            assert_eq!(actual, expected, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ranks_tests_by_call_distance() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub mod store;\npub mod api;\n").unwrap();
+        std::fs::write(
+            root.join("src/store.rs"),
+            "pub fn persist_record() {}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn test_persist_record() {\n        persist_record();\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/api.rs"),
+            "pub fn handle_save() {\n    crate::store::persist_record();\n}\n\npub fn handle_ping() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn test_ping_only() {\n        super::handle_ping();\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tests/api_tests.rs"),
+            "#[test]\nfn saves_through_the_api() {\n    demo::api::handle_save();\n}\n",
+        )
+        .unwrap();
+
+        let state = create_state(root.clone());
+        IncrementalIndexer::new()
+            .full_index(&state, &root)
+            .await
+            .unwrap();
+
+        let affected = affected_tests(&state, "src/store.rs", DEFAULT_LIMIT).unwrap();
+        let ranked: Vec<(&str, usize)> = affected
+            .tests
+            .iter()
+            .map(|t| (t.filter.as_deref().unwrap(), t.depth))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("store::tests::test_persist_record", 0),
+                ("saves_through_the_api", 2),
+            ]
+        );
+        assert_eq!(
+            affected.tests[1].via,
+            vec!["crate::handle_save", "crate::persist_record"]
+        );
+        assert_eq!(
+            affected.tests[1].target,
+            Some(TestTarget::Test("api_tests".into()))
+        );
+        assert_eq!(affected.tests[0].package.as_deref(), Some("demo"));
+        assert_eq!(
+            affected.commands,
+            vec![
+                "cargo test -p demo --lib -- --exact store::tests::test_persist_record",
+                "cargo test -p demo --test api_tests -- --exact saves_through_the_api",
+            ]
+        );
+        assert_eq!(
+            affected.nextest_filter.as_deref(),
+            Some("test(=store::tests::test_persist_record) | test(=saves_through_the_api)")
+        );
+
+        let limited = affected_tests(&state, "handle_save", 1).unwrap();
+        assert_eq!(limited.total, 1);
+        assert_eq!(limited.tests[0].symbol, "crate::saves_through_the_api");
+        assert!(affected_tests(&state, "no_such_symbol", DEFAULT_LIMIT).is_none());
+    }
+}
@@ -0,0 +1,301 @@
+//! Deleted symbol tracking.
+//!
+//! When an incremental index sees a symbol disappear for good (not renamed,
+//! see [`crate::renames`], and not defined in any other file), it keeps a
+//! small tombstone: the name, where it was last defined, and the index run
+//! and commit that saw it go. `omni symbol --include-deleted` lists the
+//! tombstones matching a lookup, so "where did `handle_login` go?" still has
+//! an answer after the code is gone.
+//!
+//! Tombstones are persisted in `.omni/tombstones.json` and pruned once
+//! older than the retention window ([`DEFAULT_RETENTION_DAYS`] unless
+//! `omni index --tombstone-retention-days` set another). A name that is
+//! defined again loses its tombstone; `omni index --force` clears them with
+//! the rest of the cache.
+
+use crate::cache::{ensure_cache_dir, tombstones_path};
+use crate::error::{OmniError, Result};
+use crate::query::{DocKind, SearchDoc};
+use crate::renames::{head_commit, simple_name, today};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days a tombstone is kept unless configured otherwise.
+pub const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+/// Upper bound on kept tombstones; the oldest go first.
+pub const MAX_TOMBSTONES: usize = 5_000;
+
+/// Last known whereabouts of a deleted symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Tombstone {
+    /// Scoped name of the deleted symbol
+    pub name: String,
+    /// Root-relative file it was last defined in
+    pub file: String,
+    pub line: usize,
+    /// Index run that saw it deleted (see [`TombstoneLog::generation`])
+    pub generation: u64,
+    /// Date of the deletion (`YYYY-MM-DD`, UTC)
+    pub date: String,
+    /// Unix time of the deletion, for pruning
+    pub deleted_at: u64,
+    /// Short HEAD commit when the deletion was seen, if in a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl Tombstone {
+    /// Human-readable note, e.g.
+    /// `crate::handle_login was deleted from src/auth.rs:42 on 2024-05-01 (at 1a2b3c4)`.
+    pub fn note(&self) -> String {
+        let mut note = format!(
+            "{} was deleted from {}:{} on {}",
+            self.name, self.file, self.line, self.date
+        );
+        if let Some(commit) = &self.commit {
+            note.push_str(&format!(" (at {})", commit));
+        }
+        note
+    }
+
+    /// Whether a lookup for `name` (scoped or simple) refers to this symbol.
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || simple_name(&self.name) == name
+    }
+}
+
+/// Persisted tombstones for a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TombstoneLog {
+    /// Index runs that recorded deletions so far
+    pub generation: u64,
+    /// Days a tombstone is kept
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+    /// Oldest first
+    pub tombstones: Vec<Tombstone>,
+}
+
+impl Default for TombstoneLog {
+    fn default() -> Self {
+        Self {
+            generation: 0,
+            retention_days: DEFAULT_RETENTION_DAYS,
+            tombstones: Vec::new(),
+        }
+    }
+}
+
+fn default_retention_days() -> u64 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl TombstoneLog {
+    /// Load the log, or an empty one if none was written yet.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = tombstones_path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(&path).map_err(|e| OmniError::io(&path, e))?;
+        serde_json::from_slice(&data).map_err(|e| OmniError::corrupt(&path, e))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        ensure_cache_dir(root)?;
+        let path = tombstones_path(root);
+        let data = serde_json::to_vec_pretty(self).map_err(|e| OmniError::corrupt(&path, e))?;
+        fs::write(&path, data).map_err(|e| OmniError::io(&path, e))
+    }
+
+    /// Record the symbols of `deleted` (from [`detect_deletions`]) as one
+    /// new generation, replacing older tombstones of the same names.
+    pub fn record(&mut self, deleted: &[&SearchDoc], commit: Option<String>, now: u64) {
+        if deleted.is_empty() {
+            return;
+        }
+        self.generation += 1;
+        let names: HashSet<&str> = deleted.iter().map(|doc| doc.symbol.as_str()).collect();
+        self.tombstones.retain(|t| !names.contains(t.name.as_str()));
+        let date = today();
+        for doc in deleted {
+            self.tombstones.push(Tombstone {
+                name: doc.symbol.clone(),
+                file: doc.file.clone(),
+                line: doc.start_line,
+                generation: self.generation,
+                date: date.clone(),
+                deleted_at: now,
+                commit: commit.clone(),
+            });
+        }
+    }
+
+    /// Drop tombstones past the retention window, beyond
+    /// [`MAX_TOMBSTONES`], or whose name is defined again.
+    pub fn prune(&mut self, live: &HashSet<&str>, now: u64) {
+        let cutoff = now.saturating_sub(self.retention_days * 86_400);
+        self.tombstones
+            .retain(|t| t.deleted_at >= cutoff && !live.contains(t.name.as_str()));
+        let excess = self.tombstones.len().saturating_sub(MAX_TOMBSTONES);
+        self.tombstones.drain(..excess);
+    }
+
+    /// Tombstones within the retention window whose name matches `name`
+    /// (scoped or simple), most recent first.
+    pub fn lookup(&self, name: &str) -> Vec<&Tombstone> {
+        let cutoff = unix_now().saturating_sub(self.retention_days * 86_400);
+        self.tombstones
+            .iter()
+            .rev()
+            .filter(|t| t.deleted_at >= cutoff && t.matches(name))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tombstones.is_empty()
+    }
+}
+
+/// Symbols of `old_docs` that are gone from `live_docs` and were not
+/// renamed.
+///
+/// `old_docs` are the search docs of changed and removed files before
+/// re-indexing, `live_docs` every doc after it, and `renames` the output of
+/// [`crate::renames::detect_renames`]. A symbol moved to another file keeps
+/// its scoped name and is not reported.
+pub fn detect_deletions<'a>(
+    old_docs: &'a [SearchDoc],
+    live_docs: &[SearchDoc],
+    renames: &[(String, String, String)],
+) -> Vec<&'a SearchDoc> {
+    let live = live_names(live_docs);
+    let renamed: HashSet<&str> = renames.iter().map(|(old, _, _)| old.as_str()).collect();
+    let mut seen = HashSet::new();
+    old_docs
+        .iter()
+        .filter(|doc| doc.kind == DocKind::Symbol)
+        .filter(|doc| !live.contains(doc.symbol.as_str()) && !renamed.contains(doc.symbol.as_str()))
+        .filter(|doc| seen.insert(doc.symbol.as_str()))
+        .collect()
+}
+
+/// Record `deleted` (from [`detect_deletions`]) in the workspace's log and
+/// prune it, first setting the retention window to `retention_days` if
+/// given.
+pub fn record_deletions(
+    root: &Path,
+    deleted: &[&SearchDoc],
+    live_docs: &[SearchDoc],
+    retention_days: Option<u64>,
+) -> Result<()> {
+    if deleted.is_empty() && retention_days.is_none() && !tombstones_path(root).exists() {
+        return Ok(());
+    }
+    let mut log = TombstoneLog::load(root)?;
+    if let Some(days) = retention_days {
+        log.retention_days = days;
+    }
+    let now = unix_now();
+    let commit = if deleted.is_empty() {
+        None
+    } else {
+        head_commit(root)
+    };
+    for doc in deleted {
+        tracing::info!("Recorded deletion of {} from {}", doc.symbol, doc.file);
+    }
+    log.record(deleted, commit, now);
+    log.prune(&live_names(live_docs), now);
+    log.save(root)
+}
+
+fn live_names(docs: &[SearchDoc]) -> HashSet<&str> {
+    docs.iter()
+        .filter(|doc| doc.kind == DocKind::Symbol)
+        .map(|doc| doc.symbol.as_str())
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(file: &str, symbol: &str) -> SearchDoc {
+        SearchDoc {
+            symbol: symbol.to_string(),
+            file: file.to_string(),
+            start_byte: 0,
+            end_byte: 0,
+            start_line: 7,
+            end_line: 9,
+            start_col: 0,
+            end_col: 0,
+            preview: String::new(),
+            indexed_text: String::new(),
+            role: "core".to_string(),
+            deprecated: None,
+            kind: DocKind::Symbol,
+            is_test_context: false,
+        }
+    }
+
+    #[test]
+    fn test_deletions_skip_moves_and_renames() {
+        let old = vec![
+            doc("src/auth.rs", "crate::handle_login"),
+            doc("src/auth.rs", "crate::moved"),
+            doc("src/auth.rs", "crate::old_name"),
+            doc("src/auth.rs", "crate::kept"),
+        ];
+        let live = vec![
+            doc("src/auth.rs", "crate::kept"),
+            doc("src/session.rs", "crate::moved"),
+            doc("src/auth.rs", "crate::new_name"),
+        ];
+        let renames = vec![(
+            "crate::old_name".to_string(),
+            "crate::new_name".to_string(),
+            "src/auth.rs".to_string(),
+        )];
+        let deleted = detect_deletions(&old, &live, &renames);
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].symbol, "crate::handle_login");
+    }
+
+    #[test]
+    fn test_log_records_generations_and_prunes() {
+        let now = unix_now();
+        let gone = doc("src/auth.rs", "crate::handle_login");
+        let mut log = TombstoneLog::default();
+        log.record(&[&gone], Some("1a2b3c4".to_string()), now);
+        assert_eq!(log.generation, 1);
+        let found = log.lookup("handle_login");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].line, 7);
+        assert!(found[0].note().ends_with("(at 1a2b3c4)"));
+
+        // Defined again: the tombstone goes
+        log.prune(&HashSet::from(["crate::handle_login"]), now);
+        assert!(log.is_empty());
+
+        log.record(&[&gone], None, now);
+        assert_eq!(log.tombstones[0].generation, 2);
+        log.retention_days = 7;
+        log.prune(&HashSet::new(), now + 6 * 86_400);
+        assert_eq!(log.tombstones.len(), 1);
+        log.prune(&HashSet::new(), now + 8 * 86_400);
+        assert!(log.is_empty());
+    }
+}
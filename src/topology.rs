@@ -1,6 +1,8 @@
 //! Module topology graph builder.
 //!
 //! Builds the high-level view of crates, modules, and files with import relationships.
+//! Nested packages (see [`FileDiscovery::discover_packages`]) are separate
+//! crate nodes contained by their enclosing crate.
 
 use crate::discovery::FileDiscovery;
 use crate::parsing::parser_for_file;
@@ -33,19 +35,27 @@ impl TopologyBuilder {
         state.path_to_node.clear();
         state.topology_metrics.clear();
 
+        let discovery = FileDiscovery::new();
+        let packages = discovery.discover_packages(root)?;
+
         // Create root crate node
-        let crate_name = root
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("root")
-            .to_string();
+        let root_package = packages.iter().find(|p| p.path == root);
+        let crate_name = root_package.map_or_else(
+            || {
+                root.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("root")
+                    .to_string()
+            },
+            |p| p.name.clone(),
+        );
 
         let root_node = {
             let mut graph = state.topology.write();
             graph.add_node(TopologyNode::Crate {
                 name: crate_name,
                 path: root.to_path_buf(),
-                is_workspace: false,
+                is_workspace: root_package.is_some_and(|p| p.is_workspace),
             })
         };
 
@@ -56,8 +66,26 @@ impl TopologyBuilder {
             .topology_metrics
             .insert(root_node, TopologyMetrics::default());
 
+        // Nested packages become crates of their own, so their files attach
+        // to them rather than to the root. Parents sort before children.
+        for package in packages.iter().filter(|p| p.path != root) {
+            let node = {
+                let mut graph = state.topology.write();
+                graph.add_node(TopologyNode::Crate {
+                    name: package.name.clone(),
+                    path: package.path.clone(),
+                    is_workspace: package.is_workspace,
+                })
+            };
+            state.path_to_node.insert(package.path.clone(), node);
+            state
+                .topology_metrics
+                .insert(node, TopologyMetrics::default());
+            self.connect_to_parent(state, &package.path, node)?;
+        }
+        self.add_dependency_edges(state, &packages);
+
         // Discover all source files
-        let discovery = FileDiscovery::new();
         let files = discovery.discover(root)?;
 
         // Add all files to topology
@@ -75,10 +103,34 @@ impl TopologyBuilder {
 
         // Compute PageRank scores
         self.compute_pagerank(state)?;
+        state.invalidate_reexports();
 
         Ok(())
     }
 
+    /// Connect crates to the workspace crates their manifests depend on.
+    fn add_dependency_edges(&self, state: &OciState, packages: &[PackageRoot]) {
+        let node_of = |path: &Path| state.path_to_node.get(path).map(|n| *n);
+        let by_name: HashMap<&str, NodeIndex> = packages
+            .iter()
+            .filter_map(|p| Some((p.name.as_str(), node_of(&p.path)?)))
+            .collect();
+
+        let mut graph = state.topology.write();
+        for package in packages {
+            let Some(from) = node_of(&package.path) else {
+                continue;
+            };
+            for dep in &package.dependencies {
+                if let Some(&to) = by_name.get(dep.as_str()) {
+                    if to != from {
+                        graph.add_edge(from, to, TopologyEdge::DependsOn);
+                    }
+                }
+            }
+        }
+    }
+
     /// Add a file to the topology.
     pub fn add_file(&self, state: &OciState, path: &Path) -> Result<NodeIndex> {
         // Check if file already exists
@@ -109,7 +161,29 @@ impl TopologyBuilder {
         self.connect_to_parent(state, path, file_node)?;
 
         // Parse imports from the file
-        self.parse_file_imports(state, path, file_id)?;
+        let source = self.parse_file_imports(state, path, file_id)?;
+
+        // Classify with whatever symbols are already indexed for the file
+        let symbols: Vec<SymbolDef> = state
+            .file_symbols
+            .get(&file_id)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| state.get_symbol(*name))
+                    .filter(|symbol| symbol.location.file == path)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let imports = state
+            .imports
+            .get(&file_id)
+            .map(|imports| imports.clone())
+            .unwrap_or_default();
+        if let Some(mut metrics) = state.topology_metrics.get_mut(&file_node) {
+            let rel = path.strip_prefix(&state.root_path).unwrap_or(path);
+            metrics.role = classify_role(rel, &source, &symbols, &imports);
+        }
 
         Ok(file_node)
     }
@@ -284,8 +358,8 @@ impl TopologyBuilder {
         Ok(())
     }
 
-    /// Parse imports from a file and store them.
-    fn parse_file_imports(&self, state: &OciState, path: &Path, file_id: FileId) -> Result<()> {
+    /// Parse imports from a file and store them, returning the source.
+    fn parse_file_imports(&self, state: &OciState, path: &Path, file_id: FileId) -> Result<String> {
         // Read file contents
         let source =
             fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
@@ -293,7 +367,7 @@ impl TopologyBuilder {
         // Get parser for the file
         let lang_parser = match parser_for_file(path) {
             Some(p) => p,
-            None => return Ok(()), // Not a supported language
+            None => return Ok(source), // Not a supported language
         };
 
         // Parse with tree-sitter
@@ -311,9 +385,10 @@ impl TopologyBuilder {
 
         if !imports.is_empty() {
             state.imports.insert(file_id, imports);
+            state.invalidate_reexports();
         }
 
-        Ok(())
+        Ok(source)
     }
 
     /// Build import edges based on parsed imports.
@@ -351,14 +426,17 @@ impl TopologyBuilder {
             let target_node = self.resolve_import_target(state, &import.path);
 
             if let Some(target) = target_node {
-                edges_to_add.push((
-                    source_node,
-                    target,
+                let edge = if import.is_reexport {
+                    TopologyEdge::ReExports {
+                        original_path: import.path.clone(),
+                    }
+                } else {
                     TopologyEdge::Imports {
                         use_path: import.path.clone(),
                         is_glob: import.is_glob,
-                    },
-                ));
+                    }
+                };
+                edges_to_add.push((source_node, target, edge));
             }
         }
 
@@ -405,6 +483,186 @@ impl TopologyBuilder {
     }
 }
 
+/// Markers that tools put near the top of generated files.
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "auto-generated",
+    "autogenerated",
+    "code generated by",
+    "automatically generated",
+];
+
+/// Directory or file stems that name a role outright.
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec", "specs"];
+const TEST_FRAMEWORKS: &[&str] = &[
+    "org.junit",
+    "kotlin.test",
+    "vitest",
+    "jest",
+    "mocha",
+    "@testing-library",
+];
+const CONFIG_STEMS: &[&str] = &[
+    "config",
+    "configs",
+    "settings",
+    "configuration",
+    "options",
+    "constants",
+];
+const UTIL_STEMS: &[&str] = &[
+    "util", "utils", "helper", "helpers", "common", "support", "misc",
+];
+const MODEL_STEMS: &[&str] = &[
+    "types", "model", "models", "schema", "schemas", "entity", "entities", "dto", "dtos",
+];
+
+/// Whether `path` follows a test naming convention: a `tests/`-style
+/// directory, a `test_`/`_test` stem, or a `.test.`/`.spec.` suffix.
+///
+/// Unlike [`classify_role`] this ignores file contents, so a production
+/// module with an inline `mod tests` is not a test file. `path` should be
+/// relative to the repository root.
+pub fn is_test_path(path: &Path) -> bool {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let stem = file_name.split('.').next().unwrap_or_default();
+    let in_test_dir = path
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .any(|d| TEST_DIRS.contains(&d.to_ascii_lowercase().as_str()));
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_tests")
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+        || (stem.ends_with("test") && (file_name.ends_with(".java") || file_name.ends_with(".kt")))
+}
+
+/// Classify a file's role from its path, source, symbols, and imports.
+///
+/// `path` should be relative to the repository root, so that directories
+/// above the repository (e.g. a checkout under `~/tests/`) do not count.
+///
+/// Checks run from most to least specific: generated markers, test
+/// locations and attributes, entry points, config, re-export surfaces,
+/// utilities, and finally type-heavy files as data models.
+pub fn classify_role(
+    path: &Path,
+    source: &str,
+    symbols: &[SymbolDef],
+    imports: &[ImportInfo],
+) -> ModuleRole {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let stem = file_name.split('.').next().unwrap_or_default();
+    let dirs: Vec<String> = path
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let in_dir = |names: &[&str]| dirs.iter().any(|d| names.contains(&d.as_str()));
+
+    // Generated
+    let header = source
+        .lines()
+        .take(20)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_ascii_lowercase();
+    if file_name.contains(".generated.")
+        || file_name.contains("_generated")
+        || file_name.contains(".pb.")
+        || in_dir(&["generated"])
+        || GENERATED_MARKERS.iter().any(|m| header.contains(m))
+    {
+        return ModuleRole::Generated;
+    }
+
+    // Tests
+    let functions: Vec<&SymbolDef> = symbols
+        .iter()
+        .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+        .collect();
+    let test_functions = functions.iter().filter(|s| s.is_test()).count();
+    if is_test_path(path)
+        || (!functions.is_empty() && test_functions * 2 >= functions.len())
+        || imports
+            .iter()
+            .any(|i| TEST_FRAMEWORKS.iter().any(|f| i.path.starts_with(f)))
+    {
+        return ModuleRole::Tests;
+    }
+
+    // Entry points
+    let defines_main = source.lines().map(str::trim_start).any(|l| {
+        ["fn main(", "pub fn main(", "async fn main(", "fun main("]
+            .iter()
+            .any(|p| l.starts_with(p))
+            || l.contains("static void main(")
+    });
+    if stem == "main" || file_name == "build.rs" || in_dir(&["bin"]) || defines_main {
+        return ModuleRole::Entrypoint;
+    }
+
+    if CONFIG_STEMS.contains(&stem) || in_dir(&["config"]) {
+        return ModuleRole::Config;
+    }
+
+    // API surface: crate roots, barrels, and mostly re-exporting modules
+    let reexports = source
+        .lines()
+        .map(str::trim_start)
+        .filter(|l| l.starts_with("pub use ") || (l.starts_with("export ") && l.contains(" from ")))
+        .count();
+    let definitions = symbols
+        .iter()
+        .filter(|s| {
+            !matches!(
+                s.kind,
+                SymbolKind::Module | SymbolKind::Field | SymbolKind::Variant
+            )
+        })
+        .count();
+    if matches!(stem, "lib" | "index" | "__init__") || (reexports > 0 && reexports >= definitions) {
+        return ModuleRole::ApiSurface;
+    }
+
+    if UTIL_STEMS.contains(&stem) || in_dir(UTIL_STEMS) {
+        return ModuleRole::Utils;
+    }
+
+    let types = symbols
+        .iter()
+        .filter(|s| {
+            matches!(
+                s.kind,
+                SymbolKind::Struct | SymbolKind::Enum | SymbolKind::TypeAlias
+            )
+        })
+        .count();
+    if MODEL_STEMS.contains(&stem)
+        || in_dir(&["models", "entities"])
+        || (types >= 3 && types > functions.len() * 2)
+    {
+        return ModuleRole::DataModel;
+    }
+
+    ModuleRole::Implementation
+}
+
 impl Default for TopologyBuilder {
     fn default() -> Self {
         Self::new()
@@ -416,6 +674,7 @@ mod tests {
     use super::*;
     use crate::state::create_state;
     use std::fs;
+    use std::path::PathBuf;
     use tempfile::TempDir;
 
     #[test]
@@ -505,4 +764,150 @@ mod tests {
             .count();
         assert!(incoming > 0);
     }
+
+    #[test]
+    fn test_nested_packages_are_separate_crates() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"api\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("api/src")).unwrap();
+        fs::write(
+            root.join("api/Cargo.toml"),
+            "[package]\nname = \"acme-api\"\n",
+        )
+        .unwrap();
+        let api_lib = root.join("api/src/lib.rs");
+        fs::write(&api_lib, "pub fn serve() {}\n").unwrap();
+        fs::create_dir_all(root.join("tools")).unwrap();
+        let tool = root.join("tools/gen.rs");
+        fs::write(&tool, "fn main() {}\n").unwrap();
+
+        let state = create_state(root.to_path_buf());
+        TopologyBuilder::new().build(&state, root).unwrap();
+
+        let graph = state.topology.read();
+        let crate_of = |file: &Path| {
+            let mut node = *state.path_to_node.get(file).unwrap();
+            loop {
+                node = graph
+                    .edges_directed(node, Direction::Incoming)
+                    .find(|e| matches!(e.weight(), TopologyEdge::Contains))
+                    .unwrap()
+                    .source();
+                if let TopologyNode::Crate {
+                    name, is_workspace, ..
+                } = &graph[node]
+                {
+                    return (name.clone(), *is_workspace, node);
+                }
+            }
+        };
+
+        let (api, api_is_workspace, api_node) = crate_of(&api_lib);
+        assert_eq!(api, "acme-api");
+        assert!(!api_is_workspace);
+        let (root_name, root_is_workspace, root_node) = crate_of(&tool);
+        assert!(root_is_workspace);
+        assert_eq!(root_name, root.file_name().unwrap().to_string_lossy());
+        assert!(
+            graph
+                .edges_directed(api_node, Direction::Incoming)
+                .any(|e| e.source() == root_node)
+        );
+    }
+
+    fn role_of(path: &str, source: &str) -> ModuleRole {
+        let path = Path::new(path);
+        let state = create_state(PathBuf::from("/repo"));
+        let lang_parser = parser_for_file(path).unwrap();
+        let mut parser = Parser::new();
+        parser.set_language(&lang_parser.language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let symbols = lang_parser
+            .extract_symbols(&tree, source, path, &state.interner)
+            .unwrap();
+        let imports = lang_parser.extract_imports(&tree, source, path).unwrap();
+        classify_role(path, source, &symbols, &imports)
+    }
+
+    #[test]
+    fn test_classify_role() {
+        let body = "pub fn run() { step(); }\nfn step() {}\n";
+        assert_eq!(
+            role_of("/repo/src/engine.rs", body),
+            ModuleRole::Implementation
+        );
+        assert_eq!(
+            role_of("/repo/src/main.rs", "fn main() {}\n"),
+            ModuleRole::Entrypoint
+        );
+        assert_eq!(
+            role_of("/repo/tools/gen.rs", "fn main() {}\n"),
+            ModuleRole::Entrypoint
+        );
+        assert_eq!(role_of("/repo/tests/it.rs", body), ModuleRole::Tests);
+        assert_eq!(
+            role_of(
+                "/repo/src/engine.rs",
+                "#[test]\nfn a() {}\n#[test]\nfn b() {}\n"
+            ),
+            ModuleRole::Tests
+        );
+        assert_eq!(
+            role_of(
+                "/repo/src/engine.rs",
+                "// @generated by build.rs\npub fn f() {}\n"
+            ),
+            ModuleRole::Generated
+        );
+        assert_eq!(role_of("/repo/src/config.rs", body), ModuleRole::Config);
+        assert_eq!(role_of("/repo/src/lib.rs", body), ModuleRole::ApiSurface);
+        assert_eq!(
+            role_of(
+                "/repo/src/net/mod.rs",
+                "mod a;\npub use a::Client;\npub use a::Server;\n"
+            ),
+            ModuleRole::ApiSurface
+        );
+        assert_eq!(
+            role_of("/repo/src/utils/strings.rs", body),
+            ModuleRole::Utils
+        );
+        assert_eq!(
+            role_of(
+                "/repo/src/records.rs",
+                "pub struct A;\npub struct B { x: u8 }\npub enum C { X }\nfn f() {}\n"
+            ),
+            ModuleRole::DataModel
+        );
+    }
+
+    #[test]
+    fn test_add_file_records_role() {
+        let temp = TempDir::new().unwrap();
+        // Directories above the root do not affect the role
+        let root = temp.path().join("tests").join("repo");
+        fs::create_dir_all(&root).unwrap();
+        let state = create_state(root.clone());
+        let builder = TopologyBuilder::new();
+        builder.build(&state, &root).unwrap();
+
+        let file = root.join("main.rs");
+        fs::write(&file, "fn main() {}\n").unwrap();
+        builder.add_file(&state, &file).unwrap();
+        assert_eq!(state.file_role(&file), ModuleRole::Entrypoint);
+
+        let engine = root.join("engine.rs");
+        fs::write(&engine, "pub fn run() {}\n").unwrap();
+        builder.add_file(&state, &engine).unwrap();
+        assert_eq!(state.file_role(&engine), ModuleRole::Implementation);
+        assert_eq!(
+            state.file_role(&root.join("missing.rs")),
+            ModuleRole::Implementation
+        );
+    }
 }
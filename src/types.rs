@@ -6,6 +6,8 @@
 //! - Semantic Embeddings (Layer 3)
 
 use lasso::Spur;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 /// Interned string handle for memory-efficient symbol storage.
@@ -16,7 +18,7 @@ pub type InternedString = Spur;
 pub struct SymbolId(pub u32);
 
 /// Unique identifier for files in the index.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FileId(pub u32);
 
 // ============================================================================
@@ -24,7 +26,7 @@ pub struct FileId(pub u32);
 // ============================================================================
 
 /// Node types for the Module Topology Graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TopologyNode {
     /// Workspace or crate root
     Crate {
@@ -43,7 +45,7 @@ pub enum TopologyNode {
 }
 
 /// Edge types for the Module Topology Graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TopologyEdge {
     /// Parent contains child (crate -> module, module -> file)
     Contains,
@@ -51,10 +53,48 @@ pub enum TopologyEdge {
     Imports { use_path: String, is_glob: bool },
     /// Re-export via `pub use`
     ReExports { original_path: String },
+    /// Crate depends on another workspace crate (from its manifest)
+    DependsOn,
+}
+
+/// Manifest that marks a package root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageKind {
+    /// `Cargo.toml` (a package or a workspace)
+    Cargo,
+    /// `package.json`
+    Npm,
+    /// A nested git repository or submodule without a known manifest
+    Git,
+}
+
+impl PackageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Git => "git",
+        }
+    }
+}
+
+/// A package or repository root inside a workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRoot {
+    /// Package name from the manifest, or the directory name
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: PackageKind,
+    /// Whether the manifest declares members (`[workspace]`, `"workspaces"`,
+    /// `pnpm-workspace.yaml`)
+    pub is_workspace: bool,
+    /// Package names the manifest depends on, including dev and build
+    /// dependencies (sorted, deduplicated)
+    pub dependencies: Vec<String>,
 }
 
 /// Metrics for a topology node.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TopologyMetrics {
     /// PageRank score for relevance ranking
     pub relevance_score: f64,
@@ -62,6 +102,43 @@ pub struct TopologyMetrics {
     pub churn_count: u32,
     /// Test coverage percentage (0.0 - 1.0)
     pub coverage: Option<f32>,
+    /// Heuristic role of the file (files only)
+    pub role: ModuleRole,
+}
+
+/// What a file is for, classified heuristically from its path, symbols, and
+/// imports (see [`crate::topology::classify_role`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ModuleRole {
+    /// Program entry point (`main`, binaries)
+    Entrypoint,
+    /// Public surface that mostly re-exports (`lib.rs`, `index.ts`)
+    ApiSurface,
+    /// Type definitions with little behavior
+    DataModel,
+    Tests,
+    Utils,
+    Config,
+    /// Machine-generated code
+    Generated,
+    /// Anything else
+    #[default]
+    Implementation,
+}
+
+impl ModuleRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Entrypoint => "entrypoint",
+            Self::ApiSurface => "api",
+            Self::DataModel => "data-model",
+            Self::Tests => "tests",
+            Self::Utils => "utils",
+            Self::Config => "config",
+            Self::Generated => "generated",
+            Self::Implementation => "implementation",
+        }
+    }
 }
 
 // ============================================================================
@@ -69,7 +146,7 @@ pub struct TopologyMetrics {
 // ============================================================================
 
 /// Location of a syntax element in a file.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Location {
     pub file: PathBuf,
     pub start_byte: usize,
@@ -109,7 +186,7 @@ impl Location {
 }
 
 /// Kind of symbol in the codebase.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SymbolKind {
     Function,
     Method,
@@ -124,6 +201,10 @@ pub enum SymbolKind {
     Macro,
     Field,
     Variant,
+    /// A SQL table (see [`crate::parsing::sql`])
+    Table,
+    /// A SQL table column
+    Column,
 }
 
 impl SymbolKind {
@@ -142,12 +223,14 @@ impl SymbolKind {
             Self::Macro => "macro",
             Self::Field => "field",
             Self::Variant => "variant",
+            Self::Table => "table",
+            Self::Column => "column",
         }
     }
 }
 
 /// Visibility of a symbol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Visibility {
     #[default]
     Private,
@@ -161,8 +244,20 @@ pub enum Visibility {
     Public,
 }
 
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::Crate => "crate",
+            Self::Super => "super",
+            Self::Restricted => "restricted",
+            Self::Public => "public",
+        }
+    }
+}
+
 /// Function/method signature information.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Signature {
     pub params: Vec<String>,
     pub return_type: Option<String>,
@@ -174,30 +269,351 @@ pub struct Signature {
 }
 
 /// A symbol definition in the codebase.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolDef {
-    /// Simple name (e.g., "foo")
+    /// Simple name (e.g., "foo"), normalized (see
+    /// [`crate::parsing::normalize_identifier`])
     pub name: InternedString,
+    /// Name as written in source when it differs from `name` (e.g.
+    /// `r#type`), for display
+    pub raw_name: Option<String>,
     /// Fully qualified scoped name (e.g., "crate::module::Struct::foo")
     pub scoped_name: InternedString,
     /// Kind of symbol
     pub kind: SymbolKind,
     /// Source location
     pub location: Location,
+    /// `location` widened upward over the doc comments, comments and
+    /// attributes directly above the symbol; `None` when nothing is attached
+    /// (see [`crate::parsing::attach_extended_locations`])
+    pub extended_location: Option<Location>,
     /// Signature (for functions/methods)
     pub signature: Option<Signature>,
     /// Visibility
     pub visibility: Visibility,
-    /// Attributes (e.g., #[test], #[derive(...)])
-    pub attributes: Vec<String>,
+    /// Attributes and annotations (e.g., `#[test]`, `#[derive(...)]`, `@Override`)
+    pub attributes: Vec<Attribute>,
+    /// Rules suppressed by `omni:ignore` comments (see
+    /// [`crate::parsing::attach_ignore_directives`])
+    pub ignores: Vec<String>,
     /// Documentation comments
     pub doc_comment: Option<String>,
     /// Parent symbol (for methods -> impl, fields -> struct)
     pub parent: Option<InternedString>,
+    /// Defined in test code: a test file, or inside a test function or
+    /// `#[cfg(test)]` module (see [`crate::parsing::mark_test_context`])
+    pub is_test_context: bool,
+}
+
+/// `omni:ignore` rule honored by dead code analyses.
+pub const IGNORE_DEAD_CODE: &str = "dead_code";
+/// `omni:ignore` rule honored by duplication checks and clone analyses.
+pub const IGNORE_DUPLICATION: &str = "duplication";
+/// `omni:ignore` rule honored by naming conflict checks.
+pub const IGNORE_NAMING: &str = "naming";
+/// Rule recorded for a bare `omni:ignore`, suppressing every rule.
+pub const IGNORE_ALL: &str = "all";
+
+impl SymbolDef {
+    /// Name for display: the source spelling (e.g. `r#type`) if it was
+    /// normalized, otherwise the interned name.
+    pub fn display_name<'a>(&'a self, state: &'a crate::state::OciState) -> &'a str {
+        self.raw_name
+            .as_deref()
+            .unwrap_or_else(|| state.resolve(self.name))
+    }
+
+    /// Span including attached doc comments and attributes, e.g. for
+    /// showing a definition in full.
+    pub fn full_location(&self) -> &Location {
+        self.extended_location.as_ref().unwrap_or(&self.location)
+    }
+
+    /// Whether an `omni:ignore` directive suppresses `rule` for this symbol.
+    pub fn is_ignored(&self, rule: &str) -> bool {
+        self.ignores.iter().any(|r| r == rule || r == IGNORE_ALL)
+    }
+
+    /// First attribute whose simple name matches `name` (case-insensitive).
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes
+            .iter()
+            .find(|a| a.simple_name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether every query is satisfied by some attribute (see [`Attribute::matches`]).
+    pub fn has_attributes(&self, queries: &[Attribute]) -> bool {
+        queries
+            .iter()
+            .all(|q| self.attributes.iter().any(|a| a.matches(q)))
+    }
+
+    /// Traits named in `#[derive(...)]` attributes.
+    pub fn derives(&self) -> impl Iterator<Item = &str> {
+        self.attributes
+            .iter()
+            .filter(|a| a.name == "derive")
+            .flat_map(|a| a.arg_list())
+    }
+
+    /// Predicates of `#[cfg(...)]` attributes.
+    pub fn cfgs(&self) -> impl Iterator<Item = &str> {
+        self.attributes
+            .iter()
+            .filter(|a| a.name == "cfg")
+            .filter_map(|a| a.args.as_deref())
+    }
+
+    /// Whether a test marker (`#[test]`, `#[tokio::test]`, `@Test`, ...) or
+    /// `#[cfg(test)]` is attached.
+    pub fn is_test(&self) -> bool {
+        self.attributes.iter().any(Attribute::is_test_marker)
+            || self.cfgs().any(|c| {
+                c.split(|ch: char| !ch.is_alphanumeric())
+                    .any(|w| w == "test")
+            })
+    }
+
+    /// Deprecation note if the symbol is deprecated (empty when no note is given).
+    ///
+    /// Reads `#[deprecated]`/`@Deprecated` attributes and `@deprecated` doc
+    /// comment tags (JSDoc, Javadoc, KDoc).
+    pub fn deprecation(&self) -> Option<&str> {
+        self.attribute("deprecated")
+            .map(Attribute::deprecation_note)
+            .or_else(|| self.doc_comment.as_deref().and_then(doc_deprecation))
+    }
+
+    /// Name of the documented replacement of a deprecated symbol, taken from
+    /// Kotlin's `ReplaceWith("..")` or the deprecation note (e.g. "use
+    /// `load_all`", "replaced by loadAll", "{@link #loadAll}").
+    pub fn deprecation_replacement(&self) -> Option<&str> {
+        let replace_with = self.attribute("deprecated").and_then(|a| {
+            a.arg_list().into_iter().find_map(|arg| {
+                let inner = arg.strip_prefix("ReplaceWith(")?.trim_start();
+                inner.strip_prefix('"')?.split('"').next()
+            })
+        });
+        replace_with
+            .or_else(|| self.deprecation().and_then(note_replacement))
+            .map(leading_path)
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// Text following an `@deprecated` doc tag, up to the end of its line.
+fn doc_deprecation(doc: &str) -> Option<&str> {
+    const TAG: &str = "@deprecated";
+    let idx = doc.find(TAG)?;
+    let rest = &doc[idx + TAG.len()..];
+    if rest.starts_with(|c: char| c.is_alphanumeric()) {
+        return None;
+    }
+    let line = rest.lines().next().unwrap_or("");
+    Some(line.trim().trim_end_matches("*/").trim())
+}
+
+/// Replacement named in a free-form deprecation note.
+fn note_replacement(note: &str) -> Option<&str> {
+    if let Some(idx) = note.find("{@link") {
+        return Some(note[idx + "{@link".len()..].trim_start());
+    }
+    let lower = note.to_ascii_lowercase();
+    if !["use", "replace", "instead", "prefer"]
+        .iter()
+        .any(|w| lower.contains(w))
+    {
+        return None;
+    }
+    if let Some((_, quoted)) = note.split_once('`') {
+        return quoted.split('`').next();
+    }
+    ["replaced by ", "replaced with ", "prefer ", "use "]
+        .iter()
+        .find_map(|marker| {
+            lower
+                .find(marker)
+                .map(|i| note[i + marker.len()..].trim_start())
+        })
+}
+
+/// Leading identifier path of `text` (e.g. "Foo::bar" from "Foo::bar() instead").
+fn leading_path(text: &str) -> &str {
+    let text = text.trim_start_matches('#');
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '#' | '$')))
+        .unwrap_or(text.len());
+    text[..end].trim_end_matches(['.', ':', '#'])
+}
+
+/// A parsed attribute or annotation, e.g. `#[derive(Debug, Clone)]` or
+/// `@Deprecated("use bar")`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribute {
+    /// Path without sigils (e.g., "derive", "tokio::test", "org.junit.Test")
+    pub name: String,
+    /// Text between the delimiters or after `=`, whitespace collapsed
+    pub args: Option<String>,
+}
+
+impl Attribute {
+    pub fn new(name: impl Into<String>, args: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            args,
+        }
+    }
+
+    /// Parse Rust attribute (`#[..]`, `#![..]`) or Java/Kotlin annotation
+    /// (`@Name(..)`, `@get:Name`) source text.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let body = if let Some(rest) = text.strip_prefix('#') {
+            rest.trim_start_matches('!')
+                .trim()
+                .strip_prefix('[')?
+                .strip_suffix(']')?
+        } else {
+            let rest = text.strip_prefix('@')?;
+            // Kotlin use-site targets (`@get:Rule`, `@file:JvmName(..)`)
+            match rest.split_once(':') {
+                Some((target, name))
+                    if !name.starts_with(':')
+                        && target.chars().all(|c| c.is_ascii_alphabetic()) =>
+                {
+                    name
+                }
+                _ => rest,
+            }
+        };
+        let body = body.trim();
+
+        let name_end = body
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | ':' | '.')))
+            .unwrap_or(body.len());
+        let name = &body[..name_end];
+        if name.is_empty() {
+            return None;
+        }
+        let rest = body[name_end..].trim();
+        let args = if let Some(value) = rest.strip_prefix('=') {
+            Some(value.trim())
+        } else {
+            rest.strip_prefix(['(', '['])
+                .and_then(|r| r.strip_suffix([')', ']']))
+        }
+        .map(|a| a.split_whitespace().collect::<Vec<_>>().join(" "));
+
+        // Edition 2024 unsafe attributes: `#[unsafe(no_mangle)]`
+        if name == "unsafe" {
+            return args.and_then(|inner| Self::parse(&format!("#[{inner}]")));
+        }
+        Some(Self::new(name, args))
+    }
+
+    /// Last path segment (e.g., "test" for `tokio::test`, "Test" for `org.junit.Test`).
+    pub fn simple_name(&self) -> &str {
+        self.name.rsplit([':', '.']).next().unwrap_or(&self.name)
+    }
+
+    /// Top-level comma-separated arguments.
+    pub fn arg_list(&self) -> Vec<&str> {
+        let Some(args) = self.args.as_deref() else {
+            return Vec::new();
+        };
+        let mut parts = Vec::new();
+        let (mut depth, mut start, mut in_string) = (0i32, 0, false);
+        for (i, c) in args.char_indices() {
+            match c {
+                '"' => in_string = !in_string,
+                _ if in_string => {}
+                '(' | '[' | '{' | '<' => depth += 1,
+                ')' | ']' | '}' | '>' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(args[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(args[start..].trim());
+        parts.retain(|p| !p.is_empty());
+        parts
+    }
+
+    /// Parse an attribute query: `deprecated`, `tokio::main`, `derive(Serialize)`,
+    /// or the same written as source (`#[..]`, `@Name`).
+    pub fn query(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let query = if text.starts_with(['#', '@']) {
+            Self::parse(text)
+        } else {
+            Self::parse(&format!("#[{text}]"))
+        };
+        // `parse` drops unbalanced arguments; a query must not widen silently
+        query.filter(|q| q.args.is_some() || !text.contains(['(', '=']))
+    }
+
+    /// Whether this attribute satisfies `query` (case-insensitive).
+    ///
+    /// Names match on whole trailing path segments, so `main` matches
+    /// `tokio::main` and `Test` matches `org.junit.Test`. Every argument of the
+    /// query must match one of ours the same way: `derive(Serialize)` matches
+    /// `#[derive(Debug, serde::Serialize)]`.
+    pub fn matches(&self, query: &Attribute) -> bool {
+        fn path_matches(path: &str, query: &str) -> bool {
+            let (path, query) = (path.to_ascii_lowercase(), query.to_ascii_lowercase());
+            path == query
+                || path
+                    .strip_suffix(&query)
+                    .is_some_and(|head| head.ends_with("::") || head.ends_with('.'))
+        }
+        if !path_matches(&self.name, &query.name) {
+            return false;
+        }
+        let ours = self.arg_list();
+        query
+            .arg_list()
+            .iter()
+            .all(|wanted| ours.iter().any(|arg| path_matches(arg, wanted)))
+    }
+
+    /// Test markers such as `test`, `tokio::test`, `rstest`, `@Test`, `@ParameterizedTest`.
+    pub fn is_test_marker(&self) -> bool {
+        self.simple_name().to_ascii_lowercase().ends_with("test")
+    }
+
+    /// The human-readable note from deprecation arguments: `note = ".."`,
+    /// `message = ".."`, or a bare string literal.
+    fn deprecation_note(&self) -> &str {
+        fn quoted(s: &str) -> Option<&str> {
+            let s = s.trim().strip_prefix('"')?;
+            s.find('"').map(|end| &s[..end])
+        }
+        let args = self.arg_list();
+        args.iter()
+            .find_map(|a| quoted(a))
+            .or_else(|| {
+                args.iter()
+                    .filter_map(|a| a.split_once('='))
+                    .find(|(k, _)| matches!(k.trim(), "note" | "message"))
+                    .and_then(|(_, v)| quoted(v))
+            })
+            .unwrap_or("")
+    }
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.args {
+            Some(args) => write!(f, "{}({})", self.name, args),
+            None => f.write_str(&self.name),
+        }
+    }
 }
 
 /// A call edge in the call graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallEdge {
     /// Scoped name of the caller
     pub caller: InternedString,
@@ -207,17 +623,127 @@ pub struct CallEdge {
     pub location: Location,
     /// Whether this is a method call (has receiver)
     pub is_method_call: bool,
+    /// Trait the call is dispatched through, for `dyn Trait` receivers
+    pub via_trait: Option<String>,
+    /// Whether this edge is an approximation (e.g. a `dyn Trait` call fanned
+    /// out to every impl); `callee_name` is then the impl's scoped name
+    pub is_approximate: bool,
+    /// Explicit generic arguments at the call site, e.g. `<u32>` for
+    /// `parse::<u32>()`
+    pub type_args: Option<String>,
+    /// Made from test code (see [`SymbolDef::is_test_context`])
+    pub is_test_context: bool,
+    /// The definition this call was bound to by
+    /// [`crate::call_resolution::resolve_call_edges`], from the call's
+    /// qualifier and the caller's imports; `None` when no candidate stands
+    /// out or the callee is outside the workspace
+    pub resolved_callee: Option<InternedString>,
 }
 
-/// Import information from `use` statements.
+/// Counts of symbols or calls from production and from test code (see
+/// [`SymbolDef::is_test_context`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct TestSplit {
+    pub production: usize,
+    pub tests: usize,
+}
+
+impl TestSplit {
+    /// Count `is_test_context` flags.
+    pub fn count(flags: impl IntoIterator<Item = bool>) -> Self {
+        flags.into_iter().fold(Self::default(), |mut split, test| {
+            if test {
+                split.tests += 1;
+            } else {
+                split.production += 1;
+            }
+            split
+        })
+    }
+}
+
+/// An `impl Trait for Type` block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraitImpl {
+    /// Trait name (last path segment)
+    pub trait_name: String,
+    /// Implementing type (simple name)
+    pub type_name: String,
+    /// Scoped names of the methods defined in the impl
+    pub methods: Vec<InternedString>,
+    /// Location of the impl block
+    pub location: Location,
+}
+
+/// How a [`Reference`] uses the name it refers to.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// A call (see [`CallEdge`])
+    Call,
+    /// A struct or struct variant literal (`Foo { .. }`)
+    StructLiteral,
+    /// A type in a signature, field, binding, cast, or generic argument
+    TypeAnnotation,
+    /// A trait bound (`T: Trait`, `where T: Trait`, `impl Trait`,
+    /// `dyn Trait`) or supertrait
+    TraitBound,
+    /// The trait or the type of an `impl` block
+    ImplTarget,
+}
+
+impl ReferenceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Call => "call",
+            Self::StructLiteral => "struct literal",
+            Self::TypeAnnotation => "type",
+            Self::TraitBound => "trait bound",
+            Self::ImplTarget => "impl",
+        }
+    }
+}
+
+/// A use of a type or trait by name, other than a call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeRef {
+    /// Referenced name (last path segment, without generics)
+    pub name: String,
+    pub kind: ReferenceKind,
+    /// Location of the name
+    pub location: Location,
+}
+
+/// A usage of a name, as found by
+/// [`OciState::find_references`](crate::state::OciState::find_references).
 #[derive(Debug, Clone)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    pub location: Location,
+    /// Scoped name of the innermost symbol containing the usage (the
+    /// caller, for calls)
+    pub from: Option<InternedString>,
+    /// Made from test code (see [`SymbolDef::is_test_context`])
+    pub is_test_context: bool,
+}
+
+/// Import information from `use` statements.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportInfo {
     /// The full use path (e.g., "std::collections::HashMap")
     pub path: String,
-    /// Imported name or alias
+    /// Local binding: the imported name, or its alias
     pub name: String,
+    /// Name of the imported item when it is bound under an alias
+    /// (`use a::B as C`, `import { B as C }`)
+    pub original_name: Option<String>,
     /// Whether it's a glob import (use foo::*)
     pub is_glob: bool,
+    /// Whether the import is public (`pub use`), re-exporting the item from
+    /// the importing module
+    pub is_reexport: bool,
     /// Location of the use statement
     pub location: Location,
 }
@@ -233,6 +759,90 @@ pub struct SemanticEntry {
     pub scoped_name: InternedString,
     /// Embedding vector (may be quantized)
     pub embedding: EmbeddingData,
+    /// Model that produced the embedding
+    pub model: EmbeddingModelInfo,
+}
+
+/// Identity of the model that produced an embedding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EmbeddingModelInfo {
+    pub name: String,
+    pub version: String,
+}
+
+impl EmbeddingModelInfo {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for EmbeddingModelInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// Breakdown of embeddings by producing model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, schemars::JsonSchema)]
+pub struct EmbeddingDriftReport {
+    /// Model currently used for new embeddings (`name@version`)
+    pub current_model: String,
+    /// Vector count per model (`name@version`)
+    pub vectors_by_model: BTreeMap<String, usize>,
+    /// Vectors produced by a model other than the current one
+    pub stale_vectors: usize,
+}
+
+impl EmbeddingDriftReport {
+    /// True if vectors from more than one model are present.
+    pub fn is_mixed(&self) -> bool {
+        self.vectors_by_model.len() > 1
+    }
+
+    /// True if any vector needs re-embedding.
+    pub fn has_drift(&self) -> bool {
+        self.stale_vectors > 0
+    }
+}
+
+/// Availability of the semantic layer.
+///
+/// Without an embedding model the index runs degraded: semantic features
+/// are unavailable and hybrid search falls back to BM25 only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SemanticStatus {
+    /// Built without the `semantic` feature
+    #[default]
+    Disabled,
+    /// No build attempted yet
+    NotBuilt,
+    /// Loading the model and embedding symbols
+    Building,
+    /// Semantic index ready
+    Ready,
+    /// The embedding model could not be loaded (e.g. offline and not yet
+    /// downloaded); retried in the background
+    Unavailable { reason: String },
+}
+
+impl SemanticStatus {
+    /// Short status line, e.g. "ready" or "unavailable (model missing)".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::NotBuilt => "not built",
+            Self::Building => "building",
+            Self::Ready => "ready",
+            Self::Unavailable { .. } => "unavailable (model missing)",
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Self::Ready)
+    }
 }
 
 /// Embedding storage format.
@@ -266,6 +876,111 @@ pub struct DeadCodeReport {
     pub entry_points: Vec<InternedString>,
     /// Symbols marked as potentially live (conservative)
     pub potentially_live: Vec<InternedString>,
+    /// Unreachable symbols suppressed by `omni:ignore dead_code`
+    pub ignored: Vec<InternedString>,
+    /// Why each dead symbol is unreachable
+    pub explanations: HashMap<InternedString, DeadExplanation>,
+}
+
+/// Why a symbol was reported dead, for triaging false positives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadExplanation {
+    /// Visibility that kept it from being an entry point
+    pub visibility: Visibility,
+    pub reason: DeadReason,
+}
+
+/// How a dead symbol's callers (or lack of them) leave it unreachable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadReason {
+    /// No call sites were found
+    NoCallers,
+    /// Every caller is itself dead. `chain` walks callers from the symbol up
+    /// to a dead symbol with no callers, or until it would revisit one
+    /// (`cyclic`).
+    DeadCallers {
+        callers: Vec<InternedString>,
+        chain: Vec<InternedString>,
+        cyclic: bool,
+    },
+    /// Only called from code behind `#[cfg(...)]` that is never reached,
+    /// most likely because the predicate is disabled
+    CfgOnly {
+        callers: Vec<InternedString>,
+        cfgs: Vec<String>,
+    },
+    /// Only called from test code, when tests are excluded from the analysis
+    TestOnly { callers: Vec<InternedString> },
+}
+
+impl DeadReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoCallers => "no_callers",
+            Self::DeadCallers { .. } => "dead_callers",
+            Self::CfgOnly { .. } => "cfg_only",
+            Self::TestOnly { .. } => "test_only",
+        }
+    }
+}
+
+/// Kind of a Cargo binary target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BinaryKind {
+    /// `src/main.rs`, `src/bin/*`, or a `[[bin]]` entry
+    Bin,
+    /// `examples/*` or an `[[example]]` entry
+    Example,
+}
+
+impl BinaryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bin => "bin",
+            Self::Example => "example",
+        }
+    }
+}
+
+/// A binary target with a `main` entry point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryTarget {
+    /// Target name (what `cargo run --bin`/`--example` expects)
+    pub name: String,
+    pub kind: BinaryKind,
+    /// Package that owns the target
+    pub package: String,
+    /// Path of the file defining `main`
+    pub path: PathBuf,
+}
+
+/// Symbols reachable from a binary's `main`.
+#[derive(Debug, Clone)]
+pub struct BinaryReachability {
+    pub target: BinaryTarget,
+    /// Scoped name of the binary's `main`, if it was indexed
+    pub entry: Option<InternedString>,
+    /// Reachable symbols with their call depth from `main`, shallowest first
+    pub reachable: Vec<(InternedString, u32)>,
+}
+
+/// Result of per-binary reachability analysis.
+#[derive(Debug, Clone)]
+pub struct BinaryUsageReport {
+    pub binaries: Vec<BinaryReachability>,
+    /// Functions and methods no binary reaches (library-only or dead)
+    pub unused: Vec<InternedString>,
+}
+
+/// Call-site instantiations of a generic function.
+#[derive(Debug, Clone)]
+pub struct GenericHotspot {
+    /// Scoped name of the generic function
+    pub symbol: InternedString,
+    /// Total call sites resolving to the function
+    pub call_sites: usize,
+    /// Distinct explicit type arguments seen at call sites, sorted
+    pub instantiations: Vec<String>,
 }
 
 /// Coverage data for a symbol.
@@ -293,6 +1008,16 @@ pub enum InterventionSeverity {
     Block,
 }
 
+impl InterventionSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Block => "block",
+        }
+    }
+}
+
 /// An intervention triggered by duplicate detection.
 #[derive(Debug, Clone)]
 pub struct Intervention {
@@ -304,6 +1029,19 @@ pub struct Intervention {
     pub recommendation: String,
 }
 
+/// Interventions for one symbol of a proposed file (see
+/// [`crate::intervention::InterventionEngine::check_file`]).
+#[derive(Debug, Clone)]
+pub struct SymbolInterventions {
+    /// Scoped name in the proposed file
+    pub symbol: InternedString,
+    pub kind: SymbolKind,
+    /// Line of the definition in the proposed content
+    pub line: usize,
+    /// Most severe first
+    pub interventions: Vec<Intervention>,
+}
+
 /// Result of similarity detection.
 #[derive(Debug, Clone)]
 pub struct SimilarityMatch {
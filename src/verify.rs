@@ -0,0 +1,310 @@
+//! Index health checks.
+//!
+//! Asserts structural invariants of a built index so that parser and indexer
+//! changes that corrupt it fail loudly (e.g. in CI via `omni verify`):
+//!
+//! - every call edge points at an indexed file
+//! - every symbol location lies within its file
+//! - no file defines the same scoped name twice with the same kind (and
+//!   parameters, so overloads are allowed)
+//! - every interned key held by the index resolves
+//!
+//! Rust scoped names are not qualified by file module, so the same scoped
+//! name in different files is expected and only same-file duplicates count.
+
+use crate::parsing;
+use crate::state::OciState;
+use crate::types::{InternedString, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// A violated invariant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+pub struct Violation {
+    /// Invariant name: `call_edge_file`, `symbol_bounds`, `duplicate_symbol`, `interner`
+    pub invariant: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// Result of verifying an index.
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct VerifyReport {
+    pub files: usize,
+    pub symbols: usize,
+    pub call_edges: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl VerifyReport {
+    /// Whether every invariant holds.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Violation counts per invariant.
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for v in &self.violations {
+            *counts.entry(v.invariant).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Check all invariants against an indexed state.
+pub fn verify(state: &OciState) -> VerifyReport {
+    let mut report = VerifyReport {
+        files: state.file_ids.len(),
+        symbols: state.symbols.len(),
+        call_edges: state.call_edges.read().len(),
+        violations: Vec::new(),
+    };
+
+    check_interner(state, &mut report.violations);
+    check_call_edges(state, &mut report.violations);
+
+    let mut files: Vec<PathBuf> = state.file_ids.iter().map(|e| e.key().clone()).collect();
+    files.sort();
+    for file in &files {
+        let Ok(contents) = std::fs::read_to_string(file) else {
+            report.violations.push(violation(
+                "symbol_bounds",
+                "indexed file is unreadable".to_string(),
+                file,
+            ));
+            continue;
+        };
+        check_symbol_bounds(state, file, &contents, &mut report.violations);
+        check_duplicates(state, file, &contents, &mut report.violations);
+    }
+
+    report
+}
+
+fn violation(invariant: &'static str, message: String, file: &Path) -> Violation {
+    Violation {
+        invariant,
+        message,
+        file: Some(file.display().to_string()),
+    }
+}
+
+fn check_interner(state: &OciState, violations: &mut Vec<Violation>) {
+    let resolves = |key: InternedString| state.interner.try_resolve(&key).is_some();
+    let mut dangling = |what: &str| {
+        violations.push(Violation {
+            invariant: "interner",
+            message: format!("{} key does not resolve", what),
+            file: None,
+        });
+    };
+
+    for entry in state.symbols.iter() {
+        let symbol = entry.value();
+        if !resolves(*entry.key()) || entry.key() != &symbol.scoped_name {
+            dangling("symbol table");
+        }
+        if !resolves(symbol.name) {
+            dangling("symbol name");
+        }
+        if symbol.parent.is_some_and(|p| !resolves(p)) {
+            dangling("symbol parent");
+        }
+    }
+    for entry in state.name_to_scoped.iter() {
+        if !resolves(*entry.key()) || !entry.value().iter().all(|s| resolves(*s)) {
+            dangling("name index");
+        }
+    }
+    for edge in state.call_edges.read().iter() {
+        if !resolves(edge.caller) {
+            dangling("call edge caller");
+        }
+    }
+}
+
+fn check_call_edges(state: &OciState, violations: &mut Vec<Violation>) {
+    let mut unknown: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    for edge in state.call_edges.read().iter() {
+        if !state.file_ids.contains_key(&edge.location.file) {
+            *unknown.entry(edge.location.file.clone()).or_default() += 1;
+        }
+    }
+    for (file, count) in unknown {
+        violations.push(violation(
+            "call_edge_file",
+            format!("{} call edges point at a file that is not indexed", count),
+            &file,
+        ));
+    }
+}
+
+fn check_symbol_bounds(
+    state: &OciState,
+    file: &Path,
+    contents: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(file_id) = state.file_ids.get(file).map(|id| *id) else {
+        return;
+    };
+    let Some(scoped_names) = state.file_symbols.get(&file_id).map(|s| s.clone()) else {
+        return;
+    };
+    let line_count = contents.lines().count();
+
+    for scoped in scoped_names {
+        let Some(symbol) = state.get_symbol(scoped) else {
+            continue;
+        };
+        // Same-named symbols in other files overwrite each other
+        if symbol.location.file != file {
+            continue;
+        }
+        let loc = &symbol.location;
+        if loc.start_byte > loc.end_byte || loc.end_byte > contents.len() {
+            violations.push(violation(
+                "symbol_bounds",
+                format!(
+                    "{} spans bytes {}..{} but the file has {}",
+                    state.resolve(scoped),
+                    loc.start_byte,
+                    loc.end_byte,
+                    contents.len()
+                ),
+                file,
+            ));
+        } else if loc.start_line > loc.end_line || loc.end_line > line_count {
+            violations.push(violation(
+                "symbol_bounds",
+                format!(
+                    "{} spans lines {}..{} but the file has {}",
+                    state.resolve(scoped),
+                    loc.start_line,
+                    loc.end_line,
+                    line_count
+                ),
+                file,
+            ));
+        }
+    }
+}
+
+/// Re-parse the file, since the symbol table keeps one entry per scoped name.
+fn check_duplicates(
+    state: &OciState,
+    file: &Path,
+    contents: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(lang_parser) = parsing::parser_for_file(file) else {
+        return;
+    };
+    let mut parser = tree_sitter::Parser::new();
+    if parser.set_language(&lang_parser.language()).is_err() {
+        return;
+    }
+    let Some(tree) = parser.parse(contents, None) else {
+        return;
+    };
+    let Ok(symbols) = lang_parser.extract_symbols(&tree, contents, file, &state.interner) else {
+        return;
+    };
+
+    // Overloads differ by parameters; a type may have several impl blocks and
+    // cfg-gated items may legitimately repeat
+    let mut seen: HashMap<(InternedString, SymbolKind, Vec<String>), usize> = HashMap::new();
+    for symbol in &symbols {
+        if symbol.kind == SymbolKind::Impl || symbol.cfgs().next().is_some() {
+            continue;
+        }
+        let params = symbol
+            .signature
+            .as_ref()
+            .map(|s| s.params.clone())
+            .unwrap_or_default();
+        *seen
+            .entry((symbol.scoped_name, symbol.kind, params))
+            .or_default() += 1;
+    }
+    let mut duplicates: Vec<_> = seen.into_iter().filter(|(_, n)| *n > 1).collect();
+    duplicates.sort_by(|a, b| state.resolve(a.0.0).cmp(state.resolve(b.0.0)));
+    for ((scoped, kind, _), count) in duplicates {
+        violations.push(violation(
+            "duplicate_symbol",
+            format!(
+                "{} ({:?}) is defined {} times",
+                state.resolve(scoped),
+                kind,
+                count
+            ),
+            file,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incremental::IncrementalIndexer;
+    use crate::state::create_state;
+    use crate::types::{CallEdge, Location};
+
+    #[tokio::test]
+    async fn test_clean_index_verifies() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("lib.rs"),
+            "pub struct Foo;\nimpl Foo {\n    pub fn new() -> Self { helper(); Foo }\n}\nfn helper() {}\n",
+        )
+        .unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        let report = verify(&state);
+        assert!(report.is_ok(), "{:?}", report.violations);
+        assert!(report.symbols > 0);
+    }
+
+    #[tokio::test]
+    async fn test_detects_violations() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("lib.rs");
+        std::fs::write(&file, "mod a {\n    fn f() {}\n    fn f() {}\n}\n").unwrap();
+        let state = create_state(temp.path().to_path_buf());
+        IncrementalIndexer::new()
+            .full_index(&state, temp.path())
+            .await
+            .unwrap();
+
+        state.add_call_edge(CallEdge {
+            caller: state.intern("crate::a::f"),
+            callee_name: "g".to_string(),
+            location: Location::new(temp.path().join("gone.rs"), 0, 1),
+            is_method_call: false,
+            via_trait: None,
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
+        });
+        // Shrink the file under the index
+        std::fs::write(&file, "").unwrap();
+
+        let counts = verify(&state).counts();
+        assert_eq!(counts.get("call_edge_file"), Some(&1));
+        assert!(counts.contains_key("symbol_bounds"));
+        assert_eq!(counts.get("duplicate_symbol"), None);
+
+        std::fs::write(&file, "mod a {\n    fn f() {}\n    fn f() {}\n}\n").unwrap();
+        let counts = verify(&state).counts();
+        assert_eq!(counts.get("duplicate_symbol"), Some(&1));
+        assert_eq!(counts.get("symbol_bounds"), None);
+    }
+}
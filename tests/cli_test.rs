@@ -60,6 +60,39 @@ fn test_index_all_command_on_multiple_repos() {
     );
 }
 
+#[test]
+fn test_index_porcelain_streams_json_lines() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::write(temp.path().join("lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+
+    let (stdout, stderr, success) =
+        run_cli(&["index", "--root", root, "--porcelain", "--no-semantic"]);
+    assert!(success, "Porcelain index should succeed: {}", stderr);
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("each line is JSON"))
+        .collect();
+    assert_eq!(lines[0]["event"], "phase_started");
+    assert!(
+        lines
+            .iter()
+            .any(|l| l["event"] == "progress" && l["file"] == "lib.rs")
+    );
+    let last = lines.last().unwrap();
+    assert_eq!(last["type"], "index");
+    assert_eq!(last["phases"].as_array().unwrap().len(), 4);
+    // Opted out of (or built without) the embed phase
+    assert!(matches!(
+        last["semantic"].as_str(),
+        Some("not built" | "disabled")
+    ));
+
+    let (stdout, _, success) = run_cli(&["index", "--root", root, "--quiet"]);
+    assert!(success);
+    assert!(stdout.contains("Phases: discover"), "{}", stdout);
+}
+
 #[test]
 fn test_query_command() {
     let root = fixture_root();
@@ -69,6 +102,180 @@ fn test_query_command() {
     assert!(success, "Query command should succeed: {}", stderr);
 }
 
+#[test]
+fn test_query_reachable_from() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(
+        temp.path().join("src/main.rs"),
+        "fn main() { load_widget(); }\nfn load_widget() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp.path().join("src/admin.rs"),
+        "pub fn purge_widget() {}\n",
+    )
+    .unwrap();
+    let _ = run_cli(&["index", "--root", root]);
+
+    let (stdout, stderr, success) = run_cli(&[
+        "query",
+        "--root",
+        root,
+        "--json",
+        "--reachable-from",
+        "src/main.rs",
+        "widget",
+    ]);
+    assert!(success, "Reachable query should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    let files: Vec<&str> = value["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["file"].as_str().unwrap())
+        .collect();
+    assert!(files.contains(&"src/main.rs"), "{}", stdout);
+    assert!(!files.contains(&"src/admin.rs"), "{}", stdout);
+
+    let (_, stderr, success) = run_cli(&[
+        "query",
+        "--root",
+        root,
+        "--reachable-from",
+        "nope",
+        "widget",
+    ]);
+    assert!(!success);
+    assert!(stderr.contains("Unknown reachability root"), "{}", stderr);
+}
+
+#[test]
+fn test_impact_crates_lists_dependents() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    let write = |rel: &str, contents: &str| {
+        let path = temp.path().join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    };
+    write("Cargo.toml", "[workspace]\nmembers = [\"base\", \"app\"]\n");
+    write("base/Cargo.toml", "[package]\nname = \"base\"\n");
+    write("base/src/lib.rs", "pub fn util() {}\n");
+    write(
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\n[dependencies]\nbase = { path = \"../base\" }\n",
+    );
+    write("app/src/main.rs", "fn main() {}\n");
+
+    let (stdout, stderr, success) = run_cli(&[
+        "impact",
+        "--root",
+        root,
+        "--json",
+        "--crates",
+        "base/src/lib.rs",
+    ]);
+    assert!(success, "Impact should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert_eq!(value["type"], "impact");
+    assert_eq!(value["owner"]["name"], "base");
+    assert_eq!(value["dependents"][0]["name"], "app");
+    assert_eq!(value["dependents"][0]["depth"], 1);
+}
+
+#[test]
+fn test_impact_symbol_groups_sites_by_file() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    let write = |rel: &str, contents: &str| {
+        let path = temp.path().join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    };
+    write("src/lib.rs", "pub mod codec;\npub mod api;\n");
+    write("src/codec.rs", "pub fn decode() -> u8 {\n    0\n}\n");
+    write(
+        "src/api.rs",
+        "use crate::codec::decode;\n\npub fn handle() -> u8 {\n    decode()\n}\n",
+    );
+
+    let (stdout, stderr, success) = run_cli(&["impact", "--root", root, "--json", "decode"]);
+    assert!(success, "Impact should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert_eq!(value["type"], "renameimpact");
+    assert_eq!(value["calls"], 1);
+    assert_eq!(value["imports"], 1);
+    assert_eq!(value["files"][0]["file"], "src/api.rs");
+    assert_eq!(value["files"][1]["file"], "src/codec.rs");
+}
+
+#[test]
+fn test_references_include_type_usages() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(
+        temp.path().join("src/lib.rs"),
+        "pub struct Point {\n    x: i32,\n}\n\nimpl Point {\n    pub fn new(x: i32) -> Point {\n        Point { x }\n    }\n}\n\npub fn origin() -> Point {\n    Point::new(0)\n}\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_cli(&["references", "--root", root, "--json", "Point"]);
+    assert!(success, "References should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert_eq!(value["type"], "references");
+    let kinds: Vec<&str> = value["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "impl_target",
+            "type_annotation",
+            "struct_literal",
+            "type_annotation"
+        ]
+    );
+    assert_eq!(value["results"][2]["from"], "crate::Point::new");
+}
+
+#[test]
+fn test_impls_lists_implementations_and_trait_of_method() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(
+        temp.path().join("src/lib.rs"),
+        "pub trait Shape {\n    fn area(&self) -> f64;\n}\n\npub struct Circle;\n\nimpl Shape for Circle {\n    fn area(&self) -> f64 {\n        3.14\n    }\n}\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_cli(&["impls", "--root", root, "--json", "Shape"]);
+    assert!(success, "Impls should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert_eq!(value["type"], "implementations");
+    assert_eq!(value["definitions"][0], "crate::Shape");
+    assert_eq!(value["implementations"][0]["type_name"], "Circle");
+
+    let (stdout, stderr, success) = run_cli(&[
+        "impls",
+        "--root",
+        root,
+        "--json",
+        "--method",
+        "crate::Circle::area",
+    ]);
+    assert!(success, "Impls --method should succeed: {}", stderr);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert_eq!(value["type"], "traitorigins");
+    assert_eq!(value["origins"][0]["trait_symbol"], "crate::Shape");
+}
+
 #[test]
 fn test_symbol_command() {
     let _ = run_cli(&["index", "--root", env!("CARGO_MANIFEST_DIR")]);
@@ -87,6 +294,55 @@ fn test_symbol_command() {
     );
 }
 
+#[test]
+fn test_symbol_command_filters_by_attribute() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(
+        temp.path().join("src/main.rs"),
+        "#[derive(Debug, serde::Serialize)]\npub struct Config;\n\n#[derive(Debug)]\npub struct Plain;\n\n#[tokio::main]\nasync fn main() {}\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_cli(&[
+        "--json",
+        "symbol",
+        "--root",
+        root,
+        "--attr",
+        "derive(Serialize)",
+    ]);
+    assert!(success, "Attribute search should succeed: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1, "{}", stdout);
+    assert_eq!(results[0]["name"], "crate::Config");
+    assert_eq!(
+        results[0]["attributes"][0],
+        "derive(Debug, serde::Serialize)"
+    );
+
+    let (stdout, _, success) = run_cli(&["symbol", "--root", root, "--attr", "tokio::main"]);
+    assert!(success);
+    assert!(stdout.contains("crate::main"), "{}", stdout);
+    assert!(!stdout.contains("crate::Config"), "{}", stdout);
+
+    // A name narrows the attribute match
+    let (stdout, _, success) = run_cli(&[
+        "--json",
+        "symbol",
+        "--root",
+        root,
+        "Plain",
+        "--attr",
+        "derive(Serialize)",
+    ]);
+    assert!(success);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    assert_eq!(json["results"].as_array().unwrap().len(), 0, "{}", stdout);
+}
+
 #[test]
 fn test_json_output() {
     let root = fixture_root();
@@ -110,3 +366,208 @@ fn test_dead_code_analysis() {
     assert!(success, "Dead code analysis should succeed: {}", stderr);
     assert!(!stdout.is_empty(), "Should produce output");
 }
+
+#[cfg(feature = "analysis")]
+#[test]
+fn test_check_baseline_only_fails_on_new_findings() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(temp.path().join("src/lib.rs"), "fn unused_legacy() {}\n").unwrap();
+
+    let (_, _, success) = run_cli(&["check", "--root", root]);
+    assert!(!success, "Existing findings should fail without a baseline");
+
+    let (stdout, stderr, success) = run_cli(&["check", "--write-baseline", "--root", root]);
+    assert!(success, "Writing a baseline should succeed: {}", stderr);
+    assert!(stdout.contains("Wrote baseline"), "{}", stdout);
+
+    let (_, stderr, success) = run_cli(&["check", "--root", root]);
+    assert!(success, "Baselined findings should pass: {}", stderr);
+
+    std::fs::write(
+        temp.path().join("src/lib.rs"),
+        "fn unused_legacy() {}\n\nfn unused_new() {}\n",
+    )
+    .unwrap();
+    let (stdout, _, success) = run_cli(&["--json", "check", "--root", root]);
+    assert!(!success, "New findings should fail");
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    assert_eq!(json["baselined"], 1);
+    assert_eq!(json["new"][0]["symbol"], "crate::unused_new");
+}
+
+#[cfg(feature = "analysis")]
+#[test]
+fn test_co_changes_lists_files_changed_together() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    let git = |args: &[&str]| {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(temp.path())
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args);
+        // Hooks export GIT_DIR and friends; target the temp repo instead
+        for (key, _) in std::env::vars() {
+            if key.starts_with("GIT_") {
+                cmd.env_remove(key);
+            }
+        }
+        assert!(cmd.output().unwrap().status.success(), "git {:?}", args);
+    };
+    git(&["init", "--initial-branch=main"]);
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    for version in 1..=3 {
+        std::fs::write(
+            temp.path().join("src/model.rs"),
+            format!("pub const VERSION: u32 = {version};\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("src/serialize.rs"),
+            format!("pub fn version() -> u32 {{\n    {version}\n}}\n"),
+        )
+        .unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--no-gpg-sign", "-m", "bump"]);
+    }
+
+    let (stdout, stderr, success) =
+        run_cli(&["--json", "co-changes", "--root", root, "src/model.rs"]);
+    assert!(success, "co-changes should succeed: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    assert_eq!(json["commits"], 3);
+    assert_eq!(json["files"][0]["file"], "src/serialize.rs");
+    assert_eq!(json["files"][0]["confidence"], 1.0);
+    assert_eq!(json["symbols"][0]["symbol"], "crate::version");
+
+    let (_, stderr, success) = run_cli(&["co-changes", "--root", root, "missing.rs"]);
+    assert!(!success);
+    assert!(stderr.contains("Unknown co-change target"), "{}", stderr);
+}
+
+#[cfg(feature = "analysis")]
+#[test]
+fn test_coverage_analysis_reports_untested_public_functions() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::create_dir_all(temp.path().join("src")).unwrap();
+    std::fs::write(
+        temp.path().join("src/lib.rs"),
+        "pub fn used(x: i32) -> i32 {\n    x + 1\n}\n\npub fn unused(x: i32) -> i32 {\n    x * 2\n}\n",
+    )
+    .unwrap();
+    // `cargo tarpaulin --out Json` shape, with a root-relative path
+    std::fs::write(
+        temp.path().join("tarpaulin-report.json"),
+        r#"{"files": [{"path": ["src", "lib.rs"], "content": "", "covered": 1, "coverable": 2,
+            "traces": [
+                {"line": 2, "address": [1], "length": 1, "stats": {"Line": 3}},
+                {"line": 6, "address": [2], "length": 1, "stats": {"Line": 0}}
+            ]}]}"#,
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_cli(&[
+        "--json",
+        "analyze",
+        "--root",
+        root,
+        "coverage",
+        "--file",
+        "tarpaulin-report.json",
+    ]);
+    assert!(success, "Coverage analysis should succeed: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    assert_eq!(json["type"], "coverage");
+    assert_eq!(json["functions"], 2);
+    assert_eq!(json["lines_covered"], 1);
+    assert_eq!(json["untested_public"][0]["symbol"], "crate::unused");
+    assert_eq!(json["untested_public"][0]["file"], "src/lib.rs");
+    assert_eq!(json["untested_public"].as_array().unwrap().len(), 1);
+
+    let (_, stderr, success) = run_cli(&["analyze", "--root", root, "coverage"]);
+    assert!(!success);
+    assert!(stderr.contains("--file"), "{}", stderr);
+}
+
+#[test]
+fn test_export_jsonl_symbols_streams_one_object_per_line() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::write(
+        temp.path().join("lib.rs"),
+        "/// Adds one.\npub fn inc(x: i32) -> i32 { x + 1 }\n\nfn twice(x: i32) -> i32 { inc(inc(x)) }\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) =
+        run_cli(&["export", "--root", root, "--format", "jsonl-symbols"]);
+    assert!(success, "Export should succeed: {}", stderr);
+    assert!(stderr.contains("Exported 2 symbols"), "{}", stderr);
+    let records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+        .collect();
+    assert_eq!(records.len(), 2);
+
+    let inc = &records[0];
+    assert_eq!(inc["scoped_name"], "crate::inc");
+    assert_eq!(inc["file"], "lib.rs");
+    assert_eq!(inc["visibility"], "public");
+    assert_eq!(inc["doc"], "/// Adds one.");
+    assert_eq!(inc["extended_start_byte"], 0);
+    assert_eq!(inc["start_byte"], 14);
+    assert!(records[1].get("extended_start_line").is_none());
+    assert_eq!(inc["signature"]["return_type"], "i32");
+    assert_eq!(inc["metrics"]["callers"], 2);
+    assert_eq!(records[1]["metrics"]["callees"], 2);
+    assert_eq!(records[0]["id"].as_str().unwrap().len(), 16);
+
+    let out = temp.path().join("symbols.jsonl");
+    let (stdout, _, success) = run_cli(&[
+        "--json",
+        "export",
+        "--root",
+        root,
+        "--format",
+        "jsonl-symbols",
+        "--output",
+        out.to_str().unwrap(),
+    ]);
+    assert!(success);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("json");
+    assert_eq!(json["symbols"], 2);
+    assert_eq!(std::fs::read_to_string(&out).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn test_export_ctags_and_etags() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path().to_str().unwrap();
+    std::fs::write(
+        temp.path().join("lib.rs"),
+        "pub mod shapes {\n    pub fn area(w: u32, h: u32) -> u32 {\n        w * h\n    }\n}\n\nfn main() {}\n",
+    )
+    .unwrap();
+
+    let (stdout, stderr, success) = run_cli(&["export", "--root", root, "--format", "ctags"]);
+    assert!(success, "Export should succeed: {}", stderr);
+    assert!(stdout.starts_with("!_TAG_FILE_FORMAT\t2\t"));
+    let tags: Vec<&str> = stdout.lines().filter(|l| !l.starts_with("!_")).collect();
+    let names: Vec<&str> = tags.iter().map(|l| l.split('\t').next().unwrap()).collect();
+    assert_eq!(names, vec!["area", "main", "shapes"], "sorted by name");
+    assert_eq!(
+        tags[0],
+        "area\tlib.rs\t2;\"\tkind:function\tline:2\tmodule:shapes\tsignature:(w: u32, h: u32)\ttyperef:typename:u32\taccess:public"
+    );
+
+    let (stdout, _, success) = run_cli(&["export", "--root", root, "--format", "etags"]);
+    assert!(success);
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("\u{c}"));
+    let header = lines.next().unwrap();
+    let section: String = lines.map(|l| format!("{l}\n")).collect();
+    assert_eq!(header, format!("lib.rs,{}", section.len()));
+    assert!(section.contains("    pub fn area(w: u32, h: u32) -> u32 {\u{7f}area\u{1}2,21\n"));
+}
@@ -401,6 +401,130 @@ path = "src/lib.rs"
     temp
 }
 
+/// The same codebase in TypeScript (with a TSX component), for call graph
+/// parity with the Rust fixture
+fn create_realistic_ts_codebase() -> TempDir {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let root = temp.path();
+
+    fs::create_dir_all(root.join("src/core")).unwrap();
+    fs::create_dir_all(root.join("src/utils")).unwrap();
+    fs::create_dir_all(root.join("src/api")).unwrap();
+    fs::write(root.join("package.json"), r#"{ "name": "test-lib" }"#).unwrap();
+
+    // core/engine.ts - classes, `this` calls, an arrow-function property
+    fs::write(
+        root.join("src/core/engine.ts"),
+        r#"import { validateInput, formatOutput } from "../utils";
+
+export abstract class Component {
+  abstract process(input: string): string;
+
+  describe(): string {
+    return this.constructor.name;
+  }
+}
+
+/** The main processing engine. */
+export class Engine extends Component {
+  constructor(public name: string) {
+    super();
+  }
+
+  static create(name: string): Engine {
+    return new Engine(name);
+  }
+
+  process(input: string): string {
+    const validated = validateInput(input);
+    const result = this.execute(validated);
+    return formatOutput(result);
+  }
+
+  onResult = (result: string) => {
+    this.logExecution(result);
+  };
+
+  private execute(input: string): string {
+    const processed = input.toUpperCase();
+    this.logExecution(processed);
+    return processed;
+  }
+
+  private logExecution(result: string) {
+    console.log(`[${this.name}] Executed: ${result}`);
+  }
+}
+"#,
+    )
+    .unwrap();
+
+    // utils/index.ts - free functions and an arrow-function const
+    fs::write(
+        root.join("src/utils/index.ts"),
+        r#"export function validateInput(input: string): string {
+  if (input.trim().length === 0) {
+    throw new Error("Empty input provided");
+  }
+  return input.trim();
+}
+
+export function formatOutput(output: string): string {
+  return output;
+}
+
+export const isValidIdentifier = (s: string): boolean => /^[A-Za-z_]\w*$/.test(s);
+"#,
+    )
+    .unwrap();
+
+    // api/server.ts - a second class with an `execute` method of its own
+    fs::write(
+        root.join("src/api/server.ts"),
+        r#"import { Engine } from "../core/engine";
+
+export class Server {
+  private engine = Engine.create("api-server");
+
+  constructor(private port: number) {}
+
+  handleRequest(request: string): string {
+    return this.execute(request);
+  }
+
+  private execute(request: string): string {
+    return this.engine.process(request);
+  }
+}
+"#,
+    )
+    .unwrap();
+
+    // App.tsx - components rendering each other
+    fs::write(
+        root.join("src/App.tsx"),
+        r#"import { Server } from "./api/server";
+
+export function StatusBadge(props: { status: string }) {
+  return <span className="badge">{props.status}</span>;
+}
+
+export function App() {
+  const server = new Server(8080);
+  const status = server.handleRequest("ping");
+  return (
+    <div>
+      <StatusBadge status={status} />
+    </div>
+  );
+}
+"#,
+    )
+    .unwrap();
+
+    temp
+}
+
 // ============================================================================
 // Feature Comparison Tests
 // ============================================================================
@@ -890,3 +1014,136 @@ fn test_signature_extraction() {
         .count();
     assert!(fns_with_sig > 0, "Functions should have signatures");
 }
+
+// ============================================================================
+// TypeScript Parity Tests
+// ============================================================================
+
+/// Index the TypeScript fixture
+fn index_ts_codebase() -> (TempDir, std::sync::Arc<omni_index::OciState>) {
+    let temp = create_realistic_ts_codebase();
+    let state = create_state(temp.path().to_path_buf());
+    let indexer = IncrementalIndexer::new();
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async { indexer.full_index(&state, temp.path()).await.unwrap() });
+    (temp, state)
+}
+
+/// Callers of `name` as `(caller, resolved callee)` scoped names
+fn ts_callers(state: &omni_index::OciState, name: &str) -> Vec<(String, Option<String>)> {
+    let mut callers: Vec<_> = state
+        .find_callers(name)
+        .iter()
+        .map(|c| {
+            (
+                state.resolve(c.caller).to_string(),
+                c.resolved_callee.map(|r| state.resolve(r).to_string()),
+            )
+        })
+        .collect();
+    callers.sort();
+    callers
+}
+
+/// Test: TypeScript class members are scoped under their class, like Rust
+/// impl methods
+#[test]
+fn test_ts_scoped_name_resolution() {
+    let (_temp, state) = index_ts_codebase();
+
+    for (name, scoped, class) in [
+        (
+            "process",
+            "file:src/core/engine.ts::Engine::process",
+            "Engine",
+        ),
+        (
+            "onResult",
+            "file:src/core/engine.ts::Engine::onResult",
+            "Engine",
+        ),
+        (
+            "describe",
+            "file:src/core/engine.ts::Component::describe",
+            "Component",
+        ),
+        (
+            "handleRequest",
+            "file:src/api/server.ts::Server::handleRequest",
+            "Server",
+        ),
+    ] {
+        let defs = state.find_by_name(name);
+        let def = defs
+            .iter()
+            .find(|d| state.resolve(d.scoped_name) == scoped)
+            .unwrap_or_else(|| panic!("{} should be defined as {}", name, scoped));
+        assert_eq!(def.kind, SymbolKind::Method);
+        assert_eq!(def.parent.map(|p| state.resolve(p)), Some(class));
+    }
+}
+
+/// Test: TypeScript calls are attributed to the enclosing method, arrow
+/// property or component
+#[test]
+fn test_ts_call_graph_accuracy() {
+    let (_temp, state) = index_ts_codebase();
+
+    assert_eq!(
+        ts_callers(&state, "validateInput"),
+        [(
+            "file:src/core/engine.ts::Engine::process".to_string(),
+            Some("file:src/utils/index.ts::validateInput".to_string())
+        )]
+    );
+    let log_callers: Vec<String> = ts_callers(&state, "logExecution")
+        .into_iter()
+        .map(|(caller, _)| caller)
+        .collect();
+    assert_eq!(
+        log_callers,
+        [
+            "file:src/core/engine.ts::Engine::execute",
+            "file:src/core/engine.ts::Engine::onResult",
+        ]
+    );
+    assert_eq!(
+        ts_callers(&state, "StatusBadge"),
+        [(
+            "file:src/App.tsx::App".to_string(),
+            Some("file:src/App.tsx::StatusBadge".to_string())
+        )]
+    );
+}
+
+/// Test: `this.method()` binds to the caller's own class when several
+/// classes define the method, as `self.method()` does in Rust
+#[test]
+fn test_ts_this_calls_bind_to_own_class() {
+    let (_temp, state) = index_ts_codebase();
+
+    assert_eq!(
+        ts_callers(&state, "execute"),
+        [
+            (
+                "file:src/api/server.ts::Server::handleRequest".to_string(),
+                Some("file:src/api/server.ts::Server::execute".to_string())
+            ),
+            (
+                "file:src/core/engine.ts::Engine::process".to_string(),
+                Some("file:src/core/engine.ts::Engine::execute".to_string())
+            ),
+        ]
+    );
+    // Through another object the receiver's class is unknown
+    let process_callers = ts_callers(&state, "process");
+    assert_eq!(process_callers.len(), 1);
+    assert_eq!(
+        process_callers[0].0,
+        "file:src/api/server.ts::Server::execute"
+    );
+}
@@ -170,3 +170,49 @@ fn contract_index_result_fields_are_stable() {
         );
     }
 }
+
+// =============================================================================
+// OUTPUT SCHEMA VERSIONING
+// =============================================================================
+
+#[test]
+fn contract_json_carries_schema_version() {
+    let root = fixture_root();
+    ensure_indexed(&root);
+
+    let (json, success) = run_cli_json(&["search", "add", "--json", "-w", &root]);
+    assert!(success);
+    assert_eq!(json["schema_version"], 2, "Must have schema_version: 2");
+
+    // A prior version is still served, for consumers not yet migrated
+    let (json, success) = run_cli_json(&[
+        "search",
+        "add",
+        "--json",
+        "--schema-version",
+        "1",
+        "-w",
+        &root,
+    ]);
+    assert!(success);
+    assert!(json.get("schema_version").is_none());
+    assert_eq!(json["type"], "search");
+    assert!(json["results"].is_array());
+}
+
+#[test]
+fn contract_schema_flag_describes_every_output() {
+    let (json, success) = run_cli_json(&["--schema"]);
+    assert!(success, "--schema must succeed without a command");
+
+    let defs = json["$defs"].as_object().expect("schema definitions");
+    assert!(defs.contains_key("ErrorResponse"));
+    let outputs: Vec<&str> = defs["SuccessResponse"]["oneOf"]
+        .as_array()
+        .expect("one schema per output type")
+        .iter()
+        .filter_map(|variant| variant["properties"]["type"]["const"].as_str())
+        .collect();
+    assert!(outputs.contains(&"search"));
+    assert!(outputs.contains(&"index"));
+}
@@ -1,4 +1,4 @@
-use omni_index::FileDiscovery;
+use omni_index::{FileDiscovery, PackageScope};
 use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
@@ -74,3 +74,108 @@ fn discovery_include_can_override_default_excludes() {
 
     assert!(rel.contains("target/generated.rs"));
 }
+
+#[test]
+fn discovery_finds_nested_package_roots() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let write = |rel: &str, content: &str| {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).expect("create dir");
+        fs::write(path, content).expect("write");
+    };
+    write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    write(
+        "crates/core/Cargo.toml",
+        "[package]\nname = \"acme-core\"\n",
+    );
+    write(
+        "web/package.json",
+        r#"{ "name": "@acme/web", "workspaces": ["apps/*"] }"#,
+    );
+    write(
+        "web/apps/admin/package.json",
+        r#"{ "name": "admin", "dependencies": { "@acme/web": "workspace:*" },
+             "devDependencies": { "vitest": "1" } }"#,
+    );
+    write(
+        "third_party/libfoo/.git",
+        "gitdir: ../../.git/modules/libfoo\n",
+    );
+    write(
+        "web/node_modules/left-pad/package.json",
+        r#"{ "name": "left-pad" }"#,
+    );
+    write("broken/Cargo.toml", "not [valid");
+
+    let packages = FileDiscovery::new()
+        .discover_packages(root)
+        .expect("discover packages");
+    let summary: Vec<(String, String, &str, bool)> = packages
+        .iter()
+        .map(|p| {
+            (
+                p.path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string(),
+                p.name.clone(),
+                p.kind.as_str(),
+                p.is_workspace,
+            )
+        })
+        .collect();
+    let root_name = root.file_name().unwrap().to_string_lossy().to_string();
+
+    assert_eq!(
+        summary,
+        vec![
+            (String::new(), root_name, "cargo", true),
+            ("broken".into(), "broken".into(), "cargo", false),
+            ("crates/core".into(), "acme-core".into(), "cargo", false),
+            ("third_party/libfoo".into(), "libfoo".into(), "git", false),
+            ("web".into(), "@acme/web".into(), "npm", true),
+            ("web/apps/admin".into(), "admin".into(), "npm", false),
+        ]
+    );
+    let admin = packages.iter().find(|p| p.name == "admin").unwrap();
+    assert_eq!(admin.dependencies, vec!["@acme/web", "vitest"]);
+}
+
+#[test]
+fn package_scope_leaves_out_nested_packages() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let write = |rel: &str, content: &str| {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).expect("create dir");
+        fs::write(path, content).expect("write");
+    };
+    write(
+        "web/package.json",
+        r#"{ "name": "@acme/web", "workspaces": ["apps/*"] }"#,
+    );
+    write("web/apps/admin/package.json", r#"{ "name": "admin" }"#);
+    write(
+        "services/api/Cargo.toml",
+        "[package]\nname = \"acme-api\"\n",
+    );
+
+    let packages = FileDiscovery::new()
+        .discover_packages(root)
+        .expect("discover packages");
+    assert!(PackageScope::find(&packages, "missing").is_none());
+
+    let web = PackageScope::find(&packages, "@acme/web").expect("web package");
+    assert_eq!(web.relative_dir(root), "web");
+    assert!(web.contains_file(&root.join("web/src/index.ts")));
+    assert!(web.contains_relative(root, "web/src/index.ts"));
+    assert!(!web.contains_relative(root, "web/apps/admin/main.ts"));
+    assert!(!web.contains_relative(root, "services/api/src/lib.rs"));
+
+    let admin = PackageScope::find(&packages, "admin").expect("admin package");
+    assert!(admin.contains_relative(root, "web/apps/admin/main.ts"));
+    let api = PackageScope::find(&packages, "acme-api").expect("api package");
+    assert!(api.contains_relative(root, "services/api/src/lib.rs"));
+}
@@ -154,10 +154,14 @@ proptest! {
                         end_line: 1,
                         end_col: 10,
                     },
+                    extended_location: None,
                     signature: None,
                     doc_comment: None,
                     attributes: vec![],
                     parent: None,
+                    ignores: Vec::new(),
+                    raw_name: None,
+                    is_test_context: false,
                 };
                 state.add_symbol(symbol);
             }
@@ -193,10 +197,14 @@ proptest! {
                 end_line: 1,
                 end_col: 10,
             },
+            extended_location: None,
             signature: None,
             doc_comment: None,
             attributes: vec![],
             parent: None,
+            ignores: Vec::new(),
+            raw_name: None,
+            is_test_context: false,
         });
 
         // Add other symbols
@@ -219,10 +227,14 @@ proptest! {
                         end_line: 1,
                         end_col: 10,
                     },
+                    extended_location: None,
                     signature: None,
                     doc_comment: None,
                     attributes: vec![],
                     parent: None,
+                    ignores: Vec::new(),
+                    raw_name: None,
+                    is_test_context: false,
                 });
             }
         }
@@ -262,6 +274,11 @@ proptest! {
                 end_col: 10,
             },
             is_method_call: false,
+            via_trait: None,
+            is_approximate: false,
+            type_args: None,
+            is_test_context: false,
+            resolved_callee: None,
         };
 
         state.add_call_edge(edge);
@@ -306,6 +323,7 @@ proptest! {
                     end_line: i + 1,
                     end_col: 10,
                 },
+                extended_location: None,
                 signature: Some(Signature {
                     params: vec!["x: i32".to_string()],
                     return_type: Some("bool".to_string()),
@@ -318,6 +336,9 @@ proptest! {
                 doc_comment: None,
                 attributes: vec![],
                 parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
             });
         }
 
@@ -575,10 +596,14 @@ proptest! {
                     end_line: i + 1,
                     end_col: 10,
                 },
+                extended_location: None,
                 signature: None,
                 doc_comment: None,
                 attributes: vec![],
                 parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
             });
         }
 
@@ -629,10 +654,14 @@ proptest! {
                     end_line: i + 1,
                     end_col: 10,
                 },
+                extended_location: None,
                 signature: None,
                 doc_comment: None,
                 attributes: vec![],
                 parent: None,
+                ignores: Vec::new(),
+                raw_name: None,
+                is_test_context: false,
             });
         }
 
@@ -712,3 +741,86 @@ proptest! {
         prop_assert!(imports.iter().any(|i| i.is_glob), "Glob import not detected");
     }
 }
+
+// ============================================================================
+// Tokenizer Property Tests
+// ============================================================================
+
+/// Words joined into identifiers; none of them are stop words.
+fn word() -> impl Strategy<Value = String> {
+    "[a-z]{3,8}".prop_filter("not a stop word", |w| !["and", "the"].contains(&w.as_str()))
+}
+
+/// Upper-case acronyms that are not stop words either.
+fn acronym() -> impl Strategy<Value = String> {
+    "[A-Z]{2,5}".prop_filter("not a stop word", |a| {
+        !["AN", "AND", "AT", "BY", "IN", "OF", "ON", "OR", "THE", "TO"].contains(&a.as_str())
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    /// Property: Tokens are non-empty pieces of the identifier, and parts
+    /// never mix letters with digits or hold underscores
+    #[test]
+    fn tokens_are_clean_parts(ident in "[A-Za-z_][A-Za-z0-9_]{0,30}") {
+        use omni_index::search::tokenize;
+
+        let tokens: Vec<&str> = tokenize(&ident).collect();
+        for token in &tokens {
+            prop_assert!(!token.is_empty());
+            prop_assert!(ident.contains(token), "{} not in {}", token, ident);
+        }
+        let parts = match tokens.split_last() {
+            Some((last, parts)) if *last == ident && !parts.is_empty() => parts,
+            _ => &tokens[..],
+        };
+        for part in parts.iter().filter(|part| **part != ident) {
+            prop_assert!(!part.contains('_'), "{} of {}", part, ident);
+            let digits = part.chars().filter(|c| c.is_ascii_digit()).count();
+            prop_assert!(digits == 0 || digits == part.len(), "{} of {}", part, ident);
+        }
+    }
+
+    /// Property: camelCase, PascalCase, and snake_case joins of words split
+    /// back into the words and keep the whole identifier
+    #[test]
+    fn joined_words_split_back(words in prop::collection::vec(word(), 2..5)) {
+        use omni_index::search::tokenize;
+
+        let capitalized: Vec<String> = words
+            .iter()
+            .map(|w| w[..1].to_uppercase() + &w[1..])
+            .collect();
+        let camel = format!("{}{}", words[0], capitalized[1..].concat());
+        let pascal = capitalized.concat();
+        let snake = words.join("_");
+
+        for ident in [&camel, &pascal, &snake] {
+            let tokens: Vec<String> = tokenize(ident).map(|t| t.to_lowercase()).collect();
+            for word in &words {
+                prop_assert!(tokens.contains(word), "{} not split out of {}", word, ident);
+            }
+            prop_assert!(tokens.contains(&ident.to_lowercase()));
+        }
+    }
+
+    /// Property: An acronym followed by a word or a number splits off whole
+    #[test]
+    fn acronyms_split_off(acronym in acronym(), word in word(), number in 0u32..1000) {
+        use omni_index::search::tokenize;
+
+        let word = word[..1].to_uppercase() + &word[1..];
+        let ident = format!("parse{}{}{}", acronym, number, word);
+        let tokens: Vec<&str> = tokenize(&ident).collect();
+        let number = number.to_string();
+        prop_assert!(tokens.contains(&acronym.as_str()), "{:?}", tokens);
+        prop_assert!(tokens.contains(&number.as_str()), "{:?}", tokens);
+        prop_assert!(tokens.contains(&word.as_str()), "{:?}", tokens);
+
+        let ident = format!("{}{}", acronym, word);
+        let tokens: Vec<&str> = tokenize(&ident).collect();
+        prop_assert_eq!(tokens, vec![acronym.as_str(), word.as_str(), ident.as_str()]);
+    }
+}
@@ -1,5 +1,12 @@
-use omni_index::query::{execute_query, load_search_index};
-use omni_index::{IncrementalIndexer, IndexOptions, create_state};
+use omni_index::parsing::LanguageOverrides;
+use omni_index::query::{
+    DocKind, SearchStrategy, SearchType, SemanticHits, apply_overlays, execute_query,
+    load_search_index, parse_query_filters, require_search_index, search_with_fallback,
+    semantic_query,
+};
+use omni_index::{
+    Budget, IncrementalIndexer, IndexEvent, IndexOptions, IndexPhase, OmniError, create_state,
+};
 use std::fs;
 use std::path::PathBuf;
 
@@ -33,6 +40,116 @@ fn copy_fixture() -> tempfile::TempDir {
     temp
 }
 
+#[tokio::test]
+async fn test_index_reports_phase_progress() {
+    let temp = copy_fixture();
+    let root = temp.path();
+    let state = create_state(root.to_path_buf());
+
+    let mut events = Vec::new();
+    let report = IncrementalIndexer::new()
+        .index_with_progress(&state, root, &IndexOptions::default(), |e| {
+            events.push(e.clone())
+        })
+        .await
+        .expect("index");
+
+    let started: Vec<IndexPhase> = events
+        .iter()
+        .filter_map(|e| match e {
+            IndexEvent::PhaseStarted { phase, .. } => Some(*phase),
+            _ => None,
+        })
+        .collect();
+    let all = [
+        IndexPhase::Discover,
+        IndexPhase::Fingerprint,
+        IndexPhase::Parse,
+        IndexPhase::Finalize,
+    ];
+    assert_eq!(started, all);
+    let timed: Vec<IndexPhase> = report.phases.iter().map(|(p, _)| *p).collect();
+    assert_eq!(timed, all);
+
+    let parsed = events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e,
+                IndexEvent::Progress {
+                    phase: IndexPhase::Parse,
+                    ..
+                }
+            )
+        })
+        .count();
+    assert_eq!(parsed, report.parsed_files);
+    assert!(matches!(
+        events.last(),
+        Some(IndexEvent::PhaseFinished {
+            phase: IndexPhase::Finalize,
+            ..
+        })
+    ));
+    let json = serde_json::to_value(&events[0]).unwrap();
+    assert_eq!(json["event"], "phase_started");
+    assert_eq!(json["phase"], "discover");
+}
+
+#[tokio::test]
+async fn test_focus_paths_are_parsed_first_and_boosted() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::create_dir_all(root.join("src/codec")).unwrap();
+    fs::write(root.join("src/a.rs"), "pub fn decode_frame() {}\n").unwrap();
+    fs::write(root.join("src/b.rs"), "pub fn other() {}\n").unwrap();
+    fs::write(
+        root.join("src/codec/frame.rs"),
+        "pub fn decode_frame() {}\n",
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+
+    let options = IndexOptions {
+        focus: vec!["src/codec/".to_string()],
+        ..Default::default()
+    };
+    let mut events = Vec::new();
+    IncrementalIndexer::new()
+        .index_with_progress(&state, root, &options, |e| events.push(e.clone()))
+        .await
+        .expect("index");
+
+    let parsed: Vec<&str> = events
+        .iter()
+        .filter_map(|e| match e {
+            IndexEvent::Progress {
+                phase: IndexPhase::Parse,
+                file,
+                ..
+            } => Some(file.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(parsed, vec!["src/codec/frame.rs", "src/a.rs", "src/b.rs"]);
+    let ready = events
+        .iter()
+        .position(|e| matches!(e, IndexEvent::FocusReady { files: 1 }))
+        .expect("focus ready event");
+    let first_other = events
+        .iter()
+        .position(|e| {
+            matches!(e, IndexEvent::Progress { phase: IndexPhase::Parse, file, .. } if file == "src/a.rs")
+        })
+        .unwrap();
+    assert!(ready < first_other);
+
+    // Identical definitions: the focused one ranks first
+    let index = require_search_index(root).expect("search index");
+    let response = execute_query(&index, "decode_frame", 10, &Default::default());
+    assert_eq!(response.results[0].file, "src/codec/frame.rs");
+}
+
 #[tokio::test]
 async fn test_incremental_indexing_skips_unchanged() {
     let temp = copy_fixture();
@@ -67,6 +184,281 @@ async fn test_incremental_indexing_skips_unchanged() {
     assert_eq!(report.parsed_files, 1);
 }
 
+#[tokio::test]
+async fn test_incremental_parsing_matches_full_reparse() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let path = root.join("lib.rs");
+    fs::write(
+        &path,
+        "fn load() { parse(); }\n\nfn parse() {}\n\nfn save() { load(); }\n",
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+    let indexer = IncrementalIndexer::new().with_incremental_parsing(true);
+    indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    fs::write(
+        &path,
+        "fn load() { parse(); }\n\n/// Parses.\nfn parse_all() {}\n\nfn save() { load(); }\n",
+    )
+    .unwrap();
+    let docs = indexer
+        .update_file(&state, &path, root)
+        .await
+        .expect("update")
+        .expect("changed");
+
+    let fresh = create_state(root.to_path_buf());
+    let fresh_docs = IncrementalIndexer::new()
+        .index_file(&fresh, &path, root)
+        .await
+        .expect("index file");
+    let spans = |docs: &[omni_index::SearchDoc]| -> Vec<(String, usize, usize, usize)> {
+        docs.iter()
+            .map(|d| (d.symbol.clone(), d.start_byte, d.end_byte, d.start_line))
+            .collect()
+    };
+    assert_eq!(spans(&docs), spans(&fresh_docs));
+    assert!(state.find_by_name("parse").is_empty());
+    let save = &state.find_by_name("save")[0];
+    assert_eq!(save.location.start_line, 5);
+    assert_eq!(state.find_callers("load").len(), 1);
+}
+
+#[tokio::test]
+async fn test_overlays_shadow_disk_until_cleared() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let path = root.join("lib.rs");
+    fs::write(&path, "fn load() { parse(); }\n\nfn parse() {}\n").unwrap();
+    let state = create_state(root.to_path_buf());
+    let indexer = IncrementalIndexer::new();
+    indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    let buffer = "fn load() { decode_frame(); }\n\nfn decode_frame() {}\n";
+    indexer
+        .overlay_file(&state, &path, root, buffer.to_string())
+        .await
+        .expect("overlay");
+    assert!(state.find_by_name("parse").is_empty());
+    assert_eq!(state.find_callers("decode_frame").len(), 1);
+    assert_eq!(
+        state.get_file_contents(&path).await.as_deref(),
+        Some(buffer)
+    );
+
+    // Search sees the buffer; the persisted index still reflects disk
+    let mut index = load_search_index(root).expect("load").expect("index");
+    assert!(
+        execute_query(&index, "decode_frame", 5, &Default::default())
+            .results
+            .is_empty()
+    );
+    apply_overlays(&mut index, &state);
+    let response = execute_query(&index, "decode_frame", 5, &Default::default());
+    assert_eq!(response.results[0].symbol, "crate::decode_frame");
+
+    // Re-indexing and update_file keep the overlay in place
+    indexer
+        .index(
+            &state,
+            root,
+            &IndexOptions {
+                force: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("reindex");
+    assert!(
+        indexer
+            .update_file(&state, &path, root)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(state.find_by_name("parse").is_empty());
+
+    assert!(
+        indexer
+            .clear_overlay(&state, &path, root)
+            .await
+            .expect("clear")
+    );
+    assert_eq!(state.find_by_name("parse").len(), 1);
+    assert!(state.find_by_name("decode_frame").is_empty());
+}
+
+#[tokio::test]
+async fn test_language_overrides_apply_to_every_parse() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let path = root.join("tools/gen");
+    fs::create_dir_all(root.join("tools")).unwrap();
+    fs::write(&path, "fn generate() {}\n").unwrap();
+    let state = create_state(root.to_path_buf());
+    let overrides = LanguageOverrides::from_rules(&["tools/*=rust".to_string()]).unwrap();
+    let indexer = IncrementalIndexer::new().with_language_overrides(overrides);
+
+    indexer.full_index(&state, root).await.expect("index");
+    assert_eq!(state.find_by_name("generate").len(), 1);
+
+    fs::write(&path, "fn render() {}\n").unwrap();
+    indexer
+        .update_file(&state, &path, root)
+        .await
+        .expect("update")
+        .expect("re-parsed");
+    assert!(state.find_by_name("generate").is_empty());
+    assert_eq!(state.find_by_name("render").len(), 1);
+
+    indexer
+        .overlay_file(&state, &path, root, "fn draft() {}\n".to_string())
+        .await
+        .expect("overlay");
+    assert_eq!(state.find_by_name("draft").len(), 1);
+}
+
+#[tokio::test]
+async fn test_touched_but_unchanged_files_are_not_reparsed() {
+    let temp = copy_fixture();
+    let root = temp.path();
+    let state = create_state(root.to_path_buf());
+    let indexer = IncrementalIndexer::new();
+
+    let report = indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    let parsed = state.stats().files_parsed;
+    assert_eq!(parsed, report.parsed_files);
+
+    // Rewrite with identical content but a different mtime
+    let lib = root.join("src/lib.rs");
+    let contents = fs::read(&lib).expect("read");
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+    fs::write(&lib, &contents).expect("write");
+    fs::File::options()
+        .write(true)
+        .open(&lib)
+        .and_then(|f| f.set_modified(later))
+        .expect("set mtime");
+
+    let report = indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    assert_eq!(report.parsed_files, 0);
+    assert_eq!(report.unchanged_files, 1);
+    assert_eq!(report.skipped_files, report.total_files);
+
+    // The single-file update path short-circuits on the hash as well
+    let skipped = state.stats().files_skipped;
+    assert!(
+        indexer
+            .update_file(&state, &lib, root)
+            .await
+            .expect("update")
+            .is_none()
+    );
+    assert_eq!(state.stats().files_skipped, skipped + 1);
+    assert_eq!(state.stats().files_parsed, parsed);
+
+    fs::write(&lib, b"pub fn changed() {}\n").expect("write");
+    let docs = indexer
+        .update_file(&state, &lib, root)
+        .await
+        .expect("update")
+        .expect("reparsed");
+    assert!(!docs.is_empty());
+    assert_eq!(state.stats().files_parsed, parsed + 1);
+}
+
+#[tokio::test]
+async fn test_branch_switch_reuses_the_branch_snapshot() {
+    let temp = copy_fixture();
+    let root = temp.path();
+    fs::create_dir_all(root.join(".git")).expect("git dir");
+    let checkout = |branch: &str| {
+        fs::write(
+            root.join(".git/HEAD"),
+            format!("ref: refs/heads/{branch}\n"),
+        )
+        .expect("HEAD")
+    };
+    let lib = root.join("src/lib.rs");
+    let main_lib = fs::read_to_string(&lib).expect("read");
+    let indexer = IncrementalIndexer::new();
+    let index = || async {
+        // A fresh state per run, as each CLI invocation starts with one
+        let state = create_state(root.to_path_buf());
+        indexer
+            .index(&state, root, &IndexOptions::default())
+            .await
+            .expect("index")
+    };
+
+    checkout("main");
+    let first = index().await;
+    assert_eq!(first.reused_files, 0);
+
+    checkout("feature/rewrite");
+    fs::write(&lib, "pub fn rewritten_entry() {}\n").expect("write");
+    let report = index().await;
+    assert_eq!(report.parsed_files, 1);
+    assert_eq!(report.reused_files, 0);
+
+    // Back on main: the file differs from the cache but matches main's
+    // snapshot, so its docs are reused instead of re-parsed
+    checkout("main");
+    fs::write(&lib, &main_lib).expect("write");
+    let report = index().await;
+    assert_eq!(report.parsed_files, 0);
+    assert_eq!(report.reused_files, 1);
+
+    let index = load_search_index(root).expect("load").expect("index");
+    let lib_docs = |index: &omni_index::query::SearchIndex| {
+        let mut symbols: Vec<String> = index
+            .docs
+            .iter()
+            .filter(|d| d.file == "src/lib.rs")
+            .map(|d| d.symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols
+    };
+    assert!(!lib_docs(&index).is_empty());
+    assert!(!lib_docs(&index).contains(&"rewritten_entry".to_string()));
+    assert!(root.join(".omni/branches/feature%2Frewrite").is_dir());
+
+    // And the feature branch's edit is reused on the way back too
+    checkout("feature/rewrite");
+    fs::write(&lib, "pub fn rewritten_entry() {}\n").expect("write");
+    let report = IncrementalIndexer::new()
+        .index(
+            &create_state(root.to_path_buf()),
+            root,
+            &IndexOptions::default(),
+        )
+        .await
+        .expect("index");
+    assert_eq!(report.parsed_files, 0);
+    assert_eq!(report.reused_files, 1);
+    let index = load_search_index(root).expect("load").expect("index");
+    assert!(
+        lib_docs(&index)
+            .iter()
+            .any(|s| s.contains("rewritten_entry"))
+    );
+}
+
 #[tokio::test]
 async fn test_query_ranking_and_schema() {
     let temp = copy_fixture();
@@ -96,6 +488,35 @@ async fn test_query_ranking_and_schema() {
     assert_eq!(top.start_line, 2);
 }
 
+#[tokio::test]
+async fn test_deprecated_symbols_rank_below_live_ones() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::write(
+        root.join("lib.rs"),
+        "#[deprecated(note = \"use `parse_ledger_v2`\")]\npub fn parse_ledger() { ledger_entries(); }\n\npub fn parse_ledger_v2() { ledger_entries(); }\n",
+    )
+    .expect("write");
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    let index = require_search_index(root).expect("index exists");
+    let response = execute_query(&index, "parse ledger", 5, &Default::default());
+    let symbols: Vec<&str> = response.results.iter().map(|r| r.symbol.as_str()).collect();
+    assert_eq!(
+        symbols,
+        vec!["crate::parse_ledger_v2", "crate::parse_ledger"]
+    );
+    assert_eq!(response.results[0].deprecated, None);
+    assert_eq!(
+        response.results[1].deprecated.as_deref(),
+        Some("use `parse_ledger_v2`")
+    );
+}
+
 #[tokio::test]
 async fn test_deleted_files_removed_from_index() {
     let temp = copy_fixture();
@@ -122,3 +543,404 @@ async fn test_deleted_files_removed_from_index() {
     let response = execute_query(&index, "helper token magic", 5, &Default::default());
     assert!(response.results.is_empty());
 }
+
+#[tokio::test]
+async fn test_renamed_symbols_found_by_old_name() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let file = root.join("lib.rs");
+    fs::write(
+        &file,
+        "pub fn load_cfg(path: &str) -> String {\n    std::fs::read_to_string(path).unwrap()\n}\n",
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+    let indexer = IncrementalIndexer::new();
+    indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    fs::write(
+        &file,
+        "pub fn load_configuration(path: &str) -> String {\n    std::fs::read_to_string(path).unwrap()\n}\n",
+    )
+    .unwrap();
+    indexer
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("reindex");
+
+    let index = require_search_index(root).expect("index");
+    let response = execute_query(&index, "load_cfg", 5, &Default::default());
+    let top = &response.results[0];
+    assert_eq!(top.symbol, "crate::load_configuration");
+    let note = top.renamed.as_deref().expect("rename note");
+    assert!(
+        note.starts_with("crate::load_cfg was renamed to crate::load_configuration on "),
+        "{}",
+        note
+    );
+
+    // Queries for the current name carry no note
+    let response = execute_query(&index, "load_configuration", 5, &Default::default());
+    assert!(response.results[0].renamed.is_none());
+}
+
+#[tokio::test]
+async fn test_selected_symbols_rank_higher() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::write(root.join("a.rs"), "fn fetch_alpha() { fetch(); }\n").unwrap();
+    fs::write(root.join("b.rs"), "fn fetch_bravo() { fetch(); }\n").unwrap();
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    let top = |root: &std::path::Path| {
+        let index = require_search_index(root).expect("index");
+        execute_query(&index, "fetch", 2, &Default::default()).results[0]
+            .symbol
+            .clone()
+    };
+    assert_eq!(top(root), "crate::fetch_alpha");
+
+    omni_index::popularity::record_selection(root, "crate::fetch_bravo").unwrap();
+    assert_eq!(top(root), "crate::fetch_bravo");
+}
+
+#[tokio::test]
+async fn test_search_falls_back_when_bm25_finds_nothing() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::write(
+        root.join("lib.rs"),
+        "pub fn reconcile() { let total_amount = 1; }\n\npub fn render() {}\n",
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    let index = require_search_index(root).expect("index");
+    let search = |query: &str, semantic: Option<Vec<(u32, f32)>>| {
+        search_with_fallback(
+            &index,
+            query,
+            5,
+            &Default::default(),
+            semantic.map(SemanticHits::new),
+            &Budget::unlimited(),
+        )
+    };
+
+    let response = search("reconcile", None);
+    assert_eq!(response.strategy, SearchStrategy::Bm25);
+    assert_eq!(response.results[0].symbol, "crate::reconcile");
+
+    // Typo: no BM25 term matches, the symbol name is close enough
+    let response = search("reconcle", None);
+    assert_eq!(response.strategy, SearchStrategy::FuzzySymbol);
+    assert_eq!(response.results[0].symbol, "crate::reconcile");
+
+    // Fragment of a body identifier
+    let response = search("otal_amou", None);
+    assert_eq!(response.strategy, SearchStrategy::Substring);
+    assert_eq!(response.results[0].symbol, "crate::reconcile");
+
+    let render = index
+        .docs
+        .iter()
+        .position(|doc| doc.symbol == "crate::render")
+        .unwrap() as u32;
+    let response = search("draw output", Some(vec![(render, 0.9)]));
+    assert_eq!(response.strategy, SearchStrategy::Hybrid);
+    assert_eq!(response.results[0].symbol, "crate::render");
+
+    assert!(search("zzzz qqqq", None).results.is_empty());
+
+    // A work budget returns partial results instead of scanning everything
+    let budget = Budget::new(None, Some(1));
+    let response = search_with_fallback(&index, "pub fn", 5, &Default::default(), None, &budget);
+    assert!(response.truncated);
+    assert_eq!(response.results.len(), 1);
+    assert!(!search("reconcile", None).truncated);
+}
+
+#[tokio::test]
+async fn test_semantic_query_ranks_hits_and_applies_filters() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn reconcile() {}\n").unwrap();
+    fs::write(root.join("render.rs"), "pub fn render() {}\n").unwrap();
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    let index = require_search_index(root).expect("index");
+    let doc = |symbol: &str| {
+        index
+            .docs
+            .iter()
+            .position(|doc| doc.symbol == symbol)
+            .unwrap() as u32
+    };
+    let hits = vec![(doc("crate::reconcile"), 0.4), (doc("crate::render"), 0.8)];
+    let semantic = |filters: &[String]| {
+        let (_, filters) = parse_query_filters("draw", filters);
+        semantic_query(
+            &index,
+            "draw",
+            5,
+            &filters,
+            SemanticHits::new(hits.clone()),
+            &Budget::unlimited(),
+        )
+    };
+
+    let response = semantic(&[]);
+    assert_eq!(response.strategy, SearchStrategy::Semantic);
+    let symbols: Vec<_> = response.results.iter().map(|r| r.symbol.as_str()).collect();
+    assert_eq!(symbols, ["crate::render", "crate::reconcile"]);
+    assert_eq!(response.results[0].score, 0.8);
+
+    let response = semantic(&["path:src".to_string()]);
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].symbol, "crate::reconcile");
+
+    assert_eq!(SearchType::parse("semantic"), Some(SearchType::Semantic));
+    assert_eq!(SearchType::default(), SearchType::Hybrid);
+    assert!(!SearchType::Bm25.uses_semantic());
+    assert_eq!(SearchType::parse("vector"), None);
+}
+
+#[tokio::test]
+async fn test_hybrid_search_collapses_near_duplicates() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::write(
+        root.join("lib.rs"),
+        "pub fn render() {}\n\npub fn render_page() { render(); }\n\npub fn draw() {}\n",
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    let index = require_search_index(root).expect("index");
+    let doc = |symbol: &str| {
+        index
+            .docs
+            .iter()
+            .position(|doc| doc.symbol == symbol)
+            .unwrap() as u32
+    };
+    let (render, render_page, draw) = (
+        doc("crate::render"),
+        doc("crate::render_page"),
+        doc("crate::draw"),
+    );
+
+    let semantic = SemanticHits::new(vec![(render, 0.9), (render_page, 0.88), (draw, 0.5)])
+        .with_embeddings(|doc_id| {
+            Some(if doc_id == draw {
+                vec![0.0, 1.0]
+            } else {
+                vec![1.0, 0.0]
+            })
+        });
+    let response = search_with_fallback(
+        &index,
+        "show output",
+        2,
+        &Default::default(),
+        Some(semantic),
+        &Budget::unlimited(),
+    );
+    let symbols: Vec<&str> = response.results.iter().map(|r| r.symbol.as_str()).collect();
+    assert_eq!(symbols, vec!["crate::render", "crate::draw"]);
+    assert_eq!(
+        response.results[0].alternates[0].symbol,
+        "crate::render_page"
+    );
+}
+
+#[tokio::test]
+async fn test_find_by_name_near_ranks_by_proximity() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    let files = [
+        ("a/Cargo.toml", "[package]\nname = \"a\"\n"),
+        (
+            "a/src/lib.rs",
+            "pub struct Lib;\nimpl Lib {\n    pub fn new() -> Self { Lib }\n}\n",
+        ),
+        (
+            "a/src/widget.rs",
+            "pub struct Widget;\nimpl Widget {\n    pub fn new() -> Self { Widget }\n}\n",
+        ),
+        (
+            "a/src/nested/deep.rs",
+            "pub struct Deep;\nimpl Deep {\n    pub fn new() -> Self { Deep }\n}\n",
+        ),
+        ("b/Cargo.toml", "[package]\nname = \"b\"\n"),
+        (
+            "b/src/lib.rs",
+            "pub struct Other;\nimpl Other {\n    pub fn new() -> Self { Other }\n}\n",
+        ),
+    ];
+    for (rel, content) in files {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).expect("create dir");
+        fs::write(path, content).expect("write file");
+    }
+
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .full_index(&state, root)
+        .await
+        .expect("index");
+
+    let ranked: Vec<PathBuf> = state
+        .find_by_name_near("new", std::path::Path::new("a/src/widget.rs"))
+        .into_iter()
+        .map(|s| s.location.file.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            PathBuf::from("a/src/widget.rs"),
+            PathBuf::from("a/src/lib.rs"),
+            PathBuf::from("a/src/nested/deep.rs"),
+            PathBuf::from("b/src/lib.rs"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_typed_errors_for_missing_and_corrupt_index() {
+    let temp = copy_fixture();
+    let root = temp.path();
+    omni_index::cache::clear_cache(root).expect("clear cache");
+
+    let err = require_search_index(root).expect_err("not indexed yet");
+    assert!(matches!(err, OmniError::IndexMissing(_)));
+
+    let state = create_state(root.to_path_buf());
+    let options = IndexOptions {
+        language_overrides: vec!["*.txt=cobol".to_string()],
+        ..Default::default()
+    };
+    let err = IncrementalIndexer::new()
+        .index(&state, root, &options)
+        .await
+        .expect_err("unknown language");
+    assert_eq!(err.code(), "unsupported_language");
+
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    fs::write(omni_index::cache::state_path(root), b"garbage").expect("corrupt state");
+    let err = require_search_index(root).expect_err("corrupt state");
+    assert!(matches!(err, OmniError::Corrupt { .. }), "{err}");
+}
+
+#[tokio::test]
+async fn test_config_keys_are_searchable() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("deploy")).unwrap();
+    fs::write(
+        root.join("src/client.rs"),
+        "pub fn retry_max_attempts(config: &Config) -> u32 {\n    config.retry_max_attempts\n}\n",
+    )
+    .unwrap();
+    fs::write(root.join("app.toml"), "[http]\nretry_max_attempts = 5\n").unwrap();
+    fs::write(
+        root.join("deploy/values.yaml"),
+        "service:\n  name: checkout\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("flags.json"),
+        r#"{"features": {"dark_mode": true}}"#,
+    )
+    .unwrap();
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+    let index = require_search_index(root).expect("search index");
+
+    // Both the definition and the code reading it
+    let response = execute_query(&index, "retry_max_attempts", 10, &Default::default());
+    let hits: Vec<(&str, DocKind)> = response
+        .results
+        .iter()
+        .map(|r| (r.symbol.as_str(), r.kind))
+        .collect();
+    assert!(hits.contains(&("http.retry_max_attempts", DocKind::Config)));
+    assert!(hits.contains(&("crate::retry_max_attempts", DocKind::Symbol)));
+    let config = response
+        .results
+        .iter()
+        .find(|r| r.kind == DocKind::Config)
+        .unwrap();
+    assert_eq!((config.file.as_str(), config.start_line), ("app.toml", 2));
+    assert_eq!(config.role, "config");
+
+    let (text, filters) = parse_query_filters("retry_max_attempts type:config", &[]);
+    let response = execute_query(&index, &text, 10, &filters);
+    assert!(response.results.iter().all(|r| r.kind == DocKind::Config));
+    assert!(!response.results.is_empty());
+
+    for (query, symbol) in [
+        ("checkout", "service.name"),
+        ("dark_mode", "features.dark_mode"),
+    ] {
+        let response = execute_query(&index, query, 10, &Default::default());
+        assert_eq!(response.results[0].symbol, symbol);
+    }
+}
+
+#[tokio::test]
+async fn test_exclude_tests_filter_drops_test_code() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let root = temp.path();
+    fs::write(
+        root.join("lib.rs"),
+        "pub fn parse_ledger() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn parse_ledger_roundtrip() { super::parse_ledger(); }\n}\n",
+    )
+    .expect("write");
+    let state = create_state(root.to_path_buf());
+    IncrementalIndexer::new()
+        .index(&state, root, &IndexOptions::default())
+        .await
+        .expect("index");
+
+    let index = require_search_index(root).expect("index exists");
+    let response = execute_query(&index, "parse ledger", 10, &Default::default());
+    let test_hit = response
+        .results
+        .iter()
+        .find(|r| r.symbol == "crate::tests::parse_ledger_roundtrip")
+        .expect("test function is searchable by default");
+    assert!(test_hit.is_test_context);
+
+    let filters = omni_index::query::QueryFilters {
+        exclude_tests: true,
+        ..Default::default()
+    };
+    let response = execute_query(&index, "parse ledger", 10, &filters);
+    assert!(!response.results.is_empty());
+    assert!(response.results.iter().all(|r| !r.is_test_context));
+}
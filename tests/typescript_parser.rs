@@ -75,3 +75,226 @@ fn typescript_calls_extract_computed_string_property() {
         "expected computed property call to use literal name"
     );
 }
+
+#[test]
+fn typescript_imports_bind_aliases_and_resolve_path_aliases() {
+    let temp = TempDir::new().expect("tempdir");
+    let root = temp.path();
+    fs::write(
+        root.join("tsconfig.json"),
+        r#"{
+            // comments and trailing commas are allowed
+            "compilerOptions": {
+                "baseUrl": "./src",
+                "paths": { "@lib/*": ["lib/*"], "@config": ["config/index.ts"], },
+            },
+        }"#,
+    )
+    .expect("tsconfig");
+
+    let file_path = root.join("src/app/main.ts");
+    let source = r#"
+        import Default, { a, b as c } from "@lib/util";
+        import * as ns from "@config";
+        import "./side-effect";
+    "#;
+
+    let parser = TypeScriptParser::new_typescript();
+    let tree = parse_ts_source(&parser, source);
+    let imports = parser
+        .extract_imports(&tree, source, &file_path)
+        .expect("imports");
+
+    let bindings: Vec<(&str, Option<&str>, &str, bool)> = imports
+        .iter()
+        .map(|i| {
+            (
+                i.name.as_str(),
+                i.original_name.as_deref(),
+                i.path.as_str(),
+                i.is_glob,
+            )
+        })
+        .collect();
+    assert_eq!(
+        bindings,
+        vec![
+            ("Default", None, "src/lib/util", false),
+            ("a", None, "src/lib/util", false),
+            ("c", Some("b"), "src/lib/util", false),
+            ("ns", None, "src/config/index.ts", true),
+            ("./side-effect", None, "./side-effect", false),
+        ]
+    );
+}
+
+#[test]
+fn typescript_jsdoc_deprecation_is_attached() {
+    let temp = TempDir::new().expect("tempdir");
+    let file_path = temp.path().join("api.ts");
+    let source = r#"
+/**
+ * Loads one record.
+ * @deprecated Use {@link loadAll} instead.
+ */
+export function loadOne() {}
+
+/** Loads every record. */
+export function loadAll() {}
+
+// not a doc comment
+function plain() {}
+"#;
+
+    let parser = TypeScriptParser::new_typescript();
+    let tree = parse_ts_source(&parser, source);
+    let interner = lasso::ThreadedRodeo::default();
+    let symbols = parser
+        .extract_symbols(&tree, source, &file_path, &interner)
+        .expect("symbols");
+    let find = |name: &str| {
+        symbols
+            .iter()
+            .find(|s| interner.resolve(&s.name) == name)
+            .expect("symbol")
+    };
+
+    let old = find("loadOne");
+    assert_eq!(old.deprecation(), Some("Use {@link loadAll} instead."));
+    assert_eq!(old.deprecation_replacement(), Some("loadAll"));
+    let new = find("loadAll");
+    assert_eq!(
+        new.doc_comment.as_deref(),
+        Some("/** Loads every record. */")
+    );
+    assert_eq!(new.deprecation(), None);
+    assert_eq!(find("plain").doc_comment, None);
+}
+
+#[test]
+fn javascript_files_get_symbols_calls_and_imports() {
+    let temp = TempDir::new().expect("tempdir");
+    let root = temp.path();
+    fs::write(root.join("package.json"), "{}").expect("package.json");
+
+    let file_path = root.join("src/App.js");
+    let source = r#"
+const React = require("react");
+const { render, hydrate: hydrateRoot } = require("react-dom");
+require("./polyfills");
+import { format } from "./format.mjs";
+
+class Store {
+    load() { return format(1); }
+}
+
+function App({ store }) {
+    return <div onClick={() => store.load()}>{format("x")}</div>;
+}
+
+const mount = (el) => render(<App store={new Store()} />, el);
+"#;
+
+    let parser = TypeScriptParser::new_javascript();
+    assert!(parser.extensions().contains(&"jsx"));
+    let tree = parse_ts_source(&parser, source);
+    assert!(!tree.root_node().has_error(), "JSX in .js should parse");
+
+    let interner = lasso::ThreadedRodeo::default();
+    let symbols = parser
+        .extract_symbols(&tree, source, &file_path, &interner)
+        .expect("symbols");
+    let names: Vec<&str> = symbols.iter().map(|s| interner.resolve(&s.name)).collect();
+    for expected in ["Store", "load", "App", "mount"] {
+        assert!(names.contains(&expected), "missing {expected} in {names:?}");
+    }
+
+    let calls = parser
+        .extract_calls(&tree, source, &file_path, &interner)
+        .expect("calls");
+    assert!(calls.iter().any(|c| c.callee_name == "render"));
+    assert!(calls.iter().any(|c| c.callee_name == "format"));
+
+    let imports = parser
+        .extract_imports(&tree, source, &file_path)
+        .expect("imports");
+    let bindings: Vec<(&str, Option<&str>, &str)> = imports
+        .iter()
+        .map(|i| (i.name.as_str(), i.original_name.as_deref(), i.path.as_str()))
+        .collect();
+    assert_eq!(
+        bindings,
+        vec![
+            ("React", None, "react"),
+            ("render", None, "react-dom"),
+            ("hydrateRoot", Some("hydrate"), "react-dom"),
+            ("./polyfills", None, "./polyfills"),
+            ("format", None, "./format.mjs"),
+        ]
+    );
+}
+
+#[test]
+fn typescript_arrow_properties_and_jsx_components_are_call_sites() {
+    let temp = TempDir::new().expect("tempdir");
+    let root = temp.path();
+    fs::write(root.join("package.json"), "{}").expect("package.json");
+
+    let file_path = root.join("App.tsx");
+    let source = r#"
+        class Store {
+            onChange = (value: string) => {
+                this.save(value);
+            };
+            save(value: string) {}
+        }
+        function App() {
+            return <Panel><Badge /></Panel>;
+        }
+    "#;
+
+    let parser = TypeScriptParser::new_tsx();
+    let tree = parse_ts_source(&parser, source);
+    let interner = lasso::ThreadedRodeo::default();
+    let symbols = parser
+        .extract_symbols(&tree, source, &file_path, &interner)
+        .expect("symbols");
+    let calls = parser
+        .extract_calls(&tree, source, &file_path, &interner)
+        .expect("calls");
+
+    let on_change = symbols
+        .iter()
+        .find(|s| interner.resolve(&s.name) == "onChange")
+        .expect("arrow property should be a symbol");
+    assert_eq!(
+        interner.resolve(&on_change.scoped_name),
+        "file:App.tsx::Store::onChange"
+    );
+    assert_eq!(
+        on_change.parent.map(|p| interner.resolve(&p)),
+        Some("Store")
+    );
+
+    let edges: Vec<(String, String)> = calls
+        .iter()
+        .map(|c| {
+            (
+                interner.resolve(&c.caller).to_string(),
+                c.callee_name.clone(),
+            )
+        })
+        .collect();
+    for expected in [
+        ("file:App.tsx::Store::onChange", "save"),
+        ("file:App.tsx::App", "Panel"),
+        ("file:App.tsx::App", "Badge"),
+    ] {
+        assert!(
+            edges
+                .iter()
+                .any(|(c, n)| c == expected.0 && n == expected.1),
+            "missing {expected:?} in {edges:?}"
+        );
+    }
+}